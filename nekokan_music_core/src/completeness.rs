@@ -0,0 +1,127 @@
+//! レコードの入力完成度（%）。古いスタブ的なレコードから優先して埋めるための目安。
+//! サーバー（一覧・統計・目標）もフロント（フォームのメーター）もここで数えるので、同じ項目・同じ重みになる。
+//! レコードの JSON のまま数える（型に合わない古いレコードも、埋まっている項目の分だけ数えるように）。
+
+use serde_json::Value;
+
+use crate::length::parse_length;
+use crate::types::MusicData;
+
+fn non_empty_str(v: &Value) -> bool {
+    v.as_str().is_some_and(|s| !s.trim().is_empty())
+}
+
+fn non_empty_array(v: &Value) -> bool {
+    v.as_array().is_some_and(|a| !a.is_empty())
+}
+
+fn valid_length(v: &Value) -> bool {
+    v.as_str().and_then(parse_length).is_some()
+}
+
+/// composer は文字列または文字列配列（共作）。
+fn has_composer(v: &Value) -> bool {
+    match v {
+        Value::String(s) => !s.trim().is_empty(),
+        Value::Array(a) => a.iter().any(non_empty_str),
+        _ => false,
+    }
+}
+
+fn has_personnel(personnel: &Value) -> bool {
+    ["leader", "sidemen", "soloists", "conductor", "orchestra", "company", "group"]
+        .iter()
+        .any(|role| personnel[role].as_array().is_some_and(|a| a.iter().any(|e| non_empty_str(&e["name"]))))
+}
+
+/// 完成度の各チェック項目（埋まっていれば true）。
+/// `has_cover` はカバー画像があるか（ファイルがあるかはサーバーでないと分からないので呼ぶ側で決める）
+fn checks(v: &Value, has_cover: bool) -> [bool; 12] {
+    let tracks = v["tracks"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+    let all_tracks = |f: fn(&Value) -> bool| !tracks.is_empty() && tracks.iter().all(f);
+    [
+        non_empty_str(&v["title"]),
+        non_empty_str(&v["label"]),
+        non_empty_str(&v["id"]),
+        non_empty_array(&v["janre"]["sub"]),
+        non_empty_array(&v["record_year"]),
+        has_personnel(&v["personnel"]),
+        all_tracks(|t| non_empty_str(&t["title"])),
+        all_tracks(|t| valid_length(&t["length"])),
+        all_tracks(|t| has_composer(&t["composer"])),
+        non_empty_str(&v["comment"]),
+        non_empty_array(&v["references"]),
+        has_cover,
+    ]
+}
+
+/// レコードの JSON の完成度を 0〜100 の整数（切り捨て）で返す。
+#[must_use]
+pub fn completeness_from_value(v: &Value, has_cover: bool) -> u8 {
+    let c = checks(v, has_cover);
+    let filled = c.iter().filter(|&&b| b).count();
+    (filled * 100 / c.len()) as u8
+}
+
+/// フォームの内容の完成度。カバーは `cover` が入っていればあるものとする
+#[must_use]
+pub fn completeness_percent(data: &MusicData) -> u8 {
+    let v = serde_json::to_value(data).unwrap_or_default();
+    completeness_from_value(&v, !data.cover.trim().is_empty())
+}
+
+#[cfg(test)]
+mod completeness_tests {
+    use super::{completeness_from_value, completeness_percent};
+    use crate::types::{LeaderEntry, MusicData, Reference, Track};
+    use serde_json::json;
+
+    #[test]
+    fn empty_record_is_zero() {
+        assert_eq!(completeness_percent(&MusicData::default()), 0);
+        assert_eq!(completeness_from_value(&json!({}), false), 0);
+    }
+
+    #[test]
+    fn fully_filled_record_is_hundred() {
+        let mut d = MusicData {
+            title: "Alone".into(),
+            label: "Verve".into(),
+            id: "V6-8792".into(),
+            record_year: vec![1968],
+            comment: "good".into(),
+            cover: "Bill_Evans__Alone.jpg".into(),
+            ..Default::default()
+        };
+        d.janre.sub = vec!["Post Hard Bop".into()];
+        d.personnel.leader.push(LeaderEntry {
+            name: "Bill Evans".into(),
+            ..Default::default()
+        });
+        d.tracks.push(Track {
+            disc_no: 1,
+            no: 1,
+            title: "Midnight Mood".into(),
+            composer: "Joe Zawinul".into(),
+            length: "5:20".into(),
+        });
+        d.references.push(Reference {
+            name: "wikipedia".into(),
+            url: "https://en.wikipedia.org/".into(),
+        });
+        assert_eq!(completeness_percent(&d), 100);
+        // サーバーが JSON のまま数えても同じ。カバーのファイルがなければ 1 項目欠ける
+        let v = serde_json::to_value(&d).unwrap();
+        assert_eq!(completeness_from_value(&v, true), 100);
+        assert_eq!(completeness_from_value(&v, false), 91);
+
+        d.tracks[0].length = String::new();
+        assert_eq!(completeness_percent(&d), 91);
+    }
+
+    #[test]
+    fn co_written_tracks_count_as_composed() {
+        let v = json!({"tracks": [{"title": "A", "length": "3:00", "composer": ["Miles Davis", "Bill Evans"]}]});
+        assert_eq!(completeness_from_value(&v, false), 25);
+    }
+}
@@ -0,0 +1,204 @@
+//! 日付の形式と時差の決まり。CLI・サーバー・フロントはここを通して日付を読み書きする。
+//!
+//! - レコードの日付（`date` / `updated_date` / 聴いた日など）は `YYYY/MM/DD` の暦日で、時刻も時差も持たない。
+//!   "2024/03/05" は「ライブラリの時差で数えた 2024 年 3 月 5 日」のこと。
+//! - 「今日」やファイルの更新日時を暦日にするときは、ライブラリの時差（設定の `utc_offset`、例 "+09:00"）で数える。
+//!   未設定ならサーバーは UTC、フロントはブラウザの時差を使う。
+//! - 時刻つきの記録（監査ログなど）は常に UTC の RFC 3339（例 "2026-10-16T09:53:20Z"）。
+
+use std::fmt;
+
+/// レコードの日付の形式（表示・エラーメッセージ用）
+pub const DATE_FORMAT: &str = "YYYY/MM/DD";
+
+/// 暦日（グレゴリオ暦）。`parse` を通ったものは実在する日付。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+}
+
+fn is_leap(y: i64) -> bool {
+    (y % 4 == 0 && y % 100 != 0) || y % 400 == 0
+}
+
+fn days_in_month(y: i64, m: u32) -> u32 {
+    match m {
+        2 if is_leap(y) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Date {
+    /// 実在しない日付（2 月 30 日など）は None
+    pub fn new(year: i64, month: u32, day: u32) -> Option<Date> {
+        ((1..=12).contains(&month) && (1..=days_in_month(year, month)).contains(&day)).then_some(Date { year, month, day })
+    }
+
+    /// `YYYY/MM/DD`（年 4 桁・月日 2 桁の数字）だけを受け付ける
+    pub fn parse(s: &str) -> Option<Date> {
+        let b = s.as_bytes();
+        if b.len() != 10 || b[4] != b'/' || b[7] != b'/' {
+            return None;
+        }
+        if !s.bytes().enumerate().all(|(i, c)| i == 4 || i == 7 || c.is_ascii_digit()) {
+            return None;
+        }
+        Date::new(s[..4].parse().ok()?, s[5..7].parse().ok()?, s[8..].parse().ok()?)
+    }
+
+    /// 1970/01/01 からの通算日数
+    pub fn days(self) -> i64 {
+        let y = if self.month <= 2 { self.year - 1 } else { self.year };
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let mp = (i64::from(self.month) + 9) % 12;
+        let doy = (153 * mp + 2) / 5 + i64::from(self.day) - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+
+    /// 通算日数から暦日に戻す
+    pub fn from_days(z: i64) -> Date {
+        let z = z + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+        Date { year: yoe + era * 400 + i64::from(month <= 2), month, day }
+    }
+
+    /// UNIX 時刻（秒）を、時差 `offset` の地域での暦日にする
+    pub fn from_unix_secs(secs: i64, offset: UtcOffset) -> Date {
+        Date::from_days((secs + i64::from(offset.minutes) * 60).div_euclid(86_400))
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}/{:02}/{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// `YYYY/MM/DD` の実在する日付か
+pub fn is_valid(s: &str) -> bool {
+    Date::parse(s).is_some()
+}
+
+/// UNIX 時刻（秒）を RFC 3339（UTC, 秒まで）にする
+pub fn timestamp_from_unix_secs(secs: i64) -> String {
+    let (d, rest) = (Date::from_days(secs.div_euclid(86_400)), secs.rem_euclid(86_400));
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        d.year,
+        d.month,
+        d.day,
+        rest / 3600,
+        rest % 3600 / 60,
+        rest % 60
+    )
+}
+
+/// UTC からの時差（分）。±14:00 まで。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UtcOffset {
+    minutes: i32,
+}
+
+impl UtcOffset {
+    pub const UTC: UtcOffset = UtcOffset { minutes: 0 };
+
+    pub fn from_minutes(minutes: i32) -> Option<UtcOffset> {
+        (-14 * 60..=14 * 60).contains(&minutes).then_some(UtcOffset { minutes })
+    }
+
+    pub fn minutes(self) -> i32 {
+        self.minutes
+    }
+
+    /// "+09:00" / "-05:30" / "+0900" / "Z" / "UTC" を読む
+    pub fn parse(s: &str) -> Option<UtcOffset> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("z") || s.eq_ignore_ascii_case("utc") {
+            return Some(UtcOffset::UTC);
+        }
+        let sign = match s.as_bytes().first()? {
+            b'+' => 1,
+            b'-' => -1,
+            _ => return None,
+        };
+        let rest = s[1..].replace(':', "");
+        if rest.len() != 4 || !rest.bytes().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let (h, m): (i32, i32) = (rest[..2].parse().ok()?, rest[2..].parse().ok()?);
+        if m >= 60 {
+            return None;
+        }
+        UtcOffset::from_minutes(sign * (h * 60 + m))
+    }
+}
+
+impl fmt::Display for UtcOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.minutes < 0 { '-' } else { '+' };
+        let m = self.minutes.abs();
+        write!(f, "{}{:02}:{:02}", sign, m / 60, m % 60)
+    }
+}
+
+#[cfg(test)]
+mod date_tests {
+    use super::{is_valid, timestamp_from_unix_secs, Date, UtcOffset};
+
+    #[test]
+    fn parse_is_strict_about_format_and_calendar() {
+        assert_eq!(Date::parse("2024/03/05"), Some(Date { year: 2024, month: 3, day: 5 }));
+        assert!(is_valid("2024/02/29"));
+        assert!(!is_valid("2023/02/29"));
+        assert!(!is_valid("2024/04/31"));
+        assert!(!is_valid("2024/13/01"));
+        assert!(!is_valid("2024/3/5"));
+        assert!(!is_valid("2024-03-05"));
+        assert!(!is_valid("2024/+3/05"));
+        assert!(!is_valid(""));
+        assert_eq!(Date::parse("2024/03/05").map(|d| d.to_string()).as_deref(), Some("2024/03/05"));
+    }
+
+    #[test]
+    fn days_round_trip() {
+        assert_eq!(Date::parse("1970/01/01").map(Date::days), Some(0));
+        for s in ["2024/02/29", "1999/12/31", "2000/03/01", "1969/07/20"] {
+            let d = Date::parse(s).unwrap();
+            assert_eq!(Date::from_days(d.days()), d);
+        }
+    }
+
+    #[test]
+    fn offset_decides_which_day_it_is() {
+        // 2024-03-04T20:00:00Z は東京では 3 月 5 日、UTC では 3 月 4 日
+        let secs = Date::parse("2024/03/04").unwrap().days() * 86_400 + 20 * 3600;
+        let tokyo = UtcOffset::parse("+09:00").unwrap();
+        assert_eq!(Date::from_unix_secs(secs, tokyo).to_string(), "2024/03/05");
+        assert_eq!(Date::from_unix_secs(secs, UtcOffset::UTC).to_string(), "2024/03/04");
+        assert_eq!(Date::from_unix_secs(secs, UtcOffset::parse("-0530").unwrap()).to_string(), "2024/03/04");
+        assert_eq!(timestamp_from_unix_secs(secs), "2024-03-04T20:00:00Z");
+    }
+
+    #[test]
+    fn offsets_parse_and_print() {
+        assert_eq!(UtcOffset::parse("+09:00").map(UtcOffset::minutes), Some(540));
+        assert_eq!(UtcOffset::parse("-05:30").map(|o| o.to_string()).as_deref(), Some("-05:30"));
+        assert_eq!(UtcOffset::parse("Z"), Some(UtcOffset::UTC));
+        assert_eq!(UtcOffset::UTC.to_string(), "+00:00");
+        assert_eq!(UtcOffset::parse("+15:00"), None);
+        assert_eq!(UtcOffset::parse("09:00"), None);
+        assert_eq!(UtcOffset::parse("+09:75"), None);
+    }
+}
@@ -0,0 +1,227 @@
+//! ファイル名の候補の規則。フロントはファイル名欄にフォーカスしたときの候補に、サーバーは設定の確認に使う。
+//! Classical はパターン（設定の `classical_filename`、例 "{soloist_last}_{instrument}__{composer_last}_{title}"）で組み立てられる。
+//! 同じソリストの協奏曲がたくさんあると "{soloist}" だけでは重なるため。
+
+use crate::label::{check_template, parse_template};
+use crate::types::MusicData;
+
+/// ファイル名に使えない文字
+const INVALID: &[char] = &['\\', '/', ':', '*', '?', '"', '<', '>', '|'];
+
+/// Classical のファイル名のパターンで使える項目
+pub const CLASSICAL_FIELDS: &[&str] =
+    &["soloist", "soloist_last", "instrument", "composer", "composer_last", "title", "conductor", "orchestra"];
+
+/// 楽器の略記（小文字の楽器名 → 略記）。ここにない楽器はそのまま小文字で使う
+const INSTRUMENT_ABBRS: &[(&[&str], &str)] = &[
+    (&["piano", "pf", "ピアノ"], "p"),
+    (&["violin", "ヴァイオリン", "バイオリン"], "vn"),
+    (&["viola", "ヴィオラ", "ビオラ"], "va"),
+    (&["cello", "violoncello", "チェロ"], "vc"),
+    (&["double bass", "contrabass", "コントラバス"], "cb"),
+    (&["flute", "フルート"], "fl"),
+    (&["oboe", "オーボエ"], "ob"),
+    (&["clarinet", "クラリネット"], "cl"),
+    (&["bassoon", "ファゴット"], "fg"),
+    (&["horn", "french horn", "ホルン"], "hr"),
+    (&["trumpet", "トランペット"], "tp"),
+    (&["trombone", "トロンボーン"], "tb"),
+    (&["harp", "ハープ"], "hp"),
+    (&["guitar", "ギター"], "g"),
+    (&["organ", "オルガン"], "org"),
+    (&["harpsichord", "cembalo", "チェンバロ"], "cemb"),
+    (&["soprano", "ソプラノ"], "s"),
+    (&["mezzo-soprano", "メゾソプラノ"], "ms"),
+    (&["alto", "contralto", "アルト"], "a"),
+    (&["tenor", "テノール"], "t"),
+    (&["baritone", "バリトン"], "br"),
+    (&["bass", "バス"], "bs"),
+];
+
+/// ファイル名として不適切な文字を除去。スペースは _ に置換する。
+pub fn sanitize(s: &str) -> String {
+    s.replace(' ', "_").chars().filter(|c| !c.is_control() && !INVALID.contains(c)).collect()
+}
+
+/// 楽器名の略記（"Piano" → "p"）
+pub fn instrument_abbr(instrument: &str) -> String {
+    let lower = instrument.trim().to_lowercase();
+    INSTRUMENT_ABBRS
+        .iter()
+        .find(|(names, _)| names.contains(&lower.as_str()))
+        .map_or(lower.clone(), |(_, abbr)| abbr.to_string())
+}
+
+/// 名前の姓（空白で区切った最後の語。空白のない名前はそのまま）
+fn last_name(name: &str) -> &str {
+    name.split_whitespace().last().unwrap_or("")
+}
+
+/// Classical のファイル名のパターンを確かめる
+pub fn validate_classical_pattern(pattern: &str) -> Result<(), String> {
+    check_template(pattern, CLASSICAL_FIELDS)
+}
+
+fn classical_field(data: &MusicData, name: &str) -> String {
+    let p = &data.personnel;
+    let soloist = p.soloists.first();
+    let composer = data.composers.first().map_or("", |c| c.trim());
+    match name {
+        "soloist" => soloist.map_or("", |s| s.name.trim()).to_string(),
+        "soloist_last" => soloist.map_or("", |s| last_name(&s.name)).to_string(),
+        "instrument" => soloist.map(|s| instrument_abbr(&s.instrument)).unwrap_or_default(),
+        "composer" => composer.to_string(),
+        "composer_last" => last_name(composer).to_string(),
+        "title" => data.title.trim().to_string(),
+        "conductor" => p.conductor.first().map_or("", |c| c.name.trim()).to_string(),
+        "orchestra" => p.orchestra.first().map_or("", |o| o.name.trim()).to_string(),
+        _ => String::new(),
+    }
+}
+
+/// パターンで Classical のファイル名の候補を作る。項目が 1 つでも空（ソリストがいないなど）なら None
+pub fn classical_filename(data: &MusicData, pattern: &str) -> Option<String> {
+    let parts = parse_template(pattern).ok()?;
+    let mut out = String::new();
+    for (is_field, text) in parts {
+        if is_field {
+            let value = classical_field(data, text);
+            if value.is_empty() {
+                return None;
+            }
+            out.push_str(&value);
+        } else {
+            out.push_str(text);
+        }
+    }
+    Some(sanitize(out.trim())).filter(|s| !s.is_empty())
+}
+
+/// レコードの中身から作るファイル名（拡張子なし）。フロントはファイル名欄にフォーカスしたときの候補に、サーバーはファイル名の一括整理に使う。
+/// グループあり時: リーダーあり → "{リーダー名}_{abbr}__{タイトル}", リーダーなし → "{abbr}__{タイトル}"。
+/// Classical は設定のパターンで作れればそれ、なければ作品の作曲者があれば "{作曲者}__{タイトル}"。
+/// それ以外は既存ロジック（Jazz/Fusion は leader、Classical は soloists/conductor/orchestra）。
+pub fn suggested_filename(data: &MusicData, classical_pattern: &str) -> Option<String> {
+    let main = data.janre.main.as_str();
+    if main == "Classical" {
+        if let Some(s) = Some(classical_pattern.trim())
+            .filter(|p| !p.is_empty())
+            .and_then(|p| classical_filename(data, p))
+        {
+            return Some(s);
+        }
+        let composer = data.composers.first().map(|c| sanitize(c.trim())).filter(|c| !c.is_empty());
+        if let Some(composer) = composer {
+            let title = sanitize(data.title.trim());
+            return Some(if title.is_empty() { composer } else { format!("{}__{}", composer, title) });
+        }
+        // soloists → conductor → orchestra の順
+        data.personnel
+            .soloists
+            .first()
+            .map(|e| sanitize(e.name.trim()))
+            .or_else(|| {
+                data.personnel
+                    .conductor
+                    .first()
+                    .map(|e| sanitize(e.name.trim()))
+            })
+            .or_else(|| {
+                data.personnel
+                    .orchestra
+                    .first()
+                    .map(|e| sanitize(e.name.trim()))
+            })
+            .filter(|s| !s.is_empty())
+    } else if main == "Jazz" || main == "Fusion" {
+        // グループが入力されていればグループ基準のファイル名を優先
+        if let Some(g) = data.personnel.group.first() {
+            let abbr = sanitize(g.abbr.trim());
+            let title = sanitize(data.title.trim());
+            if abbr.is_empty() {
+                return None;
+            }
+            let leader_name = g
+                .members
+                .iter()
+                .find(|m| m.leader)
+                .map(|m| sanitize(m.name.trim()))
+                .filter(|s| !s.is_empty());
+            return Some(if let Some(name) = leader_name {
+                if title.is_empty() {
+                    format!("{}_{}", name, abbr)
+                } else {
+                    format!("{}_{}__{}", name, abbr, title)
+                }
+            } else if title.is_empty() {
+                abbr
+            } else {
+                format!("{}__{}", abbr, title)
+            });
+        }
+        // 既存: personnel.leader 1件目
+        data.personnel.leader.first().and_then(|entry| {
+            let name = sanitize(entry.name.trim());
+            if name.is_empty() {
+                return None;
+            }
+            let title = sanitize(data.title.trim());
+            Some(if title.is_empty() {
+                name
+            } else {
+                format!("{}__{}", name, title)
+            })
+        })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod filename_tests {
+    use super::{classical_filename, instrument_abbr, sanitize, suggested_filename, validate_classical_pattern};
+    use crate::types::{GroupEntry, GroupMemberEntry, Janre, LeaderEntry, MusicData, Personnel, SoloistEntry};
+
+    #[test]
+    fn soloist_instrument_and_work() {
+        let mut d = MusicData {
+            title: "Piano Concerto 1".into(),
+            composers: vec!["Frédéric Chopin".into()],
+            personnel: Personnel {
+                soloists: vec![SoloistEntry { name: "Martha Argerich".into(), instrument: "Piano".into(), ..Default::default() }],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let pattern = "{soloist_last}_{instrument}__{composer_last}_{title}";
+        assert!(validate_classical_pattern(pattern).is_ok());
+        assert_eq!(classical_filename(&d, pattern).as_deref(), Some("Argerich_p__Chopin_Piano_Concerto_1"));
+        assert_eq!(classical_filename(&d, "{soloist}").as_deref(), Some("Martha_Argerich"));
+        // 楽器がなければ候補なし（いつもの規則に戻す）
+        d.personnel.soloists[0].instrument.clear();
+        assert_eq!(classical_filename(&d, pattern), None);
+        assert_eq!(instrument_abbr("Violoncello"), "vc");
+        assert_eq!(instrument_abbr("Theremin"), "theremin");
+        assert_eq!(sanitize("Op.10/3: Etude"), "Op.103_Etude");
+        assert!(validate_classical_pattern("{leader}").is_err());
+    }
+
+    #[test]
+    fn jazz_names_follow_the_leader_or_group() {
+        let mut d = MusicData {
+            title: "Black Fire".into(),
+            janre: Janre { main: "Jazz".into(), sub: vec![] },
+            personnel: Personnel { leader: vec![LeaderEntry { name: "Andrew Hill".into(), ..Default::default() }], ..Default::default() },
+            ..Default::default()
+        };
+        assert_eq!(suggested_filename(&d, "").as_deref(), Some("Andrew_Hill__Black_Fire"));
+        d.personnel.group = vec![GroupEntry {
+            name: "The Jazz Messengers".into(),
+            abbr: "JM".into(),
+            members: vec![GroupMemberEntry { name: "Art Blakey".into(), leader: true, ..Default::default() }],
+        }];
+        assert_eq!(suggested_filename(&d, "").as_deref(), Some("Art_Blakey_JM__Black_Fire"));
+        d.janre.main = "Rock".into();
+        assert_eq!(suggested_filename(&d, ""), None);
+    }
+}
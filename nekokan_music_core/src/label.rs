@@ -0,0 +1,285 @@
+//! 表示ラベル（例: Bill Evans: Alone）の組み立て。
+//! サーバーは一覧に `LabelFields` を載せ、フロントがこのファイルの規則でラベルを作る。
+//! ラベルの規則を変えるときはサーバーを入れ替えずにフロントだけ更新すればよい。
+//! メインジャンルごとのテンプレート（設定の `label_templates`）があれば、サーバーもフロントもそちらを使う。
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// アーティストとタイトルの区切り
+pub const ARTIST_TITLE_SEP: &str = ": ";
+const ARTIST_UNKNOWN: &str = "[Artist Unknown]";
+
+/// ラベルとツールチップに使う項目（一覧 `/api/list_with_labels` の各要素に含まれる）
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LabelFields {
+    #[serde(default)]
+    pub title: String,
+    /// janre.main
+    #[serde(default)]
+    pub genre: String,
+    #[serde(default)]
+    pub genre_sub: Vec<String>,
+    /// レーベル（Game ではアーティストの代わりに使う）
+    #[serde(default)]
+    pub label: String,
+    /// personnel の各欄の名前（並び順どおり）
+    #[serde(default)]
+    pub leaders: Vec<String>,
+    #[serde(default)]
+    pub group: Vec<String>,
+    #[serde(default)]
+    pub soloists: Vec<String>,
+    #[serde(default)]
+    pub conductor: Vec<String>,
+    #[serde(default)]
+    pub orchestra: Vec<String>,
+    /// 作曲者（レコードの `composers` の最初の 1 人。なければ最初の曲の作曲者）
+    #[serde(default)]
+    pub composer: String,
+}
+
+/// ラベルのテンプレートで使える項目
+pub const TEMPLATE_FIELDS: &[&str] =
+    &["title", "artist", "label", "genre", "leader", "group", "soloist", "conductor", "orchestra", "composer"];
+
+/// メインジャンルごとの表示ラベルのテンプレート（例: "Classical" → "{composer}: {title}"）。
+/// テンプレートのないジャンルは既定の規則（`LabelFields::display_label`）で作る。
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct LabelTemplates(pub BTreeMap<String, String>);
+
+impl LabelTemplates {
+    pub const fn new() -> Self {
+        LabelTemplates(BTreeMap::new())
+    }
+}
+
+/// テンプレートを `{項目}` と文字列に分ける。`{` `}` の対応が取れていなければ Err。
+pub(crate) fn parse_template(template: &str) -> Result<Vec<(bool, &str)>, String> {
+    let mut parts = vec![];
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}') else {
+            return Err("「{」が閉じていません".into());
+        };
+        parts.push((false, &rest[..open]));
+        parts.push((true, rest[open + 1..open + close].trim()));
+        rest = &rest[open + close + 1..];
+    }
+    if rest.contains('}') {
+        return Err("「}」に対応する「{」がありません".into());
+    }
+    parts.push((false, rest));
+    Ok(parts)
+}
+
+/// テンプレートの項目が `fields` にあるかを確かめる（使えない項目があれば Err にその名前を入れる）
+pub(crate) fn check_template(template: &str, fields: &[&str]) -> Result<(), String> {
+    let parts = parse_template(template)?;
+    match parts.iter().find(|(is_field, name)| *is_field && !fields.contains(name)) {
+        Some((_, name)) => Err(format!("{{{}}} は使えません（使えるもの: {}）", name, fields.join(", "))),
+        None if !parts.iter().any(|(is_field, _)| *is_field) => Err("{title} などの項目を 1 つは入れてください".into()),
+        None => Ok(()),
+    }
+}
+
+/// ラベルのテンプレートを確かめる
+pub fn validate_template(template: &str) -> Result<(), String> {
+    check_template(template, TEMPLATE_FIELDS)
+}
+
+/// 作曲者は文字列（"A, B" / "A | B"）か配列。最初の 1 人を返す。
+fn first_composer(v: &Value) -> String {
+    let first = match v {
+        Value::Array(a) => a.first().and_then(Value::as_str).unwrap_or(""),
+        _ => v.as_str().and_then(|c| c.split([',', '|']).next()).unwrap_or(""),
+    };
+    first.trim().to_string()
+}
+
+fn names(v: &Value) -> Vec<String> {
+    v.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|o| o["name"].as_str())
+        .map(str::to_string)
+        .collect()
+}
+
+impl LabelFields {
+    /// 音楽JSONから取り出す。
+    pub fn from_value(v: &Value) -> Self {
+        let personnel = &v["personnel"];
+        LabelFields {
+            title: v["title"].as_str().unwrap_or("").to_string(),
+            genre: v["janre"]["main"].as_str().unwrap_or("").to_string(),
+            genre_sub: v["janre"]["sub"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+            label: v["label"].as_str().unwrap_or("").to_string(),
+            leaders: names(&personnel["leader"]),
+            group: names(&personnel["group"]),
+            soloists: names(&personnel["soloists"]),
+            conductor: names(&personnel["conductor"]),
+            orchestra: names(&personnel["orchestra"]),
+            composer: Some(first_composer(&v["composers"]))
+                .filter(|c| !c.is_empty())
+                .unwrap_or_else(|| first_composer(&v["tracks"][0]["composer"])),
+        }
+    }
+
+    fn field(&self, name: &str) -> String {
+        let first = |l: &[String]| l.first().cloned().unwrap_or_default();
+        match name {
+            "title" => self.title.clone(),
+            "artist" => self.artist(),
+            "label" => self.label.clone(),
+            "genre" => self.genre.clone(),
+            "leader" => first(&self.leaders),
+            "group" => first(&self.group),
+            "soloist" => first(&self.soloists),
+            "conductor" => first(&self.conductor),
+            "orchestra" => first(&self.orchestra),
+            "composer" => self.composer.clone(),
+            _ => String::new(),
+        }
+    }
+
+    /// ジャンルのテンプレートがあればそれで、なければ既定の規則で作る表示ラベル。
+    /// テンプレートの項目が 1 つでも空なら（作曲者が未入力など）既定の規則に戻す。
+    pub fn display_label_with(&self, templates: &LabelTemplates) -> String {
+        let rendered = templates.0.get(&self.genre).and_then(|t| parse_template(t).ok()).and_then(|parts| {
+            parts.into_iter().try_fold(String::new(), |mut out, (is_field, text)| {
+                if is_field {
+                    let value = self.field(text);
+                    if value.trim().is_empty() {
+                        return None;
+                    }
+                    out.push_str(&value);
+                } else {
+                    out.push_str(text);
+                }
+                Some(out)
+            })
+        });
+        rendered.map(|s| s.trim().to_string()).unwrap_or_else(|| self.display_label())
+    }
+
+    /// 表示ラベルのアーティスト部分。
+    /// ジャンルがGameの場合は Label。
+    /// それ以外は 優先順位: leader(1人) → leader(複数) et al. → group → soloists → conductor → orchestra → [Artist Unknown]
+    pub fn artist(&self) -> String {
+        if self.genre == "Game" {
+            return self.label.clone();
+        }
+        match self.leaders.as_slice() {
+            [one] => one.clone(),
+            [first, ..] => format!("{} et al.", first),
+            [] => [&self.group, &self.soloists, &self.conductor, &self.orchestra]
+                .into_iter()
+                .find_map(|l| l.first())
+                .cloned()
+                .unwrap_or_else(|| ARTIST_UNKNOWN.to_string()),
+        }
+    }
+
+    /// アーティストとタイトルを ": " で区切ったラベル（例: Bill Evans: Alone）
+    pub fn display_label(&self) -> String {
+        format!("{}{}{}", self.artist(), ARTIST_TITLE_SEP, self.title).trim().to_string()
+    }
+
+    /// サイドバーのツールチップ。ラベルに入りきらない人名とジャンルを行ごとに出す。
+    pub fn tooltip(&self) -> String {
+        let mut lines = vec![self.title.clone()];
+        for (role, list) in [
+            ("Leader", &self.leaders),
+            ("Group", &self.group),
+            ("Soloists", &self.soloists),
+            ("Conductor", &self.conductor),
+            ("Orchestra", &self.orchestra),
+        ] {
+            if !list.is_empty() {
+                lines.push(format!("{}: {}", role, list.join(", ")));
+            }
+        }
+        if !self.genre.is_empty() {
+            lines.push(if self.genre_sub.is_empty() {
+                self.genre.clone()
+            } else {
+                format!("{} / {}", self.genre, self.genre_sub.join(", "))
+            });
+        }
+        lines.join("\n")
+    }
+}
+
+/// レコードの JSON から表示ラベルを作る（サーバーの一覧・検索・レポート用）
+pub fn display_label_from_value(v: &Value, templates: &LabelTemplates) -> String {
+    LabelFields::from_value(v).display_label_with(templates)
+}
+
+#[cfg(test)]
+mod label_tests {
+    use super::{validate_template, LabelFields, LabelTemplates};
+    use serde_json::json;
+
+    #[test]
+    fn genre_templates_override_the_default() {
+        let templates = LabelTemplates(
+            [("Classical".to_string(), "{composer}: {title}".to_string()), ("Game".to_string(), "{title} ({label})".to_string())].into(),
+        );
+        let mahler = json!({
+            "title": "Symphony No.6", "janre": {"main": "Classical"},
+            "personnel": {"conductor": [{"name": "Christoph von Dohnanyi"}]},
+            "tracks": [{"composer": "Gustav Mahler, Someone Else"}]
+        });
+        let f = LabelFields::from_value(&mahler);
+        assert_eq!(f.display_label_with(&templates), "Gustav Mahler: Symphony No.6");
+        // 作曲者がなければ既定の規則
+        let f = LabelFields { composer: String::new(), ..f };
+        assert_eq!(f.display_label_with(&templates), "Christoph von Dohnanyi: Symphony No.6");
+        let mixed = LabelFields::from_value(&json!({
+            "title": "Orchestral Works", "janre": {"main": "Classical"}, "composers": ["Schoenberg", "Webern"],
+            "tracks": [{"composer": ["Webern"]}]
+        }));
+        assert_eq!(mixed.display_label_with(&templates), "Schoenberg: Orchestral Works");
+        let webern = LabelFields::from_value(&json!({"title": "Im Sommerwind", "janre": {"main": "Classical"}, "tracks": [{"composer": ["Webern", "Berg"]}]}));
+        assert_eq!(webern.display_label_with(&templates), "Webern: Im Sommerwind");
+        let game = LabelFields::from_value(&json!({"title": "Chrono Trigger", "janre": {"main": "Game"}, "label": "Square"}));
+        assert_eq!(game.display_label_with(&templates), "Chrono Trigger (Square)");
+        assert_eq!(game.display_label_with(&LabelTemplates::new()), "Square: Chrono Trigger");
+    }
+
+    #[test]
+    fn templates_are_checked() {
+        assert!(validate_template("{composer}: {title}").is_ok());
+        assert!(validate_template("{game_title}: {title}").is_err());
+        assert!(validate_template("{title").is_err());
+        assert!(validate_template("title}").is_err());
+        assert!(validate_template("no fields").is_err());
+    }
+
+    #[test]
+    fn artist_priority_and_game_label() {
+        let v = json!({
+            "title": "Sunday at the Village Vanguard",
+            "janre": {"main": "Jazz", "sub": ["Piano Trio"]},
+            "personnel": {
+                "leader": [{"name": "Bill Evans"}, {"name": "Scott LaFaro"}],
+                "group": [{"name": "Bill Evans Trio"}]
+            }
+        });
+        let f = LabelFields::from_value(&v);
+        assert_eq!(f.display_label(), "Bill Evans et al.: Sunday at the Village Vanguard");
+        let f = LabelFields { leaders: vec![], ..f };
+        assert_eq!(f.artist(), "Bill Evans Trio");
+        let game = LabelFields::from_value(&json!({"title": "Chrono Trigger", "janre": {"main": "Game"}, "label": "Square"}));
+        assert_eq!(game.display_label(), "Square: Chrono Trigger");
+        assert_eq!(LabelFields::default().artist(), "[Artist Unknown]");
+    }
+}
@@ -0,0 +1,46 @@
+//! トラックの長さ（"分:秒"、例 "4:46"）の読み書き。入力チェック・完成度・曲の索引・年のふりかえり・外部のデータベースの取り込みが
+//! みなここを使うので、読める形と書く形がそろう。不明な長さ（`rules::UNKNOWN_LENGTH`）は読めない扱い。
+
+/// "4:46" → 286 秒。前後や ":" のまわりの空白は許す。"?" や形式違いは None
+#[must_use]
+pub fn parse_length(s: &str) -> Option<u64> {
+    let (m, sec) = s.trim().split_once(':')?;
+    Some(m.trim().parse::<u64>().ok()? * 60 + sec.trim().parse::<u64>().ok()?)
+}
+
+/// 286 秒 → "4:46"（1 時間を超えても分で数える）
+#[must_use]
+pub fn format_length(secs: u64) -> String {
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// 合計時間の表示。1 時間未満は "42:05"、それ以上は "1:02:05"
+#[must_use]
+pub fn format_duration(secs: u64) -> String {
+    match secs / 3600 {
+        0 => format_length(secs),
+        h => format!("{}:{:02}:{:02}", h, secs % 3600 / 60, secs % 60),
+    }
+}
+
+#[cfg(test)]
+mod length_tests {
+    use super::{format_duration, format_length, parse_length};
+
+    #[test]
+    fn lengths_round_trip() {
+        assert_eq!(parse_length("4:46"), Some(286));
+        assert_eq!(parse_length(" 12 : 05 "), Some(725));
+        assert_eq!(parse_length("0:07"), Some(7));
+        assert_eq!(parse_length("?"), None);
+        assert_eq!(parse_length("4分"), None);
+        assert_eq!(parse_length("-1:30"), None);
+        assert_eq!(parse_length("1:02:03"), None);
+        assert_eq!(format_length(562), "9:22");
+        assert_eq!(format_length(65), "1:05");
+        assert_eq!(format_length(3725), "62:05");
+        assert_eq!(parse_length(&format_length(3725)), Some(3725));
+        assert_eq!(format_duration(2525), "42:05");
+        assert_eq!(format_duration(3725), "1:02:05");
+    }
+}
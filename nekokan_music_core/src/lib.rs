@@ -0,0 +1,13 @@
+//! フロント（nekokan_music_wa）とサーバーで共有するデータモデル・入力チェック・表示ラベルの規則。
+//! どちらも同じ `MusicData` と `validate_form` を使うので、フロントで通った入力はサーバーの一括チェックでも通る。
+
+pub mod completeness;
+pub mod date;
+pub mod filename;
+pub mod label;
+pub mod length;
+pub mod one_liner;
+pub mod rules;
+pub mod schema;
+pub mod types;
+pub mod validation;
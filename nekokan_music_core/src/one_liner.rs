@@ -0,0 +1,113 @@
+//! 1 行のメモ（"Bill Evans - Alone (1968) Verve"）からアーティスト・タイトル・発売年・レーベルを読む。クイック入力の 1 行欄が使う。
+//!
+//! 形は「アーティスト - タイトル (年) レーベル」で、どれも省ける。
+//! - アーティストとタイトルは最初の " - "（"–" "—" も可）で分ける。なければ全部タイトル
+//! - 年は括弧（`()` か `[]`）の中の 4 桁（"(Verve, 1968)" のようにレーベルと一緒でもよい）。括弧がなければ最後の語が 4 桁の年のとき
+//! - 年の後ろに残ったものがレーベル
+
+use crate::rules::{YEAR_MAX, YEAR_MIN};
+
+/// アーティストとタイトルの区切り（前後に空白があるものだけ。"Jean-Luc" は切らない）
+const ARTIST_SEPARATORS: [&str; 3] = [" - ", " – ", " — "];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OneLiner {
+    pub artist: String,
+    pub title: String,
+    pub year: Option<i32>,
+    pub label: String,
+}
+
+/// "1968" → Some(1968)（範囲外・4 桁でないものは None）
+fn year_of(s: &str) -> Option<i32> {
+    let s = s.trim();
+    if s.len() != 4 || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok().filter(|y| (YEAR_MIN..=YEAR_MAX).contains(y))
+}
+
+/// 空白をまとめ、前後の区切り記号を落とす
+fn tidy(s: &str) -> String {
+    s.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c: char| c == ',' || c == '/' || c == '-' || c.is_whitespace())
+        .to_string()
+}
+
+/// 年の入った括弧（最後のもの）。(開き括弧の位置, 閉じ括弧の次の位置, 年, 括弧の中の年以外)
+fn year_group(s: &str) -> Option<(usize, usize, i32, String)> {
+    let mut found = None;
+    for (open, c) in s.char_indices() {
+        let close = match c {
+            '(' => ')',
+            '[' => ']',
+            _ => continue,
+        };
+        let Some(len) = s[open + 1..].find(close) else {
+            continue;
+        };
+        let inner = &s[open + 1..open + 1 + len];
+        let parts: Vec<&str> = inner.split(',').map(str::trim).collect();
+        if let Some(year) = parts.iter().find_map(|p| year_of(p)) {
+            let rest: Vec<&str> = parts.into_iter().filter(|p| year_of(p).is_none() && !p.is_empty()).collect();
+            found = Some((open, open + 1 + len + close.len_utf8(), year, rest.join(", ")));
+        }
+    }
+    found
+}
+
+pub fn parse_one_liner(line: &str) -> OneLiner {
+    let line = line.trim();
+    let (artist, rest) = ARTIST_SEPARATORS
+        .iter()
+        .filter_map(|sep| line.find(sep).map(|i| (i, sep.len())))
+        .min()
+        .map_or(("", line), |(i, len)| (&line[..i], &line[i + len..]));
+
+    let (title, year, label) = if let Some((open, end, year, inner_label)) = year_group(rest) {
+        let after = tidy(&rest[end..]);
+        let label = [inner_label, after].into_iter().filter(|s| !s.is_empty()).collect::<Vec<_>>().join(" ");
+        (tidy(&rest[..open]), Some(year), label)
+    } else {
+        // 括弧がなければ、最後の語が年のときだけ年とみなす（"1984" だけのタイトルは年にしない）
+        match rest.trim().rsplit_once(char::is_whitespace) {
+            Some((before, last)) if year_of(last.trim_matches(',')).is_some() && !tidy(before).is_empty() => {
+                (tidy(before), year_of(last.trim_matches(',')), String::new())
+            }
+            _ => (tidy(rest), None, String::new()),
+        }
+    };
+    OneLiner { artist: tidy(artist), title, year, label }
+}
+
+#[cfg(test)]
+mod one_liner_tests {
+    use super::{parse_one_liner, OneLiner};
+
+    fn parsed(artist: &str, title: &str, year: Option<i32>, label: &str) -> OneLiner {
+        OneLiner { artist: artist.into(), title: title.into(), year, label: label.into() }
+    }
+
+    #[test]
+    fn full_line() {
+        assert_eq!(parse_one_liner("Bill Evans - Alone (1968) Verve"), parsed("Bill Evans", "Alone", Some(1968), "Verve"));
+        assert_eq!(parse_one_liner("  Bill Evans – Alone [Verve, 1968] "), parsed("Bill Evans", "Alone", Some(1968), "Verve"));
+        assert_eq!(
+            parse_one_liner("Miles Davis - Kind of Blue (Legacy Edition) (1959) Columbia"),
+            parsed("Miles Davis", "Kind of Blue (Legacy Edition)", Some(1959), "Columbia")
+        );
+    }
+
+    #[test]
+    fn parts_can_be_left_out() {
+        assert_eq!(parse_one_liner("Alone"), parsed("", "Alone", None, ""));
+        assert_eq!(parse_one_liner("Jean-Luc Ponty - Enigmatic Ocean"), parsed("Jean-Luc Ponty", "Enigmatic Ocean", None, ""));
+        assert_eq!(parse_one_liner("Bill Evans - Alone 1968"), parsed("Bill Evans", "Alone", Some(1968), ""));
+        // タイトルそのものが年のようなときは年にしない
+        assert_eq!(parse_one_liner("Van Halen - 1984"), parsed("Van Halen", "1984", None, ""));
+        assert_eq!(parse_one_liner("Prince - 1999 (1982)"), parsed("Prince", "1999", Some(1982), ""));
+        assert_eq!(parse_one_liner("Keith Jarrett - Vienna Concert (3000)"), parsed("Keith Jarrett", "Vienna Concert (3000)", None, ""));
+    }
+}
@@ -0,0 +1,134 @@
+//! 入力チェックの制約（最大文字数・必須・年の範囲・形式）。
+//! validation.rs とサーバーの `/api/validation-rules` の両方がこのファイルを使う。
+//! 外部に依存しない定数だけを置くこと。
+
+/// 名前・タイトルなどの最大文字数
+pub const LONG_TEXT_MAX: usize = 128;
+/// レーベル・品番・略称・参加トラックの最大文字数
+pub const SHORT_TEXT_MAX: usize = 64;
+pub const BADGE_MAX: usize = 8;
+pub const YEAR_MIN: i32 = 1900;
+pub const YEAR_MAX: i32 = 2099;
+pub const SCORE_MIN: i32 = 1;
+pub const SCORE_MAX: i32 = 6;
+/// ファイル名（拡張子なし）の最大バイト数
+pub const FILENAME_MAX_BYTES: usize = 255;
+pub const FILENAME_FORBIDDEN: [char; 10] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|', '\0'];
+/// 分からない値の印（トラックの長さ・参加トラック）。空欄（未入力）とは別に保存し、形式チェックは通り、完成度には数えない。
+/// 録音年は数値の配列なので、代わりに `record_year_unknown` を立てる。
+pub const UNKNOWN: &str = "?";
+/// 長さが分からないトラック（古い LP で合計時間しか載っていないものなど）
+pub const UNKNOWN_LENGTH: &str = UNKNOWN;
+/// 書き出し・一覧表示での「不明」の表記
+pub const UNKNOWN_TEXT: &str = "unknown";
+
+pub fn is_unknown(s: &str) -> bool {
+    s.trim() == UNKNOWN
+}
+
+/// 書き出し用の表記。不明の印は UNKNOWN_TEXT にし、それ以外はそのまま。
+pub fn render_unknown(s: &str) -> &str {
+    if is_unknown(s) {
+        UNKNOWN_TEXT
+    } else {
+        s
+    }
+}
+
+/// 値の形式
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// YYYY/MM/DD
+    Date,
+    /// 分:秒（例 4:46）。不明なら UNKNOWN_LENGTH
+    Length,
+    /// YEAR_MIN〜YEAR_MAX の整数
+    Year,
+    /// http:// または https:// で始まる URL
+    Url,
+}
+
+impl Format {
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Date => "date",
+            Format::Length => "length",
+            Format::Year => "year",
+            Format::Url => "url",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Format::Date => "YYYY/MM/DD",
+            Format::Length => "M:SS（分:秒。例 4:46）。不明なら ?",
+            Format::Year => "1900〜2099 の整数",
+            Format::Url => "http:// または https:// で始まる空白なしの URL",
+        }
+    }
+}
+
+/// 項目ごとの制約。`path` の "[]" は配列の各要素。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FieldRule {
+    pub path: &'static str,
+    /// 空（配列なら 0 件）を許さない
+    pub required: bool,
+    pub max_len: Option<usize>,
+    pub format: Option<Format>,
+}
+
+const fn text(path: &'static str, required: bool, max: usize) -> FieldRule {
+    FieldRule { path, required, max_len: Some(max), format: None }
+}
+
+const fn formatted(path: &'static str, required: bool, format: Format) -> FieldRule {
+    FieldRule { path, required, max_len: None, format: Some(format) }
+}
+
+const fn required(path: &'static str) -> FieldRule {
+    FieldRule { path, required: true, max_len: None, format: None }
+}
+
+pub const FIELD_RULES: &[FieldRule] = &[
+    text("title", true, LONG_TEXT_MAX),
+    text("title_alt", false, LONG_TEXT_MAX),
+    text("badge", false, BADGE_MAX),
+    required("janre.main"),
+    required("janre.sub"),
+    text("label", true, SHORT_TEXT_MAX),
+    text("id", true, SHORT_TEXT_MAX),
+    formatted("release_year", true, Format::Year),
+    formatted("record_year[]", true, Format::Year),
+    text("personnel.conductor[].name", false, LONG_TEXT_MAX),
+    text("personnel.conductor[].name_alt", false, LONG_TEXT_MAX),
+    text("personnel.conductor[].tracks", false, SHORT_TEXT_MAX),
+    text("personnel.orchestra[].name", false, LONG_TEXT_MAX),
+    text("personnel.orchestra[].tracks", false, SHORT_TEXT_MAX),
+    text("personnel.company[].name", false, LONG_TEXT_MAX),
+    text("personnel.company[].tracks", false, SHORT_TEXT_MAX),
+    text("personnel.soloists[].name_alt", false, LONG_TEXT_MAX),
+    text("personnel.leader[].name", false, LONG_TEXT_MAX),
+    text("personnel.leader[].name_alt", false, LONG_TEXT_MAX),
+    text("personnel.leader[].instruments", false, LONG_TEXT_MAX),
+    text("personnel.leader[].tracks", false, SHORT_TEXT_MAX),
+    text("personnel.sidemen[].name", false, LONG_TEXT_MAX),
+    text("personnel.sidemen[].name_alt", false, LONG_TEXT_MAX),
+    text("personnel.sidemen[].instruments", false, LONG_TEXT_MAX),
+    text("personnel.sidemen[].tracks", false, SHORT_TEXT_MAX),
+    text("personnel.group[].name", true, LONG_TEXT_MAX),
+    text("personnel.group[].abbr", true, SHORT_TEXT_MAX),
+    text("personnel.group[].members[].name", true, LONG_TEXT_MAX),
+    text("personnel.group[].members[].name_alt", false, LONG_TEXT_MAX),
+    text("personnel.group[].members[].instruments", true, LONG_TEXT_MAX),
+    text("personnel.group[].members[].tracks", true, SHORT_TEXT_MAX),
+    text("composers[]", false, LONG_TEXT_MAX),
+    required("tracks"),
+    text("tracks[].title", false, LONG_TEXT_MAX),
+    text("tracks[].composer", false, LONG_TEXT_MAX),
+    formatted("tracks[].length", true, Format::Length),
+    formatted("date", true, Format::Date),
+    formatted("listens[]", false, Format::Date),
+    text("references[].name", false, LONG_TEXT_MAX),
+    formatted("references[].url", true, Format::Url),
+];
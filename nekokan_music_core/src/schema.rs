@@ -0,0 +1,246 @@
+//! レコード JSON の項目の並びと説明。サーバーは保存のたびにこの並びで書く（`to_canonical_json`）ので、
+//! 手で書いたファイルもアプリが書いたファイルも同じ形になり、差分には中身の変更だけが出る。
+//! 並びは `MusicData` などの宣言順と同じ。ここにない項目は、その階層の最後に名前順で置く。
+
+use serde_json::Value;
+use std::fmt::Write;
+
+/// 項目の説明。配列の `fields` は要素（オブジェクト）の項目。
+#[derive(Debug)]
+pub struct Field {
+    pub name: &'static str,
+    /// "string" / "number" / "bool" / "string[]" / "object" / "object[]" など
+    pub kind: &'static str,
+    /// 省略できる（空なら書かない）項目か
+    pub optional: bool,
+    pub description: &'static str,
+    pub fields: &'static [Field],
+}
+
+const fn field(name: &'static str, kind: &'static str, description: &'static str) -> Field {
+    Field { name, kind, optional: false, description, fields: &[] }
+}
+
+const fn optional(name: &'static str, kind: &'static str, description: &'static str) -> Field {
+    Field { name, kind, optional: true, description, fields: &[] }
+}
+
+const fn nested(name: &'static str, kind: &'static str, description: &'static str, fields: &'static [Field]) -> Field {
+    Field { name, kind, optional: false, description, fields }
+}
+
+const NAME_ALT: Field = optional("name_alt", "string", "名前の別表記（かな名のローマ字など）");
+const TRACKS: Field = field("tracks", "string", "参加したトラック（\"all\"、\"1,3-5\" など）");
+
+/// トップレベルの項目（保存する順）
+pub const RECORD_FIELDS: &[Field] = &[
+    field("title", "string", "タイトル（必須）"),
+    optional("title_alt", "string", "別題（邦題・原題など）"),
+    nested(
+        "janre",
+        "object",
+        "ジャンル",
+        &[field("main", "string", "メインジャンル"), field("sub", "string[]", "サブジャンル")],
+    ),
+    field("label", "string", "レーベル"),
+    field("id", "string", "品番"),
+    field("release_year", "number", "発売年"),
+    field("record_year", "number[]", "録音年（複数年にまたがることがある）"),
+    optional("record_year_unknown", "bool", "録音年が分からない（true のときだけ書く）"),
+    nested(
+        "personnel",
+        "object",
+        "参加者（役割ごとの配列）",
+        &[
+            nested("conductor", "object[]", "指揮者", &[field("name", "string", "名前"), NAME_ALT, TRACKS]),
+            nested("orchestra", "object[]", "オーケストラ", &[field("name", "string", "名前"), TRACKS]),
+            nested("company", "object[]", "合唱団・劇団など", &[field("name", "string", "名前"), TRACKS]),
+            nested(
+                "soloists",
+                "object[]",
+                "ソリスト",
+                &[field("name", "string", "名前"), NAME_ALT, field("instrument", "string", "楽器"), TRACKS],
+            ),
+            nested(
+                "leader",
+                "object[]",
+                "リーダー",
+                &[field("name", "string", "名前"), NAME_ALT, field("instruments", "string", "楽器"), TRACKS],
+            ),
+            nested(
+                "sidemen",
+                "object[]",
+                "サイドメン",
+                &[field("name", "string", "名前"), NAME_ALT, field("instruments", "string", "楽器"), TRACKS],
+            ),
+            nested(
+                "group",
+                "object[]",
+                "グループ",
+                &[
+                    field("name", "string", "グループ名"),
+                    field("abbr", "string", "略称"),
+                    nested(
+                        "members",
+                        "object[]",
+                        "メンバー",
+                        &[
+                            field("name", "string", "名前"),
+                            NAME_ALT,
+                            field("instruments", "string", "楽器"),
+                            TRACKS,
+                            optional("leader", "bool", "リーダー（true のときだけ書く）"),
+                        ],
+                    ),
+                ],
+            ),
+        ],
+    ),
+    optional("composers", "string[]", "作品の作曲者（クラシック向け。トラックの作曲者より優先してラベル・ファイル名に使う）"),
+    nested(
+        "tracks",
+        "object[]",
+        "曲目",
+        &[
+            field("disc_no", "number", "ディスク番号"),
+            field("no", "number", "トラック番号"),
+            field("title", "string", "曲名"),
+            field("composer", "string", "作曲者（複数なら文字列の配列）"),
+            field("length", "string", "長さ（分:秒、不明なら ?）"),
+        ],
+    ),
+    field("score", "number", "スコア（尺度は入力チェックのポリシーで決まる）"),
+    field("comment", "string", "コメント"),
+    field("date", "string", "記録日（YYYY/MM/DD）"),
+    optional("updated_date", "string", "最後に更新した日（YYYY/MM/DD）"),
+    nested(
+        "references",
+        "object[]",
+        "参考リンク",
+        &[field("name", "string", "リンク名"), field("url", "string", "URL")],
+    ),
+    optional("cover", "string", "カバー画像のファイル名"),
+    optional("badge", "string", "サイドバーのバッジ（絵文字）"),
+    optional("related", "string[]", "関連レコードのファイル名"),
+    optional("draft", "bool", "下書き（true のときだけ書く）"),
+    optional("listens", "string[]", "聴いた日（YYYY/MM/DD、追記順）"),
+    optional("schema_version", "number", "形式の版（サーバーが書くときに付ける。ないものは古い形式として読むときに直す）"),
+];
+
+fn pad(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+}
+
+fn write_value(out: &mut String, v: &Value, fields: &[Field], depth: usize) {
+    match v {
+        Value::Object(map) if !map.is_empty() => {
+            let rank = |k: &str| fields.iter().position(|f| f.name == k).unwrap_or(usize::MAX);
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort_by(|a, b| rank(a).cmp(&rank(b)).then_with(|| a.cmp(b)));
+            out.push_str("{\n");
+            for (i, k) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push_str(",\n");
+                }
+                pad(out, depth + 1);
+                let _ = write!(out, "{}: ", Value::String(k.clone()));
+                let sub = fields.iter().find(|f| f.name == k).map_or(&[][..], |f| f.fields);
+                write_value(out, &map[k], sub, depth + 1);
+            }
+            out.push('\n');
+            pad(out, depth);
+            out.push('}');
+        }
+        Value::Array(items) if !items.is_empty() => {
+            out.push_str("[\n");
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(",\n");
+                }
+                pad(out, depth + 1);
+                write_value(out, item, fields, depth + 1);
+            }
+            out.push('\n');
+            pad(out, depth);
+            out.push(']');
+        }
+        other => {
+            let _ = write!(out, "{}", other);
+        }
+    }
+}
+
+/// レコードを決まった並び（`RECORD_FIELDS`）の整形済み JSON にする。字下げは 2 文字。
+pub fn to_canonical_json(v: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, v, RECORD_FIELDS, 0);
+    out
+}
+
+fn describe(out: &mut String, fields: &[Field], prefix: &str) {
+    for f in fields {
+        let path = format!("{}{}", prefix, f.name);
+        let optional = if f.optional { "省略可" } else { "" };
+        let _ = writeln!(out, "| `{}` | {} | {} | {} |", path, f.kind, optional, f.description);
+        let sep = if f.kind.ends_with("[]") { "[]." } else { "." };
+        describe(out, f.fields, &format!("{}{}", path, sep));
+    }
+}
+
+/// 項目の一覧（Markdown の表）。並びは保存する順。
+pub fn schema_markdown() -> String {
+    let mut out = String::from(concat!(
+        "# レコード JSON の項目\n\n",
+        "保存するときはこの順に並べます。ここにない項目は、その階層の最後に名前順で置きます。",
+        "字下げは 2 文字、日付は YYYY/MM/DD です。\n\n",
+        "| 項目 | 型 | | 説明 |\n| --- | --- | --- | --- |\n",
+    ));
+    describe(&mut out, RECORD_FIELDS, "");
+    out
+}
+
+#[cfg(test)]
+mod schema_tests {
+    use super::{schema_markdown, to_canonical_json};
+    use crate::types::*;
+    use serde_json::json;
+
+    #[test]
+    fn canonical_order_matches_the_data_model() {
+        let data = MusicData {
+            title: "Moanin'".into(),
+            title_alt: "モーニン".into(),
+            janre: Janre { main: "Jazz".into(), sub: vec!["Hard Bop".into()] },
+            record_year: vec![1958],
+            personnel: Personnel {
+                leader: vec![LeaderEntry { name: "Art Blakey".into(), instruments: "Drums".into(), tracks: "all".into(), ..Default::default() }],
+                group: vec![GroupEntry {
+                    name: "The Jazz Messengers".into(),
+                    members: vec![GroupMemberEntry { name: "Lee Morgan".into(), leader: true, ..Default::default() }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            tracks: vec![Track { disc_no: 1, no: 1, title: "Moanin'".into(), composer: "Bobby Timmons".into(), length: "9:32".into() }],
+            score: 5.0,
+            references: vec![Reference { name: "Wikipedia".into(), url: "https://example.com".into() }],
+            listens: vec!["2024/03/05".into()],
+            ..Default::default()
+        };
+        // Value にすると項目は名前順になるが、書き出すと宣言順に戻る
+        let v = serde_json::to_value(&data).unwrap();
+        assert_eq!(to_canonical_json(&v), serde_json::to_string_pretty(&data).unwrap());
+    }
+
+    #[test]
+    fn unknown_fields_go_last_by_name() {
+        let v = json!({"zeta": 1, "title": "T", "alpha": {"b": [], "a": {}}, "date": "2024/01/01"});
+        assert_eq!(
+            to_canonical_json(&v),
+            "{\n  \"title\": \"T\",\n  \"date\": \"2024/01/01\",\n  \"alpha\": {\n    \"a\": {},\n    \"b\": []\n  },\n  \"zeta\": 1\n}"
+        );
+        assert!(schema_markdown().contains("| `personnel.group[].members[].leader` | bool | 省略可 |"));
+    }
+}
@@ -17,24 +17,83 @@ where
     }
 }
 
+/// スコアは 4 でも 4.5 でも、文字列 "4" でも受け付ける
+fn deserialize_score<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum NumOrStr {
+        Num(f64),
+        Str(String),
+    }
+    match NumOrStr::deserialize(deserializer)? {
+        NumOrStr::Num(n) => Ok(n),
+        NumOrStr::Str(s) => s.trim().parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// 整数のスコアは従来どおり整数（5）で書き、0.5 刻みのときだけ小数（4.5）で書く
+fn serialize_score<S>(v: &f64, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    if v.fract() == 0.0 {
+        serializer.serialize_i64(*v as i64)
+    } else {
+        serializer.serialize_f64(*v)
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct MusicData {
     pub title: String,
+    /// 別題（邦題・原題など）。重複・関連チェックと検索に使う。
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub title_alt: String,
     pub janre: Janre,
     pub label: String,
     pub id: String,
     #[serde(deserialize_with = "deserialize_i32_flexible")]
     pub release_year: i32,
     pub record_year: Vec<i32>,
+    /// 録音年が分からない（空の record_year とは区別する）
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub record_year_unknown: bool,
     pub personnel: Personnel,
+    /// 作品の作曲者（クラシック向け。トラックの composer とは別に、アルバム全体の主な作曲者を書く）。
+    /// 複数の作品を収めたディスクではトラックから推しはかれないので、ラベル・ファイル名・作曲者の索引はこちらを優先する。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub composers: Vec<String>,
     pub tracks: Vec<Track>,
-    #[serde(deserialize_with = "deserialize_i32_flexible")]
-    pub score: i32,
+    /// 尺度（1〜6 など）はポリシーの ScoreScale で決まる
+    #[serde(deserialize_with = "deserialize_score", serialize_with = "serialize_score")]
+    pub score: f64,
     pub comment: String,
+    /// 記録日（初めて登録した日。設定によっては最後に更新した日）
     pub date: String,
+    /// 最後に更新した日（設定で有効にしたときだけ保存のたびに入れる）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub updated_date: String,
     #[serde(default)]
     pub references: Vec<Reference>,
+    /// カバー画像のファイル名（ライブラリの `covers/` 内、`/api/covers/{cover}` で取得）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub cover: String,
+    /// サイドバーに出すバッジ（"🔴" などの絵文字。意味は設定の凡例で決める）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub badge: String,
+    /// 関連レコード（同じアルバムの別エディション・再発など）のファイル名 "xxx.json"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<String>,
+    /// 下書き（クイック追加などで作った未完成レコード）。true のときのみ保存する。
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub draft: bool,
+    /// 再生記録（聴いた日 YYYY/MM/DD、追記順）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub listens: Vec<String>,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
@@ -73,6 +132,9 @@ pub struct GroupEntry {
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct GroupMemberEntry {
     pub name: String,
+    /// 名前の別表記（かな名のローマ字など）。検索でどちらの表記でも引けるよう併記する。
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name_alt: String,
     pub instruments: String,
     pub tracks: String,
     #[serde(default)]
@@ -87,6 +149,9 @@ fn is_false(b: &bool) -> bool {
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct SoloistEntry {
     pub name: String,
+    /// 名前の別表記（かな名のローマ字など）。検索でどちらの表記でも引けるよう併記する。
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name_alt: String,
     #[serde(default)]
     pub instrument: String,
     pub tracks: String,
@@ -95,6 +160,9 @@ pub struct SoloistEntry {
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct ConductorEntry {
     pub name: String,
+    /// 名前の別表記（かな名のローマ字など）。検索でどちらの表記でも引けるよう併記する。
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name_alt: String,
     pub tracks: String,
 }
 
@@ -113,6 +181,9 @@ pub struct CompanyEntry {
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct LeaderEntry {
     pub name: String,
+    /// 名前の別表記（かな名のローマ字など）。検索でどちらの表記でも引けるよう併記する。
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name_alt: String,
     pub instruments: String,
     pub tracks: String,
 }
@@ -120,6 +191,9 @@ pub struct LeaderEntry {
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct SidemenEntry {
     pub name: String,
+    /// 名前の別表記（かな名のローマ字など）。検索でどちらの表記でも引けるよう併記する。
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name_alt: String,
     pub instruments: String,
     pub tracks: String,
 }
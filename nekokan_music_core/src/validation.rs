@@ -0,0 +1,619 @@
+//! 入力チェック。サーバーも `/api/validate-all` で同じ規則を使う。
+
+use crate::date;
+use crate::length::parse_length;
+use crate::rules::*;
+use crate::types::*;
+use std::collections::HashMap;
+
+pub type FieldErrors = HashMap<String, String>;
+
+/// 必須かどうかをポリシーで切り替えられる項目。title・date・ファイル名は常に必須。
+pub const POLICY_FIELDS: &[&str] = &[
+    "title_alt",
+    "badge",
+    "label",
+    "id",
+    "janre.sub",
+    "record_year",
+    "tracks",
+    "comment",
+    "cover",
+    "references",
+];
+
+/// 必須の切り替え 1 件。`main`・`badge`・`min_score` の条件をすべて満たすレコードにだけ効く。
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RequiredRule {
+    /// POLICY_FIELDS のどれか
+    pub field: String,
+    pub required: bool,
+    /// janre.main がこれのときだけ（空なら全ジャンル）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub main: String,
+    /// バッジがこれのときだけ（ブートレグに付けたバッジなど。空なら問わない）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub badge: String,
+    /// スコアがこれ以上のときだけ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_score: Option<f64>,
+}
+
+impl RequiredRule {
+    fn applies(&self, data: &MusicData) -> bool {
+        (self.main.is_empty() || self.main == data.janre.main)
+            && (self.badge.is_empty() || self.badge == data.badge)
+            && self.min_score.is_none_or(|s| data.score >= s)
+    }
+}
+
+/// スコアの尺度（例: 1〜6、1〜10、0.5〜5 の 0.5 刻み）
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ScoreScale {
+    pub min: f64,
+    pub max: f64,
+    pub step: f64,
+}
+
+impl Default for ScoreScale {
+    fn default() -> Self {
+        ScoreScale { min: f64::from(SCORE_MIN), max: f64::from(SCORE_MAX), step: 1.0 }
+    }
+}
+
+/// 尺度の段数の上限（選択肢が多すぎるものは受け付けない）
+const SCORE_STEPS_MAX: f64 = 100.0;
+
+/// 5 → "5"、4.5 → "4.5"
+pub fn format_score(v: f64) -> String {
+    if v.fract() == 0.0 {
+        format!("{}", v as i64)
+    } else {
+        format!("{}", v)
+    }
+}
+
+impl ScoreScale {
+    pub fn is_valid(&self) -> bool {
+        self.step > 0.0 && self.min < self.max && (self.max - self.min) / self.step <= SCORE_STEPS_MAX
+    }
+
+    /// 選べる値（小さい順）
+    pub fn values(&self) -> Vec<f64> {
+        if !self.is_valid() {
+            return vec![self.min];
+        }
+        let n = ((self.max - self.min) / self.step + 1e-9).floor() as usize;
+        (0..=n).map(|i| self.snap(self.min + i as f64 * self.step)).collect()
+    }
+
+    /// 刻みに合わせて丸める（0.1 刻みなどの誤差を消す）
+    fn snap(&self, v: f64) -> f64 {
+        let v = self.min + ((v - self.min) / self.step).round() * self.step;
+        (v.clamp(self.min, self.max) * 1e6).round() / 1e6
+    }
+
+    pub fn contains(&self, v: f64) -> bool {
+        (self.min..=self.max).contains(&v) && (self.snap(v) - v).abs() < 1e-6
+    }
+
+    /// この尺度の値を `to` の尺度に直線で写し、刻みに合わせる（1〜6 の 6 → 1〜10 の 10）
+    pub fn rescale(&self, v: f64, to: &ScoreScale) -> f64 {
+        let t = ((v - self.min) / (self.max - self.min)).clamp(0.0, 1.0);
+        to.snap(to.min + t * (to.max - to.min))
+    }
+
+    /// "1〜6" / "0.5〜5（0.5 刻み）"
+    pub fn describe(&self) -> String {
+        let range = format!("{}〜{}", format_score(self.min), format_score(self.max));
+        if self.step == 1.0 {
+            range
+        } else {
+            format!("{}（{} 刻み）", range, format_score(self.step))
+        }
+    }
+
+    /// サイドバーの表示。1 刻みで 6 以下なら ★ を並べ、それ以外は "★7.5"。
+    pub fn stars(&self, v: f64) -> String {
+        if self.step == 1.0 && self.max <= 6.0 && v.fract() == 0.0 && v > 0.0 {
+            "★".repeat(v as usize)
+        } else {
+            format!("★{}", format_score(v))
+        }
+    }
+}
+
+/// 入力チェックのポリシー（サーバーの `{DB_PATH}/.config/validation-policy.json`）。
+/// 必須項目は、同じ項目に当てはまるルールが複数あれば後のものが勝つ。どれにも当たらなければ FIELD_RULES の既定。
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ValidationPolicy {
+    #[serde(default)]
+    pub rules: Vec<RequiredRule>,
+    /// スコアの尺度（省略時は 1〜6）
+    #[serde(default)]
+    pub score: ScoreScale,
+    /// 録音からこの年数より後の発売を警告する（省略時は RELEASE_GAP_YEARS）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_gap_years: Option<u32>,
+    /// 保存したときにこの大きさ（KB）を超えるレコードを警告する（省略時は LARGE_RECORD_KB）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub large_record_kb: Option<u32>,
+}
+
+/// 録音から発売までの年数の警告の既定（発掘音源はこれを超えることもあるので警告だけ）
+pub const RELEASE_GAP_YEARS: u32 = 20;
+
+/// 大きすぎるレコードの警告の既定（KB）。ボックスセットでも曲目だけならこの 1/10 ほど。
+/// OCR のテキストをコメントに貼り付けたものなどが一覧の読み込みを遅くしていた
+pub const LARGE_RECORD_KB: u32 = 256;
+
+impl ValidationPolicy {
+    pub fn is_required(&self, field: &str, data: &MusicData) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|r| r.field == field && r.applies(data))
+            .map_or_else(|| default_required(field), |r| r.required)
+    }
+
+    /// POLICY_FIELDS にない項目を指すルール
+    pub fn unknown_fields(&self) -> Vec<&str> {
+        self.rules
+            .iter()
+            .map(|r| r.field.as_str())
+            .filter(|f| !POLICY_FIELDS.contains(f))
+            .collect()
+    }
+}
+
+/// ポリシーがないときに必須か（配列の "record_year[]" は "record_year" で引く）
+fn default_required(field: &str) -> bool {
+    FIELD_RULES
+        .iter()
+        .any(|r| r.required && r.path.strip_suffix("[]").unwrap_or(r.path) == field)
+}
+
+fn valid_len(s: &str, max: usize) -> bool {
+    s.chars().count() <= max
+}
+
+fn max_len_msg(max: usize) -> String {
+    format!("{}文字以内", max)
+}
+
+fn valid_year(y: i32) -> bool {
+    (YEAR_MIN..=YEAR_MAX).contains(&y)
+}
+
+fn valid_length_format(s: &str) -> bool {
+    s.trim() == UNKNOWN_LENGTH || parse_length(s).is_some()
+}
+
+fn valid_url(s: &str) -> bool {
+    if s.is_empty() {
+        return false;
+    }
+    let s = s.trim();
+    (s.starts_with("http://") || s.starts_with("https://"))
+        && !s.contains(char::is_whitespace)
+        && s.len() > 10
+}
+
+fn valid_filename(s: &str) -> bool {
+    if s.is_empty() {
+        return false;
+    }
+    !s.chars().any(|c| FILENAME_FORBIDDEN.contains(&c)) && s.len() <= FILENAME_MAX_BYTES
+}
+
+pub fn validate_form(data: &MusicData, filename: &str, policy: &ValidationPolicy) -> FieldErrors {
+    let mut err = FieldErrors::new();
+    let required = |field: &str| policy.is_required(field, data);
+
+    if data.title.is_empty() {
+        err.insert("title".into(), "必須です".into());
+    } else if !valid_len(&data.title, LONG_TEXT_MAX) {
+        err.insert("title".into(), max_len_msg(LONG_TEXT_MAX));
+    }
+
+    if data.title_alt.is_empty() && required("title_alt") {
+        err.insert("title_alt".into(), "必須です".into());
+    } else if !valid_len(&data.title_alt, LONG_TEXT_MAX) {
+        err.insert("title_alt".into(), max_len_msg(LONG_TEXT_MAX));
+    }
+
+    if data.badge.is_empty() && required("badge") {
+        err.insert("badge".into(), "必須です".into());
+    } else if !valid_len(&data.badge, BADGE_MAX) {
+        err.insert("badge".into(), max_len_msg(BADGE_MAX));
+    }
+
+    if data.janre.main.is_empty() {
+        err.insert("janre.main".into(), "Main Janreを選択してください".into());
+    }
+
+    if data.janre.sub.is_empty() && required("janre.sub") {
+        err.insert("janre.sub".into(), "Sub Janreを1つ以上選択してください".into());
+    }
+
+    if data.label.is_empty() {
+        if required("label") {
+            err.insert("label".into(), "必須です".into());
+        }
+    } else if !valid_len(&data.label, SHORT_TEXT_MAX) {
+        err.insert("label".into(), max_len_msg(SHORT_TEXT_MAX));
+    }
+
+    if data.id.is_empty() {
+        if required("id") {
+            err.insert("id".into(), "必須です".into());
+        }
+    } else if !valid_len(&data.id, SHORT_TEXT_MAX) {
+        err.insert("id".into(), max_len_msg(SHORT_TEXT_MAX));
+    }
+
+    if !valid_year(data.release_year) {
+        err.insert("release_year".into(), format!("{}〜{}の整数", YEAR_MIN, YEAR_MAX));
+    }
+
+    if data.record_year.is_empty() {
+        if required("record_year") && !data.record_year_unknown {
+            err.insert("record_year".into(), "1つ以上の年をカンマ区切りで入力。不明なら ?".into());
+        }
+    } else if data.record_year.iter().any(|&y| !valid_year(y)) {
+        err.insert("record_year".into(), format!("各年は{}〜{}", YEAR_MIN, YEAR_MAX));
+    }
+
+    for (i, c) in data.personnel.conductor.iter().enumerate() {
+        if !valid_len(&c.name, LONG_TEXT_MAX) {
+            err.insert(format!("personnel.conductor[{}].name", i), max_len_msg(LONG_TEXT_MAX));
+        }
+        if !valid_len(&c.name_alt, LONG_TEXT_MAX) {
+            err.insert(format!("personnel.conductor[{}].name_alt", i), max_len_msg(LONG_TEXT_MAX));
+        }
+        if !valid_len(&c.tracks, SHORT_TEXT_MAX) {
+            err.insert(format!("personnel.conductor[{}].tracks", i), max_len_msg(SHORT_TEXT_MAX));
+        }
+    }
+    for (i, o) in data.personnel.orchestra.iter().enumerate() {
+        if !valid_len(&o.name, LONG_TEXT_MAX) {
+            err.insert(format!("personnel.orchestra[{}].name", i), max_len_msg(LONG_TEXT_MAX));
+        }
+        if !valid_len(&o.tracks, SHORT_TEXT_MAX) {
+            err.insert(format!("personnel.orchestra[{}].tracks", i), max_len_msg(SHORT_TEXT_MAX));
+        }
+    }
+    for (i, c) in data.personnel.company.iter().enumerate() {
+        if !valid_len(&c.name, LONG_TEXT_MAX) {
+            err.insert(format!("personnel.company[{}].name", i), max_len_msg(LONG_TEXT_MAX));
+        }
+        if !valid_len(&c.tracks, SHORT_TEXT_MAX) {
+            err.insert(format!("personnel.company[{}].tracks", i), max_len_msg(SHORT_TEXT_MAX));
+        }
+    }
+    for (i, s) in data.personnel.soloists.iter().enumerate() {
+        if !valid_len(&s.name_alt, LONG_TEXT_MAX) {
+            err.insert(format!("personnel.soloists[{}].name_alt", i), max_len_msg(LONG_TEXT_MAX));
+        }
+    }
+    for (i, l) in data.personnel.leader.iter().enumerate() {
+        if !valid_len(&l.name, LONG_TEXT_MAX) {
+            err.insert(format!("personnel.leader[{}].name", i), max_len_msg(LONG_TEXT_MAX));
+        }
+        if !valid_len(&l.name_alt, LONG_TEXT_MAX) {
+            err.insert(format!("personnel.leader[{}].name_alt", i), max_len_msg(LONG_TEXT_MAX));
+        }
+        if !valid_len(&l.instruments, LONG_TEXT_MAX) {
+            err.insert(format!("personnel.leader[{}].instruments", i), max_len_msg(LONG_TEXT_MAX));
+        }
+        if !valid_len(&l.tracks, SHORT_TEXT_MAX) {
+            err.insert(format!("personnel.leader[{}].tracks", i), max_len_msg(SHORT_TEXT_MAX));
+        }
+    }
+    for (i, s) in data.personnel.sidemen.iter().enumerate() {
+        if !valid_len(&s.name, LONG_TEXT_MAX) {
+            err.insert(format!("personnel.sidemen[{}].name", i), max_len_msg(LONG_TEXT_MAX));
+        }
+        if !valid_len(&s.name_alt, LONG_TEXT_MAX) {
+            err.insert(format!("personnel.sidemen[{}].name_alt", i), max_len_msg(LONG_TEXT_MAX));
+        }
+        if !valid_len(&s.instruments, LONG_TEXT_MAX) {
+            err.insert(format!("personnel.sidemen[{}].instruments", i), max_len_msg(LONG_TEXT_MAX));
+        }
+        if !valid_len(&s.tracks, SHORT_TEXT_MAX) {
+            err.insert(format!("personnel.sidemen[{}].tracks", i), max_len_msg(SHORT_TEXT_MAX));
+        }
+    }
+    for (gi, g) in data.personnel.group.iter().enumerate() {
+        if g.name.is_empty() {
+            err.insert(format!("personnel.group[{}].name", gi), "必須です".into());
+        } else if !valid_len(&g.name, LONG_TEXT_MAX) {
+            err.insert(format!("personnel.group[{}].name", gi), max_len_msg(LONG_TEXT_MAX));
+        }
+        if g.abbr.is_empty() {
+            err.insert(format!("personnel.group[{}].abbr", gi), "必須です".into());
+        } else if !valid_len(&g.abbr, SHORT_TEXT_MAX) {
+            err.insert(format!("personnel.group[{}].abbr", gi), max_len_msg(SHORT_TEXT_MAX));
+        }
+        for (mi, m) in g.members.iter().enumerate() {
+            if m.name.is_empty() {
+                err.insert(
+                    format!("personnel.group[{}].members[{}].name", gi, mi),
+                    "必須です".into(),
+                );
+            } else if !valid_len(&m.name, LONG_TEXT_MAX) {
+                err.insert(
+                    format!("personnel.group[{}].members[{}].name", gi, mi),
+                    max_len_msg(LONG_TEXT_MAX),
+                );
+            }
+            if !valid_len(&m.name_alt, LONG_TEXT_MAX) {
+                err.insert(
+                    format!("personnel.group[{}].members[{}].name_alt", gi, mi),
+                    max_len_msg(LONG_TEXT_MAX),
+                );
+            }
+            if m.instruments.is_empty() {
+                err.insert(
+                    format!("personnel.group[{}].members[{}].instruments", gi, mi),
+                    "必須です".into(),
+                );
+            } else if !valid_len(&m.instruments, LONG_TEXT_MAX) {
+                err.insert(
+                    format!("personnel.group[{}].members[{}].instruments", gi, mi),
+                    max_len_msg(LONG_TEXT_MAX),
+                );
+            }
+            if m.tracks.is_empty() {
+                err.insert(
+                    format!("personnel.group[{}].members[{}].tracks", gi, mi),
+                    "必須です。不明なら ?".into(),
+                );
+            } else if !valid_len(&m.tracks, SHORT_TEXT_MAX) {
+                err.insert(
+                    format!("personnel.group[{}].members[{}].tracks", gi, mi),
+                    max_len_msg(SHORT_TEXT_MAX),
+                );
+            }
+        }
+    }
+
+    if data.composers.iter().any(|c| !valid_len(c, LONG_TEXT_MAX)) {
+        err.insert("composers".into(), format!("各作曲者は{}", max_len_msg(LONG_TEXT_MAX)));
+    }
+
+    if data.tracks.is_empty() && required("tracks") {
+        err.insert("tracks".into(), "1件以上のトラックが必要です".into());
+    }
+    for (i, t) in data.tracks.iter().enumerate() {
+        if !valid_len(&t.title, LONG_TEXT_MAX) {
+            err.insert(format!("tracks[{}].title", i), max_len_msg(LONG_TEXT_MAX));
+        }
+        if !valid_len(&t.composer, LONG_TEXT_MAX) {
+            err.insert(format!("tracks[{}].composer", i), max_len_msg(LONG_TEXT_MAX));
+        }
+        if !valid_length_format(&t.length) {
+            err.insert(format!("tracks[{}].length", i), "分:秒の形式（例 4:46）。不明なら ?".into());
+        }
+    }
+
+    if !policy.score.contains(data.score) {
+        err.insert("score".into(), format!("{}を選択", policy.score.describe()));
+    }
+
+    if data.date.is_empty() {
+        err.insert("date".into(), "YYYY/MM/DDで入力".into());
+    } else if !date::is_valid(&data.date) {
+        err.insert("date".into(), "YYYY/MM/DDの形式で".into());
+    }
+    if !data.updated_date.is_empty() && !date::is_valid(&data.updated_date) {
+        err.insert("updated_date".into(), "YYYY/MM/DDの形式で".into());
+    }
+
+    for (i, d) in data.listens.iter().enumerate() {
+        if !date::is_valid(d) {
+            err.insert(format!("listens[{}]", i), "YYYY/MM/DDの形式で".into());
+        }
+    }
+
+    if data.comment.trim().is_empty() && required("comment") {
+        err.insert("comment".into(), "必須です".into());
+    }
+
+    if data.cover.is_empty() && required("cover") {
+        err.insert("cover".into(), "カバー画像を登録してください".into());
+    }
+
+    if data.references.is_empty() && required("references") {
+        err.insert("references".into(), "1件以上の参照が必要です".into());
+    }
+    for (i, r) in data.references.iter().enumerate() {
+        if !valid_len(&r.name, LONG_TEXT_MAX) {
+            err.insert(format!("references[{}].name", i), max_len_msg(LONG_TEXT_MAX));
+        }
+        if !valid_url(&r.url) {
+            err.insert(format!("references[{}].url", i), "有効なURLを入力".into());
+        }
+    }
+
+    if filename.is_empty() {
+        err.insert("filename".into(), "ファイル名を入力してください".into());
+    } else {
+        let f = filename.trim();
+        let f = if f.ends_with(".json") {
+            f.strip_suffix(".json").unwrap_or(f)
+        } else {
+            f
+        };
+        if !valid_filename(f) {
+            err.insert("filename".into(), "ファイル名に使用できない文字が含まれています".into());
+        }
+    }
+
+    err
+}
+
+/// 保存したときのレコードの大きさ
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordSize {
+    /// 整形した JSON 全体（バイト）
+    pub bytes: usize,
+    /// いちばん大きいトップレベルの項目（名前, バイト）
+    pub largest: Option<(String, usize)>,
+}
+
+/// レコードを整形した JSON（サーバーが書くのと同じ）にしたときの大きさ
+pub fn record_size(data: &MusicData) -> RecordSize {
+    let bytes = serde_json::to_string_pretty(data).map_or(0, |s| s.len());
+    let largest = match serde_json::to_value(data) {
+        Ok(serde_json::Value::Object(map)) => map
+            .iter()
+            .map(|(k, v)| (k.clone(), serde_json::to_string(v).map_or(0, |s| s.len())))
+            .max_by_key(|(_, n)| *n),
+        _ => None,
+    };
+    RecordSize { bytes, largest }
+}
+
+/// 大きさの表示（"812 B"、"12.3 KB"、"1.5 MB"）
+pub fn format_size(bytes: usize) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// 保存は止めない注意（エラーと同じくキー → 文言）。
+/// 発売年が録音年より前、または録音から離れすぎているもの（1959 と 1995 の打ち間違いなど）を拾う。
+/// 大きすぎるレコードは "size" に、いちばん大きい項目と一緒に出す。
+pub fn warn_form(data: &MusicData, policy: &ValidationPolicy) -> FieldErrors {
+    let mut warn = FieldErrors::new();
+    let latest = data.record_year.iter().copied().max();
+    if let Some(recorded) = latest.filter(|_| valid_year(data.release_year) && data.record_year.iter().all(|&y| valid_year(y))) {
+        let gap = policy.release_gap_years.unwrap_or(RELEASE_GAP_YEARS);
+        if data.release_year < recorded {
+            warn.insert("release_year".into(), format!("録音年（{}）より前の発売年です", recorded));
+        } else if (data.release_year - recorded) as u32 > gap {
+            warn.insert(
+                "release_year".into(),
+                format!("録音（{}）から {} 年後の発売です。年の打ち間違いではありませんか", recorded, data.release_year - recorded),
+            );
+        }
+    }
+    let limit = policy.large_record_kb.unwrap_or(LARGE_RECORD_KB) as usize * 1024;
+    let size = record_size(data);
+    if size.bytes > limit {
+        let largest = size.largest.map(|(k, n)| format!("（{} が {}）", k, format_size(n))).unwrap_or_default();
+        warn.insert(
+            "size".into(),
+            format!(
+                "レコードが {}{}あり、{} を超えています。貼り付けたテキストが大きすぎないか確かめてください",
+                format_size(size.bytes),
+                largest,
+                format_size(limit)
+            ),
+        );
+    }
+    warn
+}
+
+#[cfg(test)]
+mod validation_tests {
+    use super::{validate_form, warn_form, RequiredRule, ScoreScale, ValidationPolicy};
+    use crate::types::MusicData;
+
+    fn rule(field: &str, required: bool, main: &str, min_score: Option<f64>) -> RequiredRule {
+        RequiredRule { field: field.into(), required, main: main.into(), min_score, ..Default::default() }
+    }
+
+    #[test]
+    fn policy_relaxes_and_tightens_required_fields() {
+        let data = MusicData { score: 6.0, janre: crate::types::Janre { main: "Jazz".into(), sub: vec![] }, ..Default::default() };
+        let strict = ValidationPolicy::default();
+        let errs = validate_form(&data, "x", &strict);
+        assert!(errs.contains_key("label") && !errs.contains_key("references"));
+
+        let policy = ValidationPolicy {
+            rules: vec![
+                rule("label", false, "Jazz", None),
+                rule("references", true, "", Some(6.0)),
+                rule("janre.sub", false, "", None),
+            ],
+            ..Default::default()
+        };
+        let errs = validate_form(&data, "x", &policy);
+        assert!(!errs.contains_key("label") && errs.contains_key("references") && !errs.contains_key("janre.sub"));
+        let low = MusicData { score: 3.0, ..data };
+        assert!(!validate_form(&low, "x", &policy).contains_key("references"));
+        assert!(ValidationPolicy { rules: vec![rule("titel", true, "", None)], ..Default::default() }.unknown_fields() == vec!["titel"]);
+    }
+
+    #[test]
+    fn score_scales_validate_and_rescale() {
+        let six = ScoreScale::default();
+        let ten = ScoreScale { min: 1.0, max: 10.0, step: 1.0 };
+        let half = ScoreScale { min: 0.5, max: 5.0, step: 0.5 };
+        assert_eq!(half.values().len(), 10);
+        assert!(half.contains(4.5) && !half.contains(4.25) && !six.contains(4.5));
+        assert_eq!(six.rescale(6.0, &ten), 10.0);
+        assert_eq!(six.rescale(1.0, &ten), 1.0);
+        assert_eq!(six.rescale(4.0, &half), 3.0);
+        assert_eq!(half.describe(), "0.5〜5（0.5 刻み）");
+        assert_eq!(six.stars(3.0), "★★★");
+        assert_eq!(ten.stars(7.0), "★7");
+        let data = MusicData { score: 4.5, ..Default::default() };
+        let policy = ValidationPolicy { score: half, ..Default::default() };
+        assert!(!validate_form(&data, "x", &policy).contains_key("score"));
+        assert!(validate_form(&data, "x", &ValidationPolicy::default()).contains_key("score"));
+    }
+
+    #[test]
+    fn release_year_far_from_recording_is_a_warning() {
+        let data = |release_year, record_year: Vec<i32>| MusicData { release_year, record_year, ..Default::default() };
+        let policy = ValidationPolicy::default();
+        assert!(warn_form(&data(1995, vec![1959]), &policy).contains_key("release_year"));
+        assert!(warn_form(&data(1958, vec![1957, 1959]), &policy).contains_key("release_year"));
+        assert!(warn_form(&data(1960, vec![1959]), &policy).is_empty());
+        assert!(warn_form(&data(1995, vec![]), &policy).is_empty());
+        let lenient = ValidationPolicy { release_gap_years: Some(40), ..Default::default() };
+        assert!(warn_form(&data(1995, vec![1959]), &lenient).is_empty());
+        // 警告は保存を止めない
+        assert!(!validate_form(&data(1995, vec![1959]), "x", &policy).contains_key("release_year"));
+    }
+
+    #[test]
+    fn large_records_name_the_biggest_field() {
+        let data = MusicData { comment: "OCR ".repeat(100_000), ..Default::default() };
+        let size = super::record_size(&data);
+        assert!(size.bytes > 400_000);
+        assert_eq!(size.largest.as_ref().map(|(k, _)| k.as_str()), Some("comment"));
+        let warn = warn_form(&data, &ValidationPolicy::default());
+        assert!(warn["size"].contains("comment が 390.6 KB"), "{}", warn["size"]);
+        let roomy = ValidationPolicy { large_record_kb: Some(1024), ..Default::default() };
+        assert!(warn_form(&data, &roomy).is_empty());
+        assert_eq!(super::format_size(812), "812 B");
+        assert_eq!(super::format_size(1536), "1.5 KB");
+    }
+
+    #[test]
+    fn unknown_length_passes_but_garbage_does_not() {
+        let track = |length: &str| crate::types::Track { length: length.into(), ..Default::default() };
+        let data = MusicData { tracks: vec![track("?"), track("4:33"), track("4分")], ..Default::default() };
+        let errs = validate_form(&data, "x", &ValidationPolicy::default());
+        assert!(!errs.contains_key("tracks[0].length") && !errs.contains_key("tracks[1].length"));
+        assert!(errs.contains_key("tracks[2].length"));
+    }
+
+    #[test]
+    fn unknown_record_year_is_not_empty() {
+        let policy = ValidationPolicy::default();
+        assert!(validate_form(&MusicData::default(), "x", &policy).contains_key("record_year"));
+        let data = MusicData { record_year_unknown: true, ..Default::default() };
+        assert!(!validate_form(&data, "x", &policy).contains_key("record_year"));
+        // 空とは別に保存する
+        let v = serde_json::to_value(&data).unwrap();
+        assert_eq!((v["record_year"].clone(), v["record_year_unknown"].clone()), (serde_json::json!([]), serde_json::json!(true)));
+        assert!(serde_json::to_value(MusicData::default()).unwrap().get("record_year_unknown").is_none());
+    }
+}
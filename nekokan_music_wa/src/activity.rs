@@ -0,0 +1,101 @@
+use crate::api::{self, Activity, ActivityKind};
+use crate::notes::local_timestamp;
+use yew::prelude::*;
+
+fn kind_label(kind: ActivityKind) -> &'static str {
+    match kind {
+        ActivityKind::Saved => "保存",
+        ActivityKind::Listened => "再生",
+        ActivityKind::Noted => "メモ",
+    }
+}
+
+/// 活動の説明文（"Bill Evans: Alone を保存" など）
+fn activity_text(a: &Activity) -> String {
+    let verb = match a.kind {
+        ActivityKind::Saved => "を保存",
+        ActivityKind::Listened => "を聴いた",
+        ActivityKind::Noted => "にメモ",
+    };
+    format!("{} {}", a.display_label, verb)
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ActivityFeedProps {
+    /// レコード名クリックでそのレコードを開く（"xxx.json"）
+    pub on_select: Callback<String>,
+}
+
+/// 保存・再生・メモを新しい順に並べた活動履歴。種類ごとに絞り込める。
+#[function_component(ActivityFeed)]
+pub fn activity_feed(props: &ActivityFeedProps) -> Html {
+    let kinds = use_state(|| ActivityKind::ALL.to_vec());
+    let feed = use_state(|| None::<Result<Vec<Activity>, String>>);
+
+    {
+        let feed = feed.clone();
+        use_effect_with((*kinds).clone(), move |kinds| {
+            let kinds = kinds.clone();
+            if kinds.is_empty() {
+                feed.set(Some(Ok(vec![])));
+            } else {
+                wasm_bindgen_futures::spawn_local(async move {
+                    feed.set(Some(api::activity(&kinds).await));
+                });
+            }
+            || ()
+        });
+    }
+
+    let toggle = |kind: ActivityKind| {
+        let kinds = kinds.clone();
+        Callback::from(move |_: Event| {
+            // 押した種類だけ含める・外すを反転する
+            kinds.set(
+                ActivityKind::ALL
+                    .into_iter()
+                    .filter(|x| kinds.contains(x) != (*x == kind))
+                    .collect(),
+            );
+        })
+    };
+
+    html! {
+        <div class="activity-feed">
+            <h2 class="view-title">{"活動履歴"}</h2>
+            <div class="form-section activity-filters" role="group" aria-label="種類で絞り込み">
+                { for ActivityKind::ALL.into_iter().map(|k| html! {
+                    <label class="sidebar-filter">
+                        <input type="checkbox" checked={kinds.contains(&k)} onchange={toggle(k)}/>
+                        { kind_label(k) }
+                    </label>
+                }) }
+            </div>
+            { match &*feed {
+                None => html! { <p class="sidebar-loading">{"読込中..."}</p> },
+                Some(Err(e)) => html! { <p class="load-err">{"ロードエラー: "}{ e.clone() }</p> },
+                Some(Ok(list)) if list.is_empty() => html! { <p class="hint">{"履歴はありません"}</p> },
+                Some(Ok(list)) => html! {
+                    <ul class="activity-list">
+                        { for list.iter().map(|a| {
+                            let on_select = props.on_select.clone();
+                            let filename = a.filename.clone();
+                            html! {
+                                <li class="activity-item">
+                                    <span class="activity-time">{ local_timestamp(&a.at) }</span>
+                                    <span class="related-kind">{ kind_label(a.kind) }</span>
+                                    <a href="#" onclick={move |e: MouseEvent| { e.prevent_default(); on_select.emit(filename.clone()); }}>
+                                        { activity_text(a) }
+                                    </a>
+                                    if let Some(ref d) = a.detail {
+                                        <span class="activity-detail">{ d.clone() }</span>
+                                    }
+                                </li>
+                            }
+                        }) }
+                    </ul>
+                },
+            } }
+        </div>
+    }
+}
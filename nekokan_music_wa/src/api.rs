@@ -1,73 +1,2430 @@
+use crate::label::{LabelFields, LabelTemplates};
 use crate::types::MusicData;
 use gloo_net::http::Request;
 use serde_json::Value;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
 
 const API_BASE: &str = "/api";
 
-#[derive(Clone, Debug, serde::Deserialize)]
+/// 選んでいるライブラリを覚えておく localStorage のキー
+const LIBRARY_STORAGE_KEY: &str = "nekokan_music.library";
+
+thread_local! {
+    /// 一覧・読み込み・保存・削除・名前の変更の対象のライブラリ（空なら既定のもの）
+    static LIBRARY: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window().and_then(|w| w.local_storage().ok().flatten())
+}
+
+/// 選んでいるライブラリ（空なら既定のもの）。最初はこの前に選んだものを localStorage から読む
+pub fn current_library() -> String {
+    LIBRARY.with(|l| {
+        l.borrow_mut()
+            .get_or_insert_with(|| {
+                local_storage().and_then(|s| s.get_item(LIBRARY_STORAGE_KEY).ok().flatten()).unwrap_or_default()
+            })
+            .clone()
+    })
+}
+
+/// ライブラリを切り替える（既定のものなら空を渡す）
+pub fn set_library(name: &str) {
+    LIBRARY.with(|l| *l.borrow_mut() = Some(name.to_string()));
+    if let Some(storage) = local_storage() {
+        let _ = if name.is_empty() {
+            storage.remove_item(LIBRARY_STORAGE_KEY)
+        } else {
+            storage.set_item(LIBRARY_STORAGE_KEY, name)
+        };
+    }
+}
+
+/// レコードを扱う API の URL（選んでいるライブラリの `?library=` を付ける。`path` にクエリがあればその後ろに）
+fn library_url(path: &str, library: &str) -> String {
+    if library.is_empty() {
+        format!("{}{}", API_BASE, path)
+    } else {
+        let sep = if path.contains('?') { '&' } else { '?' };
+        format!("{}{}{}library={}", API_BASE, path, sep, library)
+    }
+}
+
+fn files_url(path: &str) -> String {
+    library_url(path, &current_library())
+}
+
+/// `GET /api/libraries` のライブラリ
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct Library {
+    pub name: String,
+    /// `--db-path` のもの（`?library=` を付けない）
+    #[serde(default)]
+    pub default: bool,
+    #[serde(default)]
+    pub count: Option<usize>,
+}
+
+pub async fn libraries() -> Result<Vec<Library>, String> {
+    let resp = Request::get(&format!("{}/libraries", API_BASE)).send().await.map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "libraries failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
 pub struct ListEntryWithLabel {
     pub filename: String,
     pub display_label: String,
+    /// 入力完成度（0〜100）
+    #[serde(default)]
+    pub completeness: u8,
+    /// サイドバーに出すバッジ
+    #[serde(default)]
+    pub badge: String,
+    /// レコードの記録日（YYYY/MM/DD）
+    #[serde(default)]
+    pub date: String,
+    /// ファイルの最終更新日（YYYY/MM/DD）
+    #[serde(default)]
+    pub modified: Option<String>,
+    /// ラベルの材料（人名・タイトル・ジャンル）。ラベルはフロントで組み立てる。
+    #[serde(flatten)]
+    pub fields: LabelFields,
+    #[serde(default)]
+    pub score: Option<f64>,
+    #[serde(default)]
+    pub release_year: Option<i64>,
+    /// 録音年（年の順）
+    #[serde(default)]
+    pub record_year: Vec<i64>,
+}
+
+impl ListEntryWithLabel {
+    /// 発売年・録音年の 1 行（"発売 1960・録音 1958–1959"）。どちらもなければ None
+    pub fn years(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(y) = self.release_year.filter(|y| *y > 0) {
+            parts.push(format!("発売 {}", y));
+        }
+        match (self.record_year.first(), self.record_year.last()) {
+            (Some(a), Some(b)) if a != b => parts.push(format!("録音 {}–{}", a, b)),
+            (Some(a), _) => parts.push(format!("録音 {}", a)),
+            _ => {}
+        }
+        (!parts.is_empty()).then(|| parts.join("・"))
+    }
+}
+
+/// サイドバーの行の高さ
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SidebarDensity {
+    #[default]
+    #[serde(alias = "")]
+    Comfortable,
+    Compact,
+}
+
+/// サイドバーのラベルの形式
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelFormat {
+    /// "Bill Evans: Alone"
+    #[default]
+    #[serde(alias = "")]
+    ArtistTitle,
+    /// "Alone — Bill Evans"
+    TitleArtist,
+}
+
+/// サイドバーの表示形式（`{DB_PATH}/.config/settings.json` の sidebar）
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SidebarDisplay {
+    #[serde(default)]
+    pub density: SidebarDensity,
+    #[serde(default)]
+    pub label_format: LabelFormat,
+    /// スコアを ★ で出す
+    #[serde(default)]
+    pub show_score: bool,
+    /// 発売年を末尾に出す
+    #[serde(default)]
+    pub show_year: bool,
+}
+
+impl SidebarDisplay {
+    /// サイドバーに出すラベル。ラベルの材料がない（古いサーバーの）ときは display_label を使う。
+    /// 「アーティスト: タイトル」ではジャンルごとのテンプレートがあればそれを使う。
+    pub fn label(&self, e: &ListEntryWithLabel, templates: &LabelTemplates) -> String {
+        let base = if e.fields == LabelFields::default() {
+            e.display_label.clone()
+        } else {
+            match self.label_format {
+                LabelFormat::ArtistTitle => e.fields.display_label_with(templates),
+                LabelFormat::TitleArtist => format!("{} — {}", e.fields.title, e.fields.artist()),
+            }
+        };
+        match e.release_year.filter(|_| self.show_year) {
+            Some(y) => format!("{} ({})", base.trim(), y),
+            None => base.trim().to_string(),
+        }
+    }
+}
+
+/// 検索ヒット箇所。hit を強調表示し、前後を before / after で表示する。
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct Snippet {
+    pub before: String,
+    pub hit: String,
+    pub after: String,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct SearchMatch {
+    pub field: String,
+    pub snippet: Snippet,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct SearchResult {
+    pub filename: String,
+    pub display_label: String,
+    pub matches: Vec<SearchMatch>,
 }
 
 #[allow(dead_code)]
 pub async fn list_files() -> Result<Vec<String>, String> {
-    let resp = Request::get(&format!("{}/list", API_BASE))
+    let resp = Request::get(&files_url("/list"))
         .send()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(network_error)?;
     if !resp.ok() {
-        return Err(format!("list failed: {}", resp.status()));
+        return Err(error_of(resp, "list failed").await);
     }
     let list: Vec<String> = resp.json().await.map_err(|e| e.to_string())?;
     Ok(list)
 }
 
 pub async fn list_with_labels() -> Result<Vec<ListEntryWithLabel>, String> {
-    let resp = Request::get(&format!("{}/list-with-labels", API_BASE))
+    let resp = Request::get(&files_url("/list-with-labels"))
         .send()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(network_error)?;
     if !resp.ok() {
-        return Err(format!("list-with-labels failed: {}", resp.status()));
+        return Err(error_of(resp, "list-with-labels failed").await);
     }
     let list: Vec<ListEntryWithLabel> = resp.json().await.map_err(|e| e.to_string())?;
     Ok(list)
 }
 
-pub async fn get_file(name: &str) -> Result<MusicData, String> {
-    let path = format!("{}/files/{}", API_BASE, name);
+/// サーバー側での一覧の絞り込みと並べ替え（`/api/list-with-labels?janre=Jazz&score_min=5&sort=score&order=desc`）。
+/// 空の項目は付けない。
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ListFilter {
+    pub janre: String,
+    pub score_min: Option<f64>,
+    pub year_from: Option<i64>,
+    pub year_to: Option<i64>,
+    /// `label` / `title` / `release_year` / `score` / `modified`。空ならファイル名順
+    pub sort: &'static str,
+    pub descending: bool,
+}
+
+impl ListFilter {
+    pub fn is_empty(&self) -> bool {
+        self.query_pairs().is_empty()
+    }
+
+    pub fn query_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut q = Vec::new();
+        if !self.janre.trim().is_empty() {
+            q.push(("janre", self.janre.trim().to_string()));
+        }
+        if let Some(s) = self.score_min {
+            q.push(("score_min", s.to_string()));
+        }
+        if let Some(y) = self.year_from {
+            q.push(("year_from", y.to_string()));
+        }
+        if let Some(y) = self.year_to {
+            q.push(("year_to", y.to_string()));
+        }
+        if !self.sort.is_empty() {
+            q.push(("sort", self.sort.to_string()));
+        }
+        if self.descending {
+            q.push(("order", "desc".to_string()));
+        }
+        q
+    }
+}
+
+pub async fn list_with_labels_filtered(filter: &ListFilter) -> Result<Vec<ListEntryWithLabel>, String> {
+    let mut query = filter.query_pairs();
+    let library = current_library();
+    if !library.is_empty() {
+        query.push(("library", library));
+    }
+    let resp = Request::get(&format!("{}/list-with-labels", API_BASE))
+        .query(query.iter().map(|(k, v)| (*k, v.as_str())))
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "list-with-labels failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// サーバーのエラー（problem+json の `message`、古いサーバーなら `{"error": ...}`）を優先し、なければ状態コードから説明を作る。
+/// `message` が空で項目のエラー（`errors`）だけあるときは「項目: 説明」を並べる。
+/// 429（回数制限）はプロキシが返して本文がないこともあるので、待ってほしいことだけは伝える。
+pub fn status_error(status: u16, msg: &Value, fallback: &str) -> String {
+    let fields: Vec<String> = msg["errors"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|e| Some(format!("{}: {}", e["field"].as_str()?, e["message"].as_str()?)))
+        .collect();
+    match msg["message"].as_str().or_else(|| msg["error"].as_str()).filter(|m| !m.is_empty()) {
+        Some(m) => m.to_string(),
+        None if !fields.is_empty() => fields.join("、"),
+        None if status == 429 => "リクエストが多すぎます。少し待ってからもう一度試してください".to_string(),
+        None => format!("{}: {}", fallback, status),
+    }
+}
+
+/// 全文検索。`comments` が true のときはコメント本文も検索する。
+pub async fn search(q: &str, comments: bool) -> Result<Vec<SearchResult>, String> {
+    let resp = Request::get(&files_url("/search"))
+        .query([("q", q), ("comments", if comments { "true" } else { "false" })])
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "search failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 曲の索引の 1 演奏（`/api/tunes` の versions の要素）
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct TuneVersion {
+    pub filename: String,
+    pub display_label: String,
+    pub release_year: Option<i64>,
+    pub disc_no: Option<i64>,
+    pub no: Option<i64>,
+    pub title: String,
+    pub composer: String,
+    pub length: String,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct Tune {
+    pub title: String,
+    pub versions: Vec<TuneVersion>,
+}
+
+/// 曲名に `q` を含むトラックを曲ごとにまとめて返す（収録数の多い順）。
+pub async fn tunes(q: &str) -> Result<Vec<Tune>, String> {
+    let resp = Request::get(&files_url("/tunes"))
+        .query([("q", q)])
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "tunes failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 今日のカレンダーの 1 件の種類
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarKind {
+    Released,
+    Recorded,
+    Added,
+    Listened,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct CalendarEntry {
+    pub kind: CalendarKind,
+    pub filename: String,
+    pub display_label: String,
+    /// 周年は年（"1959"）、それ以外は日付（"2016/10/16"）
+    pub when: String,
+    /// 何年前か
+    pub years: i64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct Calendar {
+    pub date: String,
+    pub anniversaries: Vec<CalendarEntry>,
+    pub on_this_day: Vec<CalendarEntry>,
+    pub this_month: Vec<CalendarEntry>,
+}
+
+/// 今日（サーバーの日付）の周年・過去の同じ日に登録した／聴いたレコード
+pub async fn calendar() -> Result<Calendar, String> {
+    let resp = Request::get(&files_url("/calendar"))
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "calendar failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 人名の台帳の 1 人（サーバーの `names::Person` と同じ形）
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Person {
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NameRegistry {
+    #[serde(default)]
+    pub people: Vec<Person>,
+}
+
+/// `/api/composers` の 1 人
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct Composer {
+    pub name: String,
+    /// 正規名と違う表記
+    pub spellings: Vec<String>,
+    pub tracks: usize,
+    pub records: usize,
+}
+
+pub async fn get_names() -> Result<NameRegistry, String> {
+    let resp = Request::get(&format!("{}/names", API_BASE))
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "names failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 台帳を保存し、サーバー側で整えた台帳を返す。
+pub async fn save_names(names: &NameRegistry) -> Result<NameRegistry, String> {
+    let resp = Request::post(&format!("{}/names", API_BASE))
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(names).map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "save names failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 作曲者の一覧（トラック数の多い順）
+pub async fn composers() -> Result<Vec<Composer>, String> {
+    let resp = Request::get(&files_url("/composers"))
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "composers failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// レコードと、その版（`ETag`。保存のときに `SaveBase::Version` で返す）
+pub async fn get_file(name: &str) -> Result<(MusicData, Option<String>), String> {
+    let path = files_url(&format!("/files/{}", name));
     let resp = Request::get(&path)
         .send()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(network_error)?;
+    let version = resp.headers().get("etag").map(|t| t.trim_start_matches("W/").trim_matches('"').to_string());
+    if !resp.ok() {
+        return Err(error_of(resp, "ロードに失敗しました").await);
+    }
     let value: Value = resp.json().await.map_err(|e| e.to_string())?;
+    let data = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    Ok((data, version))
+}
+
+/// レコードを Markdown で取る URL（メモに貼る用）
+pub fn markdown_url(name: &str) -> String {
+    files_url(&format!("/files/{}/markdown", name))
+}
+
+/// レコードを消す（カバー・メモ・添付もサーバー側で消える）
+pub async fn delete_file(name: &str) -> Result<(), String> {
+    let path = files_url(&format!("/files/{}", name));
+    let resp = Request::delete(&path)
+        .send()
+        .await
+        .map_err(network_error)?;
     if !resp.ok() {
-        let msg = value["error"]
-            .as_str()
-            .unwrap_or("ロードに失敗しました")
-            .to_string();
-        return Err(msg);
+        return Err(error_of(resp, "削除に失敗しました").await);
     }
-    serde_json::from_value(value).map_err(|e| e.to_string())
+    Ok(())
 }
 
-pub async fn save_file(filename: &str, data: &MusicData) -> Result<(), String> {
-    let mut f = filename.trim().to_string();
-    if f.ends_with(".json") {
-        f = f.strip_suffix(".json").unwrap_or(&f).to_string();
+/// 端末ごとの API トークン（シークレットは作ったときにしか返らない）
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct DeviceToken {
+    pub id: String,
+    pub name: String,
+    pub created: String,
+    #[serde(default)]
+    pub last_used: Option<String>,
+    /// この端末のセッションで使っているトークン
+    #[serde(default)]
+    pub current: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct TokenList {
+    /// トークンが 1 つでもあれば書き込みに認証が要る
+    pub enabled: bool,
+    pub tokens: Vec<DeviceToken>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct CreatedToken {
+    pub token: DeviceToken,
+    pub secret: String,
+}
+
+/// リクエストがサーバーに届かなかったとき。ブラウザはサーバーが止まっているときも、CORS で止めたときも同じ
+/// TypeError（"Failed to fetch"）しか返さないので、どちらもありうると書く。
+const UNREACHABLE: &str = "サーバーに接続できませんでした。サーバーが動いているか確かめてください。\
+    別のオリジン（ドメイン・ポート）からこの画面を開いているなら、サーバーの --cors-origin（CORS_ORIGINS）にそのオリジンを加えてください";
+
+/// fetch の失敗をメッセージにする
+fn network_error(e: gloo_net::Error) -> String {
+    match e {
+        gloo_net::Error::JsError(js) if js.name == "TypeError" => format!("{}（{}）", UNREACHABLE, js.message),
+        e => e.to_string(),
     }
-    let body = serde_json::json!({ "filename": f, "data": data });
-    let resp = Request::post(&format!("{}/save", API_BASE))
+}
+
+async fn error_of(resp: gloo_net::http::Response, fallback: &str) -> String {
+    let status = resp.status();
+    let msg: Value = resp.json().await.unwrap_or(Value::Null);
+    status_error(status, &msg, fallback)
+}
+
+pub async fn list_tokens() -> Result<TokenList, String> {
+    let resp = Request::get(&format!("{}/tokens", API_BASE)).send().await.map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "トークンの一覧を読めませんでした").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// トークンを作る。`use_here` ならこの端末もそのトークンでログインする。
+pub async fn create_token(name: &str, use_here: bool) -> Result<CreatedToken, String> {
+    let resp = Request::post(&format!("{}/tokens", API_BASE))
         .header("Content-Type", "application/json")
-        .body(body.to_string())
+        .body(serde_json::json!({"name": name, "use_here": use_here}).to_string())
         .map_err(|e| e.to_string())?
         .send()
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(network_error)?;
     if !resp.ok() {
-        let msg: Value = resp.json().await.unwrap_or(Value::Null);
-        return Err(msg["error"].as_str().unwrap_or("save failed").to_string());
+        return Err(error_of(resp, "トークンを作れませんでした").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+pub async fn revoke_token(id: &str) -> Result<(), String> {
+    let resp = Request::delete(&format!("{}/tokens/{}", API_BASE, id)).send().await.map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "トークンを取り消せませんでした").await);
+    }
+    Ok(())
+}
+
+/// トークンでこの端末をログインさせる（サーバーがセッション Cookie を返す）。トークンの名前を返す。
+pub async fn login(token: &str) -> Result<String, String> {
+    let resp = Request::post(&format!("{}/session", API_BASE))
+        .header("Content-Type", "application/json")
+        .body(serde_json::json!({"token": token}).to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "ログインできませんでした").await);
+    }
+    let v: Value = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(v["name"].as_str().unwrap_or("").to_string())
+}
+
+pub async fn logout() -> Result<(), String> {
+    let resp = Request::delete(&format!("{}/session", API_BASE)).send().await.map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "ログアウトできませんでした").await);
     }
     Ok(())
 }
+
+/// 同期の設定（トークンはサーバーが伏せて返す）
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct SyncConfig {
+    pub primary: String,
+    pub interval_minutes: u32,
+    pub has_token: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct SyncConflict {
+    pub filename: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct SyncReport {
+    pub at: String,
+    pub dry_run: bool,
+    pub pulled: Vec<String>,
+    pub up_to_date: usize,
+    pub local_changes: Vec<String>,
+    pub conflicts: Vec<SyncConflict>,
+    pub only_here: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct SyncStatus {
+    pub config: SyncConfig,
+    pub last_report: Option<SyncReport>,
+}
+
+pub async fn sync_status() -> Result<SyncStatus, String> {
+    let resp = Request::get(&format!("{}/sync", API_BASE)).send().await.map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "同期の設定を読めませんでした").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 同期の設定を保存する。`token` が None なら今のトークンのまま。
+pub async fn save_sync_config(primary: &str, token: Option<&str>, interval_minutes: u32) -> Result<SyncConfig, String> {
+    let resp = Request::post(&format!("{}/sync", API_BASE))
+        .header("Content-Type", "application/json")
+        .body(serde_json::json!({"primary": primary, "token": token, "interval_minutes": interval_minutes}).to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "同期の設定を保存できませんでした").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 取り込み元から変更を取り込む（`dry_run` なら何を取り込むかだけ）
+pub async fn sync_pull(dry_run: bool) -> Result<SyncReport, String> {
+    let resp = Request::post(&format!("{}/sync/pull", API_BASE))
+        .header("Content-Type", "application/json")
+        .body(serde_json::json!({"dry_run": dry_run}).to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "同期に失敗しました").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 週報の設定（Webhook の URL はサーバーが伏せて返す）
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct DigestConfig {
+    pub has_webhook: bool,
+    pub smtp_host: String,
+    pub mail_from: String,
+    pub mail_to: Vec<String>,
+    /// 1 = 月曜 … 7 = 日曜、0 なら自動では送らない
+    pub weekday: u8,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct DigestReport {
+    pub at: String,
+    pub period: String,
+    pub sent: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct DigestStatus {
+    pub config: DigestConfig,
+    pub last_sent: Option<String>,
+    pub last_report: Option<DigestReport>,
+}
+
+pub async fn digest_status() -> Result<DigestStatus, String> {
+    let resp = Request::get(&format!("{}/digest", API_BASE)).send().await.map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "週報の設定を読めませんでした").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 週報の設定を保存する。`webhook` が None なら今の URL のまま。
+pub async fn save_digest_config(
+    webhook: Option<&str>,
+    smtp_host: &str,
+    mail_from: &str,
+    mail_to: &[String],
+    weekday: u8,
+) -> Result<DigestConfig, String> {
+    let body = serde_json::json!({
+        "webhook": webhook,
+        "smtp_host": smtp_host,
+        "mail_from": mail_from,
+        "mail_to": mail_to,
+        "weekday": weekday,
+    });
+    let resp = Request::post(&format!("{}/digest", API_BASE))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "週報の設定を保存できませんでした").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 今日送るとしたときの週報の本文（Markdown）
+pub async fn digest_preview() -> Result<String, String> {
+    let resp = Request::get(&files_url("/digest/preview")).send().await.map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "週報を組み立てられませんでした").await);
+    }
+    let v: Value = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(v["text"].as_str().unwrap_or_default().to_string())
+}
+
+/// 週報を今すぐ送る
+pub async fn send_digest() -> Result<DigestReport, String> {
+    let resp = Request::post(&files_url("/digest/send")).send().await.map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "週報を送れませんでした").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 保存期間（`--backup-max-days` / `--log-max-days` / `--history-keep` など、0 なら消さない）
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct Retention {
+    pub backup_max_days: u32,
+    pub log_max_days: u32,
+    #[serde(default)]
+    pub history_keep: usize,
+    #[serde(default)]
+    pub history_max_days: u32,
+    #[serde(default)]
+    pub trash_keep: usize,
+    #[serde(default)]
+    pub trash_max_days: u32,
+}
+
+/// ログの 1 つ（整理で消した分、または今の大きさ）
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct LogSize {
+    pub file: String,
+    #[serde(default)]
+    pub lines: usize,
+    pub bytes: u64,
+}
+
+/// ライブラリごとの履歴・ごみ箱（整理で消した分、または今の大きさ）。`count` はコミットかごみ箱のファイルの数
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct LibrarySize {
+    pub library: String,
+    #[serde(default)]
+    pub count: usize,
+    pub bytes: u64,
+}
+
+/// 整理の結果
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct PruneReport {
+    pub at: String,
+    pub snapshots: Vec<String>,
+    pub snapshot_bytes: u64,
+    pub logs: Vec<LogSize>,
+    #[serde(default)]
+    pub history: Vec<LibrarySize>,
+    #[serde(default)]
+    pub trash: Vec<LibrarySize>,
+    pub reclaimed_bytes: u64,
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct RetentionStatus {
+    pub retention: Retention,
+    /// 残すスナップショットの数（自動バックアップのときだけ、0 なら数では消さない）
+    pub backup_keep: usize,
+    pub snapshots: usize,
+    pub snapshot_bytes: u64,
+    pub logs: Vec<LogSize>,
+    /// レコードの履歴の大きさ（`--git-history` のライブラリだけ）
+    #[serde(default)]
+    pub history: Vec<LibrarySize>,
+    #[serde(default)]
+    pub trash: Vec<LibrarySize>,
+    pub last_report: Option<PruneReport>,
+}
+
+pub async fn retention_status() -> Result<RetentionStatus, String> {
+    let resp = Request::get(&format!("{}/maintenance/retention", API_BASE)).send().await.map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "保存期間を読めませんでした").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 保存期間を過ぎたスナップショット・ログの行・履歴のコミット・ごみ箱のレコードを今すぐ消す
+pub async fn prune_now() -> Result<PruneReport, String> {
+    let resp = Request::post(&format!("{}/maintenance/prune", API_BASE)).send().await.map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "整理できませんでした").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 変更の記録の 1 件（サーバーの `logs::AuditRow`）
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct AuditEntry {
+    pub at: String,
+    pub token: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    /// "create" / "update" / "delete" / "rename"
+    pub action: Option<String>,
+    pub file: Option<String>,
+    pub renamed_to: Option<String>,
+    pub library: Option<String>,
+    /// 変わった項目（"janre.main, score"）
+    pub fields: Option<String>,
+}
+
+/// レコードの変更の記録を新しい順に（`file` はファイル名の一部、`from`・`to` は YYYY/MM/DD。空なら絞らない）
+pub async fn audit_log(file: &str, from: &str, to: &str) -> Result<Vec<AuditEntry>, String> {
+    let query: Vec<(&str, &str)> =
+        [("file", file.trim()), ("from", from.trim()), ("to", to.trim())].into_iter().filter(|(_, v)| !v.is_empty()).collect();
+    let resp = Request::get(&format!("{}/audit", API_BASE)).query(query).send().await.map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "変更の記録を読めませんでした").await);
+    }
+    let v: Value = resp.json().await.map_err(|e| e.to_string())?;
+    serde_json::from_value(v["entries"].clone()).map_err(|e| e.to_string())
+}
+
+/// 名前を変えたあとのレコード
+#[derive(Clone, Debug, PartialEq)]
+pub struct Renamed {
+    /// 変更後のファイル名（"xxx.json"）
+    pub filename: String,
+    /// 変更後の版（カバー名を書き換えると変わる）
+    pub version: Option<String>,
+    /// カバーも新しい名前にしたときのカバーのファイル名
+    pub cover: Option<String>,
+}
+
+/// レコードの名前を変える（カバー・メモ・添付・ほかのレコードの related もサーバー側で付け替える）。
+/// `version`（読んだときの版）を渡すと、ほかで変わっていれば変えずに失敗する。
+pub async fn rename_file(from: &str, to: &str, version: Option<&str>) -> Result<Renamed, String> {
+    let resp = Request::post(&files_url("/rename"))
+        .header("Content-Type", "application/json")
+        .body(serde_json::json!({"from": from, "to": to, "version": version}).to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    let status = resp.status();
+    let value: Value = resp.json().await.unwrap_or(Value::Null);
+    if !resp.ok() {
+        return Err(status_error(status, &value, "名前の変更に失敗しました"));
+    }
+    Ok(Renamed {
+        filename: value["filename"].as_str().unwrap_or(to).to_string(),
+        version: value["version"].as_str().map(str::to_string),
+        cover: value["cover"].as_str().map(str::to_string),
+    })
+}
+
+/// 保存の前提（ほかのタブ・端末の変更を黙って上書きしないように）
+#[derive(Clone, Debug, PartialEq)]
+pub enum SaveBase {
+    /// 新しく作る（同じ名前があれば衝突）
+    New,
+    /// 読んだときの版のままなら書く
+    Version(String),
+}
+
+/// 保存の失敗
+#[derive(Clone, Debug, PartialEq)]
+pub enum SaveError {
+    Failed(String),
+    /// 指定時間、送信も応答も進まなかった（サーバー側では保存が続いている可能性がある）
+    TimedOut,
+    /// 読んだあとにほかで変更・作成・削除されていた（409）。`current` は今の版（消されていれば None）
+    Conflict { message: String, current: Option<String> },
+}
+
+fn js_err(e: wasm_bindgen::JsValue) -> SaveError {
+    SaveError::Failed(e.as_string().unwrap_or_else(|| format!("{:?}", e)))
+}
+
+/// 保存リクエストの冪等キー（UUID）。再送では同じキーを使う。
+pub fn new_idempotency_key() -> String {
+    web_sys::window()
+        .and_then(|w| w.crypto().ok())
+        .map(|c| c.random_uuid())
+        .unwrap_or_else(|| format!("{:x}-{:x}", js_sys::Date::now() as u64, (js_sys::Math::random() * 1e16) as u64))
+}
+
+/// レコードを保存する。`idempotency_key` が同じ再送はサーバーが二重に保存しない。進捗は `on_progress(送信済みバイト, 全体のバイト)` で通知する。
+/// タイムアウトは全体の時間ではなく「送信も応答も進まない時間」で、大きなレコードの送信中は切れない。
+/// タイムアウトしても送信は止めない（サーバーは受け取った分を最後まで保存する）。
+/// 書けたら新しい版を返す。`base` に合わなければ `SaveError::Conflict`。
+pub async fn save_file(
+    filename: &str,
+    data: &MusicData,
+    base: &SaveBase,
+    idempotency_key: &str,
+    stall_timeout_ms: u32,
+    on_progress: impl Fn(f64, f64) + 'static,
+) -> Result<Option<String>, SaveError> {
+    let mut f = filename.trim().to_string();
+    if f.ends_with(".json") {
+        f = f.strip_suffix(".json").unwrap_or(&f).to_string();
+    }
+    let body = serde_json::json!({ "filename": f, "data": data });
+    // アップロードの進捗は fetch では取れないので XMLHttpRequest を使う
+    let xhr = web_sys::XmlHttpRequest::new().map_err(js_err)?;
+    xhr.open_with_async("POST", &files_url("/save"), true).map_err(js_err)?;
+    xhr.set_request_header("Content-Type", "application/json").map_err(js_err)?;
+    xhr.set_request_header("Idempotency-Key", idempotency_key).map_err(js_err)?;
+    match base {
+        SaveBase::New => xhr.set_request_header("If-None-Match", "*").map_err(js_err)?,
+        SaveBase::Version(v) => xhr.set_request_header("If-Match", &format!("\"{}\"", v)).map_err(js_err)?,
+    }
+    let upload = xhr.upload().map_err(js_err)?;
+
+    let last_progress = Rc::new(Cell::new(js_sys::Date::now()));
+    let on_upload_progress = {
+        let last_progress = last_progress.clone();
+        Closure::<dyn FnMut(web_sys::ProgressEvent)>::new(move |e: web_sys::ProgressEvent| {
+            last_progress.set(js_sys::Date::now());
+            if e.length_computable() {
+                on_progress(e.loaded(), e.total());
+            }
+        })
+    };
+    upload.set_onprogress(Some(on_upload_progress.as_ref().unchecked_ref()));
+
+    let (tx, mut rx) = futures::channel::oneshot::channel::<Result<Option<String>, SaveError>>();
+    let tx = Rc::new(RefCell::new(Some(tx)));
+    let on_loadend = {
+        let xhr = xhr.clone();
+        Closure::<dyn FnMut()>::new(move || {
+            let text = xhr.response_text().ok().flatten().unwrap_or_default();
+            let msg: Value = serde_json::from_str(&text).unwrap_or(Value::Null);
+            let res = match xhr.status() {
+                Ok(s) if (200..300).contains(&s) => Ok(msg["version"].as_str().map(str::to_string)),
+                Ok(0) | Err(_) => Err(SaveError::Failed(UNREACHABLE.to_string())),
+                Ok(409) => Err(SaveError::Conflict {
+                    message: status_error(409, &msg, "conflict"),
+                    current: msg["current_version"].as_str().map(str::to_string),
+                }),
+                Ok(s) => Err(SaveError::Failed(status_error(s, &msg, "save failed"))),
+            };
+            if let Some(tx) = tx.borrow_mut().take() {
+                let _ = tx.send(res);
+            }
+        })
+    };
+    xhr.set_onloadend(Some(on_loadend.as_ref().unchecked_ref()));
+    xhr.send_with_opt_str(Some(&body.to_string())).map_err(js_err)?;
+
+    let res = loop {
+        let tick = gloo_timers::future::TimeoutFuture::new(500);
+        match futures::future::select(&mut rx, tick).await {
+            futures::future::Either::Left((res, _)) => {
+                break res.unwrap_or_else(|_| Err(SaveError::Failed("save cancelled".into())));
+            }
+            futures::future::Either::Right(((), _)) => {
+                if js_sys::Date::now() - last_progress.get() > f64::from(stall_timeout_ms) {
+                    break Err(SaveError::TimedOut);
+                }
+            }
+        }
+    };
+    // 戻ったあとにコールバックが呼ばれないよう外す（Closure はここで破棄される）
+    upload.set_onprogress(None);
+    xhr.set_onloadend(None);
+    res
+}
+
+/// リリース情報を探す外部のデータベース
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct MetadataProvider {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct MetadataProviders {
+    pub providers: Vec<MetadataProvider>,
+    /// 設定で選んでいるもの
+    pub selected: String,
+}
+
+/// 検索結果の 1 件（サーバーの `metadata::ReleaseSummary`）
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct ReleaseSummary {
+    pub id: String,
+    pub title: String,
+    pub artist: String,
+    #[serde(default)]
+    pub year: Option<i32>,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub catalog: String,
+    #[serde(default)]
+    pub country: String,
+    #[serde(default)]
+    pub format: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct ReleaseTrack {
+    pub disc_no: i32,
+    pub no: i32,
+    pub title: String,
+    #[serde(default)]
+    pub length: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct Release {
+    #[serde(flatten)]
+    pub summary: ReleaseSummary,
+    #[serde(default)]
+    pub tracks: Vec<ReleaseTrack>,
+    #[serde(default)]
+    pub url: String,
+    /// アルバム全体の作曲・編曲のクレジット（VGMdb など分かる提供元だけ）
+    #[serde(default)]
+    pub composers: Vec<String>,
+    #[serde(default)]
+    pub arrangers: Vec<String>,
+}
+
+pub async fn metadata_providers() -> Result<MetadataProviders, String> {
+    let resp = Request::get(&format!("{}/metadata/providers", API_BASE)).send().await.map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "metadata providers failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 外部のデータベースでリリースを探す（空の条件は送らない）
+pub async fn search_releases(provider: &str, artist: &str, title: &str, catalog: &str) -> Result<Vec<ReleaseSummary>, String> {
+    let query: Vec<(&str, &str)> = [("provider", provider), ("artist", artist), ("title", title), ("catalog", catalog)]
+        .into_iter()
+        .filter(|(_, v)| !v.trim().is_empty())
+        .collect();
+    let resp = Request::get(&format!("{}/metadata/search", API_BASE))
+        .query(query)
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "search failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// いちばん近いリリースと、ほかの候補（`GET /api/lookup/{provider}`）
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct LookupResult {
+    pub release: Release,
+    /// MusicData の形（クレジットから作ったパーソネル・曲ごとの作曲者も）
+    #[serde(default)]
+    pub data: MusicData,
+    #[serde(default)]
+    pub candidates: Vec<ReleaseSummary>,
+}
+
+/// CD の TOC（`cd-discid` の出力か開始フレームとリードアウト）か 8 桁のディスク ID で CDDB から曲目を引く
+pub async fn lookup_cddb(toc: &str) -> Result<LookupResult, String> {
+    let toc = toc.trim();
+    let is_disc_id = toc.len() == 8 && toc.chars().all(|c| c.is_ascii_hexdigit());
+    let resp = Request::get(&format!("{}/lookup/cddb", API_BASE))
+        .query([(if is_disc_id { "discid" } else { "toc" }, toc)])
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "CDDB lookup failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+pub async fn lookup_release(provider: &str, artist: &str, album: &str) -> Result<LookupResult, String> {
+    let resp = Request::get(&format!("{}/lookup/{}", API_BASE, provider))
+        .query([("artist", artist.trim()), ("album", album.trim())])
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "lookup failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+pub async fn fetch_release(provider: &str, id: &str) -> Result<Release, String> {
+    let resp = Request::get(&format!("{}/metadata/releases/{}", API_BASE, id))
+        .query([("provider", provider)])
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "release failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// リリースのカバーを取ってきて保存済みのレコードのカバーにする。カバーのファイル名を返す
+pub async fn import_release_cover(provider: &str, id: &str, filename: &str) -> Result<String, String> {
+    let resp = Request::post(&files_url("/metadata/cover"))
+        .header("Content-Type", "application/json")
+        .body(serde_json::json!({"provider": provider, "id": id, "filename": filename}).to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "cover failed").await);
+    }
+    let value: Value = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(value["cover"].as_str().unwrap_or("").to_string())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateKind {
+    /// 同じレコードを二重に登録している可能性
+    Duplicate,
+    /// エディション違い・再発など関連するレコード
+    Related,
+}
+
+/// 重複・関連チェックの候補
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct DuplicateCandidate {
+    pub filename: String,
+    pub display_label: String,
+    pub kind: DuplicateKind,
+    pub edition: Option<String>,
+}
+
+/// 編集中のレコードと重複・関連しそうな既存レコードを探す。`filename` は編集中のファイル（新規なら None）。
+pub async fn check_duplicates(data: &MusicData, filename: Option<&str>) -> Result<Vec<DuplicateCandidate>, String> {
+    let body = serde_json::json!({ "record": data, "filename": filename });
+    let resp = Request::post(&files_url("/duplicates/check"))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "duplicate check failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// `/api/duplicates` の 1 組（ファイル名だけ違う二重登録の候補）
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct DuplicateGroup {
+    /// "id"（同じ id）/ "title_artist"（同じタイトルと最初の leader / group）
+    #[serde(default)]
+    pub reasons: Vec<String>,
+    pub files: Vec<DuplicateFile>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct DuplicateFile {
+    pub filename: String,
+    #[serde(default)]
+    pub display_label: String,
+}
+
+pub async fn list_duplicates() -> Result<Vec<DuplicateGroup>, String> {
+    let resp = Request::get(&files_url("/duplicates")).send().await.map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "duplicates failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// カバー画像の URL
+pub fn cover_url(cover: &str) -> String {
+    files_url(&format!("/covers/{}", cover))
+}
+
+/// レコード `filename` のカバーとして画像をアップロードする。登録されたカバー名を返す。
+pub async fn upload_cover(filename: &str, file: &web_sys::File) -> Result<String, String> {
+    let form = web_sys::FormData::new().map_err(|e| format!("{:?}", e))?;
+    form.append_with_str("filename", filename).map_err(|e| format!("{:?}", e))?;
+    form.append_with_blob_and_filename("file", file, &file.name())
+        .map_err(|e| format!("{:?}", e))?;
+    let resp = Request::post(&files_url("/covers"))
+        .body(form)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    let status = resp.status();
+    let value: Value = resp.json().await.unwrap_or(Value::Null);
+    if !resp.ok() {
+        return Err(status_error(status, &value, "upload failed"));
+    }
+    value["cover"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| "upload failed".to_string())
+}
+
+/// 再生記録を追記する前と後のレコードの版
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct Listened {
+    #[serde(default)]
+    pub previous_version: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+/// 既存レコードの再生記録に `date`（YYYY/MM/DD）を追記する。
+pub async fn append_listen(filename: &str, date: &str) -> Result<Listened, String> {
+    let body = serde_json::json!({ "filename": filename, "date": date });
+    let resp = Request::post(&files_url("/listen"))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "listen failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// アーティスト・タイトルだけの下書きレコードをサーバーに作らせ、作成されたファイル名（"xxx.json"）を返す。
+/// `listened` が true なら `date` を再生記録にも入れる。
+pub async fn create_draft(artist: &str, title: &str, date: &str, listened: bool) -> Result<String, String> {
+    let body = serde_json::json!({ "artist": artist, "title": title, "date": date, "listened": listened });
+    let resp = Request::post(&files_url("/draft"))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    let status = resp.status();
+    let value: Value = resp.json().await.unwrap_or(Value::Null);
+    if !resp.ok() {
+        return Err(status_error(status, &value, "draft failed"));
+    }
+    value["filename"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| "draft failed".to_string())
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct RecordListens {
+    pub filename: String,
+    pub display_label: String,
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct WeekListens {
+    pub week_start: String,
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct GenreShare {
+    pub genre: String,
+    pub count: usize,
+    pub ratio: f64,
+}
+
+/// `/api/stats/listens` の集計結果
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct ListenStats {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub total: usize,
+    pub records: Vec<RecordListens>,
+    pub weeks: Vec<WeekListens>,
+    pub genres: Vec<GenreShare>,
+}
+
+/// 期間（YYYY/MM/DD、空なら制限なし）の再生記録を集計する。
+pub async fn listen_stats(from: &str, to: &str) -> Result<ListenStats, String> {
+    let resp = Request::get(&files_url("/stats/listens"))
+        .query([("from", from), ("to", to)])
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "listen stats failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// `/api/ws` で届くライブラリの変更
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct LibraryEvent {
+    /// "created" / "updated" / "deleted"
+    pub event: String,
+    pub filename: String,
+}
+
+/// ページと同じホストの `/api/ws`（HTTPS なら wss）
+pub fn ws_url(protocol: &str, host: &str) -> String {
+    let scheme = if protocol == "https:" { "wss" } else { "ws" };
+    format!("{}://{}{}/ws", scheme, host, API_BASE)
+}
+
+/// Discogs CSV エクスポートの URL（そのままリンク先にする）
+pub fn discogs_export_url() -> String {
+    files_url("/export/discogs.csv")
+}
+
+/// 年間のふりかえり（Markdown）の URL（そのままリンク先にする）
+pub fn year_review_url(year: &str) -> String {
+    files_url(&format!("/export/year-review?year={}", year.trim()))
+}
+
+/// 選んでいるライブラリのレコードをまとめた ZIP の URL（そのままリンク先にする）。空の条件は付けない
+pub fn zip_export_url(genre: &str, from: &str, to: &str) -> String {
+    let library = current_library();
+    let query: Vec<String> = [("library", library.as_str()), ("genre", genre), ("from", from), ("to", to)]
+        .into_iter()
+        .filter(|(_, v)| !v.trim().is_empty())
+        .map(|(k, v)| format!("{}={}", k, String::from(js_sys::encode_uri_component(v.trim()))))
+        .collect();
+    if query.is_empty() {
+        format!("{}/export/zip", API_BASE)
+    } else {
+        format!("{}/export/zip?{}", API_BASE, query.join("&"))
+    }
+}
+
+/// 取り込みで既存のレコードとぶつかったときの扱い（サーバーの `import_conflict::ConflictPolicy`）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportConflict {
+    Skip,
+    Draft,
+    #[default]
+    Merge,
+    Ask,
+}
+
+impl ImportConflict {
+    pub const ALL: [ImportConflict; 4] = [ImportConflict::Merge, ImportConflict::Draft, ImportConflict::Skip, ImportConflict::Ask];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ImportConflict::Skip => "skip",
+            ImportConflict::Draft => "draft",
+            ImportConflict::Merge => "merge",
+            ImportConflict::Ask => "ask",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == s)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ImportConflict::Skip => "取り込まない",
+            ImportConflict::Draft => "別の名前で下書きを作る",
+            ImportConflict::Merge => "既存のレコードの空の項目を埋める",
+            ImportConflict::Ask => "1 件ずつ決める",
+        }
+    }
+}
+
+/// 取り込んだ 1 件がどうなったか
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportOutcome {
+    Created,
+    DraftCopy,
+    Merged,
+    Unchanged,
+    Skipped,
+    Pending,
+    Failed,
+}
+
+impl ImportOutcome {
+    pub fn label(self) -> &'static str {
+        match self {
+            ImportOutcome::Created => "作成",
+            ImportOutcome::DraftCopy => "別名で作成",
+            ImportOutcome::Merged => "既存に反映",
+            ImportOutcome::Unchanged => "既存と同じ",
+            ImportOutcome::Skipped => "スキップ",
+            ImportOutcome::Pending => "未決定",
+            ImportOutcome::Failed => "失敗",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct ImportEntry {
+    pub line: usize,
+    pub title: String,
+    pub outcome: ImportOutcome,
+    #[serde(default)]
+    pub filename: Option<String>,
+    /// ぶつかった既存のレコード
+    #[serde(default)]
+    pub existing: Option<String>,
+    #[serde(default)]
+    pub reason: String,
+}
+
+/// 取り込みの結果（入ってきた 1 件ごと）
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct ImportSummary {
+    /// この取り込みで使った扱い
+    pub policy: ImportConflict,
+    pub entries: Vec<ImportEntry>,
+}
+
+impl ImportSummary {
+    pub fn count(&self, outcome: ImportOutcome) -> usize {
+        self.entries.iter().filter(|e| e.outcome == outcome).count()
+    }
+}
+
+/// Discogs のコレクション CSV を取り込む。`date` は Date Added がない行の記録日。
+/// `conflict` がなければ設定の扱い、`decisions` は「1 件ずつ決める」で決めた行ごとの扱い。
+pub async fn import_discogs(
+    csv: &str,
+    date: &str,
+    conflict: Option<ImportConflict>,
+    decisions: &std::collections::HashMap<usize, ImportConflict>,
+) -> Result<ImportSummary, String> {
+    let decisions: serde_json::Map<String, Value> =
+        decisions.iter().map(|(line, c)| (line.to_string(), Value::from(c.as_str()))).collect();
+    let body = serde_json::json!({ "csv": csv, "date": date, "conflict": conflict, "decisions": decisions });
+    let resp = Request::post(&files_url("/import/discogs"))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "import failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// `POST /api/import/url` で取ってきたほかのサーバーのレコード（まだ保存していない）
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct RemoteRecord {
+    /// 向こうのファイル名（"xxx.json"）
+    pub filename: String,
+    pub display_label: String,
+    pub data: Value,
+    /// 取りに行った URL
+    pub source: String,
+}
+
+/// ほかの nekokan_music の `/api/files/名前.json` の URL からレコードを取ってくる（サーバー経由、保存はしない）
+pub async fn fetch_remote_record(url: &str) -> Result<RemoteRecord, String> {
+    let body = serde_json::json!({ "url": url });
+    let resp = Request::post(&files_url("/import/url"))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        let msg: Value = resp.json().await.unwrap_or(Value::Null);
+        return Err(status_error(resp.status(), &msg, "import failed"));
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// バッジの凡例（例: "🔴" = "リッピングし直し"）
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BadgeLegend {
+    pub badge: String,
+    pub label: String,
+}
+
+/// `{DB_PATH}/.config/settings.json` の設定
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub badges: Vec<BadgeLegend>,
+    /// エラー発生時に診断情報をサーバーへ送る
+    #[serde(default)]
+    pub report_client_errors: bool,
+    /// 入力チェックで保存できなかった項目とルールをサーバーに記録する
+    #[serde(default)]
+    pub log_validation_failures: bool,
+    /// 保存の待ち時間（秒）。0 なら既定値。
+    #[serde(default)]
+    pub save_timeout_secs: u32,
+    #[serde(default)]
+    pub sidebar: SidebarDisplay,
+    #[serde(default)]
+    pub date_mode: DateMode,
+    /// 保存のたびに updated_date を今日にする
+    #[serde(default)]
+    pub track_updated_date: bool,
+    /// 日付を数える時差（例 "+09:00"）。空ならブラウザの時差
+    #[serde(default)]
+    pub utc_offset: String,
+    /// メインジャンルごとの表示ラベルのテンプレート
+    #[serde(default)]
+    pub label_templates: LabelTemplates,
+    /// Classical のファイル名の候補のパターン（例 "{soloist_last}_{instrument}__{composer_last}_{title}"）。空なら既定の規則
+    #[serde(default)]
+    pub classical_filename: String,
+    /// リリース情報を探す外部のデータベース（"musicbrainz" / "discogs" / "vgmdb"）。空ならサーバーの既定
+    #[serde(default)]
+    pub metadata_provider: String,
+    /// 取り込みで既存のレコードとぶつかったときの扱い
+    #[serde(default)]
+    pub import_conflict: ImportConflict,
+}
+
+/// 保存時の記録日（date）の扱い
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateMode {
+    /// 初めて登録した日のまま変えない
+    #[default]
+    #[serde(alias = "")]
+    FirstLogged,
+    /// 保存のたびに今日にする
+    LastUpdated,
+    /// 既存のレコードを保存するたびに今日にするか聞く
+    Ask,
+}
+
+/// 保存の待ち時間の既定値（秒）
+pub const DEFAULT_SAVE_TIMEOUT_SECS: u32 = 10;
+
+impl Settings {
+    /// 設定された時差（空・読めないときは None）
+    pub fn utc_offset(&self) -> Option<crate::date::UtcOffset> {
+        crate::date::UtcOffset::parse(&self.utc_offset)
+    }
+
+    pub fn save_timeout_secs(&self) -> u32 {
+        if self.save_timeout_secs == 0 {
+            DEFAULT_SAVE_TIMEOUT_SECS
+        } else {
+            self.save_timeout_secs
+        }
+    }
+
+    /// 保存前に記録日・更新日を付ける。`refresh_date` は date を今日にするか（`date_mode` と確認の結果で呼ぶ側が決める）。
+    pub fn stamp_dates(&self, data: &mut MusicData, refresh_date: bool, today: &str) {
+        if refresh_date {
+            data.date = today.to_string();
+        }
+        if self.track_updated_date {
+            data.updated_date = today.to_string();
+        }
+    }
+
+    /// バッジの凡例の説明（未定義なら None）
+    pub fn badge_label(&self, badge: &str) -> Option<&str> {
+        self.badges.iter().find(|b| b.badge == badge).map(|b| b.label.as_str())
+    }
+}
+
+pub async fn get_settings() -> Result<Settings, String> {
+    let resp = Request::get(&format!("{}/settings", API_BASE))
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "settings failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 必須項目のポリシー（サーバーの `.config/validation-policy.json`）。なければ既定。
+pub async fn get_validation_policy() -> Result<crate::validation::ValidationPolicy, String> {
+    let resp = Request::get(&format!("{}/validation-policy", API_BASE))
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "validation-policy failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// updated_date がないレコードに、ファイルの更新日を入れる。入れた件数を返す。
+pub async fn fill_updated_dates() -> Result<usize, String> {
+    let resp = Request::post(&files_url("/dates/fill-updated")).send().await.map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "fill updated_date failed").await);
+    }
+    let v: Value = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(v["updated"].as_u64().unwrap_or(0) as usize)
+}
+
+/// 設定を保存し、サーバー側で整えた（空のバッジを除いた）設定を返す。
+pub async fn save_settings(settings: &Settings) -> Result<Settings, String> {
+    let resp = Request::post(&format!("{}/settings", API_BASE))
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(settings).map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "save settings failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 目標の種類と条件（サーバーの `goals.rs` と同じ形）
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GoalKind {
+    /// 期間内に `target` 枚聴く
+    Listen {
+        target: usize,
+        #[serde(default)]
+        from: Option<String>,
+        #[serde(default)]
+        to: Option<String>,
+    },
+    /// 条件に合うレコードをすべて完成度 100% にする
+    Complete {
+        #[serde(default)]
+        score: Option<f64>,
+        #[serde(default)]
+        genre: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Goal {
+    pub title: String,
+    #[serde(flatten)]
+    pub kind: GoalKind,
+}
+
+/// 目標と進捗
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct GoalProgress {
+    #[serde(flatten)]
+    pub goal: Goal,
+    pub current: usize,
+    pub total: usize,
+}
+
+pub async fn get_goals() -> Result<Vec<GoalProgress>, String> {
+    let resp = Request::get(&files_url("/goals"))
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "goals failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 目標の一覧を置き換える。
+pub async fn save_goals(goals: &[Goal]) -> Result<(), String> {
+    let resp = Request::post(&format!("{}/goals", API_BASE))
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(goals).map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "save goals failed").await);
+    }
+    Ok(())
+}
+
+/// レコードのメモ
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct Note {
+    pub id: u64,
+    /// 書いた日時（RFC 3339, UTC）
+    pub created: String,
+    pub text: String,
+    #[serde(default)]
+    pub done: bool,
+}
+
+async fn notes_response(resp: gloo_net::http::Response) -> Result<Vec<Note>, String> {
+    if !resp.ok() {
+        return Err(error_of(resp, "notes failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+pub async fn get_notes(filename: &str) -> Result<Vec<Note>, String> {
+    let resp = Request::get(&files_url(&format!("/notes/{}", filename)))
+        .send()
+        .await
+        .map_err(network_error)?;
+    notes_response(resp).await
+}
+
+/// メモを追加し、更新後の一覧を返す。
+pub async fn add_note(filename: &str, text: &str) -> Result<Vec<Note>, String> {
+    let body = serde_json::json!({ "text": text });
+    let resp = Request::post(&files_url(&format!("/notes/{}", filename)))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    notes_response(resp).await
+}
+
+pub async fn set_note_done(filename: &str, id: u64, done: bool) -> Result<Vec<Note>, String> {
+    let body = serde_json::json!({ "done": done });
+    let resp = Request::patch(&files_url(&format!("/notes/{}/{}", filename, id)))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    notes_response(resp).await
+}
+
+pub async fn delete_note(filename: &str, id: u64) -> Result<Vec<Note>, String> {
+    let resp = Request::delete(&files_url(&format!("/notes/{}/{}", filename, id)))
+        .send()
+        .await
+        .map_err(network_error)?;
+    notes_response(resp).await
+}
+
+/// レコードの添付（裏ジャケット・帯などの画像、ブックレットの PDF）
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct Attachment {
+    pub name: String,
+    pub size: u64,
+    pub content_type: String,
+}
+
+impl Attachment {
+    /// スキャンしたブックレットなど（ギャラリーではなくビューアで開く）
+    pub fn is_pdf(&self) -> bool {
+        self.content_type == "application/pdf"
+    }
+}
+
+async fn attachments_response(resp: gloo_net::http::Response) -> Result<Vec<Attachment>, String> {
+    if !resp.ok() {
+        return Err(error_of(resp, "attachments failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 添付の URL
+pub fn attachment_url(filename: &str, name: &str) -> String {
+    files_url(&format!("/attachments/{}/{}", filename, name))
+}
+
+/// レコードの添付一覧（ファイル名順）
+pub async fn get_attachments(filename: &str) -> Result<Vec<Attachment>, String> {
+    let resp = Request::get(&files_url(&format!("/attachments/{}", filename)))
+        .send()
+        .await
+        .map_err(network_error)?;
+    attachments_response(resp).await
+}
+
+/// 添付をまとめてアップロードし、更新後の一覧を返す。同名があればサーバーが番号を付ける。
+pub async fn upload_attachments(filename: &str, files: &[web_sys::File]) -> Result<Vec<Attachment>, String> {
+    let form = web_sys::FormData::new().map_err(|e| format!("{:?}", e))?;
+    for file in files {
+        form.append_with_blob_and_filename("file", file, &file.name())
+            .map_err(|e| format!("{:?}", e))?;
+    }
+    let resp = Request::post(&files_url(&format!("/attachments/{}", filename)))
+        .body(form)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    attachments_response(resp).await
+}
+
+/// 添付を消し、更新後の一覧を返す。
+pub async fn delete_attachment(filename: &str, name: &str) -> Result<Vec<Attachment>, String> {
+    let resp = Request::delete(&attachment_url(filename, name))
+        .send()
+        .await
+        .map_err(network_error)?;
+    attachments_response(resp).await
+}
+
+/// 活動履歴の種類
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    Saved,
+    Listened,
+    Noted,
+}
+
+impl ActivityKind {
+    pub const ALL: [ActivityKind; 3] = [ActivityKind::Saved, ActivityKind::Listened, ActivityKind::Noted];
+
+    /// クエリ `types` に渡す名前
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ActivityKind::Saved => "saved",
+            ActivityKind::Listened => "listened",
+            ActivityKind::Noted => "noted",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct Activity {
+    pub kind: ActivityKind,
+    /// RFC 3339（UTC）または日付だけ（YYYY-MM-DD）
+    pub at: String,
+    pub filename: String,
+    pub display_label: String,
+    #[serde(default)]
+    pub detail: Option<String>,
+}
+
+/// 活動履歴を新しい順で取得する。`kinds` が空ならすべて。
+pub async fn activity(kinds: &[ActivityKind]) -> Result<Vec<Activity>, String> {
+    let types = kinds.iter().map(|k| k.as_str()).collect::<Vec<_>>().join(",");
+    let resp = Request::get(&files_url("/activity"))
+        .query([("types", types.as_str())])
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "activity failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// `GET /api/recent` の 1 件
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct RecentEntry {
+    pub filename: String,
+    pub display_label: String,
+    /// ファイルの最終更新日時（RFC 3339, UTC）
+    pub modified: String,
+    /// 同じ日の YYYY/MM/DD（ライブラリの時差）
+    #[serde(default)]
+    pub modified_date: String,
+}
+
+/// 選んでいるライブラリで最近編集したレコードを新しい順に `limit` 件
+pub async fn recent(limit: usize) -> Result<Vec<RecentEntry>, String> {
+    let mut query = vec![("limit", limit.to_string())];
+    let library = current_library();
+    if !library.is_empty() {
+        query.push(("library", library));
+    }
+    let resp = Request::get(&format!("{}/recent", API_BASE))
+        .query(query.iter().map(|(k, v)| (*k, v.as_str())))
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "recent failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// `GET /api/random` で選んだ 1 枚
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct RandomPick {
+    pub filename: String,
+    pub display_label: String,
+    /// 条件に合ったレコードの数
+    #[serde(default)]
+    pub candidates: usize,
+}
+
+/// 選んでいるライブラリからでたらめに 1 枚選ぶ。絞り込み（ジャンル・スコア・年）は一覧と同じで、並び順は使わない
+pub async fn random(filter: &ListFilter) -> Result<RandomPick, String> {
+    let mut query: Vec<(&str, String)> = filter.query_pairs().into_iter().filter(|(k, _)| !matches!(*k, "sort" | "order")).collect();
+    let library = current_library();
+    if !library.is_empty() {
+        query.push(("library", library));
+    }
+    let resp = Request::get(&format!("{}/random", API_BASE))
+        .query(query.iter().map(|(k, v)| (*k, v.as_str())))
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        let status = resp.status();
+        let msg: Value = resp.json().await.unwrap_or(Value::Null);
+        return Err(status_error(status, &msg, "random failed"));
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 人ごとのディスコグラフィの 1 つの参加（`/api/personnel/:name` の credits の要素）
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct PersonnelCredit {
+    /// "leader" / "sideman" / "soloist" / "conductor" / "group_member"
+    pub role: String,
+    #[serde(default)]
+    pub instruments: String,
+    #[serde(default)]
+    pub tracks: String,
+    /// グループのメンバーのとき、そのグループ
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct PersonnelAlbum {
+    pub filename: String,
+    pub display_label: String,
+    pub release_year: Option<i64>,
+    pub credits: Vec<PersonnelCredit>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct Discography {
+    /// 台帳の正規名
+    pub name: String,
+    /// 役割ごとのレコード数
+    #[serde(default)]
+    pub roles: std::collections::BTreeMap<String, usize>,
+    pub albums: Vec<PersonnelAlbum>,
+}
+
+/// その人が参加したレコードを発売年順に（リーダー・サイドメン・ソリスト・指揮者・グループのメンバー）
+pub async fn personnel(name: &str) -> Result<Discography, String> {
+    let mut query = Vec::new();
+    let library = current_library();
+    if !library.is_empty() {
+        query.push(("library", library));
+    }
+    let name = String::from(js_sys::encode_uri_component(name.trim()));
+    let resp = Request::get(&format!("{}/personnel/{}", API_BASE, name))
+        .query(query.iter().map(|(k, v)| (*k, v.as_str())))
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        let status = resp.status();
+        let msg: Value = resp.json().await.unwrap_or(Value::Null);
+        return Err(status_error(status, &msg, "personnel failed"));
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// レーベルの索引の 1 枚（`/api/labels` の albums の要素）
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct LabelAlbum {
+    pub filename: String,
+    pub display_label: String,
+    pub release_year: Option<i64>,
+    /// 番号（"BLP 1595" など）
+    #[serde(default)]
+    pub id: String,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct LabelEntry {
+    pub label: String,
+    pub count: usize,
+    pub albums: Vec<LabelAlbum>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct LabelIndex {
+    /// 枚数の多い順
+    pub labels: Vec<LabelEntry>,
+    /// レーベルの書いていないレコードの数
+    #[serde(default)]
+    pub unlabeled: usize,
+}
+
+/// レーベルごとの枚数とレコード（発売年・番号順）
+pub async fn labels() -> Result<LabelIndex, String> {
+    let mut query = Vec::new();
+    let library = current_library();
+    if !library.is_empty() {
+        query.push(("library", library));
+    }
+    let resp = Request::get(&format!("{}/labels", API_BASE))
+        .query(query.iter().map(|(k, v)| (*k, v.as_str())))
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        let status = resp.status();
+        let msg: Value = resp.json().await.unwrap_or(Value::Null);
+        return Err(status_error(status, &msg, "labels failed"));
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct JanreValue {
+    pub main: String,
+    #[serde(default)]
+    pub sub: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct RecordRef {
+    pub filename: String,
+    pub display_label: String,
+}
+
+/// (Main, Sub) ごとの該当レコード
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct GenreUsage {
+    pub main: String,
+    /// Sub なしのレコードは空文字
+    pub sub: String,
+    pub records: Vec<RecordRef>,
+}
+
+pub async fn genre_usage() -> Result<Vec<GenreUsage>, String> {
+    let resp = Request::get(&files_url("/genres"))
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "genres failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 一括チェックのエラー 1 件。field が空なら JSON として読めなかった。
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct CheckError {
+    pub field: String,
+    pub message: String,
+}
+
+/// 今の入力チェックに通らない、または警告のあるレコード
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct CheckFailure {
+    pub filename: String,
+    #[serde(default)]
+    pub display_label: String,
+    pub errors: Vec<CheckError>,
+    /// 保存は止めない警告（発売年と録音年の食い違いなど）
+    #[serde(default)]
+    pub warnings: Vec<CheckError>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct CheckReport {
+    pub checked: usize,
+    pub failures: Vec<CheckFailure>,
+}
+
+/// 全レコードに今の入力チェックをかける
+pub async fn validate_all() -> Result<CheckReport, String> {
+    let resp = Request::get(&files_url("/validate-all"))
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "validate-all failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// 入力チェックで保存できなかったことをサーバーに知らせる（`source` は "form" / "quick_entry"）。
+/// 値は送らず項目のキーとメッセージだけ。記録するかはサーバーの設定で決まる
+pub async fn report_validation_failures(source: &str, errors: &crate::validation::FieldErrors) -> Result<(), String> {
+    let body = serde_json::json!({ "source": source, "fields": errors });
+    let resp = Request::post(&format!("{}/validation-failures", API_BASE))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "report failed").await);
+    }
+    Ok(())
+}
+
+/// よくある入力チェックのエラー（項目・メッセージ・回数）
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct FailureCount {
+    pub field: String,
+    pub message: String,
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct FailureSummary {
+    /// 記録がオンか
+    pub enabled: bool,
+    /// 保存が止まった回数
+    pub blocked_saves: usize,
+    pub top: Vec<FailureCount>,
+}
+
+/// 保存を止めた入力チェックのエラーの多い順
+pub async fn validation_failure_summary() -> Result<FailureSummary, String> {
+    let resp = Request::get(&format!("{}/validation-failures/summary", API_BASE))
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "summary failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// ジャンルの付け替え 1 行（サーバーの `genres::GenreMapping` と同じ形）
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct GenreMapping {
+    pub from_main: String,
+    pub from_sub: String,
+    pub to_main: String,
+    pub to_sub: String,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct GenreChange {
+    pub filename: String,
+    pub display_label: String,
+    pub before: JanreValue,
+    pub after: JanreValue,
+    #[serde(default)]
+    pub sub_emptied: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct GenreMigrateReport {
+    pub dry_run: bool,
+    pub changes: Vec<GenreChange>,
+    #[serde(default)]
+    pub failed: Vec<(String, String)>,
+}
+
+/// 付け替えを適用する。`dry_run` なら書き込まずに結果だけ返す。
+pub async fn migrate_genres(mappings: &[GenreMapping], dry_run: bool) -> Result<GenreMigrateReport, String> {
+    let body = serde_json::json!({ "mappings": mappings, "dry_run": dry_run });
+    let resp = Request::post(&files_url("/genres/migrate"))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "migrate failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct ScoreChange {
+    pub filename: String,
+    pub display_label: String,
+    pub before: f64,
+    pub after: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct RescaleReport {
+    pub dry_run: bool,
+    pub changes: Vec<ScoreChange>,
+    #[serde(default)]
+    pub failed: Vec<(String, String)>,
+    /// 変換後のポリシー
+    pub policy: crate::validation::ValidationPolicy,
+}
+
+/// 既存レコードのスコアを今の尺度から `to` に変換する。`dry_run` なら書き込まずに結果だけ返す。
+pub async fn rescale_scores(to: &crate::validation::ScoreScale, dry_run: bool) -> Result<RescaleReport, String> {
+    let body = serde_json::json!({ "to": to, "dry_run": dry_run });
+    let resp = Request::post(&files_url("/scores/rescale"))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "rescale failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// ファイル名の一括整理で変える（変えた）もの
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct FilenameRename {
+    pub from: String,
+    pub to: String,
+    #[serde(default)]
+    pub display_label: String,
+}
+
+/// ファイル名の一括整理で変えないもの
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct FilenameSkipped {
+    pub filename: String,
+    #[serde(default)]
+    pub to: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct NormalizeReport {
+    pub dry_run: bool,
+    pub renames: Vec<FilenameRename>,
+    /// 今の規則の名前がもうあるか、ほかのレコードと重なるもの
+    #[serde(default)]
+    pub conflicts: Vec<FilenameSkipped>,
+    /// 名前を作れないもの
+    #[serde(default)]
+    pub skipped: Vec<FilenameSkipped>,
+    #[serde(default)]
+    pub unchanged: usize,
+    #[serde(default)]
+    pub failed: Vec<(String, String)>,
+    #[serde(default)]
+    pub related_updated: usize,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+/// 選んでいるライブラリのファイル名を今の規則に揃える。`dry_run` なら変えずに一覧だけ返す。`files` が空ならすべて変える。
+pub async fn normalize_filenames(dry_run: bool, files: &[String]) -> Result<NormalizeReport, String> {
+    let body = serde_json::json!({ "dry_run": dry_run, "files": files });
+    let resp = Request::post(&files_url("/normalize-filenames"))
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    if !resp.ok() {
+        return Err(error_of(resp, "normalize failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod api_tests {
+    use super::{library_url, status_error, ws_url, LabelFormat, ListEntryWithLabel, ListFilter, Settings, SidebarDisplay};
+    use crate::types::MusicData;
+    use serde_json::json;
+    use crate::label::{LabelFields, LabelTemplates};
+
+    #[test]
+    fn only_other_libraries_are_named_in_the_url() {
+        assert_eq!(library_url("/files/a.json", ""), "/api/files/a.json");
+        assert_eq!(library_url("/save", "classical"), "/api/save?library=classical");
+        assert_eq!(library_url("/export/year-review?year=2026", "classical"), "/api/export/year-review?year=2026&library=classical");
+    }
+
+    #[test]
+    fn ws_follows_the_page_scheme() {
+        assert_eq!(ws_url("http:", "127.0.0.1:8081"), "ws://127.0.0.1:8081/api/ws");
+        assert_eq!(ws_url("https:", "music.example"), "wss://music.example/api/ws");
+    }
+
+    #[test]
+    fn sidebar_label_formats() {
+        let e = ListEntryWithLabel {
+            display_label: "Bill Evans: Alone".into(),
+            fields: LabelFields { title: "Alone".into(), leaders: vec!["Bill Evans".into()], ..Default::default() },
+            release_year: Some(1968),
+            ..Default::default()
+        };
+        let mut d = SidebarDisplay::default();
+        let none = LabelTemplates::default();
+        assert_eq!(d.label(&e, &none), "Bill Evans: Alone");
+        let templates = LabelTemplates([(String::new(), "{title} / {leader}".to_string())].into());
+        assert_eq!(d.label(&e, &templates), "Alone / Bill Evans");
+        d.label_format = LabelFormat::TitleArtist;
+        d.show_year = true;
+        assert_eq!(d.label(&e, &templates), "Alone — Bill Evans (1968)");
+        // 古いサーバーはアーティスト・タイトルを返さない
+        let old = ListEntryWithLabel { display_label: "Bill Evans: Alone".into(), ..Default::default() };
+        assert_eq!(d.label(&old, &none), "Bill Evans: Alone");
+    }
+
+    #[test]
+    fn list_entries_carry_genre_and_years() {
+        let e: ListEntryWithLabel = serde_json::from_value(json!({
+            "filename": "a.json", "display_label": "A", "genre": "Jazz", "score": 5,
+            "release_year": 1960, "record_year": [1958, 1959]
+        }))
+        .unwrap();
+        assert_eq!((e.fields.genre.as_str(), e.score), ("Jazz", Some(5.0)));
+        assert_eq!(e.years().as_deref(), Some("発売 1960・録音 1958–1959"));
+        let old: ListEntryWithLabel = serde_json::from_value(json!({"filename": "b.json", "display_label": "B"})).unwrap();
+        assert_eq!(old.years(), None);
+    }
+
+    #[test]
+    fn dates_are_stamped_by_settings() {
+        let mut d = MusicData { date: "2020/01/01".into(), ..Default::default() };
+        let mut s = Settings::default();
+        s.stamp_dates(&mut d, false, "2026/10/16");
+        assert_eq!((d.date.as_str(), d.updated_date.as_str()), ("2020/01/01", ""));
+        s.track_updated_date = true;
+        s.stamp_dates(&mut d, false, "2026/10/16");
+        assert_eq!((d.date.as_str(), d.updated_date.as_str()), ("2020/01/01", "2026/10/16"));
+        s.stamp_dates(&mut d, true, "2026/10/17");
+        assert_eq!((d.date.as_str(), d.updated_date.as_str()), ("2026/10/17", "2026/10/17"));
+    }
+
+    #[test]
+    fn list_filter_skips_empty_fields() {
+        assert!(ListFilter { janre: "  ".into(), ..Default::default() }.is_empty());
+        let f = ListFilter { janre: "Jazz".into(), score_min: Some(4.5), year_to: Some(1965), ..Default::default() };
+        assert_eq!(
+            f.query_pairs(),
+            vec![("janre", "Jazz".to_string()), ("score_min", "4.5".to_string()), ("year_to", "1965".to_string())]
+        );
+        let sorted = ListFilter { sort: "score", descending: true, ..Default::default() };
+        assert_eq!(sorted.query_pairs(), vec![("sort", "score".to_string()), ("order", "desc".to_string())]);
+    }
+
+    #[test]
+    fn rate_limited_errors_explain_themselves() {
+        assert_eq!(status_error(429, &json!({"error": "10 秒待って"}), "save failed"), "10 秒待って");
+        assert!(status_error(429, &json!(null), "save failed").contains("少し待って"));
+        assert_eq!(status_error(500, &json!(null), "save failed"), "save failed: 500");
+    }
+
+    #[test]
+    fn problem_json_messages_and_field_errors() {
+        let problem = json!({"status": 400, "code": "bad_request", "message": "date: must be YYYY/MM/DD",
+                             "errors": [{"field": "date", "message": "must be YYYY/MM/DD"}]});
+        assert_eq!(status_error(400, &problem, "listen failed"), "date: must be YYYY/MM/DD");
+        let fields_only = json!({"code": "invalid_body", "message": "", "errors": [
+            {"field": "filename", "message": "missing"}, {"field": "date", "message": "missing"}]});
+        assert_eq!(status_error(422, &fields_only, "save failed"), "filename: missing、date: missing");
+    }
+}
+
+/// 試聴用の音声（アルバムか、ディスク・番号のトラック）
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+pub struct AudioSample {
+    pub disc: Option<u32>,
+    pub track: Option<u32>,
+    pub file: String,
+    pub size: u64,
+    pub content_type: String,
+}
+
+/// "?disc=1&track=3"（アルバムなら空）
+pub fn audio_query(disc: u32, track: Option<u32>) -> String {
+    track.map(|no| format!("?disc={}&track={}", disc, no)).unwrap_or_default()
+}
+
+/// 試聴用の音声の URL（`<audio>` の src）。ファイル名を後ろに付け、差し替えたら読み直させる
+pub fn audio_url(filename: &str, s: &AudioSample) -> String {
+    let query = audio_query(s.disc.unwrap_or(1), s.track);
+    let sep = if query.is_empty() { "?" } else { "&" };
+    files_url(&format!("/files/{}/audio{}{}v={}-{}", filename, query, sep, s.file, s.size))
+}
+
+async fn audio_response(resp: gloo_net::http::Response) -> Result<Vec<AudioSample>, String> {
+    if !resp.ok() {
+        return Err(error_of(resp, "audio failed").await);
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
+
+/// レコードの試聴用の音声の一覧
+pub async fn get_audio(filename: &str) -> Result<Vec<AudioSample>, String> {
+    let resp = Request::get(&files_url(&format!("/audio/{}", filename))).send().await.map_err(network_error)?;
+    audio_response(resp).await
+}
+
+/// 試聴用の音声を置き（同じところのものは置き換える）、更新後の一覧を返す
+pub async fn upload_audio(filename: &str, disc: u32, track: Option<u32>, file: &web_sys::File) -> Result<Vec<AudioSample>, String> {
+    let form = web_sys::FormData::new().map_err(|e| format!("{:?}", e))?;
+    form.append_with_blob_and_filename("file", file, &file.name()).map_err(|e| format!("{:?}", e))?;
+    let resp = Request::post(&files_url(&format!("/files/{}/audio{}", filename, audio_query(disc, track))))
+        .body(form)
+        .map_err(|e| e.to_string())?
+        .send()
+        .await
+        .map_err(network_error)?;
+    audio_response(resp).await
+}
+
+/// 試聴用の音声を消し、更新後の一覧を返す
+pub async fn delete_audio(filename: &str, disc: u32, track: Option<u32>) -> Result<Vec<AudioSample>, String> {
+    let resp = Request::delete(&files_url(&format!("/files/{}/audio{}", filename, audio_query(disc, track))))
+        .send()
+        .await
+        .map_err(network_error)?;
+    audio_response(resp).await
+}
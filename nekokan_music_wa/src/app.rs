@@ -1,6 +1,8 @@
 use crate::api;
-use crate::types::{sub_janres_for_main, MusicData};
-use crate::validation::{validate_form, FieldErrors};
+use crate::completeness::completeness_percent;
+use crate::date::UtcOffset;
+use crate::types::{sub_janres_for_main, MusicData, MAIN_JANRES};
+use crate::validation::{format_size, record_size, validate_form, warn_form, FieldErrors, ValidationPolicy};
 use js_sys::Date;
 use wasm_bindgen::JsValue;
 use yew::prelude::*;
@@ -25,50 +27,292 @@ fn scroll_to_top() {
     }
 }
 
-fn today_str() -> String {
-    let d = Date::new_0();
-    let y = d.get_full_year();
-    let m = d.get_month() + 1;
-    let day = d.get_date();
-    format!("{:04}/{:02}/{:02}", y, m, day)
+/// 起動からの経過時間（ページ読み込み開始からの ms）をコンソールに出す。
+/// 古い端末で最初の表示までどこに時間がかかっているかを見るため。リリースビルドでは出さない。
+fn log_timing(label: &str) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    if let Some(perf) = web_sys::window().and_then(|w| w.performance()) {
+        web_sys::console::log_1(&JsValue::from_str(&format!(
+            "[nekokan_music_wa] {}: {:.0} ms",
+            label,
+            perf.now()
+        )));
+    }
+}
+
+thread_local! {
+    /// 設定の `utc_offset`。未設定（None）ならブラウザの時差で今日を数える
+    static LIBRARY_OFFSET: std::cell::Cell<Option<UtcOffset>> = const { std::cell::Cell::new(None) };
+}
+
+pub(crate) fn set_library_offset(offset: Option<UtcOffset>) {
+    LIBRARY_OFFSET.with(|o| o.set(offset));
+}
+
+/// 今日（YYYY/MM/DD）。設定の時差で数えるので、サーバーの「今日」やファイルの更新日と揃う
+pub(crate) fn today_str() -> String {
+    let now = Date::new_0();
+    let offset = LIBRARY_OFFSET.with(|o| o.get()).unwrap_or_else(|| {
+        UtcOffset::from_minutes(-(now.get_timezone_offset() as i32)).unwrap_or_default()
+    });
+    crate::date::Date::from_unix_secs((now.get_time() / 1000.0).floor() as i64, offset).to_string()
+}
+
+/// これ以上の大きさのレコードを保存するときは送信の進捗を出す（バイト）
+const LARGE_SAVE_BYTES: f64 = 256.0 * 1024.0;
+/// 保存が応答なしになったときに再送する回数
+const SAVE_RETRIES: u32 = 1;
+
+/// 保存する。応答がないまま待ち時間を過ぎたら、サーバーに保存されたか読み直して確かめ、
+/// 保存されていなければ同じ冪等キーで送り直す（サーバーは同じキーの保存を二重に行わない）。
+/// 書けたら新しい版を返す。ほかで変更されていれば `SaveError::Conflict`（`base` を参照）。
+pub(crate) async fn save_with_retry(
+    filename: &str,
+    data: &MusicData,
+    base: &api::SaveBase,
+    timeout_secs: u32,
+    progress: UseStateHandle<Option<(f64, f64)>>,
+) -> Result<Option<String>, api::SaveError> {
+    let name = format!("{}.json", filename.trim().trim_end_matches(".json"));
+    let key = api::new_idempotency_key();
+    for _ in 0..=SAVE_RETRIES {
+        let progress = progress.clone();
+        match api::save_file(filename, data, base, &key, timeout_secs * 1000, move |loaded, total| {
+            progress.set(Some((loaded, total)))
+        })
+        .await
+        {
+            Err(api::SaveError::TimedOut) => {
+                if let Ok((saved, version)) = api::get_file(&name).await {
+                    if saved == *data {
+                        return Ok(version);
+                    }
+                }
+            }
+            res => return res,
+        }
+    }
+    Err(api::SaveError::Failed(format!(
+        "{}秒以上応答がなく、保存できたか確認できませんでした。通信状況を確認してもう一度保存してください。",
+        timeout_secs
+    )))
+}
+
+/// 保存の失敗の表示
+pub(crate) fn save_error_text(e: api::SaveError) -> String {
+    match e {
+        api::SaveError::Failed(e) => e,
+        api::SaveError::TimedOut => "応答がありませんでした".to_string(),
+        api::SaveError::Conflict { message, .. } => {
+            format!("{}。保存していません（開き直すと今の内容が見られます）。", message)
+        }
+    }
 }
 
 /// 新規追加用のクリーンなフォームデータ（Main=Classical, Sub=Classicists）
 fn new_music_data() -> MusicData {
-    let mut d = MusicData::default();
-    d.date = today_str();
-    d.release_year = 2000;
-    d.score = 1;
-    d.janre.main = "Classical".into();
-    d.janre.sub = vec!["Classicists".into()];
-    d.tracks.push(crate::types::Track {
-        disc_no: 1,
-        no: 1,
-        title: String::new(),
-        composer: String::new(),
-        length: String::new(),
-    });
-    d
+    MusicData {
+        date: today_str(),
+        release_year: 2000,
+        score: 1.0,
+        janre: crate::types::Janre {
+            main: "Classical".into(),
+            sub: vec!["Classicists".into()],
+        },
+        tracks: vec![crate::types::Track {
+            disc_no: 1,
+            no: 1,
+            title: String::new(),
+            composer: String::new(),
+            length: String::new(),
+        }],
+        ..Default::default()
+    }
 }
 
+/// サイドバーの並び順
+#[derive(Clone, Copy, PartialEq)]
+enum SidebarSort {
+    /// サーバーに並べてもらう（`sort` が空ならファイル名順）
+    Server { sort: &'static str, descending: bool },
+    /// 完成度が低い順（同率はファイル名順）
+    CompletenessAsc,
+}
+
+/// 並び順の選択肢（select の value, 並び順, 表示）
+const SIDEBAR_SORTS: &[(&str, SidebarSort, &str)] = &[
+    ("filename", SidebarSort::Server { sort: "", descending: false }, "ファイル名順"),
+    ("label", SidebarSort::Server { sort: "label", descending: false }, "表示名順"),
+    ("title", SidebarSort::Server { sort: "title", descending: false }, "タイトル順"),
+    ("release_year", SidebarSort::Server { sort: "release_year", descending: false }, "発売年が古い順"),
+    ("release_year_desc", SidebarSort::Server { sort: "release_year", descending: true }, "発売年が新しい順"),
+    ("score_desc", SidebarSort::Server { sort: "score", descending: true }, "スコアが高い順"),
+    ("modified_desc", SidebarSort::Server { sort: "modified", descending: true }, "更新が新しい順"),
+    ("completeness", SidebarSort::CompletenessAsc, "完成度が低い順"),
+];
+
+/// サイドバーの「今月」絞り込み
+#[derive(Clone, Copy, PartialEq)]
+enum MonthFilter {
+    /// 記録日（date）が今月
+    Added,
+    /// ファイルの更新日が今月
+    Edited,
+}
+
+impl MonthFilter {
+    /// `month` は "YYYY/MM/"
+    fn matches(self, entry: &api::ListEntryWithLabel, month: &str) -> bool {
+        match self {
+            MonthFilter::Added => entry.date.starts_with(month),
+            MonthFilter::Edited => entry.modified.as_deref().is_some_and(|m| m.starts_with(month)),
+        }
+    }
+}
+
+/// メイン領域に表示するビュー。
+/// 編集以外のビューは表示したときだけマウントする（feature で外したものはビルドにも入らない）。
+#[derive(Clone, Copy, PartialEq)]
+enum MainView {
+    Editor,
+    QuickEntry,
+    #[cfg(feature = "reports")]
+    ListeningReport,
+    #[cfg(feature = "import")]
+    DiscogsSync,
+    #[cfg(feature = "import")]
+    CoverImport,
+    #[cfg(feature = "import")]
+    UrlImport,
+    #[cfg(feature = "reports")]
+    Goals,
+    #[cfg(feature = "reports")]
+    Activity,
+    #[cfg(feature = "reports")]
+    Tunes,
+    #[cfg(feature = "reports")]
+    Personnel,
+    #[cfg(feature = "reports")]
+    Labels,
+    #[cfg(feature = "maintenance")]
+    GenreMigration,
+    #[cfg(feature = "maintenance")]
+    ValidationReport,
+    #[cfg(feature = "maintenance")]
+    Composers,
+    Settings,
+}
+
+impl MainView {
+    /// 診断情報に載せる名前
+    fn id(self) -> &'static str {
+        match self {
+            MainView::Editor => "editor",
+            MainView::QuickEntry => "quick_entry",
+            #[cfg(feature = "reports")]
+            MainView::ListeningReport => "listening_report",
+            #[cfg(feature = "import")]
+            MainView::DiscogsSync => "discogs_sync",
+            #[cfg(feature = "import")]
+            MainView::CoverImport => "cover_import",
+            #[cfg(feature = "import")]
+            MainView::UrlImport => "url_import",
+            #[cfg(feature = "reports")]
+            MainView::Goals => "goals",
+            #[cfg(feature = "reports")]
+            MainView::Activity => "activity",
+            #[cfg(feature = "reports")]
+            MainView::Tunes => "tunes",
+            #[cfg(feature = "reports")]
+            MainView::Personnel => "personnel",
+            #[cfg(feature = "reports")]
+            MainView::Labels => "labels",
+            #[cfg(feature = "maintenance")]
+            MainView::GenreMigration => "genre_migration",
+            #[cfg(feature = "maintenance")]
+            MainView::ValidationReport => "validation_report",
+            #[cfg(feature = "maintenance")]
+            MainView::Composers => "composers",
+            MainView::Settings => "settings",
+        }
+    }
+}
+
+/// サイドバーのビュー切り替えリンク（表示順）
+const VIEW_LINKS: &[(MainView, &str)] = &[
+    (MainView::QuickEntry, "クイック入力"),
+    #[cfg(feature = "reports")]
+    (MainView::Goals, "目標"),
+    #[cfg(feature = "reports")]
+    (MainView::Activity, "活動履歴"),
+    #[cfg(feature = "reports")]
+    (MainView::ListeningReport, "再生レポート"),
+    #[cfg(feature = "reports")]
+    (MainView::Tunes, "曲の索引"),
+    #[cfg(feature = "reports")]
+    (MainView::Personnel, "人で探す"),
+    #[cfg(feature = "reports")]
+    (MainView::Labels, "レーベル"),
+    #[cfg(feature = "import")]
+    (MainView::DiscogsSync, "Discogs 連携"),
+    #[cfg(feature = "import")]
+    (MainView::CoverImport, "カバー一括取り込み"),
+    #[cfg(feature = "import")]
+    (MainView::UrlImport, "URLからインポート"),
+    #[cfg(feature = "maintenance")]
+    (MainView::GenreMigration, "ジャンルの付け替え"),
+    #[cfg(feature = "maintenance")]
+    (MainView::ValidationReport, "一括チェック"),
+    #[cfg(feature = "maintenance")]
+    (MainView::Composers, "作曲者"),
+    (MainView::Settings, "設定"),
+];
+
 #[function_component(App)]
 pub fn app() -> Html {
-    let file_list = use_state(|| Vec::<api::ListEntryWithLabel>::new());
+    let file_list = use_state(Vec::<api::ListEntryWithLabel>::new);
     let loading = use_state(|| true);
     let selected = use_state(|| None::<String>);
-    let form_data = use_state(|| new_music_data());
-    let form_filename = use_state(|| String::new());
-    let errors = use_state(|| FieldErrors::new());
+    let form_data = use_state(new_music_data);
+    // 読み込んだとき・保存したときの中身（フォームと違えば未保存の変更がある）
+    let saved_data = use_state(|| None::<MusicData>);
+    // 読み込んだとき・保存したときのレコードの版（ファイル名, 版）。保存のときにほかでの変更を見分ける
+    let record_version = use_state(|| None::<(String, String)>);
+    // レビュー中の一覧と進み具合（localStorage にも置く）
+    let review = use_state(crate::review::load);
+    let form_filename = use_state(String::new);
+    let errors = use_state(FieldErrors::new);
     let save_status = use_state(|| None::<Result<(), String>>);
     let load_error = use_state(|| None::<String>);
     let save_in_progress = use_state(|| false);
+    // 大きなレコードの送信進捗（送信済み, 全体）バイト
+    let save_progress = use_state(|| None::<(f64, f64)>);
     let focus_title = use_state(|| false);
     let focus_filename = use_state(|| false);
+    let sidebar_sort = use_state(|| SIDEBAR_SORTS[0].1);
+    let incomplete_only = use_state(|| false);
+    let main_view = use_state(|| MainView::Editor);
+    let settings = use_state(api::Settings::default);
+    let policy = use_state(ValidationPolicy::default);
+    // サイドバーの凡例で選んだバッジ（None なら絞り込みなし）
+    let badge_filter = use_state(|| None::<String>);
+    let month_filter = use_state(|| None::<MonthFilter>);
+    // ジャンル・スコア・年の絞り込みはサーバーでする。結果は残るファイル名（None なら絞り込みなし）
+    let list_filter = use_state(api::ListFilter::default);
+    let filtered = use_state(|| None::<Vec<String>>);
+    // フォームは重いので、レコードを選ぶか Add New を押すまでマウントしない（初回表示を速くする）
+    let form_mounted = use_state(|| false);
+    // サーバーのライブラリ（1 つだけなら切り替えは出さない）と選んでいるもの（空なら既定のもの）
+    let libraries = use_state(Vec::<api::Library>::new);
+    let library = use_state(api::current_library);
 
     {
         let file_list = file_list.clone();
         let loading = loading.clone();
         use_effect_with((), move |_| {
+            log_timing("first paint");
             let file_list = file_list.clone();
             let loading = loading.clone();
             wasm_bindgen_futures::spawn_local(async move {
@@ -81,6 +325,26 @@ pub fn app() -> Html {
                     }
                 }
                 loading.set(false);
+                log_timing("list loaded");
+            });
+            || ()
+        });
+    }
+
+    {
+        let settings = settings.clone();
+        let policy = policy.clone();
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Ok(s) = api::get_settings().await {
+                    crate::crash::set_reporting(s.report_client_errors);
+                    set_library_offset(s.utc_offset());
+                    settings.set(s);
+                }
+                // 読めなければ既定の必須項目のまま（保存時のチェックが厳しめになるだけ）
+                if let Ok(p) = api::get_validation_policy().await {
+                    policy.set(p);
+                }
             });
             || ()
         });
@@ -88,28 +352,40 @@ pub fn app() -> Html {
 
     let on_select_file = {
         let form_data = form_data.clone();
+        let saved_data = saved_data.clone();
         let form_filename = form_filename.clone();
         let selected = selected.clone();
         let errors = errors.clone();
         let load_error = load_error.clone();
         let save_status = save_status.clone();
+        let main_view = main_view.clone();
+        let form_mounted = form_mounted.clone();
+        let record_version = record_version.clone();
         Callback::from(move |name: String| {
             let form_data = form_data.clone();
+            let saved_data = saved_data.clone();
+            let record_version = record_version.clone();
             let form_filename = form_filename.clone();
             let selected = selected.clone();
             let errors = errors.clone();
             let load_error = load_error.clone();
             let base = name.strip_suffix(".json").unwrap_or(&name).to_string();
+            crate::crash::record_action("open", Some(&name));
+            crate::crash::set_view(MainView::Editor.id());
             selected.set(Some(name.clone()));
             form_filename.set(base.clone());
             errors.set(FieldErrors::new());
             load_error.set(None);
             save_status.set(None); // 別曲編集開始時に「保存しました。」を消す
+            main_view.set(MainView::Editor);
+            form_mounted.set(true);
             scroll_to_top(); // Issue #27: フォームが画面外にある場合を考慮して最上部へ
             wasm_bindgen_futures::spawn_local(async move {
+                record_version.set(None);
                 match api::get_file(&name).await {
-                    Ok(mut data) => {
+                    Ok((mut data, version)) => {
                         load_error.set(None);
+                        record_version.set(version.map(|v| (name.clone(), v)));
                         // Main が変わったときに Sub がその Main の候補に含まれないと
                         // リスト表示がずれるため、読み込み時に正規化する（Issue #12）
                         let allowed: std::collections::HashSet<_> =
@@ -120,6 +396,7 @@ pub fn app() -> Html {
                                 data.janre.sub.push(first.to_string());
                             }
                         }
+                        saved_data.set(Some(data.clone()));
                         form_data.set(data);
                     }
                     Err(e) => {
@@ -132,19 +409,30 @@ pub fn app() -> Html {
 
     let on_add_new = {
         let form_data = form_data.clone();
+        let saved_data = saved_data.clone();
         let form_filename = form_filename.clone();
         let selected = selected.clone();
         let errors = errors.clone();
         let load_error = load_error.clone();
         let save_status = save_status.clone();
         let focus_title = focus_title.clone();
+        let main_view = main_view.clone();
+        let form_mounted = form_mounted.clone();
+        let record_version = record_version.clone();
         Callback::from(move |_| {
-            form_data.set(new_music_data());
+            crate::crash::record_action("new", None);
+            record_version.set(None);
+            crate::crash::set_view(MainView::Editor.id());
+            let fresh = new_music_data();
+            saved_data.set(Some(fresh.clone()));
+            form_data.set(fresh);
             form_filename.set(String::new());
             selected.set(None);
             errors.set(FieldErrors::new());
             load_error.set(None);
             save_status.set(None); // 新規追加開始時に「保存しました。」を消す
+            main_view.set(MainView::Editor);
+            form_mounted.set(true);
             focus_title.set(true);
         })
     };
@@ -154,30 +442,28 @@ pub fn app() -> Html {
         Callback::from(move |()| focus_title.set(false))
     };
 
-    // ファイル名 blur 時: 新規入力時のみ、同名が既に存在すればエラー表示しフォーカスを戻す。編集時は対象外（上書き保存は正当）。
+    // ファイル名 blur 時: 同名が既に存在すればエラー表示しフォーカスを戻す。
+    // 編集時は自分の名前のままなら対象外（上書き保存は正当）。名前を変えたときは変更先がほかと重ならないか見る。
     let on_filename_blur = {
         let file_list = file_list.clone();
         let selected = selected.clone();
         let errors = errors.clone();
         let focus_filename = focus_filename.clone();
         Callback::from(move |value: String| {
-            if selected.is_some() {
-                return;
-            }
             let base = value.trim();
             let base = if base.ends_with(".json") {
                 base.strip_suffix(".json").unwrap_or(base)
             } else {
                 base
             };
-            if base.is_empty() {
+            if base.is_empty() || selected.as_deref().and_then(|s| s.strip_suffix(".json")) == Some(base) {
                 return;
             }
             let existing: Vec<&str> = file_list
                 .iter()
                 .map(|e| e.filename.strip_suffix(".json").unwrap_or(e.filename.as_str()))
                 .collect();
-            let is_duplicate = existing.iter().any(|&s| s == base);
+            let is_duplicate = existing.contains(&base);
             if is_duplicate {
                 let mut errs = FieldErrors::new();
                 errs.insert("filename".into(), "同名ファイルが既に存在します".into());
@@ -194,52 +480,268 @@ pub fn app() -> Html {
 
     let on_save = {
         let form_data = form_data.clone();
+        let saved_data = saved_data.clone();
         let form_filename = form_filename.clone();
         let errors = errors.clone();
         let file_list = file_list.clone();
         let save_status = save_status.clone();
         let save_in_progress = save_in_progress.clone();
+        let save_progress = save_progress.clone();
+        let timeout_secs = settings.save_timeout_secs();
+        let settings = (*settings).clone();
+        let policy = (*policy).clone();
+        let selected = selected.clone();
+        let record_version = record_version.clone();
         Callback::from(move |()| {
-            let data = (*form_data).clone();
+            let mut data = (*form_data).clone();
             let filename = (*form_filename).clone();
-            let errs = validate_form(&data, &filename);
+            // 記録日・更新日は設定どおりに付ける（「毎回聞く」は既存のレコードで日付が今日でないときだけ）
+            let today = today_str();
+            let refresh_date = data.date != today
+                && match settings.date_mode {
+                    api::DateMode::FirstLogged => false,
+                    api::DateMode::LastUpdated => true,
+                    api::DateMode::Ask => {
+                        selected.is_some()
+                            && gloo_utils::window()
+                                .confirm_with_message(&format!("記録日（{}）を今日（{}）に更新しますか？", data.date, today))
+                                .unwrap_or(false)
+                    }
+                };
+            settings.stamp_dates(&mut data, refresh_date, &today);
+            if data != *form_data {
+                form_data.set(data.clone());
+            }
+            // 既存のレコードでファイル名を変えたときは、先に名前を変えてから保存する（古いファイルを残さない）
+            let target = format!("{}.json", filename.trim().trim_end_matches(".json"));
+            let rename_from = (*selected).clone().filter(|old| *old != target);
+            // 読んだ（前に保存した）ときの版のままなら書く。名前を変えるときは変える前のファイルの版
+            let source = rename_from.clone().unwrap_or_else(|| target.clone());
+            // 版が分からない（読み込み中など）ときは、ほかでの変更を黙って上書きしないよう保存しない
+            let base = match (*record_version).clone() {
+                Some((file, version)) if file == source => api::SaveBase::Version(version),
+                _ if selected.is_none() => api::SaveBase::New,
+                _ => {
+                    save_status.set(Some(Err("レコードを読み込んでいる途中です。読み込みが終わってからもう一度保存してください".into())));
+                    return;
+                }
+            };
+            crate::crash::record_action("save", Some(&format!("{}.json", filename)));
+            let errs = validate_form(&data, &filename, &policy);
             if !errs.is_empty() {
                 log_validation_errors(&errs);
+                if settings.log_validation_failures {
+                    let errs = errs.clone();
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let _ = api::report_validation_failures("form", &errs).await;
+                    });
+                }
                 errors.set(errs);
                 save_status.set(Some(Err("バリデーションエラー".into())));
                 return;
             }
             errors.set(FieldErrors::new());
             save_in_progress.set(true);
+            save_progress.set(None);
             let file_list = file_list.clone();
             let save_status = save_status.clone();
             let save_in_progress = save_in_progress.clone();
+            let save_progress = save_progress.clone();
+            let selected = selected.clone();
+            let saved_data = saved_data.clone();
+            let record_version = record_version.clone();
+            let form_data = form_data.clone();
             wasm_bindgen_futures::spawn_local(async move {
-                let save_fut = api::save_file(&filename, &data);
-                let timeout_fut = gloo_timers::future::TimeoutFuture::new(10_000);
-                futures::pin_mut!(save_fut, timeout_fut);
-                match futures::future::select(save_fut, timeout_fut).await {
-                    futures::future::Either::Left((res, _)) => {
-                        let result: Result<(), String> = res;
-                        save_status.set(Some(result.clone()));
-                        if result.is_ok() {
-                            if let Ok(list) = api::list_with_labels().await {
-                                file_list.set(list);
+                let mut base = base;
+                let mut data = data;
+                if let Some(from) = rename_from {
+                    let version = match &base {
+                        api::SaveBase::Version(v) => Some(v.clone()),
+                        api::SaveBase::New => None,
+                    };
+                    match api::rename_file(&from, &target, version.as_deref()).await {
+                        Ok(renamed) => {
+                            crate::crash::record_action("rename", Some(&renamed.filename));
+                            // カバーの名前も変わり、そのぶん版も進むので、続く保存は名前を変えたあとの版とカバーで書く
+                            if let Some(cover) = renamed.cover {
+                                data.cover = cover;
+                                form_data.set(data.clone());
+                            }
+                            if let Some(v) = renamed.version {
+                                base = api::SaveBase::Version(v);
                             }
+                            selected.set(Some(renamed.filename));
                         }
+                        Err(e) => {
+                            save_status.set(Some(Err(format!("名前を変更できませんでした: {}", e))));
+                            save_in_progress.set(false);
+                            return;
+                        }
+                    }
+                }
+                let result = loop {
+                    match save_with_retry(&filename, &data, &base, timeout_secs, save_progress.clone()).await {
+                        // ほかのタブ・端末で変わっていたら、上書きするか聞く
+                        Err(api::SaveError::Conflict { message, current })
+                            if gloo_utils::window()
+                                .confirm_with_message(&format!("{}。\nこの内容で上書きしますか？", message))
+                                .unwrap_or(false) =>
+                        {
+                            base = current.map_or(api::SaveBase::New, api::SaveBase::Version);
+                        }
+                        res => break res,
                     }
-                    futures::future::Either::Right(((), _)) => {
-                        save_status.set(Some(Err(
-                            "保存がタイムアウトしました（10秒）".into(),
-                        )));
+                };
+                let result = match result {
+                    Ok(version) => {
+                        record_version.set(version.map(|v| (target.clone(), v)));
+                        Ok(())
+                    }
+                    Err(e) => Err(save_error_text(e)),
+                };
+                save_status.set(Some(result.clone()));
+                if result.is_ok() {
+                    saved_data.set(Some(data));
+                    if let Ok(list) = api::list_with_labels().await {
+                        file_list.set(list);
                     }
                 }
                 save_in_progress.set(false);
+                save_progress.set(None);
+            });
+        })
+    };
+
+    let on_delete = {
+        let form_data = form_data.clone();
+        let saved_data = saved_data.clone();
+        let form_filename = form_filename.clone();
+        let selected = selected.clone();
+        let errors = errors.clone();
+        let file_list = file_list.clone();
+        let save_status = save_status.clone();
+        let form_mounted = form_mounted.clone();
+        Callback::from(move |()| {
+            let Some(filename) = (*selected).clone() else {
+                return;
+            };
+            let confirmed = web_sys::window()
+                .and_then(|w| w.confirm_with_message(&format!("{} を削除しますか？（レコードはごみ箱に残りますが、カバー・メモ・添付は消えます）", filename)).ok())
+                .unwrap_or(false);
+            if !confirmed {
+                return;
+            }
+            crate::crash::record_action("delete", Some(&filename));
+            let (form_data, saved_data, form_filename, selected, errors, file_list, save_status, form_mounted) = (
+                form_data.clone(),
+                saved_data.clone(),
+                form_filename.clone(),
+                selected.clone(),
+                errors.clone(),
+                file_list.clone(),
+                save_status.clone(),
+                form_mounted.clone(),
+            );
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(e) = api::delete_file(&filename).await {
+                    save_status.set(Some(Err(e)));
+                    return;
+                }
+                form_data.set(new_music_data());
+                saved_data.set(None);
+                form_filename.set(String::new());
+                selected.set(None);
+                errors.set(FieldErrors::new());
+                save_status.set(None);
+                form_mounted.set(false);
+                if let Ok(list) = api::list_with_labels().await {
+                    file_list.set(list);
+                }
+            });
+        })
+    };
+
+    // いま聴いてる: 編集中のレコードに追記した場合はフォーム側にも反映し、保存で上書きされないようにする
+    // レビュー中は開いた位置を覚えておき、再読み込みしたらそこから続ける
+    {
+        let on_select_file = on_select_file.clone();
+        let review = review.clone();
+        use_effect_with((), move |_| {
+            if let Some(current) = review.as_ref().and_then(|s| s.current.clone()) {
+                on_select_file.emit(current);
+            }
+            || ()
+        });
+    }
+    {
+        let review = review.clone();
+        use_effect_with((*selected).clone(), move |selected| {
+            if let (Some(s), Some(f)) = (review.as_ref(), selected) {
+                if s.current.as_ref() != Some(f) && s.files.contains(f) {
+                    let next = crate::review::ReviewSession { current: Some(f.clone()), ..s.clone() };
+                    crate::review::store(Some(&next));
+                    review.set(Some(next));
+                }
+            }
+            || ()
+        });
+    }
+    let on_review_change = {
+        let review = review.clone();
+        Callback::from(move |next: Option<crate::review::ReviewSession>| {
+            crate::review::store(next.as_ref());
+            review.set(next);
+        })
+    };
+
+    let on_listen_logged = {
+        let form_data = form_data.clone();
+        let saved_data = saved_data.clone();
+        let selected = selected.clone();
+        let record_version = record_version.clone();
+        Callback::from(move |(filename, date, listened): (String, String, api::Listened)| {
+            if selected.as_deref() != Some(filename.as_str()) {
+                return;
+            }
+            // 読み込み中（版がまだない）なら、読み込んだものに追記が入っている
+            let Some(form_version) = (*record_version).clone().filter(|(file, _)| *file == filename).map(|(_, v)| v) else {
+                return;
+            };
+            // フォームが追記の直前の版のときだけ追記した後の版に乗り換える。違えば（ほかのタブ・端末で変わっている）
+            // 版はそのままにして、次の保存を 409 にしてどうするか聞く
+            if listened.previous_version.as_ref() == Some(&form_version) {
+                if let Some(v) = listened.version {
+                    record_version.set(Some((filename, v)));
+                }
+            }
+            let mut d = (*form_data).clone();
+            d.listens.push(date.clone());
+            form_data.set(d);
+            // サーバー側ではもう保存されているので、未保存の変更には数えない
+            if let Some(mut s) = (*saved_data).clone() {
+                s.listens.push(date);
+                saved_data.set(Some(s));
+            }
+        })
+    };
+
+    let on_draft_created = {
+        let file_list = file_list.clone();
+        let on_select_file = on_select_file.clone();
+        Callback::from(move |filename: String| {
+            let file_list = file_list.clone();
+            let on_select_file = on_select_file.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Ok(list) = api::list_with_labels().await {
+                    file_list.set(list);
+                }
+                on_select_file.emit(filename);
             });
         })
     };
 
     let form_data_clone = (*form_data).clone();
+    let form_tracks = form_data_clone.tracks.clone();
     let on_data_change = Callback::from(move |new_data: MusicData| form_data.set(new_data));
     let form_filename_val = (*form_filename).clone();
     let on_filename_change = Callback::from(move |s: String| form_filename.set(s));
@@ -251,19 +753,335 @@ pub fn app() -> Html {
         .collect();
 
     let on_add_new_top = on_add_new.clone();
+    let form_completeness = completeness_percent(&form_data_clone);
+    let form_warnings = warn_form(&form_data_clone, &policy);
+    let form_size = record_size(&form_data_clone).bytes;
+    let size_warning = form_warnings.get("size").cloned();
+
+    // 絞り込み・並び順を変えたときと、保存などで一覧が変わったときに問い合わせ直す
+    let server_query = match *sidebar_sort {
+        SidebarSort::Server { sort, descending } => api::ListFilter { sort, descending, ..(*list_filter).clone() },
+        SidebarSort::CompletenessAsc => (*list_filter).clone(),
+    };
+    {
+        let filtered = filtered.clone();
+        use_effect_with((server_query, file_list.clone()), move |(filter, _)| {
+            if filter.is_empty() {
+                filtered.set(None);
+            } else {
+                let filter = filter.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    if let Ok(list) = api::list_with_labels_filtered(&filter).await {
+                        filtered.set(Some(list.into_iter().map(|e| e.filename).collect()));
+                    }
+                });
+            }
+            || ()
+        });
+    }
+
+    let this_month = format!("{}/", &today_str()[..7]);
+    // サーバーが返した順（絞り込み済み）に並べる。問い合わせていなければ読み込んだ順（ファイル名順）
+    let ordered: Vec<&api::ListEntryWithLabel> = match filtered.as_ref() {
+        Some(names) => {
+            let by_name: std::collections::HashMap<&str, &api::ListEntryWithLabel> =
+                file_list.iter().map(|e| (e.filename.as_str(), e)).collect();
+            names.iter().filter_map(|n| by_name.get(n.as_str()).copied()).collect()
+        }
+        None => file_list.iter().collect(),
+    };
+    let mut visible_entries: Vec<&api::ListEntryWithLabel> = ordered
+        .into_iter()
+        .filter(|e| !*incomplete_only || e.completeness < 100)
+        .filter(|e| badge_filter.as_deref().is_none_or(|b| e.badge == b))
+        .filter(|e| month_filter.is_none_or(|f| f.matches(e, &this_month)))
+        .collect();
+    if *sidebar_sort == SidebarSort::CompletenessAsc {
+        visible_entries.sort_by_key(|e| e.completeness);
+    }
+    let visible_order: Vec<String> = visible_entries.iter().map(|e| e.filename.clone()).collect();
+    // レビュー中は前後の移動もレビューの一覧に沿う
+    let nav_order = review.as_ref().map_or_else(|| visible_order.clone(), |s| s.files.clone());
+
+    // 別のレコードを開く前に、保存していない変更を捨ててよいか確かめる
+    let dirty = *form_mounted && saved_data.as_ref().is_some_and(|s| *s != form_data_clone);
+    let open_record = {
+        let on_select_file = on_select_file.clone();
+        Callback::from(move |name: String| {
+            if crate::record_nav::confirm_discard(dirty) {
+                on_select_file.emit(name);
+            }
+        })
+    };
+
+    let on_sort_change = {
+        let sidebar_sort = sidebar_sort.clone();
+        Callback::from(move |e: Event| {
+            if let Some(sel) = e.target_dyn_into::<web_sys::HtmlSelectElement>() {
+                let value = sel.value();
+                if let Some(&(_, sort, _)) = SIDEBAR_SORTS.iter().find(|(v, _, _)| *v == value) {
+                    sidebar_sort.set(sort);
+                }
+            }
+        })
+    };
+    let on_start_review = {
+        let (on_review_change, open_record, order) = (on_review_change.clone(), open_record.clone(), visible_order.clone());
+        Callback::from(move |_: MouseEvent| {
+            let session = crate::review::ReviewSession::new(order.clone());
+            let first = session.current.clone();
+            on_review_change.emit(Some(session));
+            if let Some(f) = first {
+                open_record.emit(f);
+            }
+        })
+    };
+    let on_export_checklist = {
+        let markdown = crate::checklist::checklist_markdown(&visible_entries);
+        Callback::from(move |_: MouseEvent| {
+            let filename = format!("nekokan_music-checklist-{}.md", today_str().replace('/', "-"));
+            let _ = crate::download::download_text(&filename, "text/markdown", &markdown);
+        })
+    };
+    // 保存していない内容もそのまま（サーバーと同じ並び・字下げの JSON で）
+    let on_download_json = {
+        let data = form_data_clone.clone();
+        let filename = crate::form::record_json_filename(
+            &data,
+            &form_filename_val,
+            selected.as_deref(),
+            &settings.classical_filename,
+        );
+        Callback::from(move |_: MouseEvent| {
+            if let Ok(v) = serde_json::to_value(&data) {
+                let json = nekokan_music_core::schema::to_canonical_json(&v);
+                let _ = crate::download::download_text(&filename, "application/json", &json);
+            }
+        })
+    };
+    let on_incomplete_only_toggle = {
+        let incomplete_only = incomplete_only.clone();
+        Callback::from(move |_: Event| incomplete_only.set(!*incomplete_only))
+    };
+
+    let update_list_filter = |apply: fn(&mut api::ListFilter, String)| {
+        let list_filter = list_filter.clone();
+        Callback::from(move |e: Event| {
+            let value = e
+                .target_dyn_into::<web_sys::HtmlInputElement>()
+                .map(|i| i.value())
+                .or_else(|| e.target_dyn_into::<web_sys::HtmlSelectElement>().map(|s| s.value()))
+                .unwrap_or_default();
+            let mut next = (*list_filter).clone();
+            apply(&mut next, value.trim().to_string());
+            list_filter.set(next);
+        })
+    };
+    let on_clear_list_filter = {
+        let list_filter = list_filter.clone();
+        Callback::from(move |_: MouseEvent| list_filter.set(api::ListFilter::default()))
+    };
+
+    let show_view = |view: MainView| {
+        let main_view = main_view.clone();
+        Callback::from(move |e: MouseEvent| {
+            e.prevent_default();
+            crate::crash::set_view(view.id());
+            main_view.set(view);
+        })
+    };
+
+    let toggle_month_filter = |filter: MonthFilter| {
+        let month_filter = month_filter.clone();
+        Callback::from(move |_: MouseEvent| {
+            month_filter.set(if *month_filter == Some(filter) { None } else { Some(filter) });
+        })
+    };
+
+    let toggle_badge_filter = |badge: String| {
+        let badge_filter = badge_filter.clone();
+        Callback::from(move |_: MouseEvent| {
+            if badge_filter.as_deref() == Some(badge.as_str()) {
+                badge_filter.set(None);
+            } else {
+                badge_filter.set(Some(badge.clone()));
+            }
+        })
+    };
+
+    let on_settings_saved = {
+        let settings = settings.clone();
+        Callback::from(move |s: api::Settings| {
+            crate::crash::set_reporting(s.report_client_errors);
+            set_library_offset(s.utc_offset());
+            settings.set(s);
+        })
+    };
+
+    // クイック入力・一括取り込みなどでファイルが増えたときにサイドバーを読み直す
+    let on_list_changed = {
+        let file_list = file_list.clone();
+        Callback::from(move |()| {
+            let file_list = file_list.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Ok(list) = api::list_with_labels().await {
+                    file_list.set(list);
+                }
+            });
+        })
+    };
+    // 別のタブ・ディスク上の直接の編集で変わったときも読み直す
+    crate::live::use_library_events(on_list_changed.reform(|_| ()));
+
+    {
+        let libraries = libraries.clone();
+        let library = library.clone();
+        let on_list_changed = on_list_changed.clone();
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                let Ok(list) = api::libraries().await else { return };
+                // この前に選んだライブラリがサーバーの設定から消えていたら既定のものに戻す
+                if !library.is_empty() && !list.iter().any(|l| l.name == *library) {
+                    api::set_library("");
+                    library.set(String::new());
+                    on_list_changed.emit(());
+                }
+                libraries.set(list);
+            });
+            || ()
+        });
+    }
+
+    // ライブラリを切り替えたら、開いているレコードを閉じて一覧を読み直す
+    let on_library_change = {
+        let library = library.clone();
+        let selected = selected.clone();
+        let saved_data = saved_data.clone();
+        let form_mounted = form_mounted.clone();
+        let main_view = main_view.clone();
+        let on_list_changed = on_list_changed.clone();
+        let record_version = record_version.clone();
+        Callback::from(move |e: Event| {
+            let Some(sel) = e.target_dyn_into::<web_sys::HtmlSelectElement>() else { return };
+            let name = sel.value();
+            crate::crash::record_action("library", None);
+            api::set_library(&name);
+            library.set(name);
+            selected.set(None);
+            record_version.set(None);
+            saved_data.set(None);
+            form_mounted.set(false);
+            main_view.set(MainView::Editor);
+            on_list_changed.emit(());
+        })
+    };
+
+    // スコアの尺度を変換したら、新しい尺度で入力チェックし、変換後のスコアでサイドバーを読み直す
+    let on_policy_changed = {
+        let policy = policy.clone();
+        let on_list_changed = on_list_changed.clone();
+        Callback::from(move |p: ValidationPolicy| {
+            policy.set(p);
+            on_list_changed.emit(());
+        })
+    };
+
+    // 編集・設定以外のビュー。選ばれているものだけ組み立てる。
+    let extra_view: Option<Html> = match *main_view {
+        MainView::QuickEntry => Some(html! {
+            <crate::quick_entry::QuickEntryForm
+                existing_filenames={file_list.iter().map(|e| e.filename.clone()).collect::<Vec<_>>()}
+                timeout_secs={settings.save_timeout_secs()}
+                policy={(*policy).clone()}
+                log_failures={settings.log_validation_failures}
+                on_saved={on_list_changed.clone()}
+                on_select={on_select_file.clone()}
+            />
+        }),
+        #[cfg(feature = "reports")]
+        MainView::ListeningReport => Some(html! { <crate::report::ListeningReport on_select={on_select_file.clone()} /> }),
+        #[cfg(feature = "reports")]
+        MainView::Goals => Some(html! { <crate::goals::GoalsView /> }),
+        #[cfg(feature = "reports")]
+        MainView::Activity => Some(html! { <crate::activity::ActivityFeed on_select={on_select_file.clone()} /> }),
+        #[cfg(feature = "reports")]
+        MainView::Tunes => Some(html! { <crate::tunes::TuneIndex on_select={on_select_file.clone()} /> }),
+        #[cfg(feature = "reports")]
+        MainView::Personnel => Some(html! { <crate::personnel::PersonnelView on_select={on_select_file.clone()} /> }),
+        #[cfg(feature = "reports")]
+        MainView::Labels => Some(html! { <crate::labels::LabelIndexView on_select={on_select_file.clone()} /> }),
+        #[cfg(feature = "import")]
+        MainView::DiscogsSync => Some(html! {
+            <crate::discogs::DiscogsSync on_imported={on_list_changed.clone()} on_select={on_select_file.clone()} />
+        }),
+        #[cfg(feature = "import")]
+        MainView::UrlImport => Some(html! {
+            <crate::url_import::UrlImport
+                existing_filenames={file_list.iter().map(|e| e.filename.clone()).collect::<Vec<_>>()}
+                timeout_secs={settings.save_timeout_secs()}
+                policy={(*policy).clone()}
+                on_saved={on_list_changed.clone()}
+                on_select={on_select_file.clone()}
+            />
+        }),
+        #[cfg(feature = "import")]
+        MainView::CoverImport => Some(html! {
+            <crate::covers::CoverImport entries={(*file_list).clone()} on_imported={on_list_changed} />
+        }),
+        #[cfg(feature = "maintenance")]
+        MainView::GenreMigration => Some(html! { <crate::genres::GenreMigration on_select={on_select_file.clone()} /> }),
+        #[cfg(feature = "maintenance")]
+        MainView::ValidationReport => Some(html! { <crate::validate_all::ValidationReport on_select={on_select_file.clone()} /> }),
+        #[cfg(feature = "maintenance")]
+        MainView::Composers => Some(html! { <crate::composers::ComposersView /> }),
+        MainView::Editor | MainView::Settings => None,
+    };
+
+    // 編集していないときの最初の画面に出す「何を聴くか決めて」「最近編集したもの」と「今日のカレンダー」
+    #[cfg(feature = "reports")]
+    let calendar = html! { <crate::calendar::TodayCalendar on_select={on_select_file.clone()} /> };
+    #[cfg(not(feature = "reports"))]
+    let calendar = Html::default();
+    let dashboard = html! {
+        <>
+            <crate::random::RandomPickButton filter={(*list_filter).clone()} on_select={on_select_file.clone()} />
+            <crate::recent::RecentlyEdited on_select={on_select_file.clone()} />
+            { calendar }
+        </>
+    };
 
     html! {
         <div class="layout">
             if *save_in_progress {
-                <div class="save-modal-overlay" aria-busy="true" aria-live="polite">
+                <div class="save-modal-overlay" role="dialog" aria-modal="true" aria-labelledby="save-modal-text" aria-busy="true" aria-live="polite">
                     <div class="save-modal-box">
                         <div class="save-modal-spinner" aria-hidden="true"></div>
-                        <p class="save-modal-text">{"保存中..."}</p>
+                        <p class="save-modal-text" id="save-modal-text">{"保存中..."}</p>
+                        if let Some((loaded, total)) = *save_progress {
+                            if total >= LARGE_SAVE_BYTES {
+                                <progress class="save-modal-progress" max={total.to_string()} value={loaded.to_string()}
+                                    aria-label="送信の進捗"></progress>
+                                <p class="save-modal-text">{ format!("{:.0} / {:.0} KB", loaded / 1024.0, total / 1024.0) }</p>
+                            }
+                        }
                     </div>
                 </div>
             }
-            <aside class="sidebar">
+            <aside class="sidebar" aria-label="レコード一覧">
                 <h2 class="sidebar-title">{"Nekokan Music Data"}</h2>
+                if libraries.len() > 1 {
+                    <select class="sidebar-sort sidebar-library" aria-label="ライブラリ" onchange={on_library_change}>
+                        { for libraries.iter().map(|l| {
+                            let value = if l.default { String::new() } else { l.name.clone() };
+                            let label = match l.count {
+                                Some(n) => format!("{}（{} 件）", l.name, n),
+                                None => l.name.clone(),
+                            };
+                            let selected = *library == value;
+                            html! { <option selected={selected} value={value}>{ label }</option> }
+                        }) }
+                    </select>
+                }
                 if *loading {
                     <p class="sidebar-loading">{"読込中..."}</p>
                 } else {
@@ -274,25 +1092,120 @@ pub fn app() -> Html {
                     >
                         {"Add New Music"}
                     </a>
-                    <ul class="file-list">
-                        { for file_list.iter().map(|entry| {
+                    { for VIEW_LINKS.iter().map(|&(view, label)| html! {
+                        <a href="#" class="sidebar-nav-link" onclick={show_view(view)}>{ label }</a>
+                    }) }
+                    <crate::search::SearchPalette on_select={open_record.clone()} selected={(*selected).clone()} />
+                    <div class="sidebar-controls">
+                        <select class="sidebar-sort" aria-label="並び順" onchange={on_sort_change}>
+                            { for SIDEBAR_SORTS.iter().map(|&(value, sort, label)| html! {
+                                <option value={value} selected={*sidebar_sort == sort}>{ label }</option>
+                            }) }
+                        </select>
+                        <label class="sidebar-filter">
+                            <input type="checkbox" checked={*incomplete_only} onchange={on_incomplete_only_toggle}/>
+                            {"未完成のみ"}
+                        </label>
+                        if review.is_none() && !visible_order.is_empty() {
+                            <button type="button" class="badge-legend-item" onclick={on_start_review}
+                                title="いまサイドバーに出ている順に 1 件ずつ見ていきます">
+                                { format!("この {} 件をレビュー", visible_order.len()) }
+                            </button>
+                        }
+                        if !visible_order.is_empty() {
+                            <button type="button" class="badge-legend-item" onclick={on_export_checklist}
+                                title="いまサイドバーに出ているレコードを「アーティスト — タイトル (年)」のチェックリスト（Markdown）で保存します">
+                                {"チェックリスト"}
+                            </button>
+                        }
+                    </div>
+                    <details class="list-filter" open={!list_filter.is_empty()}>
+                        <summary>{"ジャンル・スコア・年で絞り込み"}</summary>
+                        <div class="list-filter-fields">
+                            <select class="sidebar-sort" aria-label="ジャンル" onchange={update_list_filter(|f, v| f.janre = v)}>
+                                <option value="" selected={list_filter.janre.is_empty()}>{"すべてのジャンル"}</option>
+                                { for MAIN_JANRES.iter().map(|j| html! {
+                                    <option value={*j} selected={list_filter.janre == *j}>{ *j }</option>
+                                }) }
+                            </select>
+                            <input type="number" class="sidebar-sort list-filter-number" aria-label="スコアの下限" placeholder="スコア以上"
+                                min={policy.score.min.to_string()} max={policy.score.max.to_string()} step={policy.score.step.to_string()}
+                                value={list_filter.score_min.map(|s| s.to_string()).unwrap_or_default()}
+                                onchange={update_list_filter(|f, v| f.score_min = v.parse().ok())}/>
+                            <input type="number" class="sidebar-sort list-filter-number" aria-label="発売年（から）" placeholder="1955"
+                                value={list_filter.year_from.map(|y| y.to_string()).unwrap_or_default()}
+                                onchange={update_list_filter(|f, v| f.year_from = v.parse().ok())}/>
+                            <span aria-hidden="true">{"〜"}</span>
+                            <input type="number" class="sidebar-sort list-filter-number" aria-label="発売年（まで）" placeholder="1965"
+                                value={list_filter.year_to.map(|y| y.to_string()).unwrap_or_default()}
+                                onchange={update_list_filter(|f, v| f.year_to = v.parse().ok())}/>
+                            if !list_filter.is_empty() {
+                                <button type="button" class="badge-legend-item" onclick={on_clear_list_filter}>{"解除"}</button>
+                            }
+                        </div>
+                    </details>
+                    <div class="month-filter" role="group" aria-label="今月の絞り込み">
+                        { for [(MonthFilter::Added, "今月追加"), (MonthFilter::Edited, "今月編集")].into_iter().map(|(f, label)| {
+                            let active = *month_filter == Some(f);
+                            html! {
+                                <button type="button" class={classes!("badge-legend-item", active.then_some("active"))}
+                                    aria-pressed={active.to_string()} onclick={toggle_month_filter(f)}>
+                                    { label }
+                                </button>
+                            }
+                        }) }
+                    </div>
+                    if !settings.badges.is_empty() {
+                        <div class="badge-legend" role="group" aria-label="バッジで絞り込み">
+                            { for settings.badges.iter().map(|b| {
+                                let active = badge_filter.as_deref() == Some(b.badge.as_str());
+                                html! {
+                                    <button type="button" class={classes!("badge-legend-item", active.then_some("active"))}
+                                        aria-pressed={active.to_string()} onclick={toggle_badge_filter(b.badge.clone())}>
+                                        <span class="file-item-badge">{ b.badge.clone() }</span>{ b.label.clone() }
+                                    </button>
+                                }
+                            }) }
+                        </div>
+                    }
+                    <ul class={classes!("file-list", (settings.sidebar.density == api::SidebarDensity::Compact).then_some("file-list-compact"))}>
+                        { for visible_entries.iter().map(|entry| {
                             let filename = entry.filename.clone();
                             let is_selected = selected.as_deref() == Some(filename.as_str());
-                            let display_label = if entry.display_label.chars().count() >= 40 {
-                                format!("{}...", entry.display_label.chars().take(37).collect::<String>())
+                            let label = settings.sidebar.label(entry, &settings.label_templates);
+                            let display_label = if label.chars().count() >= 40 {
+                                format!("{}...", label.chars().take(37).collect::<String>())
                             } else {
-                                entry.display_label.clone()
+                                label
                             };
+                            let stars = entry
+                                .score
+                                .filter(|s| settings.sidebar.show_score && *s > 0.0)
+                                .map(|s| (crate::validation::format_score(s), policy.score.stars(s)));
                             let filename_for_click = entry.filename.clone();
-                            let on_select_file = on_select_file.clone();
+                            let on_select_file = open_record.clone();
                             html! {
                                 <li key={filename.clone()}>
                                     <button
                                         class={if is_selected { "file-item selected" } else { "file-item" }}
-                                        title={filename.clone()}
+                                        title={match (entry.fields == Default::default(), entry.years()) {
+                                            (true, _) => filename.clone(),
+                                            (false, Some(years)) => format!("{}\n{}\n{}", entry.fields.tooltip(), years, filename),
+                                            (false, None) => format!("{}\n{}", entry.fields.tooltip(), filename),
+                                        }}
+                                        aria-current={is_selected.then_some("true")}
                                         onclick={move |_| on_select_file.emit(filename_for_click.clone())}
                                     >
+                                        if !entry.badge.is_empty() {
+                                            <span class="file-item-badge" title={settings.badge_label(&entry.badge).map(str::to_string)}>
+                                                { entry.badge.clone() }
+                                            </span>
+                                        }
                                         { display_label }
+                                        if let Some((s, text)) = stars {
+                                            <span class="file-item-score" aria-label={format!("スコア {}", s)}>{ text }</span>
+                                        }
+                                        <span class="file-item-completeness" aria-label={format!("完成度 {}%", entry.completeness)}>{ format!("{}%", entry.completeness) }</span>
                                     </button>
                                 </li>
                             }
@@ -309,49 +1222,101 @@ pub fn app() -> Html {
                     </a>
                 }
             </aside>
-            <main class="content">
+            <main class="content" aria-busy={save_in_progress.then_some("true")}>
                 <div class="content-inner">
                     <h1 class="app-title">{ crate::APP_TITLE_WITH_VERSION }</h1>
-                    if let Some(ref msg) = *load_error {
-                        <p class="load-err">{"ロードエラー: "}{ msg.clone() }</p>
-                    }
-                    if has_validation_errors {
-                        <div class="form-section validation-errors-summary" id="validation-errors-box">
-                            <h3>{"バリデーションエラー"}</h3>
-                            <p class="error-count">{ format!("{} 件のエラー", errors_list.len()) }</p>
-                            <ul class="error-list">
-                                { for errors_list.iter().map(|(k, v)| html! {
-                                    <li class="error-item"><span class="error-key">{ k.clone() }</span>{ ": " }{ v.clone() }</li>
-                                }) }
-                            </ul>
+                    if let Some(view) = extra_view {
+                        { view }
+                    } else if *main_view == MainView::Settings {
+                        <crate::settings::SettingsView
+                            settings={(*settings).clone()}
+                            on_saved={on_settings_saved}
+                            policy={(*policy).clone()}
+                            on_policy_changed={on_policy_changed}
+                        />
+                    } else if !*form_mounted {
+                        <p class="hint editor-placeholder">{"左の一覧からレコードを選ぶか、「Add New Music」で新規追加してください。"}</p>
+                        { dashboard }
+                    } else {
+                        if let Some(ref msg) = *load_error {
+                            <p class="load-err">{"ロードエラー: "}{ msg.clone() }</p>
+                        }
+                        if has_validation_errors {
+                            <div class="form-section validation-errors-summary" id="validation-errors-box" role="alert">
+                                <h3>{"バリデーションエラー"}</h3>
+                                <p class="error-count">{ format!("{} 件のエラー", errors_list.len()) }</p>
+                                <ul class="error-list">
+                                    { for errors_list.iter().map(|(k, v)| html! {
+                                        <li class="error-item"><span class="error-key">{ k.clone() }</span>{ ": " }{ v.clone() }</li>
+                                    }) }
+                                </ul>
+                            </div>
+                        }
+                        if let Some(ref session) = *review {
+                            <crate::review::ReviewBar session={session.clone()} current={(*selected).clone()}
+                                on_change={on_review_change.clone()} on_go={open_record.clone()} />
+                        }
+                        if let Some(ref f) = *selected {
+                            <crate::record_nav::RecordNav order={nav_order.clone()} current={f.clone()} on_go={open_record.clone()} />
+                        }
+                        <div class="completeness-meter" title="入力完成度">
+                            <span class="completeness-label">{"完成度"}</span>
+                            <meter min="0" max="100" low="50" high="99" optimum="100" value={form_completeness.to_string()} aria-label="入力完成度"></meter>
+                            <span class="completeness-value">{ format!("{}%", form_completeness) }</span>
+                            <span class="record-size" title="保存したときの JSON の大きさ">{ format_size(form_size) }</span>
+                            <button type="button" class="btn-add btn-download-json" onclick={on_download_json}
+                                title="いま入力している内容（未保存の変更を含む）を JSON ファイルとして保存します">
+                                {"JSONをダウンロード"}
+                            </button>
                         </div>
-                    }
-                    <crate::form::Form
-                        data={form_data_clone}
-                        on_data_change={on_data_change}
-                        filename={form_filename_val}
-                        on_filename_change={on_filename_change}
-                        errors={errors_val}
-                        on_save={on_save}
-                        focus_title={*focus_title}
-                        on_focus_title_done={on_focus_title_done}
-                        existing_filenames={file_list.iter().map(|e| e.filename.clone()).collect::<Vec<_>>()}
-                        selected_filename={(*selected).clone()}
-                        on_filename_blur={on_filename_blur}
-                        focus_filename={*focus_filename}
-                        on_focus_filename_done={on_focus_filename_done}
-                    />
-                    if let Some(ref status) = *save_status {
-                        <p class={if status.is_ok() { "save-ok" } else { "save-err" }}>
-                            { if status.as_ref().ok().is_some() {
-                                "保存しました。".to_string()
-                            } else {
-                                status.as_ref().err().cloned().unwrap_or_default()
-                            } }
-                        </p>
+                        if let Some(w) = size_warning {
+                            <p class="warning-text" role="status">{ format!("⚠ {}", w) }</p>
+                        }
+                        <crate::form::Form
+                            data={form_data_clone}
+                            on_data_change={on_data_change}
+                            filename={form_filename_val}
+                            on_filename_change={on_filename_change}
+                            errors={errors_val}
+                            warnings={form_warnings}
+                            on_save={on_save}
+                            on_delete={on_delete}
+                            focus_title={*focus_title}
+                            on_focus_title_done={on_focus_title_done}
+                            existing_filenames={file_list.iter().map(|e| e.filename.clone()).collect::<Vec<_>>()}
+                            selected_filename={(*selected).clone()}
+                            on_filename_blur={on_filename_blur}
+                            focus_filename={*focus_filename}
+                            on_focus_filename_done={on_focus_filename_done}
+                            badge_legend={settings.badges.clone()}
+                            score_scale={policy.score}
+                            classical_filename={settings.classical_filename.clone()}
+                        />
+                        // 読み上げ用のライブリージョンは常に置いておき、中身だけ差し替える
+                        <div role="status" aria-live="polite">
+                            if let Some(ref status) = *save_status {
+                                <p class={if status.is_ok() { "save-ok" } else { "save-err" }}>
+                                    { if status.as_ref().ok().is_some() {
+                                        "保存しました。".to_string()
+                                    } else {
+                                        status.as_ref().err().cloned().unwrap_or_default()
+                                    } }
+                                </p>
+                            }
+                        </div>
+                        if let Some(ref f) = *selected {
+                            <crate::notes::NotesPanel filename={f.clone()} />
+                            <crate::attachments::AttachmentsPanel filename={f.clone()} />
+                            <crate::audio::AudioPanel filename={f.clone()} tracks={form_tracks.clone()} />
+                        }
                     }
                 </div>
             </main>
+            <crate::now_listening::NowListening
+                entries={(*file_list).clone()}
+                on_logged={on_listen_logged}
+                on_draft_created={on_draft_created}
+            />
         </div>
     }
 }
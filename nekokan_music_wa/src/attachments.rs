@@ -0,0 +1,273 @@
+use crate::api::{self, Attachment};
+use crate::focus::on_escape;
+use yew::prelude::*;
+
+/// 1234567 → "1.2 MB"
+pub fn format_size(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    let b = bytes as f64;
+    if b < KB {
+        format!("{} B", bytes)
+    } else if b < KB * KB {
+        format!("{:.0} KB", b / KB)
+    } else {
+        format!("{:.1} MB", b / KB / KB)
+    }
+}
+
+/// ライトボックスで前後に送る（端で反対側に回る）
+pub fn step_index(i: usize, len: usize, forward: bool) -> usize {
+    if len == 0 {
+        0
+    } else if forward {
+        (i + 1) % len
+    } else {
+        (i + len - 1) % len
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct AttachmentsPanelProps {
+    /// 対象レコード（"xxx.json"）
+    pub filename: String,
+}
+
+/// カバー以外の添付。画像（裏ジャケット・帯・ライナーの写真など）はギャラリーにし、
+/// サムネイルをクリックするとライトボックスで開いて ← → で送る。PDF（スキャンしたブックレット）はアプリ内のビューアで開く。
+/// 保存ボタンとは関係なくすぐサーバーに書く。
+#[function_component(AttachmentsPanel)]
+pub fn attachments_panel(props: &AttachmentsPanelProps) -> Html {
+    let items = use_state(Vec::<Attachment>::new);
+    let error = use_state(|| None::<String>);
+    let busy = use_state(|| false);
+    // ライトボックスで開いている画像（images の何番目か）
+    let open = use_state(|| None::<usize>);
+    // ビューアで開いている PDF の名前
+    let pdf_open = use_state(|| None::<String>);
+    let close_ref = use_node_ref();
+
+    {
+        let items = items.clone();
+        let error = error.clone();
+        let open = open.clone();
+        let pdf_open = pdf_open.clone();
+        use_effect_with(props.filename.clone(), move |filename| {
+            let filename = filename.clone();
+            items.set(vec![]);
+            error.set(None);
+            open.set(None);
+            pdf_open.set(None);
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::get_attachments(&filename).await {
+                    Ok(list) => items.set(list),
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+            || ()
+        });
+    }
+    // ライトボックスを開いたら閉じるボタンにフォーカスを移す（キー操作を受けるため）
+    {
+        let close_ref = close_ref.clone();
+        use_effect_with(open.is_some() || pdf_open.is_some(), move |opened| {
+            if *opened {
+                if let Some(el) = close_ref.cast::<web_sys::HtmlElement>() {
+                    let _ = el.focus();
+                }
+            }
+            || ()
+        });
+    }
+
+    let on_files = {
+        let items = items.clone();
+        let error = error.clone();
+        let busy = busy.clone();
+        let filename = props.filename.clone();
+        Callback::from(move |e: Event| {
+            let Some(input) = e.target_dyn_into::<web_sys::HtmlInputElement>() else {
+                return;
+            };
+            let files: Vec<web_sys::File> = input
+                .files()
+                .map(|l| (0..l.length()).filter_map(|i| l.get(i)).collect())
+                .unwrap_or_default();
+            // 同じファイルを続けて選んでも change が起きるように空にしておく
+            input.set_value("");
+            if files.is_empty() {
+                return;
+            }
+            let (items, error, busy, filename) = (items.clone(), error.clone(), busy.clone(), filename.clone());
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::upload_attachments(&filename, &files).await {
+                    Ok(list) => {
+                        items.set(list);
+                        error.set(None);
+                    }
+                    Err(e) => error.set(Some(e)),
+                }
+                busy.set(false);
+            });
+        })
+    };
+
+    let remove = |a: &Attachment| {
+        let (items, error, open, pdf_open) = (items.clone(), error.clone(), open.clone(), pdf_open.clone());
+        let (filename, name) = (props.filename.clone(), a.name.clone());
+        Callback::from(move |_: MouseEvent| {
+            let (items, error, open, pdf_open, filename, name) =
+                (items.clone(), error.clone(), open.clone(), pdf_open.clone(), filename.clone(), name.clone());
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::delete_attachment(&filename, &name).await {
+                    Ok(list) => {
+                        items.set(list);
+                        error.set(None);
+                        open.set(None);
+                        pdf_open.set(None);
+                    }
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+        })
+    };
+
+    let show = |i: usize| {
+        let open = open.clone();
+        Callback::from(move |e: MouseEvent| {
+            e.prevent_default();
+            open.set(Some(i));
+        })
+    };
+    let show_pdf = |a: &Attachment| {
+        let pdf_open = pdf_open.clone();
+        let name = a.name.clone();
+        Callback::from(move |e: MouseEvent| {
+            e.prevent_default();
+            pdf_open.set(Some(name.clone()));
+        })
+    };
+    let close = {
+        let open = open.clone();
+        let pdf_open = pdf_open.clone();
+        Callback::from(move |()| {
+            open.set(None);
+            pdf_open.set(None);
+        })
+    };
+
+    let (images, pdfs): (Vec<&Attachment>, Vec<&Attachment>) = items.iter().partition(|a| !a.is_pdf());
+    let on_lightbox_key = {
+        let open = open.clone();
+        let len = images.len();
+        let escape = on_escape(close.clone());
+        Callback::from(move |e: KeyboardEvent| match e.key().as_str() {
+            "ArrowRight" | "ArrowLeft" => {
+                e.prevent_default();
+                if let Some(i) = *open {
+                    open.set(Some(step_index(i, len, e.key() == "ArrowRight")));
+                }
+            }
+            _ => escape.emit(e),
+        })
+    };
+
+    let lightbox = open.and_then(|i| images.get(i).map(|a| (i, *a))).map(|(i, a)| {
+        let step = |forward: bool| {
+            let open = open.clone();
+            let len = images.len();
+            Callback::from(move |e: MouseEvent| {
+                e.stop_propagation();
+                open.set(Some(step_index(i, len, forward)));
+            })
+        };
+        html! {
+            <div class="lightbox-overlay" role="dialog" aria-modal="true" aria-label={a.name.clone()}
+                onkeydown={on_lightbox_key.clone()} onclick={close.reform(|_: MouseEvent| ())}>
+                <img class="lightbox-image" src={api::attachment_url(&props.filename, &a.name)} alt={a.name.clone()}
+                    onclick={|e: MouseEvent| e.stop_propagation()} />
+                <div class="lightbox-bar" onclick={|e: MouseEvent| e.stop_propagation()}>
+                    if images.len() > 1 {
+                        <button type="button" class="btn-add" aria-label="前の画像" onclick={step(false)}>{"←"}</button>
+                    }
+                    <span class="lightbox-caption">{ format!("{}（{} / {}）", a.name, i + 1, images.len()) }</span>
+                    if images.len() > 1 {
+                        <button type="button" class="btn-add" aria-label="次の画像" onclick={step(true)}>{"→"}</button>
+                    }
+                    <button type="button" class="btn-remove" ref={close_ref.clone()} onclick={close.reform(|_: MouseEvent| ())}>{"閉じる"}</button>
+                </div>
+            </div>
+        }
+    });
+
+    let pdf_viewer = pdf_open.as_ref().map(|name| {
+        let url = api::attachment_url(&props.filename, name);
+        html! {
+            <div class="lightbox-overlay" role="dialog" aria-modal="true" aria-label={name.clone()}
+                onkeydown={on_escape(close.clone())}>
+                <iframe class="pdf-frame" src={url.clone()} title={name.clone()}></iframe>
+                <div class="lightbox-bar">
+                    <span class="lightbox-caption">{ name.clone() }</span>
+                    <a href={url} target="_blank" rel="noopener">{"新しいタブで開く"}</a>
+                    <button type="button" class="btn-remove" ref={close_ref.clone()} onclick={close.reform(|_: MouseEvent| ())}>{"閉じる"}</button>
+                </div>
+            </div>
+        }
+    });
+
+    html! {
+        <div class="form-section attachments-panel">
+            <h3>{ format!("添付（画像 {}・PDF {}）", images.len(), pdfs.len()) }</h3>
+            if !pdfs.is_empty() {
+                <ul class="note-list">
+                    { for pdfs.iter().map(|a| html! {
+                        <li key={a.name.clone()} class="note-item">
+                            <a class="note-text" href={api::attachment_url(&props.filename, &a.name)} onclick={show_pdf(a)}>
+                                { format!("📄 {}", a.name) }
+                            </a>
+                            <span class="note-time">{ format_size(a.size) }</span>
+                            <button type="button" class="btn-remove" aria-label={format!("添付「{}」を削除", a.name)} onclick={remove(a)}>{"削除"}</button>
+                        </li>
+                    }) }
+                </ul>
+            }
+            <ul class="attachment-grid">
+                { for images.iter().enumerate().map(|(i, a)| html! {
+                    <li key={a.name.clone()} class="attachment-item">
+                        <a href={api::attachment_url(&props.filename, &a.name)} onclick={show(i)} title={a.name.clone()}>
+                            <img class="attachment-thumb" loading="lazy" src={api::attachment_url(&props.filename, &a.name)} alt={a.name.clone()} />
+                        </a>
+                        <span class="attachment-name">{ a.name.clone() }</span>
+                        <span class="note-time">{ format_size(a.size) }</span>
+                        <button type="button" class="btn-remove" aria-label={format!("添付「{}」を削除", a.name)} onclick={remove(a)}>{"削除"}</button>
+                    </li>
+                }) }
+            </ul>
+            <label class="settings-inline">
+                <input type="file" accept="image/jpeg,image/png,image/webp,image/gif,application/pdf" multiple={true}
+                    aria-label="添付を追加" disabled={*busy} onchange={on_files}/>
+                { if *busy { "アップロード中..." } else { "裏ジャケット・帯・ライナーの写真やブックレットの PDF を追加（複数可。画像は 20MB、PDF は 100MB まで）" } }
+            </label>
+            if let Some(ref e) = *error {
+                <p class="save-err" role="alert">{ e.clone() }</p>
+            }
+            { lightbox.unwrap_or_default() }
+            { pdf_viewer.unwrap_or_default() }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod attachments_tests {
+    use super::{format_size, step_index};
+
+    #[test]
+    fn sizes_and_lightbox_wraps() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(200 * 1024), "200 KB");
+        assert_eq!(format_size(3 * 1024 * 1024 + 300 * 1024), "3.3 MB");
+        assert_eq!(step_index(2, 3, true), 0);
+        assert_eq!(step_index(0, 3, false), 2);
+        assert_eq!(step_index(0, 0, true), 0);
+    }
+}
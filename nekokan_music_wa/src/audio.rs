@@ -0,0 +1,171 @@
+use crate::api::{self, AudioSample};
+use crate::attachments::format_size;
+use crate::types::Track;
+use yew::prelude::*;
+
+/// 置き場所の表示（"アルバム" / "1-2 Subterfuge"）
+pub fn slot_label(disc: Option<u32>, track: Option<u32>, tracks: &[Track]) -> String {
+    let Some(no) = track else {
+        return "アルバム".to_string();
+    };
+    let disc = disc.unwrap_or(1);
+    let title = tracks
+        .iter()
+        .find(|t| t.disc_no == disc as i32 && t.no == no as i32)
+        .map(|t| t.title.clone())
+        .unwrap_or_default();
+    format!("{}-{} {}", disc, no, title).trim_end().to_string()
+}
+
+/// 選択肢の値（"" ならアルバム、"1-2" ならディスク 1 のトラック 2）を読む
+fn parse_slot(value: &str) -> (u32, Option<u32>) {
+    value
+        .split_once('-')
+        .and_then(|(d, n)| Some((d.parse().ok()?, Some(n.parse().ok()?))))
+        .unwrap_or((1, None))
+}
+
+#[derive(Properties, PartialEq)]
+pub struct AudioPanelProps {
+    /// 対象レコード（"xxx.json"）
+    pub filename: String,
+    /// 編集中のトラック（トラックの音声の置き場所に出す）
+    pub tracks: Vec<Track>,
+}
+
+/// 試聴用の短い音声。アルバムに 1 つ、トラックごとに 1 つ置けて、あれば小さなプレイヤーで聴ける。
+/// 添付と同じく保存ボタンとは関係なくすぐサーバーに書く。
+#[function_component(AudioPanel)]
+pub fn audio_panel(props: &AudioPanelProps) -> Html {
+    let items = use_state(Vec::<AudioSample>::new);
+    let error = use_state(|| None::<String>);
+    let busy = use_state(|| false);
+    // アップロード先（parse_slot の形）
+    let slot = use_state(String::new);
+
+    {
+        let (items, error, slot) = (items.clone(), error.clone(), slot.clone());
+        use_effect_with(props.filename.clone(), move |filename| {
+            let filename = filename.clone();
+            items.set(vec![]);
+            error.set(None);
+            slot.set(String::new());
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::get_audio(&filename).await {
+                    Ok(list) => items.set(list),
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+            || ()
+        });
+    }
+
+    let on_slot = {
+        let slot = slot.clone();
+        Callback::from(move |e: Event| {
+            if let Some(sel) = e.target_dyn_into::<web_sys::HtmlSelectElement>() {
+                slot.set(sel.value());
+            }
+        })
+    };
+    let on_file = {
+        let (items, error, busy, slot) = (items.clone(), error.clone(), busy.clone(), slot.clone());
+        let filename = props.filename.clone();
+        Callback::from(move |e: Event| {
+            let Some(input) = e.target_dyn_into::<web_sys::HtmlInputElement>() else {
+                return;
+            };
+            let file = input.files().and_then(|l| l.get(0));
+            // 同じファイルを続けて選んでも change が起きるように空にしておく
+            input.set_value("");
+            let Some(file) = file else { return };
+            let (disc, track) = parse_slot(&slot);
+            let (items, error, busy, filename) = (items.clone(), error.clone(), busy.clone(), filename.clone());
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::upload_audio(&filename, disc, track, &file).await {
+                    Ok(list) => {
+                        items.set(list);
+                        error.set(None);
+                    }
+                    Err(e) => error.set(Some(e)),
+                }
+                busy.set(false);
+            });
+        })
+    };
+    let remove = |s: &AudioSample| {
+        let (items, error) = (items.clone(), error.clone());
+        let (filename, disc, track) = (props.filename.clone(), s.disc.unwrap_or(1), s.track);
+        Callback::from(move |_: MouseEvent| {
+            let (items, error, filename) = (items.clone(), error.clone(), filename.clone());
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::delete_audio(&filename, disc, track).await {
+                    Ok(list) => {
+                        items.set(list);
+                        error.set(None);
+                    }
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+        })
+    };
+
+    html! {
+        <div class="form-section audio-panel">
+            <h3>{"試聴"}</h3>
+            if !items.is_empty() {
+                <ul class="note-list">
+                    { for items.iter().map(|s| {
+                        let label = slot_label(s.disc, s.track, &props.tracks);
+                        html! {
+                            <li key={s.file.clone()} class="note-item audio-item">
+                                <span class="note-text">{ label.clone() }</span>
+                                <audio controls={true} preload="none" src={api::audio_url(&props.filename, s)} aria-label={format!("{} の試聴", label)}></audio>
+                                <span class="note-time">{ format_size(s.size) }</span>
+                                <button type="button" class="btn-remove" aria-label={format!("「{}」の試聴を削除", label)} onclick={remove(s)}>{"削除"}</button>
+                            </li>
+                        }
+                    }) }
+                </ul>
+            }
+            <div class="settings-inline">
+                <select class="sidebar-sort" aria-label="試聴の置き場所" onchange={on_slot}>
+                    <option value="" selected={slot.is_empty()}>{"アルバム"}</option>
+                    { for props.tracks.iter().filter(|t| t.no > 0).map(|t| {
+                        let value = format!("{}-{}", t.disc_no.max(1), t.no);
+                        html! {
+                            <option value={value.clone()} selected={*slot == value}>
+                                { slot_label(Some(t.disc_no.max(1) as u32), Some(t.no as u32), &props.tracks) }
+                            </option>
+                        }
+                    }) }
+                </select>
+                <label>
+                    <input type="file" accept="audio/*" aria-label="試聴用の音声を追加" disabled={*busy} onchange={on_file}/>
+                    { if *busy { "アップロード中..." } else { "mp3・m4a・ogg などの短い音声（15MB まで。同じところのものは置き換え）" } }
+                </label>
+            </div>
+            if let Some(ref e) = *error {
+                <p class="save-err" role="alert">{ e.clone() }</p>
+            }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod audio_tests {
+    use super::{parse_slot, slot_label};
+    use crate::types::Track;
+
+    #[test]
+    fn slots_are_labelled_and_parsed() {
+        let tracks = vec![Track { disc_no: 1, no: 2, title: "Subterfuge".into(), ..Default::default() }];
+        assert_eq!(slot_label(None, None, &tracks), "アルバム");
+        assert_eq!(slot_label(None, Some(2), &tracks), "1-2 Subterfuge");
+        assert_eq!(slot_label(Some(2), Some(5), &tracks), "2-5");
+        assert_eq!(parse_slot(""), (1, None));
+        assert_eq!(parse_slot("2-5"), (2, Some(5)));
+        assert_eq!(parse_slot("x-5"), (1, None));
+    }
+}
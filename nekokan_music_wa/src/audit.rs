@@ -0,0 +1,105 @@
+use crate::api::{self, AuditEntry};
+use yew::prelude::*;
+
+/// 操作の表示（"update" → "保存"）
+pub fn action_label(e: &AuditEntry) -> String {
+    match e.action.as_deref() {
+        Some("create") => "新規作成".to_string(),
+        Some("update") => "保存".to_string(),
+        Some("delete") => "削除".to_string(),
+        Some("rename") => format!("名前の変更 → {}", e.renamed_to.as_deref().unwrap_or("")),
+        _ => format!("{} {}", e.method, e.path),
+    }
+}
+
+/// 設定画面の「変更の記録」。レコードの保存・削除・名前の変更を、いつ・どの端末で・どの項目を変えたかと一緒に新しい順に出す。
+#[function_component(AuditSection)]
+pub fn audit_section() -> Html {
+    let file = use_state(String::new);
+    let from = use_state(String::new);
+    let to = use_state(String::new);
+    let entries = use_state(|| None::<Result<Vec<AuditEntry>, String>>);
+
+    let input = |state: &UseStateHandle<String>| {
+        let state = state.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                state.set(inp.value());
+            }
+        })
+    };
+    let invalid = [&*from, &*to].into_iter().any(|d| !d.trim().is_empty() && !crate::date::is_valid(d.trim()));
+    let on_load = {
+        let (file, from, to, entries) = (file.clone(), from.clone(), to.clone(), entries.clone());
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let (f, a, b, entries) = ((*file).clone(), (*from).clone(), (*to).clone(), entries.clone());
+            wasm_bindgen_futures::spawn_local(async move {
+                entries.set(Some(api::audit_log(&f, &a, &b).await));
+            });
+        })
+    };
+
+    html! {
+        <div class="form-section">
+            <h3>{"変更の記録"}</h3>
+            <p class="hint">{"レコードの保存・削除・名前の変更を新しい順に出します（最大 200 件、日付は UTC）。db/.config/audit.jsonl に追記したものです。"}</p>
+            <form class="settings-inline" onsubmit={on_load}>
+                <input type="text" class="input" aria-label="ファイル名で絞る" placeholder="ファイル名の一部"
+                    value={(*file).clone()} oninput={input(&file)}/>
+                <input type="text" class="input report-date" placeholder="YYYY/MM/DD" aria-label="期間の開始"
+                    value={(*from).clone()} oninput={input(&from)}/>
+                <span>{"〜"}</span>
+                <input type="text" class="input report-date" placeholder="YYYY/MM/DD" aria-label="期間の終了"
+                    value={(*to).clone()} oninput={input(&to)}/>
+                <button type="submit" class="btn-add" disabled={invalid}>{"表示"}</button>
+            </form>
+            if invalid {
+                <p class="save-err">{"日付は YYYY/MM/DD で入力してください。"}</p>
+            }
+            { match &*entries {
+                None => html! {},
+                Some(Err(e)) => html! { <p class="load-err">{ e.clone() }</p> },
+                Some(Ok(list)) if list.is_empty() => html! { <p class="hint">{"該当する変更はありません。"}</p> },
+                Some(Ok(list)) => html! {
+                    <table class="cover-table">
+                        <thead>
+                            <tr><th>{"日時"}</th><th>{"操作"}</th><th>{"ファイル"}</th><th>{"変えた項目"}</th><th>{"端末"}</th></tr>
+                        </thead>
+                        <tbody>
+                            { for list.iter().enumerate().map(|(i, e)| html! {
+                                <tr key={format!("{}#{}", e.at, i)}>
+                                    <td>{ e.at.replace('T', " ").trim_end_matches('Z').to_string() }</td>
+                                    <td>{ action_label(e) }</td>
+                                    <td>
+                                        { e.file.clone().unwrap_or_default() }
+                                        if let Some(lib) = &e.library {
+                                            <span class="note-time">{ format!("（{}）", lib) }</span>
+                                        }
+                                    </td>
+                                    <td>{ e.fields.clone().unwrap_or_default() }</td>
+                                    <td>{ e.token.clone().unwrap_or_else(|| "—".to_string()) }</td>
+                                </tr>
+                            }) }
+                        </tbody>
+                    </table>
+                },
+            } }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod audit_tests {
+    use super::action_label;
+    use crate::api::AuditEntry;
+
+    #[test]
+    fn actions_are_described() {
+        let mut e = AuditEntry { method: "POST".into(), path: "/api/notes/a.json".into(), ..Default::default() };
+        assert_eq!(action_label(&e), "POST /api/notes/a.json");
+        e.action = Some("rename".into());
+        e.renamed_to = Some("b.json".into());
+        assert_eq!(action_label(&e), "名前の変更 → b.json");
+    }
+}
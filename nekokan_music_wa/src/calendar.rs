@@ -0,0 +1,96 @@
+use crate::api::{self, Calendar, CalendarEntry, CalendarKind};
+use yew::prelude::*;
+
+/// 1 件の説明（"発売 60 周年（1966）" "6 年前に聴いた" など）
+pub fn describe(e: &CalendarEntry) -> String {
+    match e.kind {
+        CalendarKind::Released => format!("発売 {} 周年（{}）", e.years, e.when),
+        CalendarKind::Recorded => format!("録音 {} 周年（{}）", e.years, e.when),
+        CalendarKind::Added => format!("{} 年前に登録（{}）", e.years, e.when),
+        CalendarKind::Listened => format!("{} 年前に聴いた（{}）", e.years, e.when),
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct TodayCalendarProps {
+    /// レコード名クリックでそのレコードを開く（"xxx.json"）
+    pub on_select: Callback<String>,
+}
+
+/// 今日のカレンダー。発売・録音の周年と、過去の同じ日・同じ月に登録した／聴いたレコードを並べる。
+/// 何を聴くか迷ったときのきっかけ用なので、読めなくてもエラーは出さず何も表示しない。
+#[function_component(TodayCalendar)]
+pub fn today_calendar(props: &TodayCalendarProps) -> Html {
+    let cal = use_state(|| None::<Calendar>);
+    {
+        let cal = cal.clone();
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Ok(c) = api::calendar().await {
+                    cal.set(Some(c));
+                }
+            });
+            || ()
+        });
+    }
+    let Some(c) = &*cal else {
+        return html! {};
+    };
+    if c.anniversaries.is_empty() && c.on_this_day.is_empty() && c.this_month.is_empty() {
+        return html! {};
+    }
+
+    let section = |title: &str, list: &[CalendarEntry]| {
+        if list.is_empty() {
+            return html! {};
+        }
+        html! {
+            <div class="form-section">
+                <h3>{ title.to_string() }</h3>
+                <ul class="note-list">
+                    { for list.iter().map(|e| {
+                        let on_select = props.on_select.clone();
+                        let filename = e.filename.clone();
+                        html! {
+                            <li class="note-item">
+                                <a class="note-text" href="#" title={e.filename.clone()}
+                                    onclick={move |ev: MouseEvent| { ev.prevent_default(); on_select.emit(filename.clone()); }}>
+                                    { e.display_label.clone() }
+                                </a>
+                                <span class="note-time">{ describe(e) }</span>
+                            </li>
+                        }
+                    }) }
+                </ul>
+            </div>
+        }
+    };
+
+    html! {
+        <div class="today-calendar">
+            <h2 class="view-title">{ format!("今日のカレンダー（{}）", c.date) }</h2>
+            { section("周年", &c.anniversaries) }
+            { section("過去の今日", &c.on_this_day) }
+            { section("過去の今月", &c.this_month) }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod calendar_tests {
+    use super::describe;
+    use crate::api::{CalendarEntry, CalendarKind};
+
+    #[test]
+    fn entries_describe_their_reason() {
+        let e = |kind, when: &str, years| CalendarEntry {
+            kind,
+            filename: String::new(),
+            display_label: String::new(),
+            when: when.into(),
+            years,
+        };
+        assert_eq!(describe(&e(CalendarKind::Released, "1966", 60)), "発売 60 周年（1966）");
+        assert_eq!(describe(&e(CalendarKind::Listened, "2020/10/16", 6)), "6 年前に聴いた（2020/10/16）");
+    }
+}
@@ -0,0 +1,40 @@
+use crate::api::ListEntryWithLabel;
+use crate::label::LabelFields;
+
+/// サイドバーに出ているレコードを Markdown のチェックリスト（"- [ ] アーティスト — タイトル (年)"）にする。買い物・聴き直しのメモ用。
+pub fn checklist_markdown(entries: &[&ListEntryWithLabel]) -> String {
+    // 人名が 1 つもないときの "[Artist Unknown]" は出さない
+    let unknown = LabelFields::default().artist();
+    let mut out = String::new();
+    for e in entries {
+        let title = e.fields.title.trim();
+        let title = if title.is_empty() { e.filename.trim_end_matches(".json") } else { title };
+        let artist = e.fields.artist();
+        let mut line = if artist.trim().is_empty() || artist == unknown { title.to_string() } else { format!("{} — {}", artist.trim(), title) };
+        if let Some(year) = e.release_year.filter(|y| *y > 0) {
+            line.push_str(&format!(" ({})", year));
+        }
+        out.push_str("- [ ] ");
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod checklist_tests {
+    use super::checklist_markdown;
+    use crate::api::ListEntryWithLabel;
+
+    fn entry(v: serde_json::Value) -> ListEntryWithLabel {
+        serde_json::from_value(v).unwrap()
+    }
+
+    #[test]
+    fn artist_title_and_year() {
+        let a = entry(serde_json::json!({"filename": "a.json", "display_label": "", "title": "Kind of Blue",
+            "genre": "Jazz", "leaders": ["Miles Davis"], "release_year": 1959}));
+        let b = entry(serde_json::json!({"filename": "Untitled.json", "display_label": "", "release_year": 0}));
+        assert_eq!(checklist_markdown(&[&a, &b]), "- [ ] Miles Davis — Kind of Blue (1959)\n- [ ] Untitled\n");
+    }
+}
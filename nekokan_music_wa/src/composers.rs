@@ -0,0 +1,196 @@
+use crate::api::{self, Composer, NameRegistry, Person};
+use yew::prelude::*;
+
+/// `alias` を `name` の別名にする。ほかの人に付いていた同じ別名は外す（大文字小文字は区別しない）。
+/// `name` が台帳になければ追加する。別名と正規名が同じなら何もしない。
+pub fn add_alias(reg: &NameRegistry, alias: &str, name: &str) -> NameRegistry {
+    let (alias, name) = (alias.trim(), name.trim());
+    let mut reg = reg.clone();
+    if alias.is_empty() || name.is_empty() || alias.eq_ignore_ascii_case(name) {
+        return reg;
+    }
+    for p in &mut reg.people {
+        p.aliases.retain(|a| !a.eq_ignore_ascii_case(alias));
+    }
+    match reg.people.iter_mut().find(|p| p.name.eq_ignore_ascii_case(name)) {
+        Some(p) => p.aliases.push(alias.to_string()),
+        None => reg.people.push(Person { name: name.to_string(), aliases: vec![alias.to_string()] }),
+    }
+    reg
+}
+
+/// 別名を外す（別名のなくなった人は台帳から消す）
+fn remove_alias(reg: &NameRegistry, name: &str, alias: &str) -> NameRegistry {
+    let mut reg = reg.clone();
+    for p in &mut reg.people {
+        if p.name == name {
+            p.aliases.retain(|a| a != alias);
+        }
+    }
+    reg.people.retain(|p| !p.aliases.is_empty());
+    reg
+}
+
+/// 作曲者の一覧と別名の台帳。"T. Monk" を "Thelonious Monk" の別名にすると、次の保存から正規名で書かれ、
+/// 曲の索引やこの一覧でも同じ人にまとまる。
+#[function_component(ComposersView)]
+pub fn composers_view() -> Html {
+    let composers = use_state(|| None::<Result<Vec<Composer>, String>>);
+    let names = use_state(NameRegistry::default);
+    let alias = use_state(String::new);
+    let target = use_state(String::new);
+    let status = use_state(|| None::<Result<(), String>>);
+
+    let reload = {
+        let composers = composers.clone();
+        let names = names.clone();
+        let status = status.clone();
+        Callback::from(move |()| {
+            let (composers, names, status) = (composers.clone(), names.clone(), status.clone());
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::get_names().await {
+                    Ok(n) => names.set(n),
+                    Err(e) => status.set(Some(Err(e))),
+                }
+                composers.set(Some(api::composers().await));
+            });
+        })
+    };
+    {
+        let reload = reload.clone();
+        use_effect_with((), move |_| {
+            reload.emit(());
+            || ()
+        });
+    }
+
+    let save = {
+        let names = names.clone();
+        let status = status.clone();
+        let reload = reload.clone();
+        move |next: NameRegistry| {
+            let (names, status, reload) = (names.clone(), status.clone(), reload.clone());
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::save_names(&next).await {
+                    Ok(saved) => {
+                        names.set(saved);
+                        status.set(Some(Ok(())));
+                        reload.emit(());
+                    }
+                    Err(e) => status.set(Some(Err(e))),
+                }
+            });
+        }
+    };
+
+    let on_add = {
+        let (names, alias, target, save) = (names.clone(), alias.clone(), target.clone(), save.clone());
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let next = add_alias(&names, &alias, &target);
+            if next != *names {
+                alias.set(String::new());
+                save(next);
+            }
+        })
+    };
+    let input = |state: &UseStateHandle<String>| {
+        let state = state.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                state.set(inp.value());
+            }
+        })
+    };
+    let remove = |name: &str, a: &str| {
+        let (names, save) = (names.clone(), save.clone());
+        let (name, a) = (name.to_string(), a.to_string());
+        Callback::from(move |_: MouseEvent| save(remove_alias(&names, &name, &a)))
+    };
+    // 作曲者名をクリックすると別名の欄に入れる（別の人にまとめるとき）
+    let pick = |name: &str| {
+        let alias = alias.clone();
+        let name = name.to_string();
+        Callback::from(move |e: MouseEvent| {
+            e.prevent_default();
+            alias.set(name.clone());
+        })
+    };
+
+    html! {
+        <div class="composers-view">
+            <h2 class="view-title">{"作曲者"}</h2>
+            <p class="hint">{"保存時に作曲者の表記を揃えます（前後の空白、大文字だけ・小文字だけの表記）。別名を登録すると正規名に置き換えます。台帳はアーティスト名と共通です。"}</p>
+            <form class="note-form" onsubmit={on_add}>
+                <input type="text" class="input" aria-label="別名" placeholder="T. Monk" value={(*alias).clone()} oninput={input(&alias)}/>
+                <span aria-hidden="true">{"→"}</span>
+                <input type="text" class="input" aria-label="正規名" placeholder="Thelonious Monk" list="composer-names"
+                    value={(*target).clone()} oninput={input(&target)}/>
+                <button type="submit" class="btn-add">{"別名を登録"}</button>
+            </form>
+            <div role="status" aria-live="polite">
+                { match &*status {
+                    None => html! {},
+                    Some(Ok(())) => html! { <p class="save-ok">{"台帳を保存しました。既存のレコードは保存し直したときに正規名になります。"}</p> },
+                    Some(Err(e)) => html! { <p class="save-err">{ e.clone() }</p> },
+                } }
+            </div>
+            if !names.people.is_empty() {
+                <div class="form-section">
+                    <h3>{"別名の台帳"}</h3>
+                    <ul class="note-list">
+                        { for names.people.iter().flat_map(|p| p.aliases.iter().map(move |a| (p, a))).map(|(p, a)| html! {
+                            <li class="note-item" key={format!("{}\u{0}{}", p.name, a)}>
+                                <span class="note-text">{ format!("{} → {}", a, p.name) }</span>
+                                <button type="button" class="btn-remove" aria-label={format!("別名「{}」を外す", a)} onclick={remove(&p.name, a)}>{"外す"}</button>
+                            </li>
+                        }) }
+                    </ul>
+                </div>
+            }
+            { match &*composers {
+                None => html! { <p class="sidebar-loading">{"読み込み中..."}</p> },
+                Some(Err(e)) => html! { <p class="load-err">{"ロードエラー: "}{ e.clone() }</p> },
+                Some(Ok(list)) => html! {
+                    <>
+                        <datalist id="composer-names">
+                            { for list.iter().map(|c| html! { <option value={c.name.clone()} /> }) }
+                        </datalist>
+                        <table class="cover-table">
+                            <thead>
+                                <tr><th>{"作曲者"}</th><th>{"トラック"}</th><th>{"レコード"}</th><th>{"別の表記"}</th></tr>
+                            </thead>
+                            <tbody>
+                                { for list.iter().map(|c| html! {
+                                    <tr key={c.name.clone()}>
+                                        <td class="cover-name">
+                                            <a href="#" title="別名の欄に入れる" onclick={pick(&c.name)}>{ c.name.clone() }</a>
+                                        </td>
+                                        <td>{ c.tracks }</td>
+                                        <td>{ c.records }</td>
+                                        <td>{ c.spellings.join(" / ") }</td>
+                                    </tr>
+                                }) }
+                            </tbody>
+                        </table>
+                    </>
+                },
+            } }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod composers_tests {
+    use super::add_alias;
+    use crate::api::{NameRegistry, Person};
+
+    #[test]
+    fn alias_moves_to_the_new_person() {
+        let reg = NameRegistry { people: vec![Person { name: "Monk Montgomery".into(), aliases: vec!["Monk".into()] }] };
+        let reg = add_alias(&reg, "monk", "Thelonious Monk");
+        assert!(reg.people[0].aliases.is_empty());
+        assert_eq!(reg.people[1], Person { name: "Thelonious Monk".into(), aliases: vec!["monk".into()] });
+        assert_eq!(add_alias(&reg, "Thelonious Monk", "thelonious monk"), reg);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::api::{self, ListEntryWithLabel};
+use crate::fuzzy::{match_score, normalize};
+use yew::prelude::*;
+
+/// 取り込み対象にする画像の拡張子
+const IMAGE_EXTS: [&str; 5] = ["jpg", "jpeg", "png", "webp", "gif"];
+
+/// あいまい照合で採用する最低スコア（全単語を含む以上）
+const MIN_FUZZY_SCORE: u32 = 2000;
+
+fn split_ext(name: &str) -> Option<(&str, &str)> {
+    let (stem, ext) = name.rsplit_once('.')?;
+    IMAGE_EXTS
+        .iter()
+        .any(|e| e.eq_ignore_ascii_case(ext))
+        .then_some((stem, ext))
+}
+
+/// 画像ファイル名に合うレコードを探す。
+/// JSON と同じ名前（"Artist__Title.jpg"）なら確定、"{artist} - {title}.jpg" などは表示ラベルとあいまい照合する。
+pub fn match_cover<'a>(image_name: &str, entries: &'a [ListEntryWithLabel]) -> Option<&'a ListEntryWithLabel> {
+    let (stem, _) = split_ext(image_name)?;
+    if let Some(e) = entries
+        .iter()
+        .find(|e| e.filename.trim_end_matches(".json").eq_ignore_ascii_case(stem))
+    {
+        return Some(e);
+    }
+    let query = normalize(&stem.replace("__", " "));
+    entries
+        .iter()
+        .filter_map(|e| match_score(&query, &e.display_label).map(|s| (s, e)))
+        .filter(|(s, _)| *s >= MIN_FUZZY_SCORE)
+        .max_by_key(|(s, _)| *s)
+        .map(|(_, e)| e)
+}
+
+#[derive(Clone)]
+struct CoverRow {
+    file: web_sys::File,
+    name: String,
+    /// 割り当て先のレコード（"xxx.json"）。None なら取り込まない。
+    target: Option<String>,
+    status: Option<Result<(), String>>,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct CoverImportProps {
+    pub entries: Vec<ListEntryWithLabel>,
+    /// 取り込み後にファイル一覧を読み直す
+    pub on_imported: Callback<()>,
+}
+
+/// フォルダ内の画像をファイル名でレコードに割り当て、カバーとして一括登録するメンテナンス画面。
+#[function_component(CoverImport)]
+pub fn cover_import(props: &CoverImportProps) -> Html {
+    let rows = use_state(Vec::<CoverRow>::new);
+    let busy = use_state(|| false);
+
+    let on_folder = {
+        let rows = rows.clone();
+        let entries = props.entries.clone();
+        Callback::from(move |e: Event| {
+            let Some(files) = e
+                .target_dyn_into::<web_sys::HtmlInputElement>()
+                .and_then(|i| i.files())
+            else {
+                return;
+            };
+            let mut new_rows: Vec<CoverRow> = (0..files.length())
+                .filter_map(|i| files.get(i))
+                .filter(|f| split_ext(&f.name()).is_some())
+                .map(|file| {
+                    let name = file.name();
+                    let target = match_cover(&name, &entries).map(|e| e.filename.clone());
+                    CoverRow { file, name, target, status: None }
+                })
+                .collect();
+            new_rows.sort_by(|a, b| a.name.cmp(&b.name));
+            rows.set(new_rows);
+        })
+    };
+
+    let set_target = |idx: usize| {
+        let rows = rows.clone();
+        Callback::from(move |e: Event| {
+            if let Some(sel) = e.target_dyn_into::<web_sys::HtmlSelectElement>() {
+                let mut r = (*rows).clone();
+                if let Some(row) = r.get_mut(idx) {
+                    let v = sel.value();
+                    row.target = (!v.is_empty()).then_some(v);
+                    row.status = None;
+                }
+                rows.set(r);
+            }
+        })
+    };
+
+    let on_import = {
+        let rows = rows.clone();
+        let busy = busy.clone();
+        let on_imported = props.on_imported.clone();
+        Callback::from(move |_: MouseEvent| {
+            let rows = rows.clone();
+            let busy = busy.clone();
+            let on_imported = on_imported.clone();
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                let mut current = (*rows).clone();
+                for i in 0..current.len() {
+                    let Some(target) = current[i].target.clone() else {
+                        continue;
+                    };
+                    if current[i].status == Some(Ok(())) {
+                        continue;
+                    }
+                    let res = api::upload_cover(&target, &current[i].file).await.map(|_| ());
+                    current[i].status = Some(res);
+                    rows.set(current.clone());
+                }
+                busy.set(false);
+                on_imported.emit(());
+            });
+        })
+    };
+
+    let mut options: Vec<&ListEntryWithLabel> = props.entries.iter().collect();
+    options.sort_by(|a, b| a.display_label.cmp(&b.display_label));
+    let assigned = rows.iter().filter(|r| r.target.is_some()).count();
+
+    html! {
+        <div class="cover-import">
+            <h2 class="view-title">{"カバー一括取り込み"}</h2>
+            <div class="form-section">
+                <p class="hint">{"JSON と同じ名前（Artist__Title.jpg）か「Artist - Title.jpg」の画像が入ったフォルダを選ぶと、レコードとの対応案を表示します。"}</p>
+                <input type="file" webkitdirectory={true} multiple={true} accept="image/*"
+                    aria-label="カバー画像のフォルダ" onchange={on_folder} disabled={*busy}/>
+            </div>
+            if !rows.is_empty() {
+                <div class="form-section">
+                    <h3>{ format!("{} 件中 {} 件を割り当て", rows.len(), assigned) }</h3>
+                    <table class="cover-table">
+                        <thead>
+                            <tr><th>{"画像"}</th><th>{"レコード"}</th><th>{"結果"}</th></tr>
+                        </thead>
+                        <tbody>
+                            { for rows.iter().enumerate().map(|(i, row)| html! {
+                                <tr key={row.name.clone()}>
+                                    <td class="cover-name">{ row.name.clone() }</td>
+                                    <td>
+                                        <select class="input" aria-label={format!("{} の割り当て先", row.name)}
+                                            onchange={set_target(i)} disabled={*busy}>
+                                            <option value="" selected={row.target.is_none()}>{"（取り込まない）"}</option>
+                                            { for options.iter().map(|e| html! {
+                                                <option value={e.filename.clone()} selected={row.target.as_deref() == Some(e.filename.as_str())}>
+                                                    { e.display_label.clone() }
+                                                </option>
+                                            }) }
+                                        </select>
+                                    </td>
+                                    <td>
+                                        { match &row.status {
+                                            None => html! {},
+                                            Some(Ok(())) => html! { <span class="save-ok">{"登録済み"}</span> },
+                                            Some(Err(e)) => html! { <span class="save-err">{ e.clone() }</span> },
+                                        } }
+                                    </td>
+                                </tr>
+                            }) }
+                        </tbody>
+                    </table>
+                    <button type="button" class="btn-save" onclick={on_import} disabled={*busy || assigned == 0}>
+                        { if *busy { "取り込み中..." } else { "カバーとして登録" } }
+                    </button>
+                </div>
+            }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod covers_tests {
+    use super::match_cover;
+    use crate::api::ListEntryWithLabel;
+
+    fn entry(filename: &str, label: &str) -> ListEntryWithLabel {
+        ListEntryWithLabel {
+            filename: filename.into(),
+            display_label: label.into(),
+            completeness: 0,
+            badge: String::new(),
+            date: String::new(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn matches_json_name_and_artist_title() {
+        let entries = vec![
+            entry("Bill_Evans__Alone.json", "Bill Evans: Alone"),
+            entry("Art_Blakey__Moanin.json", "Art Blakey: Moanin'"),
+        ];
+        assert_eq!(match_cover("Bill_Evans__Alone.jpg", &entries).unwrap().filename, "Bill_Evans__Alone.json");
+        assert_eq!(match_cover("Art Blakey - Moanin.PNG", &entries).unwrap().filename, "Art_Blakey__Moanin.json");
+        assert!(match_cover("Coltrane - Ballads.jpg", &entries).is_none());
+        assert!(match_cover("Bill_Evans__Alone.txt", &entries).is_none());
+    }
+}
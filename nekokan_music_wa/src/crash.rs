@@ -0,0 +1,177 @@
+//! パニック時の表示と報告。
+//! WASM ではパニックするとアプリ全体が止まり画面が真っ白になるため、パニックフックで
+//! 直前の操作・レコード名を含む診断情報をパネルに出し、設定で許可されていればサーバーにも送る。
+//! Yew にはエラーバウンダリがないので、ビューごとではなくアプリ全体で受ける。
+
+use std::cell::RefCell;
+use std::panic;
+use wasm_bindgen::JsValue;
+
+/// パニック時に診断情報に載せる直前の状態
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Diagnostics {
+    /// 直前の操作（"open" / "new" / "save" など）
+    pub last_action: String,
+    /// 編集中のレコード（"xxx.json"、新規なら空）
+    pub record: String,
+    /// 表示中のビュー
+    pub view: String,
+    /// 診断情報をサーバーへ送るか（設定の report_client_errors）
+    pub report: bool,
+}
+
+thread_local! {
+    static DIAG: RefCell<Diagnostics> = RefCell::new(Diagnostics {
+        view: "editor".into(),
+        ..Default::default()
+    });
+}
+
+/// 操作を記録する。`record` が None なら編集中のレコードなし（新規）とする。
+pub fn record_action(action: &str, record: Option<&str>) {
+    DIAG.with(|d| {
+        let mut d = d.borrow_mut();
+        d.last_action = action.to_string();
+        d.record = record.unwrap_or_default().to_string();
+    });
+}
+
+pub fn set_view(view: &str) {
+    DIAG.with(|d| d.borrow_mut().view = view.to_string());
+}
+
+pub fn set_reporting(on: bool) {
+    DIAG.with(|d| d.borrow_mut().report = on);
+}
+
+/// サーバーに送る報告（サーバーの `client_errors::ClientError` と同じ形）
+pub fn report_body(message: &str, location: &str, diag: &Diagnostics) -> serde_json::Value {
+    serde_json::json!({
+        "message": message,
+        "location": location,
+        "last_action": diag.last_action,
+        "record": diag.record,
+        "view": diag.view,
+        "version": env!("CARGO_PKG_VERSION"),
+    })
+}
+
+/// 「診断情報をコピー」でコピーするテキスト
+pub fn diagnostic_text(message: &str, location: &str, diag: &Diagnostics) -> String {
+    let or_none = |s: &str| if s.is_empty() { "(なし)".to_string() } else { s.to_string() };
+    format!(
+        "{}\nエラー: {}\n場所: {}\n直前の操作: {}\nレコード: {}\nビュー: {}\nUser-Agent: {}\n",
+        crate::APP_TITLE_WITH_VERSION,
+        message,
+        or_none(location),
+        or_none(&diag.last_action),
+        or_none(&diag.record),
+        or_none(&diag.view),
+        web_sys::window()
+            .and_then(|w| w.navigator().user_agent().ok())
+            .unwrap_or_default(),
+    )
+}
+
+fn element(doc: &web_sys::Document, tag: &str, class: &str, text: &str) -> Option<web_sys::Element> {
+    let el = doc.create_element(tag).ok()?;
+    el.set_class_name(class);
+    if !text.is_empty() {
+        el.set_text_content(Some(text));
+    }
+    Some(el)
+}
+
+/// エラーパネルを body に直接足す（Yew はもう動かない）。
+/// パニック後は WASM を呼ばないよう、ボタンの動作はインラインの JS で書く。
+fn show_panel(text: &str) -> Option<()> {
+    let doc = web_sys::window()?.document()?;
+    if doc.get_element_by_id("crash-panel").is_some() {
+        return Some(());
+    }
+    let panel = element(&doc, "div", "crash-panel", "")?;
+    panel.set_id("crash-panel");
+    panel.set_attribute("role", "alertdialog").ok()?;
+    panel.set_attribute("aria-labelledby", "crash-title").ok()?;
+    let title = element(&doc, "h2", "crash-title", "予期しないエラーが発生しました")?;
+    title.set_id("crash-title");
+    let hint = element(
+        &doc,
+        "p",
+        "hint",
+        "保存していない編集内容は失われています。診断情報をコピーしてから再読み込みしてください。",
+    )?;
+    let diag = element(&doc, "textarea", "input crash-diag", text)?;
+    diag.set_id("crash-diag");
+    diag.set_attribute("readonly", "").ok()?;
+    diag.set_attribute("aria-label", "診断情報").ok()?;
+    let copy = element(&doc, "button", "btn-add", "診断情報をコピー")?;
+    copy.set_attribute("type", "button").ok()?;
+    copy.set_attribute(
+        "onclick",
+        "var t=document.getElementById('crash-diag');t.select();\
+         (navigator.clipboard?navigator.clipboard.writeText(t.value):Promise.reject()).catch(function(){document.execCommand('copy');});",
+    )
+    .ok()?;
+    let reload = element(&doc, "button", "btn-save", "再読み込み")?;
+    reload.set_attribute("type", "button").ok()?;
+    reload.set_attribute("onclick", "location.reload()").ok()?;
+    for child in [&title, &hint, &diag, &copy, &reload] {
+        panel.append_child(child).ok()?;
+    }
+    doc.body()?.append_child(&panel).ok()?;
+    Some(())
+}
+
+fn panic_message(info: &panic::PanicHookInfo) -> String {
+    if let Some(s) = info.payload().downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = info.payload().downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "panic".to_string()
+    }
+}
+
+/// パニックフックを設定する（起動時に 1 回）。
+pub fn install() {
+    panic::set_hook(Box::new(|info| {
+        // スタックトレースは従来どおりコンソールへ
+        console_error_panic_hook::hook(info);
+        let message = panic_message(info);
+        let location = info
+            .location()
+            .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+            .unwrap_or_default();
+        let diag = DIAG.with(|d| d.borrow().clone());
+        let body = report_body(&message, &location, &diag).to_string();
+        web_sys::console::error_2(&JsValue::from_str("[nekokan_music_wa] crash:"), &JsValue::from_str(&body));
+        if diag.report {
+            // パニック後は非同期処理が動かないので、ページを離れても届く sendBeacon で送る
+            if let Some(w) = web_sys::window() {
+                let _ = w.navigator().send_beacon_with_opt_str("/api/client-errors", Some(&body));
+            }
+        }
+        show_panel(&diagnostic_text(&message, &location, &diag));
+    }));
+}
+
+#[cfg(test)]
+mod crash_tests {
+    use super::{report_body, Diagnostics};
+
+    #[test]
+    fn report_carries_last_action_and_record() {
+        let diag = Diagnostics {
+            last_action: "save".into(),
+            record: "Bill_Evans__Alone.json".into(),
+            view: "editor".into(),
+            report: true,
+        };
+        let body = report_body("index out of bounds", "src/form.rs:10:5", &diag);
+        assert_eq!(body["last_action"], "save");
+        assert_eq!(body["record"], "Bill_Evans__Alone.json");
+        assert_eq!(body["location"], "src/form.rs:10:5");
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+    }
+}
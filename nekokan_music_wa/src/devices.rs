@@ -0,0 +1,183 @@
+use crate::api::{self, CreatedToken, TokenList};
+use yew::prelude::*;
+
+/// 設定画面の「端末ごとのトークン」。スマホ・ノート PC・スクリプトごとにトークンを作り、1 つずつ取り消せる。
+/// トークンが 1 つでもあると、書き込みにはトークンでのログイン（スクリプトは Authorization: Bearer）が要る。
+#[function_component(DevicesSection)]
+pub fn devices_section() -> Html {
+    let list = use_state(|| None::<Result<TokenList, String>>);
+    let name = use_state(String::new);
+    let use_here = use_state(|| true);
+    let login_token = use_state(String::new);
+    let created = use_state(|| None::<CreatedToken>);
+    let status = use_state(|| None::<Result<String, String>>);
+
+    let reload = {
+        let list = list.clone();
+        Callback::from(move |()| {
+            let list = list.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                list.set(Some(api::list_tokens().await));
+            });
+        })
+    };
+    {
+        let reload = reload.clone();
+        use_effect_with((), move |_| {
+            reload.emit(());
+            || ()
+        });
+    }
+
+    let input = |state: &UseStateHandle<String>| {
+        let state = state.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                state.set(inp.value());
+            }
+        })
+    };
+
+    let on_create = {
+        let (name, use_here, created, status, reload) =
+            (name.clone(), use_here.clone(), created.clone(), status.clone(), reload.clone());
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let (label, here) = (name.trim().to_string(), *use_here);
+            if label.is_empty() {
+                return;
+            }
+            let (name, created, status, reload) = (name.clone(), created.clone(), status.clone(), reload.clone());
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::create_token(&label, here).await {
+                    Ok(c) => {
+                        name.set(String::new());
+                        created.set(Some(c));
+                        status.set(None);
+                        reload.emit(());
+                    }
+                    Err(e) => status.set(Some(Err(e))),
+                }
+            });
+        })
+    };
+
+    let on_login = {
+        let (login_token, status, reload) = (login_token.clone(), status.clone(), reload.clone());
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let token = login_token.trim().to_string();
+            if token.is_empty() {
+                return;
+            }
+            let (login_token, status, reload) = (login_token.clone(), status.clone(), reload.clone());
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::login(&token).await {
+                    Ok(n) => {
+                        login_token.set(String::new());
+                        status.set(Some(Ok(format!("「{}」でログインしました。", n))));
+                        reload.emit(());
+                    }
+                    Err(e) => status.set(Some(Err(e))),
+                }
+            });
+        })
+    };
+
+    let on_logout = {
+        let (status, reload) = (status.clone(), reload.clone());
+        Callback::from(move |_: MouseEvent| {
+            let (status, reload) = (status.clone(), reload.clone());
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::logout().await {
+                    Ok(()) => {
+                        status.set(Some(Ok("この端末をログアウトしました。".into())));
+                        reload.emit(());
+                    }
+                    Err(e) => status.set(Some(Err(e))),
+                }
+            });
+        })
+    };
+
+    let revoke = |id: &str, label: &str| {
+        let (status, reload) = (status.clone(), reload.clone());
+        let (id, label) = (id.to_string(), label.to_string());
+        Callback::from(move |_: MouseEvent| {
+            if !gloo_utils::window()
+                .confirm_with_message(&format!("トークン「{}」を取り消しますか？この端末・スクリプトからは書き込めなくなります。", label))
+                .unwrap_or(false)
+            {
+                return;
+            }
+            let (id, status, reload) = (id.clone(), status.clone(), reload.clone());
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::revoke_token(&id).await {
+                    Ok(()) => reload.emit(()),
+                    Err(e) => status.set(Some(Err(e))),
+                }
+            });
+        })
+    };
+
+    let on_use_here_toggle = {
+        let use_here = use_here.clone();
+        Callback::from(move |_: Event| use_here.set(!*use_here))
+    };
+
+    html! {
+        <div class="form-section">
+            <h3>{"端末ごとのトークン"}</h3>
+            <p class="hint">{"トークンが 1 つもなければ誰でも書き込めます。作ると、書き込みにはトークンでのログインが要ります（読み取りはそのまま）。スクリプトからは Authorization: Bearer <トークン> を付けてください。変更は db/.config/audit.jsonl にトークン名付きで記録されます。"}</p>
+            { match &*list {
+                None => html! { <p class="sidebar-loading">{"読み込み中..."}</p> },
+                Some(Err(e)) => html! { <p class="load-err">{ e.clone() }</p> },
+                Some(Ok(l)) => html! {
+                    <ul class="note-list">
+                        { for l.tokens.iter().map(|t| html! {
+                            <li class="note-item" key={t.id.clone()}>
+                                <span class="note-text">
+                                    { t.name.clone() }
+                                    if t.current { <span class="note-time">{" （この端末）"}</span> }
+                                </span>
+                                <span class="note-time">
+                                    { format!("作成 {} / 最終使用 {}", t.created.chars().take(10).collect::<String>(), t.last_used.as_deref().unwrap_or("なし")) }
+                                </span>
+                                <button type="button" class="btn-remove" aria-label={format!("トークン「{}」を取り消す", t.name)}
+                                    onclick={revoke(&t.id, &t.name)}>{"取り消す"}</button>
+                            </li>
+                        }) }
+                    </ul>
+                },
+            } }
+            <form class="note-form" onsubmit={on_create}>
+                <input type="text" class="input" aria-label="トークンの名前" placeholder="phone / laptop / import script"
+                    value={(*name).clone()} oninput={input(&name)}/>
+                <label class="sidebar-filter">
+                    <input type="checkbox" checked={*use_here} onchange={on_use_here_toggle}/>
+                    {"この端末で使う"}
+                </label>
+                <button type="submit" class="btn-add">{"トークンを作る"}</button>
+            </form>
+            if let Some(c) = &*created {
+                <p class="save-ok">
+                    { format!("「{}」のトークン（この画面でしか表示しません）: ", c.token.name) }
+                    <code class="token-secret">{ c.secret.clone() }</code>
+                </p>
+            }
+            <form class="note-form" onsubmit={on_login}>
+                <input type="password" class="input" aria-label="トークン" placeholder="nkm_..." autocomplete="off"
+                    value={(*login_token).clone()} oninput={input(&login_token)}/>
+                <button type="submit" class="btn-add">{"この端末でログイン"}</button>
+                <button type="button" class="btn-remove" onclick={on_logout}>{"ログアウト"}</button>
+            </form>
+            <div role="status" aria-live="polite">
+                { match &*status {
+                    None => html! {},
+                    Some(Ok(m)) => html! { <p class="save-ok">{ m.clone() }</p> },
+                    Some(Err(e)) => html! { <p class="save-err">{ e.clone() }</p> },
+                } }
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,205 @@
+use crate::api::{self, DigestReport, DigestStatus};
+use yew::prelude::*;
+
+/// 曜日の選択肢（値はサーバーの `weekday`）
+const WEEKDAYS: [(u8, &str); 8] = [
+    (0, "自動では送らない"),
+    (1, "毎週月曜"),
+    (2, "毎週火曜"),
+    (3, "毎週水曜"),
+    (4, "毎週木曜"),
+    (5, "毎週金曜"),
+    (6, "毎週土曜"),
+    (7, "毎週日曜"),
+];
+
+/// 宛先欄（カンマ・空白・改行区切り）→ アドレスの一覧
+pub fn recipients(s: &str) -> Vec<String> {
+    s.split(|c: char| c == ',' || c.is_whitespace()).filter(|a| !a.is_empty()).map(str::to_string).collect()
+}
+
+/// 設定画面の「週報」。直近 7 日間の登録・再生・スコアの高いものを、決めた曜日に Webhook かメールで送る。
+#[function_component(DigestSection)]
+pub fn digest_section() -> Html {
+    let status = use_state(|| None::<Result<DigestStatus, String>>);
+    let webhook = use_state(String::new);
+    let smtp_host = use_state(String::new);
+    let mail_from = use_state(String::new);
+    let mail_to = use_state(String::new);
+    let weekday = use_state(|| 0u8);
+    let report = use_state(|| None::<Result<DigestReport, String>>);
+    let preview = use_state(|| None::<Result<String, String>>);
+    let busy = use_state(|| false);
+
+    {
+        let (status, smtp_host, mail_from, mail_to, weekday, report) =
+            (status.clone(), smtp_host.clone(), mail_from.clone(), mail_to.clone(), weekday.clone(), report.clone());
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                let res = api::digest_status().await;
+                if let Ok(s) = &res {
+                    smtp_host.set(s.config.smtp_host.clone());
+                    mail_from.set(s.config.mail_from.clone());
+                    mail_to.set(s.config.mail_to.join(", "));
+                    weekday.set(s.config.weekday);
+                    report.set(s.last_report.clone().map(Ok));
+                }
+                status.set(Some(res));
+            });
+            || ()
+        });
+    }
+
+    let input = |state: &UseStateHandle<String>| {
+        let state = state.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                state.set(inp.value());
+            }
+        })
+    };
+    let on_weekday = {
+        let weekday = weekday.clone();
+        Callback::from(move |e: Event| {
+            if let Some(sel) = e.target_dyn_into::<web_sys::HtmlSelectElement>() {
+                weekday.set(sel.value().parse().unwrap_or(0));
+            }
+        })
+    };
+
+    let on_save = {
+        let (webhook, smtp_host, mail_from, mail_to, weekday, status, report) = (
+            webhook.clone(),
+            smtp_host.clone(),
+            mail_from.clone(),
+            mail_to.clone(),
+            weekday.clone(),
+            status.clone(),
+            report.clone(),
+        );
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let (w, host, from, to, day) =
+                ((*webhook).clone(), (*smtp_host).clone(), (*mail_from).clone(), recipients(&mail_to), *weekday);
+            let (webhook, status, report) = (webhook.clone(), status.clone(), report.clone());
+            wasm_bindgen_futures::spawn_local(async move {
+                // Webhook 欄が空なら今の URL のまま
+                let w = (!w.trim().is_empty()).then_some(w);
+                match api::save_digest_config(w.as_deref(), &host, &from, &to, day).await {
+                    Ok(config) => {
+                        webhook.set(String::new());
+                        let prev = status.as_ref().and_then(|s| s.as_ref().ok()).cloned().unwrap_or_default();
+                        status.set(Some(Ok(DigestStatus { config, ..prev })));
+                    }
+                    Err(e) => report.set(Some(Err(e))),
+                }
+            });
+        })
+    };
+    let on_clear_webhook = {
+        let (smtp_host, mail_from, mail_to, weekday, status, report) =
+            (smtp_host.clone(), mail_from.clone(), mail_to.clone(), weekday.clone(), status.clone(), report.clone());
+        Callback::from(move |_: MouseEvent| {
+            let (host, from, to, day) = ((*smtp_host).clone(), (*mail_from).clone(), recipients(&mail_to), *weekday);
+            let (status, report) = (status.clone(), report.clone());
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::save_digest_config(Some(""), &host, &from, &to, day).await {
+                    Ok(config) => {
+                        let prev = status.as_ref().and_then(|s| s.as_ref().ok()).cloned().unwrap_or_default();
+                        status.set(Some(Ok(DigestStatus { config, ..prev })));
+                    }
+                    Err(e) => report.set(Some(Err(e))),
+                }
+            });
+        })
+    };
+
+    let on_preview = {
+        let preview = preview.clone();
+        Callback::from(move |_: MouseEvent| {
+            let preview = preview.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                preview.set(Some(api::digest_preview().await));
+            });
+        })
+    };
+    let on_send = {
+        let (report, busy) = (report.clone(), busy.clone());
+        Callback::from(move |_: MouseEvent| {
+            let (report, busy) = (report.clone(), busy.clone());
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                report.set(Some(api::send_digest().await));
+                busy.set(false);
+            });
+        })
+    };
+
+    let config = status.as_ref().and_then(|s| s.as_ref().ok()).map(|s| s.config.clone());
+    let has_webhook = config.as_ref().is_some_and(|c| c.has_webhook);
+    let configured = config.as_ref().is_some_and(|c| c.has_webhook || !c.smtp_host.is_empty());
+
+    html! {
+        <div class="form-section">
+            <h3>{"週報"}</h3>
+            <p class="hint">{"前日までの 7 日間に登録したレコード・聴いた回数・スコアの高いものをまとめて送ります。Webhook は Slack・Mattermost 形式（{\"text\": ...}）、メールは TLS・認証なしで同じマシンか LAN のメールサーバーに渡します。"}</p>
+            if let Some(Err(e)) = &*status {
+                <p class="load-err">{ e.clone() }</p>
+            }
+            <form class="note-form digest-form" onsubmit={on_save}>
+                <input type="password" class="input" aria-label="Webhook の URL" autocomplete="off"
+                    placeholder={if has_webhook { "（設定済み。変えるときだけ入力）" } else { "Webhook の URL（任意）" }}
+                    value={(*webhook).clone()} oninput={input(&webhook)}/>
+                if has_webhook {
+                    <button type="button" class="btn-remove" onclick={on_clear_webhook}>{"Webhook を外す"}</button>
+                }
+                <input type="text" class="input" aria-label="SMTP サーバー" placeholder="SMTP サーバー（localhost:25、任意）"
+                    value={(*smtp_host).clone()} oninput={input(&smtp_host)}/>
+                <input type="email" class="input" aria-label="差出人" placeholder="差出人（music@example.com）"
+                    value={(*mail_from).clone()} oninput={input(&mail_from)}/>
+                <input type="text" class="input" aria-label="宛先" placeholder="宛先（カンマ区切り）"
+                    value={(*mail_to).clone()} oninput={input(&mail_to)}/>
+                <select class="sidebar-sort" aria-label="送る曜日" onchange={on_weekday}>
+                    { for WEEKDAYS.iter().map(|&(v, label)| html! {
+                        <option value={v.to_string()} selected={*weekday == v}>{ label }</option>
+                    }) }
+                </select>
+                <button type="submit" class="btn-add">{"保存"}</button>
+            </form>
+            <div class="settings-inline">
+                <button type="button" class="btn-add" onclick={on_preview}>{"下書きを見る"}</button>
+                <button type="button" class="btn-save" onclick={on_send} disabled={*busy || !configured}>
+                    { if *busy { "送信中..." } else { "今すぐ送る" } }
+                </button>
+            </div>
+            <div role="status" aria-live="polite">
+                { match &*report {
+                    None => html! {},
+                    Some(Err(e)) => html! { <p class="save-err">{ e.clone() }</p> },
+                    Some(Ok(r)) => html! {
+                        <p class={if r.errors.is_empty() { "save-ok" } else { "save-warn" }}>
+                            { format!("{} の週報を {} に送りました（{}）", r.period, r.sent.join("・"), r.at) }
+                            { for r.errors.iter().map(|e| html! { <><br/>{ e.clone() }</> }) }
+                        </p>
+                    },
+                } }
+            </div>
+            { match &*preview {
+                None => html! {},
+                Some(Err(e)) => html! { <p class="save-err">{ e.clone() }</p> },
+                Some(Ok(text)) => html! { <pre class="digest-preview">{ text.clone() }</pre> },
+            } }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod digest_tests {
+    use super::recipients;
+
+    #[test]
+    fn recipients_accept_commas_and_spaces() {
+        assert_eq!(recipients(" a@example.com, b@example.com\nc@example.com,"), vec!["a@example.com", "b@example.com", "c@example.com"]);
+        assert!(recipients(" , ").is_empty());
+    }
+}
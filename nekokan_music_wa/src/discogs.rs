@@ -0,0 +1,207 @@
+use crate::api::{self, ImportConflict, ImportOutcome, ImportSummary};
+use std::collections::HashMap;
+use crate::app::today_str;
+use wasm_bindgen_futures::JsFuture;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct DiscogsSyncProps {
+    /// 取り込みで下書きを作ったときにファイル一覧を読み直す
+    pub on_imported: Callback<()>,
+    /// 結果のファイル名クリックでそのレコードを開く（"xxx.json"）
+    pub on_select: Callback<String>,
+}
+
+async fn read_file_text(file: web_sys::File) -> Result<String, String> {
+    let text = JsFuture::from(file.text())
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    text.as_string().ok_or_else(|| "ファイルを読めません".to_string())
+}
+
+/// 結果の見出し（"作成 3 / 既存に反映 1 / 未決定 2"、0 件のものは出さない）
+fn summary_counts(r: &ImportSummary) -> String {
+    [
+        ImportOutcome::Created,
+        ImportOutcome::DraftCopy,
+        ImportOutcome::Merged,
+        ImportOutcome::Unchanged,
+        ImportOutcome::Skipped,
+        ImportOutcome::Pending,
+        ImportOutcome::Failed,
+    ]
+    .into_iter()
+    .map(|o| (o, r.count(o)))
+    .filter(|(_, n)| *n > 0)
+    .map(|(o, n)| format!("{} {}", o.label(), n))
+    .collect::<Vec<_>>()
+    .join(" / ")
+}
+
+/// Discogs のコレクション CSV の書き出し・取り込みページ。
+#[function_component(DiscogsSync)]
+pub fn discogs_sync(props: &DiscogsSyncProps) -> Html {
+    let busy = use_state(|| false);
+    let result = use_state(|| None::<Result<ImportSummary, String>>);
+    // 取り込み直すときのために、読んだ CSV を持っておく
+    let csv = use_state(String::new);
+    // この取り込みだけの扱い（None なら設定のもの）
+    let conflict = use_state(|| None::<ImportConflict>);
+    // 「1 件ずつ決める」で決めた行ごとの扱い
+    let decisions = use_state(HashMap::<usize, ImportConflict>::new);
+
+    let run = {
+        let (busy, result, conflict) = (busy.clone(), result.clone(), conflict.clone());
+        let on_imported = props.on_imported.clone();
+        move |text: String, decisions: HashMap<usize, ImportConflict>| {
+            let (busy, result, conflict, on_imported) = (busy.clone(), result.clone(), *conflict, on_imported.clone());
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                let res = api::import_discogs(&text, &today_str(), conflict, &decisions).await;
+                if res.as_ref().is_ok_and(|r| {
+                    r.entries.iter().any(|e| matches!(e.outcome, ImportOutcome::Created | ImportOutcome::DraftCopy | ImportOutcome::Merged))
+                }) {
+                    on_imported.emit(());
+                }
+                result.set(Some(res));
+                busy.set(false);
+            });
+        }
+    };
+
+    let on_file = {
+        let (busy, result, csv, decisions, run) = (busy.clone(), result.clone(), csv.clone(), decisions.clone(), run.clone());
+        Callback::from(move |e: Event| {
+            let Some(input) = e.target_dyn_into::<web_sys::HtmlInputElement>() else {
+                return;
+            };
+            let Some(file) = input.files().and_then(|f| f.get(0)) else {
+                return;
+            };
+            // 同じファイルを選び直しても change が飛ぶように空にしておく
+            input.set_value("");
+            let (busy, result, csv, decisions, run) = (busy.clone(), result.clone(), csv.clone(), decisions.clone(), run.clone());
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                match read_file_text(file).await {
+                    Ok(text) => {
+                        csv.set(text.clone());
+                        decisions.set(HashMap::new());
+                        run(text, HashMap::new());
+                    }
+                    Err(e) => {
+                        result.set(Some(Err(e)));
+                        busy.set(false);
+                    }
+                }
+            });
+        })
+    };
+
+    let on_conflict_change = {
+        let conflict = conflict.clone();
+        Callback::from(move |e: Event| {
+            if let Some(sel) = e.target_dyn_into::<web_sys::HtmlSelectElement>() {
+                conflict.set(ImportConflict::parse(&sel.value()));
+            }
+        })
+    };
+
+    let decide = |line: usize| {
+        let decisions = decisions.clone();
+        Callback::from(move |e: Event| {
+            if let Some(sel) = e.target_dyn_into::<web_sys::HtmlSelectElement>() {
+                let mut d = (*decisions).clone();
+                match ImportConflict::parse(&sel.value()) {
+                    Some(c) => d.insert(line, c),
+                    None => d.remove(&line),
+                };
+                decisions.set(d);
+            }
+        })
+    };
+
+    let on_rerun = {
+        let (csv, decisions, run) = (csv.clone(), decisions.clone(), run.clone());
+        Callback::from(move |_: MouseEvent| run((*csv).clone(), (*decisions).clone()))
+    };
+
+    let file_link = |filename: &str| {
+        let on_select = props.on_select.clone();
+        let f = filename.to_string();
+        html! {
+            <a href="#" onclick={move |e: MouseEvent| { e.prevent_default(); on_select.emit(f.clone()); }}>
+                { filename.to_string() }
+            </a>
+        }
+    };
+
+    html! {
+        <div class="discogs-sync">
+            <h2 class="view-title">{"Discogs 連携"}</h2>
+            <div class="form-section">
+                <h3>{"書き出し"}</h3>
+                <p class="hint">{"Discogs のコレクションエクスポートと同じ列の CSV を書き出します（下書きは除く）。"}</p>
+                <a class="btn-add" href={api::discogs_export_url()} download="nekokan_collection.csv">{"CSVをダウンロード"}</a>
+            </div>
+            <div class="form-section">
+                <h3>{"取り込み"}</h3>
+                <p class="hint">{"見つからないものは下書きとして作ります。アーティスト・タイトルが一致するレコード（同じファイル名のもの）があるときは、次の扱いにします。"}</p>
+                <label class="settings-inline">
+                    {"一致したとき"}
+                    <select class="sidebar-sort" aria-label="既存のレコードと一致したときの扱い" onchange={on_conflict_change}>
+                        <option value="" selected={conflict.is_none()}>{"設定のとおり"}</option>
+                        { for ImportConflict::ALL.into_iter().map(|c| html! {
+                            <option value={c.as_str()} selected={*conflict == Some(c)}>{ c.label() }</option>
+                        }) }
+                    </select>
+                </label>
+                <input type="file" accept=".csv,text/csv" aria-label="Discogs CSV ファイル" onchange={on_file} disabled={*busy}/>
+                if *busy {
+                    <p class="sidebar-loading">{"取り込み中..."}</p>
+                }
+            </div>
+            if let Some(ref res) = *result {
+                { match res {
+                    Err(e) => html! { <p class="save-err">{ e.clone() }</p> },
+                    Ok(r) => html! {
+                        <div class="form-section">
+                            <h3>{ format!("結果（{}）: {}", r.policy.label(), summary_counts(r)) }</h3>
+                            <ul class="report-list">
+                                { for r.entries.iter().map(|e| html! {
+                                    <li key={e.line}>
+                                        { format!("{} 行目 {}: ", e.line, e.title) }
+                                        <span class="report-count">{ e.outcome.label() }</span>
+                                        if let Some(ref f) = e.filename {
+                                            {" "}{ file_link(f) }
+                                        }
+                                        if let Some(f) = e.existing.as_ref().filter(|x| e.filename.as_ref() != Some(*x)) {
+                                            {"（一致: "}{ file_link(f) }{"）"}
+                                        }
+                                        if !e.reason.is_empty() {
+                                            { format!(" {}", e.reason) }
+                                        }
+                                        if e.outcome == ImportOutcome::Pending {
+                                            {" "}
+                                            <select class="sidebar-sort" aria-label={format!("{} 行目の扱い", e.line)} onchange={decide(e.line)}>
+                                                <option value="" selected={!decisions.contains_key(&e.line)}>{"未決定"}</option>
+                                                { for ImportConflict::ALL.into_iter().filter(|c| *c != ImportConflict::Ask).map(|c| html! {
+                                                    <option value={c.as_str()} selected={decisions.get(&e.line) == Some(&c)}>{ c.label() }</option>
+                                                }) }
+                                            </select>
+                                        }
+                                    </li>
+                                }) }
+                            </ul>
+                            if r.count(ImportOutcome::Pending) > 0 {
+                                <button type="button" class="btn-add" onclick={on_rerun} disabled={*busy || decisions.is_empty()}>
+                                    {"決めた扱いで取り込み直す"}
+                                </button>
+                            }
+                        </div>
+                    },
+                } }
+            }
+        </div>
+    }
+}
@@ -0,0 +1,55 @@
+use crate::api;
+use crate::types::MAIN_JANRES;
+use yew::prelude::*;
+
+/// 設定画面の「ZIP で書き出す」。選んでいるライブラリのレコード JSON をまとめてダウンロードする（ジャンル・記録日で絞れる）。
+#[function_component(ZipExportSection)]
+pub fn zip_export_section() -> Html {
+    let genre = use_state(String::new);
+    let from = use_state(String::new);
+    let to = use_state(String::new);
+
+    let on_genre = {
+        let genre = genre.clone();
+        Callback::from(move |e: Event| {
+            if let Some(sel) = e.target_dyn_into::<web_sys::HtmlSelectElement>() {
+                genre.set(sel.value());
+            }
+        })
+    };
+    let on_date = |state: &UseStateHandle<String>| {
+        let state = state.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                state.set(inp.value());
+            }
+        })
+    };
+    let invalid = [&*from, &*to].into_iter().any(|d| !d.trim().is_empty() && !crate::date::is_valid(d.trim()));
+
+    html! {
+        <div class="form-section zip-export">
+            <h3>{"ZIP で書き出す"}</h3>
+            <div class="settings-inline">
+                <select class="sidebar-sort" aria-label="書き出すジャンル" onchange={on_genre}>
+                    <option value="" selected={genre.is_empty()}>{"すべてのジャンル"}</option>
+                    { for MAIN_JANRES.iter().map(|&j| html! {
+                        <option value={j} selected={*genre == j}>{ j }</option>
+                    }) }
+                </select>
+                <input type="text" class="input report-date" placeholder="YYYY/MM/DD" aria-label="記録日の開始"
+                    value={(*from).clone()} oninput={on_date(&from)}/>
+                <span>{"〜"}</span>
+                <input type="text" class="input report-date" placeholder="YYYY/MM/DD" aria-label="記録日の終了"
+                    value={(*to).clone()} oninput={on_date(&to)}/>
+                if !invalid {
+                    <a class="btn-add" href={api::zip_export_url(&genre, &from, &to)}>{"ZIP をダウンロード"}</a>
+                }
+            </div>
+            if invalid {
+                <p class="save-err">{"日付は YYYY/MM/DD で入力してください。"}</p>
+            }
+            <p class="hint">{"いま選んでいるライブラリのレコード（JSON）をそのまままとめます。記録日で絞ると、記録日のないレコードは入りません。"}</p>
+        </div>
+    }
+}
@@ -0,0 +1,101 @@
+//! キーボードだけで入力するための共通処理。
+//! 繰り返し行のブロック（Personnel, Tracks, References など）は `use_append_focus` と `enter_to_add` を使えば、
+//! 行追加後のフォーカス移動と Enter での行追加がそのまま効く。
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+/// フォーカス移動先になる入力要素
+const FOCUSABLE: &str = "input:not([disabled]), select:not([disabled]), textarea:not([disabled])";
+
+/// `container` 内で `row_selector` に合う最後の行の、最初の入力要素へフォーカスする。
+pub fn focus_first_in_last(container: &web_sys::Element, row_selector: &str) {
+    let Ok(rows) = container.query_selector_all(row_selector) else {
+        return;
+    };
+    let Some(last) = rows.length().checked_sub(1).and_then(|i| rows.get(i)) else {
+        return;
+    };
+    let target = last
+        .dyn_into::<web_sys::Element>()
+        .ok()
+        .and_then(|row| row.query_selector(FOCUSABLE).ok().flatten())
+        .and_then(|el| el.dyn_into::<web_sys::HtmlElement>().ok());
+    if let Some(el) = target {
+        let _ = el.focus();
+    }
+}
+
+/// 行追加後のフォーカス移動。`container` をブロックの要素に付け、追加コールバックを `wrap` で包む。
+#[derive(Clone)]
+pub struct AppendFocus {
+    pub container: NodeRef,
+    /// 次の描画後にフォーカスする行のセレクタ
+    pending: Rc<RefCell<Option<String>>>,
+}
+
+impl AppendFocus {
+    /// `add` を呼んだあとの描画で、`row_selector` に合う最後の行へフォーカスを移す。
+    pub fn wrap<IN: 'static>(&self, row_selector: impl Into<String>, add: Callback<IN>) -> Callback<IN> {
+        let pending = self.pending.clone();
+        let row_selector = row_selector.into();
+        Callback::from(move |e: IN| {
+            *pending.borrow_mut() = Some(row_selector.clone());
+            add.emit(e);
+        })
+    }
+}
+
+/// 行追加ボタン（またはEnter）で増えた行にだけフォーカスする。
+/// 別レコードを読み込んで行数が変わったときは動かさない。
+#[hook]
+pub fn use_append_focus() -> AppendFocus {
+    let container = use_node_ref();
+    let pending = use_mut_ref(|| None::<String>);
+    {
+        let container = container.clone();
+        let pending = pending.clone();
+        use_effect(move || {
+            if let Some(selector) = pending.borrow_mut().take() {
+                if let Some(el) = container.cast::<web_sys::Element>() {
+                    focus_first_in_last(&el, &selector);
+                }
+            }
+            || ()
+        });
+    }
+    AppendFocus { container, pending }
+}
+
+/// 繰り返し行のテキスト入力で Enter を押したら行を追加する（フォーム送信＝保存にはしない）。
+/// IME の変換確定の Enter、ボタンやチェックボックス上の Enter はそのまま通す。
+pub fn enter_to_add(add: Callback<()>) -> Callback<KeyboardEvent> {
+    Callback::from(move |e: KeyboardEvent| {
+        if e.key() != "Enter" || e.is_composing() || e.shift_key() || e.ctrl_key() || e.meta_key() {
+            return;
+        }
+        // 入力欄が自分で Enter を処理した（次の行へ移るなど）ときは足さない
+        if e.default_prevented() {
+            return;
+        }
+        let is_text = e
+            .target_dyn_into::<web_sys::HtmlInputElement>()
+            .is_some_and(|inp| !matches!(inp.type_().as_str(), "checkbox" | "radio" | "button" | "submit"));
+        if is_text {
+            e.prevent_default();
+            add.emit(());
+        }
+    })
+}
+
+/// Esc キーで `cancel` を呼ぶ（ダイアログ・ポップアップを閉じる用）。
+pub fn on_escape(cancel: Callback<()>) -> Callback<KeyboardEvent> {
+    Callback::from(move |e: KeyboardEvent| {
+        if e.key() == "Escape" && !e.is_composing() {
+            e.prevent_default();
+            cancel.emit(());
+        }
+    })
+}
@@ -1,3 +1,6 @@
+use crate::focus::{enter_to_add, use_append_focus};
+use crate::length::{format_length, parse_length};
+use crate::romanize::romanize_name;
 use crate::types::*;
 use crate::validation::FieldErrors;
 use wasm_bindgen::JsCast;
@@ -10,7 +13,11 @@ pub struct FormProps {
     pub filename: String,
     pub on_filename_change: Callback<String>,
     pub errors: FieldErrors,
+    /// 保存は止めない警告（validation::warn_form）。入力欄の下に出す。
+    pub warnings: FieldErrors,
     pub on_save: Callback<()>,
+    /// 編集中のレコードを消す（既存のレコードを開いているときだけボタンを出す）
+    pub on_delete: Callback<()>,
     pub focus_title: bool,
     pub on_focus_title_done: Callback<()>,
     /// 既存ファイル名一覧（"xxx.json" 形式）。同名チェックに使用。
@@ -21,6 +28,13 @@ pub struct FormProps {
     pub on_filename_blur: Callback<String>,
     pub focus_filename: bool,
     pub on_focus_filename_done: Callback<()>,
+    /// バッジの凡例（バッジ入力の候補）
+    pub badge_legend: Vec<crate::api::BadgeLegend>,
+    /// スコアの尺度（選択肢）
+    pub score_scale: crate::validation::ScoreScale,
+    /// Classical のファイル名の候補のパターン（設定）。空なら既定の規則
+    #[prop_or_default]
+    pub classical_filename: String,
 }
 
 fn err(props: &FormProps, key: &str) -> Option<String> {
@@ -35,94 +49,135 @@ fn input_class(props: &FormProps, key: &str) -> &'static str {
     }
 }
 
-fn record_year_join(ry: &[i32]) -> String {
-    ry.iter().map(|y| y.to_string()).collect::<Vec<_>>().join(", ")
+/// 入力欄の id（`<label for>` から参照する）
+fn field_id(key: &str) -> String {
+    format!("field-{}", key)
 }
 
-/// ファイル名として不適切な文字を除去。スペースは _ に置換する。
-fn sanitize_for_filename(s: &str) -> String {
-    const INVALID: &[char] = &['\\', '/', ':', '*', '?', '"', '<', '>', '|'];
-    s.replace(' ', "_")
-        .chars()
-        .filter(|c| !c.is_control() && !INVALID.contains(c))
-        .collect()
+/// エラー文言の id（入力欄の aria-describedby から参照する）
+fn error_id(key: &str) -> String {
+    format!("error-{}", key)
 }
 
-/// ファイル名入力フォーカス時に自動入力する値を返す。
-/// グループあり時: リーダーあり → "{リーダー名}_{abbr}__{タイトル}", リーダーなし → "{abbr}__{タイトル}"。
-/// それ以外は既存ロジック（Jazz/Fusion は leader、Classical は soloists/conductor/orchestra）。
-fn suggested_filename_on_focus(data: &MusicData) -> Option<String> {
-    let main = data.janre.main.as_str();
-    if main == "Classical" {
-        // soloists → conductor → orchestra の順
-        data.personnel
-            .soloists
-            .first()
-            .map(|e| sanitize_for_filename(e.name.trim()))
-            .or_else(|| {
-                data.personnel
-                    .conductor
-                    .first()
-                    .map(|e| sanitize_for_filename(e.name.trim()))
-            })
-            .or_else(|| {
-                data.personnel
-                    .orchestra
-                    .first()
-                    .map(|e| sanitize_for_filename(e.name.trim()))
-            })
-            .filter(|s| !s.is_empty())
-    } else if main == "Jazz" || main == "Fusion" {
-        // グループが入力されていればグループ基準のファイル名を優先
-        if let Some(g) = data.personnel.group.first() {
-            let abbr = sanitize_for_filename(g.abbr.trim());
-            let title = sanitize_for_filename(data.title.trim());
-            if abbr.is_empty() {
-                return None;
-            }
-            let leader_name = g
-                .members
-                .iter()
-                .find(|m| m.leader)
-                .map(|m| sanitize_for_filename(m.name.trim()))
-                .filter(|s| !s.is_empty());
-            return Some(if let Some(name) = leader_name {
-                if title.is_empty() {
-                    format!("{}_{}", name, abbr)
-                } else {
-                    format!("{}_{}__{}", name, abbr, title)
-                }
-            } else if title.is_empty() {
-                abbr
-            } else {
-                format!("{}__{}", abbr, title)
-            });
-        }
-        // 既存: personnel.leader 1件目
-        data.personnel.leader.first().and_then(|entry| {
-            let name = sanitize_for_filename(entry.name.trim());
-            if name.is_empty() {
-                return None;
-            }
-            let title = sanitize_for_filename(data.title.trim());
-            Some(if title.is_empty() {
-                name
-            } else {
-                format!("{}__{}", name, title)
-            })
+/// エラーがあるときだけ付ける aria-describedby
+fn described_by(errors: &FieldErrors, key: &str) -> Option<String> {
+    errors.contains_key(key).then(|| error_id(key))
+}
+
+fn aria_invalid(errors: &FieldErrors, key: &str) -> Option<&'static str> {
+    errors.contains_key(key).then_some("true")
+}
+
+/// 入力欄の下に出すエラー文言
+fn error_text(key: &str, msg: Option<String>) -> Html {
+    msg.map(|m| html! { <span class="error-text" id={error_id(key)}>{ m }</span> })
+        .unwrap_or_default()
+}
+
+/// 入力欄の下に出す警告（エラーがあるときはエラーだけ出す）
+fn warning_text(props: &FormProps, key: &str) -> Html {
+    if props.errors.contains_key(key) {
+        return Html::default();
+    }
+    props
+        .warnings
+        .get(key)
+        .map(|m| html! { <span class="warning-text" role="status">{ format!("⚠ {}", m) }</span> })
+        .unwrap_or_default()
+}
+
+/// トラックの長さの入力を "分:秒" に整える。"433" → "4:33"、"1205" → "12:05"、"45" → "0:45"、
+/// "4.33" "4 33" "4：33"（全角）→ "4:33"。秒が 60 以上になるものや数字以外を含むものはそのまま返す。
+pub(crate) fn normalize_length(s: &str) -> String {
+    let half: String = s
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '０'..='９' => char::from_u32(c as u32 - '０' as u32 + '0' as u32).unwrap_or(c),
+            '：' | '.' | ' ' | '\'' | '′' => ':',
+            _ => c,
         })
-    } else {
-        None
+        .collect();
+    if half.is_empty() {
+        return half;
+    }
+    let (min, sec) = match half.split_once(':') {
+        Some((m, s)) if s.len() == 2 => (m.to_string(), s.to_string()),
+        Some(_) => return s.trim().to_string(),
+        None if half.len() <= 2 => ("0".to_string(), format!("{:0>2}", half)),
+        None => {
+            let (m, s) = half.split_at(half.len() - 2);
+            (m.to_string(), s.to_string())
+        }
+    };
+    let all_digits = |t: &str| !t.is_empty() && t.chars().all(|c| c.is_ascii_digit());
+    match (min.parse::<u32>(), sec.parse::<u32>()) {
+        (Ok(m), Ok(sec_v)) if all_digits(&min) && all_digits(&sec) && sec_v < 60 => format!("{}:{}", m, sec),
+        _ => s.trim().to_string(),
     }
 }
 
+/// 合計時間を `count` 曲に均等に割り振った仮の長さ（割り切れない秒は前の曲から 1 秒ずつ足す）
+pub(crate) fn split_total_length(total_secs: u64, count: usize) -> Vec<String> {
+    if count == 0 {
+        return vec![];
+    }
+    let (each, rest) = (total_secs / count as u64, total_secs as usize % count);
+    (0..count).map(|i| format_length(each + u64::from(i < rest))).collect()
+}
+
+fn record_year_join(ry: &[i32], unknown: bool) -> String {
+    if unknown && ry.is_empty() {
+        return crate::rules::UNKNOWN.to_string();
+    }
+    ry.iter().map(|y| y.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// 録音年の欄（"1959, 1960"）を年の一覧にする。"?" だけなら不明（true）。
+pub(crate) fn parse_record_years(text: &str) -> (Vec<i32>, bool) {
+    if crate::rules::is_unknown(text) {
+        return (Vec::new(), true);
+    }
+    let years = text.split(',').map(str::trim).filter(|p| !p.is_empty()).filter_map(|p| p.parse().ok()).collect();
+    (years, false)
+}
+
+/// 作品の作曲者の欄の区切り（トラックの作曲者と同じ）
+const COMPOSERS_SEP: &str = " | ";
+
+fn composers_join(composers: &[String]) -> String {
+    composers.join(COMPOSERS_SEP)
+}
+
+/// 作品の作曲者の欄（"Schoenberg | Webern"）を名前の一覧にする
+pub(crate) fn parse_composers(text: &str) -> Vec<String> {
+    text.split('|').map(str::trim).filter(|c| !c.is_empty()).map(str::to_string).collect()
+}
+
+pub(crate) use crate::filename::sanitize as sanitize_for_filename;
+
+/// ファイル名入力フォーカス時に自動入力する値（規則は nekokan_music_core::filename::suggested_filename。サーバーのファイル名の一括整理と同じ）
+pub(crate) use crate::filename::suggested_filename as suggested_filename_on_focus;
+
+/// 「JSON をダウンロード」のファイル名（"xxx.json"）。ファイル名欄 → 開いているレコード → 入力から作る候補の順
+pub(crate) fn record_json_filename(data: &MusicData, filename: &str, selected: Option<&str>, classical_pattern: &str) -> String {
+    let stem = Some(sanitize_for_filename(filename.trim().trim_end_matches(".json")))
+        .filter(|s| !s.is_empty())
+        .or_else(|| selected.map(|s| s.trim_end_matches(".json").to_string()))
+        .or_else(|| suggested_filename_on_focus(data, classical_pattern))
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "record".to_string());
+    format!("{}.json", stem)
+}
+
 #[function_component(Form)]
 pub fn form(props: &FormProps) -> Html {
     let sub_opts = sub_janres_for_main(&props.data.janre.main);
     let title_input_ref = use_node_ref();
     let filename_input_ref = use_node_ref();
     let score_select_ref = use_node_ref();
-    let record_year_text = use_state(|| record_year_join(&props.data.record_year));
+    let record_year_text = use_state(|| record_year_join(&props.data.record_year, props.data.record_year_unknown));
+    let composers_text = use_state(|| composers_join(&props.data.composers));
 
     let on_save = props.on_save.clone();
     let filename = props.filename.clone();
@@ -130,10 +185,18 @@ pub fn form(props: &FormProps) -> Html {
     let on_filename_blur = props.on_filename_blur.clone();
 
     {
-        let ry = props.data.record_year.clone();
+        let ry = (props.data.record_year.clone(), props.data.record_year_unknown);
         let record_year_text = record_year_text.clone();
-        use_effect_with(ry, move |r| {
-            record_year_text.set(record_year_join(r));
+        use_effect_with(ry, move |(r, unknown)| {
+            record_year_text.set(record_year_join(r, *unknown));
+            || ()
+        });
+    }
+
+    {
+        let composers_text = composers_text.clone();
+        use_effect_with(props.data.composers.clone(), move |c| {
+            composers_text.set(composers_join(c));
             || ()
         });
     }
@@ -141,9 +204,9 @@ pub fn form(props: &FormProps) -> Html {
     {
         let score_select_ref = score_select_ref.clone();
         let score = props.data.score;
-        use_effect_with(score, move |&score| {
+        use_effect_with(score.to_bits(), move |_| {
             if let Some(sel) = score_select_ref.cast::<web_sys::HtmlSelectElement>() {
-                sel.set_value(&score.to_string());
+                sel.set_value(&crate::validation::format_score(score));
             }
             || ()
         });
@@ -179,28 +242,89 @@ pub fn form(props: &FormProps) -> Html {
         });
     }
 
+    #[cfg(feature = "import")]
+    let lookup = html! {
+        <crate::lookup::MetadataLookup data={props.data.clone()} on_data_change={props.on_data_change.clone()}
+            selected_filename={props.selected_filename.clone()} />
+    };
+    #[cfg(not(feature = "import"))]
+    let lookup = Html::default();
+
     html! {
         <form class="music-form" onsubmit={Callback::from(move |e: SubmitEvent| { e.prevent_default(); on_save.emit(()); })}>
+            { lookup }
             <div class="form-section">
                 <h3>{"Basic Information"}</h3>
                 <div class="field">
-                    <label>{"Title"}</label>
+                    <label for={field_id("title")}>{"Title"}</label>
                     <input
+                        id={field_id("title")}
                         ref={title_input_ref.clone()}
                         type="text"
                         class={input_class(props, "title")}
+                        aria-invalid={aria_invalid(&props.errors, "title")}
+                        aria-describedby={described_by(&props.errors, "title")}
                         value={props.data.title.clone()}
                         oninput={update_str(props.data.clone(), props.on_data_change.clone(), |d, v| d.title = v)}
                         maxlength="128"
                     />
-                    { for err(props, "title").into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    { error_text("title", err(props, "title")) }
+                </div>
+
+                if !props.data.cover.is_empty() {
+                    <div class="field">
+                        <label>{"Cover"}</label>
+                        <img class="cover-preview" src={crate::api::cover_url(&props.data.cover)}
+                            alt={format!("{} のカバー", props.data.title)} />
+                    </div>
+                }
+
+                <div class="field">
+                    <label for={field_id("title_alt")}>{"Title (別題)"}</label>
+                    <input
+                        id={field_id("title_alt")}
+                        type="text"
+                        class={input_class(props, "title_alt")}
+                        aria-invalid={aria_invalid(&props.errors, "title_alt")}
+                        aria-describedby={described_by(&props.errors, "title_alt")}
+                        value={props.data.title_alt.clone()}
+                        oninput={update_str(props.data.clone(), props.on_data_change.clone(), |d, v| d.title_alt = v)}
+                        maxlength="128"
+                        placeholder="邦題・原題など（任意）"
+                    />
+                    { error_text("title_alt", err(props, "title_alt")) }
+                </div>
+
+                <div class="field">
+                    <label for={field_id("badge")}>{"Badge"}</label>
+                    <input
+                        id={field_id("badge")}
+                        type="text"
+                        list="badge-legend"
+                        class={classes!(input_class(props, "badge"), "badge-input")}
+                        aria-invalid={aria_invalid(&props.errors, "badge")}
+                        aria-describedby={described_by(&props.errors, "badge")}
+                        value={props.data.badge.clone()}
+                        oninput={update_str(props.data.clone(), props.on_data_change.clone(), |d, v| d.badge = v)}
+                        maxlength="8"
+                        placeholder="🔴"
+                    />
+                    <datalist id="badge-legend">
+                        { for props.badge_legend.iter().map(|b| html! {
+                            <option value={b.badge.clone()}>{ b.label.clone() }</option>
+                        }) }
+                    </datalist>
+                    { error_text("badge", err(props, "badge")) }
                 </div>
 
                 <div class="field">
-                    <label>{"Main Janre"}</label>
+                    <label for={field_id("janre.main")}>{"Main Janre"}</label>
                     <select
+                        id={field_id("janre.main")}
                         key={props.filename.clone()}
                         class={input_class(props, "janre.main")}
+                        aria-invalid={aria_invalid(&props.errors, "janre.main")}
+                        aria-describedby={described_by(&props.errors, "janre.main")}
                         value={props.data.janre.main.clone()}
                         onchange={update_main_janre(props.data.clone(), props.on_data_change.clone())}
                     >
@@ -213,14 +337,17 @@ pub fn form(props: &FormProps) -> Html {
                             }
                         }) }
                     </select>
-                    { for err(props, "janre.main").into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    { error_text("janre.main", err(props, "janre.main")) }
                 </div>
 
                 <div class="field">
-                    <label>{"Sub Janre"}</label>
+                    <label for={field_id("janre.sub")}>{"Sub Janre"}</label>
                     <select
+                        id={field_id("janre.sub")}
                         key={props.data.janre.main.clone()}
                         class={input_class(props, "janre.sub")}
+                        aria-invalid={aria_invalid(&props.errors, "janre.sub")}
+                        aria-describedby={described_by(&props.errors, "janre.sub")}
                         multiple={true}
                         value={props.data.janre.sub.join(",")}
                         onchange={update_multi_sub(props.data.clone(), props.on_data_change.clone())}
@@ -234,58 +361,97 @@ pub fn form(props: &FormProps) -> Html {
                             }
                         }) }
                     </select>
-                    { for err(props, "janre.sub").into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    { error_text("janre.sub", err(props, "janre.sub")) }
                 </div>
 
                 <div class="field">
-                    <label>{"Label"}</label>
+                    <label for={field_id("label")}>{"Label"}</label>
                     <input
+                        id={field_id("label")}
                         type="text"
                         class={input_class(props, "label")}
+                        aria-invalid={aria_invalid(&props.errors, "label")}
+                        aria-describedby={described_by(&props.errors, "label")}
                         value={props.data.label.clone()}
                         oninput={update_str(props.data.clone(), props.on_data_change.clone(), |d, v| d.label = v)}
                         maxlength="64"
                     />
-                    { for err(props, "label").into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    { error_text("label", err(props, "label")) }
                 </div>
 
                 <div class="field">
-                    <label>{"Id"}</label>
+                    <label for={field_id("id")}>{"Id"}</label>
                     <input
+                        id={field_id("id")}
                         type="text"
                         class={input_class(props, "id")}
+                        aria-invalid={aria_invalid(&props.errors, "id")}
+                        aria-describedby={described_by(&props.errors, "id")}
                         value={props.data.id.clone()}
                         oninput={update_str(props.data.clone(), props.on_data_change.clone(), |d, v| d.id = v)}
                         maxlength="64"
                     />
-                    { for err(props, "id").into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    { error_text("id", err(props, "id")) }
                 </div>
 
                 <div class="field">
-                    <label>{"Release Year"}</label>
+                    <label for={field_id("release_year")}>{"Release Year"}</label>
                     <input
+                        id={field_id("release_year")}
                         type="number"
                         class={input_class(props, "release_year")}
+                        aria-invalid={aria_invalid(&props.errors, "release_year")}
+                        aria-describedby={described_by(&props.errors, "release_year")}
                         value={props.data.release_year.to_string()}
                         oninput={update_i32(props.data.clone(), props.on_data_change.clone(), |d, v| d.release_year = v)}
                         min="1900"
                         max="2099"
                     />
-                    { for err(props, "release_year").into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    { error_text("release_year", err(props, "release_year")) }
+                    { warning_text(props, "release_year") }
                 </div>
 
                 <div class="field">
-                    <label>{"Recording Year"}</label>
+                    <label for={field_id("record_year")}>{"Recording Year"}</label>
                     <input
+                        id={field_id("record_year")}
                         type="text"
                         class={input_class(props, "record_year")}
+                        aria-invalid={aria_invalid(&props.errors, "record_year")}
+                        aria-describedby={described_by(&props.errors, "record_year")}
                         value={(*record_year_text).clone()}
-                        oninput={record_year_input(record_year_text.clone())}
+                        oninput={edit_text(record_year_text.clone())}
                         onblur={record_year_blur(record_year_text.clone(), props.data.clone(), props.on_data_change.clone())}
-                        placeholder="例: 1991, 1992"
+                        placeholder="例: 1991, 1992（不明なら ?）"
                     />
-                    { for err(props, "record_year").into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    { error_text("record_year", err(props, "record_year")) }
                 </div>
+
+                if props.data.janre.main == "Classical" || !props.data.composers.is_empty() {
+                    <div class="field">
+                        <label for={field_id("composers")}>{"Composers (作品)"}</label>
+                        <input
+                            id={field_id("composers")}
+                            type="text"
+                            class={input_class(props, "composers")}
+                            aria-invalid={aria_invalid(&props.errors, "composers")}
+                            aria-describedby={described_by(&props.errors, "composers").map_or_else(|| "hint-composers".to_string(), |e| format!("{} hint-composers", e))}
+                            value={(*composers_text).clone()}
+                            oninput={edit_text(composers_text.clone())}
+                            onblur={{
+                                let (composers_text, data, on_data_change) = (composers_text.clone(), props.data.clone(), props.on_data_change.clone());
+                                Callback::from(move |_: FocusEvent| {
+                                    let mut d = data.clone();
+                                    d.composers = parse_composers(&composers_text);
+                                    on_data_change.emit(d);
+                                })
+                            }}
+                            placeholder="例: Schoenberg | Webern"
+                        />
+                        { error_text("composers", err(props, "composers")) }
+                        <span class="hint" id="hint-composers">{"アルバムの主な作曲者。サイドバーのラベルとファイル名の候補はトラックの作曲者よりこちらを使います"}</span>
+                    </div>
+                }
             </div>
 
             <PersonnelSection data={props.data.clone()} on_data_change={props.on_data_change.clone()} errors={props.errors.clone()} />
@@ -295,26 +461,32 @@ pub fn form(props: &FormProps) -> Html {
             <div class="form-section">
                 <h3>{"評価・日付"}</h3>
                 <div class="field">
-                    <label>{"Score"}</label>
+                    <label for={field_id("score")}>{"Score"}</label>
                     <select
+                        id={field_id("score")}
                         ref={score_select_ref.clone()}
                         class={input_class(props, "score")}
+                        aria-invalid={aria_invalid(&props.errors, "score")}
+                        aria-describedby={described_by(&props.errors, "score")}
                         onchange={update_score(props.data.clone(), props.on_data_change.clone())}
                     >
-                        { for [1,2,3,4,5,6].iter().map(|&v| {
-                            let is_selected = props.data.score == v;
-                            if is_selected {
-                                html! { <option value={v.to_string()} selected={true}>{ v }</option> }
-                            } else {
-                                html! { <option value={v.to_string()}>{ v }</option> }
-                            }
+                        // 尺度外の値（尺度を変える前のレコード）も選択肢に残し、エラーで知らせる
+                        if !props.score_scale.contains(props.data.score) {
+                            <option value={crate::validation::format_score(props.data.score)} selected={true}>
+                                { crate::validation::format_score(props.data.score) }
+                            </option>
+                        }
+                        { for props.score_scale.values().into_iter().map(|v| {
+                            let label = crate::validation::format_score(v);
+                            html! { <option value={label.clone()} selected={props.data.score == v}>{ label }</option> }
                         }) }
                     </select>
-                    { for err(props, "score").into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    { error_text("score", err(props, "score")) }
                 </div>
                 <div class="field">
-                    <label>{"Comment"}</label>
+                    <label for={field_id("comment")}>{"Comment"}</label>
                     <textarea
+                        id={field_id("comment")}
                         class="input"
                         rows="4"
                         value={props.data.comment.clone()}
@@ -322,33 +494,71 @@ pub fn form(props: &FormProps) -> Html {
                     />
                 </div>
                 <div class="field">
-                    <label>{"Date"}</label>
+                    <label class="checkbox-label">
+                        <input
+                            type="checkbox"
+                            checked={props.data.draft}
+                            onchange={{
+                                let data = props.data.clone();
+                                let on_data_change = props.on_data_change.clone();
+                                Callback::from(move |_: Event| {
+                                    let mut d = data.clone();
+                                    d.draft = !d.draft;
+                                    on_data_change.emit(d);
+                                })
+                            }}
+                        />
+                        {"下書き"}
+                    </label>
+                </div>
+                if !props.data.listens.is_empty() {
+                    <div class="field">
+                        <label>{"再生記録"}</label>
+                        <span class="hint">
+                            { format!("{} 回（最終 {}）", props.data.listens.len(), props.data.listens.last().cloned().unwrap_or_default()) }
+                        </span>
+                    </div>
+                }
+                <div class="field">
+                    <label for={field_id("date")}>{"Date"}</label>
                     <input
+                        id={field_id("date")}
                         type="text"
                         class={input_class(props, "date")}
+                        aria-invalid={aria_invalid(&props.errors, "date")}
+                        aria-describedby={described_by(&props.errors, "date")}
                         value={props.data.date.clone()}
                         oninput={update_str(props.data.clone(), props.on_data_change.clone(), |d, v| d.date = v)}
                         placeholder="YYYY/MM/DD"
                     />
-                    { for err(props, "date").into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    { error_text("date", err(props, "date")) }
+                    if !props.data.updated_date.is_empty() {
+                        <span class="hint">{ format!("最終更新 {}", props.data.updated_date) }</span>
+                    }
+                    { error_text("updated_date", err(props, "updated_date")) }
                 </div>
             </div>
 
             <ReferencesSection data={props.data.clone()} on_data_change={props.on_data_change.clone()} errors={props.errors.clone()} />
 
+            <crate::related::RelatedPanel data={props.data.clone()} on_data_change={props.on_data_change.clone()} selected_filename={props.selected_filename.clone()} />
+
             <div class="form-section">
                 <div class="field">
-                    <label>{"ファイル名"}</label>
+                    <label for={field_id("filename")}>{"ファイル名"}</label>
                     <input
+                        id={field_id("filename")}
                         ref={filename_input_ref.clone()}
                         type="text"
                         class={input_class(props, "filename")}
+                        aria-invalid={aria_invalid(&props.errors, "filename")}
+                        aria-describedby={described_by(&props.errors, "filename").map_or_else(|| "hint-filename".to_string(), |e| format!("{} hint-filename", e))}
                         value={filename}
                         onfocus={{
-                            let data = props.data.clone();
+                            let (data, pattern) = (props.data.clone(), props.classical_filename.clone());
                             let on_filename_change = props.on_filename_change.clone();
                             Callback::from(move |_: FocusEvent| {
-                                if let Some(s) = suggested_filename_on_focus(&data) {
+                                if let Some(s) = suggested_filename_on_focus(&data, &pattern) {
                                     on_filename_change.emit(s);
                                 }
                             })
@@ -375,10 +585,21 @@ pub fn form(props: &FormProps) -> Html {
                         })}
                         placeholder="例: Artist__Album"
                     />
-                    { for err(props, "filename").into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
-                    <span class="hint">{"保存時に .json が付きます"}</span>
+                    { error_text("filename", err(props, "filename")) }
+                    <span class="hint" id="hint-filename">
+                        { if props.selected_filename.is_some() {
+                            "保存時に .json が付きます。変えて保存するとファイル名を変更します（カバー・メモ・添付も付け替え）"
+                        } else {
+                            "保存時に .json が付きます"
+                        } }
+                    </span>
                 </div>
                 <button type="submit" class="btn-save">{"保存"}</button>
+                if let Some(ref name) = props.selected_filename {
+                    <a class="btn-add btn-markdown" href={crate::api::markdown_url(name)}
+                        download={format!("{}.md", name.trim_end_matches(".json"))} title="保存済みの内容を Markdown で書き出す">{"Markdown"}</a>
+                    <button type="button" class="btn-remove btn-delete-record" onclick={props.on_delete.reform(|_: MouseEvent| ())}>{"削除"}</button>
+                }
             </div>
         </form>
     }
@@ -441,14 +662,15 @@ where
     })
 }
 
-fn record_year_input(record_year_text: UseStateHandle<String>) -> Callback<InputEvent> {
+/// 入力中の文字列だけを持つ欄（録音年・作品の作曲者。確定はフォーカスを外したとき）
+fn edit_text(text: UseStateHandle<String>) -> Callback<InputEvent> {
     Callback::from(move |e: InputEvent| {
         let target = match e.target() {
             Some(t) => t,
             None => return,
         };
         if let Some(inp) = target.dyn_ref::<web_sys::HtmlInputElement>() {
-            record_year_text.set(inp.value());
+            text.set(inp.value());
         }
     })
 }
@@ -459,14 +681,10 @@ fn record_year_blur(
     on_data_change: Callback<MusicData>,
 ) -> Callback<FocusEvent> {
     Callback::from(move |_| {
-        let years: Vec<i32> = (*record_year_text)
-            .split(',')
-            .map(|p| p.trim())
-            .filter(|p| !p.is_empty())
-            .filter_map(|p| p.parse().ok())
-            .collect();
+        let (years, unknown) = parse_record_years(&record_year_text);
         let mut d = data.clone();
         d.record_year = years;
+        d.record_year_unknown = unknown;
         on_data_change.emit(d);
     })
 }
@@ -498,7 +716,7 @@ fn update_score(data: MusicData, on_data_change: Callback<MusicData>) -> Callbac
     Callback::from(move |e: Event| {
         let select = e.target_dyn_into::<web_sys::HtmlSelectElement>();
         if let Some(sel) = select {
-            if let Ok(v) = sel.value().parse::<i32>() {
+            if let Ok(v) = sel.value().parse::<f64>() {
                 let mut d = data.clone();
                 d.score = v;
                 on_data_change.emit(d);
@@ -508,6 +726,58 @@ fn update_score(data: MusicData, on_data_change: Callback<MusicData>) -> Callbac
 }
 
 // --- Personnel section ---
+
+/// 名前の別表記入力と「ローマ字」候補ボタン。名前がかな表記のときだけボタンが押せる。
+fn name_alt_field<F>(
+    data: MusicData,
+    on_data_change: Callback<MusicData>,
+    name: &str,
+    name_alt: &str,
+    key: &str,
+    errors: &FieldErrors,
+    set: F,
+) -> Html
+where
+    F: Fn(&mut MusicData, String) + Clone + 'static,
+{
+    let suggestion = romanize_name(name);
+    let err_alt = errors.get(key).cloned();
+    let oninput = {
+        let data = data.clone();
+        let on_data_change = on_data_change.clone();
+        let set = set.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                let mut d = data.clone();
+                set(&mut d, inp.value());
+                on_data_change.emit(d);
+            }
+        })
+    };
+    let on_romanize = {
+        let suggestion = suggestion.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(s) = suggestion.clone() {
+                let mut d = data.clone();
+                set(&mut d, s);
+                on_data_change.emit(d);
+            }
+        })
+    };
+    html! {
+        <>
+            <span class="input-wrap">
+                <input type="text" placeholder="Name (別表記)" value={name_alt.to_string()} oninput={oninput}
+                    class={if errors.contains_key(key) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, key)} aria-describedby={described_by(errors, key)}/>
+                { error_text(key, err_alt) }
+            </span>
+            <button type="button" class="btn-romanize" disabled={suggestion.is_none()}
+                title={suggestion.clone().unwrap_or_default()}
+                aria-label={suggestion.as_ref().map_or_else(|| "ローマ字候補なし".to_string(), |r| format!("別表記にローマ字 {} を入れる", r))}
+                onclick={on_romanize}>{"ローマ字"}</button>
+        </>
+    }
+}
 #[derive(Properties, PartialEq)]
 struct PersonnelSectionProps {
     data: MusicData,
@@ -555,14 +825,16 @@ fn conductor_row(
             <span class="input-wrap">
                 <input type="text" placeholder="Name" value={entry.name.clone()}
                     oninput={update_conductor(data.clone(), on_data_change.clone(), i, true)}
-                    class={if errors.contains_key(&key_name) { "input input-error" } else { "input" }}/>
-                { for err_name.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    class={if errors.contains_key(&key_name) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_name)} aria-describedby={described_by(errors, &key_name)}/>
+                { error_text(&key_name, err_name) }
             </span>
+            { name_alt_field(data.clone(), on_data_change.clone(), &entry.name, &entry.name_alt, &format!("personnel.conductor[{}].name_alt", i), errors,
+                move |d, v| if let Some(e) = d.personnel.conductor.get_mut(i) { e.name_alt = v }) }
             <span class="input-wrap">
                 <input type="text" placeholder="Tracks" value={entry.tracks.clone()}
                     oninput={update_conductor(data.clone(), on_data_change.clone(), i, false)}
-                    class={if errors.contains_key(&key_tracks) { "input input-error" } else { "input" }}/>
-                { for err_tracks.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    class={if errors.contains_key(&key_tracks) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_tracks)} aria-describedby={described_by(errors, &key_tracks)}/>
+                { error_text(&key_tracks, err_tracks) }
             </span>
         </>
     }
@@ -601,13 +873,13 @@ fn orchestra_row(
         <>
             <span class="input-wrap">
                 <input type="text" placeholder="Orchestra Name" value={entry.name.clone()}
-                    oninput={update_orchestra(data.clone(), on_data_change.clone(), i, true)} class={if errors.contains_key(&key_name) { "input input-error" } else { "input" }}/>
-                { for err_name.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    oninput={update_orchestra(data.clone(), on_data_change.clone(), i, true)} class={if errors.contains_key(&key_name) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_name)} aria-describedby={described_by(errors, &key_name)}/>
+                { error_text(&key_name, err_name) }
             </span>
             <span class="input-wrap">
                 <input type="text" placeholder="Tracks" value={entry.tracks.clone()}
-                    oninput={update_orchestra(data.clone(), on_data_change.clone(), i, false)} class={if errors.contains_key(&key_tracks) { "input input-error" } else { "input" }}/>
-                { for err_tracks.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    oninput={update_orchestra(data.clone(), on_data_change.clone(), i, false)} class={if errors.contains_key(&key_tracks) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_tracks)} aria-describedby={described_by(errors, &key_tracks)}/>
+                { error_text(&key_tracks, err_tracks) }
             </span>
         </>
     }
@@ -646,13 +918,13 @@ fn company_row(
         <>
             <span class="input-wrap">
                 <input type="text" placeholder="Company Name" value={entry.name.clone()}
-                    oninput={update_company(data.clone(), on_data_change.clone(), i, true)} class={if errors.contains_key(&key_name) { "input input-error" } else { "input" }}/>
-                { for err_name.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    oninput={update_company(data.clone(), on_data_change.clone(), i, true)} class={if errors.contains_key(&key_name) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_name)} aria-describedby={described_by(errors, &key_name)}/>
+                { error_text(&key_name, err_name) }
             </span>
             <span class="input-wrap">
                 <input type="text" placeholder="Tracks" value={entry.tracks.clone()}
-                    oninput={update_company(data.clone(), on_data_change.clone(), i, false)} class={if errors.contains_key(&key_tracks) { "input input-error" } else { "input" }}/>
-                { for err_tracks.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    oninput={update_company(data.clone(), on_data_change.clone(), i, false)} class={if errors.contains_key(&key_tracks) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_tracks)} aria-describedby={described_by(errors, &key_tracks)}/>
+                { error_text(&key_tracks, err_tracks) }
             </span>
         </>
     }
@@ -692,16 +964,18 @@ fn soloist_row(
     html! {
         <>
             <span class="input-wrap">
-                <input type="text" placeholder="Name" value={entry.name.clone()} oninput={update_soloist(data.clone(), on_data_change.clone(), i, 0)} class={if errors.contains_key(&key_name) { "input input-error" } else { "input" }}/>
-                { for err_name.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                <input type="text" placeholder="Name" value={entry.name.clone()} oninput={update_soloist(data.clone(), on_data_change.clone(), i, 0)} class={if errors.contains_key(&key_name) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_name)} aria-describedby={described_by(errors, &key_name)}/>
+                { error_text(&key_name, err_name) }
             </span>
+            { name_alt_field(data.clone(), on_data_change.clone(), &entry.name, &entry.name_alt, &format!("personnel.soloists[{}].name_alt", i), errors,
+                move |d, v| if let Some(e) = d.personnel.soloists.get_mut(i) { e.name_alt = v }) }
             <span class="input-wrap">
-                <input type="text" placeholder="Instrument" value={entry.instrument.clone()} oninput={update_soloist(data.clone(), on_data_change.clone(), i, 1)} class={if errors.contains_key(&key_inst) { "input input-error" } else { "input" }}/>
-                { for err_inst.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                <input type="text" placeholder="Instrument" value={entry.instrument.clone()} oninput={update_soloist(data.clone(), on_data_change.clone(), i, 1)} class={if errors.contains_key(&key_inst) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_inst)} aria-describedby={described_by(errors, &key_inst)}/>
+                { error_text(&key_inst, err_inst) }
             </span>
             <span class="input-wrap">
-                <input type="text" placeholder="Tracks" value={entry.tracks.clone()} oninput={update_soloist(data.clone(), on_data_change.clone(), i, 2)} class={if errors.contains_key(&key_tracks) { "input input-error" } else { "input" }}/>
-                { for err_tracks.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                <input type="text" placeholder="Tracks" value={entry.tracks.clone()} oninput={update_soloist(data.clone(), on_data_change.clone(), i, 2)} class={if errors.contains_key(&key_tracks) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_tracks)} aria-describedby={described_by(errors, &key_tracks)}/>
+                { error_text(&key_tracks, err_tracks) }
             </span>
         </>
     }
@@ -741,16 +1015,18 @@ fn leader_row(
     html! {
         <>
             <span class="input-wrap">
-                <input type="text" placeholder="Name" value={entry.name.clone()} oninput={update_leader(data.clone(), on_data_change.clone(), i, 0)} class={if errors.contains_key(&key_name) { "input input-error" } else { "input" }}/>
-                { for err_name.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                <input type="text" placeholder="Name" value={entry.name.clone()} oninput={update_leader(data.clone(), on_data_change.clone(), i, 0)} class={if errors.contains_key(&key_name) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_name)} aria-describedby={described_by(errors, &key_name)}/>
+                { error_text(&key_name, err_name) }
             </span>
+            { name_alt_field(data.clone(), on_data_change.clone(), &entry.name, &entry.name_alt, &format!("personnel.leader[{}].name_alt", i), errors,
+                move |d, v| if let Some(e) = d.personnel.leader.get_mut(i) { e.name_alt = v }) }
             <span class="input-wrap">
-                <input type="text" placeholder="Instruments" value={entry.instruments.clone()} oninput={update_leader(data.clone(), on_data_change.clone(), i, 1)} class={if errors.contains_key(&key_inst) { "input input-error" } else { "input" }}/>
-                { for err_inst.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                <input type="text" placeholder="Instruments" value={entry.instruments.clone()} oninput={update_leader(data.clone(), on_data_change.clone(), i, 1)} class={if errors.contains_key(&key_inst) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_inst)} aria-describedby={described_by(errors, &key_inst)}/>
+                { error_text(&key_inst, err_inst) }
             </span>
             <span class="input-wrap">
-                <input type="text" placeholder="Tracks" value={entry.tracks.clone()} oninput={update_leader(data.clone(), on_data_change.clone(), i, 2)} class={if errors.contains_key(&key_tracks) { "input input-error" } else { "input" }}/>
-                { for err_tracks.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                <input type="text" placeholder="Tracks" value={entry.tracks.clone()} oninput={update_leader(data.clone(), on_data_change.clone(), i, 2)} class={if errors.contains_key(&key_tracks) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_tracks)} aria-describedby={described_by(errors, &key_tracks)}/>
+                { error_text(&key_tracks, err_tracks) }
             </span>
         </>
     }
@@ -790,16 +1066,18 @@ fn sidemen_row(
     html! {
         <>
             <span class="input-wrap">
-                <input type="text" placeholder="Name" value={entry.name.clone()} oninput={update_sidemen(data.clone(), on_data_change.clone(), i, 0)} class={if errors.contains_key(&key_name) { "input input-error" } else { "input" }}/>
-                { for err_name.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                <input type="text" placeholder="Name" value={entry.name.clone()} oninput={update_sidemen(data.clone(), on_data_change.clone(), i, 0)} class={if errors.contains_key(&key_name) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_name)} aria-describedby={described_by(errors, &key_name)}/>
+                { error_text(&key_name, err_name) }
             </span>
+            { name_alt_field(data.clone(), on_data_change.clone(), &entry.name, &entry.name_alt, &format!("personnel.sidemen[{}].name_alt", i), errors,
+                move |d, v| if let Some(e) = d.personnel.sidemen.get_mut(i) { e.name_alt = v }) }
             <span class="input-wrap">
-                <input type="text" placeholder="Instruments" value={entry.instruments.clone()} oninput={update_sidemen(data.clone(), on_data_change.clone(), i, 1)} class={if errors.contains_key(&key_inst) { "input input-error" } else { "input" }}/>
-                { for err_inst.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                <input type="text" placeholder="Instruments" value={entry.instruments.clone()} oninput={update_sidemen(data.clone(), on_data_change.clone(), i, 1)} class={if errors.contains_key(&key_inst) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_inst)} aria-describedby={described_by(errors, &key_inst)}/>
+                { error_text(&key_inst, err_inst) }
             </span>
             <span class="input-wrap">
-                <input type="text" placeholder="Tracks" value={entry.tracks.clone()} oninput={update_sidemen(data.clone(), on_data_change.clone(), i, 2)} class={if errors.contains_key(&key_tracks) { "input input-error" } else { "input" }}/>
-                { for err_tracks.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                <input type="text" placeholder="Tracks" value={entry.tracks.clone()} oninput={update_sidemen(data.clone(), on_data_change.clone(), i, 2)} class={if errors.contains_key(&key_tracks) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_tracks)} aria-describedby={described_by(errors, &key_tracks)}/>
+                { error_text(&key_tracks, err_tracks) }
             </span>
         </>
     }
@@ -825,108 +1103,114 @@ fn update_sidemen(data: MusicData, on_data_change: Callback<MusicData>, idx: usi
 
 #[function_component(ConductorBlock)]
 fn conductor_block(props: &PersonnelBlockProps<ConductorEntry>) -> Html {
-    let add = { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |_| { let mut d = data.clone(); d.personnel.conductor.push(Default::default()); on_data_change.emit(d); }) };
+    let focus = use_append_focus();
+    let add: Callback<()> = focus.wrap(".personnel-row", { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |()| { let mut d = data.clone(); d.personnel.conductor.push(Default::default()); on_data_change.emit(d); }) });
     let remove = |i: usize| { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |_| { let mut d = data.clone(); d.personnel.conductor.remove(i); on_data_change.emit(d); }) };
     html! {
-        <div class="personnel-block">
+        <div class="personnel-block" ref={focus.container.clone()} onkeydown={enter_to_add(add.clone())}>
             <h4>{"Conductor"}</h4>
             { for props.entries.iter().enumerate().map(|(i, entry)| html! {
-                <div class="personnel-row" key={i}>
+                <div class="personnel-row" key={i} role="group" aria-label={format!("Conductor {}", i + 1)}>
                     { conductor_row(props.data.clone(), props.on_data_change.clone(), entry, i, &props.errors) }
-                    <button type="button" class="btn-remove" onclick={remove(i)}>{"削除"}</button>
+                    <button type="button" class="btn-remove" aria-label={format!("Conductor {} を削除", i + 1)} onclick={remove(i)}>{"削除"}</button>
                 </div>
             }) }
-            <button type="button" class="btn-add" onclick={add}>{"追加"}</button>
+            <button type="button" class="btn-add" onclick={add.reform(|_| ())}>{"追加"}</button>
         </div>
     }
 }
 
 #[function_component(OrchestraBlock)]
 fn orchestra_block(props: &PersonnelBlockProps<OrchestraEntry>) -> Html {
-    let add = { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |_| { let mut d = data.clone(); d.personnel.orchestra.push(Default::default()); on_data_change.emit(d); }) };
+    let focus = use_append_focus();
+    let add: Callback<()> = focus.wrap(".personnel-row", { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |()| { let mut d = data.clone(); d.personnel.orchestra.push(Default::default()); on_data_change.emit(d); }) });
     let remove = |i: usize| { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |_| { let mut d = data.clone(); d.personnel.orchestra.remove(i); on_data_change.emit(d); }) };
     html! {
-        <div class="personnel-block">
+        <div class="personnel-block" ref={focus.container.clone()} onkeydown={enter_to_add(add.clone())}>
             <h4>{"Orchestra"}</h4>
             { for props.entries.iter().enumerate().map(|(i, entry)| html! {
-                <div class="personnel-row" key={i}>
+                <div class="personnel-row" key={i} role="group" aria-label={format!("Orchestra {}", i + 1)}>
                     { orchestra_row(props.data.clone(), props.on_data_change.clone(), entry, i, &props.errors) }
-                    <button type="button" class="btn-remove" onclick={remove(i)}>{"削除"}</button>
+                    <button type="button" class="btn-remove" aria-label={format!("Orchestra {} を削除", i + 1)} onclick={remove(i)}>{"削除"}</button>
                 </div>
             }) }
-            <button type="button" class="btn-add" onclick={add}>{"追加"}</button>
+            <button type="button" class="btn-add" onclick={add.reform(|_| ())}>{"追加"}</button>
         </div>
     }
 }
 
 #[function_component(CompanyBlock)]
 fn company_block(props: &PersonnelBlockProps<CompanyEntry>) -> Html {
-    let add = { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |_| { let mut d = data.clone(); d.personnel.company.push(Default::default()); on_data_change.emit(d); }) };
+    let focus = use_append_focus();
+    let add: Callback<()> = focus.wrap(".personnel-row", { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |()| { let mut d = data.clone(); d.personnel.company.push(Default::default()); on_data_change.emit(d); }) });
     let remove = |i: usize| { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |_| { let mut d = data.clone(); d.personnel.company.remove(i); on_data_change.emit(d); }) };
     html! {
-        <div class="personnel-block">
+        <div class="personnel-block" ref={focus.container.clone()} onkeydown={enter_to_add(add.clone())}>
             <h4>{"Company"}</h4>
             { for props.entries.iter().enumerate().map(|(i, entry)| html! {
-                <div class="personnel-row" key={i}>
+                <div class="personnel-row" key={i} role="group" aria-label={format!("Company {}", i + 1)}>
                     { company_row(props.data.clone(), props.on_data_change.clone(), entry, i, &props.errors) }
-                    <button type="button" class="btn-remove" onclick={remove(i)}>{"削除"}</button>
+                    <button type="button" class="btn-remove" aria-label={format!("Company {} を削除", i + 1)} onclick={remove(i)}>{"削除"}</button>
                 </div>
             }) }
-            <button type="button" class="btn-add" onclick={add}>{"追加"}</button>
+            <button type="button" class="btn-add" onclick={add.reform(|_| ())}>{"追加"}</button>
         </div>
     }
 }
 
 #[function_component(SoloistsBlock)]
 fn soloists_block(props: &PersonnelBlockProps<SoloistEntry>) -> Html {
-    let add = { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |_| { let mut d = data.clone(); d.personnel.soloists.push(Default::default()); on_data_change.emit(d); }) };
+    let focus = use_append_focus();
+    let add: Callback<()> = focus.wrap(".personnel-row", { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |()| { let mut d = data.clone(); d.personnel.soloists.push(Default::default()); on_data_change.emit(d); }) });
     let remove = |i: usize| { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |_| { let mut d = data.clone(); d.personnel.soloists.remove(i); on_data_change.emit(d); }) };
     html! {
-        <div class="personnel-block">
+        <div class="personnel-block" ref={focus.container.clone()} onkeydown={enter_to_add(add.clone())}>
             <h4>{"Soloists"}</h4>
             { for props.entries.iter().enumerate().map(|(i, entry)| html! {
-                <div class="personnel-row" key={i}>
+                <div class="personnel-row" key={i} role="group" aria-label={format!("Soloists {}", i + 1)}>
                     { soloist_row(props.data.clone(), props.on_data_change.clone(), entry, i, &props.errors) }
-                    <button type="button" class="btn-remove" onclick={remove(i)}>{"削除"}</button>
+                    <button type="button" class="btn-remove" aria-label={format!("Soloists {} を削除", i + 1)} onclick={remove(i)}>{"削除"}</button>
                 </div>
             }) }
-            <button type="button" class="btn-add" onclick={add}>{"追加"}</button>
+            <button type="button" class="btn-add" onclick={add.reform(|_| ())}>{"追加"}</button>
         </div>
     }
 }
 
 #[function_component(LeaderBlock)]
 fn leader_block(props: &PersonnelBlockProps<LeaderEntry>) -> Html {
-    let add = { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |_| { let mut d = data.clone(); d.personnel.leader.push(Default::default()); on_data_change.emit(d); }) };
+    let focus = use_append_focus();
+    let add: Callback<()> = focus.wrap(".personnel-row", { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |()| { let mut d = data.clone(); d.personnel.leader.push(Default::default()); on_data_change.emit(d); }) });
     let remove = |i: usize| { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |_| { let mut d = data.clone(); d.personnel.leader.remove(i); on_data_change.emit(d); }) };
     html! {
-        <div class="personnel-block">
+        <div class="personnel-block" ref={focus.container.clone()} onkeydown={enter_to_add(add.clone())}>
             <h4>{"Leader"}</h4>
             { for props.entries.iter().enumerate().map(|(i, entry)| html! {
-                <div class="personnel-row" key={i}>
+                <div class="personnel-row" key={i} role="group" aria-label={format!("Leader {}", i + 1)}>
                     { leader_row(props.data.clone(), props.on_data_change.clone(), entry, i, &props.errors) }
-                    <button type="button" class="btn-remove" onclick={remove(i)}>{"削除"}</button>
+                    <button type="button" class="btn-remove" aria-label={format!("Leader {} を削除", i + 1)} onclick={remove(i)}>{"削除"}</button>
                 </div>
             }) }
-            <button type="button" class="btn-add" onclick={add}>{"追加"}</button>
+            <button type="button" class="btn-add" onclick={add.reform(|_| ())}>{"追加"}</button>
         </div>
     }
 }
 
 #[function_component(SidemenBlock)]
 fn sidemen_block(props: &PersonnelBlockProps<SidemenEntry>) -> Html {
-    let add = { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |_| { let mut d = data.clone(); d.personnel.sidemen.push(Default::default()); on_data_change.emit(d); }) };
+    let focus = use_append_focus();
+    let add: Callback<()> = focus.wrap(".personnel-row", { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |()| { let mut d = data.clone(); d.personnel.sidemen.push(Default::default()); on_data_change.emit(d); }) });
     let remove = |i: usize| { let data = props.data.clone(); let on_data_change = props.on_data_change.clone(); Callback::from(move |_| { let mut d = data.clone(); d.personnel.sidemen.remove(i); on_data_change.emit(d); }) };
     html! {
-        <div class="personnel-block">
+        <div class="personnel-block" ref={focus.container.clone()} onkeydown={enter_to_add(add.clone())}>
             <h4>{"Sidemen"}</h4>
             { for props.entries.iter().enumerate().map(|(i, entry)| html! {
-                <div class="personnel-row" key={i}>
+                <div class="personnel-row" key={i} role="group" aria-label={format!("Sidemen {}", i + 1)}>
                     { sidemen_row(props.data.clone(), props.on_data_change.clone(), entry, i, &props.errors) }
-                    <button type="button" class="btn-remove" onclick={remove(i)}>{"削除"}</button>
+                    <button type="button" class="btn-remove" aria-label={format!("Sidemen {} を削除", i + 1)} onclick={remove(i)}>{"削除"}</button>
                 </div>
             }) }
-            <button type="button" class="btn-add" onclick={add}>{"追加"}</button>
+            <button type="button" class="btn-add" onclick={add.reform(|_| ())}>{"追加"}</button>
         </div>
     }
 }
@@ -1037,20 +1321,26 @@ fn group_member_row(
             <span class="input-wrap">
                 <input type="text" placeholder="Name" value={entry.name.clone()}
                     oninput={oninput_group_member(data.clone(), on_data_change.clone(), gi, mi, 0)}
-                    class={if errors.contains_key(&key_name) { "input input-error" } else { "input" }}/>
-                { for err_name.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    class={if errors.contains_key(&key_name) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_name)} aria-describedby={described_by(errors, &key_name)}/>
+                { error_text(&key_name, err_name) }
             </span>
+            { name_alt_field(data.clone(), on_data_change.clone(), &entry.name, &entry.name_alt, &format!("personnel.group[{}].members[{}].name_alt", gi, mi), errors,
+                move |d, v| {
+                    if let Some(m) = d.personnel.group.get_mut(gi).and_then(|g| g.members.get_mut(mi)) {
+                        m.name_alt = v;
+                    }
+                }) }
             <span class="input-wrap">
                 <input type="text" placeholder="Instruments" value={entry.instruments.clone()}
                     oninput={oninput_group_member(data.clone(), on_data_change.clone(), gi, mi, 1)}
-                    class={if errors.contains_key(&key_inst) { "input input-error" } else { "input" }}/>
-                { for err_inst.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    class={if errors.contains_key(&key_inst) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_inst)} aria-describedby={described_by(errors, &key_inst)}/>
+                { error_text(&key_inst, err_inst) }
             </span>
             <span class="input-wrap">
-                <input type="text" placeholder="Tracks" value={entry.tracks.clone()}
+                <input type="text" placeholder="Tracks（不明なら ?）" value={entry.tracks.clone()}
                     oninput={oninput_group_member(data, on_data_change.clone(), gi, mi, 2)}
-                    class={if errors.contains_key(&key_tracks) { "input input-error" } else { "input" }}/>
-                { for err_tracks.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                    class={if errors.contains_key(&key_tracks) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(errors, &key_tracks)} aria-describedby={described_by(errors, &key_tracks)}/>
+                { error_text(&key_tracks, err_tracks) }
             </span>
             <label class="input-wrap group-leader-label">
                 <input type="checkbox" checked={entry.leader} onchange={on_leader_toggle}/>
@@ -1062,10 +1352,11 @@ fn group_member_row(
 
 #[function_component(GroupBlock)]
 fn group_block(props: &GroupBlockProps) -> Html {
+    let focus = use_append_focus();
     let add_group = {
         let data = props.data.clone();
         let on_data_change = props.on_data_change.clone();
-        Callback::from(move |_| {
+        focus.wrap(".group-entry-wrap", Callback::from(move |_: MouseEvent| {
             let mut d = data.clone();
             d.personnel.group.push(GroupEntry {
                 name: String::new(),
@@ -1073,7 +1364,7 @@ fn group_block(props: &GroupBlockProps) -> Html {
                 members: Vec::new(),
             });
             on_data_change.emit(d);
-        })
+        }))
     };
     let remove_group = |gi: usize| {
         let data = props.data.clone();
@@ -1084,16 +1375,16 @@ fn group_block(props: &GroupBlockProps) -> Html {
             on_data_change.emit(d);
         })
     };
-    let add_member = |gi: usize| {
+    let add_member = |gi: usize| -> Callback<()> {
         let data = props.data.clone();
         let on_data_change = props.on_data_change.clone();
-        Callback::from(move |_| {
+        focus.wrap(format!("[data-group=\"{}\"] .group-member-row", gi), Callback::from(move |()| {
             let mut d = data.clone();
             if let Some(g) = d.personnel.group.get_mut(gi) {
                 g.members.push(GroupMemberEntry::default());
             }
             on_data_change.emit(d);
-        })
+        }))
     };
     let remove_member = |gi: usize, mi: usize| {
         let data = props.data.clone();
@@ -1108,7 +1399,7 @@ fn group_block(props: &GroupBlockProps) -> Html {
     };
 
     html! {
-        <div class="personnel-block">
+        <div class="personnel-block" ref={focus.container.clone()}>
             <h4>{"Group"}</h4>
             { for props.entries.iter().enumerate().map(|(gi, g)| {
                 let key_name = format!("personnel.group[{}].name", gi);
@@ -1119,29 +1410,29 @@ fn group_block(props: &GroupBlockProps) -> Html {
                 let on_data_change = props.on_data_change.clone();
                 let errors = props.errors.clone();
                 html! {
-                    <div class="group-entry-wrap" key={gi}>
+                    <div class="group-entry-wrap" key={gi} data-group={gi.to_string()} role="group" aria-label={format!("Group {}", gi + 1)} onkeydown={enter_to_add(add_member(gi))}>
                         <div class="personnel-row">
                             <span class="input-wrap">
                                 <input type="text" placeholder="Group Name" value={g.name.clone()}
                                     oninput={oninput_group(data.clone(), on_data_change.clone(), gi, 0)}
-                                    class={if props.errors.contains_key(&key_name) { "input input-error" } else { "input" }}/>
-                                { for err_name.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                                    class={if props.errors.contains_key(&key_name) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(&props.errors, &key_name)} aria-describedby={described_by(&props.errors, &key_name)}/>
+                                { error_text(&key_name, err_name) }
                             </span>
                             <span class="input-wrap">
                                 <input type="text" placeholder="Abbr" value={g.abbr.clone()}
                                     oninput={oninput_group(data.clone(), on_data_change.clone(), gi, 1)}
-                                    class={if props.errors.contains_key(&key_abbr) { "input input-error" } else { "input" }}/>
-                                { for err_abbr.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                                    class={if props.errors.contains_key(&key_abbr) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(&props.errors, &key_abbr)} aria-describedby={described_by(&props.errors, &key_abbr)}/>
+                                { error_text(&key_abbr, err_abbr) }
                             </span>
-                            <button type="button" class="btn-remove" onclick={remove_group(gi)}>{"グループ削除"}</button>
+                            <button type="button" class="btn-remove" aria-label={format!("Group {} を削除", gi + 1)} onclick={remove_group(gi)}>{"グループ削除"}</button>
                         </div>
                         { for g.members.iter().enumerate().map(|(mi, m)| html! {
-                            <div key={mi} class="group-member-row">
+                            <div key={mi} class="group-member-row" role="group" aria-label={format!("Group {} Member {}", gi + 1, mi + 1)}>
                                 { group_member_row(data.clone(), on_data_change.clone(), m, gi, mi, &errors) }
-                                <button type="button" class="btn-remove" onclick={remove_member(gi, mi)}>{"削除"}</button>
+                                <button type="button" class="btn-remove" aria-label={format!("Group {} Member {} を削除", gi + 1, mi + 1)} onclick={remove_member(gi, mi)}>{"削除"}</button>
                             </div>
                         }) }
-                        <button type="button" class="btn-add btn-add-member" onclick={add_member(gi)}>{"メンバー追加"}</button>
+                        <button type="button" class="btn-add btn-add-member" onclick={add_member(gi).reform(|_| ())}>{"メンバー追加"}</button>
                     </div>
                 }
             }) }
@@ -1158,22 +1449,98 @@ struct TracksSectionProps {
     errors: FieldErrors,
 }
 
+/// 末尾に空のトラックを足す（ディスク番号・曲番号は前の行から続ける）
+fn push_track(d: &mut MusicData) {
+    let (disc_no, no) = disc_and_track_no_for_append(&d.tracks);
+    d.tracks.push(Track {
+        disc_no,
+        no,
+        title: String::new(),
+        composer: String::new(),
+        length: String::new(),
+    });
+}
+
 #[function_component(TracksSection)]
 fn tracks_section(props: &TracksSectionProps) -> Html {
-    let add = {
+    let focus = use_append_focus();
+    let add: Callback<()> = {
         let data = props.data.clone();
         let on_data_change = props.on_data_change.clone();
-        Callback::from(move |_| {
+        focus.wrap(".track-row", Callback::from(move |()| {
             let mut d = data.clone();
-            let (disc_no, no) = disc_and_track_no_for_append(&d.tracks);
-            d.tracks.push(Track {
-                disc_no,
-                no,
-                title: String::new(),
-                composer: String::new(),
-                length: String::new(),
-            });
+            push_track(&mut d);
+            on_data_change.emit(d);
+        }))
+    };
+    // 最後の行の長さで Enter: 長さを整えて行を足し、新しい行の長さへ移る（CD プレーヤーの表示を続けて打ち込めるように）
+    let add_after_length: Callback<String> = {
+        let data = props.data.clone();
+        let on_data_change = props.on_data_change.clone();
+        focus.wrap(".track-length", Callback::from(move |length: String| {
+            let mut d = data.clone();
+            if let Some(t) = d.tracks.last_mut() {
+                t.length = length;
+            }
+            push_track(&mut d);
             on_data_change.emit(d);
+        }))
+    };
+    // 長さの欄: blur で "433" → "4:33" に整え、Enter で次の行の長さへ移る
+    let set_length = |i: usize| {
+        let data = props.data.clone();
+        let on_data_change = props.on_data_change.clone();
+        move |inp: &web_sys::HtmlInputElement| -> String {
+            let formatted = normalize_length(&inp.value());
+            if formatted != inp.value() {
+                inp.set_value(&formatted);
+                let mut d = data.clone();
+                if let Some(t) = d.tracks.get_mut(i) {
+                    t.length = formatted.clone();
+                }
+                on_data_change.emit(d);
+            }
+            formatted
+        }
+    };
+    let on_length_blur = |i: usize| {
+        let set_length = set_length(i);
+        Callback::from(move |e: FocusEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                set_length(&inp);
+            }
+        })
+    };
+    let on_length_key = |i: usize| {
+        let set_length = set_length(i);
+        let add_after_length = add_after_length.clone();
+        let container = focus.container.clone();
+        let last = i + 1 == props.data.tracks.len();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() != "Enter" || e.is_composing() || e.shift_key() || e.ctrl_key() || e.meta_key() {
+                return;
+            }
+            // enter_to_add（セクション全体の Enter）より先に処理する
+            e.prevent_default();
+            let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() else {
+                return;
+            };
+            if last {
+                let formatted = normalize_length(&inp.value());
+                inp.set_value(&formatted);
+                add_after_length.emit(formatted);
+                return;
+            }
+            set_length(&inp);
+            let next = container
+                .cast::<web_sys::Element>()
+                .and_then(|c| c.query_selector_all(".track-length input").ok())
+                .and_then(|list| list.get(i as u32 + 1))
+                .and_then(|n| n.dyn_into::<web_sys::HtmlInputElement>().ok());
+            if let Some(next) = next {
+                let _ = next.focus();
+                next.select();
+            }
         })
     };
     let remove = |i: usize| {
@@ -1187,11 +1554,65 @@ fn tracks_section(props: &TracksSectionProps) -> Html {
             }
         })
     };
+    // 合計時間しか分からないとき: 曲数に合わせて行を揃え、仮の長さを割り振るか「不明（?）」にする
+    let split_total = use_state(String::new);
+    let split_count = use_state(|| props.data.tracks.len().max(1).to_string());
+    let apply_lengths = |even: bool| {
+        let data = props.data.clone();
+        let on_data_change = props.on_data_change.clone();
+        let split_total = split_total.clone();
+        let split_count = split_count.clone();
+        Callback::from(move |_: MouseEvent| {
+            let Some(count) = split_count.trim().parse::<usize>().ok().filter(|n| (1..=999).contains(n)) else {
+                return;
+            };
+            let lengths = if even {
+                let Some(total) = parse_length(&normalize_length(&split_total)) else {
+                    return;
+                };
+                split_total_length(total, count)
+            } else {
+                vec![crate::rules::UNKNOWN_LENGTH.to_string(); count]
+            };
+            let mut d = data.clone();
+            while d.tracks.len() < count {
+                push_track(&mut d);
+            }
+            for (t, len) in d.tracks.iter_mut().zip(lengths) {
+                t.length = len;
+            }
+            on_data_change.emit(d);
+        })
+    };
+    let text_input = |state: &UseStateHandle<String>| {
+        let state = state.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                state.set(inp.value());
+            }
+        })
+    };
     let tracks_section_err = props.errors.get("tracks").cloned();
     html! {
-        <div class="form-section">
+        <div class="form-section" ref={focus.container.clone()} onkeydown={enter_to_add(add.clone())}>
             <h3>{"Tracks"}</h3>
-            { for tracks_section_err.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+            { error_text("tracks", tracks_section_err) }
+            <details class="length-split">
+                <summary>{"合計時間から長さを入れる"}</summary>
+                <div class="settings-inline">
+                    <label>{"合計 "}
+                        <input type="text" class="input track-no" placeholder="42:10" inputmode="numeric"
+                            value={(*split_total).clone()} oninput={text_input(&split_total)}/>
+                    </label>
+                    <label>{"曲数 "}
+                        <input type="number" class="input track-no" min="1" max="999"
+                            value={(*split_count).clone()} oninput={text_input(&split_count)}/>
+                    </label>
+                    <button type="button" class="btn-add" onclick={apply_lengths(true)}>{"均等に割り振る（仮）"}</button>
+                    <button type="button" class="btn-add" onclick={apply_lengths(false)}>{"すべて不明（?）にする"}</button>
+                </div>
+                <span class="hint">{"曲数が今の行数より多ければ行を足します。仮の長さは後で分かったら上書きしてください。"}</span>
+            </details>
             { for props.data.tracks.iter().enumerate().map(|(i, t)| {
                 let can_remove_track = props.data.tracks.len() > 1;
                 let key_title = format!("tracks[{}].title", i);
@@ -1203,29 +1624,32 @@ fn tracks_section(props: &TracksSectionProps) -> Html {
                 let data = props.data.clone();
                 let on_data_change = props.on_data_change.clone();
                 html! {
-                    <div class="track-row" key={i}>
+                    <div class="track-row" key={i} role="group" aria-label={format!("Track {}", i + 1)}>
                         <span>{"Disc No:"}</span><input type="number" class="input track-no" placeholder="Disc" value={t.disc_no.to_string()}
                             oninput={update_track_field(data.clone(), on_data_change.clone(), i, 0)}/>
                         <span>{"Track No:"}</span><input type="number" class="input track-no" placeholder="No" value={t.no.to_string()}
                             oninput={update_track_field(data.clone(), on_data_change.clone(), i, 1)}/>
                         <span class="input-wrap">
-                            <input type="text" class={if props.errors.contains_key(&key_title) { "input input-error" } else { "input" }} placeholder="Title" value={t.title.clone()}
+                            <input type="text" class={if props.errors.contains_key(&key_title) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(&props.errors, &key_title)} aria-describedby={described_by(&props.errors, &key_title)} placeholder="Title" value={t.title.clone()}
                                 oninput={update_track_field_str(data.clone(), on_data_change.clone(), i, 2)}/>
-                            { for err_title.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                            { error_text(&key_title, err_title) }
                         </span>
                         <span class="input-wrap">
-                            <input type="text" class={if props.errors.contains_key(&key_composer) { "input input-error" } else { "input" }} placeholder="Composer" value={t.composer.clone()}
+                            <input type="text" class={if props.errors.contains_key(&key_composer) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(&props.errors, &key_composer)} aria-describedby={described_by(&props.errors, &key_composer)} placeholder="Composer" value={t.composer.clone()}
                                 oninput={update_track_field_str(data.clone(), on_data_change.clone(), i, 3)}/>
-                            { for err_composer.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                            { error_text(&key_composer, err_composer) }
                         </span>
-                        <span class="input-wrap">
-                            <input type="text" class={if props.errors.contains_key(&key_length) { "input input-error" } else { "input" }} placeholder="Length (MM:SS or M:SS)" value={t.length.clone()}
-                                oninput={update_track_field_str(data.clone(), on_data_change.clone(), i, 4)}/>
-                            { for err_length.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                        <span class="input-wrap track-length">
+                            <input type="text" inputmode="numeric" class={if props.errors.contains_key(&key_length) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(&props.errors, &key_length)} aria-describedby={described_by(&props.errors, &key_length)} placeholder="Length (433 → 4:33)" value={t.length.clone()}
+                                oninput={update_track_field_str(data.clone(), on_data_change.clone(), i, 4)}
+                                onblur={on_length_blur(i)}
+                                onkeydown={on_length_key(i)}/>
+                            { error_text(&key_length, err_length) }
                         </span>
                         <button
                             type="button"
                             class="btn-remove"
+                            aria-label={format!("Track {} を削除", i + 1)}
                             disabled={!can_remove_track}
                             onclick={remove(i)}
                         >
@@ -1234,7 +1658,7 @@ fn tracks_section(props: &TracksSectionProps) -> Html {
                     </div>
                 }
             }) }
-            <button type="button" class="btn-add" onclick={add}>{"トラック追加"}</button>
+            <button type="button" class="btn-add" onclick={add.reform(|_| ())}>{"トラック追加"}</button>
         </div>
     }
 }
@@ -1287,14 +1711,15 @@ struct ReferencesSectionProps {
 
 #[function_component(ReferencesSection)]
 fn references_section(props: &ReferencesSectionProps) -> Html {
-    let add = {
+    let focus = use_append_focus();
+    let add: Callback<()> = {
         let data = props.data.clone();
         let on_data_change = props.on_data_change.clone();
-        Callback::from(move |_| {
+        focus.wrap(".ref-row", Callback::from(move |()| {
             let mut d = data.clone();
             d.references.push(Reference::default());
             on_data_change.emit(d);
-        })
+        }))
     };
     let remove = |i: usize| {
         let data = props.data.clone();
@@ -1306,7 +1731,7 @@ fn references_section(props: &ReferencesSectionProps) -> Html {
         })
     };
     html! {
-        <div class="form-section">
+        <div class="form-section" ref={focus.container.clone()} onkeydown={enter_to_add(add.clone())}>
             <h3>{"References"}</h3>
             { for props.data.references.iter().enumerate().map(|(i, r)| {
                 let key_name = format!("references[{}].name", i);
@@ -1314,22 +1739,22 @@ fn references_section(props: &ReferencesSectionProps) -> Html {
                 let err_name = props.errors.get(&key_name).cloned();
                 let err_url = props.errors.get(&key_url).cloned();
                 html! {
-                    <div class="ref-row" key={i}>
+                    <div class="ref-row" key={i} role="group" aria-label={format!("Reference {}", i + 1)}>
                         <span class="input-wrap">
-                            <input type="text" class={if props.errors.contains_key(&key_name) { "input input-error" } else { "input" }} placeholder="Name" value={r.name.clone()}
+                            <input type="text" class={if props.errors.contains_key(&key_name) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(&props.errors, &key_name)} aria-describedby={described_by(&props.errors, &key_name)} placeholder="Name" value={r.name.clone()}
                                 oninput={update_ref(props.data.clone(), props.on_data_change.clone(), i, true)}/>
-                            { for err_name.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                            { error_text(&key_name, err_name) }
                         </span>
                         <span class="input-wrap">
-                            <input type="text" class={if props.errors.contains_key(&key_url) { "input input-error" } else { "input" }} placeholder="URL" value={r.url.clone()}
+                            <input type="text" class={if props.errors.contains_key(&key_url) { "input input-error" } else { "input" }} aria-invalid={aria_invalid(&props.errors, &key_url)} aria-describedby={described_by(&props.errors, &key_url)} placeholder="URL" value={r.url.clone()}
                                 oninput={update_ref(props.data.clone(), props.on_data_change.clone(), i, false)}/>
-                            { for err_url.into_iter().map(|e| html! { <span class="error-text">{ e }</span> }) }
+                            { error_text(&key_url, err_url) }
                         </span>
-                        <button type="button" class="btn-remove" onclick={remove(i)}>{"削除"}</button>
+                        <button type="button" class="btn-remove" aria-label={format!("Reference {} を削除", i + 1)} onclick={remove(i)}>{"削除"}</button>
                     </div>
                 }
             }) }
-            <button type="button" class="btn-add" onclick={add}>{"参照追加"}</button>
+            <button type="button" class="btn-add" onclick={add.reform(|_| ())}>{"参照追加"}</button>
         </div>
     }
 }
@@ -1351,3 +1776,71 @@ fn update_ref(data: MusicData, on_data_change: Callback<MusicData>, idx: usize,
         }
     })
 }
+
+#[cfg(test)]
+mod form_tests {
+    use super::{
+        normalize_length, parse_composers, parse_record_years, record_json_filename, split_total_length,
+        suggested_filename_on_focus,
+    };
+    use crate::types::{Janre, MusicData, Personnel, SoloistEntry};
+
+    #[test]
+    fn lengths_get_a_colon() {
+        assert_eq!(normalize_length("433"), "4:33");
+        assert_eq!(normalize_length(" 1205 "), "12:05");
+        assert_eq!(normalize_length("45"), "0:45");
+        assert_eq!(normalize_length("7"), "0:07");
+        assert_eq!(normalize_length("4.33"), "4:33");
+        assert_eq!(normalize_length("４：３３"), "4:33");
+        assert_eq!(normalize_length("04:33"), "4:33");
+        assert_eq!(normalize_length("4:33"), "4:33");
+        // 整えられないものはそのまま（バリデーションで知らせる）
+        assert_eq!(normalize_length("475"), "475");
+        assert_eq!(normalize_length("4:3"), "4:3");
+        assert_eq!(normalize_length("?"), "?");
+        assert_eq!(normalize_length(""), "");
+    }
+
+    #[test]
+    fn total_time_splits_evenly() {
+        assert_eq!(split_total_length(2530, 3), vec!["14:04", "14:03", "14:03"]);
+        assert_eq!(split_total_length(60, 1), vec!["1:00"]);
+        assert!(split_total_length(100, 0).is_empty());
+    }
+
+    #[test]
+    fn record_years_accept_unknown() {
+        assert_eq!(parse_record_years("1959, 1960,"), (vec![1959, 1960], false));
+        assert_eq!(parse_record_years(" ? "), (vec![], true));
+        assert_eq!(parse_record_years(""), (vec![], false));
+    }
+
+    #[test]
+    fn classical_filename_prefers_the_composer() {
+        assert_eq!(parse_composers(" Schoenberg | | Webern "), vec!["Schoenberg", "Webern"]);
+        let mut d = MusicData {
+            title: "Piano Concerto 1".into(),
+            janre: Janre { main: "Classical".into(), sub: vec![] },
+            personnel: Personnel {
+                soloists: vec![SoloistEntry { name: "Martha Argerich".into(), ..Default::default() }],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(suggested_filename_on_focus(&d, "").as_deref(), Some("Martha_Argerich"));
+        d.composers = vec!["Chopin".into(), "Liszt".into()];
+        assert_eq!(suggested_filename_on_focus(&d, "").as_deref(), Some("Chopin__Piano_Concerto_1"));
+        // パターンの項目（楽器）が空ならいつもの規則
+        let pattern = "{soloist_last}_{instrument}__{composer}_{title}";
+        assert_eq!(suggested_filename_on_focus(&d, pattern).as_deref(), Some("Chopin__Piano_Concerto_1"));
+        d.personnel.soloists[0].instrument = "piano".into();
+        assert_eq!(suggested_filename_on_focus(&d, pattern).as_deref(), Some("Argerich_p__Chopin_Piano_Concerto_1"));
+
+        // JSON のダウンロードは入力中のファイル名 → 開いているもの → 候補
+        assert_eq!(record_json_filename(&d, " My Copy.json ", Some("a.json"), ""), "My_Copy.json");
+        assert_eq!(record_json_filename(&d, "", Some("a.json"), ""), "a.json");
+        assert_eq!(record_json_filename(&d, "", None, ""), "Chopin__Piano_Concerto_1.json");
+        assert_eq!(record_json_filename(&MusicData::default(), "", None, ""), "record.json");
+    }
+}
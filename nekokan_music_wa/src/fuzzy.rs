@@ -0,0 +1,77 @@
+//! 入力文字列と表示ラベルのあいまい照合（クイック追加の候補表示などに使う）。
+
+/// 比較用に正規化する。小文字化し、英数字・かな・漢字以外は空白にして連続空白を詰める。
+#[must_use]
+pub fn normalize(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_alphanumeric() { c.to_lowercase().next().unwrap_or(c) } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `query` が `label` にどれだけ合うかのスコア（大きいほど良い）。合わなければ None。
+/// 完全一致 > 全単語を含む > 文字が順番どおりに現れる（部分列）の順に高い。
+#[must_use]
+pub fn match_score(query: &str, label: &str) -> Option<u32> {
+    let q = normalize(query);
+    let l = normalize(label);
+    if q.is_empty() {
+        return None;
+    }
+    if q == l {
+        return Some(3000);
+    }
+    let words: Vec<&str> = q.split(' ').collect();
+    if words.iter().all(|w| l.contains(w)) {
+        let matched: usize = words.iter().map(|w| w.chars().count()).sum();
+        return Some(2000 + matched as u32);
+    }
+    let mut label_chars = l.chars().filter(|c| *c != ' ');
+    let query_chars: Vec<char> = q.chars().filter(|c| *c != ' ').collect();
+    if query_chars.iter().all(|qc| label_chars.any(|lc| lc == *qc)) {
+        return Some(1000 + query_chars.len() as u32);
+    }
+    None
+}
+
+/// `items` から `query` に合うものをスコア順に最大 `limit` 件返す。
+pub fn best_matches<'a, T, F>(query: &str, items: &'a [T], label: F, limit: usize) -> Vec<&'a T>
+where
+    F: Fn(&T) -> &str,
+{
+    let mut scored: Vec<(u32, &T)> = items
+        .iter()
+        .filter_map(|item| match_score(query, label(item)).map(|s| (s, item)))
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().take(limit).map(|(_, item)| item).collect()
+}
+
+#[cfg(test)]
+mod fuzzy_tests {
+    use super::{best_matches, match_score, normalize};
+
+    #[test]
+    fn normalize_strips_punctuation_and_case() {
+        assert_eq!(normalize("Bill Evans: Alone!"), "bill evans alone");
+        assert_eq!(normalize("  Taylor's  Wailers "), "taylor s wailers");
+    }
+
+    #[test]
+    fn word_match_beats_subsequence() {
+        let word = match_score("evans alone", "Bill Evans: Alone").unwrap();
+        let subseq = match_score("bevn", "Bill Evans: Alone").unwrap();
+        assert!(word > subseq);
+        assert_eq!(match_score("coltrane", "Bill Evans: Alone"), None);
+    }
+
+    #[test]
+    fn best_matches_orders_by_score() {
+        let labels = ["Bill Evans: Alone", "Bill Evans: Alone (Again)", "Art Blakey: Moanin'"];
+        let hits = best_matches("bill evans alone", &labels, |s| s, 5);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(*hits[0], "Bill Evans: Alone");
+    }
+}
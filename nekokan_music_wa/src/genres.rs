@@ -0,0 +1,271 @@
+use crate::api::{self, GenreMapping, GenreMigrateReport, GenreUsage, JanreValue};
+use crate::types::{sub_janres_for_main, MAIN_JANRES};
+use std::collections::BTreeSet;
+use yew::prelude::*;
+
+/// "Fusion / Funk, Rock" の形で表示する。
+pub fn janre_text(j: &JanreValue) -> String {
+    if j.sub.is_empty() {
+        format!("{} / （Sub なし）", j.main)
+    } else {
+        format!("{} / {}", j.main, j.sub.join(", "))
+    }
+}
+
+/// 付け替え 1 行の説明
+pub fn mapping_text(m: &GenreMapping) -> String {
+    let from = if m.from_sub.trim().is_empty() {
+        m.from_main.trim().to_string()
+    } else {
+        format!("{} / {}", m.from_main.trim(), m.from_sub.trim())
+    };
+    let main = if m.to_main.trim().is_empty() { m.from_main.trim() } else { m.to_main.trim() };
+    let to = match (m.from_sub.trim().is_empty(), m.to_sub.trim()) {
+        (true, _) => main.to_string(),
+        (false, "") => format!("{}（{} を外す）", main, m.from_sub.trim()),
+        (false, sub) => format!("{} / {}", main, sub),
+    };
+    format!("{} → {}", from, to)
+}
+
+/// 付け替え元が入っている行だけ
+fn active_mappings(list: &[GenreMapping]) -> Vec<GenreMapping> {
+    list.iter().filter(|m| !m.from_main.trim().is_empty()).cloned().collect()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct GenreMigrationProps {
+    /// レコード名クリックでそのレコードを開く（"xxx.json"）
+    pub on_select: Callback<String>,
+}
+
+/// ジャンルの付け替え。旧ジャンルごとの該当レコードを見ながら対応表を作り、dry-run で確かめてから適用する。
+#[function_component(GenreMigration)]
+pub fn genre_migration(props: &GenreMigrationProps) -> Html {
+    let usage = use_state(|| None::<Result<Vec<GenreUsage>, String>>);
+    let mappings = use_state(|| vec![GenreMapping::default()]);
+    let report = use_state(|| None::<Result<GenreMigrateReport, String>>);
+    // いま表示している dry-run の結果がどの対応表のものか（対応表を変えたら適用できなくする）
+    let previewed = use_state(|| None::<Vec<GenreMapping>>);
+    let busy = use_state(|| false);
+
+    let reload = {
+        let usage = usage.clone();
+        Callback::from(move |()| {
+            let usage = usage.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                usage.set(Some(api::genre_usage().await));
+            });
+        })
+    };
+    {
+        let reload = reload.clone();
+        use_effect_with((), move |_| {
+            reload.emit(());
+            || ()
+        });
+    }
+
+    let run = |dry_run: bool| {
+        let mappings = mappings.clone();
+        let report = report.clone();
+        let previewed = previewed.clone();
+        let busy = busy.clone();
+        let reload = reload.clone();
+        Callback::from(move |_: MouseEvent| {
+            let list = active_mappings(&mappings);
+            if list.is_empty() {
+                report.set(Some(Err("付け替え元の Main を入力してください".into())));
+                return;
+            }
+            let (report, previewed, busy, reload) = (report.clone(), previewed.clone(), busy.clone(), reload.clone());
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                let res = api::migrate_genres(&list, dry_run).await;
+                previewed.set((dry_run && res.is_ok()).then_some(list));
+                if !dry_run && res.is_ok() {
+                    reload.emit(());
+                }
+                report.set(Some(res));
+                busy.set(false);
+            });
+        })
+    };
+
+    let update = |i: usize, field: fn(&mut GenreMapping) -> &mut String| {
+        let mappings = mappings.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                let mut list = (*mappings).clone();
+                if let Some(m) = list.get_mut(i) {
+                    *field(m) = inp.value();
+                }
+                mappings.set(list);
+            }
+        })
+    };
+    let remove = |i: usize| {
+        let mappings = mappings.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut list = (*mappings).clone();
+            list.remove(i);
+            if list.is_empty() {
+                list.push(GenreMapping::default());
+            }
+            mappings.set(list);
+        })
+    };
+    let add = {
+        let mappings = mappings.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut list = (*mappings).clone();
+            list.push(GenreMapping::default());
+            mappings.set(list);
+        })
+    };
+    // 一覧の行から付け替え元を入れる（空の行があればそこへ）
+    let use_as_source = |u: &GenreUsage| {
+        let mappings = mappings.clone();
+        let (main, sub) = (u.main.clone(), u.sub.clone());
+        Callback::from(move |_: MouseEvent| {
+            let mut list = (*mappings).clone();
+            let m = GenreMapping { from_main: main.clone(), from_sub: sub.clone(), ..Default::default() };
+            match list.iter_mut().find(|m| m.from_main.trim().is_empty()) {
+                Some(empty) => *empty = m,
+                None => list.push(m),
+            }
+            mappings.set(list);
+        })
+    };
+
+    let all_subs: BTreeSet<&str> = MAIN_JANRES
+        .iter()
+        .flat_map(|m| sub_janres_for_main(m).iter().copied())
+        .collect();
+    let active = active_mappings(&mappings);
+    let can_apply = !*busy && previewed.as_ref() == Some(&active);
+
+    let record_link = |r: &api::RecordRef| {
+        let on_select = props.on_select.clone();
+        let filename = r.filename.clone();
+        html! {
+            <a href="#" onclick={move |e: MouseEvent| { e.prevent_default(); on_select.emit(filename.clone()); }}>
+                { r.display_label.clone() }
+            </a>
+        }
+    };
+
+    html! {
+        <div class="genre-migration">
+            <h2 class="view-title">{"ジャンルの付け替え"}</h2>
+            <datalist id="genre-main-options">
+                { for MAIN_JANRES.iter().map(|&g| html! { <option value={g} /> }) }
+            </datalist>
+            <datalist id="genre-sub-options">
+                { for all_subs.iter().map(|&g| html! { <option value={g} /> }) }
+            </datalist>
+            <div class="form-section">
+                <h3>{"いまのジャンル"}</h3>
+                { match &*usage {
+                    None => html! { <p class="sidebar-loading">{"読込中..."}</p> },
+                    Some(Err(e)) => html! { <p class="load-err">{"ロードエラー: "}{ e.clone() }</p> },
+                    Some(Ok(list)) => html! {
+                        <ul class="genre-usage-list">
+                            { for list.iter().map(|u| html! {
+                                <li class="genre-usage-item">
+                                    <details>
+                                        <summary>
+                                            { format!("{} / {}", u.main, if u.sub.is_empty() { "（Sub なし）" } else { u.sub.as_str() }) }
+                                            <span class="report-count">{ format!("{} 件", u.records.len()) }</span>
+                                        </summary>
+                                        <ul class="related-list">
+                                            { for u.records.iter().map(|r| html! { <li>{ record_link(r) }</li> }) }
+                                        </ul>
+                                    </details>
+                                    <button type="button" class="btn-add" onclick={use_as_source(u)}>{"付け替え元にする"}</button>
+                                </li>
+                            }) }
+                        </ul>
+                    },
+                } }
+            </div>
+            <div class="form-section">
+                <h3>{"対応表"}</h3>
+                <p class="hint">{"上から順に、最初に当てはまった行だけを使います。付け替え元の Sub を空にすると Main だけで当て、付け替え先の Main を空にすると Main はそのまま、Sub を空にすると付け替え元の Sub を外します。"}</p>
+                { for mappings.iter().enumerate().map(|(i, m)| html! {
+                    <div class="genre-mapping-row" key={i} role="group" aria-label={format!("対応 {}", i + 1)}>
+                        <input type="text" class="input" list="genre-main-options" aria-label="付け替え元の Main" placeholder="元 Main"
+                            value={m.from_main.clone()} oninput={update(i, |m| &mut m.from_main)}/>
+                        <input type="text" class="input" list="genre-sub-options" aria-label="付け替え元の Sub" placeholder="元 Sub（任意）"
+                            value={m.from_sub.clone()} oninput={update(i, |m| &mut m.from_sub)}/>
+                        <span aria-hidden="true">{"→"}</span>
+                        <input type="text" class="input" list="genre-main-options" aria-label="付け替え先の Main" placeholder="先 Main（任意）"
+                            value={m.to_main.clone()} oninput={update(i, |m| &mut m.to_main)}/>
+                        <input type="text" class="input" list="genre-sub-options" aria-label="付け替え先の Sub" placeholder="先 Sub（任意）"
+                            value={m.to_sub.clone()} oninput={update(i, |m| &mut m.to_sub)}/>
+                        <button type="button" class="btn-remove" aria-label={format!("対応 {} を削除", i + 1)} onclick={remove(i)}>{"削除"}</button>
+                    </div>
+                }) }
+                <button type="button" class="btn-add" onclick={add}>{"行を追加"}</button>
+            </div>
+            <div class="genre-actions">
+                <button type="button" class="btn-add" onclick={run(true)} disabled={*busy}>{"確認（dry-run）"}</button>
+                <button type="button" class="btn-save" onclick={run(false)} disabled={!can_apply}
+                    title="確認したあと対応表を変えていなければ適用できます">{"適用"}</button>
+            </div>
+            { match &*report {
+                None => html! {},
+                Some(Err(e)) => html! { <p class="save-err" role="alert">{ e.clone() }</p> },
+                Some(Ok(r)) => html! {
+                    <div class="form-section genre-report" role="status">
+                        <h3>{ if r.dry_run {
+                            format!("確認: {} 件が変わります", r.changes.len())
+                        } else {
+                            format!("適用しました: {} 件", r.changes.len())
+                        } }</h3>
+                        if r.dry_run {
+                            <ul class="hint">
+                                { for active.iter().map(|m| html! { <li>{ mapping_text(m) }</li> }) }
+                            </ul>
+                        }
+                        <ul class="related-list">
+                            { for r.changes.iter().map(|c| html! {
+                                <li>
+                                    { record_link(&api::RecordRef { filename: c.filename.clone(), display_label: c.display_label.clone() }) }
+                                    <span class="hint">{ format!("{} → {}", janre_text(&c.before), janre_text(&c.after)) }</span>
+                                    if c.sub_emptied {
+                                        <span class="save-err">{"Sub が空になります"}</span>
+                                    }
+                                </li>
+                            }) }
+                        </ul>
+                        if !r.failed.is_empty() {
+                            <ul class="error-list">
+                                { for r.failed.iter().map(|(f, e)| html! { <li class="error-item">{ format!("{}: {}", f, e) }</li> }) }
+                            </ul>
+                        }
+                    </div>
+                },
+            } }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod genres_tests {
+    use super::mapping_text;
+    use crate::api::GenreMapping;
+
+    #[test]
+    fn mapping_descriptions() {
+        let split = GenreMapping {
+            from_main: "Fusion".into(),
+            from_sub: "Rock".into(),
+            to_main: "Fusion Rock".into(),
+            to_sub: "Rock".into(),
+        };
+        assert_eq!(mapping_text(&split), "Fusion / Rock → Fusion Rock / Rock");
+        let remove = GenreMapping { from_main: "Jazz".into(), from_sub: "Mode".into(), ..Default::default() };
+        assert_eq!(mapping_text(&remove), "Jazz / Mode → Jazz（Mode を外す）");
+    }
+}
@@ -0,0 +1,247 @@
+use crate::api::{self, Goal, GoalKind, GoalProgress};
+use crate::app::today_str;
+use crate::types::MAIN_JANRES;
+use yew::prelude::*;
+
+/// 目標の条件を一行で説明する。
+pub fn goal_condition(kind: &GoalKind) -> String {
+    match kind {
+        GoalKind::Listen { target, from, to } => format!(
+            "{} 〜 {} に {} 枚聴く",
+            from.as_deref().unwrap_or("（最初）"),
+            to.as_deref().unwrap_or("（最新）"),
+            target
+        ),
+        GoalKind::Complete { score, genre } => {
+            let mut cond: Vec<String> = Vec::new();
+            if let Some(s) = score {
+                cond.push(format!("スコア {}", crate::validation::format_score(*s)));
+            }
+            if let Some(g) = genre {
+                cond.push(g.clone());
+            }
+            if cond.is_empty() {
+                "すべてのレコードを完成度 100% にする".to_string()
+            } else {
+                format!("{} のレコードを完成度 100% にする", cond.join("・"))
+            }
+        }
+    }
+}
+
+fn input_value(e: &InputEvent) -> String {
+    e.target_dyn_into::<web_sys::HtmlInputElement>()
+        .map(|i| i.value())
+        .unwrap_or_default()
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    let s = s.trim();
+    (!s.is_empty()).then(|| s.to_string())
+}
+
+/// 目標の一覧（進捗つき）と追加フォーム。
+#[function_component(GoalsView)]
+pub fn goals_view() -> Html {
+    let goals = use_state(|| None::<Result<Vec<GoalProgress>, String>>);
+    let save_error = use_state(|| None::<String>);
+    let year = today_str()[..4].to_string();
+    let title = use_state(String::new);
+    let kind = use_state(|| "listen".to_string());
+    let target = use_state(|| "100".to_string());
+    let from = use_state(|| format!("{}/01/01", year));
+    let to = use_state(|| format!("{}/12/31", year));
+    let score = use_state(String::new);
+    let genre = use_state(String::new);
+
+    let reload = {
+        let goals = goals.clone();
+        Callback::from(move |()| {
+            let goals = goals.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                goals.set(Some(api::get_goals().await));
+            });
+        })
+    };
+    {
+        let reload = reload.clone();
+        use_effect_with((), move |_| {
+            reload.emit(());
+            || ()
+        });
+    }
+
+    // 一覧を置き換えて保存し、進捗を読み直す
+    let save = {
+        let save_error = save_error.clone();
+        let reload = reload.clone();
+        Callback::from(move |list: Vec<Goal>| {
+            let save_error = save_error.clone();
+            let reload = reload.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::save_goals(&list).await {
+                    Ok(()) => {
+                        save_error.set(None);
+                        reload.emit(());
+                    }
+                    Err(e) => save_error.set(Some(e)),
+                }
+            });
+        })
+    };
+    let current: Vec<Goal> = match &*goals {
+        Some(Ok(list)) => list.iter().map(|g| g.goal.clone()).collect(),
+        _ => vec![],
+    };
+
+    let on_add = {
+        let save = save.clone();
+        let save_error = save_error.clone();
+        let current = current.clone();
+        let (title, kind, target, from, to, score, genre) =
+            (title.clone(), kind.clone(), target.clone(), from.clone(), to.clone(), score.clone(), genre.clone());
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let goal_kind = if *kind == "listen" {
+                let Ok(n) = target.trim().parse::<usize>() else {
+                    save_error.set(Some("枚数は数字で入力してください".into()));
+                    return;
+                };
+                GoalKind::Listen { target: n, from: non_empty(&from), to: non_empty(&to) }
+            } else {
+                let score = match non_empty(&score).map(|s| s.parse::<f64>()) {
+                    Some(Ok(s)) => Some(s),
+                    Some(Err(_)) => {
+                        save_error.set(Some("スコアは数字で入力してください".into()));
+                        return;
+                    }
+                    None => None,
+                };
+                GoalKind::Complete { score, genre: non_empty(&genre) }
+            };
+            let title_val = non_empty(&title).unwrap_or_else(|| goal_condition(&goal_kind));
+            let mut list = current.clone();
+            list.push(Goal { title: title_val, kind: goal_kind });
+            title.set(String::new());
+            save.emit(list);
+        })
+    };
+
+    let remove = |i: usize| {
+        let save = save.clone();
+        let current = current.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut list = current.clone();
+            list.remove(i);
+            save.emit(list);
+        })
+    };
+
+    let bind = |state: &UseStateHandle<String>| {
+        let state = state.clone();
+        Callback::from(move |e: InputEvent| state.set(input_value(&e)))
+    };
+    let on_kind = {
+        let kind = kind.clone();
+        Callback::from(move |e: Event| {
+            if let Some(sel) = e.target_dyn_into::<web_sys::HtmlSelectElement>() {
+                kind.set(sel.value());
+            }
+        })
+    };
+    let on_genre = {
+        let genre = genre.clone();
+        Callback::from(move |e: Event| {
+            if let Some(sel) = e.target_dyn_into::<web_sys::HtmlSelectElement>() {
+                genre.set(sel.value());
+            }
+        })
+    };
+
+    html! {
+        <div class="goals-view">
+            <h2 class="view-title">{"目標"}</h2>
+            { match &*goals {
+                None => html! { <p class="sidebar-loading">{"読込中..."}</p> },
+                Some(Err(e)) => html! { <p class="load-err">{"ロードエラー: "}{ e.clone() }</p> },
+                Some(Ok(list)) if list.is_empty() => html! { <p class="hint">{"目標はまだありません"}</p> },
+                Some(Ok(list)) => html! {
+                    <ul class="goal-list">
+                        { for list.iter().enumerate().map(|(i, g)| {
+                            let percent = (g.current * 100).checked_div(g.total).map_or(100, |p| p.min(100));
+                            html! {
+                                <li class="goal-item" key={i}>
+                                    <div class="goal-head">
+                                        <span class="goal-title">{ g.goal.title.clone() }</span>
+                                        <span class="report-count">{ format!("{} / {}（{}%）", g.current, g.total, percent) }</span>
+                                        <button type="button" class="btn-remove" aria-label={format!("目標「{}」を削除", g.goal.title)} onclick={remove(i)}>{"削除"}</button>
+                                    </div>
+                                    <meter min="0" max="100" value={percent.to_string()} aria-label={format!("{} の進捗", g.goal.title)}></meter>
+                                    <span class="hint">{ goal_condition(&g.goal.kind) }</span>
+                                </li>
+                            }
+                        }) }
+                    </ul>
+                },
+            } }
+            <form class="form-section goal-form" onsubmit={on_add}>
+                <h3>{"目標を追加"}</h3>
+                <div class="field">
+                    <label for="goal-title">{"名前"}</label>
+                    <input id="goal-title" type="text" class="input" placeholder="省略時は条件から作ります" value={(*title).clone()} oninput={bind(&title)}/>
+                </div>
+                <div class="field">
+                    <label for="goal-kind">{"種類"}</label>
+                    <select id="goal-kind" class="input" onchange={on_kind}>
+                        <option value="listen" selected={*kind == "listen"}>{"期間内に聴く枚数"}</option>
+                        <option value="complete" selected={*kind == "complete"}>{"レコードを仕上げる（完成度 100%）"}</option>
+                    </select>
+                </div>
+                if *kind == "listen" {
+                    <div class="field">
+                        <label for="goal-target">{"枚数"}</label>
+                        <input id="goal-target" type="number" min="1" class="input" value={(*target).clone()} oninput={bind(&target)}/>
+                    </div>
+                    <div class="field report-range">
+                        <label>{"期間"}</label>
+                        <input type="text" class="input report-date" placeholder="YYYY/MM/DD" aria-label="開始日" value={(*from).clone()} oninput={bind(&from)}/>
+                        <span>{"〜"}</span>
+                        <input type="text" class="input report-date" placeholder="YYYY/MM/DD" aria-label="終了日" value={(*to).clone()} oninput={bind(&to)}/>
+                    </div>
+                } else {
+                    <div class="field">
+                        <label for="goal-score">{"スコア"}</label>
+                        <input id="goal-score" type="number" step="any" class="input" placeholder="すべて" value={(*score).clone()} oninput={bind(&score)}/>
+                    </div>
+                    <div class="field">
+                        <label for="goal-genre">{"Main Janre"}</label>
+                        <select id="goal-genre" class="input" onchange={on_genre}>
+                            <option value="" selected={genre.is_empty()}>{"すべて"}</option>
+                            { for MAIN_JANRES.iter().map(|&g| html! {
+                                <option value={g} selected={*genre == g}>{ g }</option>
+                            }) }
+                        </select>
+                    </div>
+                }
+                <button type="submit" class="btn-add">{"追加"}</button>
+                if let Some(ref e) = *save_error {
+                    <p class="save-err" role="alert">{ e.clone() }</p>
+                }
+            </form>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod goals_tests {
+    use super::goal_condition;
+    use crate::api::GoalKind;
+
+    #[test]
+    fn condition_text() {
+        let listen = GoalKind::Listen { target: 100, from: Some("2026/01/01".into()), to: None };
+        assert_eq!(goal_condition(&listen), "2026/01/01 〜 （最新） に 100 枚聴く");
+        let complete = GoalKind::Complete { score: Some(6.0), genre: Some("Jazz".into()) };
+        assert_eq!(goal_condition(&complete), "スコア 6・Jazz のレコードを完成度 100% にする");
+    }
+}
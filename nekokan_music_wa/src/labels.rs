@@ -0,0 +1,111 @@
+use crate::api::{self, LabelAlbum, LabelEntry, LabelIndex};
+use yew::prelude::*;
+
+/// 名前に `q` を含むレーベル（大文字小文字は区別しない）。`q` が空ならすべて
+pub fn matching<'a>(labels: &'a [LabelEntry], q: &str) -> Vec<&'a LabelEntry> {
+    let q = q.trim().to_lowercase();
+    labels.iter().filter(|l| l.label.to_lowercase().contains(&q)).collect()
+}
+
+/// レコードの行の補足（"1959・BLP 4003"）
+fn detail(a: &LabelAlbum) -> String {
+    let year = a.release_year.map(|y| y.to_string()).unwrap_or_default();
+    [year.as_str(), a.id.as_str()].iter().filter(|s| !s.is_empty()).copied().collect::<Vec<_>>().join("・")
+}
+
+#[derive(Properties, PartialEq)]
+pub struct LabelIndexViewProps {
+    /// レコード名クリックでそのレコードを開く（"xxx.json"）
+    pub on_select: Callback<String>,
+}
+
+/// レーベルで探す（`GET /api/labels`）。枚数の多い順に並べ、開くとそのレーベルのレコードを発売年・番号順に出す。
+#[function_component(LabelIndexView)]
+pub fn label_index_view(props: &LabelIndexViewProps) -> Html {
+    let index = use_state(|| None::<Result<LabelIndex, String>>);
+    let q = use_state(String::new);
+    {
+        let index = index.clone();
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                index.set(Some(api::labels().await));
+            });
+            || ()
+        });
+    }
+    let on_input = {
+        let q = q.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                q.set(inp.value());
+            }
+        })
+    };
+    let record_link = |a: &LabelAlbum| {
+        let on_select = props.on_select.clone();
+        let filename = a.filename.clone();
+        html! {
+            <a href="#" title={a.filename.clone()} onclick={move |e: MouseEvent| { e.prevent_default(); on_select.emit(filename.clone()); }}>
+                { a.display_label.clone() }
+            </a>
+        }
+    };
+
+    html! {
+        <div class="label-index">
+            <h2 class="view-title">{"レーベル"}</h2>
+            { match &*index {
+                None => html! { <p class="sidebar-loading">{"読込中..."}</p> },
+                Some(Err(e)) => html! { <p class="load-err">{"ロードエラー: "}{ e.clone() }</p> },
+                Some(Ok(index)) => html! {
+                    <div class="form-section">
+                        <input type="search" class="input" aria-label="レーベル名で絞り込み" placeholder="Blue Note"
+                            value={(*q).clone()} oninput={on_input}/>
+                        if index.unlabeled > 0 {
+                            <p class="hint">{ format!("レーベルの書いていないレコード: {} 件", index.unlabeled) }</p>
+                        }
+                        <ul class="genre-usage-list">
+                            { for matching(&index.labels, &q).into_iter().map(|l| html! {
+                                <li class="genre-usage-item" key={l.label.clone()}>
+                                    <details>
+                                        <summary>
+                                            { l.label.clone() }
+                                            <span class="report-count">{ format!("{} 枚", l.count) }</span>
+                                        </summary>
+                                        <ul class="related-list">
+                                            { for l.albums.iter().map(|a| html! {
+                                                <li>{ record_link(a) }<span class="note-time">{ detail(a) }</span></li>
+                                            }) }
+                                        </ul>
+                                    </details>
+                                </li>
+                            }) }
+                        </ul>
+                    </div>
+                },
+            } }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod labels_tests {
+    use super::{detail, matching};
+    use crate::api::{LabelAlbum, LabelEntry};
+
+    fn label(name: &str) -> LabelEntry {
+        LabelEntry { label: name.into(), count: 0, albums: Vec::new() }
+    }
+
+    #[test]
+    fn labels_filter_by_name_and_albums_show_year_and_number() {
+        let labels = [label("Blue Note"), label("ECM"), label("Note Records")];
+        let names = |q: &str| matching(&labels, q).iter().map(|l| l.label.clone()).collect::<Vec<_>>();
+        assert_eq!(names(" note"), ["Blue Note", "Note Records"]);
+        assert_eq!(names("").len(), 3);
+        let mut a = LabelAlbum { filename: "a.json".into(), display_label: "A".into(), release_year: Some(1959), id: "BLP 4003".into() };
+        assert_eq!(detail(&a), "1959・BLP 4003");
+        a.release_year = None;
+        assert_eq!(detail(&a), "BLP 4003");
+    }
+}
@@ -1,9 +1,66 @@
+// 使う頻度の低いビューは feature で外せる（普段の編集だけなら wasm を小さくできる）
+#[cfg(feature = "reports")]
+mod activity;
+// 外したビューだけが使う API 関数は未使用になる
+#[cfg_attr(not(all(feature = "reports", feature = "import", feature = "maintenance")), allow(dead_code))]
 mod api;
 mod app;
+mod attachments;
+mod audio;
+mod audit;
+#[cfg(feature = "reports")]
+mod calendar;
+mod checklist;
+#[cfg(feature = "maintenance")]
+mod composers;
+mod crash;
+mod devices;
+mod digest;
+#[cfg(feature = "import")]
+mod covers;
+#[cfg(feature = "import")]
+mod discogs;
+mod download;
+mod export;
+mod focus;
 mod form;
-mod types;
-mod validation;
+mod fuzzy;
+#[cfg(feature = "maintenance")]
+mod genres;
+#[cfg(feature = "reports")]
+mod goals;
+#[cfg(feature = "reports")]
+mod labels;
+mod live;
+#[cfg(feature = "import")]
+mod lookup;
+mod normalize;
+mod notes;
+mod now_listening;
+#[cfg(feature = "reports")]
+mod personnel;
+mod quick_entry;
+mod random;
+mod recent;
+mod record_nav;
+mod related;
+#[cfg(feature = "reports")]
+mod report;
+mod retention;
+mod review;
+mod romanize;
+mod search;
+mod settings;
+mod sync;
+#[cfg(feature = "reports")]
+mod tunes;
+#[cfg(feature = "import")]
+mod url_import;
+#[cfg(feature = "maintenance")]
+mod validate_all;
 
+// データモデル・入力チェック・ラベルの規則はサーバーと共有する
+use nekokan_music_core::{completeness, date, filename, label, length, one_liner, rules, types, validation};
 use wasm_bindgen::prelude::*;
 
 /// タブタイトル・メイン見出し用。`Cargo.toml` の `version` をビルド時に埋め込む。
@@ -11,7 +68,7 @@ pub const APP_TITLE_WITH_VERSION: &str = concat!("Nekokan Music ", env!("CARGO_P
 
 #[wasm_bindgen(start)]
 pub fn run() {
-    console_error_panic_hook::set_once();
+    crash::install();
     gloo_utils::document().set_title(APP_TITLE_WITH_VERSION);
     yew::Renderer::<app::App>::with_root(
         gloo_utils::document().get_element_by_id("app").unwrap(),
@@ -0,0 +1,68 @@
+//! ライブラリの変更を `/api/ws` で受け取る。別のタブでの保存や `db/` の直接の編集でもサイドバーを古いままにしない。
+//! 切れたら少し待ってつなぎ直す。
+
+use crate::api::{self, LibraryEvent};
+use futures::future::{select, Either};
+use futures::StreamExt;
+use gloo_net::websocket::{futures::WebSocket, Message};
+use gloo_timers::future::TimeoutFuture;
+use std::cell::Cell;
+use std::rc::Rc;
+use yew::prelude::*;
+
+/// 続けて届いた変更（一括の変換など）をまとめる待ち時間（ミリ秒）
+const BATCH_MS: u32 = 300;
+/// 切れたあとつなぎ直すまでの待ち時間（ミリ秒）
+const RECONNECT_MS: u32 = 5_000;
+
+fn parse(msg: Message) -> Option<LibraryEvent> {
+    match msg {
+        Message::Text(text) => serde_json::from_str(&text).ok(),
+        Message::Bytes(_) => None,
+    }
+}
+
+async fn listen(url: String, on_change: Callback<Vec<LibraryEvent>>, stopped: Rc<Cell<bool>>) {
+    while !stopped.get() {
+        if let Ok(ws) = WebSocket::open(&url) {
+            let (_, mut read) = ws.split();
+            let mut open = true;
+            while open {
+                let Some(Ok(first)) = read.next().await else { break };
+                let mut batch: Vec<LibraryEvent> = parse(first).into_iter().collect();
+                loop {
+                    match select(read.next(), TimeoutFuture::new(BATCH_MS)).await {
+                        Either::Left((Some(Ok(msg)), _)) => batch.extend(parse(msg)),
+                        Either::Left(_) => {
+                            open = false;
+                            break;
+                        }
+                        Either::Right(_) => break,
+                    }
+                }
+                if stopped.get() {
+                    return;
+                }
+                if !batch.is_empty() {
+                    on_change.emit(batch);
+                }
+            }
+        }
+        TimeoutFuture::new(RECONNECT_MS).await;
+    }
+}
+
+/// 変更が届くたびに（続けて届いたものはまとめて）`on_change` を呼ぶ
+#[hook]
+pub fn use_library_events(on_change: Callback<Vec<LibraryEvent>>) {
+    use_effect_with((), move |_| {
+        let stopped = Rc::new(Cell::new(false));
+        let location = web_sys::window().map(|w| w.location());
+        let protocol = location.as_ref().and_then(|l| l.protocol().ok()).unwrap_or_default();
+        let host = location.as_ref().and_then(|l| l.host().ok()).unwrap_or_default();
+        if !host.is_empty() {
+            wasm_bindgen_futures::spawn_local(listen(api::ws_url(&protocol, &host), on_change, stopped.clone()));
+        }
+        move || stopped.set(true)
+    });
+}
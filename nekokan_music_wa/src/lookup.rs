@@ -0,0 +1,375 @@
+//! 外部のデータベース（MusicBrainz・Discogs・VGMdb など、サーバーの `metadata` の提供元）からリリース情報を取り込む。
+//! 提供元が増えても画面はそのまま（一覧はサーバーから取る）。
+
+use crate::api::{self, MetadataProviders, Release, ReleaseSummary};
+use crate::label::LabelFields;
+use crate::types::{MusicData, Reference, Track};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct MetadataLookupProps {
+    pub data: MusicData,
+    pub on_data_change: Callback<MusicData>,
+    /// 編集中のファイル名（保存済みのレコードだけカバーを取り込める）
+    pub selected_filename: Option<String>,
+}
+
+/// 選んだリリースをフォームに入れる。タイトル・レーベル・品番・発売年は上書きし、
+/// 曲目はまだ曲名が 1 つもないときだけ入れる（作曲者などを入れたあとの曲目を消さないように）。参考リンクも足す。
+/// アルバム全体の作曲者はレコードの作曲者が空のときだけ入れ、編曲者はコメントに 1 行足す。
+pub(crate) fn apply_release(data: &MusicData, release: &Release, provider_name: &str) -> MusicData {
+    let mut d = data.clone();
+    let r = &release.summary;
+    for (field, value) in [(&mut d.title, &r.title), (&mut d.label, &r.label), (&mut d.id, &r.catalog)] {
+        if !value.trim().is_empty() {
+            *field = value.trim().to_string();
+        }
+    }
+    if let Some(year) = r.year {
+        d.release_year = year;
+    }
+    if !release.tracks.is_empty() && d.tracks.iter().all(|t| t.title.trim().is_empty()) {
+        d.tracks = release
+            .tracks
+            .iter()
+            .map(|t| Track { disc_no: t.disc_no, no: t.no, title: t.title.clone(), length: t.length.clone(), ..Default::default() })
+            .collect();
+    }
+    if d.composers.is_empty() {
+        d.composers = release.composers.clone();
+    }
+    if !release.arrangers.is_empty() {
+        let line = format!("編曲: {}", release.arrangers.join(", "));
+        if !d.comment.contains(&line) {
+            d.comment = if d.comment.trim().is_empty() { line } else { format!("{}\n{}", d.comment.trim_end(), line) };
+        }
+    }
+    if !release.url.is_empty() && !d.references.iter().any(|x| x.url == release.url) {
+        d.references.push(Reference { name: provider_name.to_string(), url: release.url.clone() });
+    }
+    d
+}
+
+/// `/api/lookup` の MusicData の形（Discogs のクレジットなど）から、リーダー・サイドメン・指揮者がまだ空ならそれを、
+/// 作曲者がまだ空の曲には作曲者を入れる
+pub(crate) fn apply_lookup_data(data: &MusicData, found: &MusicData) -> MusicData {
+    let mut d = data.clone();
+    let p = &d.personnel;
+    let blank = p.leader.iter().all(|x| x.name.trim().is_empty())
+        && p.sidemen.iter().all(|x| x.name.trim().is_empty())
+        && p.conductor.iter().all(|x| x.name.trim().is_empty());
+    if blank && !(found.personnel.leader.is_empty() && found.personnel.sidemen.is_empty() && found.personnel.conductor.is_empty()) {
+        d.personnel.leader = found.personnel.leader.clone();
+        d.personnel.sidemen = found.personnel.sidemen.clone();
+        d.personnel.conductor = found.personnel.conductor.clone();
+    }
+    for t in d.tracks.iter_mut().filter(|t| t.composer.trim().is_empty()) {
+        if let Some(f) = found.tracks.iter().find(|f| f.disc_no == t.disc_no && f.no == t.no) {
+            t.composer = f.composer.clone();
+        }
+    }
+    d
+}
+
+/// 検索結果の 1 行（"Kind of Blue — Miles Davis（1959, Columbia CL 1355, US, 12" Vinyl）"）
+fn summary_line(r: &ReleaseSummary) -> String {
+    let details: Vec<String> = [
+        r.year.map(|y| y.to_string()).unwrap_or_default(),
+        format!("{} {}", r.label, r.catalog).trim().to_string(),
+        r.country.clone(),
+        r.format.clone(),
+    ]
+    .into_iter()
+    .filter(|s| !s.is_empty())
+    .collect();
+    if details.is_empty() {
+        format!("{} — {}", r.title, r.artist)
+    } else {
+        format!("{} — {}（{}）", r.title, r.artist, details.join(", "))
+    }
+}
+
+fn input_value(e: InputEvent) -> Option<String> {
+    e.target_dyn_into::<web_sys::HtmlInputElement>().map(|i| i.value())
+}
+
+#[function_component(MetadataLookup)]
+pub fn metadata_lookup(props: &MetadataLookupProps) -> Html {
+    let providers = use_state(MetadataProviders::default);
+    // 選んだ提供元（空なら設定のもの）
+    let provider = use_state(String::new);
+    // 検索語。None ならフォームの今の値を使う
+    let artist = use_state(|| None::<String>);
+    let title = use_state(|| None::<String>);
+    let catalog = use_state(|| None::<String>);
+    let results = use_state(Vec::<ReleaseSummary>::new);
+    let with_cover = use_state(|| false);
+    // CD の TOC（cd-discid の出力か開始フレームとリードアウト）かディスク ID
+    let toc = use_state(String::new);
+    let busy = use_state(|| false);
+    let status = use_state(|| None::<Result<String, String>>);
+
+    {
+        let providers = providers.clone();
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Ok(p) = api::metadata_providers().await {
+                    providers.set(p);
+                }
+            });
+            || ()
+        });
+    }
+
+    let form_artist = serde_json::to_value(&props.data).map(|v| LabelFields::from_value(&v).artist()).unwrap_or_default();
+    let artist_value = (*artist).clone().unwrap_or(form_artist);
+    let title_value = (*title).clone().unwrap_or_else(|| props.data.title.clone());
+    let catalog_value = (*catalog).clone().unwrap_or_else(|| props.data.id.clone());
+    let provider_id = if provider.is_empty() { providers.selected.clone() } else { (*provider).clone() };
+    let provider_name = providers.providers.iter().find(|p| p.id == provider_id).map_or(provider_id.clone(), |p| p.name.clone());
+
+    let on_search = {
+        let (results, busy, status) = (results.clone(), busy.clone(), status.clone());
+        let (provider_id, artist, title, catalog) = (provider_id.clone(), artist_value.clone(), title_value.clone(), catalog_value.clone());
+        Callback::from(move |_: MouseEvent| {
+            let (results, busy, status) = (results.clone(), busy.clone(), status.clone());
+            let (provider_id, artist, title, catalog) = (provider_id.clone(), artist.clone(), title.clone(), catalog.clone());
+            busy.set(true);
+            status.set(None);
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::search_releases(&provider_id, &artist, &title, &catalog).await {
+                    Ok(list) => {
+                        if list.is_empty() {
+                            status.set(Some(Ok("見つかりませんでした。".into())));
+                        }
+                        results.set(list);
+                    }
+                    Err(e) => status.set(Some(Err(e))),
+                }
+                busy.set(false);
+            });
+        })
+    };
+
+    let on_pick = |id: String| {
+        let (busy, status, results, with_cover) = (busy.clone(), status.clone(), results.clone(), *with_cover);
+        let (data, on_data_change, filename) = (props.data.clone(), props.on_data_change.clone(), props.selected_filename.clone());
+        let (provider_id, provider_name) = (provider_id.clone(), provider_name.clone());
+        Callback::from(move |_: MouseEvent| {
+            let (busy, status, results, id) = (busy.clone(), status.clone(), results.clone(), id.clone());
+            let (data, on_data_change, filename) = (data.clone(), on_data_change.clone(), filename.clone());
+            let (provider_id, provider_name) = (provider_id.clone(), provider_name.clone());
+            busy.set(true);
+            status.set(None);
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::fetch_release(&provider_id, &id).await {
+                    Ok(release) => {
+                        let mut d = apply_release(&data, &release, &provider_name);
+                        let mut msg = format!("{} の内容を入れました。保存すると反映されます。", provider_name);
+                        if let Some(filename) = filename.filter(|_| with_cover) {
+                            match api::import_release_cover(&provider_id, &id, &filename).await {
+                                Ok(cover) => d.cover = cover,
+                                Err(e) => msg = format!("{}（カバーは取り込めませんでした: {}）", msg, e),
+                            }
+                        }
+                        on_data_change.emit(d);
+                        results.set(vec![]);
+                        status.set(Some(Ok(msg)));
+                    }
+                    Err(e) => status.set(Some(Err(e))),
+                }
+                busy.set(false);
+            });
+        })
+    };
+
+    // 探していちばん近いものをそのまま入れる。ほかの候補は一覧に残して選び直せるようにする
+    let on_best = {
+        let (busy, status, results, with_cover) = (busy.clone(), status.clone(), results.clone(), *with_cover);
+        let (data, on_data_change, filename) = (props.data.clone(), props.on_data_change.clone(), props.selected_filename.clone());
+        let (provider_id, provider_name) = (provider_id.clone(), provider_name.clone());
+        let (artist, title) = (artist_value.clone(), title_value.clone());
+        Callback::from(move |_: MouseEvent| {
+            let (busy, status, results) = (busy.clone(), status.clone(), results.clone());
+            let (data, on_data_change, filename) = (data.clone(), on_data_change.clone(), filename.clone());
+            let (provider_id, provider_name, artist, title) =
+                (provider_id.clone(), provider_name.clone(), artist.clone(), title.clone());
+            busy.set(true);
+            status.set(None);
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::lookup_release(&provider_id, &artist, &title).await {
+                    Ok(found) => {
+                        let mut d = apply_lookup_data(&apply_release(&data, &found.release, &provider_name), &found.data);
+                        let mut msg = format!("{}（{}）の内容を入れました。保存すると反映されます。", summary_line(&found.release.summary), provider_name);
+                        if let Some(filename) = filename.filter(|_| with_cover) {
+                            match api::import_release_cover(&provider_id, &found.release.summary.id, &filename).await {
+                                Ok(cover) => d.cover = cover,
+                                Err(e) => msg = format!("{}（カバーは取り込めませんでした: {}）", msg, e),
+                            }
+                        }
+                        on_data_change.emit(d);
+                        results.set(found.candidates);
+                        status.set(Some(Ok(msg)));
+                    }
+                    Err(e) => status.set(Some(Err(e))),
+                }
+                busy.set(false);
+            });
+        })
+    };
+
+    let on_cddb = {
+        let (busy, status, toc) = (busy.clone(), status.clone(), toc.clone());
+        let (data, on_data_change) = (props.data.clone(), props.on_data_change.clone());
+        Callback::from(move |_: MouseEvent| {
+            let (busy, status, toc) = (busy.clone(), status.clone(), (*toc).clone());
+            let (data, on_data_change) = (data.clone(), on_data_change.clone());
+            busy.set(true);
+            status.set(None);
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::lookup_cddb(&toc).await {
+                    Ok(found) => {
+                        on_data_change.emit(apply_release(&data, &found.release, "CDDB"));
+                        let mut msg = format!("{}（CDDB、{} 曲）を入れました。保存すると反映されます。", summary_line(&found.release.summary), found.release.tracks.len());
+                        if !found.candidates.is_empty() {
+                            msg = format!("{}ほかに {} 件の候補があります。", msg, found.candidates.len());
+                        }
+                        status.set(Some(Ok(msg)));
+                    }
+                    Err(e) => status.set(Some(Err(e))),
+                }
+                busy.set(false);
+            });
+        })
+    };
+    let on_toc = {
+        let toc = toc.clone();
+        Callback::from(move |e: InputEvent| toc.set(input_value(e).unwrap_or_default()))
+    };
+
+    let on_provider_change = {
+        let provider = provider.clone();
+        Callback::from(move |e: Event| {
+            if let Some(sel) = e.target_dyn_into::<web_sys::HtmlSelectElement>() {
+                provider.set(sel.value());
+            }
+        })
+    };
+    let set_text = |state: &UseStateHandle<Option<String>>| {
+        let state = state.clone();
+        Callback::from(move |e: InputEvent| state.set(input_value(e)))
+    };
+    let on_cover_toggle = {
+        let with_cover = with_cover.clone();
+        Callback::from(move |_: Event| with_cover.set(!*with_cover))
+    };
+
+    html! {
+        // フォームの中なので、検索語で Enter を押しても保存しないようにする
+        <details class="form-section metadata-lookup" onkeydown={|e: KeyboardEvent| if e.key() == "Enter" { e.prevent_default() }}>
+            <summary>{"外部のデータベースから探す"}</summary>
+            <div class="settings-inline">
+                <select class="sidebar-sort" aria-label="探す先" onchange={on_provider_change}>
+                    { for providers.providers.iter().map(|p| html! {
+                        <option value={p.id.clone()} selected={p.id == provider_id}>{ p.name.clone() }</option>
+                    }) }
+                </select>
+                <input type="text" class="input" placeholder="アーティスト" aria-label="アーティスト"
+                    value={artist_value} oninput={set_text(&artist)}/>
+                <input type="text" class="input" placeholder="タイトル" aria-label="タイトル"
+                    value={title_value} oninput={set_text(&title)}/>
+                <input type="text" class="input" placeholder="品番" aria-label="品番"
+                    value={catalog_value} oninput={set_text(&catalog)}/>
+                <button type="button" class="btn-add" onclick={on_search} disabled={*busy}>{"探す"}</button>
+                <button type="button" class="btn-add" onclick={on_best} disabled={*busy}>{"いちばん近いものを入れる"}</button>
+            </div>
+            <div class="settings-inline">
+                <input type="text" class="input" placeholder="CD の TOC かディスク ID" aria-label="CD の TOC かディスク ID"
+                    value={(*toc).clone()} oninput={on_toc}/>
+                <button type="button" class="btn-add" onclick={on_cddb} disabled={*busy || toc.trim().is_empty()}>{"CDDB で曲目を入れる"}</button>
+            </div>
+            <label class="sidebar-filter">
+                <input type="checkbox" checked={*with_cover} onchange={on_cover_toggle} disabled={props.selected_filename.is_none()}/>
+                {"カバーも取り込む（保存済みのレコードだけ）"}
+            </label>
+            <p class="hint">{"選んだリリースのタイトル・レーベル・品番・発売年で上書きし、曲目はまだ空のときだけ入れます。「いちばん近いものを入れる」は、パーソネルが空なら演奏者のクレジット（Discogs）も入れます。CDDB には cd-discid の出力（ディスク ID・曲数・開始フレーム・秒数）か、開始フレームとリードアウトを並べたものを貼ります。"}</p>
+            if !results.is_empty() {
+                <ul class="metadata-results">
+                    { for results.iter().map(|r| html! {
+                        <li>
+                            <button type="button" class="link-button" onclick={on_pick(r.id.clone())} disabled={*busy}>
+                                { summary_line(r) }
+                            </button>
+                        </li>
+                    }) }
+                </ul>
+            }
+            { match &*status {
+                None => html! {},
+                Some(Ok(m)) => html! { <p class="save-ok" role="status">{ m.clone() }</p> },
+                Some(Err(e)) => html! { <p class="save-err" role="status">{ e.clone() }</p> },
+            } }
+        </details>
+    }
+}
+
+#[cfg(test)]
+mod lookup_tests {
+    use super::{apply_lookup_data, apply_release, summary_line};
+    use crate::api::{Release, ReleaseSummary, ReleaseTrack};
+    use crate::types::{LeaderEntry, MusicData, Personnel, SidemenEntry, Track};
+
+    #[test]
+    fn release_fills_the_form_without_dropping_tracks() {
+        let release = Release {
+            summary: ReleaseSummary {
+                id: "1".into(),
+                title: "Kind of Blue".into(),
+                artist: "Miles Davis".into(),
+                year: Some(1959),
+                label: "Columbia".into(),
+                catalog: "CL 1355".into(),
+                ..Default::default()
+            },
+            tracks: vec![ReleaseTrack { disc_no: 1, no: 1, title: "So What".into(), length: "9:22".into() }],
+            url: "https://musicbrainz.org/release/1".into(),
+            composers: vec!["Miles Davis".into()],
+            arrangers: vec!["Gil Evans".into()],
+        };
+        let blank = MusicData { release_year: 2000, tracks: vec![Track { disc_no: 1, no: 1, ..Default::default() }], ..Default::default() };
+        let d = apply_release(&blank, &release, "MusicBrainz");
+        assert_eq!((d.title.as_str(), d.label.as_str(), d.id.as_str(), d.release_year), ("Kind of Blue", "Columbia", "CL 1355", 1959));
+        assert_eq!(d.tracks[0].title, "So What");
+        assert_eq!(d.references[0].name, "MusicBrainz");
+        // もう一度入れても参考リンクは増えず、入力済みの曲目は残す
+        let mut edited = d.clone();
+        edited.tracks[0].composer = "Miles Davis".into();
+        let again = apply_release(&edited, &release, "MusicBrainz");
+        assert_eq!(again.references.len(), 1);
+        assert_eq!((d.composers.clone(), d.comment.as_str()), (vec!["Miles Davis".to_string()], "編曲: Gil Evans"));
+        assert_eq!(again.comment, "編曲: Gil Evans");
+        assert_eq!(again.tracks[0].composer, "Miles Davis");
+        assert_eq!(summary_line(&release.summary), "Kind of Blue — Miles Davis（1959, Columbia CL 1355）");
+    }
+
+    #[test]
+    fn lookup_data_fills_blank_personnel_and_composers() {
+        let found = MusicData {
+            personnel: Personnel {
+                leader: vec![LeaderEntry { name: "Andrew Hill".into(), instruments: "Piano".into(), tracks: "all".into(), ..Default::default() }],
+                sidemen: vec![SidemenEntry { name: "Roy Haynes".into(), instruments: "Drums".into(), tracks: "all".into(), ..Default::default() }],
+                ..Default::default()
+            },
+            tracks: vec![Track { disc_no: 1, no: 1, composer: "Andrew Hill".into(), ..Default::default() }],
+            ..Default::default()
+        };
+        let blank = MusicData { tracks: vec![Track { disc_no: 1, no: 1, ..Default::default() }], ..Default::default() };
+        let d = apply_lookup_data(&blank, &found);
+        assert_eq!((d.personnel.leader[0].instruments.as_str(), d.personnel.sidemen.len()), ("Piano", 1));
+        assert_eq!(d.tracks[0].composer, "Andrew Hill");
+        // 入力済みのパーソネルはそのまま
+        let mut edited = blank.clone();
+        edited.personnel.leader = vec![LeaderEntry { name: "Andrew Hill".into(), ..Default::default() }];
+        assert!(apply_lookup_data(&edited, &found).personnel.sidemen.is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+use crate::api::{self, NormalizeReport};
+use yew::prelude::*;
+
+/// 確認で一覧に出す件数
+const PREVIEW_ROWS: usize = 30;
+
+/// 確認・結果の 1 行（"12 件の名前が変わります（重なり 2 件・対象外 5 件・そのまま 80 件）"）
+pub fn report_summary(r: &NormalizeReport) -> String {
+    let mut rest: Vec<String> = Vec::new();
+    for (label, n) in [("重なり", r.conflicts.len()), ("対象外", r.skipped.len()), ("そのまま", r.unchanged)] {
+        if n > 0 {
+            rest.push(format!("{} {} 件", label, n));
+        }
+    }
+    let head = match (r.dry_run, r.renames.len()) {
+        (true, 0) => "変わるものはありません".to_string(),
+        (true, n) => format!("{} 件の名前が変わります", n),
+        (false, n) => format!("{} 件の名前を変えました", n),
+    };
+    if rest.is_empty() {
+        head
+    } else {
+        format!("{}（{}）", head, rest.join("・"))
+    }
+}
+
+/// 設定画面の「ファイル名の整理」。レコードの中身から今の規則のファイル名を作り、違うものを確認してから一括で変える。
+/// 適用するのは確認で出たものだけ（確認のあとに増えたものは変えない）。
+#[function_component(NormalizeFilenamesSection)]
+pub fn normalize_filenames_section() -> Html {
+    let preview = use_state(|| None::<Result<NormalizeReport, String>>);
+    let busy = use_state(|| false);
+
+    let on_check = {
+        let (preview, busy) = (preview.clone(), busy.clone());
+        Callback::from(move |_: MouseEvent| {
+            let (preview, busy) = (preview.clone(), busy.clone());
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                preview.set(Some(api::normalize_filenames(true, &[]).await));
+                busy.set(false);
+            });
+        })
+    };
+    let on_apply = {
+        let (preview, busy) = (preview.clone(), busy.clone());
+        Callback::from(move |_: MouseEvent| {
+            let files: Vec<String> = match &*preview {
+                Some(Ok(r)) if r.dry_run => r.renames.iter().map(|x| x.from.clone()).collect(),
+                _ => return,
+            };
+            let (preview, busy) = (preview.clone(), busy.clone());
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                preview.set(Some(api::normalize_filenames(false, &files).await));
+                busy.set(false);
+            });
+        })
+    };
+
+    html! {
+        <div class="form-section">
+            <h3>{"ファイル名の整理"}</h3>
+            <p class="hint">{"レコードの中身から今の規則（Jazz/Fusion は リーダー__タイトル、Classical は設定のパターン）のファイル名を作り、違うものの名前を変えます。カバー・メモ・添付・関連づけも付いていきます。"}</p>
+            <button type="button" class="btn-add" onclick={on_check} disabled={*busy}>{"確認"}</button>
+            <div role="status" aria-live="polite">
+                { match &*preview {
+                    None => html! {},
+                    Some(Err(e)) => html! { <p class="save-err">{ e.clone() }</p> },
+                    Some(Ok(r)) if !r.dry_run => html! {
+                        <p class={if r.failed.is_empty() && r.warnings.is_empty() { "save-ok" } else { "save-warn" }}>
+                            { report_summary(r) }
+                            if r.related_updated > 0 {
+                                { format!("。関連づけを {} 件書き換えました", r.related_updated) }
+                            }
+                            { for r.failed.iter().map(|(f, e)| html! { <><br/><span class="save-err">{ format!("{}: {}", f, e) }</span></> }) }
+                            { for r.warnings.iter().map(|w| html! { <><br/>{ w.clone() }</> }) }
+                        </p>
+                    },
+                    Some(Ok(r)) => html! {
+                        <>
+                            <p>{ report_summary(r) }</p>
+                            <ul class="hint">
+                                { for r.renames.iter().take(PREVIEW_ROWS).map(|x| html! {
+                                    <li key={x.from.clone()} title={x.display_label.clone()}>{ format!("{} → {}", x.from, x.to) }</li>
+                                }) }
+                                if r.renames.len() > PREVIEW_ROWS {
+                                    <li>{ format!("ほか {} 件", r.renames.len() - PREVIEW_ROWS) }</li>
+                                }
+                            </ul>
+                            if !r.conflicts.is_empty() {
+                                <p class="save-warn">{"次のものは名前が重なるので変えません:"}</p>
+                                <ul class="hint">
+                                    { for r.conflicts.iter().take(PREVIEW_ROWS).map(|c| html! {
+                                        <li key={c.filename.clone()}>{ format!("{}: {}", c.filename, c.reason) }</li>
+                                    }) }
+                                </ul>
+                            }
+                            if !r.renames.is_empty() {
+                                <button type="button" class="btn-save" onclick={on_apply} disabled={*busy}>
+                                    { if *busy { "変更中..." } else { "名前を変える" } }
+                                </button>
+                            }
+                        </>
+                    },
+                } }
+            </div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod normalize_tests {
+    use super::report_summary;
+    use crate::api::{FilenameRename, FilenameSkipped, NormalizeReport};
+
+    #[test]
+    fn summary_names_only_the_non_empty_groups() {
+        let mut r = NormalizeReport {
+            dry_run: true,
+            renames: vec![],
+            conflicts: vec![],
+            skipped: vec![],
+            unchanged: 0,
+            failed: vec![],
+            related_updated: 0,
+            warnings: vec![],
+        };
+        assert_eq!(report_summary(&r), "変わるものはありません");
+        r.renames.push(FilenameRename { from: "a.json".into(), to: "A__B.json".into(), display_label: String::new() });
+        r.skipped.push(FilenameSkipped { filename: "x.json".into(), to: None, reason: String::new() });
+        r.unchanged = 3;
+        assert_eq!(report_summary(&r), "1 件の名前が変わります（対象外 1 件・そのまま 3 件）");
+        r.dry_run = false;
+        assert_eq!(report_summary(&r), "1 件の名前を変えました（対象外 1 件・そのまま 3 件）");
+    }
+}
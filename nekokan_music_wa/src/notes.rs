@@ -0,0 +1,138 @@
+use crate::api::{self, Note};
+use js_sys::Date;
+use wasm_bindgen::JsValue;
+use yew::prelude::*;
+
+/// RFC 3339 の日時をブラウザのローカル時刻 "YYYY/MM/DD HH:MM" にする。
+/// 日付だけ（YYYY-MM-DD）なら "YYYY/MM/DD"、読めなければそのまま返す。
+pub(crate) fn local_timestamp(ts: &str) -> String {
+    if !ts.contains('T') {
+        return ts.replace('-', "/");
+    }
+    let d = Date::new(&JsValue::from_str(ts));
+    if d.get_time().is_nan() {
+        return ts.to_string();
+    }
+    format!(
+        "{:04}/{:02}/{:02} {:02}:{:02}",
+        d.get_full_year(),
+        d.get_month() + 1,
+        d.get_date(),
+        d.get_hours(),
+        d.get_minutes()
+    )
+}
+
+#[derive(Properties, PartialEq)]
+pub struct NotesPanelProps {
+    /// 対象レコード（"xxx.json"）
+    pub filename: String,
+}
+
+/// レコードごとのメモ（レビューとは別の TODO・覚え書き）。保存ボタンとは関係なくすぐサーバーに書く。
+#[function_component(NotesPanel)]
+pub fn notes_panel(props: &NotesPanelProps) -> Html {
+    let notes = use_state(Vec::<Note>::new);
+    let text = use_state(String::new);
+    let error = use_state(|| None::<String>);
+
+    {
+        let notes = notes.clone();
+        let error = error.clone();
+        use_effect_with(props.filename.clone(), move |filename| {
+            let filename = filename.clone();
+            notes.set(vec![]);
+            error.set(None);
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::get_notes(&filename).await {
+                    Ok(list) => notes.set(list),
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+            || ()
+        });
+    }
+
+    // API を呼び、返ってきた一覧で置き換える
+    let apply = {
+        let notes = notes.clone();
+        let error = error.clone();
+        move |fut: std::pin::Pin<Box<dyn std::future::Future<Output = Result<Vec<Note>, String>>>>| {
+            let notes = notes.clone();
+            let error = error.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match fut.await {
+                    Ok(list) => {
+                        notes.set(list);
+                        error.set(None);
+                    }
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+        }
+    };
+
+    let on_add = {
+        let text = text.clone();
+        let filename = props.filename.clone();
+        let apply = apply.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let t = text.trim().to_string();
+            if t.is_empty() {
+                return;
+            }
+            text.set(String::new());
+            let filename = filename.clone();
+            apply(Box::pin(async move { api::add_note(&filename, &t).await }));
+        })
+    };
+    let on_input = {
+        let text = text.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                text.set(inp.value());
+            }
+        })
+    };
+    let toggle = |n: &Note| {
+        let (filename, id, done) = (props.filename.clone(), n.id, !n.done);
+        let apply = apply.clone();
+        Callback::from(move |_: Event| {
+            let filename = filename.clone();
+            apply(Box::pin(async move { api::set_note_done(&filename, id, done).await }));
+        })
+    };
+    let remove = |n: &Note| {
+        let (filename, id) = (props.filename.clone(), n.id);
+        let apply = apply.clone();
+        Callback::from(move |_: MouseEvent| {
+            let filename = filename.clone();
+            apply(Box::pin(async move { api::delete_note(&filename, id).await }));
+        })
+    };
+
+    html! {
+        <div class="form-section notes-panel">
+            <h3>{ format!("メモ（{}）", notes.iter().filter(|n| !n.done).count()) }</h3>
+            <ul class="note-list">
+                { for notes.iter().map(|n| html! {
+                    <li key={n.id} class={classes!("note-item", n.done.then_some("note-done"))}>
+                        <input type="checkbox" checked={n.done} aria-label="対応済み" onchange={toggle(n)}/>
+                        <span class="note-text">{ n.text.clone() }</span>
+                        <span class="note-time">{ local_timestamp(&n.created) }</span>
+                        <button type="button" class="btn-remove" aria-label={format!("メモ「{}」を削除", n.text)} onclick={remove(n)}>{"削除"}</button>
+                    </li>
+                }) }
+            </ul>
+            <form class="note-form" onsubmit={on_add}>
+                <input type="text" class="input" maxlength="2000" aria-label="メモ" placeholder="録音日を要確認 など"
+                    value={(*text).clone()} oninput={on_input}/>
+                <button type="submit" class="btn-add">{"メモを追加"}</button>
+            </form>
+            if let Some(ref e) = *error {
+                <p class="save-err" role="alert">{ e.clone() }</p>
+            }
+        </div>
+    }
+}
@@ -0,0 +1,182 @@
+use crate::api::{self, ListEntryWithLabel};
+use crate::app::today_str;
+use crate::focus::on_escape;
+use crate::fuzzy::best_matches;
+use yew::prelude::*;
+
+/// 候補として表示する最大件数
+const MAX_CANDIDATES: usize = 5;
+
+#[derive(Properties, PartialEq)]
+pub struct NowListeningProps {
+    pub entries: Vec<ListEntryWithLabel>,
+    /// 再生記録を追記したとき (ファイル名, 日付, 追記の前後の版) を渡す
+    pub on_logged: Callback<(String, String, api::Listened)>,
+    /// 該当なしで下書きを作ったとき、作成されたファイル名を渡す
+    pub on_draft_created: Callback<String>,
+}
+
+/// "Artist - Title" / "Artist: Title" を (artist, title) に分ける。区切りがなければ全体をタイトルとする。
+fn split_artist_title(s: &str) -> (String, String) {
+    for sep in [" - ", ": ", " / "] {
+        if let Some((a, t)) = s.split_once(sep) {
+            return (a.trim().to_string(), t.trim().to_string());
+        }
+    }
+    (String::new(), s.trim().to_string())
+}
+
+/// 常時表示の「いま聴いてる」ウィジェット。入力に近いレコードを候補表示し、
+/// クリックで今日の日付を再生記録に追記する。該当がなければ下書きレコードを作る。
+#[function_component(NowListening)]
+pub fn now_listening(props: &NowListeningProps) -> Html {
+    let query = use_state(String::new);
+    let open = use_state(|| true);
+    let busy = use_state(|| false);
+    let message = use_state(|| None::<Result<String, String>>);
+
+    let candidates: Vec<ListEntryWithLabel> = if query.trim().is_empty() {
+        vec![]
+    } else {
+        best_matches(&query, &props.entries, |e| e.display_label.as_str(), MAX_CANDIDATES)
+            .into_iter()
+            .cloned()
+            .collect()
+    };
+
+    let on_input = {
+        let query = query.clone();
+        let message = message.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                query.set(inp.value());
+                message.set(None);
+            }
+        })
+    };
+
+    let log_listen = |entry: &ListEntryWithLabel| {
+        let filename = entry.filename.clone();
+        let label = entry.display_label.clone();
+        let query = query.clone();
+        let busy = busy.clone();
+        let message = message.clone();
+        let on_logged = props.on_logged.clone();
+        Callback::from(move |_: MouseEvent| {
+            let filename = filename.clone();
+            let label = label.clone();
+            let query = query.clone();
+            let busy = busy.clone();
+            let message = message.clone();
+            let on_logged = on_logged.clone();
+            let date = today_str();
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::append_listen(&filename, &date).await {
+                    Ok(listened) => {
+                        message.set(Some(Ok(format!("{} を記録しました（{}）", label, date))));
+                        query.set(String::new());
+                        on_logged.emit((filename, date, listened));
+                    }
+                    Err(e) => message.set(Some(Err(e))),
+                }
+                busy.set(false);
+            });
+        })
+    };
+
+    let on_create_draft = {
+        let query = query.clone();
+        let busy = busy.clone();
+        let message = message.clone();
+        let on_draft_created = props.on_draft_created.clone();
+        Callback::from(move |_: MouseEvent| {
+            let (artist, title) = split_artist_title(&query);
+            if title.is_empty() {
+                return;
+            }
+            let query = query.clone();
+            let busy = busy.clone();
+            let message = message.clone();
+            let on_draft_created = on_draft_created.clone();
+            let date = today_str();
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::create_draft(&artist, &title, &date, true).await {
+                    Ok(filename) => {
+                        message.set(Some(Ok(format!("下書き {} を作成して記録しました", filename))));
+                        query.set(String::new());
+                        on_draft_created.emit(filename);
+                    }
+                    Err(e) => message.set(Some(Err(e))),
+                }
+                busy.set(false);
+            });
+        })
+    };
+
+    // Esc: 入力中なら候補を閉じ、空ならウィジェット自体を畳む
+    let on_cancel = {
+        let query = query.clone();
+        let message = message.clone();
+        let open = open.clone();
+        Callback::from(move |()| {
+            if query.is_empty() {
+                open.set(false);
+            } else {
+                query.set(String::new());
+                message.set(None);
+            }
+        })
+    };
+
+    let on_toggle = {
+        let open = open.clone();
+        Callback::from(move |_: MouseEvent| open.set(!*open))
+    };
+
+    html! {
+        <div class={if *open { "now-listening" } else { "now-listening closed" }} onkeydown={on_escape(on_cancel)}>
+            <button type="button" class="now-listening-toggle" aria-expanded={(*open).to_string()} onclick={on_toggle}>
+                { if *open { "▼ いま聴いてる" } else { "▲ いま聴いてる" } }
+            </button>
+            if *open {
+                <input
+                    type="text"
+                    class="input now-listening-input"
+                    placeholder="Artist - Title"
+                    aria-label="いま聴いているレコード（Artist - Title）"
+                    value={(*query).clone()}
+                    oninput={on_input}
+                    disabled={*busy}
+                />
+                if !query.trim().is_empty() {
+                    <ul class="now-listening-candidates" aria-label="候補">
+                        { for candidates.iter().map(|entry| html! {
+                            <li key={entry.filename.clone()}>
+                                <button type="button" class="now-listening-candidate" disabled={*busy}
+                                    title="今日の再生記録に追加" onclick={log_listen(entry)}>
+                                    { entry.display_label.clone() }
+                                </button>
+                            </li>
+                        }) }
+                        <li>
+                            <button type="button" class="now-listening-candidate now-listening-draft" disabled={*busy}
+                                onclick={on_create_draft}>
+                                { if candidates.is_empty() { "該当なし: 下書きを作って記録" } else { "どれでもない: 下書きを作って記録" } }
+                            </button>
+                        </li>
+                    </ul>
+                }
+            }
+            <div role="status" aria-live="polite">
+                if let Some(ref m) = *message {
+                    <p class={if m.is_ok() { "save-ok" } else { "save-err" }}>
+                        { match m { Ok(s) | Err(s) => s.clone() } }
+                    </p>
+                }
+            </div>
+        </div>
+    }
+}
+
@@ -0,0 +1,149 @@
+use crate::api::{self, Discography, PersonnelCredit};
+use yew::prelude::*;
+
+/// 役割の表示名
+fn role_label(role: &str) -> &str {
+    match role {
+        "leader" => "リーダー",
+        "sideman" => "サイドメン",
+        "soloist" => "ソリスト",
+        "conductor" => "指揮",
+        "group_member" => "メンバー",
+        other => other,
+    }
+}
+
+/// 1 つの参加の表示（"メンバー（The Jazz Messengers）: Trumpet [2,4]"）。トラックが "all" なら省く
+pub fn describe(c: &PersonnelCredit) -> String {
+    let mut s = role_label(&c.role).to_string();
+    if let Some(group) = &c.group {
+        s.push_str(&format!("（{}）", group));
+    }
+    if !c.instruments.is_empty() {
+        s.push_str(&format!(": {}", c.instruments));
+    }
+    if !c.tracks.is_empty() && !c.tracks.eq_ignore_ascii_case("all") {
+        s.push_str(&format!(" [{}]", c.tracks));
+    }
+    s
+}
+
+/// 役割ごとの枚数（"リーダー 3 枚・サイドメン 12 枚"）
+fn summary(d: &Discography) -> String {
+    ["leader", "sideman", "soloist", "conductor", "group_member"]
+        .iter()
+        .filter_map(|r| d.roles.get(*r).map(|n| format!("{} {} 枚", role_label(r), n)))
+        .collect::<Vec<_>>()
+        .join("・")
+}
+
+#[derive(Properties, PartialEq)]
+pub struct PersonnelViewProps {
+    /// レコード名クリックでそのレコードを開く（"xxx.json"）
+    pub on_select: Callback<String>,
+}
+
+/// 人で探す。名前（別名・かな名のローマ字でもよい）で、その人が参加したレコードを発売年順に役割・楽器つきで並べる。
+#[function_component(PersonnelView)]
+pub fn personnel_view(props: &PersonnelViewProps) -> Html {
+    let name = use_state(String::new);
+    let result = use_state(|| None::<Result<Discography, String>>);
+    let loading = use_state(|| false);
+
+    let on_input = {
+        let name = name.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                name.set(inp.value());
+            }
+        })
+    };
+    let on_submit = {
+        let name = name.clone();
+        let result = result.clone();
+        let loading = loading.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let query = name.trim().to_string();
+            if query.is_empty() {
+                return;
+            }
+            let (result, loading) = (result.clone(), loading.clone());
+            loading.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                result.set(Some(api::personnel(&query).await));
+                loading.set(false);
+            });
+        })
+    };
+
+    html! {
+        <div class="tune-index">
+            <h2 class="view-title">{"人で探す"}</h2>
+            <p class="hint">{"名前で探すと、その人がリーダー・サイドメン・ソリスト・指揮者・グループのメンバーとして参加したレコードを発売年順に並べます。大文字小文字や登録した別名は区別しません。"}</p>
+            <form class="note-form" onsubmit={on_submit}>
+                <input type="search" class="input" aria-label="名前" placeholder="Lee Morgan" value={(*name).clone()} oninput={on_input}/>
+                <button type="submit" class="btn-add" disabled={*loading}>{ if *loading { "検索中..." } else { "探す" } }</button>
+            </form>
+            { match &*result {
+                None => html! {},
+                Some(Err(e)) => html! { <p class="load-err">{"ロードエラー: "}{ e.clone() }</p> },
+                Some(Ok(d)) if d.albums.is_empty() => html! { <p class="hint" role="status">{"見つかりませんでした。"}</p> },
+                Some(Ok(d)) => html! {
+                    <div class="form-section" role="status">
+                        <h3>{ format!("{}（{} 枚）", d.name, d.albums.len()) }</h3>
+                        <p class="hint">{ summary(d) }</p>
+                        <table class="cover-table">
+                            <thead>
+                                <tr><th>{"レコード"}</th><th>{"年"}</th><th>{"参加"}</th></tr>
+                            </thead>
+                            <tbody>
+                                { for d.albums.iter().map(|a| {
+                                    let on_select = props.on_select.clone();
+                                    let filename = a.filename.clone();
+                                    html! {
+                                        <tr key={a.filename.clone()}>
+                                            <td class="cover-name">
+                                                <a href="#" title={a.filename.clone()}
+                                                    onclick={move |e: MouseEvent| { e.prevent_default(); on_select.emit(filename.clone()); }}>
+                                                    { a.display_label.clone() }
+                                                </a>
+                                            </td>
+                                            <td>{ a.release_year.map(|y| y.to_string()).unwrap_or_default() }</td>
+                                            <td>{ a.credits.iter().map(describe).collect::<Vec<_>>().join(" / ") }</td>
+                                        </tr>
+                                    }
+                                }) }
+                            </tbody>
+                        </table>
+                    </div>
+                },
+            } }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod personnel_tests {
+    use super::describe;
+    use crate::api::PersonnelCredit;
+
+    #[test]
+    fn credits_show_group_instruments_and_partial_tracks() {
+        let mut c = PersonnelCredit {
+            role: "group_member".into(),
+            instruments: "Trumpet".into(),
+            tracks: "all".into(),
+            group: Some("The Jazz Messengers".into()),
+        };
+        assert_eq!(describe(&c), "メンバー（The Jazz Messengers）: Trumpet");
+        c.role = "sideman".into();
+        c.group = None;
+        c.tracks = "2,4".into();
+        assert_eq!(describe(&c), "サイドメン: Trumpet [2,4]");
+        c.role = "conductor".into();
+        c.instruments.clear();
+        c.tracks.clear();
+        assert_eq!(describe(&c), "指揮");
+    }
+}
@@ -0,0 +1,322 @@
+//! クイック入力。タイトル・アーティスト・ジャンル・年・スコア・記録日だけで 1 枚を登録する。
+//! 入力チェックの必須項目（レーベル・品番・トラックなど）は仮の値で埋めて下書き（draft）として保存し、
+//! 詳細はあとで通常の編集画面から足す。1 行欄（"Bill Evans - Alone (1968) Verve"）に書けば各項目を埋める。
+
+use crate::one_liner::{parse_one_liner, OneLiner};
+use crate::types::{sub_janres_for_main, Janre, LeaderEntry, MusicData, Personnel, Track, MAIN_JANRES};
+use crate::validation::{format_score, validate_form, ValidationPolicy};
+use yew::prelude::*;
+
+/// 必須項目を埋める仮の値
+pub const PLACEHOLDER: &str = "未入力";
+
+/// クイック入力の項目
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuickEntry {
+    pub title: String,
+    /// リーダーとして登録する
+    pub artist: String,
+    /// 空なら仮の値
+    pub label: String,
+    pub main: String,
+    pub sub: String,
+    pub year: i32,
+    pub score: f64,
+    pub date: String,
+}
+
+impl QuickEntry {
+    pub fn new(year: i32, date: String, score: f64) -> Self {
+        let main = MAIN_JANRES.first().copied().unwrap_or_default();
+        QuickEntry {
+            title: String::new(),
+            artist: String::new(),
+            label: String::new(),
+            main: main.to_string(),
+            sub: sub_janres_for_main(main).first().copied().unwrap_or_default().to_string(),
+            year,
+            score,
+            date,
+        }
+    }
+
+    /// 保存するファイル名（拡張子なし）。"{アーティスト}__{タイトル}"、アーティストが空ならタイトルのみ。
+    pub fn filename(&self) -> String {
+        let title = crate::form::sanitize_for_filename(self.title.trim());
+        let artist = crate::form::sanitize_for_filename(self.artist.trim());
+        if artist.is_empty() {
+            title
+        } else {
+            format!("{}__{}", artist, title)
+        }
+    }
+
+    /// 1 行欄で読めた項目だけ入れる（読めなかった項目は今の値のまま）
+    pub fn apply_one_liner(&mut self, parsed: &OneLiner) {
+        for (field, value) in [(&mut self.artist, &parsed.artist), (&mut self.title, &parsed.title), (&mut self.label, &parsed.label)] {
+            if !value.is_empty() {
+                *field = value.clone();
+            }
+        }
+        if let Some(year) = parsed.year {
+            self.year = year;
+        }
+    }
+
+    /// 入力チェックを通る最小限のレコード。足りない必須項目は仮の値で埋め、下書きにする。
+    pub fn to_record(&self) -> MusicData {
+        let leader = if self.artist.trim().is_empty() {
+            vec![]
+        } else {
+            vec![LeaderEntry { name: self.artist.trim().to_string(), tracks: "all".into(), ..Default::default() }]
+        };
+        MusicData {
+            title: self.title.trim().to_string(),
+            janre: Janre { main: self.main.clone(), sub: vec![self.sub.clone()] },
+            label: if self.label.trim().is_empty() { PLACEHOLDER.into() } else { self.label.trim().to_string() },
+            id: PLACEHOLDER.into(),
+            release_year: self.year,
+            record_year: vec![self.year],
+            personnel: Personnel { leader, ..Default::default() },
+            tracks: vec![Track {
+                disc_no: 1,
+                no: 1,
+                title: PLACEHOLDER.into(),
+                composer: String::new(),
+                length: crate::rules::UNKNOWN_LENGTH.into(),
+            }],
+            score: self.score,
+            date: self.date.clone(),
+            draft: true,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct QuickEntryProps {
+    /// 既存ファイル名一覧（"xxx.json" 形式）。同名チェックに使う。
+    pub existing_filenames: Vec<String>,
+    /// 保存の待ち時間（秒）
+    pub timeout_secs: u32,
+    /// 必須項目のポリシー（クイック入力で埋めない項目が必須なら保存できない）
+    pub policy: ValidationPolicy,
+    /// 入力チェックで止まったらサーバーに知らせる（設定の log_validation_failures）
+    #[prop_or_default]
+    pub log_failures: bool,
+    /// 保存できたら一覧を読み直す
+    pub on_saved: Callback<()>,
+    /// 保存したレコードを編集画面で開く（"xxx.json"）
+    pub on_select: Callback<String>,
+}
+
+#[function_component(QuickEntryForm)]
+pub fn quick_entry_form(props: &QuickEntryProps) -> Html {
+    let min_score = props.policy.score.min;
+    let entry = use_state(|| {
+        let today = crate::app::today_str();
+        QuickEntry::new(today[..4].parse().unwrap_or(2000), today, min_score)
+    });
+    let status = use_state(|| None::<Result<String, String>>);
+    let busy = use_state(|| false);
+    let progress = use_state(|| None::<(f64, f64)>);
+
+    let edit = |f: fn(&mut QuickEntry, String)| {
+        let entry = entry.clone();
+        Callback::from(move |e: Event| {
+            let value = e
+                .target_dyn_into::<web_sys::HtmlInputElement>()
+                .map(|i| i.value())
+                .or_else(|| e.target_dyn_into::<web_sys::HtmlSelectElement>().map(|s| s.value()))
+                .unwrap_or_default();
+            let mut q = (*entry).clone();
+            f(&mut q, value);
+            entry.set(q);
+        })
+    };
+
+    let on_save = {
+        let entry = entry.clone();
+        let status = status.clone();
+        let busy = busy.clone();
+        let progress = progress.clone();
+        let existing = props.existing_filenames.clone();
+        let timeout_secs = props.timeout_secs;
+        let policy = props.policy.clone();
+        let log_failures = props.log_failures;
+        let on_saved = props.on_saved.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let q = (*entry).clone();
+            let filename = q.filename();
+            let data = q.to_record();
+            let errs = validate_form(&data, &filename, &policy);
+            if let Some(first) = errs.iter().next().map(|(field, msg)| format!("{}: {}", field, msg)) {
+                status.set(Some(Err(first)));
+                if log_failures {
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let _ = crate::api::report_validation_failures("quick_entry", &errs).await;
+                    });
+                }
+                return;
+            }
+            let name = format!("{}.json", filename);
+            if existing.contains(&name) {
+                status.set(Some(Err(format!("{} は既に存在します", name))));
+                return;
+            }
+            crate::crash::record_action("quick_save", Some(&name));
+            let (entry, status, busy, progress, on_saved) =
+                (entry.clone(), status.clone(), busy.clone(), progress.clone(), on_saved.clone());
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                // 同じ名前のレコードがほかで作られていたら上書きしない
+                match crate::app::save_with_retry(&filename, &data, &crate::api::SaveBase::New, timeout_secs, progress).await {
+                    Ok(_) => {
+                        status.set(Some(Ok(name)));
+                        // 続けて登録できるよう、ジャンル・記録日は残してタイトル・アーティスト・レーベルだけ空にする
+                        entry.set(QuickEntry { title: String::new(), artist: String::new(), label: String::new(), ..q });
+                        on_saved.emit(());
+                    }
+                    Err(e) => status.set(Some(Err(crate::app::save_error_text(e)))),
+                }
+                busy.set(false);
+            });
+        })
+    };
+
+    let on_one_liner = {
+        let entry = entry.clone();
+        Callback::from(move |e: Event| {
+            let Some(input) = e.target_dyn_into::<web_sys::HtmlInputElement>() else {
+                return;
+            };
+            let mut q = (*entry).clone();
+            q.apply_one_liner(&parse_one_liner(&input.value()));
+            entry.set(q);
+        })
+    };
+
+    let q = &*entry;
+    html! {
+        <div class="quick-entry">
+            <h2 class="view-title">{"クイック入力"}</h2>
+            <p class="hint">{ format!("必要最小限の項目だけで下書きとして登録します。レーベル（空のとき）・品番・トラックは「{}」で仮登録されるので、あとで編集画面から埋めてください。", PLACEHOLDER) }</p>
+            <div class="field">
+                <label for="quick-one-liner">{"1 行で"}</label>
+                <input id="quick-one-liner" type="text" class="input" placeholder="Bill Evans - Alone (1968) Verve"
+                    onchange={on_one_liner}/>
+                <p class="hint">{"「アーティスト - タイトル (発売年) レーベル」の形で書いて Enter を押すと、下の項目を埋めます。"}</p>
+            </div>
+            <form class="music-form" onsubmit={on_save}>
+                <div class="form-section">
+                    <div class="field">
+                        <label for="quick-title">{"Title"}</label>
+                        <input id="quick-title" type="text" class="input" maxlength="128" required={true}
+                            value={q.title.clone()} onchange={edit(|q, v| q.title = v)}/>
+                    </div>
+                    <div class="field">
+                        <label for="quick-artist">{"Artist / Leader"}</label>
+                        <input id="quick-artist" type="text" class="input" maxlength="128"
+                            value={q.artist.clone()} onchange={edit(|q, v| q.artist = v)}/>
+                    </div>
+                    <div class="field">
+                        <label for="quick-label">{"Label"}</label>
+                        <input id="quick-label" type="text" class="input" maxlength="64" placeholder={PLACEHOLDER}
+                            value={q.label.clone()} onchange={edit(|q, v| q.label = v)}/>
+                    </div>
+                    <div class="field">
+                        <label for="quick-main">{"Main Janre"}</label>
+                        <select id="quick-main" class="input" onchange={edit(|q, v| {
+                            q.sub = sub_janres_for_main(&v).first().copied().unwrap_or_default().to_string();
+                            q.main = v;
+                        })}>
+                            { for MAIN_JANRES.iter().map(|&g| html! { <option value={g} selected={q.main == g}>{ g }</option> }) }
+                        </select>
+                    </div>
+                    <div class="field">
+                        <label for="quick-sub">{"Sub Janre"}</label>
+                        <select id="quick-sub" key={q.main.clone()} class="input" onchange={edit(|q, v| q.sub = v)}>
+                            { for sub_janres_for_main(&q.main).iter().map(|&g| html! { <option value={g} selected={q.sub == g}>{ g }</option> }) }
+                        </select>
+                    </div>
+                    <div class="field">
+                        <label for="quick-year">{"Release Year"}</label>
+                        <input id="quick-year" type="number" class="input" min="1900" max="2099"
+                            value={q.year.to_string()} onchange={edit(|q, v| q.year = v.trim().parse().unwrap_or(q.year))}/>
+                    </div>
+                    <div class="field">
+                        <label for="quick-score">{"Score"}</label>
+                        <select id="quick-score" class="input" onchange={edit(|q, v| q.score = v.parse().unwrap_or(q.score))}>
+                            { for props.policy.score.values().into_iter().map(|s| html! {
+                                <option value={format_score(s)} selected={q.score == s}>{ format_score(s) }</option>
+                            }) }
+                        </select>
+                    </div>
+                    <div class="field">
+                        <label for="quick-date">{"Date"}</label>
+                        <input id="quick-date" type="text" class="input" placeholder="YYYY/MM/DD"
+                            value={q.date.clone()} onchange={edit(|q, v| q.date = v.trim().to_string())}/>
+                    </div>
+                </div>
+                <button type="submit" class="btn-save" disabled={*busy}>
+                    { if *busy { "保存中..." } else { "下書きとして保存" } }
+                </button>
+            </form>
+            <div role="status" aria-live="polite">
+                { match &*status {
+                    None => html! {},
+                    Some(Ok(name)) => {
+                        let on_select = props.on_select.clone();
+                        let name = name.clone();
+                        html! {
+                            <p class="save-ok">
+                                { format!("{} を保存しました。", name) }
+                                <a href="#" onclick={move |e: MouseEvent| { e.prevent_default(); on_select.emit(name.clone()); }}>
+                                    {"編集画面で開く"}
+                                </a>
+                            </p>
+                        }
+                    }
+                    Some(Err(e)) => html! { <p class="save-err">{ e.clone() }</p> },
+                } }
+            </div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod quick_entry_tests {
+    use super::{parse_one_liner, QuickEntry};
+    use crate::validation::{validate_form, ValidationPolicy};
+
+    #[test]
+    fn minimal_record_passes_validation_as_draft() {
+        let q = QuickEntry {
+            title: "Kind of Blue".into(),
+            artist: "Miles Davis".into(),
+            label: String::new(),
+            main: "Jazz".into(),
+            sub: "Mode".into(),
+            year: 1959,
+            score: 6.0,
+            date: "2026/10/16".into(),
+        };
+        assert_eq!(q.filename(), "Miles_Davis__Kind_of_Blue");
+        let data = q.to_record();
+        assert!(data.draft);
+        assert_eq!(data.personnel.leader[0].name, "Miles Davis");
+        assert_eq!(validate_form(&data, &q.filename(), &ValidationPolicy::default()), Default::default());
+    }
+
+    #[test]
+    fn one_liner_fills_only_what_it_found() {
+        let mut q = QuickEntry::new(2026, "2026/10/16".into(), 1.0);
+        q.title = "typed".into();
+        q.apply_one_liner(&parse_one_liner("Bill Evans - Alone (1968) Verve"));
+        assert_eq!((q.artist.as_str(), q.title.as_str(), q.year, q.label.as_str()), ("Bill Evans", "Alone", 1968, "Verve"));
+        assert_eq!(q.to_record().label, "Verve");
+        q.apply_one_liner(&parse_one_liner("Waltz for Debby"));
+        assert_eq!((q.artist.as_str(), q.title.as_str(), q.year), ("Bill Evans", "Waltz for Debby", 1968));
+    }
+}
@@ -0,0 +1,44 @@
+use crate::api::{self, ListFilter};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct RandomPickProps {
+    /// サイドバーの絞り込み（ジャンル・スコア・年）。この中から選ぶ
+    pub filter: ListFilter,
+    pub on_select: Callback<String>,
+}
+
+/// 「何を聴くか決めて」。手持ちのレコードからでたらめに 1 枚選んで開く（`GET /api/random`）。
+#[function_component(RandomPickButton)]
+pub fn random_pick_button(props: &RandomPickProps) -> Html {
+    let error = use_state(|| None::<String>);
+    let busy = use_state(|| false);
+    let onclick = {
+        let (filter, on_select, error, busy) = (props.filter.clone(), props.on_select.clone(), error.clone(), busy.clone());
+        Callback::from(move |_: MouseEvent| {
+            let (filter, on_select, error, busy) = (filter.clone(), on_select.clone(), error.clone(), busy.clone());
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::random(&filter).await {
+                    Ok(pick) => {
+                        error.set(None);
+                        on_select.emit(pick.filename);
+                    }
+                    Err(e) => error.set(Some(e)),
+                }
+                busy.set(false);
+            });
+        })
+    };
+    html! {
+        <div class="form-section">
+            <button type="button" class="btn-add" {onclick} disabled={*busy}>{"何を聴くか決めて"}</button>
+            if !props.filter.is_empty() {
+                <span class="hint">{" サイドバーの絞り込みの中から選びます"}</span>
+            }
+            if let Some(e) = &*error {
+                <p class="save-err" role="status">{ e.clone() }</p>
+            }
+        </div>
+    }
+}
@@ -0,0 +1,79 @@
+use crate::api::{self, RecentEntry};
+use yew::prelude::*;
+
+/// 出す件数
+const RECENT_ROWS: usize = 10;
+
+/// 更新日の表示。ライブラリの時差の日付（YYYY/MM/DD）、なければ更新日時の日付の部分
+pub fn describe(e: &RecentEntry) -> String {
+    if !e.modified_date.is_empty() {
+        return e.modified_date.clone();
+    }
+    e.modified.get(..10).unwrap_or(&e.modified).replace('-', "/")
+}
+
+#[derive(Properties, PartialEq)]
+pub struct RecentlyEditedProps {
+    /// レコード名クリックでそのレコードを開く（"xxx.json"）
+    pub on_select: Callback<String>,
+}
+
+/// 最近編集したレコード（`GET /api/recent`）。編集していないときの最初の画面に出す。
+/// 読めなくてもエラーは出さず何も表示しない。
+#[function_component(RecentlyEdited)]
+pub fn recently_edited(props: &RecentlyEditedProps) -> Html {
+    let list = use_state(Vec::<RecentEntry>::new);
+    {
+        let list = list.clone();
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Ok(l) = api::recent(RECENT_ROWS).await {
+                    list.set(l);
+                }
+            });
+            || ()
+        });
+    }
+    if list.is_empty() {
+        return html! {};
+    }
+    html! {
+        <div class="form-section">
+            <h3>{"最近編集したもの"}</h3>
+            <ul class="note-list">
+                { for list.iter().map(|e| {
+                    let on_select = props.on_select.clone();
+                    let filename = e.filename.clone();
+                    html! {
+                        <li class="note-item" key={e.filename.clone()}>
+                            <a class="note-text" href="#" title={e.filename.clone()}
+                                onclick={move |ev: MouseEvent| { ev.prevent_default(); on_select.emit(filename.clone()); }}>
+                                { e.display_label.clone() }
+                            </a>
+                            <span class="note-time">{ describe(e) }</span>
+                        </li>
+                    }
+                }) }
+            </ul>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod recent_tests {
+    use super::describe;
+    use crate::api::RecentEntry;
+
+    #[test]
+    fn library_date_first_then_the_timestamp() {
+        let mut e = RecentEntry {
+            filename: "a.json".into(),
+            display_label: "A".into(),
+            modified: "2026-10-15T23:30:00Z".into(),
+            modified_date: "2026/10/16".into(),
+        };
+        assert_eq!(describe(&e), "2026/10/16");
+        e.modified_date.clear();
+        assert_eq!(describe(&e), "2026/10/15");
+    }
+}
@@ -0,0 +1,103 @@
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+/// 並び順 `order` の中で `current` の前後のファイル名。一覧にないときは両方 None。
+pub fn neighbors(order: &[String], current: &str) -> (Option<String>, Option<String>) {
+    match order.iter().position(|f| f == current) {
+        Some(i) => (i.checked_sub(1).map(|p| order[p].clone()), order.get(i + 1).cloned()),
+        None => (None, None),
+    }
+}
+
+/// 保存していない変更があれば、捨ててよいか確かめる
+pub fn confirm_discard(dirty: bool) -> bool {
+    !dirty
+        || gloo_utils::window()
+            .confirm_with_message("保存していない変更があります。破棄して移動しますか？")
+            .unwrap_or(false)
+}
+
+/// 入力欄で打っている最中か（j / k をそのまま文字として使う）
+fn typing(e: &KeyboardEvent) -> bool {
+    e.target()
+        .and_then(|t| t.dyn_into::<web_sys::HtmlElement>().ok())
+        .is_some_and(|el| matches!(el.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT") || el.is_content_editable())
+}
+
+#[derive(Properties, PartialEq)]
+pub struct RecordNavProps {
+    /// サイドバーに今出ている順（絞り込み済み）
+    pub order: Vec<String>,
+    pub current: String,
+    /// 移動先のファイル名（未保存の確認は呼ぶ側で行う）
+    pub on_go: Callback<String>,
+}
+
+/// レコード見出しの ◀ / ▶。サイドバーの並び順どおりに前後のレコードへ移る。j で次、k で前。
+#[function_component(RecordNav)]
+pub fn record_nav(props: &RecordNavProps) -> Html {
+    let (prev, next) = neighbors(&props.order, &props.current);
+    let position = props.order.iter().position(|f| *f == props.current);
+
+    {
+        let (prev, next, on_go) = (prev.clone(), next.clone(), props.on_go.clone());
+        use_effect_with((prev, next), move |(prev, next)| {
+            let (prev, next) = (prev.clone(), next.clone());
+            let listener = Closure::<dyn FnMut(KeyboardEvent)>::new(move |e: KeyboardEvent| {
+                if e.ctrl_key() || e.meta_key() || e.alt_key() || typing(&e) {
+                    return;
+                }
+                let target = match e.key().as_str() {
+                    "j" => next.clone(),
+                    "k" => prev.clone(),
+                    _ => None,
+                };
+                if let Some(f) = target {
+                    e.prevent_default();
+                    on_go.emit(f);
+                }
+            });
+            let document = gloo_utils::document();
+            let _ = document.add_event_listener_with_callback("keydown", listener.as_ref().unchecked_ref());
+            move || {
+                let _ = document.remove_event_listener_with_callback("keydown", listener.as_ref().unchecked_ref());
+            }
+        });
+    }
+
+    let go = |target: &Option<String>| {
+        let (target, on_go) = (target.clone(), props.on_go.clone());
+        Callback::from(move |_: MouseEvent| {
+            if let Some(f) = target.clone() {
+                on_go.emit(f);
+            }
+        })
+    };
+
+    html! {
+        <div class="record-nav">
+            <button type="button" class="btn-add" onclick={go(&prev)} disabled={prev.is_none()}
+                aria-label="前のレコード" title="前のレコード（k）">{"◀"}</button>
+            <span class="record-nav-position">
+                { position.map(|i| format!("{} / {}", i + 1, props.order.len())).unwrap_or_default() }
+            </span>
+            <button type="button" class="btn-add" onclick={go(&next)} disabled={next.is_none()}
+                aria-label="次のレコード" title="次のレコード（j）">{"▶"}</button>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod record_nav_tests {
+    use super::neighbors;
+
+    #[test]
+    fn neighbors_follow_the_given_order() {
+        let order: Vec<String> = ["b.json", "a.json", "c.json"].map(String::from).into();
+        assert_eq!(neighbors(&order, "b.json"), (None, Some("a.json".into())));
+        assert_eq!(neighbors(&order, "a.json"), (Some("b.json".into()), Some("c.json".into())));
+        assert_eq!(neighbors(&order, "c.json"), (Some("a.json".into()), None));
+        assert_eq!(neighbors(&order, "x.json"), (None, None));
+    }
+}
@@ -0,0 +1,126 @@
+use crate::api::{self, DuplicateCandidate, DuplicateKind};
+use crate::types::MusicData;
+use yew::prelude::*;
+
+/// 入力が止まってからチェックするまでの待ち時間（ミリ秒）
+const CHECK_DEBOUNCE_MS: u32 = 600;
+
+#[derive(Properties, PartialEq)]
+pub struct RelatedPanelProps {
+    pub data: MusicData,
+    pub on_data_change: Callback<MusicData>,
+    /// 編集中のファイル名（新規なら None）。自分自身は候補から除く。
+    pub selected_filename: Option<String>,
+}
+
+/// チェック対象が変わったかの判定用キー（タイトル・別題・id・アーティスト名）
+fn check_key(d: &MusicData) -> String {
+    let p = &d.personnel;
+    let names = p
+        .leader
+        .iter()
+        .map(|e| (e.name.as_str(), e.name_alt.as_str()))
+        .chain(p.group.iter().map(|e| (e.name.as_str(), "")))
+        .chain(p.soloists.iter().map(|e| (e.name.as_str(), e.name_alt.as_str())))
+        .chain(p.conductor.iter().map(|e| (e.name.as_str(), e.name_alt.as_str())))
+        .chain(p.orchestra.iter().map(|e| (e.name.as_str(), "")))
+        .map(|(n, a)| format!("{}/{}", n, a))
+        .collect::<Vec<_>>()
+        .join("|");
+    format!("{}\n{}\n{}\n{}", d.title, d.title_alt, d.id, names)
+}
+
+/// 重複・関連レコードの候補表示。エディション違いは統合せず「関連として紐付け」で `related` に入れる。
+#[function_component(RelatedPanel)]
+pub fn related_panel(props: &RelatedPanelProps) -> Html {
+    let candidates = use_state(Vec::<DuplicateCandidate>::new);
+    let generation = use_mut_ref(|| 0u32);
+
+    {
+        let candidates = candidates.clone();
+        let data = props.data.clone();
+        let filename = props.selected_filename.clone();
+        use_effect_with((check_key(&props.data), props.selected_filename.clone()), move |_| {
+            let gen = {
+                let mut g = generation.borrow_mut();
+                *g = g.wrapping_add(1);
+                *g
+            };
+            if data.title.trim().is_empty() && data.id.trim().is_empty() {
+                candidates.set(vec![]);
+            } else {
+                wasm_bindgen_futures::spawn_local(async move {
+                    gloo_timers::future::TimeoutFuture::new(CHECK_DEBOUNCE_MS).await;
+                    if *generation.borrow() != gen {
+                        return;
+                    }
+                    if let Ok(list) = api::check_duplicates(&data, filename.as_deref()).await {
+                        if *generation.borrow() == gen {
+                            candidates.set(list);
+                        }
+                    }
+                });
+            }
+            || ()
+        });
+    }
+
+    let set_link = |filename: String, link: bool| {
+        let data = props.data.clone();
+        let on_data_change = props.on_data_change.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut d = data.clone();
+            d.related.retain(|f| f != &filename);
+            if link {
+                d.related.push(filename.clone());
+            }
+            on_data_change.emit(d);
+        })
+    };
+
+    // 紐付け済みで候補に出ていないもの（タイトルを変えた後など）も一覧に残す
+    let unlisted: Vec<&String> = props
+        .data
+        .related
+        .iter()
+        .filter(|f| !candidates.iter().any(|c| &c.filename == *f))
+        .collect();
+
+    if candidates.is_empty() && unlisted.is_empty() {
+        return html! {};
+    }
+
+    html! {
+        <div class="form-section related-panel" aria-live="polite">
+            <h3>{"重複・関連レコード"}</h3>
+            <ul class="related-list">
+                { for candidates.iter().map(|c| {
+                    let linked = props.data.related.contains(&c.filename);
+                    html! {
+                        <li key={c.filename.clone()} class="related-item">
+                            <span class={if c.kind == DuplicateKind::Duplicate { "related-kind related-kind-duplicate" } else { "related-kind" }}>
+                                { if c.kind == DuplicateKind::Duplicate { "重複の可能性" } else { "関連" } }
+                            </span>
+                            <span class="related-label" title={c.filename.clone()}>{ c.display_label.clone() }</span>
+                            if let Some(ref ed) = c.edition {
+                                <span class="related-edition">{ ed.clone() }</span>
+                            }
+                            if linked {
+                                <button type="button" class="btn-remove" onclick={set_link(c.filename.clone(), false)}>{"紐付け解除"}</button>
+                            } else {
+                                <button type="button" class="btn-add" onclick={set_link(c.filename.clone(), true)}>{"関連として紐付け"}</button>
+                            }
+                        </li>
+                    }
+                }) }
+                { for unlisted.iter().map(|f| html! {
+                    <li key={(*f).clone()} class="related-item">
+                        <span class="related-kind">{"紐付け済み"}</span>
+                        <span class="related-label">{ (*f).clone() }</span>
+                        <button type="button" class="btn-remove" onclick={set_link((*f).clone(), false)}>{"紐付け解除"}</button>
+                    </li>
+                }) }
+            </ul>
+        </div>
+    }
+}
@@ -0,0 +1,244 @@
+use crate::api::{self, ListenStats};
+use crate::download::download_text;
+use yew::prelude::*;
+
+/// レポートに載せる再生回数上位の件数
+const TOP_RECORDS: usize = 20;
+
+fn period_label(stats: &ListenStats) -> String {
+    format!(
+        "{} 〜 {}",
+        stats.from.as_deref().unwrap_or("（最初）"),
+        stats.to.as_deref().unwrap_or("（最新）")
+    )
+}
+
+/// 再生レポートを Markdown にする。
+pub fn listen_report_markdown(stats: &ListenStats) -> String {
+    let mut md = String::new();
+    md.push_str("# Listening Report\n\n");
+    md.push_str(&format!("- 期間: {}\n", period_label(stats)));
+    md.push_str(&format!("- 再生回数: {}\n\n", stats.total));
+
+    md.push_str("## よく聴いたレコード\n\n");
+    md.push_str("| # | レコード | 回数 |\n|---:|---|---:|\n");
+    for (i, r) in stats.records.iter().take(TOP_RECORDS).enumerate() {
+        md.push_str(&format!("| {} | {} | {} |\n", i + 1, r.display_label.replace('|', "\\|"), r.count));
+    }
+
+    md.push_str("\n## 週ごとの再生回数\n\n");
+    md.push_str("| 週（月曜） | 回数 |\n|---|---:|\n");
+    for w in &stats.weeks {
+        md.push_str(&format!("| {} | {} |\n", w.week_start, w.count));
+    }
+
+    md.push_str("\n## ジャンル比率\n\n");
+    md.push_str("| ジャンル | 回数 | 割合 |\n|---|---:|---:|\n");
+    for g in &stats.genres {
+        let genre = if g.genre.is_empty() { "(未設定)" } else { g.genre.as_str() };
+        md.push_str(&format!("| {} | {} | {:.1}% |\n", genre, g.count, g.ratio * 100.0));
+    }
+    md
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ListeningReportProps {
+    /// レコード名クリックでそのレコードを開く（"xxx.json"）
+    pub on_select: Callback<String>,
+}
+
+fn input_value(e: &InputEvent) -> Option<String> {
+    e.target_dyn_into::<web_sys::HtmlInputElement>().map(|i| i.value())
+}
+
+/// 再生記録の期間集計ページ。よく聴いたレコード・週ごとの回数・ジャンル比率を表示し、Markdown で書き出せる。
+#[function_component(ListeningReport)]
+pub fn listening_report(props: &ListeningReportProps) -> Html {
+    let from = use_state(String::new);
+    let to = use_state(String::new);
+    let stats = use_state(|| None::<Result<ListenStats, String>>);
+    let loading = use_state(|| false);
+    let year = use_state(|| crate::app::today_str()[..4].to_string());
+
+    let load = {
+        let from = from.clone();
+        let to = to.clone();
+        let stats = stats.clone();
+        let loading = loading.clone();
+        Callback::from(move |_: ()| {
+            let (f, t) = ((*from).trim().to_string(), (*to).trim().to_string());
+            let stats = stats.clone();
+            let loading = loading.clone();
+            loading.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                stats.set(Some(api::listen_stats(&f, &t).await));
+                loading.set(false);
+            });
+        })
+    };
+
+    {
+        let load = load.clone();
+        use_effect_with((), move |_| {
+            load.emit(());
+            || ()
+        });
+    }
+
+    let on_submit = {
+        let load = load.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            load.emit(());
+        })
+    };
+    let on_from = {
+        let from = from.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(v) = input_value(&e) {
+                from.set(v);
+            }
+        })
+    };
+    let on_to = {
+        let to = to.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(v) = input_value(&e) {
+                to.set(v);
+            }
+        })
+    };
+
+    let on_year = {
+        let year = year.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(v) = input_value(&e) {
+                year.set(v);
+            }
+        })
+    };
+
+    let body = match &*stats {
+        None => html! {},
+        Some(Err(e)) => html! { <p class="load-err">{"ロードエラー: "}{ e.clone() }</p> },
+        Some(Ok(s)) => {
+            let md = listen_report_markdown(s);
+            let on_export = Callback::from(move |_: MouseEvent| {
+                let _ = download_text("listening_report.md", "text/markdown", &md);
+            });
+            let max_week = s.weeks.iter().map(|w| w.count).max().unwrap_or(0).max(1);
+            html! {
+                <>
+                    <div class="form-section">
+                        <h3>{ format!("{}（{} 回）", period_label(s), s.total) }</h3>
+                        <button type="button" class="btn-add" onclick={on_export}>{"Markdownで書き出す"}</button>
+                    </div>
+                    <div class="form-section">
+                        <h3>{"よく聴いたレコード"}</h3>
+                        if s.records.is_empty() {
+                            <p class="hint">{"この期間の再生記録はありません"}</p>
+                        }
+                        <ol class="report-list">
+                            { for s.records.iter().take(TOP_RECORDS).map(|r| {
+                                let on_select = props.on_select.clone();
+                                let filename = r.filename.clone();
+                                html! {
+                                    <li key={r.filename.clone()}>
+                                        <a href="#" onclick={move |e: MouseEvent| { e.prevent_default(); on_select.emit(filename.clone()); }}>
+                                            { r.display_label.clone() }
+                                        </a>
+                                        <span class="report-count">{ format!("{} 回", r.count) }</span>
+                                    </li>
+                                }
+                            }) }
+                        </ol>
+                    </div>
+                    <div class="form-section">
+                        <h3>{"週ごとの再生回数"}</h3>
+                        <div class="report-bars">
+                            { for s.weeks.iter().map(|w| html! {
+                                <div class="report-bar-row" key={w.week_start.clone()}>
+                                    <span class="report-bar-label">{ w.week_start.clone() }</span>
+                                    <span class="report-bar" style={format!("width: {}%", w.count * 100 / max_week)}></span>
+                                    <span class="report-count">{ w.count }</span>
+                                </div>
+                            }) }
+                        </div>
+                    </div>
+                    <div class="form-section">
+                        <h3>{"ジャンル比率"}</h3>
+                        <div class="report-bars">
+                            { for s.genres.iter().map(|g| html! {
+                                <div class="report-bar-row" key={g.genre.clone()}>
+                                    <span class="report-bar-label">{ if g.genre.is_empty() { "(未設定)".to_string() } else { g.genre.clone() } }</span>
+                                    <span class="report-bar" style={format!("width: {:.0}%", g.ratio * 100.0)}></span>
+                                    <span class="report-count">{ format!("{:.1}%", g.ratio * 100.0) }</span>
+                                </div>
+                            }) }
+                        </div>
+                    </div>
+                </>
+            }
+        }
+    };
+
+    html! {
+        <div class="listening-report">
+            <h2 class="view-title">{"再生レポート"}</h2>
+            <form class="form-section report-range" onsubmit={on_submit}>
+                <label>{"期間"}</label>
+                <input type="text" class="input report-date" placeholder="YYYY/MM/DD" aria-label="開始日" value={(*from).clone()} oninput={on_from}/>
+                <span>{"〜"}</span>
+                <input type="text" class="input report-date" placeholder="YYYY/MM/DD" aria-label="終了日" value={(*to).clone()} oninput={on_to}/>
+                <button type="submit" class="btn-add" disabled={*loading}>{"集計"}</button>
+            </form>
+            <div class="form-section report-range">
+                <label>{"年間のふりかえり"}</label>
+                <input type="number" class="input settings-number" min="1" max="9999" aria-label="ふりかえる年"
+                    value={(*year).clone()} oninput={on_year}/>
+                <a class="btn-add" href={api::year_review_url(&year)} download={format!("year-review-{}.md", year.trim())}>
+                    {"Markdownをダウンロード"}
+                </a>
+                <span class="hint">{"その年に登録したレコード・評価・ジャンル・よく聴いたもの・いちばん長いアルバム"}</span>
+            </div>
+            if *loading {
+                <p class="sidebar-loading">{"集計中..."}</p>
+            }
+            { body }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod report_tests {
+    use super::listen_report_markdown;
+    use crate::api::{GenreShare, ListenStats, RecordListens, WeekListens};
+
+    #[test]
+    fn markdown_has_all_sections() {
+        let stats = ListenStats {
+            from: Some("2026/10/01".into()),
+            to: None,
+            total: 3,
+            records: vec![RecordListens {
+                filename: "a.json".into(),
+                display_label: "A | B".into(),
+                count: 3,
+            }],
+            weeks: vec![WeekListens {
+                week_start: "2026/09/28".into(),
+                count: 3,
+            }],
+            genres: vec![GenreShare {
+                genre: String::new(),
+                count: 3,
+                ratio: 1.0,
+            }],
+        };
+        let md = listen_report_markdown(&stats);
+        assert!(md.contains("- 期間: 2026/10/01 〜 （最新）"));
+        assert!(md.contains("| 1 | A \\| B | 3 |"));
+        assert!(md.contains("| 2026/09/28 | 3 |"));
+        assert!(md.contains("| (未設定) | 3 | 100.0% |"));
+    }
+}
@@ -0,0 +1,162 @@
+use crate::api::{self, LibrarySize, PruneReport, RetentionStatus};
+use crate::attachments::format_size;
+use yew::prelude::*;
+
+/// 日数の表示（0 は「無期限」）
+fn days_label(days: u32) -> String {
+    if days == 0 {
+        "無期限".to_string()
+    } else {
+        format!("{} 日", days)
+    }
+}
+
+/// 件数と日数の表示（"新しい 50 個・90 日"、どちらも 0 なら「無期限」）
+fn keep_label(keep: usize, days: u32) -> String {
+    match (keep, days) {
+        (0, 0) => "無期限".to_string(),
+        (0, d) => format!("{} 日", d),
+        (k, 0) => format!("新しい {} 個", k),
+        (k, d) => format!("新しい {} 個・{} 日", k, d),
+    }
+}
+
+/// ライブラリごとの大きさ（"main 1.2 MB（3 件）・classical 200 KB"）
+fn library_sizes(list: &[LibrarySize], with_count: bool) -> String {
+    if list.is_empty() {
+        return "なし".to_string();
+    }
+    list.iter()
+        .map(|l| {
+            if with_count {
+                format!("{} {}（{} 件）", l.library, format_size(l.bytes), l.count)
+            } else {
+                format!("{} {}", l.library, format_size(l.bytes))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("・")
+}
+
+/// 整理の結果の 1 行（"スナップショット 2 個・ログ 120 行・履歴のコミット 30 個・ごみ箱 4 件を消し、3.4 MB 空きました"）
+pub fn report_summary(r: &PruneReport) -> String {
+    let lines: usize = r.logs.iter().map(|l| l.lines).sum();
+    let commits: usize = r.history.iter().map(|h| h.count).sum();
+    let trashed: usize = r.trash.iter().map(|t| t.count).sum();
+    if r.snapshots.is_empty() && lines == 0 && commits == 0 && trashed == 0 {
+        return "消すものはありませんでした".to_string();
+    }
+    let mut parts = vec![format!("スナップショット {} 個", r.snapshots.len()), format!("ログ {} 行", lines)];
+    if commits > 0 {
+        parts.push(format!("履歴のコミット {} 個", commits));
+    }
+    if trashed > 0 {
+        parts.push(format!("ごみ箱 {} 件", trashed));
+    }
+    format!("{}を消し、{} 空きました", parts.join("・"), format_size(r.reclaimed_bytes))
+}
+
+/// 設定画面の「保存期間」。スナップショット・ログ・履歴・ごみ箱の大きさ、前回の整理の結果を出し、今すぐ整理できる。
+/// 日数・件数はサーバーの起動オプション（`--backup-max-days` / `--log-max-days` / `--history-keep` など）で決める。
+#[function_component(RetentionSection)]
+pub fn retention_section() -> Html {
+    let status = use_state(|| None::<Result<RetentionStatus, String>>);
+    let result = use_state(|| None::<Result<PruneReport, String>>);
+    let busy = use_state(|| false);
+
+    let reload = {
+        let status = status.clone();
+        Callback::from(move |_: ()| {
+            let status = status.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                status.set(Some(api::retention_status().await));
+            });
+        })
+    };
+    {
+        let reload = reload.clone();
+        use_effect_with((), move |_| {
+            reload.emit(());
+            || ()
+        });
+    }
+    let on_prune = {
+        let (result, busy) = (result.clone(), busy.clone());
+        Callback::from(move |_: MouseEvent| {
+            let (result, busy, reload) = (result.clone(), busy.clone(), reload.clone());
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                result.set(Some(api::prune_now().await));
+                busy.set(false);
+                reload.emit(());
+            });
+        })
+    };
+
+    html! {
+        <div class="form-section">
+            <h3>{"保存期間"}</h3>
+            { match &*status {
+                None => html! { <p class="hint">{"読み込み中..."}</p> },
+                Some(Err(e)) => html! { <p class="load-err">{ e.clone() }</p> },
+                Some(Ok(s)) => html! {
+                    <>
+                        <p class="hint">
+                            { format!("スナップショット: {}（{} 個、{}）", days_label(s.retention.backup_max_days), s.snapshots, format_size(s.snapshot_bytes)) }
+                            if s.backup_keep > 0 {
+                                { format!("・新しい {} 個まで", s.backup_keep) }
+                            }
+                            <br/>
+                            { format!("ログ: {}（{}）", days_label(s.retention.log_max_days),
+                                s.logs.iter().map(|l| format!("{} {}", l.file, format_size(l.bytes))).collect::<Vec<_>>().join("・")) }
+                            if !s.history.is_empty() {
+                                <br/>
+                                { format!("履歴: {}（{}）", keep_label(s.retention.history_keep, s.retention.history_max_days), library_sizes(&s.history, false)) }
+                            }
+                            <br/>
+                            { format!("ごみ箱: {}（{}）", keep_label(s.retention.trash_keep, s.retention.trash_max_days), library_sizes(&s.trash, true)) }
+                        </p>
+                        if let Some(r) = &s.last_report {
+                            <p class="hint">{ format!("前回の整理（{}）: {}", r.at, report_summary(r)) }</p>
+                        }
+                    </>
+                },
+            } }
+            <button type="button" class="btn-add" onclick={on_prune} disabled={*busy}>
+                { if *busy { "整理中..." } else { "今すぐ整理" } }
+            </button>
+            <div role="status" aria-live="polite">
+                { match &*result {
+                    None => html! {},
+                    Some(Err(e)) => html! { <p class="save-err">{ e.clone() }</p> },
+                    Some(Ok(r)) => html! {
+                        <p class={if r.errors.is_empty() { "save-ok" } else { "save-warn" }}>
+                            { report_summary(r) }
+                            { for r.errors.iter().map(|e| html! { <><br/>{ e.clone() }</> }) }
+                        </p>
+                    },
+                } }
+            </div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod retention_tests {
+    use super::{keep_label, report_summary};
+    use crate::api::{LibrarySize, LogSize, PruneReport};
+
+    #[test]
+    fn summary_counts_snapshots_and_lines() {
+        let mut r = PruneReport::default();
+        assert_eq!(report_summary(&r), "消すものはありませんでした");
+        r.snapshots = vec!["2026-09-01T000000Z".into()];
+        r.logs = vec![LogSize { file: "audit.jsonl".into(), lines: 120, bytes: 2048 }];
+        r.reclaimed_bytes = 3 * 1024 * 1024;
+        assert_eq!(report_summary(&r), "スナップショット 1 個・ログ 120 行を消し、3.0 MB 空きました");
+        r.history = vec![LibrarySize { library: "main".into(), count: 30, bytes: 0 }];
+        r.trash = vec![LibrarySize { library: "main".into(), count: 4, bytes: 0 }];
+        assert_eq!(report_summary(&r), "スナップショット 1 個・ログ 120 行・履歴のコミット 30 個・ごみ箱 4 件を消し、3.0 MB 空きました");
+        assert_eq!((keep_label(0, 0), keep_label(50, 90)), ("無期限".to_string(), "新しい 50 個・90 日".to_string()));
+    }
+}
@@ -0,0 +1,156 @@
+use yew::prelude::*;
+
+/// localStorage のキー
+const STORAGE_KEY: &str = "nekokan_music.review_session";
+
+/// レビューの進み具合。始めたときのサイドバーの一覧（絞り込み済み）を順に見ていく。
+/// localStorage に置くので、再読み込みしても同じところから続けられる。
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ReviewSession {
+    /// 対象のファイル名（始めたときの並び順）
+    pub files: Vec<String>,
+    /// レビュー済みにしたファイル名
+    #[serde(default)]
+    pub reviewed: Vec<String>,
+    /// 最後に開いていたファイル名
+    #[serde(default)]
+    pub current: Option<String>,
+}
+
+impl ReviewSession {
+    pub fn new(files: Vec<String>) -> Self {
+        let current = files.first().cloned();
+        Self { files, reviewed: vec![], current }
+    }
+
+    pub fn is_reviewed(&self, filename: &str) -> bool {
+        self.reviewed.iter().any(|f| f == filename)
+    }
+
+    /// (レビュー済み, 全体)。対象から外れたファイル（消した・名前を変えた）は数えない。
+    pub fn progress(&self) -> (usize, usize) {
+        (self.files.iter().filter(|f| self.is_reviewed(f)).count(), self.files.len())
+    }
+
+    pub fn label(&self) -> String {
+        let (done, total) = self.progress();
+        format!("{} / {} 件レビュー済み", done, total)
+    }
+
+    pub fn mark_reviewed(&mut self, filename: &str) {
+        if !self.is_reviewed(filename) {
+            self.reviewed.push(filename.to_string());
+        }
+    }
+
+    /// `from` より後ろで、まだレビューしていない最初のもの（最後まで行ったら先頭から探す）
+    pub fn next_unreviewed(&self, from: Option<&str>) -> Option<String> {
+        let start = from.and_then(|f| self.files.iter().position(|x| x == f)).map_or(0, |i| i + 1);
+        let n = self.files.len();
+        (0..n).map(|k| &self.files[(start + k) % n.max(1)]).find(|f| !self.is_reviewed(f)).cloned()
+    }
+}
+
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window().and_then(|w| w.local_storage().ok().flatten())
+}
+
+/// 保存してあるレビューを読む（なければ None）
+pub fn load() -> Option<ReviewSession> {
+    storage()?.get_item(STORAGE_KEY).ok().flatten().and_then(|s| serde_json::from_str(&s).ok())
+}
+
+/// None ならレビューを終える（保存してあるものを消す）
+pub fn store(session: Option<&ReviewSession>) {
+    let Some(storage) = storage() else { return };
+    let _ = match session.and_then(|s| serde_json::to_string(s).ok()) {
+        Some(json) => storage.set_item(STORAGE_KEY, &json),
+        None => storage.remove_item(STORAGE_KEY),
+    };
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ReviewBarProps {
+    pub session: ReviewSession,
+    /// 開いているレコード
+    pub current: Option<String>,
+    /// 変わったレビュー（None なら終了）
+    pub on_change: Callback<Option<ReviewSession>>,
+    /// レコードを開く（未保存の確認は呼ぶ側で行う）
+    pub on_go: Callback<String>,
+}
+
+/// レコード見出しの上に出すレビューの進み具合と「済みにして次へ」
+#[function_component(ReviewBar)]
+pub fn review_bar(props: &ReviewBarProps) -> Html {
+    let session = &props.session;
+    let in_session = props.current.as_deref().is_some_and(|c| session.files.iter().any(|f| f == c));
+    let done_here = props.current.as_deref().is_some_and(|c| session.is_reviewed(c));
+
+    let on_done_next = {
+        let (session, current) = (session.clone(), props.current.clone());
+        let (on_change, on_go) = (props.on_change.clone(), props.on_go.clone());
+        Callback::from(move |_: MouseEvent| {
+            let mut s = session.clone();
+            if let Some(c) = &current {
+                s.mark_reviewed(c);
+            }
+            let next = s.next_unreviewed(current.as_deref());
+            on_change.emit(Some(s));
+            if let Some(f) = next {
+                on_go.emit(f);
+            }
+        })
+    };
+    let on_end = {
+        let (on_change, session) = (props.on_change.clone(), session.clone());
+        Callback::from(move |_: MouseEvent| {
+            let (done, total) = session.progress();
+            if done == total
+                || gloo_utils::window()
+                    .confirm_with_message("レビューを終了しますか？（進み具合は消えます）")
+                    .unwrap_or(false)
+            {
+                on_change.emit(None);
+            }
+        })
+    };
+    let (done, total) = session.progress();
+
+    html! {
+        <div class="review-bar" role="status">
+            <progress max={total.to_string()} value={done.to_string()} aria-label="レビューの進み具合"></progress>
+            <span class="review-bar-label">{ session.label() }</span>
+            if in_session {
+                <button type="button" class="btn-save" onclick={on_done_next}>
+                    { if done_here { "次の未レビューへ" } else { "済みにして次へ" } }
+                </button>
+            } else if let Some(f) = session.next_unreviewed(None) {
+                <button type="button" class="btn-add" onclick={props.on_go.reform(move |_: MouseEvent| f.clone())}>{"レビューに戻る"}</button>
+            }
+            <button type="button" class="btn-remove" onclick={on_end}>{"レビュー終了"}</button>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod review_tests {
+    use super::ReviewSession;
+
+    #[test]
+    fn progress_and_next_skip_reviewed() {
+        let mut s = ReviewSession::new(["a.json", "b.json", "c.json"].map(String::from).into());
+        assert_eq!(s.current.as_deref(), Some("a.json"));
+        s.mark_reviewed("b.json");
+        s.mark_reviewed("b.json");
+        s.mark_reviewed("gone.json");
+        assert_eq!(s.label(), "1 / 3 件レビュー済み");
+        assert_eq!(s.next_unreviewed(Some("a.json")).as_deref(), Some("c.json"));
+        // 最後まで行ったら先頭から
+        assert_eq!(s.next_unreviewed(Some("c.json")).as_deref(), Some("a.json"));
+        s.mark_reviewed("a.json");
+        s.mark_reviewed("c.json");
+        assert_eq!(s.next_unreviewed(None), None);
+        assert_eq!(ReviewSession::new(vec![]).next_unreviewed(None), None);
+    }
+}
@@ -0,0 +1,214 @@
+//! 日本語（かな）のアーティスト名からローマ字表記の候補を作る。
+//! 漢字は読みが決まらないため対象外（候補なし）とする。
+
+/// カタカナをひらがなに寄せる（ヴ・ヵ・ヶ を含む U+30A1..=U+30F6）。
+fn to_hiragana(c: char) -> char {
+    match c {
+        '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        _ => c,
+    }
+}
+
+fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{3096}' | '\u{30A1}'..='\u{30FA}' | 'ー')
+}
+
+fn is_kanji(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '々')
+}
+
+/// 拗音（きゃ 等）の2文字の組み合わせ。子音部と母音部に分けて返す。
+fn digraph(a: char, b: char) -> Option<(&'static str, &'static str)> {
+    let consonant = match a {
+        'き' => "ky",
+        'ぎ' => "gy",
+        'し' => "sh",
+        'じ' => "j",
+        'ち' => "ch",
+        'に' => "ny",
+        'ひ' => "hy",
+        'び' => "by",
+        'ぴ' => "py",
+        'み' => "my",
+        'り' => "ry",
+        _ => return None,
+    };
+    let vowel = match b {
+        'ゃ' => "a",
+        'ゅ' => "u",
+        'ょ' => "o",
+        _ => return None,
+    };
+    Some((consonant, vowel))
+}
+
+fn monograph(c: char) -> Option<&'static str> {
+    Some(match c {
+        'あ' | 'ぁ' => "a",
+        'い' | 'ぃ' => "i",
+        'う' | 'ぅ' => "u",
+        'え' | 'ぇ' => "e",
+        'お' | 'ぉ' => "o",
+        'か' | 'ゕ' => "ka",
+        'き' => "ki",
+        'く' => "ku",
+        'け' | 'ゖ' => "ke",
+        'こ' => "ko",
+        'が' => "ga",
+        'ぎ' => "gi",
+        'ぐ' => "gu",
+        'げ' => "ge",
+        'ご' => "go",
+        'さ' => "sa",
+        'し' => "shi",
+        'す' => "su",
+        'せ' => "se",
+        'そ' => "so",
+        'ざ' => "za",
+        'じ' => "ji",
+        'ず' => "zu",
+        'ぜ' => "ze",
+        'ぞ' => "zo",
+        'た' => "ta",
+        'ち' => "chi",
+        'つ' => "tsu",
+        'て' => "te",
+        'と' => "to",
+        'だ' => "da",
+        'ぢ' => "ji",
+        'づ' => "zu",
+        'で' => "de",
+        'ど' => "do",
+        'な' => "na",
+        'に' => "ni",
+        'ぬ' => "nu",
+        'ね' => "ne",
+        'の' => "no",
+        'は' => "ha",
+        'ひ' => "hi",
+        'ふ' => "fu",
+        'へ' => "he",
+        'ほ' => "ho",
+        'ば' => "ba",
+        'び' => "bi",
+        'ぶ' => "bu",
+        'べ' => "be",
+        'ぼ' => "bo",
+        'ぱ' => "pa",
+        'ぴ' => "pi",
+        'ぷ' => "pu",
+        'ぺ' => "pe",
+        'ぽ' => "po",
+        'ま' => "ma",
+        'み' => "mi",
+        'む' => "mu",
+        'め' => "me",
+        'も' => "mo",
+        'や' | 'ゃ' => "ya",
+        'ゆ' | 'ゅ' => "yu",
+        'よ' | 'ょ' => "yo",
+        'ら' => "ra",
+        'り' => "ri",
+        'る' => "ru",
+        'れ' => "re",
+        'ろ' => "ro",
+        'わ' | 'ゎ' => "wa",
+        'ゐ' => "i",
+        'ゑ' => "e",
+        'を' => "o",
+        'ん' => "n",
+        'ゔ' => "vu",
+        _ => return None,
+    })
+}
+
+/// 単語の先頭だけ大文字にする（"miles" → "Miles"）。
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// かなを含む名前のローマ字表記（ヘボン式、長音は省略）を返す。
+/// かなを含まない、または漢字を含む場合は None（候補なし）。
+/// 全角スペース・中黒は単語区切りとして半角スペースにする（例: "ヒノ・テルマサ" → "Hino Terumasa"）。
+#[must_use]
+pub fn romanize_name(name: &str) -> Option<String> {
+    let name = name.trim();
+    if !name.chars().any(is_kana) || name.chars().any(is_kanji) {
+        return None;
+    }
+    let chars: Vec<char> = name.chars().map(to_hiragana).collect();
+    let mut out = String::new();
+    let mut geminate = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == 'っ' {
+            geminate = true;
+            i += 1;
+            continue;
+        }
+        let (syllable, used) = match chars.get(i + 1).and_then(|&n| digraph(c, n)) {
+            Some((consonant, vowel)) => (Some(format!("{}{}", consonant, vowel)), 2),
+            None => (monograph(c).map(str::to_string), 1),
+        };
+        match syllable {
+            Some(s) => {
+                if geminate {
+                    // っち → tchi、それ以外は子音を重ねる
+                    if s.starts_with("ch") {
+                        out.push('t');
+                    } else if let Some(first) = s.chars().next() {
+                        out.push(first);
+                    }
+                }
+                out.push_str(&s);
+            }
+            None => match c {
+                'ー' => {}
+                '・' | '　' | ' ' => out.push(' '),
+                _ => out.push(c),
+            },
+        }
+        geminate = false;
+        i += used;
+    }
+    let words: Vec<String> = out.split_whitespace().map(capitalize).collect();
+    if words.is_empty() {
+        None
+    } else {
+        Some(words.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod romanize_tests {
+    use super::romanize_name;
+
+    #[test]
+    fn katakana_with_middle_dot() {
+        assert_eq!(romanize_name("ヒノ・テルマサ").as_deref(), Some("Hino Terumasa"));
+    }
+
+    #[test]
+    fn hiragana_with_digraph_and_geminate() {
+        assert_eq!(romanize_name("きょうこ").as_deref(), Some("Kyouko"));
+        assert_eq!(romanize_name("はっとり").as_deref(), Some("Hattori"));
+        assert_eq!(romanize_name("まっちゃ").as_deref(), Some("Matcha"));
+    }
+
+    #[test]
+    fn long_vowel_mark_is_dropped() {
+        assert_eq!(romanize_name("サトー ユーコ").as_deref(), Some("Sato Yuko"));
+    }
+
+    #[test]
+    fn no_candidate_for_latin_or_kanji() {
+        assert_eq!(romanize_name("Bill Evans"), None);
+        assert_eq!(romanize_name("日野皓正"), None);
+        assert_eq!(romanize_name("峰 ヒロミ"), None);
+    }
+}
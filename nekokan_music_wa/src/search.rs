@@ -0,0 +1,135 @@
+use crate::api::{self, SearchResult};
+use crate::focus::on_escape;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct SearchPaletteProps {
+    /// 検索結果をクリックしたときに "xxx.json" を渡す
+    pub on_select: Callback<String>,
+    pub selected: Option<String>,
+}
+
+/// ヒットしたフィールド名の表示用ラベル（タイトル・表示ラベルは一覧に出ているのでスニペット表示しない）
+fn field_label(field: &str) -> &str {
+    match field {
+        "comment" => "コメント",
+        "personnel" => "参加",
+        "tracks.title" => "曲",
+        "composers" | "tracks.composer" => "作曲",
+        other => other,
+    }
+}
+
+/// サイドバー上部の検索パレット。Enter で検索し、結果をスニペット付きで一覧表示する。Esc で閉じる。
+#[function_component(SearchPalette)]
+pub fn search_palette(props: &SearchPaletteProps) -> Html {
+    let query = use_state(String::new);
+    let include_comments = use_state(|| false);
+    let results = use_state(|| None::<Result<Vec<SearchResult>, String>>);
+    let searching = use_state(|| false);
+
+    let on_submit = {
+        let query = query.clone();
+        let include_comments = include_comments.clone();
+        let results = results.clone();
+        let searching = searching.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let q = query.trim().to_string();
+            if q.is_empty() {
+                results.set(None);
+                return;
+            }
+            let comments = *include_comments;
+            let results = results.clone();
+            let searching = searching.clone();
+            searching.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                results.set(Some(api::search(&q, comments).await));
+                searching.set(false);
+            });
+        })
+    };
+
+    let on_input = {
+        let query = query.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                query.set(inp.value());
+            }
+        })
+    };
+
+    let on_toggle_comments = {
+        let include_comments = include_comments.clone();
+        Callback::from(move |_: Event| include_comments.set(!*include_comments))
+    };
+
+    let on_clear = {
+        let query = query.clone();
+        let results = results.clone();
+        Callback::from(move |()| {
+            query.set(String::new());
+            results.set(None);
+        })
+    };
+
+    html! {
+        <div class="search-palette" role="search">
+            <form class="search-form" onsubmit={on_submit}>
+                <input
+                    type="search"
+                    class="search-input"
+                    placeholder="検索（Enter）"
+                    aria-label="レコードを検索"
+                    value={(*query).clone()}
+                    oninput={on_input}
+                    onkeydown={on_escape(on_clear.clone())}
+                />
+                if results.is_some() {
+                    <button type="button" class="search-clear" title="検索結果を閉じる（Esc）" onclick={on_clear.reform(|_| ())}>{"×"}</button>
+                }
+            </form>
+            <label class="sidebar-filter">
+                <input type="checkbox" checked={*include_comments} onchange={on_toggle_comments}/>
+                {"コメントも検索"}
+            </label>
+            if *searching {
+                <p class="sidebar-loading">{"検索中..."}</p>
+            } else if let Some(ref res) = *results {
+                { match res {
+                    Err(e) => html! { <p class="load-err search-err">{ e.clone() }</p> },
+                    Ok(list) if list.is_empty() => html! { <p class="search-empty">{"該当なし"}</p> },
+                    Ok(list) => html! {
+                        <ul class="search-results" aria-label="検索結果">
+                            { for list.iter().map(|r| {
+                                let filename = r.filename.clone();
+                                let is_selected = props.selected.as_deref() == Some(filename.as_str());
+                                let on_select = props.on_select.clone();
+                                html! {
+                                    <li key={filename.clone()}>
+                                        <button
+                                            class={if is_selected { "search-result selected" } else { "search-result" }}
+                                            title={filename.clone()}
+                                            onclick={move |_| on_select.emit(filename.clone())}
+                                        >
+                                            <span class="search-result-label">{ r.display_label.clone() }</span>
+                                            { for r.matches.iter().filter(|m| m.field != "title" && m.field != "display_label").map(|m| html! {
+                                                <span class="search-snippet">
+                                                    <span class="search-field">{ field_label(&m.field) }</span>
+                                                    { m.snippet.before.clone() }
+                                                    <mark>{ m.snippet.hit.clone() }</mark>
+                                                    { m.snippet.after.clone() }
+                                                </span>
+                                            }) }
+                                        </button>
+                                    </li>
+                                }
+                            }) }
+                        </ul>
+                    },
+                } }
+            }
+        </div>
+    }
+}
@@ -0,0 +1,526 @@
+use crate::api::{self, BadgeLegend, DateMode, ImportConflict, LabelFormat, RescaleReport, Settings, SidebarDensity, SidebarDisplay};
+use crate::filename::{validate_classical_pattern, CLASSICAL_FIELDS};
+use crate::focus::{enter_to_add, use_append_focus};
+use crate::label::{validate_template, TEMPLATE_FIELDS};
+use crate::types::MAIN_JANRES;
+use crate::validation::{format_score, ScoreScale, ValidationPolicy};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct SettingsViewProps {
+    pub settings: Settings,
+    /// 保存できたら新しい設定を親に返す
+    pub on_saved: Callback<Settings>,
+    /// 入力チェックのポリシー（スコアの尺度）
+    pub policy: ValidationPolicy,
+    /// スコアの尺度を変換したら新しいポリシーを親に返す
+    pub on_policy_changed: Callback<ValidationPolicy>,
+}
+
+/// 選べるスコアの尺度
+const SCORE_PRESETS: &[ScoreScale] = &[
+    ScoreScale { min: 1.0, max: 6.0, step: 1.0 },
+    ScoreScale { min: 1.0, max: 10.0, step: 1.0 },
+    ScoreScale { min: 0.5, max: 5.0, step: 0.5 },
+];
+
+/// 変換の確認で一覧に出す件数
+const PREVIEW_ROWS: usize = 20;
+
+#[derive(Properties, PartialEq)]
+struct ScoreScaleSectionProps {
+    policy: ValidationPolicy,
+    on_changed: Callback<ValidationPolicy>,
+}
+
+/// スコアの尺度の切り替え。既存レコードのスコアを変換してからポリシーを書き換える（先に dry-run で確認する）。
+#[function_component(ScoreScaleSection)]
+fn score_scale_section(props: &ScoreScaleSectionProps) -> Html {
+    let current = props.policy.score;
+    let target = use_state(|| current);
+    let preview = use_state(|| None::<Result<RescaleReport, String>>);
+    let busy = use_state(|| false);
+
+    let on_select = {
+        let target = target.clone();
+        let preview = preview.clone();
+        Callback::from(move |e: Event| {
+            if let Some(sel) = e.target_dyn_into::<web_sys::HtmlSelectElement>() {
+                if let Some(&scale) = sel.value().parse::<usize>().ok().and_then(|i| SCORE_PRESETS.get(i)) {
+                    target.set(scale);
+                    preview.set(None);
+                }
+            }
+        })
+    };
+
+    let run = |dry_run: bool| {
+        let target = target.clone();
+        let preview = preview.clone();
+        let busy = busy.clone();
+        let on_changed = props.on_changed.clone();
+        Callback::from(move |_: MouseEvent| {
+            let (to, preview, busy, on_changed) = (*target, preview.clone(), busy.clone(), on_changed.clone());
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                let res = api::rescale_scores(&to, dry_run).await;
+                if let Ok(r) = &res {
+                    if !r.dry_run {
+                        on_changed.emit(r.policy.clone());
+                    }
+                }
+                preview.set(Some(res));
+                busy.set(false);
+            });
+        })
+    };
+
+    let unchanged = *target == current;
+    html! {
+        <div class="form-section">
+            <h3>{"スコアの尺度"}</h3>
+            <label class="settings-inline">
+                <select class="input" aria-label="スコアの尺度" onchange={on_select}>
+                    if !SCORE_PRESETS.contains(&current) {
+                        <option selected={*target == current}>{ current.describe() }</option>
+                    }
+                    { for SCORE_PRESETS.iter().enumerate().map(|(i, p)| html! {
+                        <option value={i.to_string()} selected={*target == *p}>{ p.describe() }</option>
+                    }) }
+                </select>
+                { format!("（今の尺度: {}）", current.describe()) }
+            </label>
+            <p class="hint">{"尺度を変えると、既存レコードのスコアを直線で写して変換します（1〜6 の 6 → 1〜10 の 10）。まず変換結果を確認してから適用してください。"}</p>
+            <button type="button" class="btn-add" onclick={run(true)} disabled={*busy || unchanged}>{"変換を確認"}</button>
+            { match &*preview {
+                None => html! {},
+                Some(Err(e)) => html! { <p class="save-err">{ e.clone() }</p> },
+                Some(Ok(r)) if !r.dry_run => html! {
+                    <p class="save-ok" role="status">
+                        { format!("{} 件を変換し、尺度を {} にしました。", r.changes.len(), r.policy.score.describe()) }
+                        { for r.failed.iter().map(|(f, e)| html! { <span class="save-err">{ format!(" {}: {}", f, e) }</span> }) }
+                    </p>
+                },
+                Some(Ok(r)) => html! {
+                    <>
+                        <p role="status">{ format!("{} 件のスコアが変わります。", r.changes.len()) }</p>
+                        <ul class="hint">
+                            { for r.changes.iter().take(PREVIEW_ROWS).map(|c| html! {
+                                <li key={c.filename.clone()}>
+                                    { format!("{}: {} → {}", c.display_label, format_score(c.before), format_score(c.after)) }
+                                </li>
+                            }) }
+                            if r.changes.len() > PREVIEW_ROWS {
+                                <li>{ format!("ほか {} 件", r.changes.len() - PREVIEW_ROWS) }</li>
+                            }
+                        </ul>
+                        <button type="button" class="btn-save" onclick={run(false)} disabled={*busy || unchanged}>
+                            { if *busy { "変換中..." } else { "変換を適用" } }
+                        </button>
+                    </>
+                },
+            } }
+        </div>
+    }
+}
+
+/// 設定画面。サイドバーのバッジの凡例と表示形式（ジャンルごとのラベル）・保存の待ち時間・エラー報告・スコアの尺度。
+#[function_component(SettingsView)]
+pub fn settings_view(props: &SettingsViewProps) -> Html {
+    let draft = use_state(|| props.settings.clone());
+    let status = use_state(|| None::<Result<(), String>>);
+    let busy = use_state(|| false);
+    let focus = use_append_focus();
+
+    let add: Callback<()> = {
+        let draft = draft.clone();
+        focus.wrap(".badge-row", Callback::from(move |()| {
+            let mut s = (*draft).clone();
+            s.badges.push(BadgeLegend::default());
+            draft.set(s);
+        }))
+    };
+    let remove = |i: usize| {
+        let draft = draft.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut s = (*draft).clone();
+            s.badges.remove(i);
+            draft.set(s);
+        })
+    };
+    let update = |i: usize, is_badge: bool| {
+        let draft = draft.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                let mut s = (*draft).clone();
+                if let Some(b) = s.badges.get_mut(i) {
+                    if is_badge {
+                        b.badge = inp.value();
+                    } else {
+                        b.label = inp.value();
+                    }
+                }
+                draft.set(s);
+            }
+        })
+    };
+
+    let on_report_toggle = {
+        let draft = draft.clone();
+        Callback::from(move |_: Event| {
+            let mut s = (*draft).clone();
+            s.report_client_errors = !s.report_client_errors;
+            draft.set(s);
+        })
+    };
+
+    let on_validation_log_toggle = {
+        let draft = draft.clone();
+        Callback::from(move |_: Event| {
+            let mut s = (*draft).clone();
+            s.log_validation_failures = !s.log_validation_failures;
+            draft.set(s);
+        })
+    };
+
+    // サイドバーの表示形式（ラジオボタン・チェックボックス）
+    let edit_sidebar = |f: fn(&mut SidebarDisplay)| {
+        let draft = draft.clone();
+        Callback::from(move |_: Event| {
+            let mut s = (*draft).clone();
+            f(&mut s.sidebar);
+            draft.set(s);
+        })
+    };
+
+    let on_template_input = |genre: &'static str| {
+        let draft = draft.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                let mut s = (*draft).clone();
+                if inp.value().trim().is_empty() {
+                    s.label_templates.0.remove(genre);
+                } else {
+                    s.label_templates.0.insert(genre.to_string(), inp.value());
+                }
+                draft.set(s);
+            }
+        })
+    };
+
+    let on_timeout_input = {
+        let draft = draft.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                let mut s = (*draft).clone();
+                s.save_timeout_secs = inp.value().trim().parse().unwrap_or(0);
+                draft.set(s);
+            }
+        })
+    };
+
+    let set_date_mode = |mode: DateMode| {
+        let draft = draft.clone();
+        Callback::from(move |_: Event| {
+            let mut s = (*draft).clone();
+            s.date_mode = mode;
+            draft.set(s);
+        })
+    };
+    let set_import_conflict = |conflict: ImportConflict| {
+        let draft = draft.clone();
+        Callback::from(move |_: Event| {
+            let mut s = (*draft).clone();
+            s.import_conflict = conflict;
+            draft.set(s);
+        })
+    };
+    let on_track_updated_toggle = {
+        let draft = draft.clone();
+        Callback::from(move |_: Event| {
+            let mut s = (*draft).clone();
+            s.track_updated_date = !s.track_updated_date;
+            draft.set(s);
+        })
+    };
+    let on_offset_input = {
+        let draft = draft.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                let mut s = (*draft).clone();
+                s.utc_offset = inp.value();
+                draft.set(s);
+            }
+        })
+    };
+    let on_classical_filename_input = {
+        let draft = draft.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                let mut s = (*draft).clone();
+                s.classical_filename = inp.value();
+                draft.set(s);
+            }
+        })
+    };
+    let classical_filename_err = Some(draft.classical_filename.trim())
+        .filter(|p| !p.is_empty())
+        .and_then(|p| validate_classical_pattern(p).err());
+    let offset_invalid = !draft.utc_offset.trim().is_empty() && draft.utc_offset().is_none();
+    let browser_offset = crate::date::UtcOffset::from_minutes(-(js_sys::Date::new_0().get_timezone_offset() as i32))
+        .unwrap_or_default();
+    let fill_status = use_state(|| None::<Result<usize, String>>);
+    let on_fill_updated = {
+        let fill_status = fill_status.clone();
+        Callback::from(move |_: MouseEvent| {
+            let fill_status = fill_status.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                fill_status.set(Some(api::fill_updated_dates().await));
+            });
+        })
+    };
+
+    let providers = use_state(api::MetadataProviders::default);
+    {
+        let providers = providers.clone();
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Ok(p) = api::metadata_providers().await {
+                    providers.set(p);
+                }
+            });
+            || ()
+        });
+    }
+    let on_provider_change = {
+        let draft = draft.clone();
+        Callback::from(move |e: Event| {
+            if let Some(sel) = e.target_dyn_into::<web_sys::HtmlSelectElement>() {
+                let mut s = (*draft).clone();
+                s.metadata_provider = sel.value();
+                draft.set(s);
+            }
+        })
+    };
+    let selected_provider = if draft.metadata_provider.is_empty() { providers.selected.clone() } else { draft.metadata_provider.clone() };
+
+    let on_save = {
+        let draft = draft.clone();
+        let status = status.clone();
+        let busy = busy.clone();
+        let on_saved = props.on_saved.clone();
+        Callback::from(move |_: MouseEvent| {
+            let draft = draft.clone();
+            let status = status.clone();
+            let busy = busy.clone();
+            let on_saved = on_saved.clone();
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::save_settings(&draft).await {
+                    Ok(saved) => {
+                        draft.set(saved.clone());
+                        on_saved.emit(saved);
+                        status.set(Some(Ok(())));
+                    }
+                    Err(e) => status.set(Some(Err(e))),
+                }
+                busy.set(false);
+            });
+        })
+    };
+
+    html! {
+        <div class="settings-view">
+            <h2 class="view-title">{"設定"}</h2>
+            <div class="form-section" ref={focus.container.clone()} onkeydown={enter_to_add(add.clone())}>
+                <h3>{"バッジの凡例"}</h3>
+                <p class="hint">{"レコードに付けたバッジ（🔴 や 🎁 などの絵文字）の意味です。サイドバーに凡例として表示し、クリックで絞り込めます。"}</p>
+                { for draft.badges.iter().enumerate().map(|(i, b)| html! {
+                    <div class="badge-row" key={i} role="group" aria-label={format!("バッジ {}", i + 1)}>
+                        <input type="text" class="input badge-input" aria-label="バッジ" placeholder="🔴"
+                            maxlength="8" value={b.badge.clone()} oninput={update(i, true)}/>
+                        <input type="text" class="input" aria-label="意味" placeholder="リッピングし直し"
+                            maxlength="64" value={b.label.clone()} oninput={update(i, false)}/>
+                        <button type="button" class="btn-remove" aria-label={format!("バッジ {} を削除", i + 1)} onclick={remove(i)}>{"削除"}</button>
+                    </div>
+                }) }
+                <button type="button" class="btn-add" onclick={add.reform(|_| ())}>{"バッジ追加"}</button>
+            </div>
+            <div class="form-section">
+                <h3>{"サイドバーの表示"}</h3>
+                <div class="settings-inline" role="radiogroup" aria-label="行の高さ">
+                    <label class="sidebar-filter">
+                        <input type="radio" name="sidebar-density" checked={draft.sidebar.density == SidebarDensity::Comfortable}
+                            onchange={edit_sidebar(|d| d.density = SidebarDensity::Comfortable)}/>
+                        {"ゆったり"}
+                    </label>
+                    <label class="sidebar-filter">
+                        <input type="radio" name="sidebar-density" checked={draft.sidebar.density == SidebarDensity::Compact}
+                            onchange={edit_sidebar(|d| d.density = SidebarDensity::Compact)}/>
+                        {"コンパクト"}
+                    </label>
+                </div>
+                <div class="settings-inline" role="radiogroup" aria-label="ラベルの形式">
+                    <label class="sidebar-filter">
+                        <input type="radio" name="sidebar-label" checked={draft.sidebar.label_format == LabelFormat::ArtistTitle}
+                            onchange={edit_sidebar(|d| d.label_format = LabelFormat::ArtistTitle)}/>
+                        {"アーティスト: タイトル"}
+                    </label>
+                    <label class="sidebar-filter">
+                        <input type="radio" name="sidebar-label" checked={draft.sidebar.label_format == LabelFormat::TitleArtist}
+                            onchange={edit_sidebar(|d| d.label_format = LabelFormat::TitleArtist)}/>
+                        {"タイトル — アーティスト"}
+                    </label>
+                </div>
+                <label class="sidebar-filter">
+                    <input type="checkbox" checked={draft.sidebar.show_score} onchange={edit_sidebar(|d| d.show_score = !d.show_score)}/>
+                    {"スコアを ★ で表示する"}
+                </label>
+                <label class="sidebar-filter">
+                    <input type="checkbox" checked={draft.sidebar.show_year} onchange={edit_sidebar(|d| d.show_year = !d.show_year)}/>
+                    {"発売年を表示する"}
+                </label>
+                <h4>{"ジャンルごとのラベル"}</h4>
+                <p class="hint">
+                    { format!("「アーティスト: タイトル」の代わりに使う形です（例: {{composer}}: {{title}}）。使える項目: {}。項目が空のレコードと、空欄のジャンルはいつもの形になります。",
+                        TEMPLATE_FIELDS.iter().map(|f| format!("{{{}}}", f)).collect::<Vec<_>>().join(" ")) }
+                </p>
+                { for MAIN_JANRES.iter().map(|&genre| {
+                    let value = draft.label_templates.0.get(genre).cloned().unwrap_or_default();
+                    let err = (!value.trim().is_empty()).then(|| validate_template(value.trim()).err()).flatten();
+                    html! {
+                        <div class="settings-inline" key={genre}>
+                            <label class="label-template-genre">{ genre }</label>
+                            <input type="text" class={classes!("input", err.is_some().then_some("input-error"))}
+                                placeholder="{artist}: {title}" aria-label={format!("{} のラベル", genre)}
+                                value={value} oninput={on_template_input(genre)}/>
+                            if let Some(e) = err {
+                                <span class="save-err">{ e }</span>
+                            }
+                        </div>
+                    }
+                }) }
+            </div>
+            <div class="form-section">
+                <h3>{"記録日（date）"}</h3>
+                <div class="settings-inline" role="radiogroup" aria-label="保存時の記録日">
+                    { for [
+                        (DateMode::FirstLogged, "初めて登録した日のまま"),
+                        (DateMode::LastUpdated, "保存のたびに今日にする"),
+                        (DateMode::Ask, "保存のたびに聞く"),
+                    ].into_iter().map(|(mode, label)| html! {
+                        <label class="sidebar-filter">
+                            <input type="radio" name="date-mode" checked={draft.date_mode == mode} onchange={set_date_mode(mode)}/>
+                            { label }
+                        </label>
+                    }) }
+                </div>
+                <label class="settings-inline">
+                    {"時差"}
+                    <input type="text" class={classes!("input", "settings-number", offset_invalid.then_some("input-error"))}
+                        placeholder={browser_offset.to_string()} aria-label="日付を数える時差（例 +09:00）"
+                        value={draft.utc_offset.clone()} oninput={on_offset_input}/>
+                </label>
+                <p class="hint">
+                    { format!("「今日」やファイルの更新日をこの時差で数えます（例 +09:00）。空ならブラウザは {}、サーバーは UTC で数えるので、ずれないよう設定しておくのがおすすめです。", browser_offset) }
+                </p>
+                if offset_invalid {
+                    <p class="save-err">{"時差は +09:00 のように入力してください。"}</p>
+                }
+                <label class="sidebar-filter">
+                    <input type="checkbox" checked={draft.track_updated_date} onchange={on_track_updated_toggle}/>
+                    {"記録日とは別に、最終更新日（updated_date）を保存のたびに入れる"}
+                </label>
+                <div class="settings-inline">
+                    <button type="button" class="btn-add" onclick={on_fill_updated}>{"既存のレコードに最終更新日を入れる"}</button>
+                    <span class="hint">{"まだ updated_date がないレコードに、ファイルの更新日を入れます。"}</span>
+                </div>
+                { match &*fill_status {
+                    None => html! {},
+                    Some(Ok(n)) => html! { <p class="save-ok">{ format!("{} 件に入れました。", n) }</p> },
+                    Some(Err(e)) => html! { <p class="save-err">{ e.clone() }</p> },
+                } }
+            </div>
+            <div class="form-section">
+                <h3>{"ファイル名の候補（Classical）"}</h3>
+                <label class="settings-inline">
+                    {"パターン"}
+                    <input type="text" class={classes!("input", classical_filename_err.is_some().then_some("input-error"))}
+                        placeholder="{soloist_last}_{instrument}__{composer_last}_{title}" aria-label="Classical のファイル名のパターン"
+                        value={draft.classical_filename.clone()} oninput={on_classical_filename_input}/>
+                </label>
+                <p class="hint">
+                    { format!("ファイル名欄に入れる候補の形です（例 Argerich_p__Chopin_Piano_Concerto_1）。使える項目: {}。instrument は p・vn・vc などの略記になります。項目が空のレコードと、パターンが空のときはいつもの形（作曲者__タイトル、なければソリスト名など）になります。",
+                        CLASSICAL_FIELDS.iter().map(|f| format!("{{{}}}", f)).collect::<Vec<_>>().join(" ")) }
+                </p>
+                if let Some(e) = classical_filename_err {
+                    <p class="save-err">{ e }</p>
+                }
+            </div>
+            <div class="form-section">
+                <h3>{"リリース情報の取り込み"}</h3>
+                <label class="settings-inline">
+                    {"探す先"}
+                    <select class="sidebar-sort" aria-label="リリース情報を探す先" onchange={on_provider_change}>
+                        { for providers.providers.iter().map(|p| html! {
+                            <option value={p.id.clone()} selected={p.id == selected_provider}>{ p.name.clone() }</option>
+                        }) }
+                    </select>
+                </label>
+                <p class="hint">{"フォームの「外部のデータベースから探す」で最初に選ばれている提供元です。ゲーム音楽は VGMdb が詳しいです。Discogs はサーバーに --discogs-token（環境変数 DISCOGS_TOKEN）でトークンを渡す必要があります。Spotify はサーバーに --spotify-client-id と --spotify-client-secret が要ります。"}</p>
+            </div>
+            <div class="form-section">
+                <h3>{"取り込みで一致したとき"}</h3>
+                <div class="settings-inline" role="radiogroup" aria-label="取り込みで既存のレコードと一致したときの扱い">
+                    { for ImportConflict::ALL.into_iter().map(|c| html! {
+                        <label class="sidebar-filter">
+                            <input type="radio" name="import-conflict" checked={draft.import_conflict == c} onchange={set_import_conflict(c)}/>
+                            { c.label() }
+                        </label>
+                    }) }
+                </div>
+                <p class="hint">{"Discogs の CSV などの取り込みで、アーティスト・タイトルが一致するレコード（同じファイル名のもの）があるときの扱いです。取り込みのたびに変えることもできます。"}</p>
+            </div>
+            <div class="form-section">
+                <h3>{"保存の待ち時間"}</h3>
+                <label class="settings-inline">
+                    <input type="number" class="input settings-number" min="1" max="600" aria-label="保存の待ち時間（秒）"
+                        value={draft.save_timeout_secs().to_string()} oninput={on_timeout_input}/>
+                    {"秒"}
+                </label>
+                <p class="hint">{"送信も応答も進まないままこの時間が過ぎると、保存されたか確認してから再送します。大きなボックスセットを Wi-Fi で保存するなら長めに。"}</p>
+            </div>
+            <div class="form-section">
+                <h3>{"エラー報告"}</h3>
+                <label class="sidebar-filter">
+                    <input type="checkbox" checked={draft.report_client_errors} onchange={on_report_toggle}/>
+                    {"アプリが異常終了したとき、診断情報（エラー内容・直前の操作・レコード名）をサーバーに送る"}
+                </label>
+                <p class="hint">{"送った内容は db/.config/client-errors.jsonl に記録されます。"}</p>
+                <label class="sidebar-filter">
+                    <input type="checkbox" checked={draft.log_validation_failures} onchange={on_validation_log_toggle}/>
+                    {"入力チェックで保存できなかったとき、引っかかった項目とルールをサーバーに記録する（入力した値は送らない）"}
+                </label>
+                <p class="hint">{"db/.config/validation-failures.jsonl に記録され、一括チェックの「よくあるエラー」にまとめて出ます。"}</p>
+            </div>
+            <button type="button" class="btn-save" onclick={on_save} disabled={*busy}>
+                { if *busy { "保存中..." } else { "設定を保存" } }
+            </button>
+            <div role="status" aria-live="polite">
+                { match &*status {
+                    None => html! {},
+                    Some(Ok(())) => html! { <p class="save-ok">{"保存しました。"}</p> },
+                    Some(Err(e)) => html! { <p class="save-err">{ e.clone() }</p> },
+                } }
+            </div>
+            <ScoreScaleSection policy={props.policy.clone()} on_changed={props.on_policy_changed.clone()} />
+            <crate::devices::DevicesSection />
+            <crate::audit::AuditSection />
+            <crate::sync::SyncSection />
+            <crate::digest::DigestSection />
+            <crate::export::ZipExportSection />
+            <crate::retention::RetentionSection />
+            <crate::normalize::NormalizeFilenamesSection />
+        </div>
+    }
+}
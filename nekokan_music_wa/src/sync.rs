@@ -0,0 +1,167 @@
+use crate::api::{self, SyncReport, SyncStatus};
+use yew::prelude::*;
+
+/// 結果の 1 行要約（"12 件取り込み・340 件は同じ・衝突 1 件"）
+pub fn summary(r: &SyncReport) -> String {
+    let mut parts = vec![
+        format!("{} 件{}", r.pulled.len(), if r.dry_run { "を取り込む予定" } else { "取り込み" }),
+        format!("{} 件は同じ", r.up_to_date),
+    ];
+    for (n, label) in [
+        (r.local_changes.len(), "こちらだけの変更"),
+        (r.conflicts.len(), "衝突"),
+        (r.only_here.len(), "取り込み元にない"),
+        (r.errors.len(), "エラー"),
+    ] {
+        if n > 0 {
+            parts.push(format!("{} {} 件", label, n));
+        }
+    }
+    parts.join("・")
+}
+
+/// 設定画面の「ほかのサーバーから取り込む」。取り込み元の変更だけを引いてきて、両方で変わったものは衝突として並べる。
+#[function_component(SyncSection)]
+pub fn sync_section() -> Html {
+    let status = use_state(|| None::<Result<SyncStatus, String>>);
+    let primary = use_state(String::new);
+    let token = use_state(String::new);
+    let interval = use_state(|| 0u32);
+    let report = use_state(|| None::<Result<SyncReport, String>>);
+    let busy = use_state(|| false);
+
+    {
+        let (status, primary, interval, report) = (status.clone(), primary.clone(), interval.clone(), report.clone());
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                let res = api::sync_status().await;
+                if let Ok(s) = &res {
+                    primary.set(s.config.primary.clone());
+                    interval.set(s.config.interval_minutes);
+                    report.set(s.last_report.clone().map(Ok));
+                }
+                status.set(Some(res));
+            });
+            || ()
+        });
+    }
+
+    let input = |state: &UseStateHandle<String>| {
+        let state = state.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                state.set(inp.value());
+            }
+        })
+    };
+    let on_interval = {
+        let interval = interval.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                interval.set(inp.value().trim().parse().unwrap_or(0));
+            }
+        })
+    };
+
+    let on_save = {
+        let (primary, token, interval, status, report) =
+            (primary.clone(), token.clone(), interval.clone(), status.clone(), report.clone());
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let (p, t, i) = ((*primary).clone(), (*token).clone(), *interval);
+            let (token, status, report) = (token.clone(), status.clone(), report.clone());
+            wasm_bindgen_futures::spawn_local(async move {
+                // トークン欄が空なら今のトークンのまま
+                let t = (!t.trim().is_empty()).then_some(t);
+                match api::save_sync_config(&p, t.as_deref(), i).await {
+                    Ok(config) => {
+                        token.set(String::new());
+                        let last_report = status.as_ref().and_then(|s| s.as_ref().ok()).and_then(|s| s.last_report.clone());
+                        status.set(Some(Ok(SyncStatus { config, last_report })));
+                    }
+                    Err(e) => report.set(Some(Err(e))),
+                }
+            });
+        })
+    };
+
+    let run = |dry_run: bool| {
+        let (report, busy) = (report.clone(), busy.clone());
+        Callback::from(move |_: MouseEvent| {
+            let (report, busy) = (report.clone(), busy.clone());
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                report.set(Some(api::sync_pull(dry_run).await));
+                busy.set(false);
+            });
+        })
+    };
+
+    let configured = matches!(&*status, Some(Ok(s)) if !s.config.primary.is_empty());
+    let has_token = matches!(&*status, Some(Ok(s)) if s.config.has_token);
+
+    html! {
+        <div class="form-section">
+            <h3>{"ほかのサーバーから取り込む（同期）"}</h3>
+            <p class="hint">{"取り込み元で変わったレコードだけを取り込みます。こちらでも変えたものは上書きせず「衝突」として残します。カバー・メモ・添付は対象外です。"}</p>
+            if let Some(Err(e)) = &*status {
+                <p class="load-err">{ e.clone() }</p>
+            }
+            <form class="note-form" onsubmit={on_save}>
+                <input type="url" class="input" aria-label="取り込み元の URL" placeholder="https://music.example.com"
+                    value={(*primary).clone()} oninput={input(&primary)}/>
+                <input type="password" class="input" aria-label="取り込み元のトークン" autocomplete="off"
+                    placeholder={if has_token { "（設定済み。変えるときだけ入力）" } else { "トークン（任意）" }}
+                    value={(*token).clone()} oninput={input(&token)}/>
+                <label class="settings-inline">
+                    <input type="number" class="input settings-number" min="0" aria-label="自動で取り込む間隔（分）"
+                        value={interval.to_string()} oninput={on_interval}/>
+                    {"分ごと（0 なら手動）"}
+                </label>
+                <button type="submit" class="btn-add">{"保存"}</button>
+            </form>
+            <div class="settings-inline">
+                <button type="button" class="btn-add" onclick={run(true)} disabled={*busy || !configured}>{"確認だけ"}</button>
+                <button type="button" class="btn-save" onclick={run(false)} disabled={*busy || !configured}>
+                    { if *busy { "取り込み中..." } else { "今すぐ取り込む" } }
+                </button>
+            </div>
+            <div role="status" aria-live="polite">
+                { match &*report {
+                    None => html! {},
+                    Some(Err(e)) => html! { <p class="save-err">{ e.clone() }</p> },
+                    Some(Ok(r)) => html! {
+                        <>
+                            <p class={if r.conflicts.is_empty() && r.errors.is_empty() { "save-ok" } else { "save-warn" }}>
+                                { format!("{}（{}）", summary(r), r.at) }
+                            </p>
+                            <ul class="note-list">
+                                { for r.conflicts.iter().map(|c| html! {
+                                    <li class="note-item"><span class="note-text">{ c.filename.clone() }</span><span class="note-time">{ c.reason.clone() }</span></li>
+                                }) }
+                                { for r.errors.iter().map(|e| html! {
+                                    <li class="note-item"><span class="save-err">{ e.clone() }</span></li>
+                                }) }
+                            </ul>
+                        </>
+                    },
+                } }
+            </div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod sync_tests {
+    use super::summary;
+    use crate::api::{SyncConflict, SyncReport};
+
+    #[test]
+    fn summary_mentions_only_what_happened() {
+        let mut r = SyncReport { pulled: vec!["a.json".into()], up_to_date: 10, ..Default::default() };
+        assert_eq!(summary(&r), "1 件取り込み・10 件は同じ");
+        r.dry_run = true;
+        r.conflicts.push(SyncConflict { filename: "b.json".into(), reason: "両方で変わっている".into() });
+        assert_eq!(summary(&r), "1 件を取り込む予定・10 件は同じ・衝突 1 件");
+    }
+}
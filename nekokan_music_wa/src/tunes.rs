@@ -0,0 +1,142 @@
+use crate::api::{self, Tune, TuneVersion};
+use crate::length::{format_length, parse_length};
+use yew::prelude::*;
+
+/// 演奏の長さの幅（"4:48 〜 11:02"）。長さの分かる演奏が 2 つ未満なら None。
+pub fn length_range(versions: &[TuneVersion]) -> Option<String> {
+    let secs: Vec<u64> = versions.iter().filter_map(|v| parse_length(&v.length)).collect();
+    if secs.len() < 2 {
+        return None;
+    }
+    Some(format!("{} 〜 {}", format_length(*secs.iter().min()?), format_length(*secs.iter().max()?)))
+}
+
+/// "Disc 2 - 3"（1 枚ものは "3"）
+fn position(v: &TuneVersion) -> String {
+    match (v.disc_no, v.no) {
+        (Some(d), Some(n)) if d > 1 => format!("Disc {} - {}", d, n),
+        (_, Some(n)) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct TuneIndexProps {
+    /// レコード名クリックでそのレコードを開く（"xxx.json"）
+    pub on_select: Callback<String>,
+}
+
+/// 曲の索引。曲名で探し、その曲を収録したアルバムを発売年順に並べて演奏を聴き比べる。
+#[function_component(TuneIndex)]
+pub fn tune_index(props: &TuneIndexProps) -> Html {
+    let q = use_state(String::new);
+    let result = use_state(|| None::<Result<Vec<Tune>, String>>);
+    let loading = use_state(|| false);
+
+    let on_input = {
+        let q = q.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                q.set(inp.value());
+            }
+        })
+    };
+    let on_submit = {
+        let q = q.clone();
+        let result = result.clone();
+        let loading = loading.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let query = q.trim().to_string();
+            if query.is_empty() {
+                return;
+            }
+            let (result, loading) = (result.clone(), loading.clone());
+            loading.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                result.set(Some(api::tunes(&query).await));
+                loading.set(false);
+            });
+        })
+    };
+
+    let record_link = |v: &TuneVersion| {
+        let on_select = props.on_select.clone();
+        let filename = v.filename.clone();
+        html! {
+            <a href="#" title={v.filename.clone()} onclick={move |e: MouseEvent| { e.prevent_default(); on_select.emit(filename.clone()); }}>
+                { v.display_label.clone() }
+            </a>
+        }
+    };
+
+    html! {
+        <div class="tune-index">
+            <h2 class="view-title">{"曲の索引"}</h2>
+            <p class="hint">{"曲名で探すと、その曲を収録したレコードを発売年順に並べます。(Alternate Take) などの括弧書きや ' の有無は区別しません。"}</p>
+            <form class="note-form" onsubmit={on_submit}>
+                <input type="search" class="input" aria-label="曲名" placeholder="Moanin'" value={(*q).clone()} oninput={on_input}/>
+                <button type="submit" class="btn-add" disabled={*loading}>{ if *loading { "検索中..." } else { "探す" } }</button>
+            </form>
+            { match &*result {
+                None => html! {},
+                Some(Err(e)) => html! { <p class="load-err">{"ロードエラー: "}{ e.clone() }</p> },
+                Some(Ok(tunes)) if tunes.is_empty() => html! { <p class="hint" role="status">{"見つかりませんでした。"}</p> },
+                Some(Ok(tunes)) => html! {
+                    <div role="status">
+                        { for tunes.iter().map(|t| html! {
+                            <div class="form-section" key={t.title.clone()}>
+                                <h3>{ format!("{}（{} 演奏）", t.title, t.versions.len()) }</h3>
+                                if let Some(range) = length_range(&t.versions) {
+                                    <p class="hint">{ format!("長さ: {}", range) }</p>
+                                }
+                                <table class="cover-table">
+                                    <thead>
+                                        <tr><th>{"レコード"}</th><th>{"年"}</th><th>{"曲順"}</th><th>{"表記"}</th><th>{"作曲"}</th><th>{"長さ"}</th></tr>
+                                    </thead>
+                                    <tbody>
+                                        { for t.versions.iter().map(|v| html! {
+                                            <tr>
+                                                <td class="cover-name">{ record_link(v) }</td>
+                                                <td>{ v.release_year.map(|y| y.to_string()).unwrap_or_default() }</td>
+                                                <td>{ position(v) }</td>
+                                                <td>{ v.title.clone() }</td>
+                                                <td>{ v.composer.clone() }</td>
+                                                <td>{ crate::rules::render_unknown(&v.length).to_string() }</td>
+                                            </tr>
+                                        }) }
+                                    </tbody>
+                                </table>
+                            </div>
+                        }) }
+                    </div>
+                },
+            } }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tunes_tests {
+    use super::length_range;
+    use crate::api::TuneVersion;
+
+    fn version(length: &str) -> TuneVersion {
+        TuneVersion {
+            filename: String::new(),
+            display_label: String::new(),
+            release_year: None,
+            disc_no: Some(1),
+            no: Some(1),
+            title: "Moanin'".into(),
+            composer: String::new(),
+            length: length.into(),
+        }
+    }
+
+    #[test]
+    fn length_range_skips_unknown_lengths() {
+        assert_eq!(length_range(&[version("9:35"), version("11:02"), version("")]).as_deref(), Some("9:35 〜 11:02"));
+        assert_eq!(length_range(&[version("9:35"), version("?")]), None);
+    }
+}
@@ -0,0 +1,191 @@
+//! URL からインポート。友だちのサーバーなど、ほかの nekokan_music の `/api/files/名前.json` の URL を貼ると、
+//! こちらのサーバー経由で取ってきて（ブラウザから直接は CORS で読めない）中身を見せ、下書きとしてこちらに保存する。
+//! 再生記録・カバー・関連レコード・バッジは向こうのものなので持ってこず、記録日は今日にする。
+
+use crate::api::{self, RemoteRecord};
+use crate::types::MusicData;
+use crate::validation::{validate_form, ValidationPolicy};
+use serde_json::Value;
+use yew::prelude::*;
+
+/// 取ってきたレコードをこちらの下書きにする
+pub fn as_draft(v: Value, today: &str) -> Result<MusicData, String> {
+    let mut data: MusicData =
+        serde_json::from_value(v).map_err(|e| format!("nekokan_music のレコードとして読めません: {}", e))?;
+    data.draft = true;
+    data.date = today.to_string();
+    data.updated_date.clear();
+    data.listens.clear();
+    data.cover.clear();
+    data.related.clear();
+    data.badge.clear();
+    Ok(data)
+}
+
+#[derive(Properties, PartialEq)]
+pub struct UrlImportProps {
+    /// 今のライブラリのファイル名（"xxx.json"）。同じ名前では保存しない
+    pub existing_filenames: Vec<String>,
+    /// 保存の待ち時間（秒）
+    pub timeout_secs: u32,
+    pub policy: ValidationPolicy,
+    /// 保存できたら一覧を読み直す
+    pub on_saved: Callback<()>,
+    /// 保存したレコードを編集画面で開く（"xxx.json"）
+    pub on_select: Callback<String>,
+}
+
+#[function_component(UrlImport)]
+pub fn url_import(props: &UrlImportProps) -> Html {
+    let url = use_state(String::new);
+    let fetched = use_state(|| None::<Result<(RemoteRecord, MusicData), String>>);
+    let filename = use_state(String::new);
+    let status = use_state(|| None::<Result<String, String>>);
+    let busy = use_state(|| false);
+    let progress = use_state(|| None::<(f64, f64)>);
+
+    let on_url = {
+        let url = url.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                url.set(inp.value());
+            }
+        })
+    };
+    let on_fetch = {
+        let (url, fetched, filename, status, busy) =
+            (url.clone(), fetched.clone(), filename.clone(), status.clone(), busy.clone());
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let (u, fetched, filename, status, busy) =
+                ((*url).clone(), fetched.clone(), filename.clone(), status.clone(), busy.clone());
+            busy.set(true);
+            status.set(None);
+            wasm_bindgen_futures::spawn_local(async move {
+                let res = api::fetch_remote_record(u.trim()).await.and_then(|r| {
+                    let data = as_draft(r.data.clone(), &crate::app::today_str())?;
+                    Ok((r, data))
+                });
+                if let Ok((r, _)) = &res {
+                    filename.set(r.filename.trim_end_matches(".json").to_string());
+                }
+                fetched.set(Some(res));
+                busy.set(false);
+            });
+        })
+    };
+    let on_filename = {
+        let filename = filename.clone();
+        Callback::from(move |e: InputEvent| {
+            if let Some(inp) = e.target_dyn_into::<web_sys::HtmlInputElement>() {
+                filename.set(inp.value());
+            }
+        })
+    };
+
+    let name = format!("{}.json", filename.trim().trim_end_matches(".json"));
+    let taken = props.existing_filenames.contains(&name);
+    let on_save = {
+        let (fetched, filename, status, busy, progress) =
+            (fetched.clone(), filename.clone(), status.clone(), busy.clone(), progress.clone());
+        let (timeout_secs, policy, on_saved) = (props.timeout_secs, props.policy.clone(), props.on_saved.clone());
+        let name = name.clone();
+        Callback::from(move |_: MouseEvent| {
+            let Some(Ok((_, data))) = (*fetched).clone() else { return };
+            let file = filename.trim().trim_end_matches(".json").to_string();
+            if let Some((field, msg)) = validate_form(&data, &file, &policy).iter().next() {
+                status.set(Some(Err(format!("{}: {}", field, msg))));
+                return;
+            }
+            crate::crash::record_action("url_import", Some(&name));
+            let (name, status, busy, progress, on_saved) =
+                (name.clone(), status.clone(), busy.clone(), progress.clone(), on_saved.clone());
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                // こちらに同じ名前のレコードがあれば上書きしない
+                match crate::app::save_with_retry(&file, &data, &api::SaveBase::New, timeout_secs, progress).await {
+                    Ok(_) => {
+                        status.set(Some(Ok(name)));
+                        on_saved.emit(());
+                    }
+                    Err(e) => status.set(Some(Err(crate::app::save_error_text(e)))),
+                }
+                busy.set(false);
+            });
+        })
+    };
+
+    html! {
+        <div class="quick-entry">
+            <h2 class="view-title">{"URLからインポート"}</h2>
+            <p class="hint">{"ほかの nekokan_music のレコードの URL（https://ホスト/api/files/名前.json）を貼ると、このサーバー経由で取ってきます。下書きとして保存し、再生記録・カバー・関連レコード・バッジは持ってきません。記録日は今日になります。"}</p>
+            <form class="settings-inline" onsubmit={on_fetch}>
+                <input type="url" class="input" aria-label="レコードの URL" placeholder="https://music.example.com/api/files/Bill_Evans__Alone.json"
+                    value={(*url).clone()} oninput={on_url}/>
+                <button type="submit" class="btn-add" disabled={*busy || url.trim().is_empty()}>{"取得"}</button>
+            </form>
+            { match &*fetched {
+                None => html! {},
+                Some(Err(e)) => html! { <p class="load-err">{ e.clone() }</p> },
+                Some(Ok((remote, data))) => html! {
+                    <div class="form-section">
+                        <h3>{ remote.display_label.clone() }</h3>
+                        <p class="hint">{ format!("{}（トラック {} 件）", remote.source, data.tracks.len()) }</p>
+                        <pre class="digest-preview">
+                            { serde_json::to_value(data).map(|v| nekokan_music_core::schema::to_canonical_json(&v)).unwrap_or_default() }
+                        </pre>
+                        <div class="field">
+                            <label for="url-import-filename">{"ファイル名"}</label>
+                            <input id="url-import-filename" type="text" class="input" value={(*filename).clone()} oninput={on_filename}/>
+                            if taken {
+                                <p class="save-err">{ format!("{} は既に存在します。ファイル名を変えてください。", name) }</p>
+                            }
+                        </div>
+                        <button type="button" class="btn-save" onclick={on_save} disabled={*busy || taken || filename.trim().is_empty()}>
+                            { if *busy { "保存中..." } else { "下書きとして保存" } }
+                        </button>
+                    </div>
+                },
+            } }
+            <div role="status" aria-live="polite">
+                { match &*status {
+                    None => html! {},
+                    Some(Ok(name)) => {
+                        let on_select = props.on_select.clone();
+                        let name = name.clone();
+                        html! {
+                            <p class="save-ok">
+                                { format!("{} を保存しました。", name) }
+                                <a href="#" onclick={move |e: MouseEvent| { e.prevent_default(); on_select.emit(name.clone()); }}>
+                                    {"編集画面で開く"}
+                                </a>
+                            </p>
+                        }
+                    }
+                    Some(Err(e)) => html! { <p class="save-err">{ e.clone() }</p> },
+                } }
+            </div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod url_import_tests {
+    use super::as_draft;
+    use serde_json::json;
+
+    #[test]
+    fn remote_records_become_local_drafts() {
+        let v = json!({
+            "title": "Alone", "janre": {"main": "Jazz", "sub": ["Piano"]}, "label": "Verve", "id": "V6-8792",
+            "release_year": 1968, "record_year": [1968], "personnel": {}, "tracks": [], "score": 5.0,
+            "comment": "good", "date": "2020/01/01", "cover": "alone.jpg", "listens": ["2020/01/02"],
+            "related": ["x.json"], "badge": "🔴",
+        });
+        let d = as_draft(v, "2026/10/16").unwrap();
+        assert!(d.draft);
+        assert_eq!((d.date.as_str(), d.comment.as_str(), d.score), ("2026/10/16", "good", 5.0));
+        assert!(d.listens.is_empty() && d.cover.is_empty() && d.related.is_empty() && d.badge.is_empty());
+        assert!(as_draft(json!({"title": 1}), "2026/10/16").is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use crate::api::{self, CheckFailure, CheckReport, DuplicateGroup, FailureSummary};
+use std::collections::BTreeMap;
+use yew::prelude::*;
+
+/// "tracks[3].length" を "tracks[].length" にまとめる（項目ごとの件数を出すため）
+pub fn field_kind(field: &str) -> String {
+    let mut out = String::new();
+    let mut in_index = false;
+    for c in field.chars() {
+        match c {
+            '[' => {
+                in_index = true;
+                out.push('[');
+            }
+            ']' => {
+                in_index = false;
+                out.push(']');
+            }
+            _ if in_index => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// 項目ごとの違反ファイル数（多い順、警告は数えない）。JSON として読めないものは "（形式）" にまとめる。
+pub fn counts_by_field(failures: &[CheckFailure]) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for f in failures {
+        let kinds: std::collections::BTreeSet<String> = f
+            .errors
+            .iter()
+            .map(|e| if e.field.is_empty() { "（形式）".to_string() } else { field_kind(&e.field) })
+            .collect();
+        for k in kinds {
+            *counts.entry(k).or_default() += 1;
+        }
+    }
+    let mut list: Vec<(String, usize)> = counts.into_iter().collect();
+    list.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+    list
+}
+
+/// 重複の組がまとめられた理由の表示
+pub fn reason_text(reasons: &[String]) -> String {
+    reasons
+        .iter()
+        .map(|r| match r.as_str() {
+            "id" => "同じ id",
+            "title_artist" => "同じタイトルとアーティスト",
+            other => other,
+        })
+        .collect::<Vec<_>>()
+        .join("・")
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ValidationReportProps {
+    /// レコード名クリックでそのレコードを開く（"xxx.json"）
+    pub on_select: Callback<String>,
+}
+
+/// 一括チェック。今の入力チェックに通らない古いレコードを一覧し、開いて直せるようにする。
+#[function_component(ValidationReport)]
+pub fn validation_report(props: &ValidationReportProps) -> Html {
+    let report = use_state(|| None::<Result<CheckReport, String>>);
+    let duplicates = use_state(|| None::<Result<Vec<DuplicateGroup>, String>>);
+    let common = use_state(|| None::<Result<FailureSummary, String>>);
+    let busy = use_state(|| false);
+
+    let run = {
+        let report = report.clone();
+        let duplicates = duplicates.clone();
+        let common = common.clone();
+        let busy = busy.clone();
+        Callback::from(move |()| {
+            let (report, duplicates, common, busy) = (report.clone(), duplicates.clone(), common.clone(), busy.clone());
+            busy.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                report.set(Some(api::validate_all().await));
+                duplicates.set(Some(api::list_duplicates().await));
+                common.set(Some(api::validation_failure_summary().await));
+                busy.set(false);
+            });
+        })
+    };
+    {
+        let run = run.clone();
+        use_effect_with((), move |_| {
+            run.emit(());
+            || ()
+        });
+    }
+
+    let record_link = |f: &CheckFailure| {
+        let on_select = props.on_select.clone();
+        let filename = f.filename.clone();
+        let label = if f.display_label.is_empty() { f.filename.clone() } else { f.display_label.clone() };
+        html! {
+            <a href="#" title={f.filename.clone()} onclick={move |e: MouseEvent| { e.prevent_default(); on_select.emit(filename.clone()); }}>
+                { label }
+            </a>
+        }
+    };
+
+    html! {
+        <div class="validation-report">
+            <h2 class="view-title">{"一括チェック"}</h2>
+            <p class="hint">{"すべてのレコードに今の入力チェックをかけます。チェックが厳しくなる前に作ったレコードは、開いて直して保存してください。警告は保存を止めませんが、打ち間違いがないか確かめてください。"}</p>
+            <button type="button" class="btn-add" onclick={run.reform(|_: MouseEvent| ())} disabled={*busy}>
+                { if *busy { "チェック中..." } else { "再チェック" } }
+            </button>
+            { match &*report {
+                None => html! { <p class="sidebar-loading">{"チェック中..."}</p> },
+                Some(Err(e)) => html! { <p class="load-err">{"ロードエラー: "}{ e.clone() }</p> },
+                Some(Ok(r)) if r.failures.is_empty() => html! {
+                    <p class="save-ok" role="status">{ format!("{} 件すべて問題ありません。", r.checked) }</p>
+                },
+                Some(Ok(r)) => html! {
+                    <>
+                        <div class="form-section" role="status">
+                            <h3>{ format!(
+                                "{} 件中 {} 件が通りません（警告 {} 件）",
+                                r.checked,
+                                r.failures.iter().filter(|f| !f.errors.is_empty()).count(),
+                                r.failures.iter().filter(|f| !f.warnings.is_empty()).count(),
+                            ) }</h3>
+                            <ul class="hint">
+                                { for counts_by_field(&r.failures).into_iter().map(|(field, n)| html! {
+                                    <li>{ format!("{}: {} 件", field, n) }</li>
+                                }) }
+                            </ul>
+                        </div>
+                        <table class="cover-table">
+                            <thead>
+                                <tr><th>{"レコード"}</th><th>{"項目"}</th><th>{"内容"}</th></tr>
+                            </thead>
+                            <tbody>
+                                { for r.failures.iter().flat_map(|f| {
+                                    let rows = f.errors.len() + f.warnings.len();
+                                    let issues = f.errors.iter().map(|e| (e, false)).chain(f.warnings.iter().map(|e| (e, true)));
+                                    issues.enumerate().map(move |(i, (e, warning))| (f, rows, i, e, warning))
+                                }).map(|(f, rows, i, e, warning)| html! {
+                                    <tr key={format!("{}#{}", f.filename, i)}>
+                                        if i == 0 {
+                                            <td class="cover-name" rowspan={rows.to_string()}>{ record_link(f) }</td>
+                                        }
+                                        <td>{ if e.field.is_empty() { "（形式）".to_string() } else { e.field.clone() } }</td>
+                                        <td class={if warning { "save-warn" } else { "save-err" }}>
+                                            { if warning { format!("警告: {}", e.message) } else { e.message.clone() } }
+                                        </td>
+                                    </tr>
+                                }) }
+                            </tbody>
+                        </table>
+                    </>
+                },
+            } }
+            { match &*duplicates {
+                None => html! {},
+                Some(Err(e)) => html! { <p class="load-err">{"重複の確認に失敗しました: "}{ e.clone() }</p> },
+                Some(Ok(groups)) if groups.is_empty() => html! {},
+                Some(Ok(groups)) => html! {
+                    <div class="form-section">
+                        <h3>{ format!("二重登録かもしれないもの（{} 組）", groups.len()) }</h3>
+                        <p class="hint">{"同じ id か、同じタイトルと最初のリーダー（グループ）のレコードです。エディション違いなら「関連」で紐付けてください。"}</p>
+                        <ul class="note-list">
+                            { for groups.iter().map(|g| html! {
+                                <li class="note-item">
+                                    <span class="note-time">{ reason_text(&g.reasons) }</span>
+                                    { for g.files.iter().map(|f| {
+                                        let on_select = props.on_select.clone();
+                                        let filename = f.filename.clone();
+                                        let label = if f.display_label.is_empty() { f.filename.clone() } else { f.display_label.clone() };
+                                        html! {
+                                            <a href="#" class="note-text" title={f.filename.clone()}
+                                                onclick={move |e: MouseEvent| { e.prevent_default(); on_select.emit(filename.clone()); }}>
+                                                { label }
+                                            </a>
+                                        }
+                                    }) }
+                                </li>
+                            }) }
+                        </ul>
+                    </div>
+                },
+            } }
+            { match &*common {
+                None => html! {},
+                Some(Err(e)) => html! { <p class="load-err">{"よくあるエラーを読めませんでした: "}{ e.clone() }</p> },
+                Some(Ok(s)) => html! {
+                    <div class="form-section">
+                        <h3>{"よくあるエラー"}</h3>
+                        if s.top.is_empty() {
+                            <p class="hint">{ if s.enabled {
+                                "保存が入力チェックで止まった記録はまだありません。"
+                            } else {
+                                "設定の「エラー報告」で入力チェックの記録をオンにすると、保存を止めたエラーをここにまとめます。"
+                            } }</p>
+                        } else {
+                            <p class="hint">{ format!("入力チェックで保存が止まった {} 回のうち、多かった項目とルールです。", s.blocked_saves) }</p>
+                            <table class="cover-table">
+                                <thead>
+                                    <tr><th>{"項目"}</th><th>{"内容"}</th><th>{"回数"}</th></tr>
+                                </thead>
+                                <tbody>
+                                    { for s.top.iter().map(|c| html! {
+                                        <tr key={format!("{}#{}", c.field, c.message)}>
+                                            <td>{ c.field.clone() }</td>
+                                            <td>{ c.message.clone() }</td>
+                                            <td>{ c.count }</td>
+                                        </tr>
+                                    }) }
+                                </tbody>
+                            </table>
+                        }
+                    </div>
+                },
+            } }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod validate_all_tests {
+    use super::{counts_by_field, field_kind, reason_text};
+    use crate::api::{CheckError, CheckFailure};
+
+    fn failure(fields: &[&str]) -> CheckFailure {
+        CheckFailure {
+            filename: "a.json".into(),
+            display_label: String::new(),
+            errors: fields.iter().map(|f| CheckError { field: f.to_string(), message: String::new() }).collect(),
+            warnings: vec![],
+        }
+    }
+
+    #[test]
+    fn counts_group_array_indexes_once_per_file() {
+        assert_eq!(field_kind("personnel.group[0].members[12].tracks"), "personnel.group[].members[].tracks");
+        let failures = vec![failure(&["tracks[0].length", "tracks[3].length", "date"]), failure(&["date"])];
+        assert_eq!(
+            counts_by_field(&failures),
+            vec![("date".to_string(), 2), ("tracks[].length".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn duplicate_reasons_are_readable() {
+        assert_eq!(reason_text(&["id".into(), "title_artist".into()]), "同じ id・同じタイトルとアーティスト");
+    }
+}
@@ -0,0 +1,141 @@
+//! コレクションの活動履歴。保存（ファイルの更新日時）・再生記録・メモを新しい順に並べる。
+
+use axum::{
+    extract::Query,
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::Value;
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::notes::{load_notes, NOTES_DIR};
+use crate::problem::{error, field_error};
+use crate::stats::timestamp_from_system_time;
+use crate::{display_label_from_value, load_all_records};
+
+/// 既定の最大件数
+const DEFAULT_LIMIT: usize = 200;
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    Saved,
+    Listened,
+    Noted,
+}
+
+impl ActivityKind {
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "saved" => Some(Self::Saved),
+            "listened" => Some(Self::Listened),
+            "noted" => Some(Self::Noted),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct Activity {
+    pub kind: ActivityKind,
+    /// 日時（RFC 3339, UTC）。再生記録は日付だけ（YYYY-MM-DD）。
+    pub at: String,
+    pub filename: String,
+    pub display_label: String,
+    /// メモの本文など
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// 1 レコード分の再生記録を活動にする。
+pub fn listen_activities(filename: &str, v: &Value) -> Vec<Activity> {
+    v["listens"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(|d| Activity {
+            kind: ActivityKind::Listened,
+            at: d.replace('/', "-"),
+            filename: filename.to_string(),
+            display_label: display_label_from_value(v),
+            detail: None,
+        })
+        .collect()
+}
+
+#[derive(serde::Deserialize)]
+pub struct ActivityParams {
+    /// カンマ区切りの種類（saved,listened,noted）。省略時はすべて。
+    types: Option<String>,
+    limit: Option<usize>,
+}
+
+/// `GET /api/activity?types=&limit=` 活動履歴を新しい順で返す。
+#[utoipa::path(get, path = "/api/activity", tag = "reports", summary = "活動履歴", params(LibraryQuery))]
+pub async fn get_activity(Lib(library): Lib, Query(params): Query<ActivityParams>) -> impl IntoResponse {
+    let kinds: Vec<ActivityKind> = match params.types.as_deref().filter(|t| !t.trim().is_empty()) {
+        None => vec![ActivityKind::Saved, ActivityKind::Listened, ActivityKind::Noted],
+        Some(t) => match t.split(',').map(ActivityKind::parse).collect::<Option<Vec<_>>>() {
+            Some(k) => k,
+            None => {
+                return field_error("types", "must be saved, listened or noted");
+            }
+        },
+    };
+    let Some(records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    let mut feed = Vec::new();
+    for (filename, v) in &records {
+        if kinds.contains(&ActivityKind::Saved) {
+            let saved = library
+                .storage
+                .modified(filename)
+                .ok()
+                .and_then(timestamp_from_system_time);
+            if let Some(at) = saved {
+                feed.push(Activity {
+                    kind: ActivityKind::Saved,
+                    at,
+                    filename: filename.clone(),
+                    display_label: display_label_from_value(v),
+                    detail: None,
+                });
+            }
+        }
+        if kinds.contains(&ActivityKind::Listened) {
+            feed.extend(listen_activities(filename, v));
+        }
+        if kinds.contains(&ActivityKind::Noted) {
+            let notes = load_notes(&library.dir.join(NOTES_DIR).join(filename)).unwrap_or_default();
+            feed.extend(notes.into_iter().map(|n| Activity {
+                kind: ActivityKind::Noted,
+                at: n.created,
+                filename: filename.clone(),
+                display_label: display_label_from_value(v),
+                detail: Some(n.text),
+            }));
+        }
+    }
+    feed.sort_by(|a, b| b.at.cmp(&a.at).then_with(|| a.display_label.cmp(&b.display_label)));
+    feed.truncate(params.limit.unwrap_or(DEFAULT_LIMIT));
+    (StatusCode::OK, Json(feed)).into_response()
+}
+
+#[cfg(test)]
+mod activity_tests {
+    use super::{listen_activities, ActivityKind};
+    use serde_json::json;
+
+    #[test]
+    fn listens_become_dated_activities() {
+        let v = json!({"title": "Alone", "personnel": {"leader": [{"name": "Bill Evans"}]}, "listens": ["2026/10/01", "2026/10/15"]});
+        let acts = listen_activities("Bill_Evans__Alone.json", &v);
+        assert_eq!(acts.len(), 2);
+        assert_eq!(acts[1].at, "2026-10-15");
+        assert_eq!(acts[1].kind, ActivityKind::Listened);
+        assert_eq!(acts[1].display_label, "Bill Evans: Alone");
+    }
+}
@@ -0,0 +1,252 @@
+//! カバー以外の添付（裏ジャケット・帯・ライナーの写真、スキャンしたブックレットの PDF など）。MusicData には入れず、
+//! `{ライブラリのディレクトリ}/attachments/{レコード名（.json なし）}/` にアップロードしたファイル名のまま置く。
+
+use axum::{
+    extract::{Multipart, Path},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::covers::content_type_for;
+use crate::libraries::{Lib, Library, LibraryQuery};
+use crate::normalize_filename;
+use crate::problem::error;
+
+/// 添付を置くディレクトリ（ライブラリのディレクトリからの相対）
+pub const ATTACHMENTS_DIR: &str = "attachments";
+
+/// 添付ファイル名の上限（バイト）
+const ATTACHMENT_NAME_MAX_BYTES: usize = 128;
+
+/// 画像 1 枚の上限（バイト、カバーと同じ）
+const IMAGE_MAX_BYTES: usize = 20 * 1024 * 1024;
+/// PDF 1 つの上限（バイト）。ボックスセットのブックレットを 300dpi でスキャンすると数十 MB になる。
+const PDF_MAX_BYTES: usize = 100 * 1024 * 1024;
+pub const PDF_TYPE: &str = "application/pdf";
+
+/// 添付として受け付ける種類（画像はカバーと同じ）
+fn attachment_type(ext: &str) -> Option<&'static str> {
+    content_type_for(ext).or_else(|| ext.eq_ignore_ascii_case("pdf").then_some(PDF_TYPE))
+}
+
+/// 種類ごとの上限に収まっているか。PDF は中身の先頭も確かめる。
+fn check_content(content_type: &str, bytes: &[u8]) -> Result<(), (StatusCode, String)> {
+    let max = if content_type == PDF_TYPE { PDF_MAX_BYTES } else { IMAGE_MAX_BYTES };
+    if bytes.len() > max {
+        return Err((StatusCode::PAYLOAD_TOO_LARGE, format!("{} MB まで", max / 1024 / 1024)));
+    }
+    if content_type == PDF_TYPE && !bytes.starts_with(b"%PDF-") {
+        return Err((StatusCode::BAD_REQUEST, "PDF ではありません".to_string()));
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct Attachment {
+    /// 添付のファイル名（`/api/attachments/{レコード}/{name}` で取得）
+    pub name: String,
+    /// バイト数
+    pub size: u64,
+    pub content_type: &'static str,
+}
+
+fn record_dir(library: &Library, filename: &str) -> PathBuf {
+    library.dir.join(ATTACHMENTS_DIR).join(filename.trim_end_matches(".json"))
+}
+
+/// アップロードされたファイル名を置ける名前にする（パス区切り・制御文字を除き、長すぎれば切る）。
+/// 拡張子がなくなるものや空になるものは None。
+pub fn sanitize_attachment_name(raw: &str) -> Option<String> {
+    let base = raw.rsplit(['/', '\\']).next().unwrap_or("");
+    let cleaned: String = base.chars().filter(|c| !c.is_control() && *c != ':').collect();
+    let cleaned = cleaned.trim().trim_start_matches('.').to_string();
+    let (stem, ext) = cleaned.rsplit_once('.')?;
+    if stem.is_empty() || ext.is_empty() {
+        return None;
+    }
+    let mut stem = stem.to_string();
+    while stem.len() + ext.len() + 1 > ATTACHMENT_NAME_MAX_BYTES {
+        stem.pop();
+    }
+    Some(format!("{}.{}", stem, ext.to_ascii_lowercase()))
+}
+
+/// 同名があれば "back-2.jpg" のように番号を付ける。
+fn unique_name(name: &str, exists: impl Fn(&str) -> bool) -> String {
+    if !exists(name) {
+        return name.to_string();
+    }
+    let (stem, ext) = name.rsplit_once('.').unwrap_or((name, ""));
+    (2..)
+        .map(|i| format!("{}-{}.{}", stem, i, ext))
+        .find(|n| !exists(n))
+        .unwrap_or_default()
+}
+
+/// レコードの添付一覧（ファイル名順）。ディレクトリがなければ空。
+fn list_attachments(dir: &std::path::Path) -> Result<Vec<Attachment>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.to_string()),
+    };
+    let mut list: Vec<Attachment> = entries
+        .filter_map(Result::ok)
+        .filter_map(|e| {
+            let name = e.file_name().into_string().ok()?;
+            let content_type = name.rsplit_once('.').and_then(|(_, ext)| attachment_type(ext))?;
+            let size = e.metadata().ok().filter(|m| m.is_file())?.len();
+            Some(Attachment { name, size, content_type })
+        })
+        .collect();
+    list.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(list)
+}
+
+fn respond_list(library: &Library, filename: &str) -> axum::response::Response {
+    match list_attachments(&record_dir(library, filename)) {
+        Ok(list) => (StatusCode::OK, Json(list)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// 添付のファイル名として正しいか（パスを含まない・受け付ける種類）
+fn valid_attachment_name(file: &str) -> bool {
+    !file.contains("..")
+        && !file.contains(['/', '\\'])
+        && file.rsplit_once('.').and_then(|(_, e)| attachment_type(e)).is_some()
+}
+
+/// `GET /api/attachments/{name}` レコードの添付一覧を返す。
+#[utoipa::path(get, path = "/api/attachments/{name}", tag = "media", summary = "添付の一覧", params(LibraryQuery))]
+pub async fn list(Lib(library): Lib, Path(name): Path<String>) -> impl IntoResponse {
+    let Some(filename) = normalize_filename(&name) else {
+        return error(StatusCode::BAD_REQUEST, "invalid filename");
+    };
+    respond_list(&library, &filename)
+}
+
+/// `POST /api/attachments/{name}` multipart（`file` を 1 つ以上）で添付を追加し、更新後の一覧を返す。
+/// 1 つでも受け付けられないファイルがあれば何も書かない。
+#[utoipa::path(
+    post,
+    path = "/api/attachments/{name}",
+    tag = "media",
+    summary = "添付を上げる",
+    params(LibraryQuery),
+    request_body(content = crate::openapi::FileUpload, content_type = "multipart/form-data"),
+)]
+pub async fn upload(
+    Lib(library): Lib,
+    Path(name): Path<String>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let Some(filename) = normalize_filename(&name) else {
+        return error(StatusCode::BAD_REQUEST, "invalid filename");
+    };
+    if !library.storage.exists(&filename) {
+        return error(StatusCode::NOT_FOUND, "record not found");
+    }
+    let mut files = Vec::new();
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(f)) => f,
+            Ok(None) => break,
+            Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+        };
+        if field.name() != Some("file") {
+            continue;
+        }
+        let Some(file_name) = field.file_name().and_then(sanitize_attachment_name) else {
+            return error(StatusCode::BAD_REQUEST, "file name with an extension is required");
+        };
+        if !valid_attachment_name(&file_name) {
+            return error(StatusCode::BAD_REQUEST, "jpg / png / webp / gif / pdf のみ対応しています");
+        }
+        let content_type = file_name.rsplit_once('.').and_then(|(_, e)| attachment_type(e)).unwrap_or_default();
+        let bytes = match field.bytes().await {
+            Ok(b) => b,
+            Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+        };
+        if let Err((status, msg)) = check_content(content_type, &bytes) {
+            return error(status, format!("{}: {}", file_name, msg));
+        }
+        files.push((file_name, bytes));
+    }
+    if files.is_empty() {
+        return error(StatusCode::BAD_REQUEST, "file is required");
+    }
+    let dir = record_dir(&library, &filename);
+    if let Err(e) = fs::create_dir_all(&dir) {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    }
+    for (file_name, bytes) in files {
+        let file_name = unique_name(&file_name, |n| dir.join(n).exists());
+        if let Err(e) = fs::write(dir.join(&file_name), &bytes) {
+            return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+        }
+    }
+    respond_list(&library, &filename)
+}
+
+/// `GET /api/attachments/{name}/{file}` 添付を返す。
+#[utoipa::path(get, path = "/api/attachments/{name}/{file}", tag = "media", summary = "添付を読む", params(LibraryQuery))]
+pub async fn get(Lib(library): Lib, Path((name, file)): Path<(String, String)>) -> impl IntoResponse {
+    let Some(filename) = normalize_filename(&name) else {
+        return error(StatusCode::BAD_REQUEST, "invalid filename");
+    };
+    if !valid_attachment_name(&file) {
+        return error(StatusCode::BAD_REQUEST, "invalid attachment name");
+    }
+    let content_type = file.rsplit_once('.').and_then(|(_, e)| attachment_type(e)).unwrap_or_default();
+    match fs::read(record_dir(&library, &filename).join(&file)) {
+        Ok(bytes) => (StatusCode::OK, [(header::CONTENT_TYPE, content_type)], bytes).into_response(),
+        Err(e) => error(StatusCode::NOT_FOUND, format!("attachment not found: {}", e)),
+    }
+}
+
+/// `DELETE /api/attachments/{name}/{file}` 添付を消し、更新後の一覧を返す。最後の 1 つならディレクトリも消す。
+#[utoipa::path(delete, path = "/api/attachments/{name}/{file}", tag = "media", summary = "添付を消す", params(LibraryQuery))]
+pub async fn delete(Lib(library): Lib, Path((name, file)): Path<(String, String)>) -> impl IntoResponse {
+    let Some(filename) = normalize_filename(&name) else {
+        return error(StatusCode::BAD_REQUEST, "invalid filename");
+    };
+    if !valid_attachment_name(&file) {
+        return error(StatusCode::BAD_REQUEST, "invalid attachment name");
+    }
+    let dir = record_dir(&library, &filename);
+    match fs::remove_file(dir.join(&file)) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return error(StatusCode::NOT_FOUND, "attachment not found"),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+    // 空になったディレクトリは残さない（中身があれば失敗するだけ）
+    let _ = fs::remove_dir(&dir);
+    respond_list(&library, &filename)
+}
+
+#[cfg(test)]
+mod attachments_tests {
+    use super::{check_content, sanitize_attachment_name, unique_name, PDF_TYPE};
+
+    #[test]
+    fn names_are_flattened_and_deduplicated() {
+        assert_eq!(sanitize_attachment_name("C:\\scans\\Obi.JPG").as_deref(), Some("Obi.jpg"));
+        assert_eq!(sanitize_attachment_name("../../etc/back.png").as_deref(), Some("back.png"));
+        assert_eq!(sanitize_attachment_name(".hidden"), None);
+        assert_eq!(sanitize_attachment_name("noext"), None);
+        let taken = ["back.jpg", "back-2.jpg"];
+        assert_eq!(unique_name("back.jpg", |n| taken.contains(&n)), "back-3.jpg");
+        assert_eq!(unique_name("obi.jpg", |n| taken.contains(&n)), "obi.jpg");
+    }
+
+    #[test]
+    fn pdfs_are_checked_by_header() {
+        assert!(check_content(PDF_TYPE, b"%PDF-1.7\n...").is_ok());
+        assert!(check_content(PDF_TYPE, b"<html>").is_err());
+        assert!(check_content("image/png", b"\x89PNG").is_ok());
+    }
+}
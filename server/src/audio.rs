@@ -0,0 +1,298 @@
+//! 試聴用の短い音声。アルバムに 1 つ、トラックごとに 1 つまで置ける。添付（attachments.rs）と同じく
+//! `{ライブラリのディレクトリ}/attachments/{レコード名（.json なし）}/audio/` に `album.mp3`・`track-{ディスク}-{番号}.ogg` の名前で置く
+//! （名前の変更・削除は添付のディレクトリごと付いていく）。
+//!
+//! - `GET /api/audio/{name}` 置いてある音声の一覧
+//! - `POST /api/files/{name}/audio?disc=1&track=3` multipart の `file` で置く（同じところのものは置き換える。`track` がなければアルバム）
+//! - `GET /api/files/{name}/audio?disc=1&track=3` 再生用。`Range` に応える
+//! - `DELETE /api/files/{name}/audio?disc=1&track=3` 消す
+
+use axum::{
+    body::Body,
+    extract::{Multipart, Path, Query, Request},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::fs;
+use std::path::{Path as FsPath, PathBuf};
+use tower_http::services::ServeFile;
+
+use crate::attachments::ATTACHMENTS_DIR;
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+use crate::{normalize_filename, storage};
+
+/// `/api/files/{name}` に続けて音声を指すところ
+pub const FILES_SUFFIX: &str = "/audio";
+/// 添付のディレクトリの下の置き場所
+const AUDIO_DIR: &str = "audio";
+/// アップロードの上限（バイト）。数十秒の試聴用なので大きくはしない
+pub const AUDIO_MAX_BYTES: usize = 15 * 1024 * 1024;
+const ALBUM_STEM: &str = "album";
+
+/// 受け付ける種類
+fn audio_type(ext: &str) -> Option<&'static str> {
+    match ext.to_ascii_lowercase().as_str() {
+        "mp3" => Some("audio/mpeg"),
+        "m4a" | "mp4" => Some("audio/mp4"),
+        "aac" => Some("audio/aac"),
+        "ogg" | "oga" | "opus" => Some("audio/ogg"),
+        "flac" => Some("audio/flac"),
+        "wav" => Some("audio/wav"),
+        "webm" => Some("audio/webm"),
+        _ => None,
+    }
+}
+
+/// どこの音声か（`track` がなければアルバム）
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct Slot {
+    pub disc: Option<u32>,
+    pub track: Option<u32>,
+}
+
+impl Slot {
+    /// ディスクを省いたら 1 枚目、アルバムならディスクは見ない
+    fn normalized(self) -> Slot {
+        match self.track {
+            Some(no) => Slot { disc: Some(self.disc.unwrap_or(1)), track: Some(no) },
+            None => Slot::default(),
+        }
+    }
+
+    /// ファイル名の拡張子の前
+    fn stem(&self) -> String {
+        match self.track {
+            Some(no) => format!("track-{}-{}", self.disc.unwrap_or(1), no),
+            None => ALBUM_STEM.to_string(),
+        }
+    }
+
+    fn from_stem(stem: &str) -> Option<Slot> {
+        if stem == ALBUM_STEM {
+            return Some(Slot::default());
+        }
+        let (disc, no) = stem.strip_prefix("track-")?.split_once('-')?;
+        Some(Slot { disc: Some(disc.parse().ok()?), track: Some(no.parse().ok()?) })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct Sample {
+    /// トラックの音声ならディスクと番号（アルバムなら null）
+    pub disc: Option<u32>,
+    pub track: Option<u32>,
+    pub file: String,
+    pub size: u64,
+    pub content_type: &'static str,
+}
+
+fn audio_dir(dir: &FsPath, filename: &str) -> PathBuf {
+    dir.join(ATTACHMENTS_DIR).join(filename.trim_end_matches(".json")).join(AUDIO_DIR)
+}
+
+/// 置いてある音声（アルバム、ディスク・番号の順）。ディレクトリがなければ空
+pub fn list_samples(dir: &FsPath) -> Result<Vec<Sample>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.to_string()),
+    };
+    let mut list: Vec<Sample> = entries
+        .filter_map(Result::ok)
+        .filter_map(|e| {
+            let file = e.file_name().into_string().ok()?;
+            let (stem, ext) = file.rsplit_once('.')?;
+            let slot = Slot::from_stem(stem)?;
+            let content_type = audio_type(ext)?;
+            let size = e.metadata().ok().filter(|m| m.is_file())?.len();
+            Some(Sample { disc: slot.disc, track: slot.track, file, size, content_type })
+        })
+        .collect();
+    list.sort_by_key(|s| (s.track.is_some(), s.disc, s.track));
+    Ok(list)
+}
+
+fn find_sample(dir: &FsPath, slot: Slot) -> Option<Sample> {
+    let slot = slot.normalized();
+    list_samples(dir).ok()?.into_iter().find(|s| s.disc == slot.disc && s.track == slot.track)
+}
+
+fn respond_list(dir: &FsPath) -> Response {
+    match list_samples(dir) {
+        Ok(list) => (StatusCode::OK, Json(list)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// レコードにそのトラックがあるか
+fn has_track(record: &serde_json::Value, slot: Slot) -> bool {
+    let Some(no) = slot.track else { return true };
+    let disc = slot.disc.unwrap_or(1);
+    record["tracks"].as_array().is_some_and(|tracks| {
+        tracks.iter().any(|t| {
+            let n = |k: &str| t[k].as_u64().or_else(|| t[k].as_str().and_then(|s| s.trim().parse().ok()));
+            n("disc_no").unwrap_or(1) == u64::from(disc) && n("no") == Some(u64::from(no))
+        })
+    })
+}
+
+/// `/api/files/{name}/audio` の `{name}` を "xxx.json" にする
+fn record_name(path: &str) -> Option<String> {
+    path.trim_start_matches('/').strip_suffix(FILES_SUFFIX).filter(|p| !p.contains('/')).and_then(normalize_filename)
+}
+
+/// `GET /api/audio/{name}`
+#[utoipa::path(get, path = "/api/audio/{name}", tag = "media", summary = "試聴用の音声の一覧", params(LibraryQuery))]
+pub async fn list(Lib(library): Lib, Path(name): Path<String>) -> impl IntoResponse {
+    let Some(filename) = normalize_filename(&name) else {
+        return error(StatusCode::BAD_REQUEST, "invalid filename");
+    };
+    respond_list(&audio_dir(&library.dir, &filename))
+}
+
+/// `GET /api/files/{name}/audio`（main.rs の get_file から）。`Range` と `If-Modified-Since` は ServeFile に任せる
+pub async fn serve(library_dir: &FsPath, path: &str, request: Request) -> Response {
+    let Some(filename) = record_name(path) else {
+        return error(StatusCode::BAD_REQUEST, "invalid path");
+    };
+    let slot = match Query::<Slot>::try_from_uri(request.uri()) {
+        Ok(Query(slot)) => slot,
+        Err(e) => return error(StatusCode::BAD_REQUEST, e.body_text()),
+    };
+    let dir = audio_dir(library_dir, &filename);
+    let Some(sample) = find_sample(&dir, slot) else {
+        return error(StatusCode::NOT_FOUND, "audio not found");
+    };
+    match ServeFile::new(dir.join(&sample.file)).try_call(request).await {
+        Ok(res) => {
+            let mut res = res.map(Body::new);
+            if res.status().is_success() {
+                res.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static(sample.content_type));
+            }
+            res
+        }
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// `POST /api/files/{name}/audio` 置いて、更新後の一覧を返す
+#[utoipa::path(
+    post,
+    path = "/api/files/{path}",
+    tag = "media",
+    summary = "試聴用の音声を置く（{name}/audio?disc=&track=、multipart の file）",
+    params(LibraryQuery),
+    request_body(content = crate::openapi::FileUpload, content_type = "multipart/form-data"),
+)]
+pub async fn upload(
+    Lib(library): Lib,
+    Path(path): Path<String>,
+    Query(slot): Query<Slot>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let Some(filename) = record_name(&path) else {
+        return error(StatusCode::NOT_FOUND, "not found");
+    };
+    let record = match storage::read_record(&*library.storage, &filename) {
+        Ok(v) => v,
+        Err(e) => return error(e.status(), e.message()),
+    };
+    if !has_track(&record, slot) {
+        return error(StatusCode::BAD_REQUEST, format!("ディスク {} のトラック {} はありません", slot.disc.unwrap_or(1), slot.track.unwrap_or(0)));
+    }
+    let mut upload = None;
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(f)) => f,
+            Ok(None) => break,
+            Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+        };
+        if field.name() != Some("file") {
+            continue;
+        }
+        let Some(ext) = field.file_name().and_then(|n| n.rsplit_once('.')).map(|(_, e)| e.to_ascii_lowercase()) else {
+            return error(StatusCode::BAD_REQUEST, "file name with an extension is required");
+        };
+        if audio_type(&ext).is_none() {
+            return error(StatusCode::BAD_REQUEST, "mp3 / m4a / aac / ogg / opus / flac / wav / webm のみ対応しています");
+        }
+        match field.bytes().await {
+            Ok(b) if b.len() > AUDIO_MAX_BYTES => {
+                return error(StatusCode::PAYLOAD_TOO_LARGE, format!("{} MB まで", AUDIO_MAX_BYTES / 1024 / 1024))
+            }
+            Ok(b) => upload = Some((ext, b)),
+            Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+        }
+    }
+    let Some((ext, bytes)) = upload else {
+        return error(StatusCode::BAD_REQUEST, "file is required");
+    };
+    let dir = audio_dir(&library.dir, &filename);
+    if let Err(e) = fs::create_dir_all(&dir) {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    }
+    let name = format!("{}.{}", slot.stem(), ext);
+    let old = find_sample(&dir, slot);
+    if let Err(e) = storage::write_atomic(&dir.join(&name), &bytes) {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    }
+    // 拡張子の違う前のものは消す
+    if let Some(old) = old.filter(|s| s.file != name) {
+        let _ = fs::remove_file(dir.join(old.file));
+    }
+    respond_list(&dir)
+}
+
+/// `DELETE /api/files/{name}/audio`（main.rs の delete_file から）。更新後の一覧を返す
+pub fn delete(library_dir: &FsPath, path: &str, slot: Slot) -> Response {
+    let Some(filename) = record_name(path) else {
+        return error(StatusCode::BAD_REQUEST, "invalid path");
+    };
+    let dir = audio_dir(library_dir, &filename);
+    let Some(sample) = find_sample(&dir, slot) else {
+        return error(StatusCode::NOT_FOUND, "audio not found");
+    };
+    if let Err(e) = fs::remove_file(dir.join(&sample.file)) {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    }
+    // 空になったら消す（中身があれば失敗するだけ）
+    let _ = fs::remove_dir(&dir);
+    respond_list(&dir)
+}
+
+#[cfg(test)]
+mod audio_tests {
+    use super::{find_sample, has_track, list_samples, record_name, Slot};
+    use serde_json::json;
+
+    #[test]
+    fn samples_are_listed_by_slot() {
+        let dir = tempfile::tempdir().unwrap();
+        for f in ["track-1-10.ogg", "album.mp3", "track-1-2.m4a", "cover.jpg", "track-x.mp3"] {
+            std::fs::write(dir.path().join(f), b"x").unwrap();
+        }
+        let list = list_samples(dir.path()).unwrap();
+        let names: Vec<&str> = list.iter().map(|s| s.file.as_str()).collect();
+        assert_eq!(names, ["album.mp3", "track-1-2.m4a", "track-1-10.ogg"]);
+        assert_eq!(list[2].content_type, "audio/ogg");
+        let track = |disc, no| Slot { disc, track: Some(no) };
+        assert_eq!(find_sample(dir.path(), track(None, 2)).unwrap().file, "track-1-2.m4a");
+        assert_eq!(find_sample(dir.path(), track(Some(1), 10)).unwrap().file, "track-1-10.ogg");
+        assert!(find_sample(dir.path(), track(Some(2), 2)).is_none());
+        assert_eq!(find_sample(dir.path(), Slot::default()).unwrap().file, "album.mp3");
+    }
+
+    #[test]
+    fn tracks_must_exist_and_paths_are_record_names() {
+        let record = json!({"tracks": [{"disc_no": 1, "no": 1}, {"disc_no": "2", "no": "3"}]});
+        assert!(has_track(&record, Slot::default()));
+        assert!(has_track(&record, Slot { disc: None, track: Some(1) }));
+        assert!(has_track(&record, Slot { disc: Some(2), track: Some(3) }));
+        assert!(!has_track(&record, Slot { disc: Some(1), track: Some(3) }));
+        assert_eq!(record_name("/Bill_Evans__Alone.json/audio").as_deref(), Some("Bill_Evans__Alone.json"));
+        assert_eq!(record_name("a/b.json/audio"), None);
+        assert_eq!(record_name("a.json"), None);
+    }
+}
@@ -0,0 +1,191 @@
+//! 変更の記録。`{DB_PATH}/.config/audit.jsonl` に 1 行 1 件で追記するだけ（書き換え・削除はしない）。
+//! 書き込みのリクエスト（GET 以外）ごとに、いつ・どのトークンで・どのパスに・結果を残す。
+//! レコードの保存・削除・名前の変更は、ハンドラーがレスポンスの extensions に `RecordChange` を入れておき、
+//! どのファイルをどうしたか（変わった項目）も同じ行に足す。まとめて変えたものは `RecordChanges` で 1 件 1 行にする。`GET /api/audit` はそれを新しい順に返す。
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::date::Date;
+use crate::logs::{read_audit, AuditRow};
+use crate::problem::error;
+use crate::settings::{config_path, CONFIG_DIR};
+use crate::stats::now_timestamp;
+use crate::AppState;
+
+pub const AUDIT_FILE: &str = "audit.jsonl";
+/// `GET /api/audit` の既定の件数
+const DEFAULT_LIMIT: usize = 200;
+
+/// レコードをどう変えたか
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct RecordChange {
+    /// "create" / "update" / "delete" / "rename"
+    pub action: &'static str,
+    pub file: String,
+    /// 名前の変更の、新しい名前
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renamed_to: Option<String>,
+    /// 既定以外のライブラリのとき、その名前
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub library: Option<String>,
+    /// 変わった項目（"janre.main, score, tracks"）
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub fields: String,
+}
+
+impl RecordChange {
+    /// 保存。前のレコードがなければ create
+    pub fn saved(file: &str, old: Option<&Value>, new: &Value) -> Self {
+        match old {
+            Some(old) => Self {
+                action: "update",
+                file: file.to_string(),
+                fields: changed_fields(old, new).join(", "),
+                ..Default::default()
+            },
+            None => Self { action: "create", file: file.to_string(), ..Default::default() },
+        }
+    }
+
+    pub fn in_library(mut self, library: &crate::libraries::Library) -> Self {
+        self.library = (!library.is_default()).then(|| library.name.clone());
+        self
+    }
+}
+
+/// まとめて変えたレコード（ファイル名の一括整理など）。1 件ごとに 1 行ずつ残す
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecordChanges(pub Vec<RecordChange>);
+
+/// 変わった項目のキー（キーの順）。オブジェクト（janre・label など）は 1 段下まで見る
+pub fn changed_fields(old: &Value, new: &Value) -> Vec<String> {
+    fn diff(old: &Value, new: &Value, prefix: &str, depth: usize, out: &mut Vec<String>) {
+        let (Some(a), Some(b)) = (old.as_object(), new.as_object()) else {
+            if old != new {
+                out.push(prefix.to_string());
+            }
+            return;
+        };
+        if depth == 0 {
+            if a != b {
+                out.push(prefix.to_string());
+            }
+            return;
+        }
+        let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+        for k in keys {
+            let (x, y) = (a.get(k).unwrap_or(&Value::Null), b.get(k).unwrap_or(&Value::Null));
+            if x != y {
+                let key = if prefix.is_empty() { k.clone() } else { format!("{}.{}", prefix, k) };
+                diff(x, y, &key, depth - 1, out);
+            }
+        }
+    }
+    let mut out = Vec::new();
+    diff(old, new, "", 2, &mut out);
+    out
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct AuditEntry<'a> {
+    /// RFC 3339（UTC）
+    pub at: String,
+    /// 操作したトークンの名前（トークンを使っていなければ None）
+    pub token: Option<&'a str>,
+    pub method: &'a str,
+    pub path: &'a str,
+    pub status: u16,
+    #[serde(flatten)]
+    pub change: Option<RecordChange>,
+}
+
+impl<'a> AuditEntry<'a> {
+    pub fn now(token: Option<&'a str>, method: &'a str, path: &'a str, status: u16) -> Self {
+        Self { at: now_timestamp(), token, method, path, status, change: None }
+    }
+}
+
+pub fn append(db_path: &Path, entry: &AuditEntry) -> std::io::Result<()> {
+    fs::create_dir_all(db_path.join(CONFIG_DIR))?;
+    let mut f = OpenOptions::new().create(true).append(true).open(config_path(db_path, AUDIT_FILE))?;
+    writeln!(f, "{}", serde_json::to_string(entry).map_err(std::io::Error::other)?)
+}
+
+#[derive(serde::Deserialize)]
+pub struct AuditParams {
+    /// ファイル名の一部（名前の変更は新しい名前でも当たる）
+    file: Option<String>,
+    /// 期間（YYYY/MM/DD、両端を含む、UTC の日付）
+    from: Option<String>,
+    to: Option<String>,
+    limit: Option<usize>,
+    /// レコード以外の書き込み（設定・メモなど）も含める
+    #[serde(default)]
+    all: bool,
+}
+
+/// 条件に合うか
+fn matches(row: &AuditRow, file: &str, from: Option<Date>, to: Option<Date>, all: bool) -> bool {
+    if !all && row.file.is_none() {
+        return false;
+    }
+    let day = row.at.get(..10).and_then(|d| Date::parse(&d.replace('-', "/")));
+    if from.is_some_and(|f| day.is_none_or(|d| d < f)) || to.is_some_and(|t| day.is_none_or(|d| d > t)) {
+        return false;
+    }
+    file.is_empty()
+        || [&row.file, &row.renamed_to].into_iter().flatten().any(|f| f.to_lowercase().contains(file))
+}
+
+/// `GET /api/audit?file=&from=&to=&limit=&all=` レコードの変更を新しい順に返す
+#[utoipa::path(get, path = "/api/audit", tag = "auth", summary = "変更の記録")]
+pub async fn get_audit(State(state): State<AppState>, Query(params): Query<AuditParams>) -> impl IntoResponse {
+    let parse_day = |d: &Option<String>| match d.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        None => Ok(None),
+        Some(d) => Date::parse(d).map(Some).ok_or_else(|| format!("invalid date: {}", d)),
+    };
+    let (from, to) = match (parse_day(&params.from), parse_day(&params.to)) {
+        (Ok(f), Ok(t)) => (f, t),
+        (Err(e), _) | (_, Err(e)) => {
+            return error(StatusCode::BAD_REQUEST, e);
+        }
+    };
+    let file = params.file.unwrap_or_default().trim().to_lowercase();
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+    let res = tokio::task::spawn_blocking(move || read_audit(&state.db_path)).await;
+    match res {
+        Ok(Ok(rows)) => {
+            let entries: Vec<AuditRow> =
+                rows.into_iter().rev().filter(|r| matches(r, &file, from, to, params.all)).take(limit).collect();
+            (StatusCode::OK, Json(serde_json::json!({"entries": entries}))).into_response()
+        }
+        Ok(Err(e)) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod audit_tests {
+    use super::{changed_fields, RecordChange};
+    use serde_json::json;
+
+    #[test]
+    fn changed_fields_go_one_level_into_objects() {
+        let old = json!({"title": "Alone", "janre": {"main": "Jazz", "sub": ["Piano"]}, "tracks": [{"title": "a"}], "score": 4});
+        let new = json!({"title": "Alone", "janre": {"main": "Jazz", "sub": ["Solo"]}, "tracks": [{"title": "b"}], "comment": "x"});
+        assert_eq!(changed_fields(&old, &new), ["comment", "janre.sub", "score", "tracks"]);
+        assert!(changed_fields(&old, &old).is_empty());
+        assert_eq!(RecordChange::saved("a.json", None, &new).action, "create");
+        assert_eq!(RecordChange::saved("a.json", Some(&old), &new).fields, "comment, janre.sub, score, tracks");
+    }
+}
@@ -0,0 +1,354 @@
+//! 端末ごとの API トークン（スマホ・ノート PC・スクリプトなど）。`{DB_PATH}/.config/tokens.json` には名前とハッシュだけを保存する。
+//! トークンが 1 つもなければこれまでどおり誰でも書き込める。1 つでも作ると、書き込み（GET 以外）とトークン一覧・変更の記録・バックアップの一覧には
+//! `Authorization: Bearer <トークン>` か、`POST /api/session` で受け取るセッション Cookie が要る。読み取りはそのまま。
+//! 誰の操作かは audit.rs の記録に残す。
+
+use axum::{
+    extract::{Path as UrlPath, Request, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::audit::{self, AuditEntry};
+use crate::listen::write_json;
+use crate::problem::error;
+use crate::settings::config_path;
+use crate::stats::{date_from_system_time, now_timestamp};
+use crate::AppState;
+
+const TOKENS_FILE: &str = "tokens.json";
+pub const SESSION_COOKIE: &str = "nekokan_session";
+/// 発行するトークンの頭（ログなどに紛れ込んだときに見分けられるように）
+const SECRET_PREFIX: &str = "nkm_";
+const SECRET_BYTES: usize = 24;
+const NAME_MAX_CHARS: usize = 64;
+/// セッション Cookie の有効期間（秒）。トークンを取り消せばその時点で使えなくなる。
+const SESSION_MAX_AGE_SECS: u64 = 180 * 24 * 60 * 60;
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DeviceToken {
+    pub id: String,
+    /// "phone" / "laptop" / "import script" など
+    pub name: String,
+    /// トークンの SHA-256（16 進）。トークンそのものは作ったときに一度だけ返す。
+    hash: String,
+    /// RFC 3339（UTC）
+    pub created: String,
+    /// 最後に使った日（YYYY/MM/DD）
+    #[serde(default)]
+    pub last_used: Option<String>,
+}
+
+/// 一覧で返す形（ハッシュは出さない）
+#[derive(serde::Serialize)]
+struct TokenInfo<'a> {
+    id: &'a str,
+    name: &'a str,
+    created: &'a str,
+    last_used: Option<&'a str>,
+    /// このリクエストに使ったトークンか
+    current: bool,
+}
+
+impl DeviceToken {
+    fn info(&self, current: Option<&str>) -> TokenInfo<'_> {
+        TokenInfo {
+            id: &self.id,
+            name: &self.name,
+            created: &self.created,
+            last_used: self.last_used.as_deref(),
+            current: current == Some(self.id.as_str()),
+        }
+    }
+}
+
+fn hash_secret(secret: &str) -> String {
+    Sha256::digest(secret.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn random_hex(bytes: usize) -> Result<String, String> {
+    let mut buf = vec![0u8; bytes];
+    getrandom::getrandom(&mut buf).map_err(|e| e.to_string())?;
+    Ok(buf.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// トークンの一覧。ファイルとメモリの両方に持ち、変えたときだけ書き出す。
+pub struct TokenStore {
+    path: PathBuf,
+    tokens: Mutex<Vec<DeviceToken>>,
+}
+
+impl TokenStore {
+    /// 読めないファイルを「トークンなし」と扱うと誰でも書き込めてしまうので、そのときはエラーにする
+    pub fn load(db_path: &Path) -> Result<Self, String> {
+        let path = config_path(db_path, TOKENS_FILE);
+        let tokens = match fs::read_to_string(&path) {
+            Ok(s) => serde_json::from_str(&s).map_err(|e| format!("{}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(format!("{}: {}", path.display(), e)),
+        };
+        Ok(Self { path, tokens: Mutex::new(tokens) })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<DeviceToken>> {
+        self.tokens.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, tokens: &[DeviceToken]) -> Result<(), String> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        write_json(&self.path, &serde_json::to_value(tokens).map_err(|e| e.to_string())?)
+    }
+
+    /// トークンが 1 つでもあれば認証を求める
+    pub fn enabled(&self) -> bool {
+        !self.lock().is_empty()
+    }
+
+    /// トークンに合うもの。使った日を記録する（書き出すのは日が変わったときだけ）。
+    pub fn authenticate(&self, secret: &str) -> Option<DeviceToken> {
+        let hash = hash_secret(secret.trim());
+        let today = date_from_system_time(std::time::SystemTime::now());
+        let mut tokens = self.lock();
+        let t = tokens.iter_mut().find(|t| t.hash == hash)?;
+        if t.last_used != today {
+            t.last_used = today;
+            let found = t.clone();
+            if let Err(e) = self.save(&tokens) {
+                eprintln!("トークンの使用日を保存できませんでした: {}", e);
+            }
+            return Some(found);
+        }
+        Some(t.clone())
+    }
+
+    /// 新しいトークンを作る。トークンそのもの（シークレット）はこの戻り値でしか分からない。
+    pub fn create(&self, name: &str) -> Result<(DeviceToken, String), String> {
+        let secret = format!("{}{}", SECRET_PREFIX, random_hex(SECRET_BYTES)?);
+        let token = DeviceToken {
+            id: random_hex(4)?,
+            name: name.to_string(),
+            hash: hash_secret(&secret),
+            created: now_timestamp(),
+            last_used: None,
+        };
+        let mut tokens = self.lock();
+        let mut next = tokens.clone();
+        next.push(token.clone());
+        self.save(&next)?;
+        *tokens = next;
+        Ok((token, secret))
+    }
+
+    /// 取り消す。なければ false。
+    pub fn revoke(&self, id: &str) -> Result<bool, String> {
+        let mut tokens = self.lock();
+        let next: Vec<DeviceToken> = tokens.iter().filter(|t| t.id != id).cloned().collect();
+        if next.len() == tokens.len() {
+            return Ok(false);
+        }
+        self.save(&next)?;
+        *tokens = next;
+        Ok(true)
+    }
+}
+
+/// リクエストのトークン（Authorization: Bearer を優先し、なければセッション Cookie）
+fn secret_from(headers: &HeaderMap) -> Option<String> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim);
+    let cookie = || {
+        headers
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(';'))
+            .find_map(|c| c.trim().strip_prefix(SESSION_COOKIE)?.strip_prefix('='))
+            .map(str::trim)
+    };
+    bearer.or_else(cookie).filter(|s| !s.is_empty()).map(str::to_string)
+}
+
+fn is_read(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// トークンがあるときに認証を求めるリクエストか。ログイン（/api/session）は除く。
+/// 変更の記録（書き出しを含む）は読むだけでも要る。
+pub(crate) fn needs_auth(method: &Method, path: &str) -> bool {
+    path.starts_with("/api/")
+        && path != "/api/session"
+        && (!is_read(method)
+            || path.starts_with("/api/tokens")
+            || path.starts_with("/api/backups")
+            || path.starts_with("/api/audit")
+            || path.starts_with("/api/logs/audit"))
+}
+
+/// 変更として記録するリクエストか（ログインやエラー報告、重複の問い合わせは除く）
+fn is_change(method: &Method, path: &str) -> bool {
+    path.starts_with("/api/")
+        && !is_read(method)
+        && !matches!(path, "/api/session" | "/api/client-errors" | "/api/validation-failures" | "/api/duplicates/check")
+}
+
+/// 使われたトークン（ハンドラーはリクエストの extensions から取れる）
+#[derive(Clone, Debug)]
+pub struct Actor(pub Option<DeviceToken>);
+
+/// すべての /api に掛けるミドルウェア。トークンを確かめ、書き込みを記録する。
+pub async fn require_token(State(state): State<AppState>, mut req: Request, next: Next) -> Response {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let token = secret_from(req.headers()).and_then(|s| state.tokens.authenticate(&s));
+    if token.is_none() && needs_auth(&method, &path) && state.tokens.enabled() {
+        return error(StatusCode::UNAUTHORIZED, "トークンが必要です（設定 → 端末ごとのトークンでログイン）");
+    }
+    req.extensions_mut().insert(Actor(token.clone()));
+    let res = next.run(req).await;
+    if is_change(&method, &path) {
+        let name = token.as_ref().map(|t| t.name.as_str());
+        let changes: Vec<Option<audit::RecordChange>> = match res.extensions().get::<audit::RecordChanges>() {
+            Some(audit::RecordChanges(list)) if !list.is_empty() => list.iter().cloned().map(Some).collect(),
+            _ => vec![res.extensions().get::<audit::RecordChange>().cloned()],
+        };
+        for change in changes {
+            let mut entry = AuditEntry::now(name, method.as_str(), &path, res.status().as_u16());
+            entry.change = change;
+            if let Err(e) = audit::append(&state.db_path, &entry) {
+                eprintln!("変更の記録を書けませんでした: {}", e);
+            }
+        }
+    }
+    res
+}
+
+fn current_id(actor: &Actor) -> Option<&str> {
+    actor.0.as_ref().map(|t| t.id.as_str())
+}
+
+/// `GET /api/tokens`
+#[utoipa::path(get, path = "/api/tokens", tag = "auth", summary = "端末のトークンの一覧")]
+pub async fn list(State(state): State<AppState>, axum::Extension(actor): axum::Extension<Actor>) -> impl IntoResponse {
+    let tokens = state.tokens.lock().clone();
+    let infos: Vec<TokenInfo> = tokens.iter().map(|t| t.info(current_id(&actor))).collect();
+    (StatusCode::OK, Json(serde_json::json!({"enabled": !tokens.is_empty(), "tokens": infos}))).into_response()
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct CreateBody {
+    name: String,
+    /// この端末のセッションにもする（Cookie を返す）
+    #[serde(default)]
+    use_here: bool,
+}
+
+fn session_cookie(secret: &str, max_age: u64) -> String {
+    let secure = if crate::tls::enabled() { "; Secure" } else { "" };
+    format!("{}={}; Path=/; HttpOnly; SameSite=Strict; Max-Age={}{}", SESSION_COOKIE, secret, max_age, secure)
+}
+
+/// `POST /api/tokens` `{"name": "phone", "use_here": true}`。作ったトークンは `secret` で一度だけ返す。
+/// 最初の 1 つはトークンなしで作れる（そこから先は認証が要る）。
+#[utoipa::path(post, path = "/api/tokens", tag = "auth", summary = "端末のトークンを作る", request_body = CreateBody)]
+pub async fn create(State(state): State<AppState>, Json(body): Json<CreateBody>) -> impl IntoResponse {
+    let name = body.name.trim();
+    if name.is_empty() || name.chars().count() > NAME_MAX_CHARS {
+        return error(StatusCode::BAD_REQUEST, format!("名前は 1〜{} 文字", NAME_MAX_CHARS));
+    }
+    if state.tokens.lock().iter().any(|t| t.name == name) {
+        return error(StatusCode::CONFLICT, format!("「{}」は既にあります", name));
+    }
+    match state.tokens.create(name) {
+        Ok((token, secret)) => {
+            let current = body.use_here.then_some(token.id.as_str());
+            let json = Json(serde_json::json!({"token": token.info(current), "secret": secret}));
+            if body.use_here {
+                ([(header::SET_COOKIE, session_cookie(&secret, SESSION_MAX_AGE_SECS))], json).into_response()
+            } else {
+                (StatusCode::OK, json).into_response()
+            }
+        }
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// `DELETE /api/tokens/:id`
+#[utoipa::path(delete, path = "/api/tokens/{id}", tag = "auth", summary = "端末のトークンを取り消す")]
+pub async fn revoke(State(state): State<AppState>, UrlPath(id): UrlPath<String>) -> impl IntoResponse {
+    match state.tokens.revoke(&id) {
+        Ok(true) => (StatusCode::OK, Json(serde_json::json!({"ok": true}))).into_response(),
+        Ok(false) => error(StatusCode::NOT_FOUND, "token not found"),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct SessionBody {
+    token: String,
+}
+
+/// `POST /api/session` `{"token": "nkm_..."}`。トークンを確かめてセッション Cookie を返す（ブラウザ用）。
+#[utoipa::path(post, path = "/api/session", tag = "auth", summary = "ログインする", request_body = SessionBody)]
+pub async fn login(State(state): State<AppState>, Json(body): Json<SessionBody>) -> impl IntoResponse {
+    match state.tokens.authenticate(&body.token) {
+        Some(t) => (
+            [(header::SET_COOKIE, session_cookie(body.token.trim(), SESSION_MAX_AGE_SECS))],
+            Json(serde_json::json!({"ok": true, "name": t.name})),
+        )
+            .into_response(),
+        None => error(StatusCode::UNAUTHORIZED, "トークンが違うか、取り消されています"),
+    }
+}
+
+/// `DELETE /api/session`（この端末のログアウト。トークンは残る）
+#[utoipa::path(delete, path = "/api/session", tag = "auth", summary = "ログアウトする")]
+pub async fn logout() -> impl IntoResponse {
+    ([(header::SET_COOKIE, session_cookie("", 0))], Json(serde_json::json!({"ok": true}))).into_response()
+}
+
+#[cfg(test)]
+mod auth_tests {
+    use super::{needs_auth, secret_from, TokenStore};
+    use axum::http::{header, HeaderMap, Method};
+
+    #[test]
+    fn tokens_authenticate_until_revoked() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TokenStore::load(dir.path()).unwrap();
+        assert!(!store.enabled());
+        let (phone, secret) = store.create("phone").unwrap();
+        assert!(secret.starts_with("nkm_") && store.enabled());
+        assert_eq!(store.authenticate(&secret).map(|t| t.name), Some("phone".to_string()));
+        assert!(store.authenticate("nkm_wrong").is_none());
+        // 保存したものを読み直しても使える
+        let reloaded = TokenStore::load(dir.path()).unwrap();
+        assert!(reloaded.authenticate(&secret).is_some());
+        assert!(reloaded.revoke(&phone.id).unwrap());
+        assert!(reloaded.authenticate(&secret).is_none() && !reloaded.enabled());
+    }
+
+    #[test]
+    fn writes_need_a_token_and_reads_do_not() {
+        assert!(needs_auth(&Method::POST, "/api/save"));
+        assert!(needs_auth(&Method::GET, "/api/tokens"));
+        assert!(!needs_auth(&Method::GET, "/api/list-with-labels"));
+        assert!(!needs_auth(&Method::POST, "/api/session"));
+
+        let mut h = HeaderMap::new();
+        h.insert(header::COOKIE, "theme=dark; nekokan_session=nkm_abc".parse().unwrap());
+        assert_eq!(secret_from(&h).as_deref(), Some("nkm_abc"));
+        h.insert(header::AUTHORIZATION, "Bearer nkm_xyz".parse().unwrap());
+        assert_eq!(secret_from(&h).as_deref(), Some("nkm_xyz"));
+    }
+}
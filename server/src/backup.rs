@@ -0,0 +1,435 @@
+//! DB のディレクトリ（既定のライブラリ）のバックアップと復元。スナップショットは `--backup-dir`（環境変数 `BACKUP_DIR`、
+//! 既定は `{DB_PATH}-backups`）の下に、作った時刻（UTC）の名前のディレクトリ（"2026-10-16T125034Z"）として丸ごと写す。
+//! レコード・カバー・メモ・添付・設定（`.config`）が入る。`--library` で足したライブラリと git の履歴（`.git`）は対象外。
+//!
+//! - `POST /api/backup` スナップショットを作る
+//! - `GET /api/backups` 一覧（新しい順）、`DELETE /api/backups/{name}`、`POST /api/backups/prune`（`{"keep": N}` 新しい N 個を残す）
+//! - `POST /api/restore/{name}` そのスナップショットに戻す。戻す前に今の状態のスナップショットを作る。
+//!   トークン（tokens.json）と変更の記録（audit.jsonl）は今のものを残す（取り消したトークンが復活しないように）。
+//!
+//! `--backup-daily`（`BACKUP_DAILY`）なら、その日（UTC）のスナップショットがなければ作り、`--backup-keep`（`BACKUP_KEEP`）個より古いものを消す。
+
+use axum::{
+    extract::{Path as UrlPath, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::audit::AUDIT_FILE;
+use crate::git_history::GIT_DIR;
+use crate::problem::{error, field_error};
+use crate::settings::CONFIG_DIR;
+use crate::stats::now_timestamp;
+use crate::AppState;
+
+/// 自動バックアップの日付を確かめる間隔
+const SCHEDULER_TICK: Duration = Duration::from_secs(60 * 60);
+/// 復元で今のものを残す `.config` の中のファイル
+const KEEP_ON_RESTORE: [&str; 2] = ["tokens.json", AUDIT_FILE];
+
+/// バックアップ・復元の最中か（同時に 2 つ走らせない）
+static BUSY: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Debug)]
+pub struct BackupConfig {
+    /// スナップショットを置くディレクトリ
+    pub dir: PathBuf,
+    /// 1 日 1 回自動で作る
+    pub daily: bool,
+    /// 自動で作ったあとに残す数（0 なら消さない）
+    pub keep: usize,
+}
+
+impl BackupConfig {
+    /// `--backup-dir` がなければ DB の隣（"db" → "db-backups"）
+    pub fn new(db_path: &Path, dir: Option<PathBuf>, daily: bool, keep: usize) -> Self {
+        let dir = dir.unwrap_or_else(|| {
+            let mut s = db_path.components().collect::<PathBuf>().into_os_string();
+            s.push("-backups");
+            PathBuf::from(s)
+        });
+        Self { dir, daily, keep }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct Snapshot {
+    pub name: String,
+    /// 作った時刻（RFC 3339、UTC）
+    pub created: String,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// "2026-10-16T12:50:34Z" → "2026-10-16T125034Z"
+pub fn snapshot_name(timestamp: &str) -> String {
+    timestamp.replace(':', "")
+}
+
+/// スナップショットの名前か（"2026-10-16T125034Z"、同じ秒に作ったものは "-2" などが付く）
+fn is_snapshot_name(name: &str) -> bool {
+    name.len() >= 18
+        && name.as_bytes()[..4].iter().all(u8::is_ascii_digit)
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// 名前から作った時刻（"2026-10-16T125034Z" → "2026-10-16T12:50:34Z"）
+pub fn created_of(name: &str) -> String {
+    match (name.get(..13), name.get(13..15), name.get(15..17)) {
+        (Some(day_hour), Some(min), Some(sec)) => format!("{}:{}:{}Z", day_hour, min, sec),
+        _ => String::new(),
+    }
+}
+
+/// 比べられるよう実体のパスにする（まだないものはそのまま）
+fn real_path(p: &Path) -> PathBuf {
+    fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf())
+}
+
+/// `from` の下を `to` に写す（`skip` は写さない）。写したファイルの数と大きさ
+fn copy_dir(from: &Path, to: &Path, skip: &Path) -> io::Result<(u64, u64)> {
+    fs::create_dir_all(to)?;
+    let (mut files, mut bytes) = (0, 0);
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        if real_path(&path) == skip || entry.file_name() == GIT_DIR {
+            continue;
+        }
+        let kind = entry.file_type()?;
+        if kind.is_dir() {
+            let (f, b) = copy_dir(&path, &to.join(entry.file_name()), skip)?;
+            files += f;
+            bytes += b;
+        } else if kind.is_file() {
+            bytes += fs::copy(&path, to.join(entry.file_name()))?;
+            files += 1;
+        }
+    }
+    Ok((files, bytes))
+}
+
+/// ファイルの数と大きさ
+pub fn dir_size(dir: &Path) -> io::Result<(u64, u64)> {
+    let (mut files, mut bytes) = (0, 0);
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let kind = entry.file_type()?;
+        if kind.is_dir() {
+            let (f, b) = dir_size(&entry.path())?;
+            files += f;
+            bytes += b;
+        } else if kind.is_file() {
+            files += 1;
+            bytes += entry.metadata()?.len();
+        }
+    }
+    Ok((files, bytes))
+}
+
+/// スナップショットを作る。書きかけのものが一覧に出ないよう、".xxx.partial" に写してから名前を変える
+pub fn create_snapshot(db_path: &Path, cfg: &BackupConfig) -> io::Result<Snapshot> {
+    fs::create_dir_all(&cfg.dir)?;
+    let base = snapshot_name(&now_timestamp());
+    let name = std::iter::once(base.clone())
+        .chain((2..100).map(|n| format!("{}-{}", base, n)))
+        .find(|n| !cfg.dir.join(n).exists())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::AlreadyExists, "too many snapshots in one second"))?;
+    let partial = cfg.dir.join(format!(".{}.partial", name));
+    let copied = copy_dir(db_path, &partial, &real_path(&cfg.dir)).and_then(|sizes| {
+        fs::rename(&partial, cfg.dir.join(&name))?;
+        Ok(sizes)
+    });
+    match copied {
+        Ok((files, bytes)) => Ok(Snapshot { created: created_of(&name), name, files, bytes }),
+        Err(e) => {
+            let _ = fs::remove_dir_all(&partial);
+            Err(e)
+        }
+    }
+}
+
+/// スナップショットの一覧（新しい順）
+pub fn list_snapshots(dir: &Path) -> io::Result<Vec<Snapshot>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut list = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !entry.file_type()?.is_dir() || !is_snapshot_name(&name) {
+            continue;
+        }
+        let (files, bytes) = dir_size(&entry.path())?;
+        list.push(Snapshot { created: created_of(&name), name, files, bytes });
+    }
+    list.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(list)
+}
+
+/// 新しい `keep` 個を残して消す。消した名前
+pub fn prune(dir: &Path, keep: usize) -> io::Result<Vec<String>> {
+    let removed = remove_snapshots(dir, |list| list.iter().skip(keep).map(|s| s.name.clone()).collect())?;
+    Ok(removed.into_iter().map(|s| s.name).collect())
+}
+
+/// 一覧（新しい順）から `select` で選んだ名前のスナップショットを消す。消したもの
+pub fn remove_snapshots(dir: &Path, select: impl FnOnce(&[Snapshot]) -> Vec<String>) -> io::Result<Vec<Snapshot>> {
+    let list = list_snapshots(dir)?;
+    let names = select(&list);
+    let mut removed = Vec::new();
+    for s in list.into_iter().filter(|s| names.contains(&s.name)) {
+        fs::remove_dir_all(dir.join(&s.name))?;
+        removed.push(s);
+    }
+    Ok(removed)
+}
+
+/// 復元で今のものを残すファイルか（`rel` は DB からの相対パス）
+fn keep_on_restore(rel: &Path) -> bool {
+    KEEP_ON_RESTORE.iter().any(|k| rel == Path::new(CONFIG_DIR).join(k))
+}
+
+/// `to` の中身を `from` と同じにする（`rel` は DB からの相対パス）。`keep_on_restore` のものと `skip` には触らない
+fn mirror_dir(from: &Path, to: &Path, rel: &Path, skip: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let kind = entry.file_type()?;
+        if name == GIT_DIR {
+            continue;
+        }
+        if kind.is_dir() {
+            mirror_dir(&entry.path(), &to.join(&name), &rel.join(&name), skip)?;
+        } else if kind.is_file() && !keep_on_restore(&rel.join(&name)) {
+            fs::copy(entry.path(), to.join(&name))?;
+        }
+    }
+    // スナップショットにないものを消す
+    for entry in fs::read_dir(to)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name();
+        if real_path(&path) == skip || name == GIT_DIR || keep_on_restore(&rel.join(&name)) || from.join(&name).exists() {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            fs::remove_dir_all(&path)?;
+        } else {
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// `name` のスナップショットに戻す。先に今の状態のスナップショットを作り、それを返す
+pub fn restore_snapshot(db_path: &Path, cfg: &BackupConfig, name: &str) -> Result<Snapshot, (StatusCode, String)> {
+    let source = cfg.dir.join(name);
+    if !is_snapshot_name(name) || !source.is_dir() {
+        return Err((StatusCode::NOT_FOUND, format!("no snapshot named {}", name)));
+    }
+    let before = create_snapshot(db_path, cfg)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("could not back up before restoring: {}", e)))?;
+    mirror_dir(&source, db_path, Path::new(""), &real_path(&cfg.dir))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{} (the state before restoring is in {})", e, before.name)))?;
+    Ok(before)
+}
+
+/// ほかのバックアップ・復元が終わるまで断る
+pub(crate) fn with_lock<T>(f: impl FnOnce() -> Result<T, (StatusCode, String)>) -> Result<T, (StatusCode, String)> {
+    if BUSY.swap(true, Ordering::SeqCst) {
+        return Err((StatusCode::CONFLICT, "another backup or restore is running".into()));
+    }
+    /// 終わったら（途中で panic しても）BUSY を戻す
+    struct Release;
+    impl Drop for Release {
+        fn drop(&mut self) {
+            BUSY.store(false, Ordering::SeqCst);
+        }
+    }
+    let _release = Release;
+    f()
+}
+
+fn io_err(e: io::Error) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+/// spawn_blocking で動かして JSON で返す
+async fn run_blocking<T: serde::Serialize + Send + 'static>(
+    f: impl FnOnce() -> Result<T, (StatusCode, String)> + Send + 'static,
+) -> Response {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(Ok(v)) => (StatusCode::OK, Json(v)).into_response(),
+        Ok(Err((status, e))) => error(status, e),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// `POST /api/backup`
+#[utoipa::path(post, path = "/api/backup", tag = "backup", summary = "スナップショットを作る")]
+pub async fn create(State(state): State<AppState>) -> Response {
+    run_blocking(move || with_lock(|| create_snapshot(&state.db_path, &state.backup).map_err(io_err))).await
+}
+
+/// `GET /api/backups`
+#[utoipa::path(
+    get,
+    path = "/api/backups",
+    tag = "backup",
+    summary = "スナップショットの一覧",
+    responses((status = 200, description = "新しい順", body = Vec<Snapshot>)),
+)]
+pub async fn list(State(state): State<AppState>) -> Response {
+    run_blocking(move || {
+        let snapshots = list_snapshots(&state.backup.dir).map_err(io_err)?;
+        Ok(serde_json::json!({"dir": state.backup.dir, "daily": state.backup.daily, "keep": state.backup.keep, "snapshots": snapshots}))
+    })
+    .await
+}
+
+/// `DELETE /api/backups/{name}`
+#[utoipa::path(delete, path = "/api/backups/{name}", tag = "backup", summary = "スナップショットを消す")]
+pub async fn delete(State(state): State<AppState>, UrlPath(name): UrlPath<String>) -> Response {
+    run_blocking(move || {
+        with_lock(|| {
+            let path = state.backup.dir.join(&name);
+            if !is_snapshot_name(&name) || !path.is_dir() {
+                return Err((StatusCode::NOT_FOUND, format!("no snapshot named {}", name)));
+            }
+            fs::remove_dir_all(&path).map_err(io_err)?;
+            Ok(serde_json::json!({"ok": true}))
+        })
+    })
+    .await
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct PruneBody {
+    /// 残す数（1 以上）
+    keep: usize,
+}
+
+/// `POST /api/backups/prune`
+#[utoipa::path(post, path = "/api/backups/prune", tag = "backup", summary = "新しい N 個を残して消す", request_body = PruneBody)]
+pub async fn prune_snapshots(State(state): State<AppState>, Json(body): Json<PruneBody>) -> Response {
+    if body.keep == 0 {
+        return field_error("keep", "must be at least 1");
+    }
+    run_blocking(move || {
+        with_lock(|| {
+            let removed = prune(&state.backup.dir, body.keep).map_err(io_err)?;
+            Ok(serde_json::json!({"removed": removed}))
+        })
+    })
+    .await
+}
+
+/// `POST /api/restore/{name}`
+#[utoipa::path(post, path = "/api/restore/{name}", tag = "backup", summary = "スナップショットに戻す")]
+pub async fn restore(State(state): State<AppState>, UrlPath(name): UrlPath<String>) -> Response {
+    run_blocking(move || {
+        let before = with_lock(|| restore_snapshot(&state.db_path, &state.backup, &name))?;
+        // 一覧は読み直し、設定（時差・ラベルのテンプレート）は戻したものを使う
+        state.records.rescan_all();
+        if let Ok(s) = crate::settings::load_settings(&state.db_path) {
+            crate::settings::apply(&s);
+        }
+        Ok(serde_json::json!({"restored": name, "before_restore": before}))
+    })
+    .await
+}
+
+/// `daily` なら、1 時間ごとにその日のスナップショットがあるか確かめ、なければ作って古いものを消す
+pub fn spawn_scheduler(state: AppState) {
+    if !state.backup.daily {
+        return;
+    }
+    tokio::spawn(async move {
+        loop {
+            let state_now = state.clone();
+            let res = tokio::task::spawn_blocking(move || {
+                let today = snapshot_name(&now_timestamp())[..10].to_string();
+                let cfg = &state_now.backup;
+                if list_snapshots(&cfg.dir).map_err(io_err)?.iter().any(|s| s.name.starts_with(&today)) {
+                    return Ok(None);
+                }
+                let snapshot = with_lock(|| create_snapshot(&state_now.db_path, cfg).map_err(io_err))?;
+                if cfg.keep > 0 {
+                    with_lock(|| prune(&cfg.dir, cfg.keep).map_err(io_err))?;
+                }
+                Ok::<_, (StatusCode, String)>(Some(snapshot))
+            })
+            .await;
+            match res {
+                Ok(Ok(Some(s))) => println!("バックアップを作りました: {} （{} ファイル）", s.name, s.files),
+                Ok(Ok(None)) => {}
+                Ok(Err((_, e))) => eprintln!("自動バックアップに失敗しました: {}", e),
+                Err(e) => eprintln!("自動バックアップに失敗しました: {}", e),
+            }
+            tokio::time::sleep(SCHEDULER_TICK).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod backup_tests {
+    use super::{create_snapshot, created_of, is_snapshot_name, list_snapshots, prune, restore_snapshot, with_lock, BackupConfig};
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn snapshot_names() {
+        assert!(is_snapshot_name("2026-10-16T125034Z"));
+        assert!(is_snapshot_name("2026-10-16T125034Z-2"));
+        assert!(!is_snapshot_name(".2026-10-16T125034Z.partial"));
+        assert!(!is_snapshot_name("../2026-10-16T125034Z"));
+        assert_eq!(created_of("2026-10-16T125034Z-2"), "2026-10-16T12:50:34Z");
+        assert_eq!(BackupConfig::new(Path::new("data/db/"), None, false, 0).dir, Path::new("data/db-backups"));
+    }
+
+    #[test]
+    fn backup_prune_and_restore() {
+        let root = tempfile::tempdir().unwrap();
+        let db = root.path().join("db");
+        fs::create_dir_all(db.join(".config")).unwrap();
+        fs::write(db.join("a.json"), "{\"title\":\"A\"}").unwrap();
+        fs::write(db.join(".config/settings.json"), "{}").unwrap();
+        fs::write(db.join(".config/tokens.json"), "[1]").unwrap();
+        // バックアップ先が DB の中でも、自分自身は写さない
+        let cfg = BackupConfig::new(&db, Some(db.join("backups")), false, 0);
+        let first = create_snapshot(&db, &cfg).unwrap();
+        assert_eq!(first.files, 3);
+
+        fs::write(db.join("a.json"), "{\"title\":\"changed\"}").unwrap();
+        fs::write(db.join("b.json"), "{}").unwrap();
+        fs::write(db.join(".config/tokens.json"), "[2]").unwrap();
+        let before = restore_snapshot(&db, &cfg, &first.name).unwrap();
+        assert_eq!(fs::read_to_string(db.join("a.json")).unwrap(), "{\"title\":\"A\"}");
+        assert!(!db.join("b.json").exists());
+        assert_eq!(fs::read_to_string(db.join(".config/tokens.json")).unwrap(), "[2]");
+        assert!(db.join("backups").join(&before.name).join("b.json").exists());
+
+        assert_eq!(list_snapshots(&cfg.dir).unwrap().len(), 2);
+        assert_eq!(prune(&cfg.dir, 1).unwrap(), vec![first.name.clone()]);
+        assert_eq!(list_snapshots(&cfg.dir).unwrap()[0].name, before.name);
+        assert!(restore_snapshot(&db, &cfg, "../db").is_err());
+    }
+
+    #[test]
+    fn a_panic_does_not_leave_the_lock_held() {
+        let panicked = std::panic::catch_unwind(|| with_lock::<()>(|| panic!("boom")));
+        assert!(panicked.is_err());
+        assert_eq!(with_lock(|| Ok(1)), Ok(1));
+    }
+}
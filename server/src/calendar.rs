@@ -0,0 +1,165 @@
+//! 今日のカレンダー（`GET /api/calendar?date=YYYY/MM/DD`）。「今日なにを聴くか」のきっかけ用。
+//! release_year / record_year は年しかないので、発売・録音からちょうど 10 年刻み（と 25・75 周年）のものを周年として出す。
+//! 日付まで分かるもの（登録日 `date` と再生記録 `listens`）は、過去の同じ日・同じ月のものを出す。
+
+use axum::{
+    extract::Query,
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::Value;
+
+use crate::date::{self, Date};
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::{error, field_error};
+use crate::stats::date_from_system_time;
+use crate::{display_label_from_value, int_from_value, load_all_records};
+
+/// 「今月」の一覧の上限（古い順）
+const THIS_MONTH_MAX: usize = 30;
+
+#[derive(serde::Deserialize)]
+pub struct CalendarParams {
+    /// 基準日（省略時は今日、UTC）
+    #[serde(default)]
+    date: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum CalendarKind {
+    Released,
+    Recorded,
+    Added,
+    Listened,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct CalendarEntry {
+    kind: CalendarKind,
+    filename: String,
+    display_label: String,
+    /// 周年は年（"1959"）、それ以外は日付（"2016/10/16"）
+    when: String,
+    /// 何年前か
+    years: i64,
+}
+
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+struct Calendar {
+    date: String,
+    /// 発売・録音の周年（周年の大きい順）
+    anniversaries: Vec<CalendarEntry>,
+    /// 過去の同じ日に登録した・聴いたもの（古い順）
+    on_this_day: Vec<CalendarEntry>,
+    /// 過去の同じ月（同じ日以外）に登録した・聴いたもの（古い順）
+    this_month: Vec<CalendarEntry>,
+}
+
+/// 祝う周年か（10 年刻みと 25・75 周年）。`year` は発売・録音年（0 は未入力）。
+fn is_anniversary(year: i64, today_year: i64) -> bool {
+    let years = today_year - year;
+    year > 0 && years > 0 && (years % 10 == 0 || years % 25 == 0)
+}
+
+fn collect_calendar(records: &[(String, Value)], date: &str) -> Calendar {
+    let Some(Date { year, month, day }) = Date::parse(date) else {
+        return Calendar::default();
+    };
+    let mut cal = Calendar { date: date.to_string(), ..Default::default() };
+    for (filename, v) in records {
+        let entry = |kind, when: String, years| CalendarEntry {
+            kind,
+            filename: filename.clone(),
+            display_label: display_label_from_value(v),
+            when,
+            years,
+        };
+        if let Some(y) = int_from_value(&v["release_year"]).filter(|y| is_anniversary(*y, year)) {
+            cal.anniversaries.push(entry(CalendarKind::Released, y.to_string(), year - y));
+        }
+        // 複数年にまたがる録音は年ごとに出す（発売と同じ年は発売だけにする）
+        let mut recorded: Vec<i64> = v["record_year"].as_array().into_iter().flatten().filter_map(int_from_value).collect();
+        recorded.sort_unstable();
+        recorded.dedup();
+        for y in recorded {
+            if is_anniversary(y, year) && Some(y) != int_from_value(&v["release_year"]) {
+                cal.anniversaries.push(entry(CalendarKind::Recorded, y.to_string(), year - y));
+            }
+        }
+
+        let added = v["date"].as_str().map(|d| (CalendarKind::Added, d));
+        let listens = v["listens"].as_array().into_iter().flatten().filter_map(Value::as_str).map(|d| (CalendarKind::Listened, d));
+        for (kind, d) in added.into_iter().chain(listens) {
+            let Some(Date { year: y, month: m, day: dd }) = Date::parse(d) else {
+                continue;
+            };
+            if y >= year || m != month {
+                continue;
+            }
+            let e = entry(kind, d.to_string(), year - y);
+            if dd == day {
+                cal.on_this_day.push(e);
+            } else {
+                cal.this_month.push(e);
+            }
+        }
+    }
+    cal.anniversaries
+        .sort_by(|a, b| b.years.cmp(&a.years).then_with(|| a.display_label.cmp(&b.display_label)));
+    for list in [&mut cal.on_this_day, &mut cal.this_month] {
+        list.sort_by(|a, b| a.when.cmp(&b.when).then_with(|| a.display_label.cmp(&b.display_label)));
+    }
+    cal.this_month.truncate(THIS_MONTH_MAX);
+    cal
+}
+
+/// `GET /api/calendar?date=YYYY/MM/DD`
+#[utoipa::path(get, path = "/api/calendar", tag = "reports", summary = "今日のカレンダー", params(LibraryQuery))]
+pub async fn get_calendar(Lib(library): Lib, Query(params): Query<CalendarParams>) -> impl IntoResponse {
+    let date = match params.date {
+        Some(d) if !date::is_valid(&d) => {
+            return field_error("date", "must be YYYY/MM/DD")
+        }
+        Some(d) => d,
+        None => date_from_system_time(std::time::SystemTime::now()).unwrap_or_default(),
+    };
+    let Some(records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    (StatusCode::OK, Json(collect_calendar(&records, &date))).into_response()
+}
+
+#[cfg(test)]
+mod calendar_tests {
+    use super::{collect_calendar, CalendarKind};
+    use serde_json::json;
+
+    #[test]
+    fn anniversaries_and_same_day_entries() {
+        let records = vec![
+            (
+                "a.json".to_string(),
+                json!({"title": "Kind of Blue", "release_year": 1959, "record_year": [1959],
+                       "personnel": {"leader": [{"name": "Miles Davis"}]},
+                       "date": "2020/10/16", "listens": ["2024/10/03", "2026/10/16"]}),
+            ),
+            (
+                "b.json".to_string(),
+                json!({"title": "Moanin'", "release_year": 1959, "record_year": [1956, 1958],
+                       "personnel": {"leader": [{"name": "Art Blakey"}]}, "date": "2021/03/01"}),
+            ),
+        ];
+        let cal = collect_calendar(&records, "2026/10/16");
+        let kinds: Vec<(CalendarKind, &str, i64)> =
+            cal.anniversaries.iter().map(|e| (e.kind, e.when.as_str(), e.years)).collect();
+        // 1959 年は 67 周年なので出ない、1956 年録音は 70 周年
+        assert_eq!(kinds, vec![(CalendarKind::Recorded, "1956", 70)]);
+        assert_eq!(cal.on_this_day.len(), 1);
+        assert_eq!((cal.on_this_day[0].kind, cal.on_this_day[0].years), (CalendarKind::Added, 6));
+        assert_eq!(cal.this_month.len(), 1);
+        assert_eq!(cal.this_month[0].when, "2024/10/03");
+        assert_eq!(collect_calendar(&records, "bad"), Default::default());
+    }
+}
@@ -0,0 +1,71 @@
+//! フロント（WASM）で起きたパニックの報告を受け取り、`{DB_PATH}/.config/client-errors.jsonl` に追記する。
+//! 送るかどうかは設定の `report_client_errors` でフロント側が決める。
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+use crate::problem::error;
+use crate::settings::{config_path, CONFIG_DIR};
+use crate::stats::now_timestamp;
+use crate::AppState;
+
+pub const LOG_FILE: &str = "client-errors.jsonl";
+/// 1 項目あたりの最大文字数（スタックトレースなどが巨大でもログが膨れないように）
+const FIELD_MAX_CHARS: usize = 4000;
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ClientError {
+    pub message: String,
+    /// パニックした場所（"src/form.rs:123:45"）
+    #[serde(default)]
+    pub location: String,
+    /// 直前の操作（"open" / "save" など）
+    #[serde(default)]
+    pub last_action: String,
+    /// 編集中のレコード（"xxx.json"）
+    #[serde(default)]
+    pub record: String,
+    #[serde(default)]
+    pub view: String,
+    #[serde(default)]
+    pub version: String,
+}
+
+fn truncate(s: &mut String) {
+    if let Some((i, _)) = s.char_indices().nth(FIELD_MAX_CHARS) {
+        s.truncate(i);
+    }
+}
+
+/// `POST /api/client-errors`
+/// navigator.sendBeacon で送られてくるため Content-Type は text/plain のことがある。本文を JSON として読む。
+#[utoipa::path(post, path = "/api/client-errors", tag = "settings", summary = "フロントのエラーを報告する", request_body = serde_json::Value)]
+pub async fn report(State(state): State<AppState>, body: String) -> impl IntoResponse {
+    let mut e: ClientError = match serde_json::from_str(&body) {
+        Ok(e) => e,
+        Err(err) => {
+            return error(StatusCode::BAD_REQUEST, format!("invalid json: {}", err));
+        }
+    };
+    for field in [&mut e.message, &mut e.location, &mut e.last_action, &mut e.record, &mut e.view, &mut e.version] {
+        truncate(field);
+    }
+    eprintln!("client error: {} at {} (action: {}, record: {})", e.message, e.location, e.last_action, e.record);
+    let mut line = serde_json::json!({"received": now_timestamp()});
+    if let (Some(obj), Ok(serde_json::Value::Object(fields))) = (line.as_object_mut(), serde_json::to_value(&e)) {
+        obj.extend(fields);
+    }
+    let res = fs::create_dir_all(state.db_path.join(CONFIG_DIR))
+        .and_then(|_| {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(config_path(&state.db_path, LOG_FILE))
+        })
+        .and_then(|mut f| writeln!(f, "{}", line));
+    match res {
+        Ok(()) => (StatusCode::OK, Json(serde_json::json!({"ok": true}))).into_response(),
+        Err(err) => error(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
@@ -0,0 +1,47 @@
+//! レスポンスの圧縮（gzip / brotli、`Accept-Encoding` で選ぶ）。一覧（`/api/list-with-labels`）やレコードの JSON、
+//! `dist/` の wasm・JS はよく縮むので、遅い回線でも早く開けるよう API と静的ファイルの両方にかける。
+//! 小さなものと、もともと縮んでいるもの（画像・PDF・ZIP・音声）はそのまま返す。
+
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+
+/// これより小さいものは縮めない（ヘッダーのほうが大きくなる）
+const MIN_SIZE: u16 = 1024;
+
+/// 圧縮のレイヤー
+pub fn layer() -> CompressionLayer<impl Predicate> {
+    let predicate = SizeAbove::new(MIN_SIZE)
+        .and(NotForContentType::GRPC)
+        .and(NotForContentType::IMAGES)
+        .and(NotForContentType::SSE)
+        .and(NotForContentType::const_new(crate::attachments::PDF_TYPE))
+        .and(NotForContentType::const_new("application/zip"))
+        .and(NotForContentType::const_new("audio/"));
+    CompressionLayer::new().gzip(true).br(true).compress_when(predicate)
+}
+
+#[cfg(test)]
+mod compression_tests {
+    use axum::{body::Body, http::header, http::Request, routing::get, Router};
+    use tower::ServiceExt;
+
+    async fn encoding(path: &str, accept: &str) -> Option<String> {
+        let app = Router::new()
+            .route("/json", get(|| async { axum::Json(vec!["Bill Evans: Alone"; 200]) }))
+            .route("/small", get(|| async { axum::Json(vec!["Bill Evans: Alone"]) }))
+            .route("/zip", get(|| async { ([(header::CONTENT_TYPE, "application/zip")], vec![0u8; 4096]) }))
+            .layer(super::layer());
+        let req = Request::get(path).header(header::ACCEPT_ENCODING, accept).body(Body::empty()).unwrap();
+        let res = app.oneshot(req).await.unwrap();
+        res.headers().get(header::CONTENT_ENCODING).map(|v| v.to_str().unwrap().to_string())
+    }
+
+    #[tokio::test]
+    async fn large_json_is_compressed_but_small_and_zip_are_not() {
+        assert_eq!(encoding("/json", "gzip").await.as_deref(), Some("gzip"));
+        assert_eq!(encoding("/json", "br;q=1.0, gzip;q=0.5").await.as_deref(), Some("br"));
+        assert_eq!(encoding("/json", "identity").await, None);
+        assert_eq!(encoding("/small", "gzip").await, None);
+        assert_eq!(encoding("/zip", "gzip").await, None);
+    }
+}
@@ -0,0 +1,58 @@
+//! CORS。既定ではどのオリジンにも許さない（フロントはこのサーバーから配信するので同じオリジンで足りる）。
+//! ほかのオリジンのページ（別ポートで動かすツールなど）から API を呼ぶときは `--cors-origin https://tools.example.com`
+//! （環境変数 `CORS_ORIGINS` ならカンマ区切り）で足す。認証は `Authorization: Bearer` で、セッション Cookie は送らせない。
+//! `--cors-permissive`（`CORS_PERMISSIVE`）はどのオリジン・メソッド・ヘッダーも許す開発用。
+
+use axum::http::{header, HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+/// `--cors-origin` を読む。"https://example.com:8443" のようにスキーム・ホスト・ポートだけ（末尾の / は落とす）
+pub fn parse_origin(s: &str) -> Result<String, String> {
+    let origin = s.trim().trim_end_matches('/');
+    let rest = origin
+        .strip_prefix("http://")
+        .or_else(|| origin.strip_prefix("https://"))
+        .ok_or_else(|| format!("origin must start with http:// or https://: {}", s))?;
+    if rest.is_empty() || rest.contains(['/', '?', '#', '*']) || rest.contains(char::is_whitespace) {
+        return Err(format!("origin must be scheme://host[:port] without a path: {}", s));
+    }
+    HeaderValue::from_str(origin).map_err(|_| format!("invalid origin: {}", s))?;
+    Ok(origin.to_ascii_lowercase())
+}
+
+/// 設定から CORS のレイヤーを作る
+pub fn layer(origins: &[String], permissive: bool) -> CorsLayer {
+    if permissive {
+        return CorsLayer::new().allow_origin(Any).allow_methods(Any).allow_headers(Any).expose_headers(Any);
+    }
+    let origins: Vec<HeaderValue> = origins.iter().filter_map(|o| HeaderValue::from_str(o).ok()).collect();
+    if origins.is_empty() {
+        return CorsLayer::new();
+    }
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::DELETE])
+        .allow_headers([
+            header::CONTENT_TYPE,
+            header::AUTHORIZATION,
+            header::IF_MATCH,
+            header::IF_NONE_MATCH,
+            HeaderName::from_static(crate::idempotency::HEADER),
+        ])
+        .expose_headers([header::ETAG])
+}
+
+#[cfg(test)]
+mod cors_tests {
+    use super::parse_origin;
+
+    #[test]
+    fn origins_are_scheme_host_and_port() {
+        assert_eq!(parse_origin(" https://Tools.example.com:8443/ ").unwrap(), "https://tools.example.com:8443");
+        assert_eq!(parse_origin("http://localhost:8080").unwrap(), "http://localhost:8080");
+        assert!(parse_origin("tools.example.com").is_err());
+        assert!(parse_origin("https://example.com/app").is_err());
+        assert!(parse_origin("https://*.example.com").is_err());
+        assert!(parse_origin("https://").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+//! カバー画像。`{ライブラリのディレクトリ}/covers/` に "{レコードのファイル名}.{拡張子}" で保存し、
+//! レコード JSON の `cover` にそのファイル名を入れる。
+
+use axum::{
+    extract::{Multipart, Path},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+
+use crate::libraries::{Lib, Library, LibraryQuery};
+use crate::normalize_filename;
+use crate::problem::error;
+use crate::storage::write_atomic;
+use crate::versions::{self, WriteError};
+
+/// カバー画像を置くディレクトリ（ライブラリのディレクトリからの相対）
+pub const COVERS_DIR: &str = "covers";
+
+/// アップロードを受け付ける拡張子と Content-Type
+const IMAGE_TYPES: [(&str, &str); 5] = [
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("png", "image/png"),
+    ("webp", "image/webp"),
+    ("gif", "image/gif"),
+];
+
+pub(crate) fn content_type_for(ext: &str) -> Option<&'static str> {
+    let ext = ext.to_ascii_lowercase();
+    IMAGE_TYPES.iter().find(|(e, _)| *e == ext).map(|(_, t)| *t)
+}
+
+/// 置いてあるカバー画像のファイル名（完成度の「カバーがあるか」に使う。レコードごとに stat しないよう一度に読む）
+#[derive(Debug, Default)]
+pub struct CoverFiles(HashSet<String>);
+
+impl CoverFiles {
+    /// レコードの `cover` のファイルが置いてあるか
+    pub fn contains_cover_of(&self, v: &Value) -> bool {
+        v["cover"].as_str().is_some_and(|c| !c.trim().is_empty() && self.0.contains(c))
+    }
+}
+
+/// ライブラリの `covers/` のファイルを読む（ディレクトリがなければ空）
+pub fn cover_files(library_dir: &std::path::Path) -> CoverFiles {
+    let names = fs::read_dir(library_dir.join(COVERS_DIR))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| e.file_name().into_string().ok())
+        .collect();
+    CoverFiles(names)
+}
+
+/// `POST /api/covers` multipart（`filename`: 対象レコード, `file`: 画像）でカバーを登録する。
+#[utoipa::path(
+    post,
+    path = "/api/covers",
+    tag = "media",
+    summary = "カバー画像を上げる",
+    params(LibraryQuery),
+    request_body(content = crate::openapi::FileUpload, content_type = "multipart/form-data"),
+)]
+pub async fn upload_cover(Lib(library): Lib, mut multipart: Multipart) -> impl IntoResponse {
+    let mut filename = None;
+    let mut image = None;
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(f)) => f,
+            Ok(None) => break,
+            Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+        };
+        match field.name() {
+            Some("filename") => filename = field.text().await.ok(),
+            Some("file") => {
+                let ext = field
+                    .file_name()
+                    .and_then(|n| n.rsplit_once('.'))
+                    .map(|(_, e)| e.to_ascii_lowercase())
+                    .unwrap_or_default();
+                match field.bytes().await {
+                    Ok(b) => image = Some((ext, b)),
+                    Err(e) => return error(StatusCode::BAD_REQUEST, e.to_string()),
+                }
+            }
+            _ => {}
+        }
+    }
+    let Some(filename) = filename.as_deref().and_then(normalize_filename) else {
+        return error(StatusCode::BAD_REQUEST, "invalid filename");
+    };
+    let Some((ext, bytes)) = image else {
+        return error(StatusCode::BAD_REQUEST, "file is required");
+    };
+    if content_type_for(&ext).is_none() {
+        return error(StatusCode::BAD_REQUEST, "jpg / png / webp / gif のみ対応しています");
+    }
+    match save_cover(&library, &filename, &ext, &bytes) {
+        Ok(cover) => {
+            (StatusCode::OK, Json(serde_json::json!({"ok": true, "filename": filename, "cover": cover}))).into_response()
+        }
+        Err((status, e)) => error(status, e),
+    }
+}
+
+/// 画像を "{レコードのファイル名}.{拡張子}" で置き、レコードの `cover` に入れる。カバーのファイル名を返す
+pub(crate) fn save_cover(library: &Library, filename: &str, ext: &str, bytes: &[u8]) -> Result<String, (StatusCode, String)> {
+    let covers_dir = library.dir.join(COVERS_DIR);
+    fs::create_dir_all(&covers_dir).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let stem = filename.trim_end_matches(".json");
+    let cover = format!("{}.{}", stem, ext);
+    let failed = |e: std::io::Error| WriteError::Failed(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    versions::update(&*library.storage, filename, |v| {
+        // 拡張子の違う古いカバーは消しておく
+        if let Some(old) = v["cover"].as_str().filter(|old| *old != cover) {
+            let _ = fs::remove_file(covers_dir.join(old));
+        }
+        write_atomic(&covers_dir.join(&cover), bytes).map_err(failed)?;
+        v["cover"] = Value::String(cover.clone());
+        Ok(true)
+    })
+    .map_err(|e| (e.status(), e.to_string()))?;
+    Ok(cover)
+}
+
+/// `GET /api/covers/{name}` カバー画像を返す。
+#[utoipa::path(get, path = "/api/covers/{name}", tag = "media", summary = "カバー画像", params(LibraryQuery))]
+pub async fn get_cover(Lib(library): Lib, Path(name): Path<String>) -> impl IntoResponse {
+    if name.contains("..") || name.contains('/') || name.contains('\\') {
+        return error(StatusCode::BAD_REQUEST, "invalid path");
+    }
+    let Some(content_type) = name.rsplit_once('.').and_then(|(_, e)| content_type_for(e)) else {
+        return error(StatusCode::BAD_REQUEST, "unsupported image type");
+    };
+    match fs::read(library.dir.join(COVERS_DIR).join(&name)) {
+        Ok(bytes) => (StatusCode::OK, [(header::CONTENT_TYPE, content_type)], bytes).into_response(),
+        Err(e) => error(StatusCode::NOT_FOUND, format!("cover not found: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod covers_tests {
+    use super::{cover_files, COVERS_DIR};
+    use serde_json::json;
+
+    #[test]
+    fn covers_count_only_when_the_file_is_there() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!cover_files(dir.path()).contains_cover_of(&json!({"cover": "a.jpg"})));
+        std::fs::create_dir(dir.path().join(COVERS_DIR)).unwrap();
+        std::fs::write(dir.path().join(COVERS_DIR).join("a.jpg"), b"jpg").unwrap();
+        let covers = cover_files(dir.path());
+        assert!(covers.contains_cover_of(&json!({"cover": "a.jpg"})));
+        assert!(!covers.contains_cover_of(&json!({"cover": "b.jpg"})));
+        assert!(!covers.contains_cover_of(&json!({})));
+    }
+}
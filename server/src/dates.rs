@@ -0,0 +1,64 @@
+//! 更新日（`updated_date`）の移行。設定で更新日を付けるようにしたとき、まだないレコードにファイルの更新日を入れる。
+//! 保存時の `date` / `updated_date` の付け方はフロントが設定（`date_mode` / `track_updated_date`）に従って決める。
+
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use serde_json::Value;
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::load_all_records;
+use crate::problem::error;
+use crate::stats::date_from_system_time;
+use crate::storage::Storage;
+use crate::versions::{self, Precondition};
+
+/// updated_date がない（空の）レコードに入れる値。ファイルの更新日、読めなければ記録日。
+fn fill_value(v: &Value, modified: Option<String>) -> Option<String> {
+    if v["updated_date"].as_str().is_some_and(|d| !d.is_empty()) {
+        return None;
+    }
+    modified.or_else(|| v["date"].as_str().filter(|d| !d.is_empty()).map(String::from))
+}
+
+fn fill_updated_dates(storage: &dyn Storage) -> Result<usize, String> {
+    let records = load_all_records(storage).ok_or("cannot read db directory")?;
+    let mut updated = 0;
+    for (filename, mut v) in records {
+        let modified = storage.modified(&filename).ok().and_then(date_from_system_time);
+        let Some(date) = fill_value(&v, modified) else {
+            continue;
+        };
+        let read = Precondition::Version(versions::version_of(&v));
+        let Some(obj) = v.as_object_mut() else {
+            continue;
+        };
+        obj.insert("updated_date".into(), Value::String(date));
+        versions::write(storage, &filename, &read, &v).map_err(|e| format!("{}: {}", filename, e))?;
+        updated += 1;
+    }
+    Ok(updated)
+}
+
+/// `POST /api/dates/fill-updated`
+#[utoipa::path(post, path = "/api/dates/fill-updated", tag = "maintenance", summary = "更新日を埋める", params(LibraryQuery))]
+pub async fn fill_updated(Lib(library): Lib) -> impl IntoResponse {
+    match tokio::task::spawn_blocking(move || fill_updated_dates(&*library.storage)).await {
+        Ok(Ok(updated)) => (StatusCode::OK, Json(serde_json::json!({"updated": updated}))).into_response(),
+        Ok(Err(e)) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod dates_tests {
+    use super::fill_value;
+    use serde_json::json;
+
+    #[test]
+    fn only_missing_updated_dates_are_filled() {
+        let v = json!({"date": "2020/01/02"});
+        assert_eq!(fill_value(&v, Some("2024/05/06".into())).as_deref(), Some("2024/05/06"));
+        assert_eq!(fill_value(&v, None).as_deref(), Some("2020/01/02"));
+        assert_eq!(fill_value(&json!({"date": "2020/01/02", "updated_date": "2021/01/01"}), None), None);
+        assert_eq!(fill_value(&json!({"date": ""}), None), None);
+    }
+}
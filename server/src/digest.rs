@@ -0,0 +1,491 @@
+//! 週報。直近 7 日間（送る日の前日まで）に登録したレコード（`date`）・聴いた記録（`listens`）・スコアの高いものを
+//! Markdown にまとめ、設定した Webhook か SMTP に送る。設定は `{DB_PATH}/.config/digest.json`。
+//!
+//! - Webhook は `{"text": 本文, "digest": 集計}` を POST する（Slack・Mattermost の Incoming Webhook、Discord なら末尾に `/slack` を付けた URL）
+//! - SMTP は TLS・認証なしで渡すだけなので、同じマシンか LAN のメールサーバー（postfix など）を指定する
+//! - `weekday`（1 = 月曜 … 7 = 日曜）を決めると、その曜日（ライブラリの時差で）に 1 回送る。0 なら `POST /api/digest/send` だけ
+//!
+//! `GET /api/digest` 設定と前回の結果、`POST /api/digest` 設定の保存、`GET /api/digest/preview` 本文の下書き。
+//! 決まった曜日の週報は既定のライブラリのもの。`preview` と `send` は `?library=` でほかのライブラリの分も作れる。
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::date::Date;
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+use crate::settings::{load_config_json, save_config_json};
+use crate::stats::now_timestamp;
+use crate::storage::Storage;
+use crate::{display_label_from_value, load_all_records, score_from_value, AppState};
+
+const CONFIG_FILE: &str = "digest.json";
+const STATE_FILE: &str = "digest-state.json";
+/// Webhook・SMTP の待ち時間
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+/// 送る曜日になったか確かめる間隔
+const SCHEDULER_TICK: Duration = Duration::from_secs(30 * 60);
+/// まとめる日数
+const PERIOD_DAYS: i64 = 7;
+/// 「よく聴いたもの」「スコアの高いもの」に出す件数
+const TOP_N: usize = 5;
+
+/// 送っている最中か（同時に 2 つ走らせない）
+static SENDING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DigestConfig {
+    /// Webhook の URL。URL そのものが鍵なので画面には返さない
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub webhook: String,
+    /// SMTP サーバー（"localhost:25"）。空ならメールは送らない
+    #[serde(default)]
+    pub smtp_host: String,
+    #[serde(default)]
+    pub mail_from: String,
+    #[serde(default)]
+    pub mail_to: Vec<String>,
+    /// 送る曜日（1 = 月曜 … 7 = 日曜）。0 なら自動では送らない
+    #[serde(default)]
+    pub weekday: u8,
+}
+
+impl DigestConfig {
+    fn has_target(&self) -> bool {
+        !self.webhook.is_empty() || !self.smtp_host.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SendReport {
+    /// RFC 3339
+    pub at: String,
+    /// 週報の期間（YYYY/MM/DD〜YYYY/MM/DD）
+    pub period: String,
+    /// 送れた先（"webhook" / "smtp"）
+    pub sent: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct DigestState {
+    /// 自動で最後に送った日（YYYY/MM/DD、ライブラリの時差で）
+    #[serde(default)]
+    last_sent: Option<String>,
+    #[serde(default)]
+    last_report: Option<SendReport>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct Ranked {
+    pub display_label: String,
+    pub value: f64,
+}
+
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+pub struct Digest {
+    /// 期間（YYYY/MM/DD、両端を含む）
+    pub from: String,
+    pub to: String,
+    /// 登録したレコード（下書きは除く、記録日順）
+    pub added: Vec<String>,
+    pub listens: usize,
+    pub listened_records: usize,
+    pub most_listened: Vec<Ranked>,
+    /// 登録したか聴いたレコードのうちスコアの高いもの
+    pub top_scores: Vec<Ranked>,
+}
+
+fn in_period(d: &str, from: Date, to: Date) -> bool {
+    Date::parse(d).is_some_and(|d| (from..=to).contains(&d))
+}
+
+fn by_value_desc(list: &mut Vec<Ranked>) {
+    list.sort_by(|a, b| b.value.total_cmp(&a.value).then_with(|| a.display_label.cmp(&b.display_label)));
+    list.truncate(TOP_N);
+}
+
+/// `to` までの 7 日間をまとめる
+fn collect_digest(records: &[(String, Value)], to: Date) -> Digest {
+    let from = Date::from_days(to.days() - (PERIOD_DAYS - 1));
+    let mut digest = Digest { from: from.to_string(), to: to.to_string(), ..Default::default() };
+    let mut added: Vec<(Date, String)> = Vec::new();
+    let mut scored = Vec::new();
+    for (_, v) in records.iter().filter(|(_, v)| v["draft"].as_bool() != Some(true)) {
+        let label = display_label_from_value(v);
+        let is_added = v["date"].as_str().is_some_and(|d| in_period(d, from, to));
+        if is_added {
+            added.push((v["date"].as_str().and_then(Date::parse).unwrap_or(from), label.clone()));
+        }
+        let count = v["listens"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .filter(|d| in_period(d, from, to))
+            .count();
+        if count > 0 {
+            digest.listens += count;
+            digest.listened_records += 1;
+            digest.most_listened.push(Ranked { display_label: label.clone(), value: count as f64 });
+        }
+        if let Some(score) = score_from_value(v).filter(|s| *s > 0.0 && (is_added || count > 0)) {
+            scored.push(Ranked { display_label: label, value: score });
+        }
+    }
+    added.sort();
+    digest.added = added.into_iter().map(|(_, label)| label).collect();
+    by_value_desc(&mut digest.most_listened);
+    by_value_desc(&mut scored);
+    digest.top_scores = scored;
+    digest
+}
+
+fn render_markdown(d: &Digest) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# nekokan_music 週報（{}〜{}）\n", d.from, d.to);
+    let _ = writeln!(out, "- 登録したレコード: {} 枚", d.added.len());
+    let _ = writeln!(out, "- 聴いた回数: {} 回（{} 枚）", d.listens, d.listened_records);
+    if !d.added.is_empty() {
+        let _ = writeln!(out, "\n## 登録したレコード\n");
+        for label in &d.added {
+            let _ = writeln!(out, "- {}", label);
+        }
+    }
+    if !d.most_listened.is_empty() {
+        let _ = writeln!(out, "\n## よく聴いたもの\n");
+        for (i, e) in d.most_listened.iter().enumerate() {
+            let _ = writeln!(out, "{}. {} — {} 回", i + 1, e.display_label, e.value);
+        }
+    }
+    if !d.top_scores.is_empty() {
+        let _ = writeln!(out, "\n## スコアの高いもの\n");
+        for (i, e) in d.top_scores.iter().enumerate() {
+            let _ = writeln!(out, "{}. {} — {}", i + 1, e.display_label, e.value);
+        }
+    }
+    out
+}
+
+/// 今日（ライブラリの時差で）
+fn today() -> Date {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    Date::from_unix_secs(secs, crate::settings::library_offset())
+}
+
+/// 曜日（1 = 月曜 … 7 = 日曜）。1970/01/01 は木曜日。
+fn weekday_of(d: Date) -> u8 {
+    ((d.days() + 3).rem_euclid(7) + 1) as u8
+}
+
+/// 送る日の週報（前日までの 7 日間）
+fn build(storage: &dyn Storage, send_day: Date) -> Result<Digest, String> {
+    let records = load_all_records(storage).ok_or("レコードの一覧を読めません")?;
+    Ok(collect_digest(&records, Date::from_days(send_day.days() - 1)))
+}
+
+fn send_webhook(url: &str, text: &str, digest: &Digest) -> Result<(), String> {
+    match ureq::post(url)
+        .timeout(SEND_TIMEOUT)
+        .send_json(serde_json::json!({"text": text, "digest": digest}))
+    {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, _)) => Err(format!("webhook: {}", code)),
+        Err(e) => Err(format!("webhook: {}", e)),
+    }
+}
+
+/// メールの本文（ヘッダー込み、行末は CRLF、"." で始まる行は "." を重ねる）
+fn mail_message(from: &str, to: &[String], subject: &str, body: &str) -> String {
+    let mut out = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=UTF-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n",
+        from,
+        to.join(", "),
+        subject
+    );
+    for line in body.lines() {
+        if line.starts_with('.') {
+            out.push('.');
+        }
+        out.push_str(line);
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// 応答を読む（"250-..." が続く間は読み進める）。コードが `expect` で始まらなければ Err
+fn smtp_reply(reader: &mut impl BufRead, expect: char) -> Result<(), String> {
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).map_err(|e| format!("smtp: {}", e))? == 0 {
+            return Err("smtp: 接続が切れました".into());
+        }
+        if !line.starts_with(expect) {
+            return Err(format!("smtp: {}", line.trim_end()));
+        }
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok(());
+        }
+    }
+}
+
+fn send_mail(cfg: &DigestConfig, subject: &str, body: &str) -> Result<(), String> {
+    if cfg.mail_from.trim().is_empty() || cfg.mail_to.is_empty() {
+        return Err("smtp: 差出人と宛先を設定してください".into());
+    }
+    let addr = cfg
+        .smtp_host
+        .to_socket_addrs()
+        .map_err(|e| format!("smtp: {}: {}", cfg.smtp_host, e))?
+        .next()
+        .ok_or_else(|| format!("smtp: {} が見つかりません", cfg.smtp_host))?;
+    let stream = TcpStream::connect_timeout(&addr, SEND_TIMEOUT).map_err(|e| format!("smtp: {}", e))?;
+    stream.set_read_timeout(Some(SEND_TIMEOUT)).map_err(|e| e.to_string())?;
+    stream.set_write_timeout(Some(SEND_TIMEOUT)).map_err(|e| e.to_string())?;
+    let mut writer = stream.try_clone().map_err(|e| e.to_string())?;
+    let mut reader = BufReader::new(stream);
+    let mut command = |reader: &mut BufReader<TcpStream>, line: &str, expect: char| {
+        write!(writer, "{}\r\n", line).map_err(|e| format!("smtp: {}", e))?;
+        smtp_reply(reader, expect)
+    };
+    smtp_reply(&mut reader, '2')?;
+    command(&mut reader, "EHLO nekokan-music", '2')?;
+    command(&mut reader, &format!("MAIL FROM:<{}>", cfg.mail_from.trim()), '2')?;
+    for to in &cfg.mail_to {
+        command(&mut reader, &format!("RCPT TO:<{}>", to.trim()), '2')?;
+    }
+    command(&mut reader, "DATA", '3')?;
+    command(&mut reader, &format!("{}.", mail_message(&cfg.mail_from, &cfg.mail_to, subject, body)), '2')?;
+    let _ = command(&mut reader, "QUIT", '2');
+    Ok(())
+}
+
+/// 設定した先すべてに送る（ブロッキング）
+fn send(storage: &dyn Storage, cfg: &DigestConfig, send_day: Date) -> Result<SendReport, String> {
+    let digest = build(storage, send_day)?;
+    let text = render_markdown(&digest);
+    let mut report =
+        SendReport { at: now_timestamp(), period: format!("{}〜{}", digest.from, digest.to), ..Default::default() };
+    if !cfg.webhook.is_empty() {
+        match send_webhook(&cfg.webhook, &text, &digest) {
+            Ok(()) => report.sent.push("webhook".into()),
+            Err(e) => report.errors.push(e),
+        }
+    }
+    if !cfg.smtp_host.is_empty() {
+        // 件名は ASCII にしておく（エンコードしない MTA でも化けないように）
+        let subject = format!("nekokan_music weekly digest {} - {}", digest.from, digest.to);
+        match send_mail(cfg, &subject, &text) {
+            Ok(()) => report.sent.push("smtp".into()),
+            Err(e) => report.errors.push(e),
+        }
+    }
+    Ok(report)
+}
+
+/// 1 回送って結果を残す。`scheduled` なら送った日も覚える
+async fn run_send(state: AppState, storage: Arc<dyn Storage>, scheduled: Option<Date>) -> Result<SendReport, (StatusCode, String)> {
+    let cfg: DigestConfig = load_config_json(&state.db_path, CONFIG_FILE).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    if !cfg.has_target() {
+        return Err((StatusCode::BAD_REQUEST, "送り先（Webhook か SMTP）が設定されていません".into()));
+    }
+    if SENDING.swap(true, Ordering::SeqCst) {
+        return Err((StatusCode::CONFLICT, "週報を送っている最中です".into()));
+    }
+    let res = tokio::task::spawn_blocking(move || {
+        let report = send(&*storage, &cfg, scheduled.unwrap_or_else(today))?;
+        let mut digest_state: DigestState = load_config_json(&state.db_path, STATE_FILE)?;
+        if let Some(day) = scheduled {
+            digest_state.last_sent = Some(day.to_string());
+        }
+        digest_state.last_report = Some(report.clone());
+        save_config_json(&state.db_path, STATE_FILE, &digest_state)?;
+        Ok::<_, String>(report)
+    })
+    .await;
+    SENDING.store(false, Ordering::SeqCst);
+    match res {
+        Ok(Ok(report)) if report.sent.is_empty() => Err((StatusCode::BAD_GATEWAY, report.errors.join("、"))),
+        Ok(Ok(report)) => Ok(report),
+        Ok(Err(e)) => Err((StatusCode::INTERNAL_SERVER_ERROR, e)),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+/// 返すときは Webhook の URL を伏せる
+fn public_config(cfg: &DigestConfig) -> Value {
+    serde_json::json!({
+        "has_webhook": !cfg.webhook.is_empty(),
+        "smtp_host": cfg.smtp_host,
+        "mail_from": cfg.mail_from,
+        "mail_to": cfg.mail_to,
+        "weekday": cfg.weekday,
+    })
+}
+
+/// `GET /api/digest` 設定と前回の結果
+#[utoipa::path(get, path = "/api/digest", tag = "sync", summary = "週報の設定と前回の結果")]
+pub async fn get_status(State(state): State<AppState>) -> impl IntoResponse {
+    let (cfg, digest_state) = match (
+        load_config_json::<DigestConfig>(&state.db_path, CONFIG_FILE),
+        load_config_json::<DigestState>(&state.db_path, STATE_FILE),
+    ) {
+        (Ok(c), Ok(s)) => (c, s),
+        (Err(e), _) | (_, Err(e)) => return error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "config": public_config(&cfg),
+            "last_sent": digest_state.last_sent,
+            "last_report": digest_state.last_report,
+        })),
+    )
+        .into_response()
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[schema(as = DigestConfigBody)]
+pub struct ConfigBody {
+    /// None なら今の URL のまま、空文字なら消す
+    #[serde(default)]
+    webhook: Option<String>,
+    #[serde(default)]
+    smtp_host: String,
+    #[serde(default)]
+    mail_from: String,
+    #[serde(default)]
+    mail_to: Vec<String>,
+    #[serde(default)]
+    weekday: u8,
+}
+
+/// `POST /api/digest` 設定を保存する
+#[utoipa::path(post, path = "/api/digest", tag = "sync", summary = "週報の設定を保存する", request_body = ConfigBody)]
+pub async fn save_config(State(state): State<AppState>, Json(body): Json<ConfigBody>) -> impl IntoResponse {
+    if body.weekday > 7 {
+        return error(StatusCode::BAD_REQUEST, "曜日は 1（月曜）〜 7（日曜）、送らないなら 0");
+    }
+    let mut cfg: DigestConfig = match load_config_json(&state.db_path, CONFIG_FILE) {
+        Ok(c) => c,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    if let Some(url) = body.webhook.map(|u| u.trim().to_string()) {
+        if !(url.is_empty() || url.starts_with("http://") || url.starts_with("https://")) {
+            return error(StatusCode::BAD_REQUEST, "Webhook は http:// か https:// で始まる URL");
+        }
+        cfg.webhook = url;
+    }
+    let smtp_host = body.smtp_host.trim().to_string();
+    cfg.smtp_host = if smtp_host.is_empty() || smtp_host.contains(':') { smtp_host } else { format!("{}:25", smtp_host) };
+    cfg.mail_from = body.mail_from.trim().to_string();
+    cfg.mail_to = body.mail_to.iter().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect();
+    cfg.weekday = body.weekday;
+    // ヘッダーや SMTP のコマンドに入るので、改行や山括弧は受け付けない
+    if std::iter::once(&cfg.mail_from).chain(&cfg.mail_to).any(|a| a.contains(['\r', '\n', '<', '>'])) {
+        return error(StatusCode::BAD_REQUEST, "メールアドレスに使えない文字が入っています");
+    }
+    if !cfg.smtp_host.is_empty() && (cfg.mail_from.is_empty() || cfg.mail_to.is_empty()) {
+        return error(StatusCode::BAD_REQUEST, "SMTP で送るには差出人と宛先が要ります");
+    }
+    match save_config_json(&state.db_path, CONFIG_FILE, &cfg) {
+        Ok(()) => (StatusCode::OK, Json(public_config(&cfg))).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// `GET /api/digest/preview` 今日送るとしたときの本文（Markdown）と集計
+#[utoipa::path(get, path = "/api/digest/preview", tag = "sync", summary = "週報の下書き", params(LibraryQuery))]
+pub async fn preview(Lib(library): Lib) -> impl IntoResponse {
+    let res = tokio::task::spawn_blocking(move || build(&*library.storage, today())).await;
+    match res {
+        Ok(Ok(digest)) => {
+            (StatusCode::OK, Json(serde_json::json!({"text": render_markdown(&digest), "digest": digest}))).into_response()
+        }
+        Ok(Err(e)) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// `POST /api/digest/send` 今すぐ送る
+#[utoipa::path(post, path = "/api/digest/send", tag = "sync", summary = "週報を今すぐ送る", params(LibraryQuery))]
+pub async fn send_now(State(state): State<AppState>, Lib(library): Lib) -> impl IntoResponse {
+    match run_send(state, library.storage, None).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err((status, e)) => error(status, e),
+    }
+}
+
+/// 送る曜日になっていて、今日まだ送っていなければ送る。設定は毎回読み直す。
+pub fn spawn_scheduler(state: AppState) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(SCHEDULER_TICK).await;
+            let (Ok(cfg), Ok(digest_state)) = (
+                load_config_json::<DigestConfig>(&state.db_path, CONFIG_FILE),
+                load_config_json::<DigestState>(&state.db_path, STATE_FILE),
+            ) else {
+                continue;
+            };
+            let day = today();
+            if cfg.weekday == 0
+                || !cfg.has_target()
+                || weekday_of(day) != cfg.weekday
+                || digest_state.last_sent.as_deref() == Some(day.to_string().as_str())
+            {
+                continue;
+            }
+            match run_send(state.clone(), state.storage.clone(), Some(day)).await {
+                Ok(r) if !r.errors.is_empty() => eprintln!("週報の一部を送れませんでした: {}", r.errors.join("、")),
+                Ok(_) => {}
+                Err((_, e)) => eprintln!("週報を送れませんでした: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod digest_tests {
+    use super::{collect_digest, mail_message, render_markdown, smtp_reply, weekday_of};
+    use crate::date::Date;
+    use serde_json::json;
+
+    #[test]
+    fn digest_covers_the_last_seven_days() {
+        let records = vec![
+            ("a.json".to_string(), json!({"title": "Alone", "date": "2026/10/14", "score": 4, "listens": ["2026/10/14", "2026/10/15"]})),
+            ("b.json".to_string(), json!({"title": "Old", "date": "2020/01/01", "score": 5, "listens": ["2026/10/09", "2026/10/08"]})),
+            ("c.json".to_string(), json!({"title": "Draft", "date": "2026/10/12", "draft": true, "listens": ["2026/10/12"]})),
+            ("d.json".to_string(), json!({"title": "Quiet", "date": "2026/10/01", "score": 5})),
+        ];
+        let d = collect_digest(&records, Date::parse("2026/10/15").unwrap());
+        assert_eq!((d.from.as_str(), d.to.as_str()), ("2026/10/09", "2026/10/15"));
+        assert_eq!(d.added.len(), 1);
+        assert_eq!((d.listens, d.listened_records), (3, 2));
+        assert_eq!(d.most_listened.iter().map(|r| r.value).collect::<Vec<_>>(), vec![2.0, 1.0]);
+        // 期間中に登録も再生もしていないもの（Quiet）は入らない
+        assert_eq!(d.top_scores.iter().map(|r| r.value).collect::<Vec<_>>(), vec![5.0, 4.0]);
+        assert!(render_markdown(&d).starts_with("# nekokan_music 週報（2026/10/09〜2026/10/15）"));
+    }
+
+    #[test]
+    fn weekdays_and_mail_format() {
+        // 2026/10/16 は金曜日
+        assert_eq!(weekday_of(Date::parse("2026/10/16").unwrap()), 5);
+        assert_eq!(weekday_of(Date::parse("2026/10/18").unwrap()), 7);
+        let msg = mail_message("a@example.com", &["b@example.com".into()], "digest", "# x\n.hidden\n");
+        assert!(msg.contains("To: b@example.com\r\n"));
+        assert!(msg.ends_with("\r\n\r\n# x\r\n..hidden\r\n"));
+        assert!(smtp_reply(&mut "250-hello\r\n250 OK\r\n".as_bytes(), '2').is_ok());
+        assert!(smtp_reply(&mut "550 no such user\r\n".as_bytes(), '2').is_err());
+    }
+}
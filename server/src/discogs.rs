@@ -0,0 +1,412 @@
+//! Discogs のコレクション CSV との相互変換。
+//! エクスポートは Discogs の「Export collection」と同じ列構成、
+//! インポートは既存レコードとアーティスト・タイトルで突き合わせ、見つからないものを下書きとして作る。
+//! 一致したもの（と同じファイル名があるもの）の扱いは import_conflict の設定に従う。
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde_json::Value;
+
+use crate::date;
+use crate::import_conflict::{copy_filename, merge_blanks, ConflictOptions, ConflictPolicy, ImportEntry, ImportSummary, Outcome};
+use crate::libraries::{Lib, LibraryQuery};
+use crate::listen::{draft_filename, draft_value};
+use crate::problem::{error, field_error};
+use crate::versions::{self, Precondition};
+use crate::{load_all_records, AppState};
+
+/// Discogs コレクションエクスポートの列（この順で書き出す）
+const DISCOGS_COLUMNS: [&str; 13] = [
+    "Catalog#",
+    "Artist",
+    "Title",
+    "Label",
+    "Format",
+    "Rating",
+    "Released",
+    "release_id",
+    "CollectionFolder",
+    "Date Added",
+    "Collection Media Condition",
+    "Collection Sleeve Condition",
+    "Collection Notes",
+];
+
+const DISCOGS_RELEASE_URL: &str = "https://www.discogs.com/release/";
+
+/// アーティストとして扱う personnel の役割（表示ラベルと同じ優先順）
+const ARTIST_ROLES: [&str; 5] = ["leader", "group", "soloists", "conductor", "orchestra"];
+
+/// 突き合わせ用に正規化する。小文字化し、英数字・かな・漢字以外は空白にして連続空白を詰める。
+fn normalize(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_alphanumeric() { c.to_lowercase().next().unwrap_or(c) } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Discogs のアーティスト表記を素の名前にする。
+/// 同名異人の番号 "Bill Evans (2)" と別名義マーク "Miles Davis*" を取り除く。
+fn clean_discogs_artist(s: &str) -> String {
+    let s = s.trim().trim_end_matches('*').trim();
+    if let Some((name, rest)) = s.rsplit_once(" (") {
+        if rest.strip_suffix(')').is_some_and(|n| n.chars().all(|c| c.is_ascii_digit())) {
+            return name.trim_end_matches('*').trim().to_string();
+        }
+    }
+    s.to_string()
+}
+
+fn artist_names(v: &Value) -> Vec<&str> {
+    ARTIST_ROLES
+        .iter()
+        .filter_map(|role| v["personnel"][role].as_array())
+        .flatten()
+        .filter_map(|e| e["name"].as_str())
+        .filter(|n| !n.trim().is_empty())
+        .collect()
+}
+
+/// エクスポート時のアーティスト。Game は表示ラベルと同じくレーベル名を使う。
+fn primary_artist(v: &Value) -> String {
+    if v["janre"]["main"].as_str() == Some("Game") {
+        return v["label"].as_str().unwrap_or("").to_string();
+    }
+    artist_names(v).first().map(|s| s.to_string()).unwrap_or_default()
+}
+
+/// references 内の Discogs リリース URL から release_id を取り出す。
+fn discogs_release_id(v: &Value) -> Option<String> {
+    v["references"].as_array()?.iter().find_map(|r| {
+        let url = r["url"].as_str()?;
+        let rest = url.split("discogs.com/release/").nth(1)?;
+        let id: String = rest.chars().take_while(char::is_ascii_digit).collect();
+        (!id.is_empty()).then_some(id)
+    })
+}
+
+/// "YYYY/MM/DD" → Discogs の "YYYY-MM-DD 00:00:00"
+fn to_discogs_date(date: &str) -> String {
+    if date::is_valid(date) {
+        format!("{} 00:00:00", date.replace('/', "-"))
+    } else {
+        String::new()
+    }
+}
+
+/// Discogs の "YYYY-MM-DD HH:MM:SS" → "YYYY/MM/DD"
+fn from_discogs_date(s: &str) -> Option<String> {
+    let d = s.trim().get(..10)?.replace('-', "/");
+    date::is_valid(&d).then_some(d)
+}
+
+fn export_row(v: &Value) -> [String; 13] {
+    let score = v["score"].as_f64().unwrap_or(0.0).round() as i64;
+    let release_year = v["release_year"].as_i64().filter(|y| *y > 0);
+    [
+        v["id"].as_str().unwrap_or("").to_string(),
+        primary_artist(v),
+        v["title"].as_str().unwrap_or("").to_string(),
+        v["label"].as_str().unwrap_or("").to_string(),
+        String::new(),
+        // Discogs の評価は 1〜5 の整数（本アプリの尺度はポリシーの ScoreScale。既定は 1〜6）
+        if score > 0 { score.min(5).to_string() } else { String::new() },
+        release_year.map(|y| y.to_string()).unwrap_or_default(),
+        discogs_release_id(v).unwrap_or_default(),
+        "Uncategorized".to_string(),
+        to_discogs_date(v["date"].as_str().unwrap_or("")),
+        String::new(),
+        String::new(),
+        v["comment"].as_str().unwrap_or("").to_string(),
+    ]
+}
+
+/// `GET /api/export/discogs.csv` 全レコードを Discogs 互換 CSV で返す（下書きは除く）。
+#[utoipa::path(
+    get,
+    path = "/api/export/discogs.csv",
+    tag = "import-export",
+    summary = "Discogs の CSV に書き出す",
+    params(LibraryQuery),
+)]
+pub async fn export_csv(Lib(library): Lib) -> impl IntoResponse {
+    let Some(records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    let mut w = csv::Writer::from_writer(vec![]);
+    let rows = std::iter::once(DISCOGS_COLUMNS.map(String::from)).chain(
+        records
+            .iter()
+            .filter(|(_, v)| v["draft"].as_bool() != Some(true))
+            .map(|(_, v)| export_row(v)),
+    );
+    for row in rows {
+        if let Err(e) = w.write_record(&row) {
+            return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+        }
+    }
+    let body = match w.into_inner() {
+        Ok(b) => b,
+        Err(e) => {
+            return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+        }
+    };
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"nekokan_collection.csv\""),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct ImportBody {
+    /// Discogs のコレクションエクスポート CSV 本文
+    csv: String,
+    /// Date Added がない行の記録日（YYYY/MM/DD）
+    date: String,
+    /// 既存のレコードとぶつかったときの扱い
+    #[serde(flatten)]
+    options: ConflictOptions,
+}
+
+/// CSV 1 行分（必要な列だけ）
+#[derive(Debug, Default, PartialEq)]
+struct DiscogsRow {
+    artist: String,
+    title: String,
+    catalog: String,
+    label: String,
+    released: Option<i64>,
+    release_id: String,
+    rating: i64,
+    date_added: Option<String>,
+    notes: String,
+}
+
+fn parse_rows(text: &str) -> Result<Vec<(usize, DiscogsRow)>, String> {
+    let mut rdr = csv::ReaderBuilder::new().flexible(true).from_reader(text.as_bytes());
+    let headers = rdr.headers().map_err(|e| e.to_string())?.clone();
+    let col = |name: &str| headers.iter().position(|h| h.trim() == name);
+    let (Some(artist_col), Some(title_col)) = (col("Artist"), col("Title")) else {
+        return Err("CSV に Artist / Title 列がありません".into());
+    };
+    let (catalog_col, label_col, released_col, id_col, rating_col, added_col, notes_col) = (
+        col("Catalog#"),
+        col("Label"),
+        col("Released"),
+        col("release_id"),
+        col("Rating"),
+        col("Date Added"),
+        col("Collection Notes"),
+    );
+    let mut rows = Vec::new();
+    for (i, rec) in rdr.records().enumerate() {
+        let rec = rec.map_err(|e| e.to_string())?;
+        let get = |c: Option<usize>| c.and_then(|c| rec.get(c)).unwrap_or("").trim().to_string();
+        rows.push((
+            // ヘッダーが 1 行目なのでデータは 2 行目から
+            i + 2,
+            DiscogsRow {
+                artist: clean_discogs_artist(&get(Some(artist_col))),
+                title: get(Some(title_col)),
+                catalog: get(catalog_col),
+                label: get(label_col),
+                released: get(released_col).get(..4).and_then(|y| y.parse().ok()),
+                release_id: get(id_col),
+                rating: get(rating_col).parse().unwrap_or(0),
+                date_added: from_discogs_date(&get(added_col)),
+                notes: get(notes_col),
+            },
+        ));
+    }
+    Ok(rows)
+}
+
+/// タイトルが一致し、アーティストが personnel のいずれかの名前と一致するレコードを探す。
+fn find_existing(records: &[(String, Value)], row: &DiscogsRow) -> Option<usize> {
+    let title = normalize(&row.title);
+    let artist = normalize(&row.artist);
+    records.iter().position(|(_, v)| {
+        normalize(v["title"].as_str().unwrap_or("")) == title
+            && (artist.is_empty()
+                || normalize(&primary_artist(v)) == artist
+                || artist_names(v).iter().any(|n| normalize(n) == artist))
+    })
+}
+
+fn discogs_reference(release_id: &str) -> Value {
+    serde_json::json!({"name": "Discogs", "url": format!("{}{}", DISCOGS_RELEASE_URL, release_id)})
+}
+
+fn row_to_draft(row: &DiscogsRow, fallback_date: &str) -> Value {
+    let date = row.date_added.as_deref().unwrap_or(fallback_date);
+    let mut v = draft_value(&row.artist, &row.title, date, false);
+    v["label"] = Value::String(row.label.clone());
+    v["id"] = Value::String(row.catalog.clone());
+    if let Some(y) = row.released {
+        v["release_year"] = Value::from(y);
+    }
+    if (1..=5).contains(&row.rating) {
+        v["score"] = Value::from(row.rating);
+    }
+    v["comment"] = Value::String(row.notes.clone());
+    if !row.release_id.is_empty() {
+        v["references"] = Value::Array(vec![discogs_reference(&row.release_id)]);
+    }
+    v
+}
+
+/// ぶつかった行を扱いに従って書き込む
+fn resolve_conflict(
+    storage: &dyn crate::storage::Storage,
+    policy: ConflictPolicy,
+    existing: &mut (String, Value),
+    incoming: &Value,
+    filename: &str,
+) -> (Outcome, Option<String>, String) {
+    match policy {
+        ConflictPolicy::Skip => (Outcome::Skipped, None, String::new()),
+        ConflictPolicy::Ask => (Outcome::Pending, None, String::new()),
+        ConflictPolicy::Merge => {
+            let (name, v) = existing;
+            let read = Precondition::Version(versions::version_of(v));
+            if !merge_blanks(v, incoming) {
+                return (Outcome::Unchanged, Some(name.clone()), String::new());
+            }
+            match versions::write(storage, name, &read, v) {
+                Ok(_) => (Outcome::Merged, Some(name.clone()), String::new()),
+                Err(e) => (Outcome::Failed, None, e.to_string()),
+            }
+        }
+        ConflictPolicy::Draft => {
+            let Some(copy) = copy_filename(storage, filename) else {
+                return (Outcome::Failed, None, "ファイル名を作れません".into());
+            };
+            match versions::write(storage, &copy, &Precondition::Absent, incoming) {
+                Ok(_) => (Outcome::DraftCopy, Some(copy), String::new()),
+                Err(e) => (Outcome::Failed, None, e.to_string()),
+            }
+        }
+    }
+}
+
+/// `POST /api/import/discogs` Discogs の CSV を取り込む。
+/// 一致しない行は下書きレコードを作り、既存のレコードに一致した行（同じファイル名がある行）は扱い（`conflict`）に従う。
+#[utoipa::path(
+    post,
+    path = "/api/import/discogs",
+    tag = "import-export",
+    summary = "Discogs の CSV を取り込む",
+    params(LibraryQuery),
+    request_body = ImportBody,
+)]
+pub async fn import_csv(State(state): State<AppState>, Lib(library): Lib, Json(body): Json<ImportBody>) -> impl IntoResponse {
+    if !date::is_valid(&body.date) {
+        return field_error("date", "must be YYYY/MM/DD");
+    }
+    let rows = match parse_rows(&body.csv) {
+        Ok(r) => r,
+        Err(e) => {
+            return error(StatusCode::BAD_REQUEST, e);
+        }
+    };
+    let Some(mut records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    let policy = body.options.policy(&state.db_path);
+    let mut summary = ImportSummary::new(policy);
+    for (line, row) in rows {
+        let title = format!("{} - {}", row.artist, row.title).trim_start_matches(" - ").to_string();
+        let entry = |outcome, filename, existing, reason: String| ImportEntry { line, title: title.clone(), outcome, filename, existing, reason };
+        if row.title.is_empty() {
+            summary.push(entry(Outcome::Skipped, None, None, "Title が空です".into()));
+            continue;
+        }
+        let Some(filename) = draft_filename(&row.artist, &row.title) else {
+            summary.push(entry(Outcome::Skipped, None, None, "ファイル名を作れません".into()));
+            continue;
+        };
+        let incoming = row_to_draft(&row, &body.date);
+        let existing = find_existing(&records, &row).or_else(|| records.iter().position(|(f, _)| *f == filename));
+        if let Some(pos) = existing {
+            let existing_name = records[pos].0.clone();
+            let (outcome, written, reason) =
+                resolve_conflict(&*library.storage, body.options.for_line(policy, line), &mut records[pos], &incoming, &filename);
+            if outcome == Outcome::DraftCopy {
+                if let Some(copy) = &written {
+                    records.push((copy.clone(), incoming));
+                }
+            }
+            summary.push(entry(outcome, written, Some(existing_name), reason));
+            continue;
+        }
+        match versions::write(&*library.storage, &filename, &Precondition::Absent, &incoming) {
+            Ok(_) => {
+                summary.push(entry(Outcome::Created, Some(filename.clone()), None, String::new()));
+                records.push((filename, incoming));
+            }
+            Err(e) => summary.push(entry(Outcome::Failed, None, None, e.to_string())),
+        }
+    }
+    (StatusCode::OK, Json(summary)).into_response()
+}
+
+#[cfg(test)]
+mod discogs_tests {
+    use super::{clean_discogs_artist, discogs_release_id, from_discogs_date, parse_rows, ImportBody};
+    use crate::import_conflict::ConflictPolicy;
+
+    #[test]
+    fn artist_suffixes_are_removed() {
+        assert_eq!(clean_discogs_artist("Bill Evans (2)"), "Bill Evans");
+        assert_eq!(clean_discogs_artist("Miles Davis*"), "Miles Davis");
+        assert_eq!(clean_discogs_artist("Weather Report (Live)"), "Weather Report (Live)");
+    }
+
+    #[test]
+    fn release_id_from_reference_url() {
+        let v = serde_json::json!({"references": [
+            {"name": "Wikipedia", "url": "https://en.wikipedia.org/wiki/Alone"},
+            {"name": "discogs", "url": "https://www.discogs.com/release/14390414-Akio-Sasajima"}
+        ]});
+        assert_eq!(discogs_release_id(&v).as_deref(), Some("14390414"));
+    }
+
+    #[test]
+    fn parses_discogs_export() {
+        let csv = "Catalog#,Artist,Title,Label,Format,Rating,Released,release_id,CollectionFolder,Date Added,Collection Media Condition,Collection Sleeve Condition,Collection Notes\n\
+                   CK 64935,Miles Davis*,Kind Of Blue,Columbia,\"CD, Album\",5,1997,1234,Uncategorized,2021-03-14 10:22:01,,,\"good, very\"\n";
+        let rows = parse_rows(csv).unwrap();
+        assert_eq!(rows.len(), 1);
+        let (line, row) = &rows[0];
+        assert_eq!(*line, 2);
+        assert_eq!(row.artist, "Miles Davis");
+        assert_eq!(row.released, Some(1997));
+        assert_eq!(row.rating, 5);
+        assert_eq!(row.date_added.as_deref(), Some("2021/03/14"));
+        assert_eq!(row.notes, "good, very");
+        assert_eq!(from_discogs_date("bad"), None);
+    }
+
+    #[test]
+    fn conflict_options_in_the_body() {
+        let body: ImportBody = serde_json::from_value(serde_json::json!({
+            "csv": "", "date": "2024/01/01", "conflict": "ask", "decisions": {"2": "merge", "5": "skip"}
+        }))
+        .unwrap();
+        assert_eq!(body.options.conflict, Some(ConflictPolicy::Ask));
+        assert_eq!(body.options.for_line(ConflictPolicy::Ask, 2), ConflictPolicy::Merge);
+        let body: ImportBody = serde_json::from_value(serde_json::json!({"csv": "", "date": "2024/01/01"})).unwrap();
+        assert_eq!(body.options.conflict, None);
+    }
+}
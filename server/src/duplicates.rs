@@ -0,0 +1,380 @@
+//! 重複・関連レコードの検出。
+//! タイトルからエディション表記（"(Legacy Edition)" など）を外し、別題 `title_alt` と
+//! アーティストの別表記 `name_alt` も使って突き合わせる。エディション違いは「関連」として返し、
+//! 統合ではなく `related` での紐付けを促す。
+//! `GET /api/duplicates` は DB 全体から、同じ id か同じ（タイトル + 最初の leader / group）のレコードをまとめて返す。
+
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+use crate::{display_label_from_value, load_all_records};
+
+/// エディション・再発を表す語（小文字）。括弧内やハイフン以降にこれらを含めばエディション表記とみなす。
+const EDITION_WORDS: [&str; 27] = [
+    "edition",
+    "remaster",
+    "remastered",
+    "deluxe",
+    "expanded",
+    "anniversary",
+    "reissue",
+    "bonus",
+    "mono",
+    "stereo",
+    "version",
+    "legacy",
+    "collector",
+    "special",
+    "complete",
+    "sacd",
+    "shm",
+    "xrcd",
+    "24bit",
+    "紙ジャケ",
+    "リマスター",
+    "完全版",
+    "デラックス",
+    "限定",
+    "盤",
+    "エディション",
+    "再発",
+];
+
+/// アーティストとして扱う personnel の役割
+const ARTIST_ROLES: [&str; 5] = ["leader", "group", "soloists", "conductor", "orchestra"];
+
+/// 突き合わせ用に正規化する。小文字化し、英数字・かな・漢字以外は空白にして連続空白を詰める。
+pub fn normalize(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_alphanumeric() { c.to_lowercase().next().unwrap_or(c) } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_edition_note(s: &str) -> bool {
+    let lower = s.to_lowercase();
+    EDITION_WORDS.iter().any(|w| lower.contains(w))
+}
+
+/// タイトルを (エディション表記を除いた本体, エディション表記) に分ける。
+/// 末尾の括弧 "(...)" "[...]" "（...）" "【...】" と " - 2009 Remaster" 形式を対象にする。
+pub fn split_edition(title: &str) -> (String, Option<String>) {
+    let mut base = title.trim().to_string();
+    let mut notes = Vec::new();
+    loop {
+        let trimmed = base.trim_end();
+        let close = trimmed.chars().last();
+        let open = match close {
+            Some(')') => '(',
+            Some(']') => '[',
+            Some('）') => '（',
+            Some('】') => '【',
+            _ => break,
+        };
+        let Some(start) = trimmed.rfind(open) else {
+            break;
+        };
+        let inner = &trimmed[start + open.len_utf8()..trimmed.len() - close.map_or(0, char::len_utf8)];
+        if start == 0 || !is_edition_note(inner) {
+            break;
+        }
+        notes.push(inner.trim().to_string());
+        base = trimmed[..start].trim_end().to_string();
+    }
+    if let Some((head, tail)) = base.rsplit_once(" - ") {
+        if !head.trim().is_empty() && is_edition_note(tail) {
+            notes.push(tail.trim().to_string());
+            base = head.trim_end().to_string();
+        }
+    }
+    notes.reverse();
+    let edition = (!notes.is_empty()).then(|| notes.join(" / "));
+    (base, edition)
+}
+
+/// 突き合わせに使うタイトル集合（title と title_alt）。`base` が true ならエディション表記を除いた本体。
+fn title_keys(v: &Value, base: bool) -> HashSet<String> {
+    ["title", "title_alt"]
+        .iter()
+        .filter_map(|k| v[k].as_str())
+        .map(|t| if base { normalize(&split_edition(t).0) } else { normalize(t) })
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// 突き合わせに使うアーティスト集合（name と別表記 name_alt）
+fn artist_keys(v: &Value) -> HashSet<String> {
+    ARTIST_ROLES
+        .iter()
+        .filter_map(|role| v["personnel"][role].as_array())
+        .flatten()
+        .flat_map(|e| [e["name"].as_str(), e["name_alt"].as_str()])
+        .flatten()
+        .map(normalize)
+        .filter(|n| !n.is_empty())
+        .collect()
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    /// 同じタイトル（エディション表記も同じ、別題を含む）・同じアーティスト、または同じ id
+    Duplicate,
+    /// タイトル本体とアーティストは同じだがエディション・別題が違う
+    Related,
+}
+
+/// `a` と `b` が重複・関連かどうか。どちらでもなければ None。
+pub fn match_kind(a: &Value, b: &Value) -> Option<MatchKind> {
+    let id_a = a["id"].as_str().map(normalize).unwrap_or_default();
+    if !id_a.is_empty() && b["id"].as_str().map(normalize).as_deref() == Some(id_a.as_str()) {
+        return Some(MatchKind::Duplicate);
+    }
+    if title_keys(a, true).is_disjoint(&title_keys(b, true)) {
+        return None;
+    }
+    let (artists_a, artists_b) = (artist_keys(a), artist_keys(b));
+    if !artists_a.is_empty() && !artists_b.is_empty() && artists_a.is_disjoint(&artists_b) {
+        return None;
+    }
+    if !title_keys(a, false).is_disjoint(&title_keys(b, false)) {
+        Some(MatchKind::Duplicate)
+    } else {
+        Some(MatchKind::Related)
+    }
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct CheckBody {
+    /// 編集中のレコード（フォームの内容）
+    #[schema(value_type = crate::openapi::Record)]
+    record: Value,
+    /// 編集中のファイル名。自分自身は結果から除く。新規なら省略。
+    #[serde(default)]
+    filename: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct Candidate {
+    filename: String,
+    display_label: String,
+    kind: MatchKind,
+    /// エディション表記（あれば）
+    edition: Option<String>,
+    /// 既に `related` で紐付け済みか
+    linked: bool,
+}
+
+/// `POST /api/duplicates/check` 編集中のレコードと重複・関連しそうな既存レコードを返す。
+#[utoipa::path(
+    post,
+    path = "/api/duplicates/check",
+    tag = "records",
+    summary = "編集中のレコードと重複しそうなもの",
+    params(LibraryQuery),
+    request_body = CheckBody,
+)]
+pub async fn check(Lib(library): Lib, Json(body): Json<CheckBody>) -> impl IntoResponse {
+    let Some(records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    let self_name = body.filename.as_deref().map(|f| f.trim_end_matches(".json").to_string());
+    let linked: HashSet<&str> = body.record["related"]
+        .as_array()
+        .map(|a| a.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let candidates: Vec<Candidate> = records
+        .iter()
+        .filter(|(f, _)| self_name.as_deref() != Some(f.trim_end_matches(".json")))
+        .filter_map(|(f, v)| {
+            let kind = match_kind(&body.record, v)?;
+            Some(Candidate {
+                filename: f.clone(),
+                display_label: display_label_from_value(v),
+                kind,
+                edition: split_edition(v["title"].as_str().unwrap_or("")).1,
+                linked: linked.contains(f.as_str()),
+            })
+        })
+        .collect();
+    (StatusCode::OK, Json(candidates)).into_response()
+}
+
+/// 一覧での突き合わせの理由
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum GroupReason {
+    /// 同じ id
+    Id,
+    /// 同じタイトル（正規化後）で、最初の leader（いなければ group）も同じ
+    TitleArtist,
+}
+
+/// レコードの突き合わせ用の鍵。タイトルかアーティストが空なら title_artist の鍵は作らない。
+fn group_keys(v: &Value) -> Vec<(GroupReason, String)> {
+    let mut keys = Vec::new();
+    let id = v["id"].as_str().map(normalize).unwrap_or_default();
+    if !id.is_empty() {
+        keys.push((GroupReason::Id, id));
+    }
+    let title = normalize(v["title"].as_str().unwrap_or(""));
+    let artist = ["leader", "group"]
+        .iter()
+        .find_map(|role| v["personnel"][role][0]["name"].as_str().map(normalize).filter(|n| !n.is_empty()));
+    if let (false, Some(artist)) = (title.is_empty(), artist) {
+        keys.push((GroupReason::TitleArtist, format!("{}\u{1f}{}", title, artist)));
+    }
+    keys
+}
+
+/// 鍵を 1 つでも共有するレコードをまとめる（A と B が id、B と C がタイトルで一致すれば 3 件で 1 組）。
+/// 返すのは (レコードの添字, 一致した理由) の組で、2 件以上のものだけ。
+fn group_duplicates(records: &[(String, Value)]) -> Vec<(Vec<usize>, Vec<GroupReason>)> {
+    fn find(parent: &mut [usize], i: usize) -> usize {
+        let mut root = i;
+        while parent[root] != root {
+            root = parent[root];
+        }
+        let mut i = i;
+        while parent[i] != root {
+            let next = parent[i];
+            parent[i] = root;
+            i = next;
+        }
+        root
+    }
+    let mut parent: Vec<usize> = (0..records.len()).collect();
+    let mut first: HashMap<(GroupReason, String), usize> = HashMap::new();
+    let mut hits: Vec<(usize, GroupReason)> = Vec::new();
+    for (i, (_, v)) in records.iter().enumerate() {
+        for key in group_keys(v) {
+            let reason = key.0;
+            match first.get(&key) {
+                Some(&j) => {
+                    let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                    parent[a] = b;
+                    hits.push((j, reason));
+                }
+                None => {
+                    first.insert(key, i);
+                }
+            }
+        }
+    }
+    let mut groups: BTreeMap<usize, (Vec<usize>, Vec<GroupReason>)> = BTreeMap::new();
+    for i in 0..records.len() {
+        let root = find(&mut parent, i);
+        groups.entry(root).or_default().0.push(i);
+    }
+    for (i, reason) in hits {
+        let root = find(&mut parent, i);
+        let reasons = &mut groups.entry(root).or_default().1;
+        if !reasons.contains(&reason) {
+            reasons.push(reason);
+        }
+    }
+    let mut out: Vec<(Vec<usize>, Vec<GroupReason>)> = groups.into_values().filter(|(members, _)| members.len() > 1).collect();
+    for (_, reasons) in &mut out {
+        reasons.sort();
+    }
+    out.sort_by_key(|(members, _)| members[0]);
+    out
+}
+
+#[derive(serde::Serialize)]
+struct GroupFile {
+    filename: String,
+    display_label: String,
+}
+
+#[derive(serde::Serialize)]
+struct DuplicateGroup {
+    reasons: Vec<GroupReason>,
+    files: Vec<GroupFile>,
+}
+
+/// `GET /api/duplicates` ファイル名だけ違う二重登録の候補をまとめて返す。
+#[utoipa::path(get, path = "/api/duplicates", tag = "records", summary = "二重登録の候補", params(LibraryQuery))]
+pub async fn list(Lib(library): Lib) -> impl IntoResponse {
+    let Some(records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    let groups: Vec<DuplicateGroup> = group_duplicates(&records)
+        .into_iter()
+        .map(|(members, reasons)| DuplicateGroup {
+            reasons,
+            files: members
+                .into_iter()
+                .map(|i| GroupFile { filename: records[i].0.clone(), display_label: display_label_from_value(&records[i].1) })
+                .collect(),
+        })
+        .collect();
+    (StatusCode::OK, Json(groups)).into_response()
+}
+
+#[cfg(test)]
+mod duplicates_tests {
+    use super::{group_duplicates, match_kind, split_edition, GroupReason, MatchKind};
+    use serde_json::json;
+
+    #[test]
+    fn edition_is_split_from_title() {
+        assert_eq!(
+            split_edition("Kind of Blue (Legacy Edition)"),
+            ("Kind of Blue".into(), Some("Legacy Edition".into()))
+        );
+        assert_eq!(
+            split_edition("Moanin' - 2009 Remaster"),
+            ("Moanin'".into(), Some("2009 Remaster".into()))
+        );
+        // エディション以外の括弧はタイトルの一部
+        assert_eq!(split_edition("Live (At the Pershing)"), ("Live (At the Pershing)".into(), None));
+    }
+
+    #[test]
+    fn reissue_is_related_not_duplicate() {
+        let a = json!({"title": "Kind of Blue", "personnel": {"leader": [{"name": "Miles Davis"}]}});
+        let b = json!({"title": "Kind of Blue (Legacy Edition)", "personnel": {"leader": [{"name": "Miles Davis"}]}});
+        let c = json!({"title": "Kind of Blue", "personnel": {"leader": [{"name": "Bill Evans"}]}});
+        assert_eq!(match_kind(&a, &b), Some(MatchKind::Related));
+        assert_eq!(match_kind(&a, &a), Some(MatchKind::Duplicate));
+        assert_eq!(match_kind(&a, &c), None);
+    }
+
+    #[test]
+    fn title_alt_and_name_alt_are_considered() {
+        let a = json!({"title": "カインド・オブ・ブルー", "title_alt": "Kind of Blue",
+            "personnel": {"leader": [{"name": "マイルス・デイヴィス", "name_alt": "Miles Davis"}]}});
+        let b = json!({"title": "Kind of Blue", "personnel": {"leader": [{"name": "Miles Davis"}]}});
+        assert_eq!(match_kind(&a, &b), Some(MatchKind::Duplicate));
+        let reissue = json!({"title": "Kind of Blue (Remastered)", "personnel": {"leader": [{"name": "Miles Davis"}]}});
+        assert_eq!(match_kind(&a, &reissue), Some(MatchKind::Related));
+    }
+
+    #[test]
+    fn groups_join_on_id_or_title_and_first_artist() {
+        let records = vec![
+            ("a.json".to_string(), json!({"id": "BLP-1577", "title": "Blue Train", "personnel": {"leader": [{"name": "John Coltrane"}]}})),
+            ("b.json".to_string(), json!({"id": "blp 1577", "title": "Blue Train!", "personnel": {}})),
+            ("c.json".to_string(), json!({"title": "blue train", "personnel": {"leader": [{"name": "John  Coltrane"}]}})),
+            ("d.json".to_string(), json!({"title": "Blue Train", "personnel": {"leader": [{"name": "Lee Morgan"}]}})),
+            ("e.json".to_string(), json!({"title": "Moanin'", "personnel": {"group": [{"name": "The Jazz Messengers"}]}})),
+            ("f.json".to_string(), json!({"title": "Moanin", "personnel": {"group": [{"name": "the jazz messengers"}]}})),
+            ("g.json".to_string(), json!({"title": "Untitled", "personnel": {}})),
+            ("h.json".to_string(), json!({"title": "Untitled", "personnel": {}})),
+        ];
+        let groups = group_duplicates(&records);
+        assert_eq!(
+            groups,
+            vec![
+                (vec![0, 1, 2], vec![GroupReason::Id, GroupReason::TitleArtist]),
+                (vec![4, 5], vec![GroupReason::TitleArtist]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! ライブラリのレコード JSON をまとめた ZIP（`GET /api/export/zip`）。ブラウザから定期的にスナップショットを取る用。
+//! `?genre=Jazz`（メインジャンル）・`?from=YYYY/MM/DD&to=YYYY/MM/DD`（記録日 `date`、両端を含む）で絞れる。`?library=` で対象のライブラリ。
+//!
+//! 全部を手元に溜めずに送るよう、書き終わったエントリーの分から順にレスポンスに流す（ZIP の書き込みは spawn_blocking）。
+
+use axum::{
+    body::Body,
+    extract::Query,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde_json::Value;
+use std::io::{self, Seek, SeekFrom, Write};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::mpsc;
+use zip::write::SimpleFileOptions;
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+use crate::storage::Storage;
+
+/// 送り出し待ちのかたまりの数（書く側がこれ以上先に進まないように）
+const CHANNEL_CHUNKS: usize = 16;
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+pub struct ZipFilter {
+    /// メインジャンル（空ならすべて）
+    #[serde(default)]
+    genre: String,
+    #[serde(default)]
+    from: String,
+    #[serde(default)]
+    to: String,
+}
+
+impl ZipFilter {
+    fn is_empty(&self) -> bool {
+        self.genre.is_empty() && self.from.is_empty() && self.to.is_empty()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (name, d) in [("from", &self.from), ("to", &self.to)] {
+            if !d.is_empty() && !crate::date::is_valid(d) {
+                return Err(format!("{} must be YYYY/MM/DD", name));
+            }
+        }
+        Ok(())
+    }
+
+    fn matches(&self, v: &Value) -> bool {
+        if !self.genre.is_empty() && v["janre"]["main"].as_str() != Some(self.genre.as_str()) {
+            return false;
+        }
+        if self.from.is_empty() && self.to.is_empty() {
+            return true;
+        }
+        // 記録日は YYYY/MM/DD なので文字列の順がそのまま日付の順
+        let date = v["date"].as_str().unwrap_or("");
+        crate::date::is_valid(date)
+            && (self.from.is_empty() || date >= self.from.as_str())
+            && (self.to.is_empty() || date <= self.to.as_str())
+    }
+}
+
+/// 書き終わった分を送り出せる書き先。ZipWriter はエントリーを書き終えるときにその頭（ローカルヘッダー）へ戻って
+/// 大きさを書き直すので、書きかけのエントリーの分だけ手元に残し、それより前は `release` で送る。
+struct Sink {
+    /// `base` からあとの、まだ送っていない分
+    buf: Vec<u8>,
+    base: u64,
+    pos: u64,
+    tx: mpsc::Sender<io::Result<Vec<u8>>>,
+}
+
+impl Sink {
+    fn end(&self) -> u64 {
+        self.base + self.buf.len() as u64
+    }
+
+    /// `upto` より前を送る（もう書き直さない分）
+    fn release(&mut self, upto: u64) -> io::Result<()> {
+        let n = upto.saturating_sub(self.base).min(self.buf.len() as u64) as usize;
+        if n == 0 {
+            return Ok(());
+        }
+        let chunk: Vec<u8> = self.buf.drain(..n).collect();
+        self.base += n as u64;
+        // 受け取る側（レスポンス）がいなくなったら書くのをやめる
+        self.tx.blocking_send(Ok(chunk)).map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "client went away"))
+    }
+}
+
+#[derive(Clone)]
+struct SharedSink(Arc<Mutex<Sink>>);
+
+impl SharedSink {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Sink> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Write for SharedSink {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let mut s = self.lock();
+        let Some(at) = s.pos.checked_sub(s.base) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "already sent"));
+        };
+        let at = at as usize;
+        let overlap = s.buf.len().saturating_sub(at).min(data.len());
+        s.buf[at..at + overlap].copy_from_slice(&data[..overlap]);
+        s.buf.extend_from_slice(&data[overlap..]);
+        s.pos += data.len() as u64;
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for SharedSink {
+    fn seek(&mut self, to: SeekFrom) -> io::Result<u64> {
+        let mut s = self.lock();
+        let pos = match to {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(d) => s.end().checked_add_signed(d),
+            SeekFrom::Current(d) => s.pos.checked_add_signed(d),
+        };
+        match pos {
+            Some(p) if p >= s.base && p <= s.end() => {
+                s.pos = p;
+                Ok(p)
+            }
+            _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "seek outside the unsent part")),
+        }
+    }
+}
+
+/// ZIP を書いて `tx` に流す。エントリー名はレコードのファイル名
+fn write_zip(storage: &dyn Storage, filter: &ZipFilter, tx: mpsc::Sender<io::Result<Vec<u8>>>) -> io::Result<usize> {
+    let sink = SharedSink(Arc::new(Mutex::new(Sink { buf: Vec::new(), base: 0, pos: 0, tx })));
+    let mut zip = zip::ZipWriter::new(sink.clone());
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut count = 0;
+    for name in storage.list()? {
+        let Ok(bytes) = storage.read(&name) else {
+            continue;
+        };
+        // 絞り込むときは読めない JSON は入れない（どこに入るか分からないので）
+        if !filter.is_empty() && !serde_json::from_slice::<Value>(&bytes).is_ok_and(|v| filter.matches(&v)) {
+            continue;
+        }
+        // ここまでが前のエントリー。start_file が前のエントリーを閉じたら、そこまでは書き直されない
+        let done = sink.lock().end();
+        zip.start_file(name.as_str(), options)?;
+        sink.lock().release(done)?;
+        zip.write_all(&bytes)?;
+        count += 1;
+    }
+    let sink = zip.finish()?;
+    let end = sink.lock().end();
+    sink.lock().release(end)?;
+    Ok(count)
+}
+
+/// "nekokan_music-2026-10-16.zip"（絞り込んだらジャンルも付ける）
+fn zip_filename(filter: &ZipFilter, today: &str) -> String {
+    let genre: String = filter.genre.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    let today = today.replace('/', "-");
+    if genre.is_empty() {
+        format!("nekokan_music-{}.zip", today)
+    } else {
+        format!("nekokan_music-{}-{}.zip", genre, today)
+    }
+}
+
+/// `GET /api/export/zip`
+#[utoipa::path(get, path = "/api/export/zip", tag = "import-export", summary = "レコードを ZIP で書き出す", params(LibraryQuery))]
+pub async fn export_zip(Lib(library): Lib, Query(filter): Query<ZipFilter>) -> Response {
+    if let Err(e) = filter.validate() {
+        return error(StatusCode::BAD_REQUEST, e);
+    }
+    let today = crate::stats::date_from_system_time(std::time::SystemTime::now()).unwrap_or_default();
+    let filename = zip_filename(&filter, &today);
+    let (tx, mut rx) = mpsc::channel(CHANNEL_CHUNKS);
+    let storage = library.storage.clone();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = write_zip(&*storage, &filter, tx.clone()) {
+            // 途中で失敗したらレスポンスを打ち切る（壊れた ZIP を最後まで送らない）
+            eprintln!("ZIP の書き出しに失敗しました: {}", e);
+            let _ = tx.blocking_send(Err(e));
+        }
+    });
+    let stream = futures_util::stream::poll_fn(move |cx| rx.poll_recv(cx));
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        Body::from_stream(stream),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod export_zip_tests {
+    use super::{write_zip, zip_filename, ZipFilter};
+    use crate::storage::{FsStorage, Layout, Storage};
+    use serde_json::json;
+    use std::io::Read;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn filter_by_genre_and_date() {
+        let f = ZipFilter { genre: "Jazz".into(), from: "2024/01/01".into(), to: "2024/12/31".into() };
+        assert!(f.validate().is_ok());
+        assert!(f.matches(&json!({"janre": {"main": "Jazz"}, "date": "2024/12/31"})));
+        assert!(!f.matches(&json!({"janre": {"main": "Jazz"}, "date": "2025/01/01"})));
+        assert!(!f.matches(&json!({"janre": {"main": "Rock"}, "date": "2024/05/01"})));
+        assert!(!f.matches(&json!({"janre": {"main": "Jazz"}, "date": ""})));
+        assert!(ZipFilter { from: "2024-01-01".into(), ..Default::default() }.validate().is_err());
+        assert_eq!(zip_filename(&f, "2026/10/16"), "nekokan_music-Jazz-2026-10-16.zip");
+    }
+
+    #[test]
+    fn streamed_zip_can_be_read_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = FsStorage::new(dir.path(), Layout::Flat, false);
+        for (name, genre) in [("a.json", "Jazz"), ("b.json", "Rock"), ("c.json", "Jazz")] {
+            let body = json!({"title": name, "janre": {"main": genre}, "comment": "x".repeat(5000)});
+            storage.write(name, body.to_string().as_bytes()).unwrap();
+        }
+        let (tx, mut rx) = mpsc::channel(1024);
+        let filter = ZipFilter { genre: "Jazz".into(), ..Default::default() };
+        assert_eq!(write_zip(&storage, &filter, tx).unwrap(), 2);
+        let mut bytes = Vec::new();
+        let mut chunks = 0;
+        while let Ok(chunk) = rx.try_recv() {
+            bytes.extend(chunk.unwrap());
+            chunks += 1;
+        }
+        assert!(chunks > 1);
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).unwrap();
+        assert_eq!(archive.file_names().collect::<Vec<_>>().len(), 2);
+        let mut text = String::new();
+        archive.by_name("c.json").unwrap().read_to_string(&mut text).unwrap();
+        assert!(text.contains("\"c.json\""));
+    }
+}
@@ -0,0 +1,216 @@
+//! ジャンル（janre）の付け替え。分類を組み替えたとき（例: Fusion の Sub "Rock" を新しい Main に独立させる）に、
+//! 旧ジャンルごとの該当レコードを一覧し、対応表をまとめて適用する。適用前に dry-run で結果を確かめられる。
+
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+use crate::versions::{self, Precondition};
+use crate::{display_label_from_value, load_all_records};
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Janre {
+    pub main: String,
+    #[serde(default)]
+    pub sub: Vec<String>,
+}
+
+impl Janre {
+    fn of(v: &Value) -> Self {
+        Janre {
+            main: v["janre"]["main"].as_str().unwrap_or("").to_string(),
+            sub: v["janre"]["sub"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+/// 付け替えの 1 行。`from_sub` が空なら Main だけで当てる。
+/// `to_main` が空なら Main はそのまま、`to_sub` が空なら `from_sub` を外す。
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, utoipa::ToSchema)]
+pub struct GenreMapping {
+    pub from_main: String,
+    #[serde(default)]
+    pub from_sub: String,
+    #[serde(default)]
+    pub to_main: String,
+    #[serde(default)]
+    pub to_sub: String,
+}
+
+impl GenreMapping {
+    fn matches(&self, j: &Janre) -> bool {
+        j.main == self.from_main.trim() && (self.from_sub.trim().is_empty() || j.sub.iter().any(|s| s == self.from_sub.trim()))
+    }
+}
+
+/// 最初に当てはまった対応を適用した結果。どれにも当たらない・変わらないなら None。
+pub fn apply_mappings(before: &Janre, mappings: &[GenreMapping]) -> Option<Janre> {
+    let m = mappings.iter().find(|m| m.matches(before))?;
+    let mut after = before.clone();
+    if !m.to_main.trim().is_empty() {
+        after.main = m.to_main.trim().to_string();
+    }
+    let (from_sub, to_sub) = (m.from_sub.trim(), m.to_sub.trim());
+    if !from_sub.is_empty() {
+        let mut sub = Vec::new();
+        for s in &before.sub {
+            let s = if s == from_sub { to_sub } else { s.as_str() };
+            if !s.is_empty() && !sub.iter().any(|x| x == s) {
+                sub.push(s.to_string());
+            }
+        }
+        after.sub = sub;
+    }
+    (after != *before).then_some(after)
+}
+
+#[derive(serde::Serialize)]
+struct RecordRef {
+    filename: String,
+    display_label: String,
+}
+
+#[derive(serde::Serialize)]
+struct GenreUsage {
+    main: String,
+    /// Sub なしのレコードは空文字
+    sub: String,
+    records: Vec<RecordRef>,
+}
+
+/// `GET /api/genres` (Main, Sub) ごとの該当レコード。Sub が複数あるレコードはそれぞれに出る。
+#[utoipa::path(get, path = "/api/genres", tag = "maintenance", summary = "ジャンルの使われ方", params(LibraryQuery))]
+pub async fn get_usage(Lib(library): Lib) -> impl IntoResponse {
+    let Some(records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    let mut usage: BTreeMap<(String, String), Vec<RecordRef>> = BTreeMap::new();
+    for (filename, v) in &records {
+        let j = Janre::of(v);
+        let subs = if j.sub.is_empty() { vec![String::new()] } else { j.sub.clone() };
+        for sub in subs {
+            usage.entry((j.main.clone(), sub)).or_default().push(RecordRef {
+                filename: filename.clone(),
+                display_label: display_label_from_value(v),
+            });
+        }
+    }
+    let list: Vec<GenreUsage> = usage
+        .into_iter()
+        .map(|((main, sub), records)| GenreUsage { main, sub, records })
+        .collect();
+    (StatusCode::OK, Json(list)).into_response()
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[schema(as = GenreMigrateBody)]
+pub struct MigrateBody {
+    mappings: Vec<GenreMapping>,
+    /// true なら書き込まずに結果だけ返す
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(serde::Serialize)]
+struct Change {
+    filename: String,
+    display_label: String,
+    before: Janre,
+    after: Janre,
+    /// 付け替えで Sub が空になった（保存時のチェックで弾かれる）
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    sub_emptied: bool,
+}
+
+#[derive(Default, serde::Serialize)]
+struct MigrateReport {
+    dry_run: bool,
+    changes: Vec<Change>,
+    /// 書き込みに失敗したもの（ファイル名, 理由）
+    failed: Vec<(String, String)>,
+}
+
+/// `POST /api/genres/migrate` 対応表をまとめて適用する。
+#[utoipa::path(
+    post,
+    path = "/api/genres/migrate",
+    tag = "maintenance",
+    summary = "ジャンルを付け替える",
+    params(LibraryQuery),
+    request_body = MigrateBody,
+)]
+pub async fn migrate(Lib(library): Lib, Json(body): Json<MigrateBody>) -> impl IntoResponse {
+    if body.mappings.iter().any(|m| m.from_main.trim().is_empty()) {
+        return error(StatusCode::BAD_REQUEST, "from_main is required");
+    }
+    let Some(records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    let mut report = MigrateReport { dry_run: body.dry_run, ..Default::default() };
+    for (filename, mut v) in records {
+        let before = Janre::of(&v);
+        let Some(after) = apply_mappings(&before, &body.mappings) else {
+            continue;
+        };
+        if !body.dry_run {
+            // 読んだあとにほかで変わっていたら書かない（版で確かめる）
+            let read = Precondition::Version(versions::version_of(&v));
+            v["janre"] = serde_json::json!({"main": after.main, "sub": after.sub});
+            if let Err(e) = versions::write(&*library.storage, &filename, &read, &v) {
+                report.failed.push((filename, e.to_string()));
+                continue;
+            }
+        }
+        report.changes.push(Change {
+            display_label: display_label_from_value(&v),
+            sub_emptied: after.sub.is_empty() && !before.sub.is_empty(),
+            filename,
+            before,
+            after,
+        });
+    }
+    (StatusCode::OK, Json(report)).into_response()
+}
+
+#[cfg(test)]
+mod genres_tests {
+    use super::{apply_mappings, GenreMapping, Janre};
+
+    fn janre(main: &str, sub: &[&str]) -> Janre {
+        Janre { main: main.into(), sub: sub.iter().map(|s| s.to_string()).collect() }
+    }
+
+    #[test]
+    fn sub_genre_moves_to_new_main() {
+        let m = vec![GenreMapping {
+            from_main: "Fusion".into(),
+            from_sub: "Rock".into(),
+            to_main: "Fusion Rock".into(),
+            to_sub: "Rock".into(),
+        }];
+        let after = apply_mappings(&janre("Fusion", &["Funk", "Rock"]), &m).unwrap();
+        assert_eq!(after, janre("Fusion Rock", &["Funk", "Rock"]));
+        assert_eq!(apply_mappings(&janre("Fusion", &["Funk"]), &m), None);
+    }
+
+    #[test]
+    fn renaming_sub_dedupes_and_empty_target_removes() {
+        let rename = vec![GenreMapping {
+            from_main: "Jazz".into(),
+            from_sub: "Avrant-Garde".into(),
+            to_sub: "Free".into(),
+            ..Default::default()
+        }];
+        assert_eq!(apply_mappings(&janre("Jazz", &["Avrant-Garde", "Free"]), &rename), Some(janre("Jazz", &["Free"])));
+        let remove = vec![GenreMapping { from_main: "Jazz".into(), from_sub: "Mode".into(), ..Default::default() }];
+        assert_eq!(apply_mappings(&janre("Jazz", &["Mode"]), &remove), Some(janre("Jazz", &[])));
+    }
+}
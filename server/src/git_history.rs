@@ -0,0 +1,512 @@
+//! git によるレコードの履歴。`--git-history`（環境変数 `GIT_HISTORY`）なら、サーバー経由の保存・削除・名前の変更のたびに
+//! そのレコードのファイルだけを DB のディレクトリの git リポジトリにコミットする（メッセージはファイル名と表示ラベル）。
+//! コミットは保存の応答を待たせないよう別のスレッド（`CommitQueue`）で行い、続けて来た変更（一括の書き換えなど）は
+//! `BATCH_WINDOW` の間隔で切れるまで 1 つのコミットにまとめる。履歴の一覧と終了の前には積んであるものを先にコミットする。
+//! DB のディレクトリがまだ git の管理下になければ `git init` する。既にほかのリポジトリの中なら、そのリポジトリに
+//! レコードのファイルだけをコミットする（ほかに add してあるものは巻き込まない）。
+//! カバー・メモ・設定はコミットしない。`git remote` を足して `git push` すれば、ほかの場所にも残せる。
+//!
+//! - `GET /api/history/{name}` そのレコードのコミットの一覧（新しい順、`?limit=` 既定 100）
+//!
+//! git のコマンドが失敗しても保存は止めない（標準エラーに出すだけ）。`db/` を直接編集したものはコミットしない。
+//! `--history-keep` / `--history-max-days` なら、retention.rs の整理で古いコミットを消す（`GitRepo::prune`）。
+
+use axum::{
+    extract::{Path as UrlPath, Query},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::ffi::OsStr;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::{mpsc, Arc, Mutex, PoisonError};
+use std::time::{Duration, SystemTime};
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+use crate::storage::{FsStorage, Storage};
+
+/// git のディレクトリ（バックアップでは写さない）
+pub const GIT_DIR: &str = ".git";
+/// 履歴の一覧の既定の件数
+const DEFAULT_LIMIT: usize = 100;
+/// user.name / user.email を設定していないときのコミットの作者
+const FALLBACK_AUTHOR: (&str, &str) = ("nekokan_music", "nekokan_music@localhost");
+/// この間に次の変更が来れば同じコミットにまとめる
+const BATCH_WINDOW: Duration = Duration::from_millis(500);
+/// 1 つのコミットにまとめる変更の上限（コマンドラインが長くなりすぎないように）
+const MAX_BATCH: usize = 200;
+
+/// レコードをコミットするリポジトリ（ライブラリのディレクトリごと）
+pub struct GitRepo {
+    /// ライブラリのディレクトリ（git はここで動かす）
+    root: PathBuf,
+    /// 作者を設定していないリポジトリなら代わりに使う
+    fallback_author: bool,
+    /// index を同時に触らないように
+    lock: Mutex<()>,
+}
+
+impl GitRepo {
+    /// `root` のリポジトリを開く。git の管理下になければ `git init` する
+    pub fn open(root: &Path) -> Result<Self, String> {
+        let mut repo = Self { root: root.to_path_buf(), fallback_author: false, lock: Mutex::new(()) };
+        if !repo.git(&["rev-parse", "--is-inside-work-tree"]).is_ok_and(|o| o.status.success()) {
+            std::fs::create_dir_all(root).map_err(|e| e.to_string())?;
+            repo.run(&["init", "--quiet"])?;
+        }
+        repo.fallback_author = !repo.git(&["config", "user.email"]).is_ok_and(|o| o.status.success());
+        Ok(repo)
+    }
+
+    fn git<S: AsRef<OsStr>>(&self, args: &[S]) -> io::Result<Output> {
+        self.git_with(args, &[])
+    }
+
+    /// `envs` は作者・日時など、git に渡す環境変数（作者を設定していないときの代わりより優先する）
+    fn git_with<S: AsRef<OsStr>>(&self, args: &[S], envs: &[(&str, &str)]) -> io::Result<Output> {
+        let mut cmd = Command::new("git");
+        // ファイル名の [ ] * をパターンとして読ませない
+        cmd.current_dir(&self.root).env("GIT_LITERAL_PATHSPECS", "1").args(args);
+        if self.fallback_author {
+            let (name, email) = FALLBACK_AUTHOR;
+            cmd.env("GIT_AUTHOR_NAME", name)
+                .env("GIT_AUTHOR_EMAIL", email)
+                .env("GIT_COMMITTER_NAME", name)
+                .env("GIT_COMMITTER_EMAIL", email);
+        }
+        cmd.envs(envs.iter().copied()).output()
+    }
+
+    /// 成功したら標準出力、失敗したら標準エラー
+    fn run<S: AsRef<OsStr>>(&self, args: &[S]) -> Result<String, String> {
+        self.run_with(args, &[])
+    }
+
+    fn run_with<S: AsRef<OsStr>>(&self, args: &[S], envs: &[(&str, &str)]) -> Result<String, String> {
+        let out = self.git_with(args, envs).map_err(|e| format!("git を実行できません: {}", e))?;
+        if out.status.success() {
+            Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+        } else {
+            let cmd = args.first().map(|a| a.as_ref().to_string_lossy().into_owned()).unwrap_or_default();
+            Err(format!("git {}: {}", cmd, String::from_utf8_lossy(&out.stderr).trim()))
+        }
+    }
+
+    /// `paths`（`root` からの相対パス）の今の状態をコミットする。変わっていなければ何もしない
+    pub fn commit(&self, paths: &[String], message: &str) -> Result<(), String> {
+        let _lock = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let with_paths = |cmd: &[&str], paths: &[String]| -> Vec<String> {
+            cmd.iter().map(|s| s.to_string()).chain(["--".to_string()]).chain(paths.iter().cloned()).collect()
+        };
+        // 今あるものと、前にコミットしたもの（消した・移したもの）だけを対象にする
+        let tracked = self.run(&with_paths(&["ls-files", "-z"], paths))?;
+        let (on_disk, gone): (Vec<String>, Vec<String>) = paths.iter().cloned().partition(|p| self.root.join(p).is_file());
+        let mut targets: Vec<String> = gone.into_iter().filter(|p| tracked.split('\0').any(|l| l == p)).collect();
+        if !on_disk.is_empty() {
+            self.run(&with_paths(&["add"], &on_disk))?;
+            targets.extend(on_disk);
+        }
+        if targets.is_empty() || self.run(&with_paths(&["status", "--porcelain"], &targets))?.trim().is_empty() {
+            return Ok(());
+        }
+        self.run(&with_paths(&["commit", "--quiet", "--only", "-m", message], &targets)).map(|_| ())
+    }
+
+    /// 続けて来た変更をまとめてコミットする。メッセージは 1 件ならそのまま、複数なら件数と最初のもの、本文に全部
+    fn commit_batch(&self, batch: &[Pending]) -> Result<(), String> {
+        let Some(first) = batch.first() else {
+            return Ok(());
+        };
+        let mut paths: Vec<String> = Vec::new();
+        for path in batch.iter().flat_map(|p| &p.paths) {
+            if !paths.contains(path) {
+                paths.push(path.clone());
+            }
+        }
+        let message = match batch {
+            [one] => one.message.clone(),
+            _ => {
+                let body: Vec<&str> = batch.iter().map(|p| p.message.as_str()).collect();
+                format!("{} 件の変更（{} ほか）\n\n{}", batch.len(), first.message, body.join("\n"))
+            }
+        };
+        self.commit(&paths, &message)
+    }
+
+    /// 新しい `keep` 個（0 なら数では消さない）と `cutoff`（UNIX 時刻の秒）より後のコミットだけを残すよう履歴を作り直し、
+    /// 古いコミットを `git gc` で消す。HEAD は古くても残す。残すいちばん古いコミットを根にして、その先を同じ中身・作者・日時・
+    /// メッセージで積み直すので、コミットの ID は変わる（`git push` 済みなら次は `--force` が要る）。
+    /// ほかのリポジトリの中にあるとき（そのリポジトリの履歴を書き換えてしまう）は整理しない。消したコミットの数
+    pub fn prune(&self, keep: usize, cutoff: Option<u64>) -> Result<usize, String> {
+        let _lock = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        if !self.git(&["rev-parse", "--verify", "--quiet", "HEAD"]).is_ok_and(|o| o.status.success()) {
+            return Ok(0);
+        }
+        let top = PathBuf::from(self.run(&["rev-parse", "--show-toplevel"])?.trim());
+        if std::fs::canonicalize(&top).ok() != std::fs::canonicalize(&self.root).ok() {
+            return Err(format!("{} のリポジトリの履歴は整理しません", top.display()));
+        }
+        let log = self.run(&["log", "--first-parent", "--format=%H%x1f%T%x1f%ct%x1f%an%x1f%ae%x1f%aI%x1f%cn%x1f%ce%x1f%cI%x1f%B%x1e"])?;
+        let commits: Vec<Vec<&str>> =
+            log.split('\u{1e}').map(|c| c.trim_start_matches('\n').splitn(10, '\u{1f}').collect()).filter(|f: &Vec<&str>| f.len() == 10).collect();
+        let kept = commits
+            .iter()
+            .enumerate()
+            .take_while(|(i, c)| {
+                let time = c[2].parse::<u64>().unwrap_or(u64::MAX);
+                *i == 0 || ((keep == 0 || *i < keep) && cutoff.is_none_or(|cutoff| time >= cutoff))
+            })
+            .count();
+        if kept == commits.len() {
+            return Ok(0);
+        }
+        let mut parent: Option<String> = None;
+        for c in commits[..kept].iter().rev() {
+            let envs = [
+                ("GIT_AUTHOR_NAME", c[3]),
+                ("GIT_AUTHOR_EMAIL", c[4]),
+                ("GIT_AUTHOR_DATE", c[5]),
+                ("GIT_COMMITTER_NAME", c[6]),
+                ("GIT_COMMITTER_EMAIL", c[7]),
+                ("GIT_COMMITTER_DATE", c[8]),
+            ];
+            let mut args = vec!["commit-tree", c[1], "-m", c[9].trim_end()];
+            if let Some(p) = &parent {
+                args.extend(["-p", p.as_str()]);
+            }
+            parent = Some(self.run_with(&args, &envs)?.trim().to_string());
+        }
+        let new_head = parent.unwrap_or_default();
+        self.run(&["update-ref", "-m", "nekokan_music: 履歴の整理", "HEAD", &new_head, commits[0][0]])?;
+        self.run(&["reflog", "expire", "--expire=now", "--all"])?;
+        self.run(&["gc", "--prune=now", "--quiet"])?;
+        Ok(commits.len() - kept)
+    }
+
+    /// `.git` の大きさ（バイト）
+    pub fn size(&self) -> u64 {
+        crate::backup::dir_size(&self.root.join(GIT_DIR)).map(|(_, bytes)| bytes).unwrap_or(0)
+    }
+
+    /// `paths` のどれかを変えたコミット（新しい順）
+    pub fn log(&self, paths: &[String], limit: usize) -> Result<Vec<HistoryEntry>, String> {
+        // まだ 1 つもコミットがなければ空
+        if !self.git(&["rev-parse", "--verify", "--quiet", "HEAD"]).is_ok_and(|o| o.status.success()) {
+            return Ok(Vec::new());
+        }
+        let max = format!("--max-count={}", limit);
+        let mut args = vec!["log", "--format=%H%x1f%aI%x1f%an%x1f%s", max.as_str(), "--"];
+        args.extend(paths.iter().map(String::as_str));
+        Ok(self.run(&args)?.lines().filter_map(parse_log_line).collect())
+    }
+}
+
+/// コミット待ちの変更
+struct Pending {
+    paths: Vec<String>,
+    message: String,
+}
+
+enum Job {
+    Commit(Pending),
+    /// それまでに積んだものをコミットしたら知らせる
+    Flush(mpsc::Sender<()>),
+}
+
+/// コミットを別のスレッドで順に行う待ち行列（ライブラリごとに 1 つ）
+pub struct CommitQueue {
+    tx: mpsc::Sender<Job>,
+}
+
+impl CommitQueue {
+    /// `repo` にコミットするスレッドを立てる
+    pub fn start(repo: Arc<GitRepo>) -> Self {
+        let (tx, rx) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("git-history".into())
+            .spawn(move || run_queue(&repo, &rx))
+            .expect("git の履歴のスレッドを起動できません");
+        Self { tx }
+    }
+
+    fn push(&self, paths: Vec<String>, message: String) {
+        let _ = self.tx.send(Job::Commit(Pending { paths, message }));
+    }
+
+    /// 積んであるものをコミットし終わるまで待つ
+    pub fn flush(&self) {
+        let (done, wait) = mpsc::channel();
+        if self.tx.send(Job::Flush(done)).is_ok() {
+            let _ = wait.recv();
+        }
+    }
+}
+
+/// 変更を受け取り、`BATCH_WINDOW` 以内に続けて来たもの（`MAX_BATCH` まで）をまとめてコミットする
+fn run_queue(repo: &GitRepo, rx: &mpsc::Receiver<Job>) {
+    while let Ok(job) = rx.recv() {
+        let (mut batch, mut waiting) = (Vec::new(), Vec::new());
+        let mut next = Some(job);
+        while let Some(job) = next {
+            match job {
+                Job::Commit(pending) => batch.push(pending),
+                Job::Flush(done) => waiting.push(done),
+            }
+            next = if batch.len() >= MAX_BATCH {
+                None
+            } else if waiting.is_empty() {
+                rx.recv_timeout(BATCH_WINDOW).ok()
+            } else {
+                // 待っている人がいれば、もう届いているものだけ入れてすぐコミットする
+                rx.try_recv().ok()
+            };
+        }
+        if let Err(e) = repo.commit_batch(&batch) {
+            let files: Vec<&str> = batch.iter().flat_map(|p| &p.paths).map(String::as_str).collect();
+            eprintln!("{} を git にコミットできませんでした: {}", files.join(", "), e);
+        }
+        for done in waiting {
+            let _ = done.send(());
+        }
+    }
+}
+
+/// 履歴の 1 件
+#[derive(Clone, Debug, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct HistoryEntry {
+    pub commit: String,
+    /// コミットした日時（ISO 8601）
+    pub at: String,
+    pub author: String,
+    pub message: String,
+}
+
+/// `git log --format=%H%x1f%aI%x1f%an%x1f%s` の 1 行を読む
+fn parse_log_line(line: &str) -> Option<HistoryEntry> {
+    let mut it = line.splitn(4, '\u{1f}');
+    let (commit, at, author, message) = (it.next()?, it.next()?, it.next()?, it.next()?);
+    (!commit.is_empty()).then(|| HistoryEntry {
+        commit: commit.to_string(),
+        at: at.to_string(),
+        author: author.to_string(),
+        message: message.to_string(),
+    })
+}
+
+/// コミットメッセージ（"更新: a.json（Artist / Title）"）
+pub fn commit_message(action: &str, filename: &str, label: Option<&str>) -> String {
+    match label.map(str::trim).filter(|l| !l.is_empty()) {
+        Some(label) => format!("{}: {}（{}）", action, filename, label),
+        None => format!("{}: {}", action, filename),
+    }
+}
+
+/// レコードが置かれうる場所（ライブラリのディレクトリからの相対パス）
+fn record_paths(storage: &FsStorage, filenames: &[&str]) -> Vec<String> {
+    filenames
+        .iter()
+        .flat_map(|f| storage.record_paths(f))
+        .filter_map(|p| p.strip_prefix(storage.root()).ok().map(|p| p.to_string_lossy().replace('\\', "/")))
+        .collect()
+}
+
+/// 書き込み・削除・名前の変更のたびに、そのレコードのファイルのコミットを積むストレージ（`queue` がなければそのまま）
+pub struct Committing {
+    pub inner: FsStorage,
+    pub queue: Option<Arc<CommitQueue>>,
+}
+
+impl Committing {
+    fn label_of(&self, filename: &str) -> Option<String> {
+        crate::storage::read_record(&self.inner, filename).ok().map(|v| crate::display_label_from_value(&v))
+    }
+
+    fn commit(&self, filenames: &[&str], message: impl FnOnce() -> String) {
+        if let Some(queue) = &self.queue {
+            queue.push(record_paths(&self.inner, filenames), message());
+        }
+    }
+}
+
+impl Storage for Committing {
+    fn list(&self) -> io::Result<Vec<String>> {
+        self.inner.list()
+    }
+
+    fn read(&self, filename: &str) -> io::Result<Vec<u8>> {
+        self.inner.read(filename)
+    }
+
+    fn write(&self, filename: &str, data: &[u8]) -> io::Result<()> {
+        let existed = self.queue.is_some() && self.inner.exists(filename);
+        self.inner.write(filename, data)?;
+        self.commit(&[filename], || {
+            let label = serde_json::from_slice(data).ok().map(|v| crate::display_label_from_value(&v));
+            commit_message(if existed { "更新" } else { "追加" }, filename, label.as_deref())
+        });
+        Ok(())
+    }
+
+    fn modified(&self, filename: &str) -> io::Result<SystemTime> {
+        self.inner.modified(filename)
+    }
+
+    fn remove(&self, filename: &str) -> io::Result<()> {
+        let label = self.queue.as_ref().and_then(|_| self.label_of(filename));
+        self.inner.remove(filename)?;
+        self.commit(&[filename], || commit_message("削除", filename, label.as_deref()));
+        Ok(())
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        self.inner.rename(from, to)?;
+        self.commit(&[from, to], || {
+            commit_message("名前の変更", &format!("{} → {}", from, to), self.label_of(to).as_deref())
+        });
+        Ok(())
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct HistoryQuery {
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+/// `GET /api/history/{name}` レコードのコミットの一覧
+#[utoipa::path(
+    get,
+    path = "/api/history/{name}",
+    tag = "records",
+    summary = "レコードの git の履歴（--git-history のとき）",
+    params(LibraryQuery),
+    responses((status = 200, description = "新しい順", body = Vec<HistoryEntry>)),
+)]
+pub async fn get_history(
+    Lib(library): Lib,
+    UrlPath(name): UrlPath<String>,
+    Query(q): Query<HistoryQuery>,
+) -> Response {
+    if name.contains("..") || name.contains(['/', '\\']) || !name.ends_with(".json") {
+        return error(StatusCode::BAD_REQUEST, "invalid path");
+    }
+    let Some(history) = library.history.clone() else {
+        return error(StatusCode::NOT_FOUND, "git の履歴（--git-history）を有効にしていません");
+    };
+    let limit = q.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, 1000);
+    let res = tokio::task::spawn_blocking(move || {
+        // さっきの保存もコミットしてから一覧にする
+        history.queue.flush();
+        history.repo.log(&record_paths(&history.layout, &[&name]), limit)
+    })
+    .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    match res {
+        Ok(entries) => Json(entries).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// ライブラリの履歴（リポジトリとレコードの置き方）
+pub struct LibraryHistory {
+    pub repo: Arc<GitRepo>,
+    /// 書き込みのコミットを積む先
+    pub queue: Arc<CommitQueue>,
+    /// レコードの置き場所を知るためのもの（書き込みには使わない）
+    pub layout: FsStorage,
+}
+
+#[cfg(test)]
+mod git_history_tests {
+    use super::{commit_message, parse_log_line, record_paths, CommitQueue, Committing, GitRepo};
+    use crate::storage::{FsStorage, Layout, Storage};
+    use std::sync::Arc;
+
+    #[test]
+    fn log_lines_and_messages() {
+        let e = parse_log_line("abc\u{1f}2026-10-16T12:00:00+09:00\u{1f}neko\u{1f}更新: a.json（A / B）").unwrap();
+        assert_eq!((e.commit.as_str(), e.author.as_str(), e.message.as_str()), ("abc", "neko", "更新: a.json（A / B）"));
+        assert!(parse_log_line("").is_none());
+        assert_eq!(commit_message("削除", "a.json", Some(" ")), "削除: a.json");
+    }
+
+    #[test]
+    fn saves_are_committed_per_record() {
+        if std::process::Command::new("git").arg("--version").output().is_err() {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Arc::new(GitRepo::open(dir.path()).unwrap());
+        let queue = Arc::new(CommitQueue::start(repo.clone()));
+        let storage = Committing { inner: FsStorage::new(dir.path(), Layout::Prefix, false), queue: Some(queue.clone()) };
+        // 1 件ずつ待てば 1 件ずつのコミットになる
+        let step = |f: &dyn Fn(&Committing)| {
+            f(&storage);
+            queue.flush();
+        };
+        step(&|s| s.write("a[1].json", br#"{"title":"A"}"#).unwrap());
+        step(&|s| s.write("a[1].json", br#"{"title":"B"}"#).unwrap());
+        // 中身が同じならコミットしない
+        step(&|s| s.write("a[1].json", br#"{"title":"B"}"#).unwrap());
+        step(&|s| s.write("b.json", br#"{"title":"C"}"#).unwrap());
+        step(&|s| s.rename("a[1].json", "c.json").unwrap());
+        step(&|s| s.remove("b.json").unwrap());
+        let log = repo.log(&record_paths(&storage.inner, &["a[1].json"]), 10).unwrap();
+        let messages: Vec<&str> = log.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        assert!(messages[0].starts_with("名前の変更: a[1].json → c.json"));
+        assert!(messages[1].starts_with("更新: a[1].json"));
+        assert!(messages[2].starts_with("追加: a[1].json"));
+        let log = repo.log(&record_paths(&storage.inner, &["b.json"]), 10).unwrap();
+        assert!(log[0].message.starts_with("削除: b.json"));
+        assert_eq!(repo.log(&record_paths(&storage.inner, &["b.json"]), 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn changes_in_a_burst_become_one_commit() {
+        if std::process::Command::new("git").arg("--version").output().is_err() {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Arc::new(GitRepo::open(dir.path()).unwrap());
+        let queue = Arc::new(CommitQueue::start(repo.clone()));
+        let storage = Committing { inner: FsStorage::new(dir.path(), Layout::Prefix, false), queue: Some(queue.clone()) };
+        for name in ["a.json", "b.json", "c.json"] {
+            storage.write(name, br#"{"title":"A"}"#).unwrap();
+        }
+        storage.write("a.json", br#"{"title":"B"}"#).unwrap();
+        queue.flush();
+        let log = repo.log(&record_paths(&storage.inner, &["a.json", "b.json", "c.json"]), 10).unwrap();
+        assert_eq!(log.len(), 1, "{:?}", log);
+        assert!(log[0].message.starts_with("4 件の変更（追加: a.json"), "{}", log[0].message);
+        assert_eq!(storage.read("a.json").unwrap(), br#"{"title":"B"}"#);
+        assert!(repo.run(&["status", "--porcelain"]).unwrap().trim().is_empty());
+    }
+
+    #[test]
+    fn old_commits_are_pruned() {
+        if std::process::Command::new("git").arg("--version").output().is_err() {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let repo = GitRepo::open(dir.path()).unwrap();
+        let storage = FsStorage::new(dir.path(), Layout::Flat, false);
+        for title in ["A", "B", "C", "D"] {
+            storage.write("a.json", format!(r#"{{"title":"{}"}}"#, title).as_bytes()).unwrap();
+            repo.commit(&record_paths(&storage, &["a.json"]), &format!("更新: a.json（{}）", title)).unwrap();
+        }
+        let paths = record_paths(&storage, &["a.json"]);
+        assert_eq!(repo.prune(0, None).unwrap(), 0);
+        assert_eq!(repo.prune(2, None).unwrap(), 2);
+        let messages: Vec<String> = repo.log(&paths, 10).unwrap().into_iter().map(|e| e.message).collect();
+        assert_eq!(messages, ["更新: a.json（D）", "更新: a.json（C）"]);
+        // いちばん新しいものは古くても残す
+        assert_eq!(repo.prune(0, Some(u64::MAX)).unwrap(), 1);
+        assert_eq!(repo.log(&paths, 10).unwrap().len(), 1);
+        assert!(repo.run(&["status", "--porcelain"]).unwrap().trim().is_empty());
+        assert_eq!(storage.read("a.json").unwrap(), br#"{"title":"D"}"#);
+    }
+}
@@ -0,0 +1,180 @@
+//! コレクションの目標（「今年 100 枚聴く」「スコア 6 のレコードを全部仕上げる」など）。
+//! 目標は `{DB_PATH}/.config/goals.json` に置き、進捗は取得のたびに再生記録・完成度から数える。
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+use crate::completeness::completeness_from_value;
+use crate::covers::CoverFiles;
+use crate::date;
+use crate::libraries::{Lib, LibraryQuery};
+use crate::listen::write_json;
+use crate::problem::{error, field_error};
+use crate::settings::{config_path, CONFIG_DIR};
+use crate::{load_all_records, AppState};
+
+const GOALS_FILE: &str = "goals.json";
+
+/// 目標の種類と条件
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GoalKind {
+    /// 期間内（YYYY/MM/DD、両端を含む）に `target` 枚のレコードを聴く（同じレコードは 1 枚と数える）
+    Listen {
+        target: usize,
+        #[serde(default)]
+        from: Option<String>,
+        #[serde(default)]
+        to: Option<String>,
+    },
+    /// 条件に合うレコードをすべて完成度 100% にする。条件を省略したものは絞り込まない。
+    Complete {
+        #[serde(default)]
+        score: Option<f64>,
+        #[serde(default)]
+        genre: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Goal {
+    pub title: String,
+    #[serde(flatten)]
+    pub kind: GoalKind,
+}
+
+#[derive(serde::Serialize)]
+struct GoalProgress {
+    #[serde(flatten)]
+    goal: Goal,
+    current: usize,
+    /// 目標値（聴く枚数、または条件に合うレコード数）
+    total: usize,
+}
+
+fn load_goals(db_path: &Path) -> Result<Vec<Goal>, String> {
+    match fs::read_to_string(config_path(db_path, GOALS_FILE)) {
+        Ok(data) => serde_json::from_str(&data).map_err(|e| format!("invalid goals: {}", e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// 目標の (現在値, 目標値)
+pub fn progress(kind: &GoalKind, records: &[(String, Value)], covers: &CoverFiles) -> (usize, usize) {
+    match kind {
+        GoalKind::Listen { target, from, to } => {
+            let in_range = |d: &str| {
+                from.as_deref().is_none_or(|f| d >= f) && to.as_deref().is_none_or(|t| d <= t)
+            };
+            let listened = records
+                .iter()
+                .filter(|(_, v)| {
+                    v["listens"]
+                        .as_array()
+                        .is_some_and(|a| a.iter().filter_map(Value::as_str).any(in_range))
+                })
+                .count();
+            (listened, *target)
+        }
+        GoalKind::Complete { score, genre } => {
+            let targets: Vec<&Value> = records
+                .iter()
+                .map(|(_, v)| v)
+                .filter(|v| score.is_none_or(|s| v["score"].as_f64() == Some(s)))
+                .filter(|v| genre.as_deref().is_none_or(|g| v["janre"]["main"].as_str() == Some(g)))
+                .collect();
+            let done = targets.iter().filter(|v| completeness_from_value(v, covers.contains_cover_of(v)) == 100).count();
+            (done, targets.len())
+        }
+    }
+}
+
+/// `GET /api/goals` 目標と進捗を返す。
+#[utoipa::path(get, path = "/api/goals", tag = "reports", summary = "目標", params(LibraryQuery))]
+pub async fn get_goals(State(state): State<AppState>, Lib(library): Lib) -> impl IntoResponse {
+    let goals = match load_goals(&state.db_path) {
+        Ok(g) => g,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    let Some(records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    let covers = crate::covers::cover_files(&library.dir);
+    let list: Vec<GoalProgress> = goals
+        .into_iter()
+        .map(|goal| {
+            let (current, total) = progress(&goal.kind, &records, &covers);
+            GoalProgress { goal, current, total }
+        })
+        .collect();
+    (StatusCode::OK, Json(list)).into_response()
+}
+
+/// `POST /api/goals` 目標の一覧を置き換える。
+#[utoipa::path(
+    post,
+    path = "/api/goals",
+    tag = "reports",
+    summary = "目標を保存する",
+    params(LibraryQuery),
+    request_body = serde_json::Value,
+)]
+pub async fn save_goals(State(state): State<AppState>, Json(goals): Json<Vec<Goal>>) -> impl IntoResponse {
+    for g in &goals {
+        if g.title.trim().is_empty() {
+            return field_error("title", "is required");
+        }
+        if let GoalKind::Listen { target, from, to } = &g.kind {
+            if *target == 0 {
+                return error(StatusCode::BAD_REQUEST, "target must be at least 1");
+            }
+            if from.iter().chain(to.iter()).any(|d| !date::is_valid(d)) {
+                return error(StatusCode::BAD_REQUEST, "from/to must be YYYY/MM/DD");
+            }
+        }
+    }
+    let path = config_path(&state.db_path, GOALS_FILE);
+    let res = fs::create_dir_all(state.db_path.join(CONFIG_DIR))
+        .map_err(|e| e.to_string())
+        .and_then(|_| serde_json::to_value(&goals).map_err(|e| e.to_string()))
+        .and_then(|v| write_json(&path, &v));
+    match res {
+        Ok(()) => (StatusCode::OK, Json(serde_json::json!({"ok": true}))).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+#[cfg(test)]
+mod goals_tests {
+    use super::{progress, GoalKind};
+    use crate::covers::CoverFiles;
+    use serde_json::json;
+
+    #[test]
+    fn listen_goal_counts_distinct_records_in_range() {
+        let records = vec![
+            ("a.json".to_string(), json!({"listens": ["2026/01/05", "2026/02/01"]})),
+            ("b.json".to_string(), json!({"listens": ["2025/12/31"]})),
+            ("c.json".to_string(), json!({})),
+        ];
+        let kind = GoalKind::Listen {
+            target: 100,
+            from: Some("2026/01/01".into()),
+            to: Some("2026/12/31".into()),
+        };
+        assert_eq!(progress(&kind, &records, &CoverFiles::default()), (1, 100));
+    }
+
+    #[test]
+    fn complete_goal_filters_by_score() {
+        let records = vec![
+            ("a.json".to_string(), json!({"score": 6, "title": "A"})),
+            ("b.json".to_string(), json!({"score": 5})),
+        ];
+        let kind = GoalKind::Complete { score: Some(6.0), genre: None };
+        assert_eq!(progress(&kind, &records, &CoverFiles::default()), (0, 1));
+    }
+}
@@ -0,0 +1,175 @@
+//! personnel から共演ネットワークを作り、Gephi などで読める GraphML / DOT で書き出す。
+//! ノードはアーティスト、エッジは同じレコードに参加したアーティストの組で、重みは共演したレコード数。
+
+use axum::{
+    extract::Query,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::load_all_records;
+use crate::problem::error;
+
+/// ノードにする（個人の）役割。グループはメンバーを個別に数える。
+const PERSON_ROLES: [&str; 4] = ["leader", "sidemen", "soloists", "conductor"];
+
+/// レコードに参加したアーティスト名（重複なし）
+fn artists_of(v: &Value) -> BTreeSet<String> {
+    let personnel = &v["personnel"];
+    let group_members = personnel["group"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|g| g["members"].as_array())
+        .flatten();
+    PERSON_ROLES
+        .iter()
+        .filter_map(|role| personnel[role].as_array())
+        .flatten()
+        .chain(group_members)
+        .filter_map(|e| e["name"].as_str())
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .collect()
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Graph {
+    /// アーティスト名 → 参加レコード数
+    pub nodes: BTreeMap<String, usize>,
+    /// (名前, 名前)（辞書順）→ 共演レコード数
+    pub edges: BTreeMap<(String, String), usize>,
+}
+
+pub fn build_graph<'a>(records: impl IntoIterator<Item = &'a Value>) -> Graph {
+    let mut graph = Graph::default();
+    for v in records {
+        let artists: Vec<String> = artists_of(v).into_iter().collect();
+        for (i, a) in artists.iter().enumerate() {
+            *graph.nodes.entry(a.clone()).or_default() += 1;
+            for b in &artists[i + 1..] {
+                *graph.edges.entry((a.clone(), b.clone())).or_default() += 1;
+            }
+        }
+    }
+    graph
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn to_graphml(graph: &Graph) -> String {
+    let ids: BTreeMap<&str, usize> = graph.nodes.keys().enumerate().map(|(i, n)| (n.as_str(), i)).collect();
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        "  <key id=\"records\" for=\"node\" attr.name=\"records\" attr.type=\"int\"/>\n",
+        "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"double\"/>\n",
+        "  <graph id=\"personnel\" edgedefault=\"undirected\">\n",
+    ));
+    for (name, count) in &graph.nodes {
+        out.push_str(&format!(
+            "    <node id=\"n{}\"><data key=\"label\">{}</data><data key=\"records\">{}</data></node>\n",
+            ids[name.as_str()],
+            xml_escape(name),
+            count
+        ));
+    }
+    for ((a, b), w) in &graph.edges {
+        out.push_str(&format!(
+            "    <edge source=\"n{}\" target=\"n{}\"><data key=\"weight\">{}</data></edge>\n",
+            ids[a.as_str()],
+            ids[b.as_str()],
+            w
+        ));
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+fn dot_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub fn to_dot(graph: &Graph) -> String {
+    let mut out = String::from("graph personnel {\n");
+    for (name, count) in &graph.nodes {
+        out.push_str(&format!("  {} [records={}];\n", dot_quote(name), count));
+    }
+    for ((a, b), w) in &graph.edges {
+        out.push_str(&format!("  {} -- {} [weight={}];\n", dot_quote(a), dot_quote(b), w));
+    }
+    out.push_str("}\n");
+    out
+}
+
+#[derive(serde::Deserialize)]
+pub struct GraphParams {
+    /// "graphml"（既定）または "dot"
+    format: Option<String>,
+    /// 指定したときは Main ジャンルがこれのレコードだけ使う（例: Jazz）
+    genre: Option<String>,
+}
+
+/// `GET /api/export/graph?format=graphml|dot&genre=` 共演ネットワークを書き出す。
+#[utoipa::path(get, path = "/api/export/graph", tag = "import-export", summary = "共演のグラフ", params(LibraryQuery))]
+pub async fn export_graph(Lib(library): Lib, Query(params): Query<GraphParams>) -> impl IntoResponse {
+    let Some(records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    let genre = params.genre.filter(|g| !g.trim().is_empty());
+    let graph = build_graph(
+        records
+            .iter()
+            .map(|(_, v)| v)
+            .filter(|v| genre.as_deref().is_none_or(|g| v["janre"]["main"].as_str() == Some(g))),
+    );
+    let (body, content_type, filename) = match params.format.as_deref().unwrap_or("graphml") {
+        "graphml" => (to_graphml(&graph), "application/graphml+xml; charset=utf-8", "personnel.graphml"),
+        "dot" => (to_dot(&graph), "text/vnd.graphviz; charset=utf-8", "personnel.dot"),
+        _ => {
+            return error(StatusCode::BAD_REQUEST, "format must be graphml or dot");
+        }
+    };
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod graph_tests {
+    use super::{build_graph, to_dot, to_graphml};
+    use serde_json::json;
+
+    #[test]
+    fn edges_are_weighted_by_shared_records() {
+        let a = json!({"personnel": {
+            "leader": [{"name": "Miles Davis"}],
+            "sidemen": [{"name": "Bill Evans"}, {"name": "Paul Chambers"}]
+        }});
+        let b = json!({"personnel": {
+            "group": [{"name": "Quintet", "members": [{"name": "Miles Davis"}, {"name": "Paul Chambers"}]}]
+        }});
+        let g = build_graph([&a, &b]);
+        assert_eq!(g.nodes["Miles Davis"], 2);
+        assert_eq!(g.edges[&("Miles Davis".to_string(), "Paul Chambers".to_string())], 2);
+        assert_eq!(g.edges[&("Bill Evans".to_string(), "Miles Davis".to_string())], 1);
+        assert_eq!(g.edges.len(), 3);
+        assert!(to_dot(&g).contains("\"Miles Davis\" -- \"Paul Chambers\" [weight=2];"));
+        assert!(to_graphml(&g).contains("<data key=\"label\">Bill Evans</data>"));
+    }
+}
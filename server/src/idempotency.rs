@@ -0,0 +1,167 @@
+//! 保存リクエストの冪等キー（`Idempotency-Key` ヘッダー）。
+//! 同じキーの再送には最初の結果をそのまま返し、同じ保存を二重に行わない。
+//! 最初のリクエストがまだ処理中なら、その結果を待って返す。
+//! キーはメソッド・パス・本文のハッシュと組で覚え、同じキーで中身の違うリクエストが来たら実行せずに 422 を返す
+//! （クライアントのキーの使い回しで、別の保存に前の結果が返らないように）。
+
+use axum::http::{HeaderMap, Method, StatusCode, Uri};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+use crate::problem::Problem;
+
+pub const HEADER: &str = "idempotency-key";
+/// 覚えておくキーの数と期間
+const MAX_KEYS: usize = 1000;
+const TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// キーとして受け付ける最大長（UUID なら 36 文字）
+const KEY_MAX_LEN: usize = 128;
+
+pub type Outcome = (StatusCode, Value);
+
+/// リクエストのキーと、その中身のハッシュ
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Key {
+    key: String,
+    fingerprint: String,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// キー → (覚えた時刻, 中身のハッシュ, 結果)
+    cells: HashMap<String, (Instant, String, Arc<OnceCell<Outcome>>)>,
+    /// 古い順
+    order: VecDeque<String>,
+}
+
+#[derive(Default)]
+pub struct IdempotencyCache {
+    inner: Mutex<Inner>,
+}
+
+/// リクエストのキー（なければ None）。`body` は受け取った本文（手を入れる前のもの）
+pub fn key_from(headers: &HeaderMap, method: &Method, uri: &Uri, body: &Value) -> Option<Key> {
+    let key = headers
+        .get(HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|k| !k.is_empty() && k.len() <= KEY_MAX_LEN)?;
+    let target = uri.path_and_query().map_or(uri.path(), |pq| pq.as_str());
+    let fingerprint = crate::sync::value_hash(&json!([method.as_str(), target, body]));
+    Some(Key { key: key.to_string(), fingerprint })
+}
+
+/// 同じキーで中身の違うリクエストへの 422
+fn reused(key: &str) -> Outcome {
+    let problem = Problem::new(StatusCode::UNPROCESSABLE_ENTITY, format!("Idempotency-Key {} は別のリクエストで使われています", key))
+        .code("idempotency_key_reused");
+    (StatusCode::UNPROCESSABLE_ENTITY, problem.to_value())
+}
+
+impl IdempotencyCache {
+    /// キーの結果の入れ物。同じキーが別の中身で覚えられていれば None
+    fn cell(&self, key: &Key, now: Instant) -> Option<Arc<OnceCell<Outcome>>> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        while let Some(oldest) = inner.order.front().cloned() {
+            let expired = inner.cells.get(&oldest).is_none_or(|(t, _, _)| now.duration_since(*t) > TTL);
+            if !expired && inner.order.len() < MAX_KEYS {
+                break;
+            }
+            inner.order.pop_front();
+            inner.cells.remove(&oldest);
+        }
+        if let Some((_, fingerprint, cell)) = inner.cells.get(&key.key) {
+            return (*fingerprint == key.fingerprint).then(|| cell.clone());
+        }
+        let cell = Arc::new(OnceCell::new());
+        inner.cells.insert(key.key.clone(), (now, key.fingerprint.clone(), cell.clone()));
+        inner.order.push_back(key.key.clone());
+        Some(cell)
+    }
+
+    fn forget(&self, key: &str) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.cells.remove(key);
+        inner.order.retain(|k| k != key);
+    }
+
+    /// キーがあれば同じキーで最初に得た結果を返し、なければ `run` を実行する。
+    /// サーバー側の失敗（5xx）は覚えず、再送で改めて実行する。同じキーで中身が違えば実行せずに 422。
+    pub async fn run<F, Fut>(&self, key: Option<Key>, run: F) -> Outcome
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Outcome>,
+    {
+        let Some(key) = key else {
+            return run().await;
+        };
+        let Some(cell) = self.cell(&key, Instant::now()) else {
+            return reused(&key.key);
+        };
+        let outcome = cell.get_or_init(run).await.clone();
+        if outcome.0.is_server_error() {
+            self.forget(&key.key);
+        }
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod idempotency_tests {
+    use super::{key_from, IdempotencyCache, Key};
+    use axum::http::{HeaderMap, Method, StatusCode, Uri};
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn key(k: &str, body: Value) -> Option<Key> {
+        let mut headers = HeaderMap::new();
+        headers.insert("idempotency-key", k.parse().unwrap());
+        key_from(&headers, &Method::POST, &Uri::from_static("/api/save"), &body)
+    }
+
+    #[tokio::test]
+    async fn retries_with_the_same_key_run_once() {
+        let cache = IdempotencyCache::default();
+        let runs = AtomicUsize::new(0);
+        let save = || async {
+            runs.fetch_add(1, Ordering::SeqCst);
+            (StatusCode::OK, json!({"ok": true}))
+        };
+        let key = key("3f1c0c1e-key", json!({"filename": "a", "data": {}}));
+        assert_eq!(cache.run(key.clone(), save).await.0, StatusCode::OK);
+        assert_eq!(cache.run(key, save).await.1, json!({"ok": true}));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        // キーなしは毎回実行する
+        cache.run(None, save).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_not_remembered() {
+        let cache = IdempotencyCache::default();
+        let key = key("k", json!({}));
+        let failed = cache.run(key.clone(), || async { (StatusCode::INTERNAL_SERVER_ERROR, json!({})) }).await;
+        assert_eq!(failed.0, StatusCode::INTERNAL_SERVER_ERROR);
+        let retried = cache.run(key, || async { (StatusCode::OK, json!({"ok": true})) }).await;
+        assert_eq!(retried.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn reusing_a_key_for_another_request_is_rejected() {
+        let cache = IdempotencyCache::default();
+        let saved = cache.run(key("k", json!({"filename": "a"})), || async { (StatusCode::OK, json!({"ok": true})) }).await;
+        assert_eq!(saved.0, StatusCode::OK);
+        let (status, body) = cache.run(key("k", json!({"filename": "b"})), || async { unreachable!() }).await;
+        assert_eq!((status, body["code"].as_str()), (StatusCode::UNPROCESSABLE_ENTITY, Some("idempotency_key_reused")));
+        // 別のライブラリへの同じ本文も別のリクエスト
+        let mut headers = HeaderMap::new();
+        headers.insert("idempotency-key", "k".parse().unwrap());
+        let other = key_from(&headers, &Method::POST, &Uri::from_static("/api/save?library=jazz"), &json!({"filename": "a"}));
+        assert_eq!(cache.run(other, || async { unreachable!() }).await.0, StatusCode::UNPROCESSABLE_ENTITY);
+        // 元のリクエストの再送には最初の結果
+        assert_eq!(cache.run(key("k", json!({"filename": "a"})), || async { unreachable!() }).await, saved);
+    }
+}
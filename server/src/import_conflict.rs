@@ -0,0 +1,180 @@
+//! 取り込みで、入ってくるレコードが既にあるレコードとぶつかったとき（突き合わせで一致した・同じファイル名がある）の扱い。
+//! 設定の `import_conflict` が既定で、取り込みのたびに `conflict` で変えられ、「毎回確かめる」にした行は `decisions`（行 → 扱い）で決める。
+//! 取り込み（いまは Discogs の CSV）はここの `ImportSummary` で、入ってきた 1 件ごとにどうなったかを返す。
+
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::storage::Storage;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// 何もしない
+    Skip,
+    /// 別のファイル名（"xxx_2.json"）で下書きとして作る
+    Draft,
+    /// 既にあるレコードの空の項目だけ埋める（参考リンクは足す）
+    #[default]
+    Merge,
+    /// 書き込まずに `pending` として返し、`decisions` で決めてもらう
+    Ask,
+}
+
+/// 入ってきた 1 件がどうなったか
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// ぶつからずに作った
+    Created,
+    /// ぶつかったので別のファイル名で下書きを作った
+    DraftCopy,
+    /// 既にあるレコードの空の項目を埋めた
+    Merged,
+    /// 既にあるレコードと同じで、埋める項目がなかった
+    Unchanged,
+    /// 扱いが skip だった・読めない行だった
+    Skipped,
+    /// 扱いが ask で、まだ決まっていない
+    Pending,
+    /// 書き込みに失敗した
+    Failed,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ImportEntry {
+    /// 取り込み元での位置（CSV なら行番号）
+    pub line: usize,
+    /// 入ってきたものの見出し（"アーティスト - タイトル"）
+    pub title: String,
+    pub outcome: Outcome,
+    /// 作った・書き換えたレコード
+    pub filename: Option<String>,
+    /// ぶつかった既存のレコード
+    pub existing: Option<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub reason: String,
+}
+
+#[derive(Debug, Default, serde::Serialize)]
+pub struct ImportSummary {
+    /// この取り込みで使った扱い
+    pub policy: ConflictPolicy,
+    pub entries: Vec<ImportEntry>,
+    /// 結果ごとの件数
+    pub counts: BTreeMap<Outcome, usize>,
+}
+
+impl ImportSummary {
+    pub fn new(policy: ConflictPolicy) -> Self {
+        Self { policy, ..Default::default() }
+    }
+
+    pub fn push(&mut self, entry: ImportEntry) {
+        *self.counts.entry(entry.outcome).or_default() += 1;
+        self.entries.push(entry);
+    }
+}
+
+/// 1 回の取り込みの扱い（既定と行ごとの決定）
+#[derive(Clone, Debug, Default, serde::Deserialize, utoipa::ToSchema)]
+pub struct ConflictOptions {
+    /// この取り込みだけの扱い。なければ設定のもの
+    #[serde(default)]
+    pub conflict: Option<ConflictPolicy>,
+    /// 「毎回確かめる」で決めた行ごとの扱い（キーは行番号。ほかの項目と一緒に読むため文字列のまま持つ）
+    #[serde(default)]
+    pub decisions: HashMap<String, ConflictPolicy>,
+}
+
+impl ConflictOptions {
+    /// この取り込みの扱い
+    pub fn policy(&self, db_path: &std::path::Path) -> ConflictPolicy {
+        self.conflict.unwrap_or_else(|| crate::settings::load_settings(db_path).map(|s| s.import_conflict).unwrap_or_default())
+    }
+
+    /// その行の扱い（決めてあればそれ）
+    pub fn for_line(&self, policy: ConflictPolicy, line: usize) -> ConflictPolicy {
+        self.decisions.get(&line.to_string()).copied().unwrap_or(policy)
+    }
+}
+
+fn is_blank(v: &Value) -> bool {
+    match v {
+        Value::Null => true,
+        Value::String(s) => s.trim().is_empty(),
+        Value::Number(n) => n.as_f64() == Some(0.0),
+        Value::Array(a) => a.is_empty(),
+        _ => false,
+    }
+}
+
+/// 既にあるレコードの空の項目を、入ってきたもので埋める。参考リンクは URL が無いものだけ足す。
+/// 下書きの印（`draft`）は入ってきたものからは移さない。何か変えたら true
+pub fn merge_blanks(existing: &mut Value, incoming: &Value) -> bool {
+    let (Some(target), Some(source)) = (existing.as_object_mut(), incoming.as_object()) else {
+        return false;
+    };
+    let mut changed = false;
+    for (key, value) in source {
+        if key == "draft" || is_blank(value) {
+            continue;
+        }
+        if key == "references" {
+            let refs = target.entry(key.clone()).or_insert_with(|| Value::Array(vec![]));
+            let Some(refs) = refs.as_array_mut() else {
+                continue;
+            };
+            for r in value.as_array().into_iter().flatten() {
+                if !refs.iter().any(|x| x["url"] == r["url"]) {
+                    refs.push(r.clone());
+                    changed = true;
+                }
+            }
+            continue;
+        }
+        match target.get(key) {
+            Some(current) if !is_blank(current) => {}
+            _ => {
+                target.insert(key.clone(), value.clone());
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+/// 同じ名前がないファイル名（"xxx.json" → "xxx_2.json"、"xxx_3.json" ...）
+pub fn copy_filename(storage: &dyn Storage, filename: &str) -> Option<String> {
+    let stem = filename.strip_suffix(".json").unwrap_or(filename);
+    (2..1000).map(|n| format!("{}_{}.json", stem, n)).find(|f| !storage.exists(f))
+}
+
+#[cfg(test)]
+mod import_conflict_tests {
+    use super::{merge_blanks, ConflictOptions, ConflictPolicy};
+    use serde_json::json;
+
+    #[test]
+    fn merge_fills_only_blank_fields() {
+        let mut existing = json!({"title": "Kind of Blue", "label": "", "score": 0, "comment": "mine",
+            "references": [{"name": "Discogs", "url": "https://www.discogs.com/release/1"}]});
+        let incoming = json!({"title": "Kind Of Blue", "label": "Columbia", "score": 5, "comment": "theirs", "draft": true,
+            "references": [{"name": "Discogs", "url": "https://www.discogs.com/release/1"}, {"name": "x", "url": "https://x"}]});
+        assert!(merge_blanks(&mut existing, &incoming));
+        assert_eq!(existing["title"], "Kind of Blue");
+        assert_eq!((existing["label"].as_str(), existing["score"].as_i64()), (Some("Columbia"), Some(5)));
+        assert_eq!(existing["comment"], "mine");
+        assert_eq!(existing["references"].as_array().map(Vec::len), Some(2));
+        assert!(existing.get("draft").is_none());
+        assert!(!merge_blanks(&mut existing, &incoming));
+    }
+
+    #[test]
+    fn decisions_override_the_run_policy() {
+        let opts: ConflictOptions = serde_json::from_value(json!({"conflict": "ask", "decisions": {"3": "draft"}})).unwrap();
+        assert_eq!(opts.conflict, Some(ConflictPolicy::Ask));
+        assert_eq!(opts.for_line(ConflictPolicy::Ask, 3), ConflictPolicy::Draft);
+        assert_eq!(opts.for_line(ConflictPolicy::Ask, 4), ConflictPolicy::Ask);
+    }
+}
@@ -0,0 +1,114 @@
+//! レーベルの索引（`GET /api/labels`）。`label` ごとにレコードの数と一覧（発売年・番号順）を返し、Blue Note や ECM から
+//! 手持ちをたどれるようにする。大文字小文字と空白の違いは同じレーベルにまとめ、いちばん多い書き方で出す。
+
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+use crate::{display_label_from_value, int_from_value};
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct LabelAlbum {
+    filename: String,
+    display_label: String,
+    release_year: Option<i64>,
+    /// 番号（`id`、"BLP 1595" など）
+    id: String,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct Label {
+    label: String,
+    count: usize,
+    /// 発売年順（不明は最後）、同じ年は番号順
+    albums: Vec<LabelAlbum>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct LabelIndex {
+    /// 枚数の多い順、同じ数は名前順
+    labels: Vec<Label>,
+    /// レーベルの書いていないレコードの数
+    unlabeled: usize,
+}
+
+/// まとめるときのキー（"  blue   NOTE " → "blue note"）
+fn label_key(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn collect_labels(records: &[(String, &Value)]) -> LabelIndex {
+    // キー → (書き方ごとの数, レコード)
+    let mut groups: HashMap<String, (BTreeMap<String, usize>, Vec<LabelAlbum>)> = HashMap::new();
+    let mut unlabeled = 0;
+    for (filename, v) in records {
+        let raw = v["label"].as_str().unwrap_or("");
+        let key = label_key(raw);
+        if key.is_empty() {
+            unlabeled += 1;
+            continue;
+        }
+        let (spellings, albums) = groups.entry(key).or_default();
+        *spellings.entry(raw.split_whitespace().collect::<Vec<_>>().join(" ")).or_default() += 1;
+        albums.push(LabelAlbum {
+            filename: filename.clone(),
+            display_label: display_label_from_value(v),
+            release_year: int_from_value(&v["release_year"]).filter(|y| *y > 0),
+            id: v["id"].as_str().unwrap_or("").trim().to_string(),
+        });
+    }
+    let mut labels: Vec<Label> = groups
+        .into_values()
+        .map(|(spellings, mut albums)| {
+            // 同じ数なら BTreeMap の順（大文字が先）で最初のもの
+            let label = spellings.iter().rev().max_by_key(|(_, n)| **n).map(|(s, _)| s.clone()).unwrap_or_default();
+            albums.sort_by(|a, b| {
+                (a.release_year.is_none(), a.release_year, &a.id, &a.filename).cmp(&(b.release_year.is_none(), b.release_year, &b.id, &b.filename))
+            });
+            Label { label, count: albums.len(), albums }
+        })
+        .collect();
+    labels.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.label.to_lowercase().cmp(&b.label.to_lowercase())));
+    LabelIndex { labels, unlabeled }
+}
+
+/// `GET /api/labels`
+#[utoipa::path(get, path = "/api/labels", tag = "reports", summary = "レーベルごとの枚数とレコード", params(LibraryQuery))]
+pub async fn get_labels(Lib(library): Lib) -> impl IntoResponse {
+    let records = match library.records.records(&*library.storage) {
+        Ok(records) => records,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    let values: Vec<(String, &Value)> = records.iter().map(|(name, r)| (name.clone(), &r.value)).collect();
+    (StatusCode::OK, Json(collect_labels(&values))).into_response()
+}
+
+#[cfg(test)]
+mod labels_tests {
+    use super::collect_labels;
+    use serde_json::json;
+
+    #[test]
+    fn spellings_are_merged_and_albums_sorted() {
+        let a = json!({"title": "Moanin'", "label": "Blue Note", "id": "BLP 4003", "release_year": 1959});
+        let b = json!({"title": "Somethin' Else", "label": "BLUE  NOTE", "id": "BLP 1595", "release_year": 1958});
+        let c = json!({"title": "Blue Train", "label": "Blue Note ", "id": "BLP 1577", "release_year": 1958});
+        let d = json!({"title": "Köln Concert", "label": "ECM", "id": "ECM 1064/65", "release_year": 1975});
+        let e = json!({"title": "Bootleg", "label": " "});
+        let records = vec![
+            ("a.json".to_string(), &a),
+            ("b.json".to_string(), &b),
+            ("c.json".to_string(), &c),
+            ("d.json".to_string(), &d),
+            ("e.json".to_string(), &e),
+        ];
+        let index = collect_labels(&records);
+        assert_eq!(index.unlabeled, 1);
+        let labels: Vec<(&str, usize)> = index.labels.iter().map(|l| (l.label.as_str(), l.count)).collect();
+        assert_eq!(labels, [("Blue Note", 3), ("ECM", 1)]);
+        let ids: Vec<&str> = index.labels[0].albums.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, ["BLP 1577", "BLP 1595", "BLP 4003"]);
+    }
+}
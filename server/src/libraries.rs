@@ -0,0 +1,132 @@
+//! 複数のライブラリ（レコード JSON を置くディレクトリ）。クラシックとゲーム音楽を別のディレクトリに分けておく用。
+//! `--db-path` のものを既定のライブラリ（`main`）とし、`--library 名前=パス`（環境変数 `LIBRARIES` ならカンマ区切り）で足す。
+//!
+//! レコードを読み書きする API（一覧・保存・検索・統計・カバー・メモ・添付・音源など）は `?library=名前` で対象のライブラリを選ぶ
+//! （なければ既定のライブラリ）。カバー・メモ・添付・音源はそのライブラリのディレクトリに置く。
+//! 設定・名前の台帳・トークン・ログはライブラリに関わらず既定のライブラリの `.config` に置く。
+//! ほかのサーバーとの同期（sync.rs）と決まった曜日の週報（digest.rs）は既定のライブラリだけを扱う。
+
+use axum::{
+    extract::{FromRequestParts, Query, State},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::git_history::LibraryHistory;
+use crate::problem::error;
+use crate::record_cache::RecordCache;
+use crate::storage::Storage;
+use crate::AppState;
+
+/// `--db-path` のライブラリの名前
+pub const DEFAULT_LIBRARY: &str = "main";
+
+#[derive(Clone)]
+pub struct Library {
+    pub name: String,
+    /// ライブラリのディレクトリ（カバー・メモ・添付・音源もここに置く）
+    pub dir: PathBuf,
+    pub storage: Arc<dyn Storage>,
+    pub records: Arc<RecordCache>,
+    /// git の履歴（`--git-history` のときだけ）
+    pub history: Option<Arc<LibraryHistory>>,
+}
+
+impl Library {
+    pub fn is_default(&self) -> bool {
+        self.name == DEFAULT_LIBRARY
+    }
+}
+
+/// `名前=パス` を読む。名前は英小文字・数字・`-`・`_` だけ（URL にそのまま載せるため）
+pub fn parse_library(s: &str) -> Result<(String, PathBuf), String> {
+    let (name, path) = s.split_once('=').ok_or_else(|| format!("expected NAME=PATH: {}", s))?;
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_') {
+        return Err(format!("library name must be lowercase letters, digits, - or _: {}", name));
+    }
+    if name == DEFAULT_LIBRARY {
+        return Err(format!("{} is the --db-path library", DEFAULT_LIBRARY));
+    }
+    let path = path.trim();
+    if path.is_empty() {
+        return Err(format!("library {} has no path", name));
+    }
+    Ok((name.to_string(), PathBuf::from(path)))
+}
+
+/// 名前が重なっていないか確かめる
+pub fn check_unique(libraries: &[(String, PathBuf)]) -> Result<(), String> {
+    for (i, (name, _)) in libraries.iter().enumerate() {
+        if libraries[..i].iter().any(|(n, _)| n == name) {
+            return Err(format!("library {} is given twice", name));
+        }
+    }
+    Ok(())
+}
+
+/// `Lib` を取るハンドラの `?library=`（OpenAPI の `params(LibraryQuery)` にも使う）
+#[derive(serde::Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LibraryQuery {
+    /// 対象のライブラリ（なければ既定のもの）
+    #[serde(default)]
+    library: Option<String>,
+}
+
+/// `?library=` で選んだライブラリ（なければ既定のもの、知らない名前なら 404）
+pub struct Lib(pub Library);
+
+#[axum::async_trait]
+impl FromRequestParts<AppState> for Lib {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let Query(q) = Query::<LibraryQuery>::from_request_parts(parts, state).await.map_err(IntoResponse::into_response)?;
+        let name = q.library.filter(|n| !n.is_empty()).unwrap_or_else(|| DEFAULT_LIBRARY.to_string());
+        match state.libraries.iter().find(|l| l.name == name) {
+            Some(library) => Ok(Lib(library.clone())),
+            None => Err(error(StatusCode::NOT_FOUND, format!("unknown library: {}", name))),
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct LibraryInfo {
+    name: String,
+    default: bool,
+    count: Option<usize>,
+}
+
+/// `GET /api/libraries` ライブラリの一覧（既定のものが先頭）とレコードの件数
+#[utoipa::path(get, path = "/api/libraries", tag = "records", summary = "ライブラリの一覧")]
+pub async fn list(State(state): State<AppState>) -> impl IntoResponse {
+    let list: Vec<LibraryInfo> = state
+        .libraries
+        .iter()
+        .map(|l| LibraryInfo { name: l.name.clone(), default: l.is_default(), count: l.storage.list().ok().map(|n| n.len()) })
+        .collect();
+    Json(list)
+}
+
+#[cfg(test)]
+mod libraries_tests {
+    use super::{check_unique, parse_library};
+    use std::path::PathBuf;
+
+    #[test]
+    fn name_and_path() {
+        assert_eq!(parse_library("classical=/data/classical"), Ok(("classical".into(), PathBuf::from("/data/classical"))));
+        assert_eq!(parse_library(" game-music = d:\\vgm ").map(|(n, _)| n), Ok("game-music".into()));
+        assert!(parse_library("classical").is_err());
+        assert!(parse_library("Classical=/x").is_err());
+        assert!(parse_library("main=/x").is_err());
+        assert!(parse_library("vgm=").is_err());
+        let libs = vec![("a".to_string(), PathBuf::from("x")), ("a".to_string(), PathBuf::from("y"))];
+        assert!(check_unique(&libs).is_err());
+        assert!(check_unique(&libs[..1]).is_ok());
+    }
+}
@@ -0,0 +1,158 @@
+//! `/api/list-with-labels` の絞り込み（`?janre=Jazz&score_min=5&year_from=1955&year_to=1965`）。
+//! 全ファイルをクライアントで読まなくても、サイドバーに一部だけ出せるようにする。条件を省略したものは絞り込まない。
+//! 並び順も `?sort=score&order=desc` で指定できる（既定はファイル名順）。
+
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use crate::record_cache::CachedRecord;
+use crate::{display_label_from_value, int_from_value, score_from_value};
+
+/// 並べ替えのキー
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    #[default]
+    Filename,
+    /// サイドバーの表示名
+    Label,
+    Title,
+    ReleaseYear,
+    Score,
+    /// ファイルの最終更新日時
+    Modified,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct ListFilter {
+    /// janre.main か janre.sub のどれかがこれ（大文字小文字は区別しない）
+    #[serde(default)]
+    janre: Option<String>,
+    #[serde(default)]
+    score_min: Option<f64>,
+    #[serde(default)]
+    score_max: Option<f64>,
+    /// 発売年の範囲（両端を含む）
+    #[serde(default)]
+    year_from: Option<i64>,
+    #[serde(default)]
+    year_to: Option<i64>,
+    #[serde(default)]
+    sort: SortKey,
+    #[serde(default)]
+    order: SortOrder,
+}
+
+impl ListFilter {
+    pub fn matches(&self, v: &Value) -> bool {
+        let janre_ok = self.janre.as_deref().map(str::trim).filter(|j| !j.is_empty()).is_none_or(|j| {
+            std::iter::once(&v["janre"]["main"])
+                .chain(v["janre"]["sub"].as_array().into_iter().flatten())
+                .filter_map(Value::as_str)
+                .any(|g| g.eq_ignore_ascii_case(j))
+        });
+        let score = score_from_value(v);
+        let year = int_from_value(&v["release_year"]);
+        // スコア・年で絞るときは、未入力のものは外す
+        janre_ok
+            && self.score_min.is_none_or(|min| score.is_some_and(|s| s >= min))
+            && self.score_max.is_none_or(|max| score.is_some_and(|s| s > 0.0 && s <= max))
+            && self.year_from.is_none_or(|from| year.is_some_and(|y| y >= from))
+            && self.year_to.is_none_or(|to| year.is_some_and(|y| y > 0 && y <= to))
+    }
+
+    /// `sort` / `order` のとおりに並べる。値のないもの（未入力のスコア・年など）は向きによらず最後、同じ値はファイル名順。
+    pub fn sort(&self, records: Vec<(String, Arc<CachedRecord>)>) -> Vec<(String, Arc<CachedRecord>)> {
+        let desc = self.order == SortOrder::Desc;
+        let directed = |o: Ordering| if desc { o.reverse() } else { o };
+        let mut keyed: Vec<_> = records.into_iter().map(|(name, r)| (self.sort_value(&name, &r), name, r)).collect();
+        keyed.sort_by(|(a, an, _), (b, bn, _)| {
+            match (a, b) {
+                (Some(a), Some(b)) => directed(a.partial_cmp(b).unwrap_or(Ordering::Equal)),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+            .then_with(|| an.cmp(bn))
+        });
+        keyed.into_iter().map(|(_, name, r)| (name, r)).collect()
+    }
+
+    fn sort_value(&self, filename: &str, r: &CachedRecord) -> Option<SortValue> {
+        let v = &r.value;
+        let text = |t: String| (!t.trim().is_empty()).then(|| SortValue::Text(t.trim().to_lowercase()));
+        match self.sort {
+            SortKey::Filename => Some(SortValue::Text(filename.to_string())),
+            SortKey::Label => text(display_label_from_value(v)),
+            SortKey::Title => text(v["title"].as_str().unwrap_or("").to_string()),
+            SortKey::ReleaseYear => int_from_value(&v["release_year"]).filter(|&y| y > 0).map(|y| SortValue::Number(y as f64)),
+            SortKey::Score => score_from_value(v).filter(|&s| s > 0.0).map(SortValue::Number),
+            SortKey::Modified => r
+                .modified
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| SortValue::Number(d.as_secs_f64())),
+        }
+    }
+}
+
+/// 並べ替えに使う値（キーごとにどちらか一方しか出てこない）
+#[derive(Debug, PartialEq, PartialOrd)]
+enum SortValue {
+    Text(String),
+    Number(f64),
+}
+
+#[cfg(test)]
+mod list_filter_tests {
+    use super::ListFilter;
+    use crate::record_cache::CachedRecord;
+    use axum::extract::Query;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn filters_combine() {
+        let v = json!({"janre": {"main": "Jazz", "sub": ["Hard Bop"]}, "score": 5, "release_year": 1959});
+        let stub = json!({"janre": {"main": "Jazz", "sub": []}, "score": 0, "release_year": 0});
+        let f = |q: &str| -> ListFilter { Query::try_from_uri(&format!("/?{}", q).parse().unwrap()).unwrap().0 };
+        assert!(f("").matches(&v) && f("").matches(&stub));
+        assert!(f("janre=jazz&score_min=5&year_from=1955&year_to=1965").matches(&v));
+        assert!(f("janre=Hard%20Bop").matches(&v));
+        assert!(!f("janre=Classical").matches(&v));
+        assert!(!f("score_min=6").matches(&v));
+        assert!(!f("year_to=1958").matches(&v));
+        assert!(!f("score_max=3").matches(&stub));
+        assert!(!f("year_to=1965").matches(&stub));
+    }
+
+    #[test]
+    fn sort_puts_missing_values_last() {
+        let rec = |name: &str, v: serde_json::Value| {
+            (name.to_string(), Arc::new(CachedRecord { value: v, modified: None }))
+        };
+        let records = vec![
+            rec("c.json", json!({"title": "Blue Train", "score": 4, "release_year": 1957})),
+            rec("a.json", json!({"title": "", "score": 0, "release_year": 0})),
+            rec("b.json", json!({"title": "a Love Supreme", "score": 5, "release_year": 1965})),
+        ];
+        let order = |q: &str| -> Vec<String> {
+            let f: ListFilter = Query::try_from_uri(&format!("/?{}", q).parse().unwrap()).unwrap().0;
+            f.sort(records.clone()).into_iter().map(|(n, _)| n).collect()
+        };
+        assert_eq!(order(""), ["a.json", "b.json", "c.json"]);
+        assert_eq!(order("order=desc"), ["c.json", "b.json", "a.json"]);
+        assert_eq!(order("sort=title"), ["b.json", "c.json", "a.json"]);
+        assert_eq!(order("sort=score&order=desc"), ["b.json", "c.json", "a.json"]);
+        assert_eq!(order("sort=release_year"), ["c.json", "b.json", "a.json"]);
+        assert!(Query::<ListFilter>::try_from_uri(&"/?sort=color".parse().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,152 @@
+//! 再生記録（listens）の追記と、該当レコードがないときの下書き作成。
+
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use serde_json::Value;
+
+use crate::date;
+use crate::libraries::{Lib, LibraryQuery};
+use crate::normalize_filename;
+use crate::problem::{error, field_error};
+use crate::storage::write_atomic;
+use crate::versions::{self, Precondition, WriteError};
+
+pub fn write_json(path: &std::path::Path, v: &Value) -> Result<(), String> {
+    let json_str = serde_json::to_string_pretty(v).map_err(|e| e.to_string())?;
+    write_atomic(path, json_str.as_bytes()).map_err(|e| e.to_string())
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct ListenBody {
+    filename: String,
+    /// 聴いた日（YYYY/MM/DD）。日付はブラウザ側の「今日」を使う。
+    date: String,
+}
+
+/// 既存レコードの `listens` に日付を1件追記する。
+#[utoipa::path(
+    post,
+    path = "/api/listen",
+    tag = "listening",
+    summary = "再生記録を足す（追記の前後の版 previous_version / version を返す）",
+    params(LibraryQuery),
+    request_body = ListenBody,
+)]
+pub async fn append_listen(
+    Lib(library): Lib,
+    Json(body): Json<ListenBody>,
+) -> impl IntoResponse {
+    if !date::is_valid(&body.date) {
+        return field_error("date", "must be YYYY/MM/DD");
+    }
+    let Some(filename) = normalize_filename(&body.filename) else {
+        return error(StatusCode::BAD_REQUEST, "invalid filename");
+    };
+    let res = versions::update(&*library.storage, &filename, |v| {
+        let Some(obj) = v.as_object_mut() else {
+            return Err(WriteError::Failed(StatusCode::UNPROCESSABLE_ENTITY, "invalid json: not an object".into()));
+        };
+        let listens = obj.entry("listens").or_insert_with(|| Value::Array(vec![]));
+        if !listens.is_array() {
+            *listens = Value::Array(vec![]);
+        }
+        if let Some(a) = listens.as_array_mut() {
+            a.push(Value::String(body.date.clone()));
+        }
+        Ok(true)
+    });
+    match res {
+        // 追記の前後の版。開いているフォームは前の版のままなら新しい版に乗り換え、違えばほかでの変更として扱う
+        Ok(Some(written)) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "ok": true,
+                "filename": filename,
+                "previous_version": written.old.as_ref().map(versions::version_of),
+                "version": written.version,
+            })),
+        )
+            .into_response(),
+        Ok(None) => error(StatusCode::INTERNAL_SERVER_ERROR, "listen was not written"),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct DraftBody {
+    #[serde(default)]
+    artist: String,
+    title: String,
+    /// 記録日（YYYY/MM/DD）
+    date: String,
+    /// true なら記録日を再生記録にも1件追加する
+    #[serde(default)]
+    listened: bool,
+}
+
+/// 下書きレコードの骨組み。必須項目は空のまま保存し、あとでフォームから埋める。
+pub fn draft_value(artist: &str, title: &str, date: &str, listened: bool) -> Value {
+    let leader = if artist.is_empty() {
+        vec![]
+    } else {
+        vec![serde_json::json!({"name": artist, "instruments": "", "tracks": "all"})]
+    };
+    let listens = if listened { vec![date.to_string()] } else { vec![] };
+    serde_json::json!({
+        "title": title,
+        "janre": {"main": "", "sub": []},
+        "label": "",
+        "id": "",
+        "release_year": 0,
+        "record_year": [],
+        "personnel": {"leader": leader},
+        "tracks": [],
+        "score": 0,
+        "comment": "",
+        "date": date,
+        "references": [],
+        "draft": true,
+        "listens": listens,
+    })
+}
+
+/// 下書きのファイル名 "{artist}__{title}.json"（空白は "_"）。アーティストが空ならタイトルのみ。
+pub fn draft_filename(artist: &str, title: &str) -> Option<String> {
+    let base = if artist.is_empty() {
+        title.to_string()
+    } else {
+        format!("{}__{}", artist, title)
+    };
+    normalize_filename(&base.replace(' ', "_"))
+}
+
+/// アーティスト・タイトルだけから下書きレコードを作る。ファイル名は "{artist}__{title}"。
+#[utoipa::path(
+    post,
+    path = "/api/draft",
+    tag = "records",
+    summary = "アーティスト・タイトルだけの下書きを作る",
+    params(LibraryQuery),
+    request_body = DraftBody,
+)]
+pub async fn create_draft(
+    Lib(library): Lib,
+    Json(body): Json<DraftBody>,
+) -> impl IntoResponse {
+    let artist = body.artist.trim();
+    let title = body.title.trim();
+    if title.is_empty() {
+        return field_error("title", "is required");
+    }
+    if !date::is_valid(&body.date) {
+        return field_error("date", "must be YYYY/MM/DD");
+    }
+    let Some(filename) = draft_filename(artist, title) else {
+        return error(StatusCode::BAD_REQUEST, "invalid filename");
+    };
+    let v = draft_value(artist, title, &body.date, body.listened);
+    match versions::write(&*library.storage, &filename, &Precondition::Absent, &v) {
+        Ok(_) => (StatusCode::OK, Json(serde_json::json!({"ok": true, "filename": filename}))).into_response(),
+        Err(WriteError::Conflict { .. }) => error(StatusCode::CONFLICT, format!("{} は既に存在します", filename)),
+        Err(e) => e.into_response(),
+    }
+}
@@ -0,0 +1,181 @@
+//! ライブラリの変更の通知（`GET /api/ws`、WebSocket）。
+//! サーバー経由の保存・削除（`Invalidating`）と `db/` の監視の両方から `created` / `updated` / `deleted` を流し、
+//! フロントはそれを受けてサイドバーの一覧を取り直す（別のタブでの追加やディスク上の直接の編集も反映する）。
+//!
+//! 送るのは `{"event": "updated", "filename": "xxx.json"}` の形の JSON テキスト。
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::IntoResponse,
+};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+use crate::AppState;
+
+/// 受け手が読み遅れたときに溜めておく件数（超えた分は捨て、次のイベントで一覧ごと取り直してもらう）
+const CHANNEL_CAPACITY: usize = 256;
+
+/// 保存のあとに監視からも同じ変更が届くので、この間の同じ種類（あり/削除）の通知はまとめる
+const DEDUP_WINDOW: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct LibraryEvent {
+    pub event: EventKind,
+    pub filename: String,
+}
+
+#[derive(Default)]
+struct Recent {
+    /// ファイル名ごとに最後に流した時刻と、削除だったか
+    published: HashMap<String, (Instant, bool)>,
+    /// サーバー経由で書き込み中のファイル（同時に書いている数）
+    writing: HashMap<String, usize>,
+}
+
+pub struct LibraryEvents {
+    tx: broadcast::Sender<LibraryEvent>,
+    recent: Mutex<Recent>,
+}
+
+impl Default for LibraryEvents {
+    fn default() -> Self {
+        Self { tx: broadcast::channel(CHANNEL_CAPACITY).0, recent: Mutex::default() }
+    }
+}
+
+/// 直前の通知と重なる（同じファイルの同じ種類の変更が `DEDUP_WINDOW` 内）なら流さない
+fn is_duplicate(last: Option<&(Instant, bool)>, now: Instant, deleted: bool) -> bool {
+    last.is_some_and(|&(at, was_deleted)| was_deleted == deleted && now.duration_since(at) < DEDUP_WINDOW)
+}
+
+impl LibraryEvents {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Recent> {
+        self.recent.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn send(&self, recent: &mut Recent, event: EventKind, filename: &str) {
+        let now = Instant::now();
+        let deleted = event == EventKind::Deleted;
+        if is_duplicate(recent.published.get(filename), now, deleted) {
+            return;
+        }
+        recent.published.retain(|_, (at, _)| now.duration_since(*at) < DEDUP_WINDOW);
+        recent.published.insert(filename.to_string(), (now, deleted));
+        // 聞いている人がいなければ何もしない
+        let _ = self.tx.send(LibraryEvent { event, filename: filename.to_string() });
+    }
+
+    /// サーバー経由の書き込みを始める。終わるまで監視のイベントは流さない（監視の方が先に届くと種類を取り違えるため）
+    pub fn begin(&self, filename: &str) {
+        *self.lock().writing.entry(filename.to_string()).or_default() += 1;
+    }
+
+    /// 書き込みが終わった。成功していれば `event` を流す
+    pub fn finish(&self, filename: &str, event: Option<EventKind>) {
+        let mut recent = self.lock();
+        if let Some(n) = recent.writing.get_mut(filename) {
+            *n -= 1;
+            if *n == 0 {
+                recent.writing.remove(filename);
+            }
+        }
+        if let Some(event) = event {
+            self.send(&mut recent, event, filename);
+        }
+    }
+
+    /// 監視で見つけた変更を流す（サーバーが書き込み中のものは除く）
+    pub fn observed(&self, event: EventKind, filename: &str) {
+        let mut recent = self.lock();
+        if !recent.writing.contains_key(filename) {
+            self.send(&mut recent, event, filename);
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LibraryEvent> {
+        self.tx.subscribe()
+    }
+}
+
+/// `GET /api/ws`
+#[utoipa::path(get, path = "/api/ws", tag = "records", summary = "レコードの作成・更新・削除の通知（WebSocket）")]
+pub async fn ws(State(state): State<AppState>, upgrade: WebSocketUpgrade) -> impl IntoResponse {
+    let rx = state.events.subscribe();
+    upgrade.on_upgrade(move |socket| forward(socket, rx))
+}
+
+/// 変更を送り続ける。相手が閉じたか送れなくなったら終わる
+async fn forward(mut socket: WebSocket, mut rx: broadcast::Receiver<LibraryEvent>) {
+    loop {
+        tokio::select! {
+            event = rx.recv() => match event {
+                Ok(event) => {
+                    let Ok(text) = serde_json::to_string(&event) else { continue };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        return;
+                    }
+                }
+                // 取りこぼした分は、次の通知でフロントが一覧ごと取り直すので捨ててよい
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod live_tests {
+    use super::{is_duplicate, EventKind, LibraryEvent, LibraryEvents, DEDUP_WINDOW};
+    use std::time::Instant;
+
+    #[test]
+    fn save_and_watcher_events_are_merged() {
+        let now = Instant::now();
+        assert!(!is_duplicate(None, now, false));
+        assert!(is_duplicate(Some(&(now, false)), now, false));
+        // 更新のあとの削除は別の通知
+        assert!(!is_duplicate(Some(&(now, false)), now, true));
+        assert!(!is_duplicate(Some(&(now, false)), now + DEDUP_WINDOW, false));
+
+        let events = LibraryEvents::default();
+        let mut rx = events.subscribe();
+        // 書き込み中に監視が拾ったものは流さず、書き込みの結果を流す
+        events.begin("a.json");
+        events.observed(EventKind::Updated, "a.json");
+        events.finish("a.json", Some(EventKind::Created));
+        events.observed(EventKind::Updated, "a.json");
+        events.observed(EventKind::Deleted, "a.json");
+        events.begin("b.json");
+        events.finish("b.json", None);
+        let received: Vec<LibraryEvent> = std::iter::from_fn(|| rx.try_recv().ok()).collect();
+        assert_eq!(
+            received,
+            [
+                LibraryEvent { event: EventKind::Created, filename: "a.json".into() },
+                LibraryEvent { event: EventKind::Deleted, filename: "a.json".into() },
+            ]
+        );
+        assert_eq!(
+            serde_json::to_string(&LibraryEvent { event: EventKind::Updated, filename: "b.json".into() }).unwrap(),
+            r#"{"event":"updated","filename":"b.json"}"#
+        );
+    }
+}
@@ -0,0 +1,436 @@
+//! 変更の記録（audit.jsonl）と再生記録（各レコードの `listens`）の書き出し・取り込み。
+//! 別のマシンへ移るときに履歴を持っていけるようにする。形式は JSONL か CSV。
+//! 取り込みは何度やっても同じ結果になる（同じ行は足さない。再生記録は日付ごとの回数が取り込み元より少ないときだけ足す）。
+//!
+//! サーバーからは `GET /api/logs/{audit|listens}?format=csv` と `POST /api/logs/{audit|listens}?format=jsonl`（本文がそのまま中身。再生記録は `?library=` のライブラリ）、
+//! コマンドからは `nekokan_music_server logs export <audit|listens> [jsonl|csv]` / `logs import <audit|listens> <ファイル>`。
+
+use axum::{
+    extract::{Path as UrlPath, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::audit::AUDIT_FILE;
+use crate::date;
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+use crate::settings::{config_path, CONFIG_DIR};
+use crate::storage::{read_record, Storage};
+use crate::versions::{self, WriteError};
+use crate::{normalize_filename, AppState};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogKind {
+    Audit,
+    Listens,
+}
+
+impl LogKind {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "audit" => Some(Self::Audit),
+            "listens" => Some(Self::Listens),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Audit => "audit",
+            Self::Listens => "listens",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    #[default]
+    Jsonl,
+    Csv,
+}
+
+impl LogFormat {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "jsonl" | "json" => Some(Self::Jsonl),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    fn ext(self) -> &'static str {
+        match self {
+            Self::Jsonl => "jsonl",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// audit.jsonl の 1 行（`audit::AuditEntry` の持ち主版）。CSV でも列がそろうよう、レコードの変更も平たく持つ
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub(crate) struct AuditRow {
+    pub at: String,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub token: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub action: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub file: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub renamed_to: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub library: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub fields: Option<String>,
+}
+
+/// CSV では None が空文字になるので戻す
+fn empty_as_none<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Option<String>, D::Error> {
+    let s: Option<String> = serde::Deserialize::deserialize(d)?;
+    Ok(s.filter(|s| !s.is_empty()))
+}
+
+/// 再生記録 1 件
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct ListenRow {
+    filename: String,
+    date: String,
+}
+
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+pub struct ImportReport {
+    /// 足した件数
+    pub added: usize,
+    /// もうあった件数
+    pub skipped: usize,
+    /// 取り込めなかった行（レコードがない・日付が正しくないなど）
+    pub errors: Vec<String>,
+}
+
+pub(crate) fn read_audit(db_path: &Path) -> Result<Vec<AuditRow>, String> {
+    let text = match fs::read_to_string(config_path(db_path, AUDIT_FILE)) {
+        Ok(t) => t,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.to_string()),
+    };
+    // 壊れた行は飛ばす（追記の途中で落ちたときなど）
+    Ok(text.lines().filter_map(|l| serde_json::from_str(l).ok()).collect())
+}
+
+fn read_listens(storage: &dyn Storage) -> Result<Vec<ListenRow>, String> {
+    let mut rows = Vec::new();
+    for filename in storage.list().map_err(|e| e.to_string())? {
+        let Ok(v) = read_record(storage, &filename) else { continue };
+        for date in v["listens"].as_array().into_iter().flatten().filter_map(|d| d.as_str()) {
+            rows.push(ListenRow { filename: filename.clone(), date: date.to_string() });
+        }
+    }
+    Ok(rows)
+}
+
+fn to_text<T: serde::Serialize>(rows: &[T], format: LogFormat) -> Result<String, String> {
+    match format {
+        LogFormat::Jsonl => rows
+            .iter()
+            .map(|r| serde_json::to_string(r).map(|l| l + "\n").map_err(|e| e.to_string()))
+            .collect(),
+        LogFormat::Csv => {
+            let mut w = csv::Writer::from_writer(vec![]);
+            for r in rows {
+                w.serialize(r).map_err(|e| e.to_string())?;
+            }
+            String::from_utf8(w.into_inner().map_err(|e| e.to_string())?).map_err(|e| e.to_string())
+        }
+    }
+}
+
+fn from_text<T: serde::de::DeserializeOwned>(text: &str, format: LogFormat) -> Result<Vec<T>, String> {
+    match format {
+        LogFormat::Jsonl => text
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+            .map(|(i, l)| serde_json::from_str(l).map_err(|e| format!("{} 行目: {}", i + 1, e)))
+            .collect(),
+        LogFormat::Csv => csv::Reader::from_reader(text.as_bytes())
+            .deserialize()
+            .enumerate()
+            .map(|(i, r)| r.map_err(|e| format!("{} 行目: {}", i + 2, e)))
+            .collect(),
+    }
+}
+
+pub fn export(kind: LogKind, format: LogFormat, db_path: &Path, storage: &dyn Storage) -> Result<String, String> {
+    match kind {
+        LogKind::Audit => to_text(&read_audit(db_path)?, format),
+        LogKind::Listens => to_text(&read_listens(storage)?, format),
+    }
+}
+
+pub fn import(kind: LogKind, format: LogFormat, text: &str, db_path: &Path, storage: &dyn Storage) -> Result<ImportReport, String> {
+    match kind {
+        LogKind::Audit => import_audit(from_text(text, format)?, db_path),
+        LogKind::Listens => Ok(import_listens(from_text(text, format)?, storage)),
+    }
+}
+
+fn import_audit(rows: Vec<AuditRow>, db_path: &Path) -> Result<ImportReport, String> {
+    let mut seen: HashSet<AuditRow> = read_audit(db_path)?.into_iter().collect();
+    let mut report = ImportReport::default();
+    let mut lines = String::new();
+    for row in rows {
+        if seen.contains(&row) {
+            report.skipped += 1;
+            continue;
+        }
+        lines.push_str(&serde_json::to_string(&row).map_err(|e| e.to_string())?);
+        lines.push('\n');
+        seen.insert(row);
+        report.added += 1;
+    }
+    if !lines.is_empty() {
+        fs::create_dir_all(db_path.join(CONFIG_DIR)).map_err(|e| e.to_string())?;
+        let mut f = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(config_path(db_path, AUDIT_FILE))
+            .map_err(|e| e.to_string())?;
+        f.write_all(lines.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    Ok(report)
+}
+
+/// 取り込む日付のうち、手元の回数を超える分だけを返す（同じ日に 2 回聴いたものは 2 件として数える）
+fn missing_dates(local: &[String], incoming: &[String]) -> Vec<String> {
+    let mut have: BTreeMap<&str, usize> = BTreeMap::new();
+    for d in local {
+        *have.entry(d).or_default() += 1;
+    }
+    incoming
+        .iter()
+        .filter(|d| match have.get_mut(d.as_str()) {
+            Some(n) if *n > 0 => {
+                *n -= 1;
+                false
+            }
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
+
+fn import_listens(rows: Vec<ListenRow>, storage: &dyn Storage) -> ImportReport {
+    let mut report = ImportReport::default();
+    let mut by_file: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for row in rows {
+        // パスを含むものは受け付けない（直して別のレコードに足したりもしない）
+        if normalize_filename(&row.filename).as_deref() != Some(row.filename.as_str()) {
+            report.errors.push(format!("ファイル名が正しくありません: {}", row.filename));
+        } else if date::is_valid(&row.date) {
+            by_file.entry(row.filename).or_default().push(row.date);
+        } else {
+            report.errors.push(format!("{}: 日付が YYYY/MM/DD ではありません: {}", row.filename, row.date));
+        }
+    }
+    for (filename, dates) in by_file {
+        let mut missing = Vec::new();
+        let res = versions::update(storage, &filename, |v| {
+            if !v.is_object() {
+                return Err(WriteError::Failed(StatusCode::UNPROCESSABLE_ENTITY, "invalid json: not an object".into()));
+            }
+            let local: Vec<String> =
+                v["listens"].as_array().into_iter().flatten().filter_map(|d| d.as_str().map(String::from)).collect();
+            missing = missing_dates(&local, &dates);
+            if missing.is_empty() {
+                return Ok(false);
+            }
+            let mut merged = local;
+            merged.extend(missing.iter().cloned());
+            merged.sort();
+            v["listens"] = serde_json::json!(merged);
+            Ok(true)
+        });
+        match res {
+            Ok(written) => {
+                report.skipped += dates.len() - missing.len();
+                if written.is_some() {
+                    report.added += missing.len();
+                }
+            }
+            // 読めないもの・オブジェクトでないものはないものとして数える
+            Err(WriteError::Failed(StatusCode::NOT_FOUND | StatusCode::UNPROCESSABLE_ENTITY, _)) => {
+                report.errors.push(format!("{}: レコードがありません（{} 件）", filename, dates.len()));
+            }
+            Err(e) => report.errors.push(format!("{}: {}", filename, e)),
+        }
+    }
+    report
+}
+
+#[derive(serde::Deserialize)]
+pub struct FormatQuery {
+    #[serde(default)]
+    format: LogFormat,
+}
+
+/// `GET /api/logs/{kind}?format=jsonl|csv` ダウンロード
+#[utoipa::path(get, path = "/api/logs/{kind}", tag = "import-export", summary = "監査ログ・再生記録を書き出す", params(LibraryQuery))]
+pub async fn export_logs(
+    State(state): State<AppState>,
+    Lib(library): Lib,
+    UrlPath(kind): UrlPath<String>,
+    Query(q): Query<FormatQuery>,
+) -> Response {
+    let Some(kind) = LogKind::parse(&kind) else {
+        return error(StatusCode::NOT_FOUND, "unknown log (audit or listens)");
+    };
+    let res = tokio::task::spawn_blocking(move || export(kind, q.format, &state.db_path, &*library.storage)).await;
+    match res {
+        Ok(Ok(body)) => (
+            StatusCode::OK,
+            [
+                (
+                    header::CONTENT_TYPE,
+                    if q.format == LogFormat::Csv { "text/csv; charset=utf-8" } else { "application/x-ndjson; charset=utf-8" }.to_string(),
+                ),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"nekokan_{}.{}\"", kind.name(), q.format.ext())),
+            ],
+            body,
+        )
+            .into_response(),
+        Ok(Err(e)) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// `POST /api/logs/{kind}?format=jsonl|csv` 本文を取り込む
+#[utoipa::path(
+    post,
+    path = "/api/logs/{kind}",
+    tag = "import-export",
+    summary = "監査ログ・再生記録を取り込む",
+    params(LibraryQuery),
+    request_body(content = String, content_type = "text/plain"),
+)]
+pub async fn import_logs(
+    State(state): State<AppState>,
+    Lib(library): Lib,
+    UrlPath(kind): UrlPath<String>,
+    Query(q): Query<FormatQuery>,
+    body: String,
+) -> Response {
+    let Some(kind) = LogKind::parse(&kind) else {
+        return error(StatusCode::NOT_FOUND, "unknown log (audit or listens)");
+    };
+    let res = tokio::task::spawn_blocking(move || import(kind, q.format, &body, &state.db_path, &*library.storage)).await;
+    match res {
+        Ok(Ok(report)) => (StatusCode::OK, Json(report)).into_response(),
+        Ok(Err(e)) => error(StatusCode::BAD_REQUEST, e),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+fn kind_arg(s: &str) -> Result<LogKind, String> {
+    LogKind::parse(s).ok_or_else(|| "audit か listens".to_string())
+}
+
+fn format_arg(s: &str) -> Result<LogFormat, String> {
+    LogFormat::parse(s).ok_or_else(|| "jsonl か csv".to_string())
+}
+
+/// `nekokan_music_server logs ...`（サーバーは立てない）
+#[derive(Debug, clap::Subcommand)]
+pub enum LogsCommand {
+    /// 標準出力に書き出す
+    Export {
+        /// audit / listens
+        #[arg(value_parser = kind_arg)]
+        kind: LogKind,
+        /// jsonl / csv
+        #[arg(value_parser = format_arg, default_value = "jsonl")]
+        format: LogFormat,
+    },
+    /// ファイルから取り込む。形式は拡張子（.csv かそれ以外）で決める
+    Import {
+        /// audit / listens
+        #[arg(value_parser = kind_arg)]
+        kind: LogKind,
+        file: std::path::PathBuf,
+    },
+}
+
+/// `nekokan_music_server logs ...` を実行する。終了コードを返す。
+pub fn run_cli(command: LogsCommand, db_path: &Path, storage: &dyn Storage) -> i32 {
+    let result = match command {
+        LogsCommand::Export { kind, format } => export(kind, format, db_path, storage).map(|text| print!("{}", text)),
+        LogsCommand::Import { kind, file } => {
+            let format = if file.extension().is_some_and(|e| e == "csv") { LogFormat::Csv } else { LogFormat::Jsonl };
+            fs::read_to_string(&file)
+                .map_err(|e| format!("{}: {}", file.display(), e))
+                .and_then(|text| import(kind, format, &text, db_path, storage))
+                .map(|r| {
+                    println!("{} 件取り込み、{} 件はもうありました", r.added, r.skipped);
+                    for e in &r.errors {
+                        eprintln!("{}", e);
+                    }
+                })
+        }
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod logs_tests {
+    use super::{export, import, missing_dates, LogFormat, LogKind};
+    use crate::storage::{read_record, write_record, FsStorage, Layout};
+    use serde_json::json;
+
+    #[test]
+    fn listens_only_add_what_is_missing() {
+        let local = vec!["2024/01/01".to_string(), "2024/01/01".to_string()];
+        let incoming: Vec<String> = ["2024/01/01", "2024/01/01", "2024/01/01", "2024/02/02"].map(String::from).into();
+        assert_eq!(missing_dates(&local, &incoming), ["2024/01/01", "2024/02/02"]);
+    }
+
+    #[test]
+    fn round_trip_is_idempotent() {
+        let from = tempfile::tempdir().unwrap();
+        let to = tempfile::tempdir().unwrap();
+        let (a, b) = (FsStorage::new(from.path(), Layout::Flat, false), FsStorage::new(to.path(), Layout::Flat, false));
+        write_record(&a, "x.json", &json!({"title": "X", "listens": ["2024/01/01", "2024/03/01"]})).unwrap();
+        write_record(&b, "x.json", &json!({"title": "X", "listens": ["2024/01/01"]})).unwrap();
+
+        for format in [LogFormat::Jsonl, LogFormat::Csv] {
+            let text = export(LogKind::Listens, format, from.path(), &a).unwrap();
+            let report = import(LogKind::Listens, format, &text, to.path(), &b).unwrap();
+            assert_eq!(report.errors, Vec::<String>::new());
+            assert_eq!(read_record(&b, "x.json").ok().unwrap()["listens"], json!(["2024/01/01", "2024/03/01"]));
+        }
+
+        crate::audit::append(from.path(), &crate::audit::AuditEntry::now(Some("phone"), "POST", "/api/save", 200)).unwrap();
+        crate::audit::append(from.path(), &crate::audit::AuditEntry::now(None, "DELETE", "/api/files/x.json", 200)).unwrap();
+        let csv = export(LogKind::Audit, LogFormat::Csv, from.path(), &a).unwrap();
+        assert_eq!(import(LogKind::Audit, LogFormat::Csv, &csv, to.path(), &b).unwrap().added, 2);
+        assert_eq!(import(LogKind::Audit, LogFormat::Csv, &csv, to.path(), &b).unwrap().skipped, 2);
+        assert_eq!(
+            export(LogKind::Audit, LogFormat::Jsonl, to.path(), &b).unwrap(),
+            export(LogKind::Audit, LogFormat::Jsonl, from.path(), &a).unwrap()
+        );
+    }
+}
@@ -1,244 +1,767 @@
 use axum::{
-    extract::Path,
+    extract::{DefaultBodyLimit, Path},
     http::StatusCode,
     response::IntoResponse,
-    routing::{get, post},
+    routing::{get, patch, post},
     Json, Router,
 };
 use serde_json::Value;
-use std::fs;
 use std::path::PathBuf;
-use tower_http::cors::{Any, CorsLayer};
+use std::sync::Arc;
 use tower_http::services::ServeDir;
 
+// データモデル・入力チェック・ラベルの規則はフロントと共有する
+use nekokan_music_core::{completeness, date, filename, label, length, rules, types, validation};
+
+use crate::problem::error;
+
+mod activity;
+mod attachments;
+mod audio;
+mod audit;
+mod auth;
+mod backup;
+mod calendar;
+mod client_errors;
+mod compression;
+mod cors;
+mod covers;
+mod dates;
+mod digest;
+mod discogs;
+mod duplicates;
+mod export_zip;
+mod goals;
+mod genres;
+mod git_history;
+mod graph;
+mod idempotency;
+mod import_conflict;
+mod labels;
+mod libraries;
+mod list_filter;
+mod listen;
+mod live;
+mod logs;
+mod markdown;
+mod metadata;
+mod migrations;
+mod names;
+mod normalize_filenames;
+mod notes;
+mod openapi;
+mod personnel;
+mod policy;
+mod problem;
+mod random;
+mod rate_limit;
+mod recent;
+mod record_cache;
+mod remote_import;
+mod rename;
+mod retention;
+mod schema;
+mod scores;
+mod search;
+mod settings;
+mod stats;
+mod storage;
+mod sync;
+mod tls;
+mod trash;
+mod tunes;
+mod validate_all;
+mod validation_log;
+mod validation_rules;
+mod versions;
+mod year_review;
+
 const DB_DIR: &str = "db";
+const DIST_DIR: &str = "nekokan_music_wa/dist";
+/// カバー画像アップロードの上限（バイト）
+const COVER_MAX_BYTES: usize = 20 * 1024 * 1024;
+/// 添付のアップロード 1 回分の上限（バイト、複数ファイルの合計）。1 ファイルごとの上限は attachments.rs。
+const ATTACHMENT_MAX_BYTES: usize = 200 * 1024 * 1024;
+/// 保存するレコード JSON の上限（既定の 2MB では大きなボックスセットが入らない）
+const RECORD_MAX_BYTES: usize = 32 * 1024 * 1024;
+
+/// nekokan_music の API サーバー（フロントのビルド結果も配信する）。
+/// 指定しなかったものは環境変数、それもなければ既定値を使う。
+#[derive(clap::Parser)]
+#[command(version)]
+struct Cli {
+    /// 待ち受ける IP アドレス（ほかの端末・コンテナの外から使うなら 0.0.0.0）
+    #[arg(long, env = "LISTEN_HOST", default_value = "127.0.0.1")]
+    host: std::net::IpAddr,
+    /// 待ち受けるポート
+    #[arg(long, env = "LISTEN_PORT", default_value_t = 12989)]
+    port: u16,
+    /// レコード JSON を置くディレクトリ
+    #[arg(long, env = "DB_PATH", default_value = DB_DIR)]
+    db_path: PathBuf,
+    /// 配信するフロントのビルド結果（trunk build の出力）
+    #[arg(long, env = "DIST_DIR", default_value = DIST_DIR)]
+    dist_dir: PathBuf,
+    /// HTTPS の証明書（PEM）。--tls-key と一緒に指定する
+    #[arg(long, env = "TLS_CERT")]
+    tls_cert: Option<PathBuf>,
+    /// HTTPS の秘密鍵（PEM）
+    #[arg(long, env = "TLS_KEY")]
+    tls_key: Option<PathBuf>,
+    /// ほかのライブラリ（名前=パス、何度でも指定できる）。環境変数ではカンマ区切り
+    #[arg(long = "library", env = "LIBRARIES", value_delimiter = ',', value_parser = libraries::parse_library)]
+    libraries: Vec<(String, PathBuf)>,
+    /// バックアップ（スナップショット）を置くディレクトリ。なければ DB の隣（"db" なら "db-backups"）
+    #[arg(long, env = "BACKUP_DIR")]
+    backup_dir: Option<PathBuf>,
+    /// 1 日 1 回、自動でバックアップを作る
+    #[arg(long, env = "BACKUP_DAILY")]
+    backup_daily: bool,
+    /// 自動バックアップのあとに残すスナップショットの数（0 なら消さない）
+    #[arg(long, env = "BACKUP_KEEP", default_value_t = 14)]
+    backup_keep: usize,
+    /// この日数より古いスナップショットを消す（0 なら消さない。いちばん新しいものは残す）
+    #[arg(long, env = "BACKUP_MAX_DAYS", default_value_t = 0)]
+    backup_max_days: u32,
+    /// 監査ログ・エラー報告・入力チェックの記録から、この日数より古い行を消す（0 なら消さない）
+    #[arg(long, env = "LOG_MAX_DAYS", default_value_t = 0)]
+    log_max_days: u32,
+    /// レコードの履歴（--git-history）のコミットを新しいものからこの数だけ残す（0 なら数では消さない）
+    #[arg(long, env = "HISTORY_KEEP", default_value_t = 0)]
+    history_keep: usize,
+    /// レコードの履歴のコミットのうち、この日数より古いものを消す（0 なら日数では消さない。いちばん新しいものは残す）
+    #[arg(long, env = "HISTORY_MAX_DAYS", default_value_t = 0)]
+    history_max_days: u32,
+    /// ごみ箱（削除したレコード）に新しいものからこの数だけ残す（0 なら数では消さない）
+    #[arg(long, env = "TRASH_KEEP", default_value_t = 0)]
+    trash_keep: usize,
+    /// ごみ箱から、この日数より前に削除したものを消す（0 なら日数では消さない）
+    #[arg(long, env = "TRASH_MAX_DAYS", default_value_t = 0)]
+    trash_max_days: u32,
+    /// API を呼んでよいほかのオリジン（"https://tools.example.com"、何度でも指定できる）。環境変数ではカンマ区切り。
+    /// 指定しなければ同じオリジンだけ
+    #[arg(long = "cors-origin", env = "CORS_ORIGINS", value_delimiter = ',', value_parser = cors::parse_origin)]
+    cors_origins: Vec<String>,
+    /// どのオリジンからでも呼べるようにする（開発用）
+    #[arg(long, env = "CORS_PERMISSIVE")]
+    cors_permissive: bool,
+    /// サーバー経由の保存・削除・名前の変更のたびに、レコードを DB のディレクトリの git リポジトリにコミットする
+    #[arg(long, env = "GIT_HISTORY")]
+    git_history: bool,
+    /// Discogs の個人のトークン（外部のデータベースから探すとき・`/api/lookup/discogs` に使う）
+    #[arg(long, env = "DISCOGS_TOKEN", hide_env_values = true)]
+    discogs_token: Option<String>,
+    /// CD の TOC から曲目を引く CDDB のサーバー
+    #[arg(long, env = "CDDB_URL", default_value = metadata::cddb::DEFAULT_URL)]
+    cddb_url: String,
+    /// CDDB に名乗るユーザー名（gnudb はメールアドレスを求める）
+    #[arg(long, env = "CDDB_USER", default_value = "nekokan")]
+    cddb_user: String,
+    /// Spotify のアプリのクライアント ID（`/api/lookup/spotify` に使う）
+    #[arg(long, env = "SPOTIFY_CLIENT_ID")]
+    spotify_client_id: Option<String>,
+    /// Spotify のアプリのクライアントシークレット
+    #[arg(long, env = "SPOTIFY_CLIENT_SECRET", hide_env_values = true)]
+    spotify_client_secret: Option<String>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// 監査ログ・再生記録の書き出しと取り込み（サーバーは立てない）
+    #[command(subcommand)]
+    Logs(logs::LogsCommand),
+}
 
 #[tokio::main]
 async fn main() {
-    let db_path = std::env::var("DB_PATH").unwrap_or_else(|_| DB_DIR.to_string());
+    let cli = <Cli as clap::Parser>::parse();
+    let db_path = cli.db_path;
+    let layout = std::env::var("DB_LAYOUT").unwrap_or_default();
+    let layout = storage::Layout::parse(&layout).expect("DB_LAYOUT must be flat, prefix or hash");
+    let compress = match std::env::var("DB_COMPRESS").unwrap_or_default().as_str() {
+        "" | "none" => false,
+        "zstd" => true,
+        other => panic!("DB_COMPRESS must be none or zstd: {}", other),
+    };
+    let fs_storage = open_storage(&db_path, layout, compress);
+    match settings::load_settings(&db_path) {
+        Ok(s) => settings::apply(&s),
+        Err(e) => eprintln!("設定を読めないため、日付は UTC・ラベルは既定の規則で作ります: {}", e),
+    }
+    if let Some(Command::Logs(command)) = cli.command {
+        std::process::exit(logs::run_cli(command, &db_path, &fs_storage));
+    }
+    let tls = tls::configure(cli.tls_cert, cli.tls_key).unwrap_or_else(|e| panic!("{}", e));
+    if cli.cors_permissive {
+        eprintln!("--cors-permissive: どのオリジンからでも API を呼べます。公開するサーバーでは使わないでください");
+    }
+    libraries::check_unique(&cli.libraries).unwrap_or_else(|e| panic!("{}", e));
+    let events = Arc::new(live::LibraryEvents::default());
+    // 監視はサーバーが動いている間だけ持っておく
+    let mut watchers = Vec::new();
+    let mut libraries = vec![open_library(libraries::DEFAULT_LIBRARY, &db_path, fs_storage, cli.git_history, &events, &mut watchers)];
+    for (name, path) in &cli.libraries {
+        let fs_storage = open_storage(path, layout, compress);
+        libraries.push(open_library(name, path, fs_storage, cli.git_history, &events, &mut watchers));
+    }
+    let tokens = auth::TokenStore::load(&db_path).expect("トークンの一覧を読めません");
+    let backup = backup::BackupConfig::new(&db_path, cli.backup_dir, cli.backup_daily, cli.backup_keep);
+    let state = AppState {
+        db_path,
+        storage: libraries[0].storage.clone(),
+        records: libraries[0].records.clone(),
+        libraries: Arc::new(libraries),
+        events,
+        tokens: Arc::new(tokens),
+        rate_limit: Arc::new(rate_limit::RateLimiter::from_env()),
+        save_keys: Arc::new(idempotency::IdempotencyCache::default()),
+        backup: Arc::new(backup),
+        retention: retention::Retention {
+            backup_max_days: cli.backup_max_days,
+            log_max_days: cli.log_max_days,
+            history_keep: cli.history_keep,
+            history_max_days: cli.history_max_days,
+            trash_keep: cli.trash_keep,
+            trash_max_days: cli.trash_max_days,
+        },
+        provider_config: Arc::new(metadata::ProviderConfig {
+            discogs_token: cli.discogs_token,
+            cddb_url: cli.cddb_url,
+            cddb_user: cli.cddb_user,
+            spotify_client_id: cli.spotify_client_id,
+            spotify_client_secret: cli.spotify_client_secret,
+            ..Default::default()
+        }),
+    };
+    sync::spawn_scheduler(state.clone());
+    backup::spawn_scheduler(state.clone());
+    retention::spawn_scheduler(state.clone());
+    digest::spawn_scheduler(state.clone());
     let app = Router::new()
         .route("/api/list", get(list_files))
         .route("/api/list-with-labels", get(list_files_with_labels))
-        .route("/api/save", post(save_file))
-        .route("/api/files/*path", get(get_file))
-        .nest_service("/", ServeDir::new("nekokan_music_wa/dist"))
-        .layer(CorsLayer::new().allow_origin(Any).allow_methods(Any).allow_headers(Any))
-        .with_state(AppState { db_path: PathBuf::from(db_path) });
+        .route("/api/libraries", get(libraries::list))
+        .route("/api/ws", get(live::ws))
+        .route("/api/validate-all", get(validate_all::validate_all))
+        .route("/api/validation-policy", get(policy::get_policy).post(policy::save_policy))
+        .route("/api/scores/rescale", post(scores::rescale))
+        .route("/api/dates/fill-updated", post(dates::fill_updated))
+        .route("/api/schema", get(schema::get_schema))
+        .route("/api/schema/canonicalize", post(schema::canonicalize))
+        .route("/api/schema/migrate", post(migrations::migrate))
+        .route("/api/save", post(save_file).layer(DefaultBodyLimit::max(RECORD_MAX_BYTES)))
+        .route(
+            "/api/files/*path",
+            get(get_file).post(audio::upload).delete(delete_file).layer(DefaultBodyLimit::max(audio::AUDIO_MAX_BYTES)),
+        )
+        .route("/api/audio/:name", get(audio::list))
+        .route("/api/rename", post(rename::rename))
+        .route("/api/normalize-filenames", post(normalize_filenames::normalize))
+        .route("/api/history/:name", get(git_history::get_history))
+        .route("/api/stats", get(stats::get_stats))
+        .route("/api/stats/listens", get(stats::get_listen_stats))
+        .route("/api/search", get(search::search))
+        .route("/api/tunes", get(tunes::get_tunes))
+        .route("/api/personnel/:name", get(personnel::get_personnel))
+        .route("/api/labels", get(labels::get_labels))
+        .route("/api/composers", get(names::get_composers))
+        .route("/api/names", get(names::get_names).post(names::save_names))
+        .route("/api/activity", get(activity::get_activity))
+        .route("/api/recent", get(recent::get_recent))
+        .route("/api/random", get(random::get_random))
+        .route("/api/calendar", get(calendar::get_calendar))
+        .route("/api/listen", post(listen::append_listen))
+        .route("/api/draft", post(listen::create_draft))
+        .route(
+            "/api/covers",
+            post(covers::upload_cover).layer(DefaultBodyLimit::max(COVER_MAX_BYTES)),
+        )
+        .route("/api/covers/:name", get(covers::get_cover))
+        .route(
+            "/api/attachments/:name",
+            get(attachments::list).post(attachments::upload).layer(DefaultBodyLimit::max(ATTACHMENT_MAX_BYTES)),
+        )
+        .route("/api/attachments/:name/:file", get(attachments::get).delete(attachments::delete))
+        .route("/api/duplicates", get(duplicates::list))
+        .route("/api/duplicates/check", post(duplicates::check))
+        .route("/api/metadata/providers", get(metadata::list_providers))
+        .route("/api/metadata/search", get(metadata::search))
+        .route("/api/metadata/releases/:id", get(metadata::release))
+        .route("/api/metadata/cover", post(metadata::import_cover))
+        .route("/api/lookup/cddb", get(metadata::cddb::lookup))
+        .route("/api/lookup/:provider", get(metadata::lookup))
+        .route("/api/export/discogs.csv", get(discogs::export_csv))
+        .route("/api/import/discogs", post(discogs::import_csv))
+        .route("/api/import/url", post(remote_import::import_url))
+        .route("/api/export/graph", get(graph::export_graph))
+        .route("/api/export/zip", get(export_zip::export_zip))
+        .route("/api/export/year-review", get(year_review::export_year_review))
+        .route("/api/notes/:name", get(notes::get_notes).post(notes::add_note))
+        .route("/api/notes/:name/:id", patch(notes::update_note).delete(notes::delete_note))
+        .route("/api/goals", get(goals::get_goals).post(goals::save_goals))
+        .route("/api/settings", get(settings::get_settings).post(settings::save_settings))
+        .route("/api/client-errors", post(client_errors::report))
+        .route("/api/validation-failures", post(validation_log::report))
+        .route("/api/validation-failures/summary", get(validation_log::summary))
+        .route("/api/validation-rules", get(validation_rules::get_rules))
+        .route("/api/logs/:kind", get(logs::export_logs).post(logs::import_logs))
+        .route("/api/audit", get(audit::get_audit))
+        .route("/api/tokens", get(auth::list).post(auth::create))
+        .route("/api/tokens/:id", axum::routing::delete(auth::revoke))
+        .route("/api/session", post(auth::login).delete(auth::logout))
+        .route("/api/sync", get(sync::get_status).post(sync::save_config))
+        .route("/api/sync/manifest", get(sync::manifest))
+        .route("/api/sync/pull", post(sync::pull_now))
+        .route("/api/digest", get(digest::get_status).post(digest::save_config))
+        .route("/api/digest/preview", get(digest::preview))
+        .route("/api/digest/send", post(digest::send_now))
+        .route("/api/backup", post(backup::create))
+        .route("/api/backups", get(backup::list))
+        .route("/api/backups/prune", post(backup::prune_snapshots))
+        .route("/api/backups/:name", axum::routing::delete(backup::delete))
+        .route("/api/restore/:name", post(backup::restore))
+        .route("/api/maintenance/retention", get(retention::get_status))
+        .route("/api/maintenance/prune", post(retention::prune_now))
+        .route("/api/trash", get(trash::get_list))
+        .route("/api/trash/:name/restore", post(trash::restore))
+        .route("/api/genres", get(genres::get_usage))
+        .route("/api/genres/migrate", post(genres::migrate))
+        .merge(openapi::swagger_ui())
+        .nest_service("/", ServeDir::new(&cli.dist_dir))
+        .layer(axum::middleware::from_fn_with_state(state.clone(), auth::require_token))
+        // トークンの総当たりも抑えるよう、認証より外側に置く
+        .layer(axum::middleware::from_fn_with_state(state.clone(), rate_limit::limit))
+        // 認証・回数制限・本文の読み取りのエラーも同じ形にそろえる
+        .layer(axum::middleware::from_fn(problem::envelope))
+        .layer(cors::layer(&cli.cors_origins, cli.cors_permissive))
+        .layer(compression::layer())
+        .with_state(state.clone());
+
+    let addr = std::net::SocketAddr::new(cli.host, cli.port);
+    let app = app.into_make_service_with_connect_info::<std::net::SocketAddr>();
+    match tls {
+        Some(paths) => {
+            let _ = rustls::crypto::ring::default_provider().install_default();
+            let config = axum_server::tls_rustls::RustlsConfig::from_pem_file(&paths.cert, &paths.key)
+                .await
+                .unwrap_or_else(|e| panic!("証明書・鍵（--tls-cert / --tls-key）を読めません: {}", e));
+            println!("https://{} で待ち受けます", addr);
+            let handle = axum_server::Handle::new();
+            tokio::spawn({
+                let handle = handle.clone();
+                async move {
+                    shutdown_signal().await;
+                    handle.graceful_shutdown(None);
+                }
+            });
+            axum_server::bind_rustls(addr, config).handle(handle).serve(app).await.unwrap();
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+            axum::serve(listener, app).with_graceful_shutdown(shutdown_signal()).await.unwrap();
+        }
+    }
+    // 積んである git のコミットを済ませてから止まる
+    for library in state.libraries.iter() {
+        if let Some(history) = &library.history {
+            history.queue.flush();
+        }
+    }
+    // 処理中の保存（spawn_blocking）はランタイムを閉じるときに終わるまで待つ
+    println!("終了しました");
+}
+
+/// ライブラリのディレクトリのストレージ。置き方（DB_LAYOUT / DB_COMPRESS）が変わっていれば移す
+fn open_storage(path: &std::path::Path, layout: storage::Layout, compress: bool) -> storage::FsStorage {
+    let fs_storage = storage::FsStorage::new(path, layout, compress);
+    match fs_storage.migrate() {
+        Ok(0) => {}
+        Ok(n) => println!("{}: {} 件のレコードを DB_LAYOUT / DB_COMPRESS の置き方に移しました", path.display(), n),
+        Err(e) => eprintln!("{}: レコードの置き直しに失敗しました: {}", path.display(), e),
+    }
+    fs_storage
+}
 
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:12989").await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+/// 一覧のキャッシュと監視をつけたライブラリ。監視は `watchers` に足す。`git_history` なら書き込みを git にコミットする
+fn open_library(
+    name: &str,
+    path: &std::path::Path,
+    fs_storage: storage::FsStorage,
+    git_history: bool,
+    events: &Arc<live::LibraryEvents>,
+    watchers: &mut Vec<notify::RecommendedWatcher>,
+) -> libraries::Library {
+    let records = Arc::new(record_cache::RecordCache::new(path));
+    match records.watch(events.clone()) {
+        Ok(w) => watchers.push(w),
+        Err(e) => eprintln!("{} を監視できないため、一覧は毎回読み直します: {}", path.display(), e),
+    }
+    let repo = git_history.then(|| git_history::GitRepo::open(path)).map(|r| {
+        Arc::new(r.unwrap_or_else(|e| panic!("{} を git のリポジトリにできません: {}", path.display(), e)))
+    });
+    let history = repo.map(|repo| {
+        let queue = Arc::new(git_history::CommitQueue::start(repo.clone()));
+        Arc::new(git_history::LibraryHistory { repo, queue, layout: fs_storage.clone() })
+    });
+    let committing = git_history::Committing { inner: fs_storage, queue: history.as_ref().map(|h| h.queue.clone()) };
+    let storage = record_cache::Invalidating { inner: committing, cache: records.clone(), events: events.clone() };
+    libraries::Library { name: name.to_string(), dir: path.to_path_buf(), storage: Arc::new(storage), records, history }
+}
+
+/// Ctrl+C（SIGINT）か SIGTERM を受けたら新しい接続を断り、処理中のリクエストが終わってから止まる
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut s) => {
+                s.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    println!("終了の合図を受けました。処理中のリクエストを終えてから止まります");
 }
 
 #[derive(Clone)]
 struct AppState {
+    /// カバー・メモ・設定など、レコード以外のファイルの置き場所
     db_path: PathBuf,
+    /// レコード JSON の読み書き
+    storage: Arc<dyn storage::Storage>,
+    /// 一覧用のレコードのキャッシュ（storage への書き込みと db/ の監視で更新する）
+    records: Arc<record_cache::RecordCache>,
+    /// ライブラリの一覧（先頭が既定のもので、storage・records はその中身）
+    libraries: Arc<Vec<libraries::Library>>,
+    /// レコードの作成・更新・削除の通知（`/api/ws`）
+    events: Arc<live::LibraryEvents>,
+    /// 端末ごとの API トークン（なければ認証なし）
+    tokens: Arc<auth::TokenStore>,
+    /// IP ごとの書き込み・検索の回数制限
+    rate_limit: Arc<rate_limit::RateLimiter>,
+    /// 保存リクエストの冪等キーと結果
+    save_keys: Arc<idempotency::IdempotencyCache>,
+    /// バックアップの置き場所と自動バックアップ
+    backup: Arc<backup::BackupConfig>,
+    /// スナップショットとログを残す日数
+    retention: retention::Retention,
+    /// 外部のデータベースのトークン
+    provider_config: Arc<metadata::ProviderConfig>,
 }
 
-async fn list_files(axum::extract::State(state): axum::extract::State<AppState>) -> impl IntoResponse {
-    let dir = state.db_path;
-    let Ok(entries) = fs::read_dir(&dir) else {
+#[utoipa::path(get, path = "/api/list", tag = "records", summary = "レコードのファイル名の一覧", params(libraries::LibraryQuery))]
+async fn list_files(libraries::Lib(library): libraries::Lib) -> impl IntoResponse {
+    let Ok(names) = library.storage.list() else {
         return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!([]))).into_response();
     };
-    let mut names: Vec<String> = entries
-        .filter_map(|e| e.ok())
-        .filter_map(|e| {
-            let n = e.file_name();
-            let s = n.to_string_lossy();
-            if s.ends_with(".json") {
-                Some(s.to_string())
-            } else {
-                None
-            }
-        })
-        .collect();
-    names.sort();
     (StatusCode::OK, Json(names)).into_response()
 }
 
-/// アーティスト（またはラベル）とタイトルの区切り（コロン + スペース1つ）
-const ARTIST_TITLE_SEP: &str = ": ";
+/// 数値か数字の文字列（"2000"）を整数として読む。
+fn int_from_value(v: &Value) -> Option<i64> {
+    v.as_i64().or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+/// 録音年（数か数字の文字列の配列）を年の順に。重なりは 1 つにする
+fn record_years_from_value(v: &Value) -> Vec<i64> {
+    let mut years: Vec<i64> = v["record_year"].as_array().into_iter().flatten().filter_map(int_from_value).filter(|y| *y > 0).collect();
+    years.sort_unstable();
+    years.dedup();
+    years
+}
 
-/// 音楽JSONからサイドバー用表示ラベルを算出する。
-/// ジャンルがGameの場合は "{Label}: {タイトル}"。
-/// それ以外は 優先順位: leader(1人) → leader(複数) et al. → group → soloists → conductor → orchestra → [Artist Unknown]
-/// アーティストとタイトルは ": " で区切る（例: Bill Evans: Alone）。
+/// レコードの表示ラベル（設定のジャンルごとのテンプレートを使う）
 fn display_label_from_value(v: &Value) -> String {
-    let title = v["title"].as_str().unwrap_or("").to_string();
-    if v["janre"]["main"].as_str() == Some("Game") {
-        let label_val = v["label"].as_str().unwrap_or("").to_string();
-        return format!("{}{}{}", label_val, ARTIST_TITLE_SEP, title).trim().to_string();
-    }
-    let personnel = &v["personnel"];
-    let first_leader_name = personnel["leader"]
-        .as_array()
-        .and_then(|a| a.first())
-        .and_then(|o| o["name"].as_str());
-    let leader_count = personnel["leader"].as_array().map(|a| a.len()).unwrap_or(0);
-    let first_group_name = personnel["group"]
-        .as_array()
-        .and_then(|a| a.first())
-        .and_then(|o| o["name"].as_str());
-    let first_soloist = personnel["soloists"]
-        .as_array()
-        .and_then(|a| a.first())
-        .and_then(|o| o["name"].as_str());
-    let first_conductor = personnel["conductor"]
-        .as_array()
-        .and_then(|a| a.first())
-        .and_then(|o| o["name"].as_str());
-    let first_orchestra = personnel["orchestra"]
-        .as_array()
-        .and_then(|a| a.first())
-        .and_then(|o| o["name"].as_str());
-
-    let label = if leader_count == 1 {
-        format!("{}{}{}", first_leader_name.unwrap_or(""), ARTIST_TITLE_SEP, title)
-    } else if leader_count > 1 {
-        format!(
-            "{} et al.{}{}",
-            first_leader_name.unwrap_or(""),
-            ARTIST_TITLE_SEP,
-            title
-        )
-    } else if let Some(name) = first_group_name {
-        format!("{}{}{}", name, ARTIST_TITLE_SEP, title)
-    } else if let Some(name) = first_soloist {
-        format!("{}{}{}", name, ARTIST_TITLE_SEP, title)
-    } else if let Some(name) = first_conductor {
-        format!("{}{}{}", name, ARTIST_TITLE_SEP, title)
-    } else if let Some(name) = first_orchestra {
-        format!("{}{}{}", name, ARTIST_TITLE_SEP, title)
-    } else {
-        format!("[Artist Unknown]{}{}", ARTIST_TITLE_SEP, title)
-    };
-    label.trim().to_string()
+    label::display_label_from_value(v, &settings::label_templates())
+}
+
+/// スコア（数値か数字の文字列、0.5 刻みの尺度では小数）を読む。
+fn score_from_value(v: &Value) -> Option<f64> {
+    v["score"].as_f64().or_else(|| v["score"].as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+/// 全レコードを (ファイル名, 値) で読み込む（ファイル名順）。
+/// 読めない・パースできないファイルは飛ばす。一覧自体が取れなければ None。
+fn load_all_records(storage: &dyn storage::Storage) -> Option<Vec<(String, Value)>> {
+    let names = storage.list().ok()?;
+    let records = names
+        .into_iter()
+        .filter_map(|filename| {
+            let v = storage::read_record(storage, &filename).ok()?;
+            Some((filename, v))
+        })
+        .collect();
+    Some(records)
 }
 
 #[derive(serde::Serialize)]
 struct ListEntryWithLabel {
     filename: String,
     display_label: String,
+    /// ラベルの材料（フロントが label.rs の規則で表示を組み立てる）
+    #[serde(flatten)]
+    label_fields: label::LabelFields,
+    score: Option<f64>,
+    release_year: Option<i64>,
+    /// 録音年（年の順）。`janre.main` はラベルの材料の `genre`
+    record_year: Vec<i64>,
+    /// 入力完成度（0〜100）
+    completeness: u8,
+    /// サイドバーに出すバッジ（色の絵文字など）
+    #[serde(skip_serializing_if = "String::is_empty")]
+    badge: String,
+    /// レコードの記録日（`date`、YYYY/MM/DD）
+    date: String,
+    /// ファイルの最終更新日（YYYY/MM/DD）
+    modified: Option<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/list-with-labels",
+    tag = "records",
+    summary = "レコードの一覧（表示ラベル・ジャンル・スコア・発売年・録音年などつき）",
+    params(libraries::LibraryQuery),
+)]
 async fn list_files_with_labels(
-    axum::extract::State(state): axum::extract::State<AppState>,
+    libraries::Lib(library): libraries::Lib,
+    axum::extract::Query(filter): axum::extract::Query<list_filter::ListFilter>,
 ) -> impl IntoResponse {
-    let dir = state.db_path;
-    let Ok(entries) = fs::read_dir(&dir) else {
+    let Ok(records) = library.records.records(&*library.storage) else {
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json::<Vec<ListEntryWithLabel>>(vec![]),
         )
             .into_response();
     };
-    let mut list: Vec<ListEntryWithLabel> = entries
-        .filter_map(|e| e.ok())
-        .filter_map(|e| {
-            let n = e.file_name();
-            let s = n.to_string_lossy();
-            if !s.ends_with(".json") {
-                return None;
-            }
-            let filename = s.to_string();
-            let full = dir.join(&filename);
-            let Ok(data) = fs::read_to_string(&full) else {
-                return None;
-            };
-            let Ok(v) = serde_json::from_str::<Value>(&data) else {
-                return None;
-            };
-            let display_label = display_label_from_value(&v);
-            Some(ListEntryWithLabel {
+    // 中身はキャッシュから取り、変わったものだけ読み直す。絞り込み・並べ替えもここで済ませる
+    let records = records.into_iter().filter(|(_, r)| filter.matches(&r.value)).collect();
+    let covers = covers::cover_files(&library.dir);
+    let list: Vec<ListEntryWithLabel> = filter
+        .sort(records)
+        .into_iter()
+        .map(|(filename, r)| {
+            let v = &r.value;
+            ListEntryWithLabel {
+                modified: r.modified.and_then(stats::date_from_system_time),
                 filename,
-                display_label,
-            })
+                display_label: display_label_from_value(v),
+                label_fields: label::LabelFields::from_value(v),
+                score: score_from_value(v),
+                release_year: int_from_value(&v["release_year"]),
+                record_year: record_years_from_value(v),
+                completeness: completeness::completeness_from_value(v, covers.contains_cover_of(v)),
+                badge: v["badge"].as_str().unwrap_or("").to_string(),
+                date: v["date"].as_str().unwrap_or("").to_string(),
+            }
         })
         .collect();
-    list.sort_by(|a, b| a.filename.cmp(&b.filename));
     (StatusCode::OK, Json(list)).into_response()
 }
 
+#[derive(serde::Deserialize)]
+struct FileParams {
+    /// "md"（"markdown"）なら Markdown で返す
+    #[serde(default)]
+    format: String,
+}
+
+/// `GET /api/files/{name}` レコードの JSON。`/api/files/{name}/markdown` か `?format=md` なら Markdown（markdown.rs）。
+/// `/api/files/{name}/audio` は試聴用の音声（audio.rs）
+#[utoipa::path(
+    get,
+    path = "/api/files/{path}",
+    tag = "records",
+    summary = "レコードを読む（ETag に版。{name}/audio なら試聴用の音声、Range 可）",
+    params(libraries::LibraryQuery),
+    responses((status = 200, description = "レコード（ETag に版）", body = openapi::Record)),
+)]
 async fn get_file(
-    axum::extract::State(state): axum::extract::State<AppState>,
+    libraries::Lib(library): libraries::Lib,
     Path(path): Path<String>,
+    axum::extract::Query(params): axum::extract::Query<FileParams>,
+    request: axum::extract::Request,
 ) -> impl IntoResponse {
+    if path.ends_with(audio::FILES_SUFFIX) {
+        return audio::serve(&library.dir, &path, request).await;
+    }
     let path = path.trim_start_matches('/');
+    let (path, as_markdown) = match path.strip_suffix("/markdown") {
+        Some(p) => (p, true),
+        None => match params.format.as_str() {
+            "" | "json" => (path, false),
+            "md" | "markdown" => (path, true),
+            _ => return error(StatusCode::BAD_REQUEST, "format must be json or md"),
+        },
+    };
     if path.contains("..") || path.contains('\\') {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({"error": "invalid path"})),
-        )
-            .into_response();
+        return error(StatusCode::BAD_REQUEST, "invalid path");
     }
-    let full = state.db_path.join(path);
-    if full.strip_prefix(&state.db_path).is_err() {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(serde_json::json!({"error": "forbidden"})),
-        )
-            .into_response();
+    // レコードは置き方（DB_LAYOUT）によらずファイル名だけで引く
+    if path.contains('/') {
+        return error(StatusCode::BAD_REQUEST, "invalid path");
     }
-    // Issue #14: read as bytes then decode with lossy so non-UTF8 files (e.g. BOM, legacy encoding) still load
-    let bytes = match fs::read(&full) {
-        Ok(b) => b,
-        Err(e) => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(serde_json::json!({"error": format!("file not found: {}", e)})),
+    match storage::read_record(&*library.storage, path) {
+        Ok(json) if as_markdown => match serde_json::from_value::<types::MusicData>(json) {
+            Ok(data) => (
+                StatusCode::OK,
+                [(axum::http::header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+                markdown::render_album(&data),
             )
-                .into_response();
+                .into_response(),
+            Err(e) => error(StatusCode::UNPROCESSABLE_ENTITY, format!("レコードの形式が正しくありません: {}", e)),
+        },
+        Ok(json) => {
+            let etag = versions::etag(&versions::version_of(&json));
+            (StatusCode::OK, [(axum::http::header::ETAG, etag)], Json(json)).into_response()
         }
-    };
-    let data = String::from_utf8_lossy(&bytes).to_string();
-    let json: Value = match serde_json::from_str(&data) {
-        Ok(j) => j,
-        Err(e) => {
-            return (
-                StatusCode::UNPROCESSABLE_ENTITY,
-                Json(serde_json::json!({"error": format!("invalid json: {}", e)})),
-            )
-                .into_response();
+        Err(e) => error(e.status(), e.message()),
+    }
+}
+
+/// `DELETE /api/files/{name}` レコードを消す。カバー・メモ・添付もいっしょに消す。
+/// `/api/files/{name}/audio` は試聴用の音声だけ消す（audio.rs）
+#[utoipa::path(
+    delete,
+    path = "/api/files/{path}",
+    tag = "records",
+    summary = "レコードを消す（{name}/audio なら試聴用の音声）",
+    params(libraries::LibraryQuery),
+)]
+async fn delete_file(
+    libraries::Lib(library): libraries::Lib,
+    Path(path): Path<String>,
+    axum::extract::Query(slot): axum::extract::Query<audio::Slot>,
+) -> impl IntoResponse {
+    if path.ends_with(audio::FILES_SUFFIX) {
+        return audio::delete(&library.dir, &path, slot);
+    }
+    let path = path.trim_start_matches('/');
+    // get_file と同じく、パスを含むものやファイル名でないものは受け付けない
+    if path.contains("..") || path.contains('\\') || path.contains('/') || !path.ends_with(".json") {
+        return error(StatusCode::BAD_REQUEST, "invalid path");
+    }
+    // 読めないレコード（壊れた JSON）でも消せるように、カバー名は読めたときだけ使う
+    let cover = storage::read_record(&*library.storage, path)
+        .ok()
+        .and_then(|v| v["cover"].as_str().map(str::to_string));
+    // 消す前の中身をごみ箱に残す。残せなければ消さない
+    let removed = {
+        let _lock = versions::save_lock();
+        match library.storage.read(path) {
+            Ok(data) => trash::put(&library.dir, path, &data).and_then(|_| library.storage.remove(path)),
+            Err(e) => Err(e),
         }
     };
-    (StatusCode::OK, Json(json)).into_response()
+    if let Err(e) = removed {
+        let status = if e.kind() == std::io::ErrorKind::NotFound {
+            StatusCode::NOT_FOUND
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+        return error(status, e.to_string());
+    }
+    let change = audit::RecordChange { action: "delete", file: path.to_string(), ..Default::default() }.in_library(&library);
+    // 付随するファイルは消せなくてもレコードの削除は成功として返す
+    if let Some(cover) = cover.filter(|c| !c.contains(['/', '\\']) && !c.contains("..")) {
+        let _ = std::fs::remove_file(library.dir.join(covers::COVERS_DIR).join(cover));
+    }
+    let _ = std::fs::remove_file(library.dir.join(notes::NOTES_DIR).join(path));
+    let _ = std::fs::remove_dir_all(library.dir.join(attachments::ATTACHMENTS_DIR).join(path.trim_end_matches(".json")));
+    let mut res = (StatusCode::OK, Json(serde_json::json!({"ok": true, "filename": path}))).into_response();
+    res.extensions_mut().insert(change);
+    res
 }
 
-#[derive(serde::Deserialize)]
+/// クライアントから受け取ったファイル名を "xxx.json" に正規化する。
+/// パス区切り・".."・":" は取り除き、空になる場合は None。
+fn normalize_filename(raw: &str) -> Option<String> {
+    let mut filename = raw.trim().to_string();
+    if filename.ends_with(".json") {
+        filename = filename.strip_suffix(".json").unwrap_or(&filename).to_string();
+    }
+    filename = filename.replace("..", "").replace(['/', '\\', ':'], "");
+    if filename.is_empty() {
+        return None;
+    }
+    Some(format!("{}.json", filename))
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
 struct SaveBody {
     filename: String,
+    #[schema(value_type = crate::openapi::Record)]
     data: Value,
 }
 
+/// `POST /api/save` `Idempotency-Key` ヘッダーつきの再送には最初の結果を返す（同じキーで中身が違えば 422）。
+/// `If-Match`（読んだときの `ETag`）か `If-None-Match: *` が要る（なければ 428）。今のレコードと合わないときは書かずに 409（versions.rs）。
+/// 書けたら新しい版を `version` で返す。
+#[utoipa::path(
+    post,
+    path = "/api/save",
+    tag = "records",
+    summary = "レコードを保存する（If-Match / If-None-Match のどちらかが要る、Idempotency-Key）",
+    params(libraries::LibraryQuery),
+    request_body = SaveBody,
+)]
 async fn save_file(
     axum::extract::State(state): axum::extract::State<AppState>,
-    Json(body): Json<SaveBody>,
+    libraries::Lib(library): libraries::Lib,
+    method: axum::http::Method,
+    uri: axum::http::Uri,
+    headers: axum::http::HeaderMap,
+    Json(mut body): Json<SaveBody>,
 ) -> impl IntoResponse {
-    let mut filename = body.filename.trim().to_string();
-    if filename.ends_with(".json") {
-        filename = filename.strip_suffix(".json").unwrap_or(&filename).to_string();
-    }
-    filename = filename
-        .replace("..", "")
-        .replace('/', "")
-        .replace('\\', "")
-        .replace(':', "");
-    if filename.is_empty() {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "invalid filename"}))).into_response();
-    }
-    let filename = format!("{}.json", filename);
-    let full = state.db_path.join(&filename);
-    if full.strip_prefix(&state.db_path).is_err() {
-        return (StatusCode::FORBIDDEN, Json(serde_json::json!({"error": "forbidden"}))).into_response();
-    }
-    let Ok(json_str) = serde_json::to_string_pretty(&body.data) else {
-        return (StatusCode::BAD_REQUEST, Json(serde_json::json!({"error": "invalid json"}))).into_response();
+    // 冪等キーは受け取った本文のまま（表記を揃える前）のハッシュと組にする
+    let idempotency_key = idempotency::key_from(&headers, &method, &uri, &serde_json::json!({"filename": body.filename, "data": body.data}));
+    let Some(filename) = normalize_filename(&body.filename) else {
+        return error(StatusCode::BAD_REQUEST, "invalid filename");
     };
-    if let Err(e) = fs::write(&full, json_str) {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(serde_json::json!({"error": e.to_string()})),
-        )
-            .into_response();
+    // 作曲者の表記を揃える（台帳が読めなくても保存は止めない）
+    let names = names::load_names(&state.db_path).unwrap_or_default();
+    names::normalize_record(&mut body.data, &names.index());
+    let Some(precondition) = versions::Precondition::from_headers(&headers) else {
+        return versions::required().into_response();
+    };
+    // 変更の記録に足す中身（同じキーの再送では書かないので入らない）
+    let change = Arc::new(std::sync::Mutex::new(None::<audit::RecordChange>));
+    let (status, json) = state
+        .save_keys
+        .run(idempotency_key, || async {
+            // クライアントがタイムアウトして切断しても書き込みは最後まで行う（ハンドラと一緒に捨てられないよう別タスクで）。
+            let store = library.storage.clone();
+            let change = change.clone();
+            let res = tokio::task::spawn_blocking(move || {
+                let written = versions::write(&*store, &filename, &precondition, &body.data)
+                    .map_err(|e| (e.status(), e.problem().to_value()))?;
+                let saved = audit::RecordChange::saved(&filename, written.old.as_ref(), &written.value).in_library(&library);
+                *change.lock().unwrap_or_else(std::sync::PoisonError::into_inner) = Some(saved);
+                // 書いた形（数の書き方など）で読み直したものの版を返す。GET の ETag と揃うように
+                Ok(written.version)
+            })
+            .await
+            .unwrap_or_else(|e| Err((StatusCode::INTERNAL_SERVER_ERROR, problem::Problem::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).to_value())));
+            match res {
+                Ok(version) => (StatusCode::OK, serde_json::json!({"ok": true, "version": version})),
+                Err(e) => e,
+            }
+        })
+        .await;
+    // 失敗のときの json は problem+json の形（Content-Type は problem::envelope で付く）
+    let mut res = (status, Json(json)).into_response();
+    if let Some(change) = change.lock().unwrap_or_else(std::sync::PoisonError::into_inner).take() {
+        res.extensions_mut().insert(change);
     }
-    (StatusCode::OK, Json(serde_json::json!({"ok": true}))).into_response()
+    res
 }
@@ -0,0 +1,198 @@
+//! 1 枚のレコードを Markdown にする（`GET /api/files/{name}/markdown` か `?format=md`）。メモ帳に貼る用。
+//! タイトル・基本情報・パーソネルの表・曲目（ディスクごと、長さの合計つき）・コメント・参考リンクの順に並べる。
+
+use std::fmt::Write;
+
+use crate::label::LabelFields;
+use crate::length::{format_duration, parse_length};
+use crate::types::MusicData;
+use crate::validation::format_score;
+use crate::year_review::cell;
+
+/// "Bill Evans（ビル・エヴァンス）"
+fn with_alt(name: &str, alt: &str) -> String {
+    if alt.trim().is_empty() {
+        name.to_string()
+    } else {
+        format!("{}（{}）", name, alt.trim())
+    }
+}
+
+/// パーソネルの表の行（役割, 名前, 楽器, 曲）
+fn personnel_rows(d: &MusicData) -> Vec<[String; 4]> {
+    let p = &d.personnel;
+    let mut rows = Vec::new();
+    for e in &p.leader {
+        rows.push(["Leader".into(), with_alt(&e.name, &e.name_alt), e.instruments.clone(), e.tracks.clone()]);
+    }
+    for g in &p.group {
+        for m in &g.members {
+            let role = if m.leader { format!("{}（leader）", g.name) } else { g.name.clone() };
+            rows.push([role, with_alt(&m.name, &m.name_alt), m.instruments.clone(), m.tracks.clone()]);
+        }
+        if g.members.is_empty() {
+            rows.push(["Group".into(), g.name.clone(), String::new(), String::new()]);
+        }
+    }
+    for e in &p.sidemen {
+        rows.push(["Sidemen".into(), with_alt(&e.name, &e.name_alt), e.instruments.clone(), e.tracks.clone()]);
+    }
+    for e in &p.soloists {
+        rows.push(["Soloist".into(), with_alt(&e.name, &e.name_alt), e.instrument.clone(), e.tracks.clone()]);
+    }
+    for e in &p.conductor {
+        rows.push(["Conductor".into(), with_alt(&e.name, &e.name_alt), String::new(), e.tracks.clone()]);
+    }
+    for e in &p.orchestra {
+        rows.push(["Orchestra".into(), e.name.clone(), String::new(), e.tracks.clone()]);
+    }
+    for e in &p.company {
+        rows.push(["Company".into(), e.name.clone(), String::new(), e.tracks.clone()]);
+    }
+    rows.retain(|r| !r[1].trim().is_empty());
+    rows
+}
+
+/// 曲の作曲者（"A | B"）を "A, B" にする
+fn composers(s: &str) -> String {
+    s.split('|').map(str::trim).filter(|c| !c.is_empty()).collect::<Vec<_>>().join(", ")
+}
+
+/// 曲の長さの合計（読めない長さがあれば "以上" を付ける）
+fn total_length(tracks: &[&crate::types::Track]) -> Option<String> {
+    let secs: Vec<Option<u64>> = tracks.iter().map(|t| parse_length(&t.length)).collect();
+    let sum: u64 = secs.iter().flatten().sum();
+    if sum == 0 {
+        return None;
+    }
+    let partial = if secs.iter().any(Option::is_none) { " 以上" } else { "" };
+    Some(format!("{}{}", format_duration(sum), partial))
+}
+
+pub fn render_album(d: &MusicData) -> String {
+    let fields = serde_json::to_value(d).map(|v| LabelFields::from_value(&v)).unwrap_or_default();
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}\n", if d.title.trim().is_empty() { "（タイトルなし）" } else { d.title.trim() });
+    if !d.title_alt.trim().is_empty() {
+        let _ = writeln!(out, "*{}*\n", d.title_alt.trim());
+    }
+
+    let mut info = vec![("アーティスト", fields.artist())];
+    if !d.composers.is_empty() {
+        info.push(("作曲者", d.composers.join(", ")));
+    }
+    let label = format!("{} {}", d.label.trim(), d.id.trim()).trim().to_string();
+    info.push(("レーベル", label));
+    if d.release_year > 0 {
+        info.push(("発売年", d.release_year.to_string()));
+    }
+    if !d.record_year.is_empty() {
+        info.push(("録音年", d.record_year.iter().map(i32::to_string).collect::<Vec<_>>().join(", ")));
+    }
+    let genre = std::iter::once(d.janre.main.as_str())
+        .chain(d.janre.sub.iter().map(String::as_str))
+        .filter(|s| !s.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join(" / ");
+    info.push(("ジャンル", genre));
+    if d.score > 0.0 {
+        info.push(("スコア", format_score(d.score)));
+    }
+    info.push(("登録日", d.date.clone()));
+    for (name, value) in info.into_iter().filter(|(_, v)| !v.trim().is_empty()) {
+        let _ = writeln!(out, "- {}: {}", name, value.trim());
+    }
+
+    let rows = personnel_rows(d);
+    if !rows.is_empty() {
+        let _ = writeln!(out, "\n## パーソネル\n");
+        let _ = writeln!(out, "| 役割 | 名前 | 楽器 | 曲 |\n| --- | --- | --- | --- |");
+        for r in &rows {
+            let _ = writeln!(out, "| {} | {} | {} | {} |", cell(&r[0]), cell(&r[1]), cell(&r[2]), cell(&r[3]));
+        }
+    }
+
+    let tracks: Vec<_> = d.tracks.iter().filter(|t| !t.title.trim().is_empty()).collect();
+    if !tracks.is_empty() {
+        let _ = writeln!(out, "\n## 曲目\n");
+        let mut discs: Vec<i32> = tracks.iter().map(|t| t.disc_no).collect();
+        discs.sort_unstable();
+        discs.dedup();
+        let with_composer = tracks.iter().any(|t| !t.composer.trim().is_empty());
+        for disc in &discs {
+            let on_disc: Vec<_> = tracks.iter().copied().filter(|t| t.disc_no == *disc).collect();
+            if discs.len() > 1 {
+                let _ = writeln!(out, "### Disc {}\n", disc);
+            }
+            if with_composer {
+                let _ = writeln!(out, "| No. | 曲名 | 作曲者 | 長さ |\n| ---: | --- | --- | ---: |");
+            } else {
+                let _ = writeln!(out, "| No. | 曲名 | 長さ |\n| ---: | --- | ---: |");
+            }
+            for t in &on_disc {
+                if with_composer {
+                    let _ = writeln!(out, "| {} | {} | {} | {} |", t.no, cell(&t.title), cell(&composers(&t.composer)), cell(&t.length));
+                } else {
+                    let _ = writeln!(out, "| {} | {} | {} |", t.no, cell(&t.title), cell(&t.length));
+                }
+            }
+            if let Some(total) = total_length(&on_disc) {
+                let _ = writeln!(out, "\n合計 {}", total);
+            }
+            out.push('\n');
+        }
+        if discs.len() > 1 {
+            if let Some(total) = total_length(&tracks) {
+                let _ = writeln!(out, "全 {} 枚 合計 {}\n", discs.len(), total);
+            }
+        }
+        while out.ends_with("\n\n") {
+            out.pop();
+        }
+    }
+
+    if !d.comment.trim().is_empty() {
+        let _ = writeln!(out, "\n## コメント\n\n{}", d.comment.trim());
+    }
+
+    let refs: Vec<_> = d.references.iter().filter(|r| !r.url.trim().is_empty()).collect();
+    if !refs.is_empty() {
+        let _ = writeln!(out, "\n## 参考\n");
+        for r in refs {
+            let name = if r.name.trim().is_empty() { r.url.trim() } else { r.name.trim() };
+            let _ = writeln!(out, "- [{}]({})", name.replace(['[', ']'], ""), r.url.trim());
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod markdown_tests {
+    use super::render_album;
+    use crate::types::MusicData;
+    use serde_json::json;
+
+    #[test]
+    fn album_sections() {
+        let d: MusicData = serde_json::from_value(json!({
+            "title": "Kind of Blue", "janre": {"main": "Jazz", "sub": ["Modal"]}, "label": "Columbia", "id": "CL 1355",
+            "release_year": 1959, "record_year": [1959],
+            "personnel": {"leader": [{"name": "Miles Davis", "instruments": "tp", "tracks": ""}],
+                          "sidemen": [{"name": "Bill Evans", "instruments": "p", "tracks": "1-3|5"}]},
+            "tracks": [
+                {"disc_no": 1, "no": 1, "title": "So What", "composer": "", "length": "9:22"},
+                {"disc_no": 1, "no": 2, "title": "Freddie Freeloader", "composer": "", "length": "?"}
+            ],
+            "score": 5, "comment": "", "date": "2024/03/05",
+            "references": [{"name": "Discogs", "url": "https://www.discogs.com/release/1"}]
+        }))
+        .unwrap();
+        let md = render_album(&d);
+        assert!(md.starts_with("# Kind of Blue\n\n- アーティスト: Miles Davis\n- レーベル: Columbia CL 1355\n- 発売年: 1959\n"));
+        assert!(md.contains("| Sidemen | Bill Evans | p | 1-3\\|5 |"));
+        assert!(md.contains("| No. | 曲名 | 長さ |\n| ---: | --- | ---: |\n| 1 | So What | 9:22 |\n| 2 | Freddie Freeloader | ? |\n\n合計 9:22 以上\n"));
+        assert!(md.ends_with("## 参考\n\n- [Discogs](https://www.discogs.com/release/1)\n"));
+        assert!(!md.contains("### Disc"));
+        assert!(!md.contains("## コメント"));
+    }
+}
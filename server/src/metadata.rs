@@ -0,0 +1,651 @@
+//! 外部のデータベース（MusicBrainz・Discogs・VGMdb・Spotify）からのリリース情報の取り込み。提供元は `MetadataProvider` を実装し、`PROVIDERS` に足せば
+//! 画面（フォームの「外部のデータベースから探す」）はそのまま使える。使う提供元は設定の `metadata_provider`（既定は MusicBrainz）。
+//!
+//! - `GET /api/metadata/providers` 提供元の一覧と設定で選んでいるもの
+//! - `GET /api/metadata/search?artist=&title=&catalog=&provider=` リリースを探す
+//! - `GET /api/metadata/releases/{id}?provider=` リリースの詳細（曲目まで）
+//! - `POST /api/metadata/cover` `{"provider", "id", "filename"}` カバーを取ってきてレコードのカバーにする
+//! - `GET /api/lookup/{provider}?artist=&album=`（か `?id=`） いちばん近いリリースを MusicData の形で返す（スクリプトやフォームの「いちばん近いものを入れる」から）
+//! - `GET /api/lookup/cddb?toc=`（か `?discid=`） CD の TOC から gnudb などの CDDB で曲目を引く（cddb.rs）
+//!
+//! HTTP はブロッキング（ureq）なので spawn_blocking から呼ぶ。
+
+pub mod cddb;
+mod discogs;
+mod musicbrainz;
+mod spotify;
+mod vgmdb;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use std::io::Read;
+use std::sync::Arc;
+use std::time::Duration;
+
+use nekokan_music_core::types::{ConductorEntry, LeaderEntry, MusicData, Reference, SidemenEntry, Track};
+use std::collections::BTreeSet;
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+use crate::{normalize_filename, AppState};
+
+/// 提供元への 1 リクエストの待ち時間
+const HTTP_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// 提供元に名乗る User-Agent（MusicBrainz・Discogs とも必須）
+fn user_agent() -> String {
+    format!("nekokan_music/{}", env!("CARGO_PKG_VERSION"))
+}
+
+/// 探す条件（空の項目は使わない）
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+pub struct ReleaseQuery {
+    #[serde(default)]
+    pub artist: String,
+    #[serde(default)]
+    pub title: String,
+    /// 品番
+    #[serde(default)]
+    pub catalog: String,
+}
+
+impl ReleaseQuery {
+    fn is_empty(&self) -> bool {
+        self.artist.trim().is_empty() && self.title.trim().is_empty() && self.catalog.trim().is_empty()
+    }
+}
+
+/// 検索結果の 1 件
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct ReleaseSummary {
+    /// 提供元での ID（fetch_release・fetch_cover に渡す）
+    pub id: String,
+    pub title: String,
+    pub artist: String,
+    pub year: Option<i32>,
+    pub label: String,
+    pub catalog: String,
+    pub country: String,
+    pub format: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct ReleaseTrack {
+    pub disc_no: u32,
+    pub no: u32,
+    pub title: String,
+    /// "分:秒"（分からなければ空）
+    pub length: String,
+}
+
+/// リリースの詳細
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct Release {
+    #[serde(flatten)]
+    pub summary: ReleaseSummary,
+    pub tracks: Vec<ReleaseTrack>,
+    /// 提供元のページ（レコードの参考リンクに入れる）
+    pub url: String,
+    /// アルバム全体の作曲・編曲のクレジット（分かる提供元だけ）
+    pub composers: Vec<String>,
+    pub arrangers: Vec<String>,
+    /// 演奏者・制作者のクレジット（Discogs など分かる提供元だけ）
+    pub credits: Vec<Credit>,
+}
+
+/// クレジットの 1 件
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct Credit {
+    pub name: String,
+    /// 提供元の書き方のまま（"Tenor Saxophone"、"Producer"、"Written-By"）
+    pub role: String,
+    /// 関わった曲の（ディスク, 番号）。空なら全曲
+    pub tracks: Vec<(u32, u32)>,
+}
+
+/// カバー画像（拡張子は covers の IMAGE_TYPES のどれか）
+pub struct Cover {
+    pub ext: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+/// 外部のデータベース。エラーは画面にそのまま出す文言
+pub trait MetadataProvider: Send + Sync {
+    /// 設定・API で使う名前（"musicbrainz"）
+    fn id(&self) -> &'static str;
+    /// 画面に出す名前（"MusicBrainz"）
+    fn name(&self) -> &'static str;
+    fn search_release(&self, query: &ReleaseQuery) -> Result<Vec<ReleaseSummary>, String>;
+    fn fetch_release(&self, id: &str) -> Result<Release, String>;
+    /// カバーがなければ None
+    fn fetch_cover(&self, id: &str) -> Result<Option<Cover>, String>;
+}
+
+/// 提供元への接続の設定（コマンドラインか環境変数から。トークンが設定の GET で見えないよう設定ファイルには置かない）
+#[derive(Clone, Debug, Default)]
+pub struct ProviderConfig {
+    pub discogs_token: Option<String>,
+    /// CDDB のサーバー（cddb.cgi の URL）と、あいさつ（hello）で名乗るユーザー名
+    pub cddb_url: String,
+    pub cddb_user: String,
+    /// Spotify のアプリのクライアント ID とシークレット（Client Credentials で使う）
+    pub spotify_client_id: Option<String>,
+    pub spotify_client_secret: Option<String>,
+    /// 取ったアクセストークン（期限まで使い回す）
+    pub spotify_token: Arc<spotify::TokenCache>,
+}
+
+type MakeProvider = fn(&ProviderConfig) -> Box<dyn MetadataProvider>;
+
+/// 使える提供元（先頭が既定）
+const PROVIDERS: &[MakeProvider] = &[
+    |_| Box::new(musicbrainz::MusicBrainz),
+    |config| Box::new(discogs::Discogs::new(config.discogs_token.clone())),
+    |_| Box::new(vgmdb::Vgmdb),
+    |config| Box::new(spotify::Spotify::new(config)),
+];
+
+pub const DEFAULT_PROVIDER: &str = "musicbrainz";
+
+fn providers(config: &ProviderConfig) -> impl Iterator<Item = Box<dyn MetadataProvider>> + '_ {
+    PROVIDERS.iter().map(move |make| make(config))
+}
+
+/// 名前で提供元を選ぶ。空なら設定のもの
+fn provider(id: &str, state: &AppState) -> Option<Box<dyn MetadataProvider>> {
+    let id = match id.trim() {
+        "" => selected_provider(&state.db_path),
+        id => id.to_string(),
+    };
+    providers(&state.provider_config).find(|p| p.id() == id)
+}
+
+/// 設定で選んでいる提供元（読めないか空なら既定のもの）
+fn selected_provider(db_path: &std::path::Path) -> String {
+    crate::settings::load_settings(db_path)
+        .ok()
+        .map(|s| s.metadata_provider)
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| DEFAULT_PROVIDER.to_string())
+}
+
+/// 設定の `metadata_provider` を確かめる
+pub fn validate_provider(id: &str) -> Result<(), String> {
+    if providers(&ProviderConfig::default()).any(|p| p.id() == id) {
+        Ok(())
+    } else {
+        Err(format!("unknown provider: {}", id))
+    }
+}
+
+/// GET して JSON を読む。`token` があれば Discogs の形で付ける
+fn get_json(url: &str, query: &[(&str, &str)], token: Option<&str>) -> Result<serde_json::Value, String> {
+    get_json_with(url, query, token.map(|t| format!("Discogs token={}", t)).as_deref())
+}
+
+/// GET して JSON を読む。`authorization` はそのまま Authorization ヘッダーに
+fn get_json_with(url: &str, query: &[(&str, &str)], authorization: Option<&str>) -> Result<serde_json::Value, String> {
+    let mut req = ureq::get(url).timeout(HTTP_TIMEOUT).set("User-Agent", &user_agent()).set("Accept", "application/json");
+    if let Some(authorization) = authorization {
+        req = req.set("Authorization", authorization);
+    }
+    for (k, v) in query {
+        req = req.query(k, v);
+    }
+    match req.call() {
+        Ok(resp) => resp.into_json().map_err(|e| format!("{}: {}", url, e)),
+        Err(ureq::Error::Status(code, _)) => Err(format!("{}: {}", url, code)),
+        Err(e) => Err(format!("{}: {}", url, e)),
+    }
+}
+
+/// 画像を取ってくる。見つからなければ None
+fn get_image(url: &str, token: Option<&str>) -> Result<Option<Cover>, String> {
+    let mut req = ureq::get(url).timeout(HTTP_TIMEOUT).set("User-Agent", &user_agent());
+    if let Some(token) = token {
+        req = req.set("Authorization", &format!("Discogs token={}", token));
+    }
+    let resp = match req.call() {
+        Ok(resp) => resp,
+        Err(ureq::Error::Status(404, _)) => return Ok(None),
+        Err(e) => return Err(format!("{}: {}", url, e)),
+    };
+    let ext = match resp.content_type() {
+        "image/png" => "png",
+        "image/webp" => "webp",
+        "image/gif" => "gif",
+        _ => "jpg",
+    };
+    let mut bytes = Vec::new();
+    resp.into_reader()
+        .take(crate::COVER_MAX_BYTES as u64)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("{}: {}", url, e))?;
+    Ok(Some(Cover { ext, bytes }))
+}
+
+/// 年は日付（"1959-08-17"）の先頭から読む
+fn year_of(date: &str) -> Option<i32> {
+    date.get(..4).and_then(|y| y.parse().ok())
+}
+
+fn unknown_provider() -> axum::response::Response {
+    error(StatusCode::BAD_REQUEST, "unknown provider")
+}
+
+#[derive(serde::Serialize)]
+struct ProviderInfo {
+    id: &'static str,
+    name: &'static str,
+}
+
+/// `GET /api/metadata/providers`
+#[utoipa::path(get, path = "/api/metadata/providers", tag = "metadata", summary = "外部のデータベースの一覧")]
+pub async fn list_providers(State(state): State<AppState>) -> impl IntoResponse {
+    let list: Vec<ProviderInfo> = providers(&state.provider_config).map(|p| ProviderInfo { id: p.id(), name: p.name() }).collect();
+    Json(serde_json::json!({"providers": list, "selected": selected_provider(&state.db_path)}))
+}
+
+#[derive(serde::Deserialize)]
+pub struct ProviderParam {
+    #[serde(default)]
+    provider: String,
+}
+
+/// `GET /api/metadata/search`
+#[utoipa::path(get, path = "/api/metadata/search", tag = "metadata", summary = "外部のデータベースを探す")]
+pub async fn search(
+    State(state): State<AppState>,
+    Query(p): Query<ProviderParam>,
+    Query(query): Query<ReleaseQuery>,
+) -> impl IntoResponse {
+    let Some(provider) = provider(&p.provider, &state) else {
+        return unknown_provider();
+    };
+    if query.is_empty() {
+        return error(StatusCode::BAD_REQUEST, "artist, title or catalog is required");
+    }
+    match tokio::task::spawn_blocking(move || provider.search_release(&query)).await {
+        Ok(Ok(list)) => Json(list).into_response(),
+        Ok(Err(e)) => error(StatusCode::BAD_GATEWAY, e),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// `GET /api/metadata/releases/{id}`
+#[utoipa::path(get, path = "/api/metadata/releases/{id}", tag = "metadata", summary = "外部のデータベースのリリース")]
+pub async fn release(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(p): Query<ProviderParam>,
+) -> impl IntoResponse {
+    let Some(provider) = provider(&p.provider, &state) else {
+        return unknown_provider();
+    };
+    match tokio::task::spawn_blocking(move || provider.fetch_release(&id)).await {
+        Ok(Ok(release)) => Json(release).into_response(),
+        Ok(Err(e)) => error(StatusCode::BAD_GATEWAY, e),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct CoverBody {
+    #[serde(default)]
+    provider: String,
+    id: String,
+    /// カバーを付けるレコード（保存済みのもの）
+    filename: String,
+}
+
+/// `POST /api/metadata/cover` カバーを取ってきて、アップロードと同じくレコードのカバーにする
+#[utoipa::path(
+    post,
+    path = "/api/metadata/cover",
+    tag = "metadata",
+    summary = "外部のデータベースのカバーを取り込む",
+    params(LibraryQuery),
+    request_body = CoverBody,
+)]
+pub async fn import_cover(State(state): State<AppState>, Lib(library): Lib, Json(body): Json<CoverBody>) -> impl IntoResponse {
+    let Some(provider) = provider(&body.provider, &state) else {
+        return unknown_provider();
+    };
+    let Some(filename) = normalize_filename(&body.filename) else {
+        return error(StatusCode::BAD_REQUEST, "invalid filename");
+    };
+    let id = body.id;
+    let cover = match tokio::task::spawn_blocking(move || provider.fetch_cover(&id)).await {
+        Ok(Ok(Some(cover))) => cover,
+        Ok(Ok(None)) => return error(StatusCode::NOT_FOUND, "カバーが見つかりません"),
+        Ok(Err(e)) => return error(StatusCode::BAD_GATEWAY, e),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    match crate::covers::save_cover(&library, &filename, cover.ext, &cover.bytes) {
+        Ok(name) => Json(serde_json::json!({"ok": true, "filename": filename, "cover": name})).into_response(),
+        Err((status, e)) => error(status, e),
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct LookupQuery {
+    #[serde(default)]
+    artist: String,
+    #[serde(default)]
+    album: String,
+    /// 提供元のリリースの ID（あれば探さずにこれを使う）。Spotify のアルバムの URL・URI も `?url=` で
+    #[serde(default, alias = "url")]
+    id: String,
+}
+
+/// 検索結果からいちばん近いもの。タイトルが同じ（大文字・小文字は見ない）ものがあればその先頭、なければ先頭
+pub fn best_match<'a>(list: &'a [ReleaseSummary], album: &str) -> Option<&'a ReleaseSummary> {
+    let album = album.trim().to_lowercase();
+    list.iter().find(|r| r.title.trim().to_lowercase() == album).or_else(|| list.first())
+}
+
+/// クレジットの役割の分け方
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RoleKind {
+    /// 楽器・歌（パーソネルに入れる）
+    Performer,
+    Composer,
+    Conductor,
+    /// 制作・録音・デザインなど（MusicData には入れない）
+    Staff,
+}
+
+/// 制作側の役割に含まれる語（小文字）
+const STAFF_WORDS: &[&str] = &[
+    "producer", "engineer", "notes", "design", "artwork", "art direction", "layout", "photograph", "mastered", "lacquer",
+    "management", "coordinator", "a&r", "copyright", "lyrics", "translated", "compiled", "edited", "technician", "tape op",
+    "transfer", "supervised", "liner", "other",
+];
+
+/// 役割の補足（"Tenor Saxophone [Uncredited]" の角括弧）を取る
+fn clean_role(role: &str) -> String {
+    let mut out = String::new();
+    let mut depth = 0;
+    for c in role.chars() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth = (depth - 1).max(0),
+            c if depth == 0 => out.push(c),
+            _ => {}
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn role_kind(role: &str) -> RoleKind {
+    let r = role.to_lowercase();
+    if ["written-by", "written by", "composed by", "music by", "composer", "songwriter"].contains(&r.as_str()) {
+        RoleKind::Composer
+    } else if r == "conductor" || r == "conducted by" {
+        RoleKind::Conductor
+    } else if r.ends_with(" by") || r.ends_with("-by") || STAFF_WORDS.iter().any(|w| r.contains(w)) {
+        RoleKind::Staff
+    } else {
+        RoleKind::Performer
+    }
+}
+
+/// 曲の指定をレコードの書き方にする。全曲なら "all"、1 枚なら "1-3,5"、複数枚なら "Disc 1: 2,4 / Disc 2: 1-3"
+fn tracks_label(tracks: &BTreeSet<(u32, u32)>, all: &[ReleaseTrack]) -> String {
+    if tracks.is_empty() || all.iter().all(|t| tracks.contains(&(t.disc_no, t.no))) {
+        return "all".to_string();
+    }
+    let discs: BTreeSet<u32> = all.iter().map(|t| t.disc_no).chain(tracks.iter().map(|&(d, _)| d)).collect();
+    let numbers = |disc: u32| {
+        let mut parts: Vec<String> = Vec::new();
+        let mut run: Option<(u32, u32)> = None;
+        for &(_, no) in tracks.iter().filter(|&&(d, _)| d == disc) {
+            run = match run {
+                Some((from, to)) if no == to + 1 => Some((from, no)),
+                Some((from, to)) => {
+                    parts.push(if from == to { from.to_string() } else { format!("{}-{}", from, to) });
+                    Some((no, no))
+                }
+                None => Some((no, no)),
+            };
+        }
+        if let Some((from, to)) = run {
+            parts.push(if from == to { from.to_string() } else { format!("{}-{}", from, to) });
+        }
+        parts.join(",")
+    };
+    if discs.len() <= 1 {
+        return numbers(discs.first().copied().unwrap_or(1));
+    }
+    discs
+        .iter()
+        .map(|&d| (d, numbers(d)))
+        .filter(|(_, n)| !n.is_empty())
+        .map(|(d, n)| format!("Disc {}: {}", d, n))
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+/// クレジットをパーソネル・作曲者に振り分ける。リーダーと同じ名前の演奏者はリーダーの楽器に、ほかはサイドメンにまとめる。
+/// 曲ごとの作曲者はまだ空の曲だけに入れる
+fn apply_credits(data: &mut MusicData, release: &Release) {
+    // 名前ごとの楽器と曲（None は全曲）
+    struct Performer {
+        name: String,
+        roles: Vec<String>,
+        tracks: Option<BTreeSet<(u32, u32)>>,
+    }
+    let mut performers: Vec<Performer> = Vec::new();
+    for c in &release.credits {
+        let name = c.name.trim();
+        if name.is_empty() {
+            continue;
+        }
+        let tracks: BTreeSet<(u32, u32)> = c.tracks.iter().copied().collect();
+        for role in c.role.split(',').map(clean_role).filter(|r| !r.is_empty()) {
+            match role_kind(&role) {
+                RoleKind::Composer if tracks.is_empty() => {
+                    if !data.composers.iter().any(|x| x == name) {
+                        data.composers.push(name.to_string());
+                    }
+                }
+                RoleKind::Composer => {
+                    for t in data.tracks.iter_mut().filter(|t| tracks.contains(&(t.disc_no as u32, t.no as u32))) {
+                        if t.composer.is_empty() {
+                            t.composer = name.to_string();
+                        } else if !t.composer.split(", ").any(|x| x == name) {
+                            t.composer = format!("{}, {}", t.composer, name);
+                        }
+                    }
+                }
+                RoleKind::Conductor => {
+                    if !data.personnel.conductor.iter().any(|x| x.name == name) {
+                        let tracks = tracks_label(&tracks, &release.tracks);
+                        data.personnel.conductor.push(ConductorEntry { name: name.to_string(), tracks, ..Default::default() });
+                    }
+                }
+                RoleKind::Performer => {
+                    let i = match performers.iter().position(|p| p.name == name) {
+                        Some(i) => i,
+                        None => {
+                            performers.push(Performer { name: name.to_string(), roles: vec![], tracks: Some(BTreeSet::new()) });
+                            performers.len() - 1
+                        }
+                    };
+                    let p = &mut performers[i];
+                    if !p.roles.contains(&role) {
+                        p.roles.push(role);
+                    }
+                    match &mut p.tracks {
+                        Some(on) if !tracks.is_empty() => on.extend(tracks.iter().copied()),
+                        on => *on = None,
+                    }
+                }
+                RoleKind::Staff => {}
+            }
+        }
+    }
+    for Performer { name, roles, tracks } in performers {
+        let instruments = roles.join(", ");
+        let tracks = tracks_label(&tracks.unwrap_or_default(), &release.tracks);
+        match data.personnel.leader.iter_mut().find(|l| l.name.eq_ignore_ascii_case(&name)) {
+            Some(leader) => leader.instruments = instruments,
+            None => data.personnel.sidemen.push(SidemenEntry { name, instruments, tracks, ..Default::default() }),
+        }
+    }
+}
+
+/// リリースを MusicData の形にする（分からない項目は空のまま）。アーティストはリーダーに、クレジットはパーソネルと作曲者に、
+/// 提供元のページは参考リンクに入れる
+pub fn to_music_data(release: &Release, provider_name: &str) -> MusicData {
+    let r = &release.summary;
+    let mut data = MusicData {
+        title: r.title.trim().to_string(),
+        label: r.label.trim().to_string(),
+        id: r.catalog.trim().to_string(),
+        release_year: r.year.unwrap_or_default(),
+        composers: release.composers.clone(),
+        tracks: release
+            .tracks
+            .iter()
+            .map(|t| Track {
+                disc_no: t.disc_no as i32,
+                no: t.no as i32,
+                title: t.title.clone(),
+                length: t.length.clone(),
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
+    if !r.artist.trim().is_empty() && !r.artist.eq_ignore_ascii_case("Various Artists") {
+        data.personnel.leader.push(LeaderEntry { name: r.artist.trim().to_string(), tracks: "all".into(), ..Default::default() });
+    }
+    apply_credits(&mut data, release);
+    if !release.url.is_empty() {
+        data.references.push(Reference { name: provider_name.to_string(), url: release.url.clone() });
+    }
+    data
+}
+
+/// `GET /api/lookup/{provider}` 探していちばん近いものを取ってくる。
+/// `release` は提供元の形のまま、`data` は MusicData の形、`candidates` はほかの候補（`?id=` で選び直せる）
+#[utoipa::path(get, path = "/api/lookup/{provider}", tag = "metadata", summary = "外部のデータベースのいちばん近いリリース（MusicData の形）")]
+pub async fn lookup(
+    State(state): State<AppState>,
+    Path(provider_id): Path<String>,
+    Query(q): Query<LookupQuery>,
+) -> impl IntoResponse {
+    let Some(provider) = provider(&provider_id, &state) else {
+        return unknown_provider();
+    };
+    if q.id.trim().is_empty() && q.artist.trim().is_empty() && q.album.trim().is_empty() {
+        return error(StatusCode::BAD_REQUEST, "artist, album or id is required");
+    }
+    let res = tokio::task::spawn_blocking(move || {
+        let (id, candidates) = if q.id.trim().is_empty() {
+            let query = ReleaseQuery { artist: q.artist, title: q.album.clone(), catalog: String::new() };
+            let list = provider.search_release(&query).map_err(|e| (StatusCode::BAD_GATEWAY, e))?;
+            let best = best_match(&list, &q.album).ok_or((StatusCode::NOT_FOUND, "見つかりませんでした".to_string()))?;
+            let id = best.id.clone();
+            (id.clone(), list.into_iter().filter(|r| r.id != id).collect())
+        } else {
+            (q.id.trim().to_string(), vec![])
+        };
+        let release = provider.fetch_release(&id).map_err(|e| (StatusCode::BAD_GATEWAY, e))?;
+        let data = to_music_data(&release, provider.name());
+        Ok::<_, (StatusCode, String)>(serde_json::json!({
+            "provider": provider.id(),
+            "release": release,
+            "data": data,
+            "candidates": candidates,
+        }))
+    })
+    .await;
+    match res {
+        Ok(Ok(v)) => Json(v).into_response(),
+        Ok(Err((status, e))) => error(status, e),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod metadata_tests {
+    use super::{
+        best_match, role_kind, to_music_data, tracks_label, validate_provider, year_of, Credit, Release, ReleaseSummary,
+        ReleaseTrack, RoleKind,
+    };
+
+    #[test]
+    fn helpers_and_known_providers() {
+        assert_eq!(year_of("1959-08-17"), Some(1959));
+        assert_eq!(year_of(""), None);
+        assert!(validate_provider("musicbrainz").is_ok());
+        assert!(validate_provider("discogs").is_ok());
+        assert!(validate_provider("vgmdb").is_ok());
+        assert!(validate_provider("spotify").is_ok());
+        assert!(validate_provider("rym").is_err());
+    }
+
+    #[test]
+    fn lookup_picks_the_matching_title_and_maps_to_music_data() {
+        let summary = |id: &str, title: &str| ReleaseSummary { id: id.into(), title: title.into(), ..Default::default() };
+        let list = vec![summary("1", "Kind of Blue (Legacy Edition)"), summary("2", "kind of blue")];
+        assert_eq!(best_match(&list, "Kind of Blue").unwrap().id, "2");
+        assert_eq!(best_match(&list, "Sketches of Spain").unwrap().id, "1");
+        assert!(best_match(&[], "x").is_none());
+
+        let release = Release {
+            summary: ReleaseSummary {
+                title: "Kind of Blue".into(),
+                artist: "Miles Davis".into(),
+                year: Some(1959),
+                label: "Columbia".into(),
+                catalog: "CL 1355".into(),
+                ..Default::default()
+            },
+            tracks: vec![ReleaseTrack { disc_no: 1, no: 1, title: "So What".into(), length: "9:22".into() }],
+            url: "https://musicbrainz.org/release/mbid-1".into(),
+            ..Default::default()
+        };
+        let d = to_music_data(&release, "MusicBrainz");
+        assert_eq!((d.title.as_str(), d.label.as_str(), d.id.as_str(), d.release_year), ("Kind of Blue", "Columbia", "CL 1355", 1959));
+        assert_eq!((d.tracks[0].no, d.tracks[0].length.as_str()), (1, "9:22"));
+        assert_eq!(d.personnel.leader[0].name, "Miles Davis");
+        assert_eq!(d.references[0].name, "MusicBrainz");
+    }
+
+    #[test]
+    fn credits_become_personnel_and_composers() {
+        let track = |no: u32, title: &str| ReleaseTrack { disc_no: 1, no, title: title.into(), ..Default::default() };
+        let credit = |name: &str, role: &str, tracks: Vec<(u32, u32)>| Credit { name: name.into(), role: role.into(), tracks };
+        let release = Release {
+            summary: ReleaseSummary { title: "Black Fire".into(), artist: "Andrew Hill".into(), ..Default::default() },
+            tracks: vec![track(1, "Pumpkin"), track(2, "Subterfuge"), track(3, "Black Fire"), track(4, "Cantarnos")],
+            credits: vec![
+                credit("Andrew Hill", "Piano", vec![]),
+                credit("Joe Henderson", "Tenor Saxophone [Uncredited]", vec![(1, 1), (1, 2), (1, 4)]),
+                credit("Richard Davis", "Bass, Liner Notes", vec![]),
+                credit("Alfred Lion", "Producer", vec![]),
+                credit("Andrew Hill", "Written-By", vec![(1, 1), (1, 2)]),
+                credit("Rudy Van Gelder", "Recorded By", vec![]),
+            ],
+            ..Default::default()
+        };
+        let d = to_music_data(&release, "Discogs");
+        assert_eq!((d.personnel.leader[0].name.as_str(), d.personnel.leader[0].instruments.as_str()), ("Andrew Hill", "Piano"));
+        let sidemen: Vec<(&str, &str, &str)> =
+            d.personnel.sidemen.iter().map(|s| (s.name.as_str(), s.instruments.as_str(), s.tracks.as_str())).collect();
+        assert_eq!(sidemen, [("Joe Henderson", "Tenor Saxophone", "1-2,4"), ("Richard Davis", "Bass", "all")]);
+        assert_eq!(d.tracks.iter().map(|t| t.composer.as_str()).collect::<Vec<_>>(), ["Andrew Hill", "Andrew Hill", "", ""]);
+
+        let two_discs = [ReleaseTrack { disc_no: 1, no: 1, ..Default::default() }, ReleaseTrack { disc_no: 2, no: 1, ..Default::default() }];
+        assert_eq!(tracks_label(&[(2, 1)].into_iter().collect(), &two_discs), "Disc 2: 1");
+        assert_eq!(tracks_label(&[(1, 1), (2, 1)].into_iter().collect(), &two_discs), "all");
+        assert_eq!((role_kind("Conductor"), role_kind("Photography By"), role_kind("Vibraphone")), (RoleKind::Conductor, RoleKind::Staff, RoleKind::Performer));
+    }
+}
@@ -0,0 +1,338 @@
+//! CDDB（gnudb・freedb 互換のサーバー）。リッピングした CD の TOC（各トラックの開始フレーム）かディスク ID から曲目を引く。
+//! HTTP の cddb.cgi に `cddb query` で候補を、`cddb read` で xmcd の形のエントリーを取る（proto 6 で UTF-8）。
+//!
+//! `GET /api/lookup/cddb` は次のどれかを受け取り、`/api/lookup/{provider}` と同じ形（`release`・`data`・`candidates`）で返す。
+//! - `?toc=150,18637,...,242130` 各トラックの開始フレームと最後にリードアウトのフレーム（空白区切りも可）
+//! - `?toc=a50e1d0c 12 150 ... 3342` `cd-discid` の出力そのまま（ディスク ID、曲数、開始フレーム、全体の秒数）
+//! - `?discid=a50e1d0c` ディスク ID だけ（ジャンルごとに read を試す）
+//! - `?id=jazz/a50e1d0c` 候補のジャンルとディスク ID
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+
+use super::{error, to_music_data, user_agent, Release, ReleaseSummary, ReleaseTrack, HTTP_TIMEOUT};
+use crate::length::format_length;
+use crate::AppState;
+
+pub const DEFAULT_URL: &str = "https://gnudb.gnudb.org/~cddb/cddb.cgi";
+/// 画面・参考に出す名前
+const NAME: &str = "CDDB";
+/// CD のフレーム（1 秒 = 75 フレーム）
+const FRAMES_PER_SEC: u32 = 75;
+/// CDDB の決まったジャンル（ディスク ID だけのときに順に read する）
+const CATEGORIES: &[&str] = &["blues", "classical", "country", "data", "folk", "jazz", "misc", "newage", "reggae", "rock", "soundtrack"];
+
+/// CD の目次
+#[derive(Clone, Debug, PartialEq)]
+pub struct Toc {
+    /// 8 桁の 16 進
+    pub disc_id: String,
+    /// 各トラックの開始フレーム（リードインの 150 を含む）
+    pub offsets: Vec<u32>,
+    /// 全体の秒数（リードアウトのフレーム / 75）
+    pub total_secs: u32,
+}
+
+/// 各トラックの開始フレームとリードアウトのフレームから CDDB のディスク ID を作る
+pub fn disc_id(offsets: &[u32], leadout: u32) -> String {
+    let digit_sum = |mut n: u32| {
+        let mut sum = 0;
+        while n > 0 {
+            sum += n % 10;
+            n /= 10;
+        }
+        sum
+    };
+    let n: u32 = offsets.iter().map(|&o| digit_sum(o / FRAMES_PER_SEC)).sum();
+    let t = leadout / FRAMES_PER_SEC - offsets.first().copied().unwrap_or(0) / FRAMES_PER_SEC;
+    format!("{:08x}", ((n % 0xff) << 24) | (t << 8) | offsets.len() as u32)
+}
+
+/// `?toc=` を読む（形はモジュールの説明のとおり）
+pub fn parse_toc(s: &str) -> Result<Toc, String> {
+    let words: Vec<&str> = s.split(|c: char| c == ',' || c.is_whitespace()).filter(|w| !w.is_empty()).collect();
+    let bad = || format!("TOC が読めません: {}", s.trim());
+    // cd-discid の出力（先頭が 16 進のディスク ID、次が曲数）
+    if let [id, count, rest @ ..] = words.as_slice() {
+        let count = count.parse::<usize>().ok().filter(|&n| n > 0 && rest.len() == n + 1);
+        if let (8, Ok(_), Some(count)) = (id.len(), u32::from_str_radix(id, 16), count) {
+            let numbers: Vec<u32> = rest.iter().map(|w| w.parse()).collect::<Result<_, _>>().map_err(|_| bad())?;
+            return Ok(Toc { disc_id: id.to_ascii_lowercase(), offsets: numbers[..count].to_vec(), total_secs: numbers[count] });
+        }
+    }
+    let frames: Vec<u32> = words.iter().map(|w| w.parse()).collect::<Result<_, _>>().map_err(|_| bad())?;
+    let [offsets @ .., leadout] = frames.as_slice() else {
+        return Err(bad());
+    };
+    if offsets.is_empty() || offsets.windows(2).any(|w| w[0] >= w[1]) || offsets.last().is_some_and(|&l| l >= *leadout) {
+        return Err(bad());
+    }
+    Ok(Toc { disc_id: disc_id(offsets, *leadout), offsets: offsets.to_vec(), total_secs: leadout / FRAMES_PER_SEC })
+}
+
+/// `cddb query` の答えの 1 件
+#[derive(Clone, Debug, PartialEq)]
+struct Match {
+    genre: String,
+    disc_id: String,
+    artist: String,
+    title: String,
+}
+
+/// "Artist / Title" を分ける（" / " がなければ両方同じ）
+fn split_dtitle(s: &str) -> (String, String) {
+    match s.split_once(" / ") {
+        Some((artist, title)) => (artist.trim().to_string(), title.trim().to_string()),
+        None => (s.trim().to_string(), s.trim().to_string()),
+    }
+}
+
+/// "jazz a50e1d0c Artist / Title"
+fn parse_match(line: &str) -> Option<Match> {
+    let mut parts = line.trim().splitn(3, ' ');
+    let (genre, disc_id) = (parts.next()?, parts.next()?);
+    let (artist, title) = split_dtitle(parts.next().unwrap_or(""));
+    Some(Match { genre: genre.to_string(), disc_id: disc_id.to_string(), artist, title })
+}
+
+/// `cddb query` の答え。200 は 1 件、210・211 は "." までの一覧、202 は見つからない
+fn parse_query(text: &str) -> Result<Vec<Match>, String> {
+    let mut lines = text.lines();
+    let first = lines.next().unwrap_or("").trim();
+    let (code, rest) = first.split_once(' ').unwrap_or((first, ""));
+    match code {
+        "200" => Ok(parse_match(rest).into_iter().collect()),
+        "210" | "211" => Ok(lines.take_while(|l| l.trim() != ".").filter_map(parse_match).collect()),
+        "202" => Ok(vec![]),
+        _ => Err(format!("CDDB: {}", first)),
+    }
+}
+
+/// xmcd のエントリー
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Entry {
+    artist: String,
+    title: String,
+    year: Option<i32>,
+    tracks: Vec<String>,
+    /// コメントの "# Track frame offsets:" と "# Disc length: N seconds"
+    offsets: Vec<u32>,
+    disc_secs: Option<u32>,
+}
+
+/// `cddb read` の答え（"210 ..." に続けて "." まで）。同じキーの行は続けてつなぐ
+fn parse_entry(text: &str) -> Result<Entry, String> {
+    let mut lines = text.lines();
+    let first = lines.next().unwrap_or("").trim();
+    if !first.starts_with("210") {
+        return Err(format!("CDDB: {}", first));
+    }
+    let mut entry = Entry::default();
+    let mut dtitle = String::new();
+    let mut in_offsets = false;
+    for line in lines.take_while(|l| l.trim() != ".") {
+        if let Some(comment) = line.strip_prefix('#') {
+            let comment = comment.trim();
+            if comment.starts_with("Track frame offsets") {
+                in_offsets = true;
+            } else if let Some(secs) = comment.strip_prefix("Disc length:") {
+                in_offsets = false;
+                entry.disc_secs = secs.split_whitespace().next().and_then(|n| n.parse().ok());
+            } else if let (true, Ok(frame)) = (in_offsets, comment.parse::<u32>()) {
+                entry.offsets.push(frame);
+            } else if !comment.is_empty() {
+                in_offsets = false;
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else { continue };
+        match key.trim() {
+            "DTITLE" => dtitle.push_str(value),
+            "DYEAR" => entry.year = value.trim().parse().ok().filter(|&y| y > 0),
+            key => {
+                if let Some(i) = key.strip_prefix("TTITLE").and_then(|n| n.parse::<usize>().ok()) {
+                    if entry.tracks.len() <= i {
+                        entry.tracks.resize(i + 1, String::new());
+                    }
+                    entry.tracks[i].push_str(value);
+                }
+            }
+        }
+    }
+    (entry.artist, entry.title) = split_dtitle(&dtitle);
+    Ok(entry)
+}
+
+/// エントリーをリリースにする。長さはエントリーの開始フレーム（なければ TOC）から
+fn to_release(entry: &Entry, genre: &str, disc_id: &str, toc: Option<&Toc>) -> Release {
+    let (offsets, total_secs) = match toc {
+        Some(toc) if entry.offsets.len() != entry.tracks.len() => (toc.offsets.clone(), Some(toc.total_secs)),
+        _ => (entry.offsets.clone(), entry.disc_secs),
+    };
+    let length = |i: usize| {
+        let start = *offsets.get(i)?;
+        let end = offsets.get(i + 1).copied().or(total_secs.map(|s| s * FRAMES_PER_SEC))?;
+        (end > start).then(|| format_length(u64::from((end - start) / FRAMES_PER_SEC)))
+    };
+    Release {
+        summary: ReleaseSummary {
+            id: format!("{}/{}", genre, disc_id),
+            title: entry.title.clone(),
+            artist: entry.artist.clone(),
+            year: entry.year,
+            format: "CD".into(),
+            ..Default::default()
+        },
+        tracks: entry
+            .tracks
+            .iter()
+            .enumerate()
+            .map(|(i, title)| ReleaseTrack { disc_no: 1, no: i as u32 + 1, title: title.trim().to_string(), length: length(i).unwrap_or_default() })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// cddb.cgi に 1 つのコマンドを送る
+fn command(url: &str, user: &str, cmd: &str) -> Result<String, String> {
+    let hello = format!("{} localhost nekokan_music {}", user, env!("CARGO_PKG_VERSION"));
+    let req = ureq::get(url)
+        .timeout(HTTP_TIMEOUT)
+        .set("User-Agent", &user_agent())
+        .query("cmd", cmd)
+        .query("hello", &hello)
+        .query("proto", "6");
+    match req.call() {
+        Ok(resp) => resp.into_string().map_err(|e| format!("{}: {}", url, e)),
+        Err(ureq::Error::Status(code, _)) => Err(format!("{}: {}", url, code)),
+        Err(e) => Err(format!("{}: {}", url, e)),
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct CddbQuery {
+    #[serde(default)]
+    toc: String,
+    #[serde(default)]
+    discid: String,
+    /// 候補の "ジャンル/ディスク ID"
+    #[serde(default)]
+    id: String,
+}
+
+/// 探して読む。返すのはいちばん近いものとほかの候補
+fn find(url: &str, user: &str, q: &CddbQuery) -> Result<(Release, Vec<ReleaseSummary>), (StatusCode, String)> {
+    let gateway = |e: String| (StatusCode::BAD_GATEWAY, e);
+    let not_found = || (StatusCode::NOT_FOUND, "見つかりませんでした".to_string());
+    let read = |genre: &str, disc_id: &str| command(url, user, &format!("cddb read {} {}", genre, disc_id));
+    let toc = match q.toc.trim() {
+        "" => None,
+        s => Some(parse_toc(s).map_err(|e| (StatusCode::BAD_REQUEST, e))?),
+    };
+    if let Some((genre, disc_id)) = q.id.trim().split_once('/') {
+        let entry = parse_entry(&read(genre, disc_id).map_err(gateway)?).map_err(|_| not_found())?;
+        return Ok((to_release(&entry, genre, disc_id, toc.as_ref()), vec![]));
+    }
+    if let Some(toc) = toc {
+        let offsets: Vec<String> = toc.offsets.iter().map(u32::to_string).collect();
+        let cmd = format!("cddb query {} {} {} {}", toc.disc_id, toc.offsets.len(), offsets.join(" "), toc.total_secs);
+        let matches = parse_query(&command(url, user, &cmd).map_err(gateway)?).map_err(gateway)?;
+        let best = matches.first().ok_or_else(not_found)?;
+        let entry = parse_entry(&read(&best.genre, &best.disc_id).map_err(gateway)?).map_err(gateway)?;
+        let candidates = matches
+            .iter()
+            .skip(1)
+            .map(|m| ReleaseSummary {
+                id: format!("{}/{}", m.genre, m.disc_id),
+                title: m.title.clone(),
+                artist: m.artist.clone(),
+                format: "CD".into(),
+                ..Default::default()
+            })
+            .collect();
+        return Ok((to_release(&entry, &best.genre, &best.disc_id, Some(&toc)), candidates));
+    }
+    let disc_id = q.discid.trim().to_ascii_lowercase();
+    if disc_id.len() != 8 || u32::from_str_radix(&disc_id, 16).is_err() {
+        return Err((StatusCode::BAD_REQUEST, "toc, discid (8 桁の 16 進) or id is required".to_string()));
+    }
+    // ディスク ID だけなら、見つかるまでジャンルを順に読む
+    for genre in CATEGORIES {
+        if let Ok(entry) = parse_entry(&read(genre, &disc_id).map_err(gateway)?) {
+            return Ok((to_release(&entry, genre, &disc_id, None), vec![]));
+        }
+    }
+    Err(not_found())
+}
+
+/// `GET /api/lookup/cddb`
+#[utoipa::path(
+    get,
+    path = "/api/lookup/cddb",
+    tag = "metadata",
+    summary = "CD の TOC かディスク ID から CDDB（gnudb）で曲目を引く（MusicData の形）",
+)]
+pub async fn lookup(State(state): State<AppState>, Query(q): Query<CddbQuery>) -> impl IntoResponse {
+    let config = state.provider_config.clone();
+    let res = tokio::task::spawn_blocking(move || {
+        let (release, candidates) = find(&config.cddb_url, &config.cddb_user, &q)?;
+        let data = to_music_data(&release, NAME);
+        Ok::<_, (StatusCode, String)>(serde_json::json!({
+            "provider": "cddb",
+            "release": release,
+            "data": data,
+            "candidates": candidates,
+        }))
+    })
+    .await;
+    match res {
+        Ok(Ok(v)) => Json(v).into_response(),
+        Ok(Err((status, e))) => error(status, e),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod cddb_tests {
+    use super::{disc_id, parse_entry, parse_query, parse_toc, to_release};
+
+    #[test]
+    fn toc_and_disc_id() {
+        // 3 曲・リードアウト 60000 フレーム
+        let toc = parse_toc("150, 20000 40000 60000").unwrap();
+        assert_eq!(toc.offsets, [150, 20000, 40000]);
+        assert_eq!((toc.total_secs, toc.disc_id.as_str()), (800, disc_id(&[150, 20000, 40000], 60000).as_str()));
+        assert_eq!(disc_id(&[150, 20000, 40000], 60000), "1b031e03");
+        let from_tool = parse_toc("a50e1d0c 3 150 20000 40000 800").unwrap();
+        assert_eq!((from_tool.disc_id.as_str(), from_tool.offsets.len(), from_tool.total_secs), ("a50e1d0c", 3, 800));
+        assert!(parse_toc("150").is_err());
+        assert!(parse_toc("20000 150 60000").is_err());
+        assert!(parse_toc("150 x 60000").is_err());
+    }
+
+    #[test]
+    fn query_and_read_responses() {
+        let one = parse_query("200 jazz 1e031f03 Andrew Hill / Black Fire\r\n").unwrap();
+        assert_eq!((one[0].genre.as_str(), one[0].artist.as_str(), one[0].title.as_str()), ("jazz", "Andrew Hill", "Black Fire"));
+        let many = parse_query("211 close matches found\njazz 1e031f03 A / B\nmisc 1e031f03 C / D\n.\n").unwrap();
+        assert_eq!(many.len(), 2);
+        assert!(parse_query("202 No match found").unwrap().is_empty());
+        assert!(parse_query("500 Command syntax error").is_err());
+
+        let text = "210 jazz 1e031f03 CD database entry follows (until terminating `.')\n\
+            # xmcd\n#\n# Track frame offsets:\n#\t150\n#\t20000\n#\t40000\n#\n# Disc length: 800 seconds\n#\n\
+            DISCID=1e031f03\nDTITLE=Andrew Hill / Black Fire\nDYEAR=1964\nDGENRE=Jazz\n\
+            TTITLE0=Pumpkin\nTTITLE1=Subterfuge\nTTITLE2=Black \nTTITLE2=Fire\nEXTD=\n.\n";
+        let entry = parse_entry(text).unwrap();
+        assert_eq!((entry.artist.as_str(), entry.title.as_str(), entry.year), ("Andrew Hill", "Black Fire", Some(1964)));
+        assert_eq!(entry.tracks, ["Pumpkin", "Subterfuge", "Black Fire"]);
+        let r = to_release(&entry, "jazz", "1e031f03", None);
+        assert_eq!(r.summary.id, "jazz/1e031f03");
+        assert_eq!(r.tracks.iter().map(|t| t.length.as_str()).collect::<Vec<_>>(), ["4:24", "4:26", "4:26"]);
+        assert!(parse_entry("401 jazz 1e031f03 No such CD entry in database.").is_err());
+    }
+}
@@ -0,0 +1,260 @@
+//! Discogs（https://api.discogs.com/）。検索には個人のトークンが要る（`--discogs-token` か環境変数 `DISCOGS_TOKEN`、設定の GET で見えないように）。
+//! ジャズのレコードは演奏者のクレジット（`extraartists`）が細かいので、曲ごとのものまで `Release::credits` に入れる。
+//! コレクション CSV の読み書きは crate::discogs。
+
+use serde_json::Value;
+
+use super::{get_image, get_json, Cover, Credit, MetadataProvider, Release, ReleaseQuery, ReleaseSummary, ReleaseTrack};
+
+const API: &str = "https://api.discogs.com";
+const SEARCH_LIMIT: &str = "15";
+
+pub struct Discogs {
+    token: Option<String>,
+}
+
+impl Discogs {
+    pub fn new(token: Option<String>) -> Self {
+        Self { token: token.filter(|t| !t.trim().is_empty()) }
+    }
+
+    fn token(&self) -> Result<&str, String> {
+        self.token
+            .as_deref()
+            .ok_or_else(|| "Discogs で探すにはサーバーに --discogs-token（環境変数 DISCOGS_TOKEN）でトークンを渡してください".to_string())
+    }
+}
+
+/// 検索結果の title は "アーティスト - タイトル"
+fn split_title(s: &str) -> (String, String) {
+    match s.split_once(" - ") {
+        Some((artist, title)) => (artist.trim().to_string(), title.trim().to_string()),
+        None => (String::new(), s.trim().to_string()),
+    }
+}
+
+fn join_strings(v: &Value, sep: &str) -> String {
+    v.as_array().into_iter().flatten().filter_map(|s| s.as_str()).collect::<Vec<_>>().join(sep)
+}
+
+fn parse_search(v: &Value) -> Vec<ReleaseSummary> {
+    v["results"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|r| {
+            let (artist, title) = split_title(r["title"].as_str().unwrap_or(""));
+            ReleaseSummary {
+                id: r["id"].as_u64().map(|id| id.to_string()).unwrap_or_default(),
+                title,
+                artist,
+                year: r["year"].as_str().and_then(|y| y.parse().ok()),
+                label: r["label"][0].as_str().unwrap_or("").to_string(),
+                catalog: r["catno"].as_str().unwrap_or("").to_string(),
+                country: r["country"].as_str().unwrap_or("").to_string(),
+                format: join_strings(&r["format"], ", "),
+            }
+        })
+        .collect()
+}
+
+/// 曲の位置（"A1"、"2-3"、"CD1-3"）からディスク番号を読む。ディスクのないもの（A 面・B 面）は 1 枚目
+fn disc_of(position: &str) -> Option<u32> {
+    let (disc, _) = position.split_once(['-', '.'])?;
+    disc.trim_start_matches(|c: char| c.is_ascii_alphabetic()).parse().ok()
+}
+
+/// Discogs の名前の同名異人の番号 "Bill Evans (2)" と別名義マーク "*" を取る
+fn clean_artist(s: &str) -> String {
+    let s = s.trim().trim_end_matches('*').trim();
+    match s.rsplit_once(" (") {
+        Some((name, n)) if n.strip_suffix(')').is_some_and(|n| n.chars().all(|c| c.is_ascii_digit())) => name.to_string(),
+        _ => s.to_string(),
+    }
+}
+
+/// クレジットの曲の指定（"A1 to A3, B2"）を（ディスク, 番号）にする。`positions` は曲順の位置。分からない位置は飛ばす
+fn credit_tracks(spec: &str, positions: &[(String, (u32, u32))]) -> Vec<(u32, u32)> {
+    let index = |p: &str| positions.iter().position(|(x, _)| x.eq_ignore_ascii_case(p.trim()));
+    let mut out: Vec<(u32, u32)> = Vec::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let range = match part.split_once(" to ") {
+            Some((from, to)) => index(from).zip(index(to)).filter(|(a, b)| a <= b).map(|(a, b)| a..=b),
+            None => index(part).map(|i| i..=i),
+        };
+        for i in range.into_iter().flatten() {
+            if !out.contains(&positions[i].1) {
+                out.push(positions[i].1);
+            }
+        }
+    }
+    out
+}
+
+/// `extraartists` の 1 件
+fn credit(a: &Value, tracks: Vec<(u32, u32)>) -> Credit {
+    Credit {
+        name: clean_artist(a["name"].as_str().unwrap_or("")),
+        role: a["role"].as_str().unwrap_or("").trim().to_string(),
+        tracks,
+    }
+}
+
+fn parse_release(v: &Value) -> Release {
+    let artist = v["artists"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|a| format!("{}{}", clean_artist(a["name"].as_str().unwrap_or("")), match a["join"].as_str().unwrap_or("") {
+            "" | "," => ", ".to_string(),
+            j => format!(" {} ", j),
+        }))
+        .collect::<String>();
+    let artist = artist.trim_end_matches(", ").trim().to_string();
+    let label = &v["labels"][0];
+    let mut tracks: Vec<ReleaseTrack> = Vec::new();
+    let mut positions: Vec<(String, (u32, u32))> = Vec::new();
+    let mut credits: Vec<Credit> = Vec::new();
+    for t in v["tracklist"].as_array().into_iter().flatten() {
+        // 見出し（"heading"）や組曲の親（"index"）は飛ばす
+        if t["type_"].as_str().is_some_and(|ty| ty != "track") {
+            continue;
+        }
+        let disc_no = disc_of(t["position"].as_str().unwrap_or("")).unwrap_or(1);
+        let no = tracks.iter().filter(|x| x.disc_no == disc_no).count() as u32 + 1;
+        positions.push((t["position"].as_str().unwrap_or("").trim().to_string(), (disc_no, no)));
+        credits.extend(t["extraartists"].as_array().into_iter().flatten().map(|a| credit(a, vec![(disc_no, no)])));
+        tracks.push(ReleaseTrack {
+            disc_no,
+            no,
+            title: t["title"].as_str().unwrap_or("").to_string(),
+            length: t["duration"].as_str().unwrap_or("").to_string(),
+        });
+    }
+    // アルバム全体のクレジット（`tracks` が空なら全曲）。曲ごとのものより前に並べる
+    let album_credits: Vec<Credit> = v["extraartists"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|a| credit(a, credit_tracks(a["tracks"].as_str().unwrap_or(""), &positions)))
+        .collect();
+    credits.splice(0..0, album_credits);
+    let id = v["id"].as_u64().map(|id| id.to_string()).unwrap_or_default();
+    Release {
+        summary: ReleaseSummary {
+            id,
+            title: v["title"].as_str().unwrap_or("").to_string(),
+            artist,
+            year: v["year"].as_i64().filter(|&y| y > 0).map(|y| y as i32),
+            label: label["name"].as_str().map(clean_artist).unwrap_or_default(),
+            catalog: label["catno"].as_str().unwrap_or("").to_string(),
+            country: v["country"].as_str().unwrap_or("").to_string(),
+            format: v["formats"].as_array().into_iter().flatten().filter_map(|f| f["name"].as_str()).collect::<Vec<_>>().join(", "),
+        },
+        tracks,
+        url: v["uri"].as_str().unwrap_or("").to_string(),
+        credits,
+        ..Default::default()
+    }
+}
+
+/// 表の画像（なければ 1 枚目）
+fn cover_url(v: &Value) -> Option<&str> {
+    let images = v["images"].as_array()?;
+    images.iter().find(|i| i["type"] == "primary").or(images.first())?["uri"].as_str().filter(|u| !u.is_empty())
+}
+
+impl MetadataProvider for Discogs {
+    fn id(&self) -> &'static str {
+        "discogs"
+    }
+
+    fn name(&self) -> &'static str {
+        "Discogs"
+    }
+
+    fn search_release(&self, query: &ReleaseQuery) -> Result<Vec<ReleaseSummary>, String> {
+        let token = self.token()?;
+        let mut params = vec![("type", "release"), ("per_page", SEARCH_LIMIT)];
+        for (k, v) in [("artist", &query.artist), ("release_title", &query.title), ("catno", &query.catalog)] {
+            if !v.trim().is_empty() {
+                params.push((k, v.trim()));
+            }
+        }
+        let v = get_json(&format!("{}/database/search", API), &params, Some(token))?;
+        Ok(parse_search(&v))
+    }
+
+    fn fetch_release(&self, id: &str) -> Result<Release, String> {
+        let v = get_json(&format!("{}/releases/{}", API, id), &[], self.token.as_deref())?;
+        Ok(parse_release(&v))
+    }
+
+    fn fetch_cover(&self, id: &str) -> Result<Option<Cover>, String> {
+        let token = self.token()?;
+        let v = get_json(&format!("{}/releases/{}", API, id), &[], Some(token))?;
+        match cover_url(&v) {
+            Some(url) => get_image(url, Some(token)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod discogs_api_tests {
+    use super::{cover_url, credit_tracks, disc_of, parse_release, parse_search};
+    use serde_json::json;
+
+    #[test]
+    fn search_and_release_responses() {
+        let list = parse_search(&json!({"results": [{
+            "id": 1234, "title": "Miles Davis - Kind Of Blue", "year": "1959",
+            "label": ["Columbia", "CBS"], "catno": "CL 1355", "country": "US", "format": ["Vinyl", "LP"]
+        }]}));
+        assert_eq!((list[0].artist.as_str(), list[0].title.as_str()), ("Miles Davis", "Kind Of Blue"));
+        assert_eq!((list[0].id.as_str(), list[0].year, list[0].format.as_str()), ("1234", Some(1959), "Vinyl, LP"));
+
+        assert_eq!(disc_of("A1"), None);
+        assert_eq!(disc_of("2-3"), Some(2));
+        assert_eq!(disc_of("CD2-1"), Some(2));
+        let r = parse_release(&json!({
+            "id": 1234, "title": "Kind Of Blue", "year": 1959, "uri": "https://www.discogs.com/release/1234",
+            "artists": [{"name": "Miles Davis (2)*", "join": ""}],
+            "labels": [{"name": "Columbia", "catno": "CL 1355"}],
+            "tracklist": [
+                {"position": "", "title": "Side A", "type_": "heading"},
+                {"position": "A1", "title": "So What", "duration": "9:22", "type_": "track"},
+                {"position": "A2", "title": "Freddie Freeloader", "duration": "", "type_": "track"}
+            ],
+            "images": [{"type": "secondary", "uri": "https://i/2.jpg"}, {"type": "primary", "uri": "https://i/1.jpg"}]
+        }));
+        assert_eq!(r.summary.artist, "Miles Davis");
+        assert_eq!(r.tracks.iter().map(|t| (t.disc_no, t.no)).collect::<Vec<_>>(), vec![(1, 1), (1, 2)]);
+        assert_eq!(r.tracks[0].length, "9:22");
+        assert_eq!(cover_url(&json!({"images": [{"type": "secondary", "uri": "https://i/2.jpg"}]})), Some("https://i/2.jpg"));
+    }
+
+    #[test]
+    fn album_and_track_credits() {
+        let r = parse_release(&json!({
+            "artists": [{"name": "Andrew Hill", "join": ""}],
+            "extraartists": [
+                {"name": "Joe Henderson", "role": "Tenor Saxophone", "tracks": ""},
+                {"name": "Roy Haynes", "role": "Drums", "tracks": "A1 to A3, B2"},
+                {"name": "Alfred Lion", "role": "Producer", "tracks": ""}
+            ],
+            "tracklist": [
+                {"position": "A1", "title": "Pumpkin", "type_": "track", "extraartists": [{"name": "Andrew Hill (2)", "role": "Written-By"}]},
+                {"position": "A2", "title": "Subterfuge", "type_": "track"},
+                {"position": "A3", "title": "Black Fire", "type_": "track"},
+                {"position": "B1", "title": "Cantarnos", "type_": "track"},
+                {"position": "B2", "title": "Tired Trade", "type_": "track"}
+            ]
+        }));
+        let c = |i: usize| (r.credits[i].name.as_str(), r.credits[i].role.as_str(), r.credits[i].tracks.clone());
+        assert_eq!(c(0), ("Joe Henderson", "Tenor Saxophone", vec![]));
+        assert_eq!(c(1), ("Roy Haynes", "Drums", vec![(1, 1), (1, 2), (1, 3), (1, 5)]));
+        assert_eq!(c(3), ("Andrew Hill", "Written-By", vec![(1, 1)]));
+        assert_eq!(credit_tracks("C9, A2", &[("A2".into(), (1, 2))]), vec![(1, 2)]);
+    }
+}
@@ -0,0 +1,136 @@
+//! MusicBrainz（https://musicbrainz.org/ws/2/）。トークンは要らない。カバーは Cover Art Archive から取る。
+
+use serde_json::Value;
+
+use super::{get_image, get_json, year_of, Cover, MetadataProvider, Release, ReleaseQuery, ReleaseSummary, ReleaseTrack};
+use crate::length::format_length;
+
+const API: &str = "https://musicbrainz.org/ws/2";
+const RELEASE_URL: &str = "https://musicbrainz.org/release/";
+const COVER_URL: &str = "https://coverartarchive.org/release/";
+const SEARCH_LIMIT: &str = "15";
+
+pub struct MusicBrainz;
+
+/// Lucene の検索式の中の語（"" で囲むので " と \ だけ逃がす）
+fn quoted(s: &str) -> String {
+    format!("\"{}\"", s.trim().replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn search_expr(q: &ReleaseQuery) -> String {
+    [("artist", &q.artist), ("release", &q.title), ("catno", &q.catalog)]
+        .iter()
+        .filter(|(_, v)| !v.trim().is_empty())
+        .map(|(k, v)| format!("{}:{}", k, quoted(v)))
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+/// "artist-credit" をつなげた名前（"Miles Davis & John Coltrane"）
+fn artist_credit(v: &Value) -> String {
+    v["artist-credit"]
+        .as_array()
+        .map(|credits| {
+            credits
+                .iter()
+                .map(|c| format!("{}{}", c["name"].as_str().unwrap_or(""), c["joinphrase"].as_str().unwrap_or("")))
+                .collect::<String>()
+        })
+        .unwrap_or_default()
+}
+
+fn summary(v: &Value) -> ReleaseSummary {
+    let label_info = &v["label-info"][0];
+    let formats: Vec<&str> = v["media"].as_array().into_iter().flatten().filter_map(|m| m["format"].as_str()).collect();
+    ReleaseSummary {
+        id: v["id"].as_str().unwrap_or("").to_string(),
+        title: v["title"].as_str().unwrap_or("").to_string(),
+        artist: artist_credit(v),
+        year: year_of(v["date"].as_str().unwrap_or("")),
+        label: label_info["label"]["name"].as_str().unwrap_or("").to_string(),
+        catalog: label_info["catalog-number"].as_str().unwrap_or("").to_string(),
+        country: v["country"].as_str().unwrap_or("").to_string(),
+        format: formats.join(" + "),
+    }
+}
+
+fn parse_search(v: &Value) -> Vec<ReleaseSummary> {
+    v["releases"].as_array().into_iter().flatten().map(summary).collect()
+}
+
+fn parse_release(v: &Value) -> Release {
+    let mut tracks = Vec::new();
+    for (i, medium) in v["media"].as_array().into_iter().flatten().enumerate() {
+        let disc_no = medium["position"].as_u64().map_or(i as u32 + 1, |p| p as u32);
+        for (j, t) in medium["tracks"].as_array().into_iter().flatten().enumerate() {
+            tracks.push(ReleaseTrack {
+                disc_no,
+                no: t["position"].as_u64().map_or(j as u32 + 1, |p| p as u32),
+                title: t["title"].as_str().unwrap_or("").to_string(),
+                length: t["length"].as_u64().map(|ms| format_length((ms + 500) / 1000)).unwrap_or_default(),
+            });
+        }
+    }
+    let summary = summary(v);
+    let url = format!("{}{}", RELEASE_URL, summary.id);
+    Release { summary, tracks, url, ..Default::default() }
+}
+
+impl MetadataProvider for MusicBrainz {
+    fn id(&self) -> &'static str {
+        "musicbrainz"
+    }
+
+    fn name(&self) -> &'static str {
+        "MusicBrainz"
+    }
+
+    fn search_release(&self, query: &ReleaseQuery) -> Result<Vec<ReleaseSummary>, String> {
+        let expr = search_expr(query);
+        let v = get_json(&format!("{}/release/", API), &[("query", &expr), ("fmt", "json"), ("limit", SEARCH_LIMIT)], None)?;
+        Ok(parse_search(&v))
+    }
+
+    fn fetch_release(&self, id: &str) -> Result<Release, String> {
+        let url = format!("{}/release/{}", API, id);
+        let v = get_json(&url, &[("inc", "recordings+labels+artist-credits"), ("fmt", "json")], None)?;
+        Ok(parse_release(&v))
+    }
+
+    fn fetch_cover(&self, id: &str) -> Result<Option<Cover>, String> {
+        get_image(&format!("{}{}/front-500", COVER_URL, id), None)
+    }
+}
+
+#[cfg(test)]
+mod musicbrainz_tests {
+    use super::{parse_release, parse_search, search_expr};
+    use crate::metadata::ReleaseQuery;
+    use serde_json::json;
+
+    #[test]
+    fn search_and_release_responses() {
+        let q = ReleaseQuery { artist: "Miles Davis".into(), title: "Kind of \"Blue\"".into(), catalog: String::new() };
+        assert_eq!(search_expr(&q), r#"artist:"Miles Davis" AND release:"Kind of \"Blue\"""#);
+
+        let release = json!({
+            "id": "mbid-1",
+            "title": "Kind of Blue",
+            "date": "1959-08-17",
+            "country": "US",
+            "artist-credit": [{"name": "Miles Davis", "joinphrase": ""}],
+            "label-info": [{"catalog-number": "CL 1355", "label": {"name": "Columbia"}}],
+            "media": [{"position": 1, "format": "12\" Vinyl", "tracks": [
+                {"position": 1, "title": "So What", "length": 562_000},
+                {"position": 2, "title": "Freddie Freeloader", "length": null}
+            ]}]
+        });
+        let list = parse_search(&json!({"releases": [release.clone()]}));
+        assert_eq!(list[0].year, Some(1959));
+        assert_eq!((list[0].label.as_str(), list[0].catalog.as_str()), ("Columbia", "CL 1355"));
+        let r = parse_release(&release);
+        assert_eq!(r.url, "https://musicbrainz.org/release/mbid-1");
+        assert_eq!((r.tracks[0].no, r.tracks[0].length.as_str()), (1, "9:22"));
+        assert_eq!(r.tracks[1].length, "");
+    }
+}
@@ -0,0 +1,215 @@
+//! Spotify（https://api.spotify.com/v1）。アプリのクライアント ID・シークレット（`--spotify-client-id` / `--spotify-client-secret`）で
+//! Client Credentials のアクセストークンを取り、期限まで使い回す。アルバムはアルバムの URL（`https://open.spotify.com/album/…`）・
+//! URI（`spotify:album:…`）・ID のどれでも指定できる。品番は持っていないので空のまま。
+
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::{
+    get_image, get_json_with, user_agent, year_of, Cover, MetadataProvider, ProviderConfig, Release, ReleaseQuery,
+    ReleaseSummary, ReleaseTrack, HTTP_TIMEOUT,
+};
+use crate::length::format_length;
+
+const API: &str = "https://api.spotify.com/v1";
+const TOKEN_URL: &str = "https://accounts.spotify.com/api/token";
+const SEARCH_LIMIT: &str = "15";
+/// 期限ぎりぎりのトークンは使わない
+const TOKEN_MARGIN: Duration = Duration::from_secs(60);
+
+/// アクセストークンと期限
+#[derive(Debug, Default)]
+pub struct TokenCache(Mutex<Option<(String, Instant)>>);
+
+pub struct Spotify {
+    credentials: Option<(String, String)>,
+    token: Arc<TokenCache>,
+}
+
+impl Spotify {
+    pub fn new(config: &ProviderConfig) -> Self {
+        let non_empty = |s: &Option<String>| s.clone().filter(|s| !s.trim().is_empty());
+        Self {
+            credentials: non_empty(&config.spotify_client_id).zip(non_empty(&config.spotify_client_secret)),
+            token: config.spotify_token.clone(),
+        }
+    }
+
+    /// 使えるアクセストークン（なければ取る）
+    fn access_token(&self) -> Result<String, String> {
+        let (id, secret) = self.credentials.as_ref().ok_or_else(|| {
+            "Spotify を使うにはサーバーに --spotify-client-id と --spotify-client-secret（環境変数 SPOTIFY_CLIENT_ID / SPOTIFY_CLIENT_SECRET）を渡してください"
+                .to_string()
+        })?;
+        let mut cached = self.token.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((token, _)) = cached.as_ref().filter(|(_, expires)| Instant::now() + TOKEN_MARGIN < *expires) {
+            return Ok(token.clone());
+        }
+        let resp = ureq::post(TOKEN_URL)
+            .timeout(HTTP_TIMEOUT)
+            .set("User-Agent", &user_agent())
+            .send_form(&[("grant_type", "client_credentials"), ("client_id", id), ("client_secret", secret)]);
+        let v: Value = match resp {
+            Ok(resp) => resp.into_json().map_err(|e| format!("{}: {}", TOKEN_URL, e))?,
+            Err(ureq::Error::Status(code, _)) => return Err(format!("{}: {}（クライアント ID・シークレットを確かめてください）", TOKEN_URL, code)),
+            Err(e) => return Err(format!("{}: {}", TOKEN_URL, e)),
+        };
+        let token = v["access_token"].as_str().filter(|t| !t.is_empty()).ok_or_else(|| format!("{}: no access_token", TOKEN_URL))?;
+        let expires = Instant::now() + Duration::from_secs(v["expires_in"].as_u64().unwrap_or(3600));
+        *cached = Some((token.to_string(), expires));
+        Ok(token.to_string())
+    }
+
+    fn get(&self, url: &str, query: &[(&str, &str)]) -> Result<Value, String> {
+        let token = self.access_token()?;
+        get_json_with(url, query, Some(&format!("Bearer {}", token)))
+    }
+}
+
+/// アルバムの URL・URI・ID から ID を取る
+fn album_id(s: &str) -> Option<String> {
+    let s = s.trim();
+    let id = match s.strip_prefix("spotify:album:") {
+        Some(id) => id,
+        None if s.contains("open.spotify.com/") => {
+            let path = s.split(['?', '#']).next().unwrap_or("");
+            let mut segments = path.split('/').skip_while(|seg| *seg != "album");
+            segments.nth(1)?
+        }
+        None => s,
+    };
+    (!id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric())).then(|| id.to_string())
+}
+
+fn artist_names(v: &Value) -> String {
+    v.as_array().into_iter().flatten().filter_map(|a| a["name"].as_str()).collect::<Vec<_>>().join(", ")
+}
+
+fn parse_search(v: &Value) -> Vec<ReleaseSummary> {
+    v["albums"]["items"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|a| !a.is_null())
+        .map(|a| ReleaseSummary {
+            id: a["id"].as_str().unwrap_or("").to_string(),
+            title: a["name"].as_str().unwrap_or("").to_string(),
+            artist: artist_names(&a["artists"]),
+            year: year_of(a["release_date"].as_str().unwrap_or("")),
+            format: a["album_type"].as_str().unwrap_or("").to_string(),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn parse_track(t: &Value) -> ReleaseTrack {
+    ReleaseTrack {
+        disc_no: t["disc_number"].as_u64().unwrap_or(1) as u32,
+        no: t["track_number"].as_u64().unwrap_or(0) as u32,
+        title: t["name"].as_str().unwrap_or("").to_string(),
+        length: t["duration_ms"].as_u64().map(|ms| format_length((ms + 500) / 1000)).unwrap_or_default(),
+    }
+}
+
+/// アルバム（曲目は最初のページだけ。続きは fetch_release で足す）
+fn parse_album(v: &Value) -> Release {
+    Release {
+        summary: ReleaseSummary {
+            id: v["id"].as_str().unwrap_or("").to_string(),
+            title: v["name"].as_str().unwrap_or("").to_string(),
+            artist: artist_names(&v["artists"]),
+            year: year_of(v["release_date"].as_str().unwrap_or("")),
+            label: v["label"].as_str().unwrap_or("").trim().to_string(),
+            format: v["album_type"].as_str().unwrap_or("").to_string(),
+            ..Default::default()
+        },
+        tracks: v["tracks"]["items"].as_array().into_iter().flatten().map(parse_track).collect(),
+        url: v["external_urls"]["spotify"].as_str().unwrap_or("").to_string(),
+        ..Default::default()
+    }
+}
+
+impl MetadataProvider for Spotify {
+    fn id(&self) -> &'static str {
+        "spotify"
+    }
+
+    fn name(&self) -> &'static str {
+        "Spotify"
+    }
+
+    fn search_release(&self, query: &ReleaseQuery) -> Result<Vec<ReleaseSummary>, String> {
+        let mut terms: Vec<String> = Vec::new();
+        for (k, v) in [("artist", &query.artist), ("album", &query.title)] {
+            if !v.trim().is_empty() {
+                terms.push(format!("{}:{}", k, v.trim()));
+            }
+        }
+        if !query.catalog.trim().is_empty() {
+            terms.push(query.catalog.trim().to_string());
+        }
+        let v = self.get(&format!("{}/search", API), &[("q", &terms.join(" ")), ("type", "album"), ("limit", SEARCH_LIMIT)])?;
+        Ok(parse_search(&v))
+    }
+
+    fn fetch_release(&self, id: &str) -> Result<Release, String> {
+        let id = album_id(id).ok_or_else(|| format!("Spotify のアルバムの URL・URI ではありません: {}", id.trim()))?;
+        let v = self.get(&format!("{}/albums/{}", API, id), &[])?;
+        let mut release = parse_album(&v);
+        // 50 曲を超えるアルバムは曲目が続きのページにある
+        let mut next = v["tracks"]["next"].as_str().map(str::to_string);
+        while let Some(url) = next {
+            let page = self.get(&url, &[])?;
+            release.tracks.extend(page["items"].as_array().into_iter().flatten().map(parse_track));
+            next = page["next"].as_str().map(str::to_string);
+        }
+        Ok(release)
+    }
+
+    fn fetch_cover(&self, id: &str) -> Result<Option<Cover>, String> {
+        let id = album_id(id).ok_or_else(|| format!("Spotify のアルバムの URL・URI ではありません: {}", id.trim()))?;
+        let v = self.get(&format!("{}/albums/{}", API, id), &[])?;
+        // 大きいものから並んでいる
+        match v["images"][0]["url"].as_str().filter(|u| !u.is_empty()) {
+            Some(url) => get_image(url, None),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod spotify_tests {
+    use super::{album_id, parse_album, parse_search};
+    use serde_json::json;
+
+    #[test]
+    fn album_ids_from_urls_and_uris() {
+        assert_eq!(album_id("spotify:album:1weenld61qoidwYuZ1GESA").as_deref(), Some("1weenld61qoidwYuZ1GESA"));
+        assert_eq!(album_id("https://open.spotify.com/album/1weenld61qoidwYuZ1GESA?si=abc").as_deref(), Some("1weenld61qoidwYuZ1GESA"));
+        assert_eq!(album_id("https://open.spotify.com/intl-ja/album/1weenld61qoidwYuZ1GESA").as_deref(), Some("1weenld61qoidwYuZ1GESA"));
+        assert_eq!(album_id(" 1weenld61qoidwYuZ1GESA ").as_deref(), Some("1weenld61qoidwYuZ1GESA"));
+        assert_eq!(album_id("https://open.spotify.com/track/xyz"), None);
+        assert_eq!(album_id("spotify:album:../x"), None);
+    }
+
+    #[test]
+    fn search_and_album_responses() {
+        let list = parse_search(&json!({"albums": {"items": [
+            {"id": "a1", "name": "Kind Of Blue", "artists": [{"name": "Miles Davis"}], "release_date": "1959-08-17", "album_type": "album"},
+            null
+        ]}}));
+        assert_eq!(list.len(), 1);
+        assert_eq!((list[0].id.as_str(), list[0].artist.as_str(), list[0].year), ("a1", "Miles Davis", Some(1959)));
+
+        let r = parse_album(&json!({
+            "id": "a1", "name": "Kind Of Blue", "release_date": "1959", "label": "Columbia", "album_type": "album",
+            "artists": [{"name": "Miles Davis"}, {"name": "John Coltrane"}],
+            "external_urls": {"spotify": "https://open.spotify.com/album/a1"},
+            "tracks": {"items": [{"disc_number": 1, "track_number": 1, "name": "So What", "duration_ms": 562000}], "next": null}
+        }));
+        assert_eq!((r.summary.artist.as_str(), r.summary.year, r.summary.label.as_str()), ("Miles Davis, John Coltrane", Some(1959), "Columbia"));
+        assert_eq!((r.tracks[0].no, r.tracks[0].length.as_str()), (1, "9:22"));
+        assert_eq!(r.url, "https://open.spotify.com/album/a1");
+    }
+}
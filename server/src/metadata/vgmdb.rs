@@ -0,0 +1,185 @@
+//! VGMdb（ゲーム音楽）。VGMdb 自体に API はないので、その JSON 版の vgmdb.info（https://vgmdb.info/）を使う。トークンは要らない。
+//! 名前は英語表記を優先し、なければ日本語表記。作曲・編曲のクレジットも返す（フォームではレコードの作曲者とコメントに入れる）。
+
+use serde_json::Value;
+
+use super::{get_image, get_json, year_of, Cover, MetadataProvider, Release, ReleaseQuery, ReleaseSummary, ReleaseTrack};
+
+const API: &str = "https://vgmdb.info";
+const ALBUM_URL: &str = "https://vgmdb.net/album/";
+const SEARCH_LIMIT: usize = 15;
+
+pub struct Vgmdb;
+
+/// `{"en": .., "ja": ..}`（曲名は `{"English": .., "Japanese": ..}`）から 1 つ選ぶ
+fn pick_name(names: &Value) -> String {
+    let Some(map) = names.as_object() else {
+        return names.as_str().unwrap_or("").to_string();
+    };
+    ["en", "English", "ja-latn", "Romaji", "ja", "Japanese"]
+        .iter()
+        .find_map(|k| map.get(*k).and_then(Value::as_str).filter(|s| !s.trim().is_empty()))
+        .or_else(|| map.values().find_map(Value::as_str))
+        .unwrap_or("")
+        .trim()
+        .to_string()
+}
+
+/// "album/1234" → "1234"
+fn album_id(link: &str) -> String {
+    link.trim_start_matches('/').trim_start_matches("album/").to_string()
+}
+
+/// クレジット（composers・arrangers など）の名前
+fn credit_names(v: &Value) -> Vec<String> {
+    v.as_array()
+        .into_iter()
+        .flatten()
+        .map(|a| pick_name(&a["names"]))
+        .filter(|n| !n.is_empty())
+        .collect()
+}
+
+fn parse_search(v: &Value) -> Vec<ReleaseSummary> {
+    v["results"]["albums"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .take(SEARCH_LIMIT)
+        .map(|a| ReleaseSummary {
+            id: album_id(a["link"].as_str().unwrap_or("")),
+            title: pick_name(&a["titles"]),
+            year: year_of(a["release_date"].as_str().unwrap_or("")),
+            catalog: a["catalog"].as_str().unwrap_or("").to_string(),
+            format: a["media_format"].as_str().unwrap_or("").to_string(),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// レーベル（なければ発売元）
+fn label_of(v: &Value) -> String {
+    let orgs = v["organizations"].as_array().into_iter().flatten();
+    orgs.clone()
+        .find(|o| o["role"] == "label")
+        .or_else(|| orgs.clone().find(|o| o["role"] == "publisher"))
+        .map(|o| pick_name(&o["names"]))
+        .unwrap_or_else(|| pick_name(&v["publisher"]["names"]))
+}
+
+fn parse_release(id: &str, v: &Value) -> Release {
+    let mut tracks = Vec::new();
+    for (i, disc) in v["discs"].as_array().into_iter().flatten().enumerate() {
+        for (j, t) in disc["tracks"].as_array().into_iter().flatten().enumerate() {
+            let length = t["track_length"].as_str().unwrap_or("");
+            tracks.push(ReleaseTrack {
+                disc_no: i as u32 + 1,
+                no: j as u32 + 1,
+                title: pick_name(&t["names"]),
+                // 長さの分からない曲は "Unknown"
+                length: if length.contains(':') { length.to_string() } else { String::new() },
+            });
+        }
+    }
+    let composers = credit_names(&v["composers"]);
+    // ゲーム音楽のアーティストは作曲者のことが多い（表示ラベルはレーベルを使う）
+    let artist = composers.join(", ");
+    Release {
+        summary: ReleaseSummary {
+            id: id.to_string(),
+            title: pick_name(&v["names"]),
+            artist,
+            year: year_of(v["release_date"].as_str().unwrap_or("")),
+            label: label_of(v),
+            catalog: v["catalog"].as_str().filter(|c| *c != "N/A").unwrap_or("").to_string(),
+            country: String::new(),
+            format: v["media_format"].as_str().unwrap_or("").to_string(),
+        },
+        tracks,
+        url: v["vgmdb_link"].as_str().map_or_else(|| format!("{}{}", ALBUM_URL, id), str::to_string),
+        composers,
+        arrangers: credit_names(&v["arrangers"]),
+        ..Default::default()
+    }
+}
+
+/// 表（"Front"）の画像、なければアルバムの画像
+fn cover_url(v: &Value) -> Option<&str> {
+    v["covers"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|c| c["name"].as_str().is_some_and(|n| n.to_lowercase().starts_with("front")))
+        .and_then(|c| c["full"].as_str())
+        .or_else(|| v["picture_full"].as_str())
+        .filter(|u| !u.is_empty())
+}
+
+impl MetadataProvider for Vgmdb {
+    fn id(&self) -> &'static str {
+        "vgmdb"
+    }
+
+    fn name(&self) -> &'static str {
+        "VGMdb"
+    }
+
+    fn search_release(&self, query: &ReleaseQuery) -> Result<Vec<ReleaseSummary>, String> {
+        // 品番がいちばん確実。なければタイトル（作曲者名でもアルバムは引ける）
+        let q = [&query.catalog, &query.title, &query.artist]
+            .into_iter()
+            .map(|s| s.trim())
+            .find(|s| !s.is_empty())
+            .unwrap_or("");
+        let v = get_json(&format!("{}/search/albums", API), &[("q", q), ("format", "json")], None)?;
+        Ok(parse_search(&v))
+    }
+
+    fn fetch_release(&self, id: &str) -> Result<Release, String> {
+        let v = get_json(&format!("{}/album/{}", API, id), &[("format", "json")], None)?;
+        Ok(parse_release(id, &v))
+    }
+
+    fn fetch_cover(&self, id: &str) -> Result<Option<Cover>, String> {
+        let v = get_json(&format!("{}/album/{}", API, id), &[("format", "json")], None)?;
+        match cover_url(&v) {
+            Some(url) => get_image(url, None),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod vgmdb_tests {
+    use super::{cover_url, parse_release, parse_search};
+    use serde_json::json;
+
+    #[test]
+    fn search_and_album_responses() {
+        let list = parse_search(&json!({"results": {"albums": [{
+            "link": "album/79", "catalog": "SQEX-10001~3", "release_date": "2006-03-22",
+            "titles": {"ja": "ファイナルファンタジーXII オリジナル・サウンドトラック", "en": "FINAL FANTASY XII Original Soundtrack"}
+        }]}}));
+        assert_eq!((list[0].id.as_str(), list[0].year), ("79", Some(2006)));
+        assert_eq!(list[0].title, "FINAL FANTASY XII Original Soundtrack");
+
+        let album = json!({
+            "names": {"en": "FINAL FANTASY XII Original Soundtrack"}, "catalog": "SQEX-10001~3", "release_date": "2006-03-22",
+            "media_format": "4 CD", "vgmdb_link": "https://vgmdb.net/album/79",
+            "organizations": [{"role": "manufacturer", "names": {"en": "Sony"}}, {"role": "label", "names": {"en": "Square Enix Music"}}],
+            "composers": [{"names": {"en": "Hitoshi Sakimoto", "ja": "崎元仁"}}],
+            "arrangers": [{"names": {"ja": "岩田匡治"}}],
+            "discs": [
+                {"name": "Disc 1", "tracks": [{"names": {"English": "FINAL FANTASY", "Japanese": "ファイナルファンタジー"}, "track_length": "2:06"}]},
+                {"name": "Disc 2", "tracks": [{"names": {"Japanese": "ラバナスタ"}, "track_length": "Unknown"}]}
+            ],
+            "covers": [{"name": "Back", "full": "https://media.vgm.io/b.jpg"}, {"name": "Front", "full": "https://media.vgm.io/f.jpg"}]
+        });
+        let r = parse_release("79", &album);
+        assert_eq!((r.summary.label.as_str(), r.summary.artist.as_str()), ("Square Enix Music", "Hitoshi Sakimoto"));
+        assert_eq!(r.arrangers, vec!["岩田匡治".to_string()]);
+        assert_eq!(r.tracks.iter().map(|t| (t.disc_no, t.no, t.length.as_str())).collect::<Vec<_>>(), vec![(1, 1, "2:06"), (2, 1, "")]);
+        assert_eq!(r.tracks[1].title, "ラバナスタ");
+        assert_eq!(cover_url(&album), Some("https://media.vgm.io/f.jpg"));
+    }
+}
@@ -0,0 +1,173 @@
+//! レコード JSON の形式の版（`schema_version`）と移行。項目の名前を変えたり分けたりするときは、
+//! `SCHEMA_VERSION` を上げて `MIGRATIONS` に 1 つ前の版からの書き換えを足す。古いファイルは読むたびに（`storage::read_record`）
+//! 今の版に直してから使うので、手で何百ものファイルを直さなくてよい。ファイルそのものは保存したときか、
+//! `POST /api/schema/migrate` でまとめて書き直す。`schema_version` のないファイルは版 0 として扱う。
+
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+use crate::storage::Storage;
+use crate::versions::{self, Precondition};
+
+/// 今の形式の版
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// 1 つ前の版から `to` への書き換え
+struct Migration {
+    to: u64,
+    description: &'static str,
+    apply: fn(&mut Map<String, Value>),
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    to: 1,
+    description: "文字列で書いた発売年・スコアを数にし、録音年が 1 つの数なら配列にする",
+    apply: numbers_from_strings,
+}];
+
+/// 版 0 → 1。読むときは文字列の "1959" や "4.5" も受け付けていたが、集計や一括の処理が数だけを見られるようにする
+fn numbers_from_strings(obj: &mut Map<String, Value>) {
+    for key in ["release_year", "score"] {
+        let parsed = obj.get(key).and_then(Value::as_str).and_then(|s| s.trim().parse::<f64>().ok());
+        if let Some(n) = parsed {
+            let n = if n.fract() == 0.0 { Value::from(n as i64) } else { Value::from(n) };
+            obj.insert(key.into(), n);
+        }
+    }
+    if let Some(year) = obj.get("record_year").filter(|y| y.is_number()).cloned() {
+        obj.insert("record_year".into(), Value::Array(vec![year]));
+    }
+}
+
+/// レコードの版（書いていなければ 0）
+pub fn schema_version(v: &Value) -> u64 {
+    v["schema_version"].as_u64().unwrap_or(0)
+}
+
+/// 今の版に直す。直したら true。このサーバーより新しい版のレコードは、壊さないよう読まずにエラーにする。
+/// オブジェクトでないものはそのまま（入力チェックで見つける）。
+pub fn upgrade(v: &mut Value) -> Result<bool, String> {
+    let version = schema_version(v);
+    if version > SCHEMA_VERSION {
+        return Err(format!("schema_version {} のレコードです（このサーバーは {} まで）。サーバーを新しくしてください", version, SCHEMA_VERSION));
+    }
+    let Some(obj) = v.as_object_mut() else {
+        return Ok(false);
+    };
+    if version == SCHEMA_VERSION {
+        return Ok(false);
+    }
+    for m in MIGRATIONS.iter().filter(|m| m.to > version) {
+        (m.apply)(obj);
+    }
+    obj.insert("schema_version".into(), Value::from(SCHEMA_VERSION));
+    Ok(true)
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[schema(as = SchemaMigrateBody)]
+pub struct MigrateBody {
+    /// true なら書き直さずに数えるだけ
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Default, serde::Serialize)]
+struct MigrateReport {
+    dry_run: bool,
+    schema_version: u64,
+    /// 移行の一覧（版, 説明）
+    migrations: Vec<(u64, &'static str)>,
+    /// ファイルに書いてある版ごとの件数
+    versions: BTreeMap<u64, usize>,
+    /// 書き直す（書き直した）ファイル
+    migrated: Vec<String>,
+    /// 読めない・新しすぎる・書けないもの（ファイル名, 理由）
+    failed: Vec<(String, String)>,
+}
+
+/// 古い版のファイルを書き直す。ファイルの版を見るので `read_record`（読むときに直す）は通さない
+fn migrate_records(storage: &dyn Storage, dry_run: bool) -> Result<MigrateReport, String> {
+    let mut report = MigrateReport {
+        dry_run,
+        schema_version: SCHEMA_VERSION,
+        migrations: MIGRATIONS.iter().map(|m| (m.to, m.description)).collect(),
+        ..Default::default()
+    };
+    for filename in storage.list().map_err(|e| e.to_string())? {
+        let parsed = storage
+            .read(&filename)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| serde_json::from_str::<Value>(&String::from_utf8_lossy(&bytes)).map_err(|e| e.to_string()));
+        let mut v = match parsed {
+            Ok(v) => v,
+            Err(e) => {
+                report.failed.push((filename, e));
+                continue;
+            }
+        };
+        *report.versions.entry(schema_version(&v)).or_default() += 1;
+        match upgrade(&mut v) {
+            Ok(false) => {}
+            Ok(true) if dry_run => report.migrated.push(filename),
+            // 読むときにも同じ直し方をするので、直したものの版が今の版
+            Ok(true) => match versions::write(storage, &filename, &Precondition::Version(versions::version_of(&v)), &v) {
+                Ok(_) => report.migrated.push(filename),
+                Err(e) => report.failed.push((filename, e.to_string())),
+            },
+            Err(e) => report.failed.push((filename, e)),
+        }
+    }
+    Ok(report)
+}
+
+/// `POST /api/schema/migrate` `{"dry_run": true}` で版ごとの件数と書き直すファイルを返し、`{}` で書き直す
+#[utoipa::path(
+    post,
+    path = "/api/schema/migrate",
+    tag = "schema",
+    summary = "古い形式のファイルを今の形式に書き直す",
+    params(LibraryQuery),
+    request_body = MigrateBody,
+)]
+pub async fn migrate(Lib(library): Lib, Json(body): Json<MigrateBody>) -> impl IntoResponse {
+    match tokio::task::spawn_blocking(move || migrate_records(&*library.storage, body.dry_run)).await {
+        Ok(Ok(report)) => (StatusCode::OK, Json(report)).into_response(),
+        Ok(Err(e)) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod migrations_tests {
+    use super::{upgrade, MIGRATIONS, SCHEMA_VERSION};
+    use serde_json::json;
+
+    #[test]
+    fn migrations_run_in_order_up_to_the_current_version() {
+        for (i, m) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(m.to, i as u64 + 1);
+        }
+        assert_eq!(MIGRATIONS.last().map(|m| m.to), Some(SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn old_records_are_upgraded_and_stamped() {
+        let mut v = json!({"title": "Alone", "release_year": "1968", "score": " 4.5", "record_year": 1968});
+        assert_eq!(upgrade(&mut v), Ok(true));
+        assert_eq!(v, json!({"title": "Alone", "release_year": 1968, "score": 4.5, "record_year": [1968], "schema_version": SCHEMA_VERSION}));
+        // 2 回目は何もしない
+        assert_eq!(upgrade(&mut v), Ok(false));
+
+        let mut current = json!({"title": "X", "score": "5", "schema_version": SCHEMA_VERSION});
+        assert_eq!(upgrade(&mut current), Ok(false));
+        assert_eq!(current["score"], "5");
+
+        let mut newer = json!({"title": "X", "schema_version": SCHEMA_VERSION + 1});
+        assert!(upgrade(&mut newer).is_err());
+        assert_eq!(upgrade(&mut json!([1])), Ok(false));
+    }
+}
@@ -0,0 +1,294 @@
+//! 人名の台帳（正規名と別名）。`{DB_PATH}/.config/names.json` に置く。
+//! 役割ごとではなく人ごとの表なので、作曲者とアーティスト（personnel）で同じ表を使う。
+//! いまは作曲者の正規化に使う: 保存時にトラックの composer を整え（前後の空白・連続空白・大文字だけ/小文字だけの表記）、
+//! 別名（"T. Monk"）を正規名（"Thelonious Monk"）に置き換える。曲の索引と `/api/composers` も同じ規則でまとめる。
+//!
+//! 例: `{"people": [{"name": "Thelonious Monk", "aliases": ["T. Monk", "Monk"]}]}`
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::listen::write_json;
+use crate::problem::error;
+use crate::settings::{config_path, CONFIG_DIR};
+use crate::{load_all_records, AppState};
+
+const NAMES_FILE: &str = "names.json";
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Person {
+    /// 正規名
+    pub name: String,
+    /// 別表記（大文字小文字は区別しない）
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NameRegistry {
+    #[serde(default)]
+    pub people: Vec<Person>,
+}
+
+/// 別名・正規名（小文字）→ 正規名 の引き表
+pub struct NameIndex(HashMap<String, String>);
+
+/// 大文字だけ・小文字だけで書かれた名前を語頭だけ大文字にする（"THELONIOUS MONK" → "Thelonious Monk"、"j.s. bach" → "J.S. Bach"）。
+/// 大文字小文字が混ざっているもの（"McCoy Tyner"）や、かな・漢字はそのまま。連続空白は 1 つにする。
+pub fn canonical_casing(s: &str) -> String {
+    let s = s.split_whitespace().collect::<Vec<_>>().join(" ");
+    let has_upper = s.chars().any(char::is_uppercase);
+    let has_lower = s.chars().any(char::is_lowercase);
+    if has_upper && has_lower {
+        return s;
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut prev: Option<char> = None;
+    for c in s.chars() {
+        let word_start = prev.is_none_or(|p| !p.is_alphabetic() && p != '\'' && p != '’');
+        if word_start {
+            out.extend(c.to_uppercase());
+        } else {
+            out.extend(c.to_lowercase());
+        }
+        prev = Some(c);
+    }
+    out
+}
+
+impl NameRegistry {
+    pub fn index(&self) -> NameIndex {
+        let mut map = HashMap::new();
+        for p in &self.people {
+            for n in std::iter::once(&p.name).chain(p.aliases.iter()) {
+                map.insert(n.trim().to_lowercase(), p.name.trim().to_string());
+            }
+        }
+        NameIndex(map)
+    }
+
+    /// 2 人以上に同じ別名が付いているもの（どちらに寄せるか決められない）
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut owner: HashMap<String, &str> = HashMap::new();
+        let mut dup = BTreeSet::new();
+        for p in &self.people {
+            for n in std::iter::once(&p.name).chain(p.aliases.iter()) {
+                let key = n.trim().to_lowercase();
+                if let Some(prev) = owner.insert(key, &p.name) {
+                    if prev != p.name {
+                        dup.insert(n.trim().to_string());
+                    }
+                }
+            }
+        }
+        dup.into_iter().collect()
+    }
+}
+
+impl NameIndex {
+    /// 表記を整え、台帳にあれば正規名にする。
+    pub fn canonical(&self, raw: &str) -> String {
+        let cased = canonical_casing(raw);
+        self.0.get(&cased.to_lowercase()).cloned().unwrap_or(cased)
+    }
+}
+
+/// composer（文字列または共作の文字列配列）を正規化する。
+pub fn normalize_composer(v: &Value, index: &NameIndex) -> Value {
+    match v {
+        Value::String(s) => Value::String(index.canonical(s)),
+        Value::Array(a) => Value::Array(
+            a.iter()
+                .map(|c| match c {
+                    Value::String(s) => Value::String(index.canonical(s)),
+                    other => other.clone(),
+                })
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// レコードの composers と全トラックの composer を正規化する（保存時）。
+pub fn normalize_record(v: &mut Value, index: &NameIndex) {
+    if v.get("composers").is_some() {
+        v["composers"] = normalize_composer(&v["composers"], index);
+    }
+    if let Some(tracks) = v["tracks"].as_array_mut() {
+        for t in tracks {
+            if t.get("composer").is_some() {
+                t["composer"] = normalize_composer(&t["composer"], index);
+            }
+        }
+    }
+}
+
+/// 台帳を読む。ファイルがなければ空。
+pub fn load_names(db_path: &Path) -> Result<NameRegistry, String> {
+    match fs::read_to_string(config_path(db_path, NAMES_FILE)) {
+        Ok(data) => serde_json::from_str(&data).map_err(|e| format!("invalid names: {}", e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(NameRegistry::default()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// `GET /api/names`
+#[utoipa::path(get, path = "/api/names", tag = "maintenance", summary = "名前の台帳")]
+pub async fn get_names(State(state): State<AppState>) -> impl IntoResponse {
+    match load_names(&state.db_path) {
+        Ok(r) => (StatusCode::OK, Json(r)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// `POST /api/names` 台帳を置き換える。名前が空の人は捨て、同じ別名が 2 人に付いていれば 400。
+#[utoipa::path(post, path = "/api/names", tag = "maintenance", summary = "名前の台帳を保存する", request_body = serde_json::Value)]
+pub async fn save_names(State(state): State<AppState>, Json(mut body): Json<NameRegistry>) -> impl IntoResponse {
+    body.people.retain(|p| !p.name.trim().is_empty());
+    for p in &mut body.people {
+        p.name = p.name.trim().to_string();
+        p.aliases = p.aliases.iter().map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect();
+    }
+    let conflicts = body.conflicts();
+    if !conflicts.is_empty() {
+        return error(StatusCode::BAD_REQUEST, format!("alias used by more than one person: {}", conflicts.join(", ")));
+    }
+    let path = config_path(&state.db_path, NAMES_FILE);
+    let res = fs::create_dir_all(state.db_path.join(CONFIG_DIR))
+        .map_err(|e| e.to_string())
+        .and_then(|_| serde_json::to_value(&body).map_err(|e| e.to_string()))
+        .and_then(|v| write_json(&path, &v));
+    match res {
+        Ok(()) => (StatusCode::OK, Json(body)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+struct Composer {
+    /// 正規名
+    name: String,
+    /// レコードに書かれている表記（正規名と違うもの。保存し直すと正規名になる）
+    spellings: Vec<String>,
+    tracks: usize,
+    /// レコード数（レコードの composers に書いたものも数える）
+    records: usize,
+}
+
+/// composer（文字列または共作の配列）の名前
+fn composer_names(v: &Value) -> Vec<&str> {
+    match v {
+        Value::String(s) => vec![s.as_str()],
+        Value::Array(a) => a.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    }
+    .into_iter()
+    .map(str::trim)
+    .filter(|r| !r.is_empty())
+    .collect()
+}
+
+/// 全トラックとレコードの composers の作曲者を正規名でまとめる（トラック数の多い順、同数はレコード数・名前順）。
+fn collect_composers(records: &[(String, Value)], index: &NameIndex) -> Vec<Composer> {
+    let mut map: BTreeMap<String, (BTreeSet<String>, usize, BTreeSet<&str>)> = BTreeMap::new();
+    for (filename, v) in records {
+        let on_record = composer_names(&v["composers"]).into_iter().map(|r| (r, false));
+        let on_tracks = v["tracks"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(|t| composer_names(&t["composer"]).into_iter().map(|r| (r, true)));
+        for (r, is_track) in on_record.chain(on_tracks) {
+            let name = index.canonical(r);
+            let entry = map.entry(name.clone()).or_default();
+            if r != name {
+                entry.0.insert(r.to_string());
+            }
+            entry.1 += usize::from(is_track);
+            entry.2.insert(filename);
+        }
+    }
+    let mut list: Vec<Composer> = map
+        .into_iter()
+        .map(|(name, (spellings, tracks, files))| Composer {
+            name,
+            spellings: spellings.into_iter().collect(),
+            tracks,
+            records: files.len(),
+        })
+        .collect();
+    list.sort_by(|a, b| b.tracks.cmp(&a.tracks).then_with(|| b.records.cmp(&a.records)).then_with(|| a.name.cmp(&b.name)));
+    list
+}
+
+/// `GET /api/composers` 作曲者の一覧（正規名ごとの表記ゆれ・トラック数・レコード数）。
+#[utoipa::path(get, path = "/api/composers", tag = "maintenance", summary = "作曲者の一覧", params(LibraryQuery))]
+pub async fn get_composers(State(state): State<AppState>, Lib(library): Lib) -> impl IntoResponse {
+    let index = match load_names(&state.db_path) {
+        Ok(r) => r.index(),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    let Some(records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    (StatusCode::OK, Json(collect_composers(&records, &index))).into_response()
+}
+
+#[cfg(test)]
+mod names_tests {
+    use super::{canonical_casing, collect_composers, normalize_record, NameRegistry, Person};
+    use serde_json::json;
+
+    fn registry() -> NameRegistry {
+        NameRegistry {
+            people: vec![Person { name: "Thelonious Monk".into(), aliases: vec!["T. Monk".into(), "Monk".into()] }],
+        }
+    }
+
+    #[test]
+    fn casing_only_touches_single_case_names() {
+        assert_eq!(canonical_casing("  THELONIOUS   MONK "), "Thelonious Monk");
+        assert_eq!(canonical_casing("j.s. bach"), "J.S. Bach");
+        assert_eq!(canonical_casing("McCoy Tyner"), "McCoy Tyner");
+        assert_eq!(canonical_casing("o'brien"), "O'brien");
+        assert_eq!(canonical_casing("武満徹"), "武満徹");
+    }
+
+    #[test]
+    fn aliases_collapse_on_save_and_in_registry() {
+        let index = registry().index();
+        let mut v = json!({"composers": ["MONK"], "tracks": [
+            {"title": "Bemsha Swing", "composer": "t. monk"},
+            {"title": "Blue Monk", "composer": ["Thelonious Monk", "Denzil Best"]}
+        ]});
+        normalize_record(&mut v, &index);
+        assert_eq!(v["tracks"][0]["composer"], "Thelonious Monk");
+        assert_eq!(v["composers"], json!(["Thelonious Monk"]));
+
+        let records = vec![("a.json".to_string(), json!({"tracks": [{"composer": "T. Monk"}, {"composer": "MONK"}]}))];
+        let composers = collect_composers(&records, &index);
+        assert_eq!(composers.len(), 1);
+        assert_eq!((composers[0].name.as_str(), composers[0].tracks, composers[0].records), ("Thelonious Monk", 2, 1));
+        assert_eq!(composers[0].spellings, vec!["MONK", "T. Monk"]);
+
+        // レコードの composers はレコード数にだけ数える
+        let records = vec![
+            ("a.json".to_string(), json!({"tracks": [{"composer": "T. Monk"}]})),
+            ("b.json".to_string(), json!({"composers": ["t. monk", "Bud Powell"], "tracks": [{"composer": ""}]})),
+        ];
+        let composers = collect_composers(&records, &index);
+        assert_eq!((composers[0].name.as_str(), composers[0].tracks, composers[0].records), ("Thelonious Monk", 1, 2));
+        assert_eq!((composers[1].name.as_str(), composers[1].tracks, composers[1].records), ("Bud Powell", 0, 1));
+    }
+
+    #[test]
+    fn shared_alias_is_a_conflict() {
+        let mut r = registry();
+        r.people.push(Person { name: "Monk Montgomery".into(), aliases: vec!["monk".into()] });
+        assert_eq!(r.conflicts(), vec!["monk"]);
+    }
+}
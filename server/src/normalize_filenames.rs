@@ -0,0 +1,210 @@
+//! ファイル名の一括整理（`POST /api/normalize-filenames`）。ファイル名の規則（`{リーダー}__{タイトル}`、`{リーダー}_{グループ略称}__{タイトル}`、
+//! Classical は設定のパターン）は途中で変わってきたので、古いレコードは今の規則に合っていない。
+//! レコードの中身から今の規則の名前（フォームのファイル名の候補と同じ `suggested_filename`）を作り、
+//! `dry_run` なら変わるものの一覧だけを、そうでなければ `POST /api/rename` と同じく名前を変える（カバー・メモ・添付・related も付いていく）。
+//! `files` を渡せば、確認画面で選んだそのレコードだけを変える。
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+use nekokan_music_core::filename::suggested_filename;
+use nekokan_music_core::types::MusicData;
+
+use crate::audit::{RecordChange, RecordChanges};
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+use crate::rename::{move_record, update_related};
+use crate::versions::Precondition;
+use crate::{display_label_from_value, load_all_records, normalize_filename, AppState};
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct NormalizeBody {
+    /// true なら変えずに一覧だけ返す
+    #[serde(default)]
+    dry_run: bool,
+    /// 変えるレコード（空ならすべて）
+    #[serde(default)]
+    files: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+struct Rename {
+    from: String,
+    to: String,
+    display_label: String,
+}
+
+/// 変えられないもの・変えないもの
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+struct Skipped {
+    filename: String,
+    /// 今の規則の名前（作れたとき）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to: Option<String>,
+    reason: String,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Plan {
+    renames: Vec<Rename>,
+    /// 今の規則の名前がもうあるか、ほかのレコードと重なるもの
+    conflicts: Vec<Skipped>,
+    /// 名前を作れないもの（規則のないジャンル、リーダー・タイトルが空など）
+    skipped: Vec<Skipped>,
+    unchanged: usize,
+}
+
+#[derive(Default, serde::Serialize)]
+struct NormalizeReport {
+    dry_run: bool,
+    /// 変える（変えた）もの
+    renames: Vec<Rename>,
+    conflicts: Vec<Skipped>,
+    skipped: Vec<Skipped>,
+    unchanged: usize,
+    /// 変えられなかったもの（ファイル名, 理由）
+    failed: Vec<(String, String)>,
+    related_updated: usize,
+    warnings: Vec<String>,
+}
+
+/// レコードを MusicData として読む（ない項目は既定値）
+fn music_data(v: &Value) -> Result<MusicData, String> {
+    let mut base = serde_json::to_value(MusicData::default()).map_err(|e| e.to_string())?;
+    if let (Some(base), Some(v)) = (base.as_object_mut(), v.as_object()) {
+        for (k, x) in v {
+            base.insert(k.clone(), x.clone());
+        }
+    }
+    serde_json::from_value(base).map_err(|e| e.to_string())
+}
+
+/// 今の規則に合わせる名前の変更を決める。`pattern` は設定の Classical のファイル名のパターン
+fn plan(records: &[(String, Value)], pattern: &str) -> Plan {
+    let existing: HashSet<&str> = records.iter().map(|(f, _)| f.as_str()).collect();
+    let mut plan = Plan::default();
+    let mut wanted: Vec<(&String, &Value, String)> = Vec::new();
+    for (filename, v) in records {
+        let data = match music_data(v) {
+            Ok(d) => d,
+            Err(e) => {
+                plan.skipped.push(Skipped { filename: filename.clone(), to: None, reason: format!("読めません: {}", e) });
+                continue;
+            }
+        };
+        let Some(to) = suggested_filename(&data, pattern).and_then(|stem| normalize_filename(&stem)) else {
+            plan.skipped.push(Skipped {
+                filename: filename.clone(),
+                to: None,
+                reason: "名前を作れません（規則のないジャンルか、リーダー・タイトルなどが空）".into(),
+            });
+            continue;
+        };
+        if &to == filename {
+            plan.unchanged += 1;
+        } else {
+            wanted.push((filename, v, to));
+        }
+    }
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (_, _, to) in &wanted {
+        *counts.entry(to.as_str()).or_default() += 1;
+    }
+    for (filename, v, to) in &wanted {
+        let reason = if existing.contains(to.as_str()) {
+            Some(format!("{} がもうあります", to))
+        } else if counts[to.as_str()] > 1 {
+            Some(format!("ほかのレコードも {} になります", to))
+        } else {
+            None
+        };
+        match reason {
+            Some(reason) => plan.conflicts.push(Skipped { filename: (*filename).clone(), to: Some(to.clone()), reason }),
+            None => plan.renames.push(Rename { from: (*filename).clone(), to: to.clone(), display_label: display_label_from_value(v) }),
+        }
+    }
+    plan
+}
+
+/// `POST /api/normalize-filenames` `{"dry_run": true}` で確認し、`{"files": [...]}`（空ならすべて）で変える
+#[utoipa::path(
+    post,
+    path = "/api/normalize-filenames",
+    tag = "maintenance",
+    summary = "ファイル名を今の規則に揃える",
+    params(LibraryQuery),
+    request_body = NormalizeBody,
+)]
+pub async fn normalize(State(state): State<AppState>, Lib(library): Lib, Json(body): Json<NormalizeBody>) -> impl IntoResponse {
+    let pattern = crate::settings::load_settings(&state.db_path).map(|s| s.classical_filename).unwrap_or_default();
+    let Some(mut records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    records.sort_by(|a, b| a.0.cmp(&b.0));
+    let Plan { mut renames, conflicts, skipped, unchanged } = plan(&records, &pattern);
+    if !body.files.is_empty() {
+        let files: HashSet<String> = body.files.iter().filter_map(|f| normalize_filename(f)).collect();
+        renames.retain(|r| files.contains(&r.from));
+    }
+    let mut report = NormalizeReport { dry_run: body.dry_run, conflicts, skipped, unchanged, ..Default::default() };
+    if body.dry_run {
+        report.renames = renames;
+        return (StatusCode::OK, Json(report)).into_response();
+    }
+    let mut done: Vec<(String, String)> = Vec::new();
+    let mut changes: Vec<RecordChange> = Vec::new();
+    for r in renames {
+        match move_record(&library, &r.from, &r.to, &Precondition::Any) {
+            Ok(moved) => {
+                report.warnings.extend(moved.warnings.into_iter().map(|w| format!("{}: {}", r.from, w)));
+                done.push((r.from.clone(), r.to.clone()));
+                changes.push(
+                    RecordChange { action: "rename", file: r.from.clone(), renamed_to: Some(r.to.clone()), ..Default::default() }
+                        .in_library(&library),
+                );
+                report.renames.push(r);
+            }
+            Err(e) => report.failed.push((r.from, e.to_string())),
+        }
+    }
+    if !done.is_empty() {
+        let (updated, warnings) = update_related(&library, &done);
+        report.related_updated = updated;
+        report.warnings.extend(warnings);
+    }
+    let mut res = (StatusCode::OK, Json(report)).into_response();
+    res.extensions_mut().insert(RecordChanges(changes));
+    res
+}
+
+#[cfg(test)]
+mod normalize_filenames_tests {
+    use super::plan;
+    use serde_json::json;
+
+    #[test]
+    fn renames_follow_the_current_rules_and_collisions_are_held_back() {
+        let jazz = |leader: &str, title: &str| {
+            json!({"title": title, "janre": {"main": "Jazz", "sub": []}, "personnel": {"leader": [{"name": leader, "instruments": "", "tracks": "all"}]}})
+        };
+        let records = vec![
+            ("Andrew_Hill__Black_Fire.json".to_string(), jazz("Andrew Hill", "Black Fire")),
+            ("andrew-hill-smoke-stack.json".to_string(), jazz("Andrew Hill", "Smoke Stack")),
+            ("old1.json".to_string(), jazz("Bill Evans", "Alone")),
+            ("old2.json".to_string(), jazz("Bill Evans", "Alone")),
+            ("hill_black_fire_copy.json".to_string(), jazz("Andrew Hill", "Black Fire")),
+            ("rock.json".to_string(), json!({"title": "x", "janre": {"main": "Rock", "sub": []}})),
+            ("broken.json".to_string(), json!({"title": 1})),
+        ];
+        let p = plan(&records, "");
+        assert_eq!(p.unchanged, 1);
+        assert_eq!(p.renames.len(), 1);
+        assert_eq!((p.renames[0].from.as_str(), p.renames[0].to.as_str()), ("andrew-hill-smoke-stack.json", "Andrew_Hill__Smoke_Stack.json"));
+        let conflicts: Vec<&str> = p.conflicts.iter().map(|c| c.filename.as_str()).collect();
+        assert_eq!(conflicts, ["old1.json", "old2.json", "hill_black_fire_copy.json"]);
+        assert_eq!(p.conflicts[2].to.as_deref(), Some("Andrew_Hill__Black_Fire.json"));
+        let skipped: Vec<&str> = p.skipped.iter().map(|c| c.filename.as_str()).collect();
+        assert_eq!(skipped, ["rock.json", "broken.json"]);
+    }
+}
@@ -0,0 +1,171 @@
+//! レコードごとのメモ（「録音日を要確認」などの TODO）。MusicData には入れず、
+//! `{ライブラリのディレクトリ}/.notes/{レコードのファイル名}` に別ファイルとして置く。
+
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::libraries::{Lib, Library, LibraryQuery};
+use crate::listen::write_json;
+use crate::normalize_filename;
+use crate::problem::error;
+use crate::stats::now_timestamp;
+
+/// メモを置くディレクトリ（ライブラリのディレクトリからの相対）
+pub const NOTES_DIR: &str = ".notes";
+
+/// メモの本文の上限（文字数）
+const NOTE_MAX_CHARS: usize = 2000;
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Note {
+    /// レコード内で一意な番号（追加順）
+    pub id: u64,
+    /// 書いた日時（RFC 3339, UTC）
+    pub created: String,
+    pub text: String,
+    /// 対応済み
+    #[serde(default)]
+    pub done: bool,
+}
+
+fn notes_path(library: &Library, filename: &str) -> PathBuf {
+    library.dir.join(NOTES_DIR).join(filename)
+}
+
+/// レコードのメモを読む。ファイルがなければ空。
+pub fn load_notes(path: &std::path::Path) -> Result<Vec<Note>, String> {
+    match fs::read_to_string(path) {
+        Ok(data) => serde_json::from_str(&data).map_err(|e| format!("invalid notes: {}", e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn save_notes(library: &Library, filename: &str, notes: &[Note]) -> Result<(), String> {
+    let path = notes_path(library, filename);
+    if notes.is_empty() {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        };
+    }
+    fs::create_dir_all(library.dir.join(NOTES_DIR)).map_err(|e| e.to_string())?;
+    let v = serde_json::to_value(notes).map_err(|e| e.to_string())?;
+    write_json(&path, &v)
+}
+
+/// パスのファイル名を正規化し、メモを読み込む。
+fn open(library: &Library, name: &str) -> Result<(String, Vec<Note>), (StatusCode, String)> {
+    let filename = normalize_filename(name).ok_or((StatusCode::BAD_REQUEST, "invalid filename".to_string()))?;
+    let notes = load_notes(&notes_path(library, &filename)).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok((filename, notes))
+}
+
+fn respond(library: &Library, filename: &str, notes: Vec<Note>) -> axum::response::Response {
+    match save_notes(library, filename, &notes) {
+        Ok(()) => (StatusCode::OK, Json(notes)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// `GET /api/notes/{name}` メモを古い順で返す。
+#[utoipa::path(get, path = "/api/notes/{name}", tag = "media", summary = "メモの一覧", params(LibraryQuery))]
+pub async fn get_notes(Lib(library): Lib, Path(name): Path<String>) -> impl IntoResponse {
+    match open(&library, &name) {
+        Ok((_, notes)) => (StatusCode::OK, Json(notes)).into_response(),
+        Err((status, msg)) => error(status, msg),
+    }
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct AddNoteBody {
+    text: String,
+}
+
+/// `POST /api/notes/{name}` メモを追加し、更新後の一覧を返す。
+#[utoipa::path(
+    post,
+    path = "/api/notes/{name}",
+    tag = "media",
+    summary = "メモを足す",
+    params(LibraryQuery),
+    request_body = AddNoteBody,
+)]
+pub async fn add_note(
+    Lib(library): Lib,
+    Path(name): Path<String>,
+    Json(body): Json<AddNoteBody>,
+) -> impl IntoResponse {
+    let text = body.text.trim();
+    if text.is_empty() {
+        return error(StatusCode::BAD_REQUEST, "text is required");
+    }
+    if text.chars().count() > NOTE_MAX_CHARS {
+        return error(StatusCode::BAD_REQUEST, format!("{}文字以内", NOTE_MAX_CHARS));
+    }
+    let (filename, mut notes) = match open(&library, &name) {
+        Ok(v) => v,
+        Err((status, msg)) => return error(status, msg),
+    };
+    if !library.storage.exists(&filename) {
+        return error(StatusCode::NOT_FOUND, "record not found");
+    }
+    notes.push(Note {
+        id: notes.iter().map(|n| n.id).max().map_or(1, |m| m + 1),
+        created: now_timestamp(),
+        text: text.to_string(),
+        done: false,
+    });
+    respond(&library, &filename, notes)
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct UpdateNoteBody {
+    done: bool,
+}
+
+/// `PATCH /api/notes/{name}/{id}` 対応済みの切り替え。
+#[utoipa::path(
+    patch,
+    path = "/api/notes/{name}/{id}",
+    tag = "media",
+    summary = "メモを直す",
+    params(LibraryQuery),
+    request_body = UpdateNoteBody,
+)]
+pub async fn update_note(
+    Lib(library): Lib,
+    Path((name, id)): Path<(String, u64)>,
+    Json(body): Json<UpdateNoteBody>,
+) -> impl IntoResponse {
+    let (filename, mut notes) = match open(&library, &name) {
+        Ok(v) => v,
+        Err((status, msg)) => return error(status, msg),
+    };
+    let Some(note) = notes.iter_mut().find(|n| n.id == id) else {
+        return error(StatusCode::NOT_FOUND, "note not found");
+    };
+    note.done = body.done;
+    respond(&library, &filename, notes)
+}
+
+/// `DELETE /api/notes/{name}/{id}`
+#[utoipa::path(delete, path = "/api/notes/{name}/{id}", tag = "media", summary = "メモを消す", params(LibraryQuery))]
+pub async fn delete_note(Lib(library): Lib, Path((name, id)): Path<(String, u64)>) -> impl IntoResponse {
+    let (filename, mut notes) = match open(&library, &name) {
+        Ok(v) => v,
+        Err((status, msg)) => return error(status, msg),
+    };
+    let before = notes.len();
+    notes.retain(|n| n.id != id);
+    if notes.len() == before {
+        return error(StatusCode::NOT_FOUND, "note not found");
+    }
+    respond(&library, &filename, notes)
+}
@@ -0,0 +1,279 @@
+//! API の一覧（OpenAPI 3.1）。スクリプトやほかのツールから呼ぶとき用に `GET /api/openapi.json` で返し、
+//! `GET /api/docs` で Swagger UI を出す。Swagger UI の JS・CSS は utoipa-swagger-ui がバイナリに埋め込んだものを出すので、
+//! 外に出られないネットワークでも見られる。
+//!
+//! 一覧は各ハンドラの `#[utoipa::path]`（メソッド・パス・要約・本文の型）と、本文・応答の型の `ToSchema` から `ApiDoc` が作る。
+//! ハンドラを足したら `ApiDoc` の `paths(...)` にも足す。main.rs のルートと食い違えばテスト（`every_route_is_documented`）が落ちる。
+//! `Lib` を取るハンドラは `params(LibraryQuery)` で `?library=` を載せる（`library_handlers_take_the_library_param`）。
+//! レコードの形（`Record`）は `nekokan_music_core::schema::RECORD_FIELDS` から作る。
+//! どの操作にも共通のもの（エラーの形・認証・パスのパラメーター・operationId）は `Conventions` で足す。
+
+use nekokan_music_core::schema::{Field, RECORD_FIELDS};
+use serde_json::{json, Map, Value};
+use utoipa::openapi::path::{Operation, Parameter, ParameterBuilder, ParameterIn};
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityRequirement, SecurityScheme};
+use utoipa::openapi::{Content, Ref, RefOr, Required, ResponseBuilder, Schema};
+use utoipa::{Modify, OpenApi, PartialSchema, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
+
+/// レコード JSON（`RECORD_FIELDS` から作る）
+pub struct Record;
+
+impl PartialSchema for Record {
+    fn schema() -> RefOr<Schema> {
+        serde_json::from_value(object_schema(RECORD_FIELDS)).expect("RECORD_FIELDS は JSON Schema にできる")
+    }
+}
+
+impl ToSchema for Record {}
+
+/// ファイルを上げる本文（multipart/form-data の `file`）
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct FileUpload {
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "nekokan_music",
+        description = "レコード JSON の一覧・編集・統計の API。トークンを作っていれば、書き込みには `Authorization: Bearer` が要る。"
+    ),
+    servers((url = "/")),
+    paths(
+        crate::list_files, crate::list_files_with_labels, crate::libraries::list, crate::live::ws,
+        crate::validate_all::validate_all, crate::policy::get_policy, crate::policy::save_policy,
+        crate::scores::rescale, crate::dates::fill_updated, crate::schema::get_schema, crate::schema::canonicalize,
+        crate::migrations::migrate, crate::save_file, crate::get_file, crate::audio::upload, crate::delete_file,
+        crate::audio::list, crate::rename::rename, crate::normalize_filenames::normalize,
+        crate::git_history::get_history, crate::stats::get_stats, crate::stats::get_listen_stats,
+        crate::search::search, crate::tunes::get_tunes, crate::personnel::get_personnel, crate::labels::get_labels,
+        crate::names::get_composers, crate::names::get_names, crate::names::save_names, crate::activity::get_activity,
+        crate::recent::get_recent, crate::random::get_random, crate::calendar::get_calendar,
+        crate::listen::append_listen, crate::listen::create_draft, crate::covers::upload_cover,
+        crate::covers::get_cover, crate::attachments::list, crate::attachments::upload, crate::attachments::get,
+        crate::attachments::delete, crate::duplicates::list, crate::duplicates::check, crate::metadata::list_providers,
+        crate::metadata::search, crate::metadata::release, crate::metadata::import_cover,
+        crate::metadata::cddb::lookup, crate::metadata::lookup, crate::discogs::export_csv, crate::discogs::import_csv,
+        crate::remote_import::import_url, crate::graph::export_graph, crate::export_zip::export_zip,
+        crate::year_review::export_year_review, crate::notes::get_notes, crate::notes::add_note,
+        crate::notes::update_note, crate::notes::delete_note, crate::goals::get_goals, crate::goals::save_goals,
+        crate::settings::get_settings, crate::settings::save_settings, crate::client_errors::report,
+        crate::validation_log::report, crate::validation_log::summary, crate::validation_rules::get_rules,
+        crate::logs::export_logs, crate::logs::import_logs, crate::audit::get_audit, crate::auth::list,
+        crate::auth::create, crate::auth::revoke, crate::auth::login, crate::auth::logout, crate::sync::get_status,
+        crate::sync::save_config, crate::sync::manifest, crate::sync::pull_now, crate::digest::get_status,
+        crate::digest::save_config, crate::digest::preview, crate::digest::send_now, crate::backup::create,
+        crate::backup::list, crate::backup::prune_snapshots, crate::backup::delete, crate::backup::restore,
+        crate::retention::get_status, crate::retention::prune_now, crate::trash::get_list, crate::trash::restore,
+        crate::genres::get_usage, crate::genres::migrate,
+    ),
+    components(schemas(crate::problem::ProblemBody)),
+    modifiers(&Conventions)
+)]
+pub struct ApiDoc;
+
+/// `RECORD_FIELDS` の型の書き方（"string[]" など）→ JSON Schema
+fn field_schema(f: &Field) -> Value {
+    let item = |kind: &str| match kind {
+        "string" => json!({"type": "string"}),
+        "number" => json!({"type": "number"}),
+        "bool" => json!({"type": "boolean"}),
+        _ => object_schema(f.fields),
+    };
+    let mut schema = match f.kind.strip_suffix("[]") {
+        Some(kind) => json!({"type": "array", "items": item(kind)}),
+        None => item(f.kind),
+    };
+    schema["description"] = Value::from(f.description);
+    schema
+}
+
+fn object_schema(fields: &[Field]) -> Value {
+    let properties: Map<String, Value> = fields.iter().map(|f| (f.name.to_string(), field_schema(f))).collect();
+    let required: Vec<&str> = fields.iter().filter(|f| !f.optional).map(|f| f.name).collect();
+    json!({"type": "object", "properties": properties, "required": required})
+}
+
+/// "/api/notes/{name}/{id}" → ["name", "id"]
+fn path_params(path: &str) -> Vec<&str> {
+    path.split('/').filter_map(|seg| seg.strip_prefix('{').and_then(|s| s.strip_suffix('}'))).collect()
+}
+
+/// どの操作にも共通のものを足す
+struct Conventions;
+
+impl Modify for Conventions {
+    fn modify(&self, api: &mut utoipa::openapi::OpenApi) {
+        api.components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme("bearer", SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)));
+        for (path, item) in api.paths.paths.iter_mut() {
+            let ops = [("get", &mut item.get), ("post", &mut item.post), ("patch", &mut item.patch), ("delete", &mut item.delete)];
+            for (method, op) in ops {
+                if let Some(op) = op {
+                    complete_operation(method, path, op);
+                }
+            }
+        }
+    }
+}
+
+/// operationId（ハンドラの名前はモジュールをまたいで重なるので、メソッドとパスから）・パスのパラメーター・
+/// 応答（書いていなければ JSON とだけ）・エラーの形・認証を足す
+fn complete_operation(method: &str, path: &str, op: &mut Operation) {
+    op.operation_id = Some(format!("{}{}", method, path.replace(['/', '{', '}', '-', '.'], "_")));
+    let parameters = op.parameters.get_or_insert_with(Vec::new);
+    let missing: Vec<Parameter> = path_params(path)
+        .into_iter()
+        .filter(|name| !parameters.iter().any(|p| p.name == *name && p.parameter_in == ParameterIn::Path))
+        .map(|name| {
+            ParameterBuilder::new()
+                .name(name)
+                .parameter_in(ParameterIn::Path)
+                .required(Required::True)
+                .schema(Some(String::schema()))
+                .build()
+        })
+        .collect();
+    parameters.splice(0..0, missing);
+    let responses = &mut op.responses.responses;
+    if responses.is_empty() {
+        let ok = ResponseBuilder::new().description("OK").content("application/json", Content::new(Some(Value::schema())));
+        responses.insert("200".into(), ok.build().into());
+    }
+    let problem = Content::new(Some(Ref::from_schema_name("Error")));
+    responses.insert("default".into(), ResponseBuilder::new().description("エラー").content(crate::problem::CONTENT_TYPE, problem).build().into());
+    let method = method.to_uppercase().parse().unwrap_or(axum::http::Method::GET);
+    // 認証が要るかどうかはパスの中身によらないので、パラメーターは仮の値で確かめる
+    if crate::auth::needs_auth(&method, &path.replace(['{', '}'], "")) {
+        op.security = Some(vec![SecurityRequirement::new("bearer", Vec::<String>::new())]);
+    }
+}
+
+/// `GET /api/openapi.json` と `GET /api/docs`（Swagger UI）
+pub fn swagger_ui() -> SwaggerUi {
+    SwaggerUi::new("/api/docs").url("/api/openapi.json", ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod openapi_tests {
+    use super::ApiDoc;
+    use serde_json::Value;
+    use utoipa::OpenApi;
+
+    fn spec() -> Value {
+        serde_json::to_value(ApiDoc::openapi()).unwrap()
+    }
+
+    /// main.rs の `.route("パス", get(..).post(..))` からパスとメソッドを拾う
+    fn routes_in_main() -> Vec<(String, String)> {
+        let src = include_str!("main.rs");
+        let mut out = Vec::new();
+        let mut rest = src;
+        while let Some(i) = rest.find(".route(") {
+            rest = &rest[i + ".route(".len()..];
+            let mut depth = 1;
+            let end = rest
+                .char_indices()
+                .find(|&(_, c)| {
+                    depth += match c {
+                        '(' => 1,
+                        ')' => -1,
+                        _ => 0,
+                    };
+                    depth == 0
+                })
+                .map_or(rest.len(), |(i, _)| i);
+            let call = &rest[..end];
+            let path = call.split('"').nth(1).unwrap_or_default().to_string();
+            for method in ["get", "post", "patch", "delete"] {
+                let pat = format!("{}(", method);
+                let found = call.match_indices(&pat).any(|(i, _)| {
+                    !call[..i].ends_with(|c: char| c.is_alphanumeric() || c == '_')
+                });
+                if found {
+                    out.push((method.to_string(), path.clone()));
+                }
+            }
+        }
+        out
+    }
+
+    /// "/api/notes/:name/:id" → "/api/notes/{name}/{id}"
+    fn openapi_path(path: &str) -> String {
+        path.split('/')
+            .map(|seg| match seg.strip_prefix(':').or_else(|| seg.strip_prefix('*')) {
+                Some(name) => format!("{{{}}}", name),
+                None => seg.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    #[test]
+    fn every_route_is_documented() {
+        let s = spec();
+        let routes = routes_in_main();
+        assert!(routes.len() > 60);
+        for (method, path) in &routes {
+            assert!(s["paths"][openapi_path(path)][method].is_object(), "{} {} is not in ApiDoc", method, path);
+        }
+        for (path, item) in s["paths"].as_object().unwrap() {
+            for method in item.as_object().unwrap().keys() {
+                assert!(routes.iter().any(|(m, p)| m == method && openapi_path(p) == *path), "{} {} is not routed", method, path);
+            }
+        }
+    }
+
+    /// `Lib` を取るハンドラの `#[utoipa::path]` には `params(LibraryQuery)` がある
+    #[test]
+    fn library_handlers_take_the_library_param() {
+        fn sources(dir: &std::path::Path, out: &mut Vec<(String, String)>) {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    sources(&path, out);
+                } else if path.extension().is_some_and(|e| e == "rs") {
+                    out.push((path.display().to_string(), std::fs::read_to_string(&path).unwrap()));
+                }
+            }
+        }
+        let mut files = Vec::new();
+        sources(&std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src"), &mut files);
+        let mut checked = 0;
+        for (file, src) in &files {
+            for (i, _) in src.match_indices("\n#[utoipa::path(") {
+                let rest = &src[i..];
+                let attr_end = rest.find(")]\n").unwrap();
+                let sig_start = rest.find("async fn ").unwrap();
+                let sig = &rest[sig_start..sig_start + rest[sig_start..].find('{').unwrap()];
+                if sig.contains("Lib(") {
+                    assert!(rest[..attr_end].contains("LibraryQuery"), "{}: {} has no params(LibraryQuery)", file, sig.lines().next().unwrap());
+                    checked += 1;
+                }
+            }
+        }
+        assert!(checked > 40, "{}", checked);
+    }
+
+    #[test]
+    fn record_schema_errors_and_auth() {
+        let s = spec();
+        let record = &s["components"]["schemas"]["Record"];
+        assert_eq!(record["properties"]["tracks"]["type"], "array");
+        assert_eq!(record["properties"]["janre"]["properties"]["sub"]["items"]["type"], "string");
+        assert!(record["required"].as_array().unwrap().iter().any(|r| r == "title"));
+        assert_eq!(s["components"]["schemas"]["SaveBody"]["properties"]["data"]["$ref"], "#/components/schemas/Record");
+        assert!(s["components"]["schemas"]["Error"]["properties"]["code"].is_object());
+        assert!(s["paths"]["/api/save"]["post"]["security"].is_array());
+        assert!(s["paths"]["/api/list"]["get"]["security"].is_null());
+        let get_file = &s["paths"]["/api/files/{path}"]["get"];
+        assert_eq!((&get_file["parameters"][0]["name"], &get_file["parameters"][1]["name"]), (&"path".into(), &"library".into()));
+        assert_eq!(get_file["operationId"], "get_api_files__path_");
+        assert_eq!(get_file["responses"]["default"]["content"]["application/problem+json"]["schema"]["$ref"], "#/components/schemas/Error");
+        assert_eq!(s["paths"]["/api/rename"]["post"]["requestBody"]["content"]["application/json"]["schema"]["$ref"], "#/components/schemas/RenameBody");
+    }
+}
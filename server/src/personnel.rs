@@ -0,0 +1,167 @@
+//! 人ごとのディスコグラフィ（`GET /api/personnel/:name`）。その人がリーダー・サイドメン・ソリスト・指揮者・グループのメンバーとして
+//! 参加したレコードを、役割・楽器・参加トラックつきで発売年順に並べる。名前は大文字小文字を区別せず、names.rs の台帳の別名と
+//! `name_alt`（かな名のローマ字など）でも引ける。
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::names::NameIndex;
+use crate::problem::{error, field_error};
+use crate::{display_label_from_value, int_from_value, AppState};
+
+/// 個人の役割（personnel の欄, 返す名前, 楽器の欄）
+const ROLES: [(&str, &str, &str); 4] =
+    [("leader", "leader", "instruments"), ("sidemen", "sideman", "instruments"), ("soloists", "soloist", "instrument"), ("conductor", "conductor", "")];
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct Credit {
+    /// "leader" / "sideman" / "soloist" / "conductor" / "group_member"
+    role: &'static str,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    instruments: String,
+    /// 参加したトラック（"all"、"1,3-5" など）
+    #[serde(skip_serializing_if = "String::is_empty")]
+    tracks: String,
+    /// グループのメンバーのとき、そのグループ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct Album {
+    filename: String,
+    display_label: String,
+    release_year: Option<i64>,
+    credits: Vec<Credit>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct Discography {
+    /// 台帳の正規名（なければ表記を整えたもの）
+    name: String,
+    /// 役割ごとのレコード数
+    roles: BTreeMap<&'static str, usize>,
+    /// 発売年順（不明は最後）
+    albums: Vec<Album>,
+}
+
+fn text(v: &Value) -> String {
+    v.as_str().unwrap_or("").trim().to_string()
+}
+
+/// 1 枚のレコードでのその人の参加
+fn credits_of(v: &Value, is_target: &dyn Fn(&Value) -> bool) -> Vec<Credit> {
+    let personnel = &v["personnel"];
+    let mut credits = Vec::new();
+    for (key, role, instruments) in ROLES {
+        for e in personnel[key].as_array().into_iter().flatten().filter(|e| is_target(e)) {
+            let instruments = if instruments.is_empty() { String::new() } else { text(&e[instruments]) };
+            credits.push(Credit { role, instruments, tracks: text(&e["tracks"]), group: None });
+        }
+    }
+    for g in personnel["group"].as_array().into_iter().flatten() {
+        for m in g["members"].as_array().into_iter().flatten().filter(|m| is_target(m)) {
+            let group = Some(text(&g["name"])).filter(|n| !n.is_empty());
+            let role = if m["leader"].as_bool() == Some(true) { "leader" } else { "group_member" };
+            credits.push(Credit { role, instruments: text(&m["instruments"]), tracks: text(&m["tracks"]), group });
+        }
+    }
+    credits
+}
+
+fn discography(records: &[(String, &Value)], name: &str, index: &NameIndex) -> Discography {
+    let key = |s: &str| index.canonical(s).to_lowercase();
+    let target = key(name);
+    let is_target = |e: &Value| {
+        [&e["name"], &e["name_alt"]].into_iter().filter_map(Value::as_str).any(|n| !n.trim().is_empty() && key(n) == target)
+    };
+    let mut roles: BTreeMap<&'static str, usize> = BTreeMap::new();
+    let mut albums: Vec<Album> = Vec::new();
+    for (filename, v) in records {
+        let credits = credits_of(v, &is_target);
+        if credits.is_empty() {
+            continue;
+        }
+        let mut seen: Vec<&str> = credits.iter().map(|c| c.role).collect();
+        seen.sort_unstable();
+        seen.dedup();
+        for role in seen {
+            *roles.entry(role).or_default() += 1;
+        }
+        albums.push(Album {
+            filename: filename.clone(),
+            display_label: display_label_from_value(v),
+            release_year: int_from_value(&v["release_year"]).filter(|y| *y > 0),
+            credits,
+        });
+    }
+    albums.sort_by(|a, b| {
+        (a.release_year.is_none(), a.release_year, &a.display_label).cmp(&(b.release_year.is_none(), b.release_year, &b.display_label))
+    });
+    Discography { name: index.canonical(name), roles, albums }
+}
+
+/// `GET /api/personnel/:name`
+#[utoipa::path(get, path = "/api/personnel/{name}", tag = "reports", summary = "人ごとのディスコグラフィ（役割・楽器つき）", params(LibraryQuery))]
+pub async fn get_personnel(State(state): State<AppState>, Lib(library): Lib, Path(name): Path<String>) -> impl IntoResponse {
+    if name.trim().is_empty() {
+        return field_error("name", "is required");
+    }
+    let index = match crate::names::load_names(&state.db_path) {
+        Ok(r) => r.index(),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    let records = match library.records.records(&*library.storage) {
+        Ok(records) => records,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    let values: Vec<(String, &Value)> = records.iter().map(|(name, r)| (name.clone(), &r.value)).collect();
+    (StatusCode::OK, Json(discography(&values, &name, &index))).into_response()
+}
+
+#[cfg(test)]
+mod personnel_tests {
+    use super::discography;
+    use crate::names::{NameRegistry, Person};
+    use serde_json::json;
+
+    #[test]
+    fn every_role_is_found_in_year_order() {
+        let moanin = json!({"title": "Moanin'", "release_year": 1959, "personnel": {
+            "leader": [{"name": "Art Blakey", "instruments": "Drums", "tracks": "all"}],
+            "group": [{"name": "The Jazz Messengers", "members": [{"name": "Lee Morgan", "instruments": "Trumpet", "tracks": "all"}]}]
+        }});
+        let sidewinder = json!({"title": "The Sidewinder", "release_year": "1964", "personnel": {
+            "leader": [{"name": "LEE MORGAN", "instruments": "Trumpet", "tracks": "all"}]
+        }});
+        let candy = json!({"title": "Candy", "personnel": {
+            "sidemen": [{"name": "L. Morgan", "instruments": "Flugelhorn", "tracks": "2,4"}]
+        }});
+        let other = json!({"title": "X", "personnel": {"leader": [{"name": "Lee Konitz"}]}});
+        let records = vec![
+            ("candy.json".to_string(), &candy),
+            ("moanin.json".to_string(), &moanin),
+            ("other.json".to_string(), &other),
+            ("sidewinder.json".to_string(), &sidewinder),
+        ];
+        let names = NameRegistry { people: vec![Person { name: "Lee Morgan".into(), aliases: vec!["L. Morgan".into()] }] };
+        let d = discography(&records, "lee morgan", &names.index());
+        assert_eq!(d.name, "Lee Morgan");
+        let files: Vec<&str> = d.albums.iter().map(|a| a.filename.as_str()).collect();
+        assert_eq!(files, ["moanin.json", "sidewinder.json", "candy.json"]);
+        let c = &d.albums[0].credits[0];
+        assert_eq!((c.role, c.instruments.as_str(), c.group.as_deref()), ("group_member", "Trumpet", Some("The Jazz Messengers")));
+        assert_eq!(d.albums[2].credits[0].role, "sideman");
+        assert_eq!(d.albums[2].credits[0].tracks, "2,4");
+        assert_eq!(d.roles.get("leader"), Some(&1));
+        assert_eq!(d.roles.get("sideman"), Some(&1));
+        assert_eq!(d.roles.get("group_member"), Some(&1));
+    }
+}
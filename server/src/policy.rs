@@ -0,0 +1,70 @@
+//! 必須項目のポリシー。`{DB_PATH}/.config/validation-policy.json` に置き、フロントの入力チェックと
+//! `/api/validate-all` の両方が共有の validation.rs でこれに従う。
+//!
+//! 例（バッジ 🏴 を付けたブートレグはレーベル・品番なしを許し、スコア 6 のレコードには参照を必須にする）:
+//! `{"rules": [{"field": "label", "required": false, "badge": "🏴"}, {"field": "id", "required": false, "badge": "🏴"},
+//!   {"field": "references", "required": true, "min_score": 6}]}`
+//! `main` を付けるとそのジャンルだけに効く。同じ項目に当てはまるルールが複数あれば後のものが勝つ。
+//! スコアの尺度も `"score": {"min": 0.5, "max": 5, "step": 0.5}` のようにここに置く（既存レコードの変換は scores.rs）。
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use std::fs;
+use std::path::Path;
+
+use crate::listen::write_json;
+use crate::problem::error;
+use crate::settings::{config_path, CONFIG_DIR};
+use crate::validation::{ValidationPolicy, POLICY_FIELDS};
+use crate::AppState;
+
+const POLICY_FILE: &str = "validation-policy.json";
+
+/// ポリシーを読む。ファイルがなければ既定（rules.rs の FIELD_RULES どおり、スコアは 1〜6）。
+pub fn load_policy(db_path: &Path) -> Result<ValidationPolicy, String> {
+    match fs::read_to_string(config_path(db_path, POLICY_FILE)) {
+        Ok(data) => serde_json::from_str(&data).map_err(|e| format!("invalid validation policy: {}", e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ValidationPolicy::default()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// ポリシーを書き込む（スコアの尺度を変換したときにも使う）。
+pub fn write_policy(db_path: &Path, policy: &ValidationPolicy) -> Result<(), String> {
+    fs::create_dir_all(db_path.join(CONFIG_DIR)).map_err(|e| e.to_string())?;
+    let v = serde_json::to_value(policy).map_err(|e| e.to_string())?;
+    write_json(&config_path(db_path, POLICY_FILE), &v)
+}
+
+/// `GET /api/validation-policy`
+#[utoipa::path(get, path = "/api/validation-policy", tag = "schema", summary = "入力チェックのポリシー")]
+pub async fn get_policy(State(state): State<AppState>) -> impl IntoResponse {
+    match load_policy(&state.db_path) {
+        Ok(p) => (StatusCode::OK, Json(p)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// `POST /api/validation-policy` ポリシーを置き換える。切り替えられない項目を指すルールは 400。
+#[utoipa::path(
+    post,
+    path = "/api/validation-policy",
+    tag = "schema",
+    summary = "入力チェックのポリシーを保存する",
+    request_body = serde_json::Value,
+)]
+pub async fn save_policy(State(state): State<AppState>, Json(policy): Json<ValidationPolicy>) -> impl IntoResponse {
+    if !policy.score.is_valid() {
+        return error(StatusCode::BAD_REQUEST, "score scale needs min < max, step > 0 and at most 100 steps");
+    }
+    let unknown = policy.unknown_fields();
+    if !unknown.is_empty() {
+        return error(
+            StatusCode::BAD_REQUEST,
+            format!("unknown field: {} (allowed: {})", unknown.join(", "), POLICY_FIELDS.join(", ")),
+        );
+    }
+    match write_policy(&state.db_path, &policy) {
+        Ok(()) => (StatusCode::OK, Json(policy)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
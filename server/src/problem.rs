@@ -0,0 +1,227 @@
+//! API のエラーの形（RFC 9457 の `application/problem+json`）。どのハンドラも失敗したときは
+//! `{"type": "about:blank", "title": "Bad Request", "status": 400, "code": "bad_request", "message": "date must be YYYY/MM/DD",
+//! "errors": [{"field": "date", "message": "YYYY/MM/DD で入力してください"}]}` を返す。`code` は機械向けの種類（既定は状態コードから）、
+//! `message` は人向けの説明、`errors` は入力のどの項目が悪いか（あるときだけ）。
+//! ハンドラを通らないエラー（JSON の本文が読めない・ルートがない・本文が大きすぎるなど）も `envelope` で同じ形に包む。
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::{Map, Value};
+
+pub const CONTENT_TYPE: &str = "application/problem+json";
+
+/// 包み直すときに読むエラーの本文の上限
+const MAX_ERROR_BODY: usize = 64 * 1024;
+
+/// 入力の 1 項目のエラー
+#[derive(Clone, Debug, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// エラーの本文の形（OpenAPI の `Error`。実際の本文は `Problem::to_value` が作る）
+#[derive(utoipa::ToSchema)]
+#[schema(as = Error)]
+#[allow(dead_code)]
+pub struct ProblemBody {
+    /// いつも "about:blank"
+    r#type: String,
+    title: String,
+    status: u16,
+    /// 機械向けの種類（not_found, version_conflict など）
+    code: String,
+    /// 人向けの説明
+    message: String,
+    /// 入力のどの項目が悪いか（あるときだけ）
+    errors: Option<Vec<FieldError>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Problem {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+    errors: Vec<FieldError>,
+    /// `retry_after` や `current_version` など、エラーごとの追加の項目
+    extra: Map<String, Value>,
+}
+
+/// 状態コードからの既定の `code`
+pub fn code_for(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "bad_request",
+        StatusCode::UNAUTHORIZED => "unauthorized",
+        StatusCode::FORBIDDEN => "forbidden",
+        StatusCode::NOT_FOUND => "not_found",
+        StatusCode::METHOD_NOT_ALLOWED => "method_not_allowed",
+        StatusCode::CONFLICT => "conflict",
+        StatusCode::PRECONDITION_FAILED => "precondition_failed",
+        StatusCode::PAYLOAD_TOO_LARGE => "payload_too_large",
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => "unsupported_media_type",
+        StatusCode::RANGE_NOT_SATISFIABLE => "range_not_satisfiable",
+        StatusCode::PRECONDITION_REQUIRED => "precondition_required",
+        StatusCode::UNPROCESSABLE_ENTITY => "unprocessable",
+        StatusCode::TOO_MANY_REQUESTS => "rate_limited",
+        StatusCode::BAD_GATEWAY => "bad_gateway",
+        StatusCode::SERVICE_UNAVAILABLE => "unavailable",
+        StatusCode::GATEWAY_TIMEOUT => "timeout",
+        s if s.is_server_error() => "internal",
+        _ => "error",
+    }
+}
+
+impl Problem {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Problem { status, code: code_for(status), message: message.into(), errors: Vec::new(), extra: Map::new() }
+    }
+
+    /// 既定（状態コードから）と違う `code` にする
+    pub fn code(mut self, code: &'static str) -> Self {
+        self.code = code;
+        self
+    }
+
+    /// 入力の項目のエラーを足す
+    pub fn field(mut self, field: impl Into<String>, message: impl Into<String>) -> Self {
+        self.errors.push(FieldError { field: field.into(), message: message.into() });
+        self
+    }
+
+    /// エラーごとの追加の項目を足す
+    pub fn with(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.extra.insert(key.to_string(), value.into());
+        self
+    }
+
+    pub fn to_value(&self) -> Value {
+        let mut v = Map::new();
+        v.insert("type".into(), "about:blank".into());
+        v.insert("title".into(), self.status.canonical_reason().unwrap_or("Error").into());
+        v.insert("status".into(), self.status.as_u16().into());
+        v.insert("code".into(), self.code.into());
+        v.insert("message".into(), self.message.clone().into());
+        if !self.errors.is_empty() {
+            v.insert("errors".into(), serde_json::to_value(&self.errors).unwrap_or_default());
+        }
+        for (k, x) in &self.extra {
+            v.entry(k.clone()).or_insert_with(|| x.clone());
+        }
+        Value::Object(v)
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        (self.status, [(header::CONTENT_TYPE, CONTENT_TYPE)], self.to_value().to_string()).into_response()
+    }
+}
+
+/// `(status, message)` のエラーの応答
+pub fn error(status: StatusCode, msg: impl Into<String>) -> Response {
+    Problem::new(status, msg).into_response()
+}
+
+/// 入力の 1 項目が悪いときの 400
+pub fn field_error(field: &str, msg: impl Into<String>) -> Response {
+    let msg = msg.into();
+    Problem::new(StatusCode::BAD_REQUEST, format!("{}: {}", field, msg)).field(field, msg).into_response()
+}
+
+/// ほかの nekokan_music（同期の相手・取り込み元）のエラーの本文の説明。古い版のサーバーの `{"error": ...}` も読む
+pub fn message_of(body: &Value) -> &str {
+    body["message"].as_str().or_else(|| body["error"].as_str()).unwrap_or("")
+}
+
+/// axum が本文を読めなかったときの文（"Failed to deserialize the JSON body into the target type: missing field `filename` at line 1 column 2"）
+/// から項目名を拾う
+fn field_of_rejection(text: &str) -> Option<&str> {
+    let rest = &text[text.find(" field `")? + " field `".len()..];
+    Some(&rest[..rest.find('`')?])
+}
+
+/// ハンドラの外で作られたエラーの本文を同じ形にする。JSON で `message` と `code` があればそのまま、
+/// 古い形の `{"error": ...}` ならその文、それ以外は本文の文字列（空なら状態の名前）を `message` にする
+fn wrap(status: StatusCode, body: &[u8]) -> Value {
+    let parsed: Option<Map<String, Value>> = serde_json::from_slice(body).ok();
+    if let Some(obj) = &parsed {
+        if obj.get("code").is_some_and(Value::is_string) && obj.get("message").is_some_and(Value::is_string) {
+            return Value::Object(obj.clone());
+        }
+        if let Some(e) = obj.get("error").and_then(Value::as_str) {
+            let mut p = Problem::new(status, e);
+            for (k, v) in obj.iter().filter(|(k, _)| *k != "error") {
+                p = p.with(k, v.clone());
+            }
+            return p.to_value();
+        }
+    }
+    let text = String::from_utf8_lossy(body).trim().to_string();
+    if text.is_empty() {
+        return Problem::new(status, status.canonical_reason().unwrap_or("Error")).to_value();
+    }
+    let mut p = Problem::new(status, text.clone());
+    if let Some(field) = field_of_rejection(&text) {
+        p = p.code("invalid_body").field(field, text.clone());
+    }
+    p.to_value()
+}
+
+/// `/api/` のエラーの応答を problem+json にそろえる。ハンドラが `Problem` で返したものはそのまま通す
+pub async fn envelope(req: Request, next: Next) -> Response {
+    if !req.uri().path().starts_with("/api/") {
+        return next.run(req).await;
+    }
+    let res = next.run(req).await;
+    let status = res.status();
+    let is_problem = res.headers().get(header::CONTENT_TYPE).is_some_and(|v| v.as_bytes().starts_with(CONTENT_TYPE.as_bytes()));
+    if !(status.is_client_error() || status.is_server_error()) || is_problem {
+        return res;
+    }
+    let (mut parts, body) = res.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_ERROR_BODY).await.unwrap_or_default();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE));
+    Response::from_parts(parts, Body::from(wrap(status, &bytes).to_string()))
+}
+
+#[cfg(test)]
+mod problem_tests {
+    use super::{wrap, Problem};
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    #[test]
+    fn problems_carry_code_message_and_fields() {
+        let p = Problem::new(StatusCode::BAD_REQUEST, "utc_offset: must be like +09:00").field("utc_offset", "must be like +09:00");
+        assert_eq!(
+            p.to_value(),
+            json!({"type": "about:blank", "title": "Bad Request", "status": 400, "code": "bad_request",
+                   "message": "utc_offset: must be like +09:00", "errors": [{"field": "utc_offset", "message": "must be like +09:00"}]})
+        );
+        let conflict = Problem::new(StatusCode::CONFLICT, "changed").code("version_conflict").with("current_version", "abc");
+        assert_eq!(conflict.to_value()["code"], "version_conflict");
+        assert_eq!(conflict.to_value()["current_version"], "abc");
+        assert!(conflict.to_value().get("errors").is_none());
+    }
+
+    #[test]
+    fn foreign_errors_are_wrapped() {
+        let legacy = wrap(StatusCode::NOT_FOUND, br#"{"error": "file not found: a.json"}"#);
+        assert_eq!((legacy["code"].as_str(), legacy["message"].as_str()), (Some("not_found"), Some("file not found: a.json")));
+        let rejection = wrap(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            b"Failed to deserialize the JSON body into the target type: missing field `filename` at line 1 column 2",
+        );
+        assert_eq!(rejection["code"], "invalid_body");
+        assert_eq!(rejection["errors"][0]["field"], "filename");
+        assert_eq!(wrap(StatusCode::METHOD_NOT_ALLOWED, b"")["message"], "Method Not Allowed");
+        let already = Problem::new(StatusCode::CONFLICT, "x").to_value();
+        assert_eq!(wrap(StatusCode::CONFLICT, already.to_string().as_bytes()), already);
+    }
+}
@@ -0,0 +1,87 @@
+//! 何を聴くか決める（`GET /api/random`）。手持ちのレコードから 1 枚をでたらめに選び、ファイル名と表示ラベルを返す。
+//! 絞り込みはサイドバーの一覧と同じ（`?janre=Jazz&score_min=5` など、list_filter.rs）。下書きは選ばない。
+
+use axum::{extract::Query, http::StatusCode, response::IntoResponse, Json};
+use serde_json::Value;
+
+use crate::display_label_from_value;
+use crate::libraries::{Lib, LibraryQuery};
+use crate::list_filter::ListFilter;
+use crate::problem::error;
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct RandomPick {
+    filename: String,
+    display_label: String,
+    /// 条件に合ったレコードの数
+    candidates: usize,
+}
+
+/// 選べるレコード（下書きでなく、条件に合うもの）から `r` で 1 つ選ぶ
+fn pick<'a>(records: &'a [(String, &'a Value)], filter: &ListFilter, r: u64) -> Option<(&'a str, &'a Value, usize)> {
+    let candidates: Vec<&(String, &Value)> =
+        records.iter().filter(|(_, v)| v["draft"].as_bool() != Some(true) && filter.matches(v)).collect();
+    let (filename, v) = candidates.get((r % candidates.len().max(1) as u64) as usize)?;
+    Some((filename.as_str(), *v, candidates.len()))
+}
+
+fn random_u64() -> u64 {
+    let mut buf = [0u8; 8];
+    // 乱数が取れなければ時刻で代える（選ぶだけなので偏っても困らない）
+    if getrandom::getrandom(&mut buf).is_err() {
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+        return nanos as u64;
+    }
+    u64::from_le_bytes(buf)
+}
+
+/// `GET /api/random?janre=Jazz&score_min=5`
+#[utoipa::path(
+    get,
+    path = "/api/random",
+    tag = "records",
+    summary = "でたらめに 1 枚選ぶ（?janre=&score_min= など一覧と同じ絞り込み）",
+    params(LibraryQuery),
+)]
+pub async fn get_random(Lib(library): Lib, Query(filter): Query<ListFilter>) -> impl IntoResponse {
+    let records = match library.records.records(&*library.storage) {
+        Ok(records) => records,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    let values: Vec<(String, &Value)> = records.iter().map(|(name, r)| (name.clone(), &r.value)).collect();
+    match pick(&values, &filter, random_u64()) {
+        Some((filename, v, candidates)) => Json(RandomPick {
+            filename: filename.to_string(),
+            display_label: display_label_from_value(v),
+            candidates,
+        })
+        .into_response(),
+        None => error(StatusCode::NOT_FOUND, "条件に合うレコードがありません"),
+    }
+}
+
+#[cfg(test)]
+mod random_tests {
+    use super::pick;
+    use crate::list_filter::ListFilter;
+    use serde_json::json;
+
+    #[test]
+    fn picks_only_matching_non_drafts() {
+        let a = json!({"title": "A", "janre": {"main": "Jazz", "sub": []}, "score": 5});
+        let b = json!({"title": "B", "janre": {"main": "Jazz", "sub": []}, "score": 3});
+        let c = json!({"title": "C", "janre": {"main": "Jazz", "sub": []}, "score": 6, "draft": true});
+        let d = json!({"title": "D", "janre": {"main": "Classical", "sub": []}, "score": 6});
+        let records = vec![("a.json".to_string(), &a), ("b.json".to_string(), &b), ("c.json".to_string(), &c), ("d.json".to_string(), &d)];
+        let filter: ListFilter = serde_json::from_value(json!({"janre": "jazz", "score_min": 4.0})).unwrap();
+        for r in 0..5 {
+            let (name, _, n) = pick(&records, &filter, r).unwrap();
+            assert_eq!((name, n), ("a.json", 1));
+        }
+        let all = ListFilter::default();
+        let names: Vec<&str> = (0..3).map(|r| pick(&records, &all, r).unwrap().0).collect();
+        assert_eq!(names, ["a.json", "b.json", "d.json"]);
+        let none: ListFilter = serde_json::from_value(json!({"janre": "Rock"})).unwrap();
+        assert_eq!(pick(&records, &none, 7), None);
+    }
+}
@@ -0,0 +1,173 @@
+//! IP ごとの回数制限（公開するインスタンス向け）。書き込み（GET 以外）と検索だけに掛け、超えたら 429 と Retry-After を返す。
+//! 1 分あたりの回数は `RATE_LIMIT_WRITE` / `RATE_LIMIT_SEARCH` で変えられ、0 なら制限しない。
+//! リバースプロキシの後ろに置くなら `TRUST_PROXY=1` で X-Forwarded-For の最後（そのプロキシが足したもの）を使う（直接公開するときは付けないこと）。
+//! 先頭のほうはクライアントが好きに書けるので見ない。
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::problem::Problem;
+use crate::AppState;
+
+/// 既定の 1 分あたりの回数
+const DEFAULT_WRITE_PER_MIN: u32 = 60;
+const DEFAULT_SEARCH_PER_MIN: u32 = 30;
+/// これだけ使われていない IP の記録は捨てる
+const IDLE: Duration = Duration::from_secs(10 * 60);
+/// 記録がこれより増えたら古いものを掃除する
+const PRUNE_AT: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Class {
+    Write,
+    Search,
+}
+
+fn class_of(method: &Method, path: &str) -> Option<Class> {
+    if !path.starts_with("/api/") {
+        return None;
+    }
+    if !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        Some(Class::Write)
+    } else if path == "/api/search" {
+        Some(Class::Search)
+    } else {
+        None
+    }
+}
+
+/// 残り回数（トークンバケット。1 分あたり `per_min` 回ずつ戻る）
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    write_per_min: u32,
+    search_per_min: u32,
+    trust_proxy: bool,
+    buckets: Mutex<HashMap<(IpAddr, Class), Bucket>>,
+}
+
+fn env_per_min(name: &str, default: u32) -> u32 {
+    match std::env::var(name) {
+        Ok(v) if !v.trim().is_empty() => v.trim().parse().unwrap_or_else(|_| panic!("{} must be a number (per minute)", name)),
+        _ => default,
+    }
+}
+
+impl RateLimiter {
+    pub fn new(write_per_min: u32, search_per_min: u32, trust_proxy: bool) -> Self {
+        Self { write_per_min, search_per_min, trust_proxy, buckets: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(
+            env_per_min("RATE_LIMIT_WRITE", DEFAULT_WRITE_PER_MIN),
+            env_per_min("RATE_LIMIT_SEARCH", DEFAULT_SEARCH_PER_MIN),
+            matches!(std::env::var("TRUST_PROXY").as_deref(), Ok("1") | Ok("true")),
+        )
+    }
+
+    fn per_min(&self, class: Class) -> u32 {
+        match class {
+            Class::Write => self.write_per_min,
+            Class::Search => self.search_per_min,
+        }
+    }
+
+    /// 1 回使う。使えなければ次に使えるまでの秒数。
+    fn take(&self, ip: IpAddr, class: Class, now: Instant) -> Result<(), u64> {
+        let per_min = self.per_min(class);
+        if per_min == 0 {
+            return Ok(());
+        }
+        let capacity = f64::from(per_min);
+        let per_sec = capacity / 60.0;
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= PRUNE_AT {
+            buckets.retain(|_, b| now.duration_since(b.last) < IDLE);
+        }
+        let b = buckets.entry((ip, class)).or_insert(Bucket { tokens: capacity, last: now });
+        b.tokens = (b.tokens + now.duration_since(b.last).as_secs_f64() * per_sec).min(capacity);
+        b.last = now;
+        if b.tokens >= 1.0 {
+            b.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - b.tokens) / per_sec).ceil().max(1.0) as u64)
+        }
+    }
+
+    fn client_ip(&self, headers: &HeaderMap, peer: Option<IpAddr>) -> Option<IpAddr> {
+        let forwarded = || {
+            headers
+                .get("x-forwarded-for")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.rsplit(',').next())
+                .and_then(|ip| ip.trim().parse().ok())
+        };
+        self.trust_proxy.then(forwarded).flatten().or(peer)
+    }
+}
+
+/// 回数制限のミドルウェア
+pub async fn limit(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    if let Some(class) = class_of(req.method(), req.uri().path()) {
+        let peer = req.extensions().get::<ConnectInfo<SocketAddr>>().map(|c| c.0.ip());
+        if let Some(ip) = state.rate_limit.client_ip(req.headers(), peer) {
+            if let Err(wait) = state.rate_limit.take(ip, class, Instant::now()) {
+                let message = format!("リクエストが多すぎます。{} 秒ほど待ってからもう一度試してください", wait);
+                let problem = Problem::new(StatusCode::TOO_MANY_REQUESTS, message).with("retry_after", wait);
+                return ([(header::RETRY_AFTER, wait.to_string())], problem).into_response();
+            }
+        }
+    }
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod rate_limit_tests {
+    use super::{class_of, Class, RateLimiter};
+    use axum::http::{HeaderMap, Method};
+    use std::net::IpAddr;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn buckets_refill_per_ip() {
+        let limiter = RateLimiter::new(2, 0, false);
+        let (a, b): (IpAddr, IpAddr) = ("192.0.2.1".parse().unwrap(), "192.0.2.2".parse().unwrap());
+        let now = Instant::now();
+        assert!(limiter.take(a, Class::Write, now).is_ok());
+        assert!(limiter.take(a, Class::Write, now).is_ok());
+        assert_eq!(limiter.take(a, Class::Write, now), Err(30));
+        assert!(limiter.take(b, Class::Write, now).is_ok());
+        assert!(limiter.take(a, Class::Write, now + Duration::from_secs(30)).is_ok());
+        // 0 は制限なし
+        assert!((0..100).all(|_| limiter.take(a, Class::Search, now).is_ok()));
+    }
+
+    #[test]
+    fn only_writes_and_search_are_limited() {
+        assert_eq!(class_of(&Method::POST, "/api/save"), Some(Class::Write));
+        assert_eq!(class_of(&Method::GET, "/api/search"), Some(Class::Search));
+        assert_eq!(class_of(&Method::GET, "/api/list-with-labels"), None);
+        assert_eq!(class_of(&Method::GET, "/index.html"), None);
+
+        let mut h = HeaderMap::new();
+        h.insert("x-forwarded-for", "10.9.9.9, 203.0.113.7".parse().unwrap());
+        let peer: IpAddr = "127.0.0.1".parse().unwrap();
+        assert_eq!(RateLimiter::new(1, 1, false).client_ip(&h, Some(peer)), Some(peer));
+        assert_eq!(RateLimiter::new(1, 1, true).client_ip(&h, Some(peer)), "203.0.113.7".parse().ok());
+    }
+}
@@ -0,0 +1,85 @@
+//! 最近編集したレコード（`GET /api/recent?limit=20`）。ファイルの更新日時の新しい順に、表示ラベルといっしょに返す。
+//! 一覧のキャッシュ（record_cache.rs）から取るので、クライアントが 1 件ずつ更新日時を読まなくてよい。
+
+use axum::{extract::Query, http::StatusCode, response::IntoResponse, Json};
+use std::sync::Arc;
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+use crate::record_cache::CachedRecord;
+use crate::stats::{date_from_system_time, timestamp_from_system_time};
+use crate::display_label_from_value;
+
+/// 既定の件数
+const DEFAULT_LIMIT: usize = 20;
+/// 一度に返す最大の件数
+const MAX_LIMIT: usize = 200;
+
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct RecentParams {
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct RecentEntry {
+    filename: String,
+    display_label: String,
+    /// ファイルの最終更新日時（RFC 3339, UTC）
+    modified: String,
+    /// 同じ日の YYYY/MM/DD（ライブラリの時差）
+    modified_date: String,
+}
+
+/// 更新日時の新しい順に `limit` 件。更新日時の取れないものは入れない。同じ日時はファイル名順
+fn recent(records: Vec<(String, Arc<CachedRecord>)>, limit: usize) -> Vec<RecentEntry> {
+    let mut dated: Vec<_> = records.into_iter().filter_map(|(name, r)| Some((r.modified?, name, r))).collect();
+    dated.sort_by(|(a, an, _), (b, bn, _)| b.cmp(a).then_with(|| an.cmp(bn)));
+    dated
+        .into_iter()
+        .take(limit)
+        .map(|(modified, filename, r)| RecentEntry {
+            display_label: display_label_from_value(&r.value),
+            modified: timestamp_from_system_time(modified).unwrap_or_default(),
+            modified_date: date_from_system_time(modified).unwrap_or_default(),
+            filename,
+        })
+        .collect()
+}
+
+/// `GET /api/recent?limit=20`
+#[utoipa::path(get, path = "/api/recent", tag = "records", summary = "最近編集したレコード（?limit=20、更新日時の新しい順）", params(LibraryQuery))]
+pub async fn get_recent(Lib(library): Lib, Query(params): Query<RecentParams>) -> impl IntoResponse {
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    match library.records.records(&*library.storage) {
+        Ok(records) => (StatusCode::OK, Json(recent(records, limit))).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod recent_tests {
+    use super::recent;
+    use crate::record_cache::CachedRecord;
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn newest_first_without_undated_files() {
+        let at = |secs: u64| Some(UNIX_EPOCH + Duration::from_secs(secs));
+        let record = |title: &str, modified| Arc::new(CachedRecord { value: json!({"title": title}), modified });
+        let records = vec![
+            ("a.json".to_string(), record("A", at(100))),
+            ("b.json".to_string(), record("B", at(300))),
+            ("c.json".to_string(), record("C", None)),
+            ("d.json".to_string(), record("D", at(300))),
+            ("e.json".to_string(), record("E", at(200))),
+        ];
+        let list = recent(records, 3);
+        let names: Vec<&str> = list.iter().map(|e| e.filename.as_str()).collect();
+        assert_eq!(names, ["b.json", "d.json", "e.json"]);
+        assert_eq!(list[0].modified, "1970-01-01T00:05:00Z");
+        assert!(list[0].display_label.contains('B'));
+    }
+}
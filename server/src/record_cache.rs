@@ -0,0 +1,239 @@
+//! 一覧用のレコードのキャッシュ。`/api/list-with-labels` のたびに全 JSON を読み直していたのを、変わったものだけ読み直すようにする。
+//! サーバー経由の書き込みは `Invalidating` で、`db/` を直接編集したものは notify の監視で古い印を付ける。
+//! 監視を始められなかったときは、これまでどおり毎回すべて読む。
+//! どちらの変更も `live::LibraryEvents` に流し、`/api/ws` で開いているフロントに知らせる。
+
+use notify::{RecursiveMode, Watcher};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use crate::attachments::ATTACHMENTS_DIR;
+use crate::covers::COVERS_DIR;
+use crate::live::{EventKind, LibraryEvents};
+use crate::storage::{self, Storage, ZSTD_EXT};
+
+/// キャッシュした 1 件（中身と最終更新日時）
+pub struct CachedRecord {
+    pub value: Value,
+    pub modified: Option<SystemTime>,
+}
+
+#[derive(Default)]
+struct Inner {
+    records: BTreeMap<String, Arc<CachedRecord>>,
+    /// 読み直すレコード
+    stale: BTreeSet<String>,
+    /// ファイル名の一覧から取り直す（初回、ディレクトリごとの変更、監視のイベント取りこぼし）
+    rescan: bool,
+    /// 監視中か（false なら毎回すべて読む）
+    watching: bool,
+}
+
+pub struct RecordCache {
+    root: PathBuf,
+    inner: Mutex<Inner>,
+}
+
+impl RecordCache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        // 監視のイベントは実体のパスで来ることがあるので揃えておく
+        let root = root.into();
+        let root = std::fs::canonicalize(&root).unwrap_or(root);
+        Self { root, inner: Mutex::new(Inner { rescan: true, ..Default::default() }) }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// レコードを読み直す印を付ける
+    pub fn invalidate(&self, filename: &str) {
+        self.lock().stale.insert(filename.to_string());
+    }
+
+    /// すべて読み直す印を付ける（バックアップから戻したときなど）
+    pub fn rescan_all(&self) {
+        let mut inner = self.lock();
+        inner.records.clear();
+        inner.rescan = true;
+    }
+
+    /// DB の下で変わったパスに印を付ける。レコードならそのファイル名、カバー・メモ・添付・設定は無視、
+    /// それ以外（シャードのディレクトリなど）は一覧から取り直す。レコードならそのファイル名を返す。
+    pub fn note_path(&self, path: &Path) -> Option<String> {
+        let Ok(rel) = path.strip_prefix(&self.root) else {
+            self.lock().rescan = true;
+            return None;
+        };
+        let first = rel.components().next();
+        if let Some(Component::Normal(dir)) = first {
+            let dir = dir.to_string_lossy();
+            if rel.components().count() > 1 && (dir.starts_with('.') || dir == COVERS_DIR || dir == ATTACHMENTS_DIR) {
+                return None;
+            }
+        }
+        let name = rel.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let name = name.strip_suffix(ZSTD_EXT).unwrap_or(&name);
+        let mut inner = self.lock();
+        if name.ends_with(".json") {
+            inner.stale.insert(name.to_string());
+            Some(name.to_string())
+        } else {
+            inner.rescan = true;
+            None
+        }
+    }
+
+    /// `db/` の監視を始める。返したものを持っている間だけ監視する。レコードの変更は `events` にも流す。
+    pub fn watch(self: &Arc<Self>, events: Arc<LibraryEvents>) -> notify::Result<notify::RecommendedWatcher> {
+        let cache = Arc::clone(self);
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) if !event.need_rescan() && !event.paths.is_empty() => {
+                for p in &event.paths {
+                    let Some(filename) = cache.note_path(p) else { continue };
+                    let kind = match (p.exists(), event.kind) {
+                        (false, _) => EventKind::Deleted,
+                        (true, notify::EventKind::Create(_)) => EventKind::Created,
+                        (true, _) => EventKind::Updated,
+                    };
+                    events.observed(kind, &filename);
+                }
+            }
+            _ => cache.lock().rescan = true,
+        })?;
+        watcher.watch(&self.root, RecursiveMode::Recursive)?;
+        self.lock().watching = true;
+        Ok(watcher)
+    }
+
+    /// 全レコード（名前順）。変わったものだけ読み直す。
+    pub fn records(&self, storage: &dyn Storage) -> io::Result<Vec<(String, Arc<CachedRecord>)>> {
+        let mut inner = self.lock();
+        if !inner.watching {
+            inner.records.clear();
+            inner.rescan = true;
+        }
+        let load = |name: &str| {
+            let value = storage::read_record(storage, name).ok()?;
+            Some(Arc::new(CachedRecord { value, modified: storage.modified(name).ok() }))
+        };
+        if inner.rescan {
+            let names = storage.list()?;
+            let keep: BTreeSet<&String> = names.iter().collect();
+            inner.records.retain(|name, _| keep.contains(name));
+            for name in names {
+                if inner.stale.remove(&name) || !inner.records.contains_key(&name) {
+                    match load(&name) {
+                        Some(r) => inner.records.insert(name, r),
+                        None => inner.records.remove(&name),
+                    };
+                }
+            }
+            inner.rescan = false;
+        }
+        for name in std::mem::take(&mut inner.stale) {
+            match load(&name) {
+                Some(r) => inner.records.insert(name, r),
+                None => inner.records.remove(&name),
+            };
+        }
+        Ok(inner.records.iter().map(|(k, v)| (k.clone(), Arc::clone(v))).collect())
+    }
+}
+
+/// 書き込みのたびにキャッシュへ印を付け、変更を流す `Storage`。監視のイベントより先に一覧を取られても古いものを返さない。
+pub struct Invalidating<S> {
+    pub inner: S,
+    pub cache: Arc<RecordCache>,
+    pub events: Arc<LibraryEvents>,
+}
+
+impl<S: Storage> Storage for Invalidating<S> {
+    fn list(&self) -> io::Result<Vec<String>> {
+        self.inner.list()
+    }
+
+    fn read(&self, filename: &str) -> io::Result<Vec<u8>> {
+        self.inner.read(filename)
+    }
+
+    fn write(&self, filename: &str, data: &[u8]) -> io::Result<()> {
+        self.events.begin(filename);
+        let existed = self.inner.exists(filename);
+        let res = self.inner.write(filename, data);
+        self.cache.invalidate(filename);
+        let kind = if existed { EventKind::Updated } else { EventKind::Created };
+        self.events.finish(filename, res.is_ok().then_some(kind));
+        res
+    }
+
+    fn modified(&self, filename: &str) -> io::Result<SystemTime> {
+        self.inner.modified(filename)
+    }
+
+    fn remove(&self, filename: &str) -> io::Result<()> {
+        self.events.begin(filename);
+        let res = self.inner.remove(filename);
+        self.cache.invalidate(filename);
+        self.events.finish(filename, res.is_ok().then_some(EventKind::Deleted));
+        res
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        self.events.begin(from);
+        self.events.begin(to);
+        let res = self.inner.rename(from, to);
+        self.cache.invalidate(from);
+        self.cache.invalidate(to);
+        self.events.finish(from, res.is_ok().then_some(EventKind::Deleted));
+        self.events.finish(to, res.is_ok().then_some(EventKind::Created));
+        res
+    }
+}
+
+#[cfg(test)]
+mod record_cache_tests {
+    use super::{Invalidating, RecordCache};
+    use crate::live::{EventKind, LibraryEvents};
+    use crate::storage::{FsStorage, Layout, Storage};
+    use std::fs;
+    use std::sync::Arc;
+
+    #[test]
+    fn only_changed_records_are_reread() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let cache = Arc::new(RecordCache::new(&root));
+        cache.lock().watching = true;
+        let events = Arc::new(LibraryEvents::default());
+        let mut rx = events.subscribe();
+        let storage = Invalidating { inner: FsStorage::new(&root, Layout::Flat, false), cache: cache.clone(), events };
+        storage.write("a.json", br#"{"title":"A"}"#).unwrap();
+        storage.write("b.json", br#"{"title":"B"}"#).unwrap();
+        let first = cache.records(&storage).unwrap();
+        assert_eq!(first.len(), 2);
+
+        // 監視が拾った直接の編集・カバーの追加
+        fs::write(root.join("a.json"), r#"{"title":"A2"}"#).unwrap();
+        cache.note_path(&root.join("a.json"));
+        cache.note_path(&root.join("covers").join("a.jpg"));
+        let second = cache.records(&storage).unwrap();
+        assert_eq!(second[0].1.value["title"], "A2");
+        assert!(Arc::ptr_eq(&first[1].1, &second[1].1));
+
+        storage.remove("b.json").unwrap();
+        let third = cache.records(&storage).unwrap();
+        assert_eq!(third.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>(), vec!["a.json"]);
+
+        // 保存・削除は開いているフロントに流す
+        let kinds: Vec<(EventKind, String)> = std::iter::from_fn(|| rx.try_recv().ok()).map(|e| (e.event, e.filename)).collect();
+        assert_eq!(
+            kinds,
+            [(EventKind::Created, "a.json".into()), (EventKind::Created, "b.json".into()), (EventKind::Deleted, "b.json".into())]
+        );
+    }
+}
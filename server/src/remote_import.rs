@@ -0,0 +1,149 @@
+//! ほかの nekokan_music のレコードを URL から取り込む。ブラウザからほかのサーバーは CORS で読めないので、こちらのサーバーが代わりに取ってくる。
+//! 受け付けるのは `http(s)://ホスト/api/files/名前.json`（`?library=` つきも可）だけで、ほかの URL は取りに行かない。
+//! 取ってきたものは返すだけで保存しない（フロントが下書きにして保存する）。
+//!
+//! - `POST /api/import/url` `{"url": "..."}` → `{"filename", "data", "display_label", "exists", "source"}`
+
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use serde_json::Value;
+use std::io::Read;
+use std::time::Duration;
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+
+/// 取り込み元への待ち時間
+const HTTP_TIMEOUT: Duration = Duration::from_secs(20);
+/// レコードの URL のパス
+const FILES_PATH: &str = "/api/files/";
+
+/// "%E3%81%82" などを戻す（UTF-8 でなければ None）
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// 貼り付けられた URL を確かめ、取りに行く URL とファイル名（"xxx.json"）を返す
+pub fn record_url(input: &str) -> Result<(String, String), String> {
+    let url = input.trim();
+    let (scheme, rest) = ["http://", "https://"]
+        .into_iter()
+        .find_map(|scheme| Some((scheme, url.strip_prefix(scheme)?)))
+        .ok_or_else(|| "http:// か https:// の URL を貼ってください".to_string())?;
+    // ホストとパスを分ける前にクエリとフラグメントを切る（"https://example.com?x=/api/files/a.json" でホストの根を取りに行かないように）
+    let (rest, query) = rest.split_at(rest.find(['?', '#']).unwrap_or(rest.len()));
+    let query = query.split('#').next().unwrap_or("");
+    let (host, path) = rest.split_once('/').map(|(h, p)| (h, format!("/{}", p))).unwrap_or((rest, String::new()));
+    if host.is_empty() || host.contains(['@', '\\', '?', '#', '%']) || host.contains(char::is_whitespace) {
+        return Err(format!("ホスト名が読めません: {}", input.trim()));
+    }
+    let not_a_record = || format!("ほかの nekokan_music の {}名前.json の URL を貼ってください", FILES_PATH);
+    let encoded = path.strip_prefix(FILES_PATH).ok_or_else(not_a_record)?;
+    let filename = percent_decode(encoded).ok_or_else(not_a_record)?;
+    if filename.contains(['/', '\\']) || filename.contains("..") || !filename.ends_with(".json") || filename == ".json" {
+        return Err(not_a_record());
+    }
+    Ok((format!("{}{}{}{}", scheme, host, path, query), filename))
+}
+
+/// 取ってくる（ブロッキング。spawn_blocking から呼ぶ）
+fn fetch_record(url: &str) -> Result<Value, String> {
+    // リダイレクトはたどらない（確かめた URL から別のところへ飛ばされないように）
+    let agent = ureq::AgentBuilder::new().redirects(0).timeout(HTTP_TIMEOUT).build();
+    let resp = match agent.get(url).set("Accept", "application/json").call() {
+        Ok(resp) if resp.status() >= 300 => {
+            return Err(format!("{}: {} リダイレクトはたどりません（{}）", url, resp.status(), resp.header("Location").unwrap_or("")));
+        }
+        Ok(resp) => resp,
+        Err(ureq::Error::Status(code, resp)) => {
+            let msg: Value = resp.into_json().unwrap_or(Value::Null);
+            return Err(format!("{}: {} {}", url, code, crate::problem::message_of(&msg)));
+        }
+        Err(e) => return Err(format!("{}: {}", url, e)),
+    };
+    let mut bytes = Vec::new();
+    resp.into_reader()
+        .take(crate::RECORD_MAX_BYTES as u64 + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("{}: {}", url, e))?;
+    if bytes.len() > crate::RECORD_MAX_BYTES {
+        return Err(format!("{}: レコードが大きすぎます", url));
+    }
+    let mut v: Value = serde_json::from_slice(&bytes).map_err(|_| format!("{}: JSON ではありません", url))?;
+    if !v.is_object() || !v["title"].is_string() {
+        return Err(format!("{}: nekokan_music のレコードではありません", url));
+    }
+    // 古いサーバーのレコードは今の形式にしてからフォームに渡す
+    crate::migrations::upgrade(&mut v).map_err(|e| format!("{}: {}", url, e))?;
+    Ok(v)
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct ImportUrlBody {
+    url: String,
+}
+
+/// `POST /api/import/url` ほかのサーバーのレコードを取ってきて返す（保存はしない）
+#[utoipa::path(
+    post,
+    path = "/api/import/url",
+    tag = "import-export",
+    summary = "ほかの nekokan_music のレコードを取ってくる（保存しない）",
+    params(LibraryQuery),
+    request_body = ImportUrlBody,
+)]
+pub async fn import_url(Lib(library): Lib, Json(body): Json<ImportUrlBody>) -> impl IntoResponse {
+    let (url, filename) = match record_url(&body.url) {
+        Ok(v) => v,
+        Err(e) => return error(StatusCode::BAD_REQUEST, e),
+    };
+    let fetch_url = url.clone();
+    let res = tokio::task::spawn_blocking(move || fetch_record(&fetch_url)).await.unwrap_or_else(|e| Err(e.to_string()));
+    match res {
+        Ok(data) => Json(serde_json::json!({
+            "display_label": crate::display_label_from_value(&data),
+            "exists": library.storage.exists(&filename),
+            "filename": filename,
+            "data": data,
+            "source": url,
+        }))
+        .into_response(),
+        Err(e) => error(StatusCode::BAD_GATEWAY, e),
+    }
+}
+
+#[cfg(test)]
+mod remote_import_tests {
+    use super::record_url;
+
+    #[test]
+    fn only_record_urls_are_fetched() {
+        let (url, name) = record_url(" https://music.example.com:8443/api/files/Bill_Evans__Alone.json?library=jazz#x ").unwrap();
+        assert_eq!(url, "https://music.example.com:8443/api/files/Bill_Evans__Alone.json?library=jazz");
+        assert_eq!(name, "Bill_Evans__Alone.json");
+        let (_, name) = record_url("http://192.168.0.5:12989/api/files/%E3%81%82.json").unwrap();
+        assert_eq!(name, "あ.json");
+        assert!(record_url("ftp://example.com/api/files/a.json").is_err());
+        assert!(record_url("https://example.com/api/list").is_err());
+        assert!(record_url("https://example.com/api/files/..%2Fsecret.json").is_err());
+        assert!(record_url("https://example.com/api/files/a.txt").is_err());
+        assert!(record_url("https://user@example.com/api/files/a.json").is_err());
+        // クエリ・フラグメントの中の "/api/files/" はパスではない
+        assert!(record_url("https://example.com?x=/api/files/a.json").is_err());
+        assert!(record_url("https://example.com#/api/files/a.json").is_err());
+        assert!(record_url("https://example.com%2F/api/files/a.json").is_err());
+        assert!(record_url("https://example.com/?/api/files/a.json").is_err());
+    }
+}
@@ -0,0 +1,181 @@
+//! レコードの名前の変更（`POST /api/rename`）。アーティスト名の打ち間違いを直したときにファイル名も直せるように。
+//! レコードは置き換えで一度に移し、カバー・メモ・添付も新しい名前に移す。ほかのレコードの related も書き換える。
+
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+use crate::attachments::ATTACHMENTS_DIR;
+use crate::audit::RecordChange;
+use crate::covers::COVERS_DIR;
+use crate::libraries::{Lib, Library, LibraryQuery};
+use crate::notes::NOTES_DIR;
+use crate::problem::error;
+use crate::storage::read_record;
+use crate::versions::{self, Precondition, WriteError};
+use crate::{load_all_records, normalize_filename};
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct RenameBody {
+    from: String,
+    to: String,
+    /// 読んだときの版。あれば、今のレコードがこの版のときだけ変える（違えば 409）
+    #[serde(default)]
+    version: Option<String>,
+}
+
+/// 古い名前で付けたカバー（"{旧名}.jpg"）なら新しい名前のものを返す。別の名前のカバーはそのまま。
+fn renamed_cover(cover: &str, from_stem: &str, to_stem: &str) -> Option<String> {
+    let ext = cover.strip_prefix(from_stem)?.strip_prefix('.')?;
+    (!ext.is_empty() && !ext.contains(['/', '\\'])).then(|| format!("{}.{}", to_stem, ext))
+}
+
+/// related の `from` を `to` に書き換える。書き換えたら true。
+fn replace_related(v: &mut Value, from: &str, to: &str) -> bool {
+    let Some(related) = v["related"].as_array_mut() else {
+        return false;
+    };
+    let mut changed = false;
+    for r in related.iter_mut().filter(|r| r.as_str() == Some(from)) {
+        *r = Value::String(to.to_string());
+        changed = true;
+    }
+    changed
+}
+
+/// メモと添付を新しい名前に移す（ないものは飛ばす）
+fn move_side_files(dir: &Path, from: &str, to: &str) -> std::io::Result<()> {
+    let moves = [
+        (dir.join(NOTES_DIR).join(from), dir.join(NOTES_DIR).join(to)),
+        (
+            dir.join(ATTACHMENTS_DIR).join(from.trim_end_matches(".json")),
+            dir.join(ATTACHMENTS_DIR).join(to.trim_end_matches(".json")),
+        ),
+    ];
+    for (src, dst) in moves {
+        if src.exists() && !dst.exists() {
+            fs::rename(src, dst)?;
+        }
+    }
+    Ok(())
+}
+
+/// レコードを移した結果
+pub struct Moved {
+    /// 移したあとの失敗（名前の変更自体は成功）
+    pub warnings: Vec<String>,
+    /// カバーも新しい名前にしたときのカバーのファイル名
+    pub cover: Option<String>,
+}
+
+/// レコードを `from` から `to` に移し、メモ・添付・カバーも移す（ほかのレコードの related は update_related で）。
+/// `pre` は移す前のレコードで確かめる。レコードを移せなければエラー、その後の失敗は警告として返す
+pub fn move_record(library: &Library, from: &str, to: &str, pre: &Precondition) -> Result<Moved, WriteError> {
+    versions::rename(&*library.storage, from, to, pre)?;
+    // ここから先はレコードの名前は変わった後なので、失敗しても名前の変更自体は成功として返す
+    let mut warnings: Vec<String> = Vec::new();
+    let mut moved_cover = None;
+    if let Err(e) = move_side_files(&library.dir, from, to) {
+        warnings.push(format!("メモ・添付を移せませんでした: {}", e));
+    }
+    let res = versions::update(&*library.storage, to, |v| {
+        let cover = v["cover"].as_str().unwrap_or("").to_string();
+        let Some(new_cover) = renamed_cover(&cover, from.trim_end_matches(".json"), to.trim_end_matches(".json")) else {
+            return Ok(false);
+        };
+        let covers = library.dir.join(COVERS_DIR);
+        if let Err(e) = fs::rename(covers.join(&cover), covers.join(&new_cover)) {
+            warnings.push(format!("カバーを移せませんでした: {}", e));
+            return Ok(false);
+        }
+        v["cover"] = Value::String(new_cover.clone());
+        moved_cover = Some(new_cover);
+        Ok(true)
+    });
+    if let Err(e) = res {
+        warnings.push(format!("カバー名を書き換えられませんでした: {}", e));
+        moved_cover = None;
+    }
+    Ok(Moved { warnings, cover: moved_cover })
+}
+
+/// ほかのレコードの related を `renames`（旧, 新）のとおりに書き換える。書き換えたレコードの数と警告を返す
+pub fn update_related(library: &Library, renames: &[(String, String)]) -> (usize, Vec<String>) {
+    let mut updated = 0;
+    let mut warnings: Vec<String> = Vec::new();
+    for (filename, mut v) in load_all_records(&*library.storage).unwrap_or_default() {
+        let read = Precondition::Version(versions::version_of(&v));
+        let mut changed = false;
+        for (from, to) in renames {
+            changed |= replace_related(&mut v, from, to);
+        }
+        if changed {
+            match versions::write(&*library.storage, &filename, &read, &v) {
+                Ok(_) => updated += 1,
+                Err(e) => warnings.push(format!("{} の related を書き換えられませんでした: {}", filename, e)),
+            }
+        }
+    }
+    (updated, warnings)
+}
+
+/// `POST /api/rename` `{"from": "旧.json", "to": "新.json"}`。新しい名前が既にあれば 409。
+#[utoipa::path(
+    post,
+    path = "/api/rename",
+    tag = "records",
+    summary = "レコードの名前を変える（version があればその版のときだけ。変えたあとの version と cover を返す）",
+    params(LibraryQuery),
+    request_body = RenameBody,
+)]
+pub async fn rename(Lib(library): Lib, Json(body): Json<RenameBody>) -> impl IntoResponse {
+    let (Some(from), Some(to)) = (normalize_filename(&body.from), normalize_filename(&body.to)) else {
+        return error(StatusCode::BAD_REQUEST, "invalid filename");
+    };
+    if from == to {
+        return error(StatusCode::BAD_REQUEST, "new name is the same as the old one");
+    }
+    let pre = body.version.map_or(Precondition::Any, Precondition::Version);
+    let Moved { mut warnings, cover } = match move_record(&library, &from, &to, &pre) {
+        Ok(moved) => moved,
+        Err(e) => return e.into_response(),
+    };
+    let (related_updated, related_warnings) = update_related(&library, &[(from.clone(), to.clone())]);
+    warnings.extend(related_warnings);
+    // カバー名の書き換えで版が変わるので、続けて保存するときはこの版と `cover` を使う
+    let version = read_record(&*library.storage, &to).ok().map(|v| versions::version_of(&v));
+    let change = RecordChange { action: "rename", file: from, renamed_to: Some(to.clone()), ..Default::default() };
+    let mut res = (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "ok": true,
+            "filename": to,
+            "version": version,
+            "cover": cover,
+            "related_updated": related_updated,
+            "warnings": warnings,
+        })),
+    )
+        .into_response();
+    res.extensions_mut().insert(change.in_library(&library));
+    res
+}
+
+#[cfg(test)]
+mod rename_tests {
+    use super::{renamed_cover, replace_related};
+    use serde_json::json;
+
+    #[test]
+    fn cover_and_related_follow_the_new_name() {
+        assert_eq!(renamed_cover("Bil_Evans__Alone.jpg", "Bil_Evans__Alone", "Bill_Evans__Alone").as_deref(), Some("Bill_Evans__Alone.jpg"));
+        assert_eq!(renamed_cover("shared.jpg", "Bil_Evans__Alone", "Bill_Evans__Alone"), None);
+        assert_eq!(renamed_cover("Bil_Evans__Alone_2.jpg", "Bil_Evans__Alone", "Bill_Evans__Alone"), None);
+
+        let mut v = json!({"related": ["Bil_Evans__Alone.json", "Other.json"]});
+        assert!(replace_related(&mut v, "Bil_Evans__Alone.json", "Bill_Evans__Alone.json"));
+        assert_eq!(v["related"], json!(["Bill_Evans__Alone.json", "Other.json"]));
+        assert!(!replace_related(&mut json!({}), "a.json", "b.json"));
+    }
+}
@@ -0,0 +1,376 @@
+//! 古いものの整理。小さなディスクで `{DB_PATH}-backups`・`.config` のログ・レコードの履歴・ごみ箱が増え続けないよう、保存期間を決めて消す。
+//!
+//! - スナップショット（backup.rs）: `--backup-max-days`（`BACKUP_MAX_DAYS`）日より古いものを消す。
+//!   `--backup-daily` のときは `--backup-keep` 個より古いものも消す。いちばん新しいものは古くても残す。
+//! - ログ（`audit.jsonl`・`client-errors.jsonl`・`validation-failures.jsonl`）: `--log-max-days`（`LOG_MAX_DAYS`）日より古い行を消す。
+//!   年のふりかえり（year_review.rs）は `audit.jsonl` から読むので、残したい年の分は短くしすぎないこと。
+//! - レコードの履歴（git_history.rs、`--git-history` のとき）: ライブラリごとに、新しい `--history-keep`（`HISTORY_KEEP`）個と
+//!   `--history-max-days`（`HISTORY_MAX_DAYS`）日以内のコミットだけを残す。いちばん新しいコミットは古くても残す。
+//!   コミットを書き換えるので、`git push` している先には次から `--force` で送ることになる。
+//! - ごみ箱（trash.rs）: ライブラリごとに、新しい `--trash-keep`（`TRASH_KEEP`）個と `--trash-max-days`（`TRASH_MAX_DAYS`）日以内に
+//!   消したものだけを残す。
+//!
+//! どれも 0 なら消さない。どれかを決めていれば 1 日 1 回整理し、結果（消したもの・空いた大きさ）を `.config/retention-report.json` に残す。
+//!
+//! - `GET /api/maintenance/retention` 設定・今の大きさ・前回の結果
+//! - `POST /api/maintenance/prune` 今すぐ整理する
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::backup::{self, Snapshot};
+use crate::libraries::Library;
+use crate::problem::error;
+use crate::settings::{config_path, CONFIG_DIR};
+use crate::stats::{now_timestamp, timestamp_from_system_time};
+use crate::storage::write_atomic;
+use crate::trash;
+use crate::AppState;
+
+const REPORT_FILE: &str = "retention-report.json";
+/// 整理の日付を確かめる間隔
+const SCHEDULER_TICK: Duration = Duration::from_secs(60 * 60);
+/// 整理するログと、行の時刻の項目
+const LOGS: [(&str, &str); 3] = [
+    (crate::audit::AUDIT_FILE, "at"),
+    (crate::client_errors::LOG_FILE, "received"),
+    (crate::validation_log::LOG_FILE, "received"),
+];
+
+/// 保存期間
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize)]
+pub struct Retention {
+    /// スナップショットを残す日数（0 なら日数では消さない）
+    pub backup_max_days: u32,
+    /// ログの行を残す日数（0 なら消さない）
+    pub log_max_days: u32,
+    /// レコードの履歴のコミットを残す数（0 なら数では消さない）
+    pub history_keep: usize,
+    /// レコードの履歴のコミットを残す日数（0 なら日数では消さない）
+    pub history_max_days: u32,
+    /// ごみ箱に残す数（0 なら数では消さない）
+    pub trash_keep: usize,
+    /// ごみ箱に残す日数（0 なら日数では消さない）
+    pub trash_max_days: u32,
+}
+
+impl Retention {
+    /// 何か消す設定があるか
+    pub fn is_active(&self, backup: &backup::BackupConfig) -> bool {
+        self.backup_max_days > 0
+            || self.log_max_days > 0
+            || (backup.daily && backup.keep > 0)
+            || self.prunes_history()
+            || self.prunes_trash()
+    }
+
+    fn prunes_history(&self) -> bool {
+        self.history_keep > 0 || self.history_max_days > 0
+    }
+
+    fn prunes_trash(&self) -> bool {
+        self.trash_keep > 0 || self.trash_max_days > 0
+    }
+}
+
+/// 1 つのログの整理の結果
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct LogPruned {
+    pub file: String,
+    pub lines: usize,
+    pub bytes: u64,
+}
+
+/// 1 つのライブラリの履歴・ごみ箱の整理の結果
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct LibraryPruned {
+    pub library: String,
+    /// 消したもの（コミットの数、ごみ箱のファイルの数）
+    pub count: usize,
+    pub bytes: u64,
+}
+
+/// 整理の結果
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct PruneReport {
+    /// 整理した時刻（RFC 3339、UTC）
+    pub at: String,
+    /// 消したスナップショットの名前
+    pub snapshots: Vec<String>,
+    pub snapshot_bytes: u64,
+    pub logs: Vec<LogPruned>,
+    /// 消した履歴のコミット（ライブラリごと）
+    #[serde(default)]
+    pub history: Vec<LibraryPruned>,
+    /// ごみ箱から消したもの（ライブラリごと）
+    #[serde(default)]
+    pub trash: Vec<LibraryPruned>,
+    /// 空いた大きさの合計（バイト）
+    pub reclaimed_bytes: u64,
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+/// `days` 日前の時刻（RFC 3339、UTC）。ログの時刻と文字列のまま比べられる
+fn cutoff(now: SystemTime, days: u32) -> String {
+    timestamp_from_system_time(now - Duration::from_secs(u64::from(days) * 24 * 60 * 60)).unwrap_or_default()
+}
+
+/// `cutoff` より古いスナップショット（いちばん新しいものは残す）と、`keep` 個より古いもの。`list` は新しい順
+pub fn expired_snapshots<'a>(list: &'a [Snapshot], keep: usize, cutoff: Option<&str>) -> Vec<&'a Snapshot> {
+    list.iter()
+        .enumerate()
+        .skip(1)
+        .filter(|(i, s)| (keep > 0 && *i >= keep) || cutoff.is_some_and(|c| s.created.as_str() < c))
+        .map(|(_, s)| s)
+        .collect()
+}
+
+/// `key` の時刻が `cutoff` より古い行を消す。読めない行は残す。消した行数と大きさ
+pub fn prune_log(path: &Path, key: &str, cutoff: &str) -> io::Result<(usize, u64)> {
+    let text = match fs::read_to_string(path) {
+        Ok(t) => t,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok((0, 0)),
+        Err(e) => return Err(e),
+    };
+    let mut kept = String::with_capacity(text.len());
+    let (mut lines, mut bytes) = (0, 0);
+    for line in text.lines() {
+        let at = serde_json::from_str::<serde_json::Value>(line).ok().and_then(|v| v[key].as_str().map(str::to_string));
+        if at.is_some_and(|at| at.as_str() < cutoff) {
+            lines += 1;
+            bytes += line.len() as u64 + 1;
+        } else {
+            kept.push_str(line);
+            kept.push('\n');
+        }
+    }
+    if lines > 0 {
+        write_atomic(path, kept.as_bytes())?;
+    }
+    Ok((lines, bytes))
+}
+
+/// `days` 日前の UNIX 時刻（秒）。git のコミットの時刻と比べる
+fn cutoff_secs(now: SystemTime, days: u32) -> u64 {
+    now.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0).saturating_sub(u64::from(days) * 24 * 60 * 60)
+}
+
+/// ライブラリのレコードの履歴を整理する（`--git-history` でなければ何もしない）
+fn prune_history(library: &Library, retention: Retention, now: SystemTime) -> Result<Option<LibraryPruned>, String> {
+    let Some(history) = &library.history else {
+        return Ok(None);
+    };
+    // 積んであるコミットを先に済ませる
+    history.queue.flush();
+    let before = history.repo.size();
+    let cutoff = (retention.history_max_days > 0).then(|| cutoff_secs(now, retention.history_max_days));
+    let count = history.repo.prune(retention.history_keep, cutoff)?;
+    let bytes = before.saturating_sub(history.repo.size());
+    Ok((count > 0).then(|| LibraryPruned { library: library.name.clone(), count, bytes }))
+}
+
+/// ライブラリのごみ箱を整理する
+fn prune_trash(library: &Library, retention: Retention, now: SystemTime) -> io::Result<Option<LibraryPruned>> {
+    let trash_cutoff = (retention.trash_max_days > 0).then(|| cutoff(now, retention.trash_max_days));
+    let removed = trash::remove(&library.dir, |list| {
+        trash::expired(list, retention.trash_keep, trash_cutoff.as_deref()).into_iter().map(|e| e.name.clone()).collect()
+    })?;
+    let bytes = removed.iter().map(|e| e.bytes).sum();
+    Ok((!removed.is_empty()).then(|| LibraryPruned { library: library.name.clone(), count: removed.len(), bytes }))
+}
+
+/// 整理する（ブロッキング）。結果は `.config` にも残す
+pub fn run(db_path: &Path, libraries: &[Library], backup: &backup::BackupConfig, retention: Retention) -> PruneReport {
+    let now = SystemTime::now();
+    let mut report = PruneReport { at: now_timestamp(), ..Default::default() };
+    let keep = if backup.daily { backup.keep } else { 0 };
+    let backup_cutoff = (retention.backup_max_days > 0).then(|| cutoff(now, retention.backup_max_days));
+    if keep > 0 || backup_cutoff.is_some() {
+        let removed = backup::with_lock(|| {
+            backup::remove_snapshots(&backup.dir, |list| {
+                expired_snapshots(list, keep, backup_cutoff.as_deref()).into_iter().map(|s| s.name.clone()).collect()
+            })
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+        });
+        match removed {
+            Ok(removed) => {
+                report.snapshot_bytes = removed.iter().map(|s| s.bytes).sum();
+                report.snapshots = removed.into_iter().map(|s| s.name).collect();
+            }
+            Err((_, e)) => report.errors.push(format!("スナップショット: {}", e)),
+        }
+    }
+    if retention.log_max_days > 0 {
+        let log_cutoff = cutoff(now, retention.log_max_days);
+        for (file, key) in LOGS {
+            match prune_log(&config_path(db_path, file), key, &log_cutoff) {
+                Ok((0, _)) => {}
+                Ok((lines, bytes)) => report.logs.push(LogPruned { file: file.to_string(), lines, bytes }),
+                Err(e) => report.errors.push(format!("{}: {}", file, e)),
+            }
+        }
+    }
+    for library in libraries {
+        if retention.prunes_history() {
+            match prune_history(library, retention, now) {
+                Ok(pruned) => report.history.extend(pruned),
+                Err(e) => report.errors.push(format!("{} の履歴: {}", library.name, e)),
+            }
+        }
+        if retention.prunes_trash() {
+            match prune_trash(library, retention, now) {
+                Ok(pruned) => report.trash.extend(pruned),
+                Err(e) => report.errors.push(format!("{} のごみ箱: {}", library.name, e)),
+            }
+        }
+    }
+    report.reclaimed_bytes = report.snapshot_bytes
+        + report.logs.iter().map(|l| l.bytes).sum::<u64>()
+        + report.history.iter().chain(&report.trash).map(|l| l.bytes).sum::<u64>();
+    let saved = fs::create_dir_all(db_path.join(CONFIG_DIR))
+        .map_err(|e| e.to_string())
+        .and_then(|_| serde_json::to_value(&report).map_err(|e| e.to_string()))
+        .and_then(|v| crate::listen::write_json(&config_path(db_path, REPORT_FILE), &v));
+    if let Err(e) = saved {
+        report.errors.push(format!("{}: {}", REPORT_FILE, e));
+    }
+    report
+}
+
+fn last_report(db_path: &Path) -> Option<PruneReport> {
+    fs::read_to_string(config_path(db_path, REPORT_FILE)).ok().and_then(|s| serde_json::from_str(&s).ok())
+}
+
+/// ファイルの大きさ（なければ 0）
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// `GET /api/maintenance/retention`
+#[utoipa::path(get, path = "/api/maintenance/retention", tag = "maintenance", summary = "保存期間・今の大きさ（スナップショット・ログ・履歴・ごみ箱）・前回の整理")]
+pub async fn get_status(State(state): State<AppState>) -> impl IntoResponse {
+    let res = tokio::task::spawn_blocking(move || {
+        let snapshots = backup::list_snapshots(&state.backup.dir).unwrap_or_default();
+        let logs: Vec<serde_json::Value> = LOGS
+            .iter()
+            .map(|(file, _)| serde_json::json!({"file": file, "bytes": file_size(&config_path(&state.db_path, file))}))
+            .collect();
+        let history: Vec<serde_json::Value> = state
+            .libraries
+            .iter()
+            .filter_map(|l| l.history.as_ref().map(|h| serde_json::json!({"library": l.name, "bytes": h.repo.size()})))
+            .collect();
+        let trash: Vec<serde_json::Value> = state
+            .libraries
+            .iter()
+            .map(|l| {
+                let entries = trash::list(&l.dir).unwrap_or_default();
+                serde_json::json!({"library": l.name, "count": entries.len(), "bytes": entries.iter().map(|e| e.bytes).sum::<u64>()})
+            })
+            .collect();
+        serde_json::json!({
+            "retention": state.retention,
+            "backup_keep": if state.backup.daily { state.backup.keep } else { 0 },
+            "snapshots": snapshots.len(),
+            "snapshot_bytes": snapshots.iter().map(|s| s.bytes).sum::<u64>(),
+            "logs": logs,
+            "history": history,
+            "trash": trash,
+            "last_report": last_report(&state.db_path),
+        })
+    })
+    .await;
+    match res {
+        Ok(v) => (StatusCode::OK, Json(v)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// `POST /api/maintenance/prune`
+#[utoipa::path(
+    post,
+    path = "/api/maintenance/prune",
+    tag = "maintenance",
+    summary = "保存期間を過ぎたものを今すぐ消す",
+    responses((status = 200, description = "整理の結果", body = PruneReport)),
+)]
+pub async fn prune_now(State(state): State<AppState>) -> impl IntoResponse {
+    let res = tokio::task::spawn_blocking(move || run(&state.db_path, &state.libraries, &state.backup, state.retention)).await;
+    match res {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// 保存期間を決めていれば、1 時間ごとにその日（UTC）の整理が済んでいるか確かめ、まだなら整理する
+pub fn spawn_scheduler(state: AppState) {
+    if !state.retention.is_active(&state.backup) {
+        return;
+    }
+    tokio::spawn(async move {
+        loop {
+            let state_now = state.clone();
+            let res = tokio::task::spawn_blocking(move || {
+                let today = now_timestamp()[..10].to_string();
+                if last_report(&state_now.db_path).is_some_and(|r| r.at.starts_with(&today)) {
+                    return None;
+                }
+                Some(run(&state_now.db_path, &state_now.libraries, &state_now.backup, state_now.retention))
+            })
+            .await;
+            match res {
+                Ok(Some(r)) if r.reclaimed_bytes > 0 => {
+                    let commits: usize = r.history.iter().map(|h| h.count).sum();
+                    let trashed: usize = r.trash.iter().map(|t| t.count).sum();
+                    println!(
+                        "古いものを整理しました: スナップショット {} 個、履歴のコミット {} 個、ごみ箱 {} 件、{} バイト",
+                        r.snapshots.len(),
+                        commits,
+                        trashed,
+                        r.reclaimed_bytes
+                    )
+                }
+                Ok(Some(r)) if !r.errors.is_empty() => eprintln!("整理に失敗しました: {}", r.errors.join(" / ")),
+                Ok(_) => {}
+                Err(e) => eprintln!("整理に失敗しました: {}", e),
+            }
+            tokio::time::sleep(SCHEDULER_TICK).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod retention_tests {
+    use super::{expired_snapshots, prune_log};
+    use crate::backup::Snapshot;
+
+    fn snapshot(created: &str) -> Snapshot {
+        Snapshot { name: created.replace(':', ""), created: created.to_string(), files: 1, bytes: 10 }
+    }
+
+    #[test]
+    fn old_snapshots_expire_but_the_newest_stays() {
+        let list = vec![snapshot("2026-10-01T00:00:00Z"), snapshot("2026-09-20T00:00:00Z"), snapshot("2026-09-01T00:00:00Z")];
+        let names = |v: Vec<&Snapshot>| v.into_iter().map(|s| s.created.clone()).collect::<Vec<_>>();
+        assert_eq!(names(expired_snapshots(&list, 0, Some("2026-09-15T00:00:00Z"))), vec!["2026-09-01T00:00:00Z"]);
+        assert_eq!(names(expired_snapshots(&list, 2, None)), vec!["2026-09-01T00:00:00Z"]);
+        assert_eq!(expired_snapshots(&list, 0, Some("2027-01-01T00:00:00Z")).len(), 2);
+        assert!(expired_snapshots(&list, 0, None).is_empty());
+    }
+
+    #[test]
+    fn old_log_lines_are_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let old = r#"{"at":"2025-01-01T00:00:00Z","method":"POST"}"#;
+        let new = r#"{"at":"2026-10-01T00:00:00Z","method":"POST"}"#;
+        std::fs::write(&path, format!("{}\nbroken\n{}\n", old, new)).unwrap();
+        assert_eq!(prune_log(&path, "at", "2026-01-01T00:00:00Z").unwrap(), (1, old.len() as u64 + 1));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), format!("broken\n{}\n", new));
+        assert_eq!(prune_log(&dir.path().join("none.jsonl"), "at", "2026-01-01T00:00:00Z").unwrap(), (0, 0));
+    }
+}
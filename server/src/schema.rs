@@ -0,0 +1,54 @@
+//! レコード JSON の項目の説明（`GET /api/schema`）と、既存のファイルを決まった並びに書き直す移行。
+//! 並びそのものは `nekokan_music_core::schema` にある。
+
+use axum::{
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+use crate::storage::Storage;
+use crate::versions;
+
+pub use nekokan_music_core::schema::to_canonical_json;
+
+/// `GET /api/schema` 項目の一覧（Markdown）
+#[utoipa::path(get, path = "/api/schema", tag = "schema", summary = "レコード JSON の項目（Markdown）")]
+pub async fn get_schema() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+        nekokan_music_core::schema::schema_markdown(),
+    )
+}
+
+/// 並びが違うファイルだけ書き直す。書き直した件数を返す。
+fn canonicalize_records(storage: &dyn Storage) -> Result<usize, String> {
+    let mut updated = 0;
+    for filename in storage.list().map_err(|e| e.to_string())? {
+        // 読めないファイルはそのまま（一括チェックで見つける）
+        let Ok(bytes) = storage.read(&filename) else {
+            continue;
+        };
+        let Ok(v) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+            continue;
+        };
+        if bytes != to_canonical_json(&v).as_bytes() {
+            // 読んだ形のまま書き直す（間にほかで変わっていれば、その中身を並べ直す）
+            versions::update(storage, &filename, |_| Ok(true)).map_err(|e| format!("{}: {}", filename, e))?;
+            updated += 1;
+        }
+    }
+    Ok(updated)
+}
+
+/// `POST /api/schema/canonicalize`
+#[utoipa::path(post, path = "/api/schema/canonicalize", tag = "schema", summary = "並びが違うファイルを書き直す", params(LibraryQuery))]
+pub async fn canonicalize(Lib(library): Lib) -> impl IntoResponse {
+    match tokio::task::spawn_blocking(move || canonicalize_records(&*library.storage)).await {
+        Ok(Ok(updated)) => (StatusCode::OK, Json(serde_json::json!({"updated": updated}))).into_response(),
+        Ok(Err(e)) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
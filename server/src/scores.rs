@@ -0,0 +1,139 @@
+//! スコアの尺度の変換（`POST /api/scores/rescale`）。
+//! validation-policy.json の尺度を変えるときに、既存レコードのスコアを新しい尺度へ直線で写す（1〜6 の 6 → 1〜10 の 10）。
+//! 適用前に dry-run で変換結果を確かめられる。適用するとポリシーの尺度も書き換える。
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde_json::Value;
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+use crate::validation::{ScoreScale, ValidationPolicy};
+use crate::versions::{self, Precondition};
+use crate::{display_label_from_value, load_all_records, score_from_value, AppState};
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct RescaleBody {
+    /// 新しい尺度
+    #[schema(value_type = String)]
+    to: ScoreScale,
+    /// true なら書き込まずに結果だけ返す
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(serde::Serialize)]
+struct Change {
+    filename: String,
+    display_label: String,
+    before: f64,
+    after: f64,
+}
+
+#[derive(Default, serde::Serialize)]
+struct RescaleReport {
+    dry_run: bool,
+    changes: Vec<Change>,
+    /// 書き込みに失敗したもの（ファイル名, 理由）
+    failed: Vec<(String, String)>,
+    /// 変換後のポリシー（dry-run でも返す）
+    policy: ValidationPolicy,
+}
+
+/// 整数なら整数のまま書く（1〜6 の既存レコードと同じ形）
+fn score_value(v: f64) -> Value {
+    if v.fract() == 0.0 {
+        Value::from(v as i64)
+    } else {
+        Value::from(v)
+    }
+}
+
+/// ポリシーの尺度を `to` にし、ルールの min_score も同じように写す。
+fn rescale_policy(policy: &ValidationPolicy, to: &ScoreScale) -> ValidationPolicy {
+    let mut p = policy.clone();
+    for rule in &mut p.rules {
+        rule.min_score = rule.min_score.map(|s| policy.score.rescale(s, to));
+    }
+    p.score = *to;
+    p
+}
+
+/// `POST /api/scores/rescale` スコアのあるレコード（0 より大きいもの）を今の尺度から `to` に変換する。
+#[utoipa::path(
+    post,
+    path = "/api/scores/rescale",
+    tag = "maintenance",
+    summary = "スコアの尺度を変える",
+    params(LibraryQuery),
+    request_body = RescaleBody,
+)]
+pub async fn rescale(State(state): State<AppState>, Lib(library): Lib, Json(body): Json<RescaleBody>) -> impl IntoResponse {
+    if !body.to.is_valid() {
+        return error(StatusCode::BAD_REQUEST, "score scale needs min < max, step > 0 and at most 100 steps");
+    }
+    let policy = match crate::policy::load_policy(&state.db_path) {
+        Ok(p) => p,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    let Some(records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    let from = policy.score;
+    let mut report = RescaleReport {
+        dry_run: body.dry_run,
+        policy: rescale_policy(&policy, &body.to),
+        ..Default::default()
+    };
+    for (filename, mut v) in records {
+        let Some(before) = score_from_value(&v).filter(|&s| s > 0.0) else {
+            continue;
+        };
+        let after = from.rescale(before, &body.to);
+        if after == before && score_value(after) == v["score"] {
+            continue;
+        }
+        if !body.dry_run {
+            let read = Precondition::Version(versions::version_of(&v));
+            v["score"] = score_value(after);
+            if let Err(e) = versions::write(&*library.storage, &filename, &read, &v) {
+                report.failed.push((filename, e.to_string()));
+                continue;
+            }
+        }
+        report.changes.push(Change { display_label: display_label_from_value(&v), filename, before, after });
+    }
+    if !body.dry_run {
+        if let Err(e) = crate::policy::write_policy(&state.db_path, &report.policy) {
+            return error(StatusCode::INTERNAL_SERVER_ERROR, e);
+        }
+    }
+    (StatusCode::OK, Json(report)).into_response()
+}
+
+#[cfg(test)]
+mod scores_tests {
+    use super::{rescale_policy, score_value};
+    use crate::validation::{RequiredRule, ScoreScale, ValidationPolicy};
+
+    #[test]
+    fn policy_rules_follow_the_new_scale() {
+        let policy = ValidationPolicy {
+            rules: vec![RequiredRule {
+                field: "references".into(),
+                required: true,
+                main: String::new(),
+                badge: String::new(),
+                min_score: Some(6.0),
+            }],
+            score: ScoreScale::default(),
+            release_gap_years: None,
+            large_record_kb: None,
+        };
+        let ten = ScoreScale { min: 1.0, max: 10.0, step: 1.0 };
+        let p = rescale_policy(&policy, &ten);
+        assert_eq!(p.score, ten);
+        assert_eq!(p.rules[0].min_score, Some(10.0));
+        assert_eq!(score_value(4.0), serde_json::json!(4));
+        assert_eq!(score_value(4.5), serde_json::json!(4.5));
+    }
+}
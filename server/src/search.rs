@@ -0,0 +1,229 @@
+//! `/api/search` の全文検索。大文字小文字を区別しない部分一致で、
+//! ヒット箇所の前後を切り出したスニペットを返す。
+//! 対象はタイトル（別題を含む）・表示ラベル・personnel の名前・トラック名・作曲者、`comments=true` ならコメントも。
+
+use axum::{
+    extract::Query,
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::Value;
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::{error, field_error};
+use crate::{display_label_from_value, load_all_records};
+
+/// スニペットでヒット箇所の前後に残す文字数
+const SNIPPET_CONTEXT_CHARS: usize = 30;
+
+#[derive(serde::Deserialize)]
+pub struct SearchParams {
+    q: String,
+    /// true のとき comment 本文も検索対象にする
+    #[serde(default)]
+    comments: bool,
+}
+
+/// ヒット箇所を before / hit / after に分けたスニペット（フロントで hit を強調表示する）。
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct Snippet {
+    before: String,
+    hit: String,
+    after: String,
+}
+
+/// personnel の役割（名前の欄を持つもの）
+pub(crate) const PERSONNEL_ROLES: &[&str] = &["leader", "sidemen", "soloists", "conductor", "orchestra", "company"];
+
+#[derive(serde::Serialize)]
+struct SearchMatch {
+    /// ヒットしたフィールド（"title", "display_label", "personnel", "composers", "tracks.title", "tracks.composer", "comment"）
+    field: String,
+    snippet: Snippet,
+}
+
+#[derive(serde::Serialize)]
+struct SearchResult {
+    filename: String,
+    display_label: String,
+    matches: Vec<SearchMatch>,
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// `text` 内で `query` に最初にヒットした箇所のスニペットを返す（大文字小文字は区別しない）。
+pub fn find_snippet(text: &str, query: &str) -> Option<Snippet> {
+    let hay: Vec<char> = text.chars().collect();
+    let needle: Vec<char> = query.chars().map(fold).collect();
+    if needle.is_empty() || needle.len() > hay.len() {
+        return None;
+    }
+    let start = (0..=hay.len() - needle.len())
+        .find(|&i| hay[i..i + needle.len()].iter().map(|&c| fold(c)).eq(needle.iter().copied()))?;
+    let end = start + needle.len();
+    let from = start.saturating_sub(SNIPPET_CONTEXT_CHARS);
+    let to = (end + SNIPPET_CONTEXT_CHARS).min(hay.len());
+    let mut before: String = hay[from..start].iter().collect();
+    if from > 0 {
+        before.insert(0, '…');
+    }
+    let mut after: String = hay[end..to].iter().collect();
+    if to < hay.len() {
+        after.push('…');
+    }
+    Some(Snippet {
+        before,
+        hit: hay[start..end].iter().collect(),
+        after,
+    })
+}
+
+/// personnel の名前（別名・グループ名・略称・メンバーを含む）
+fn personnel_names(v: &Value) -> Vec<&str> {
+    let personnel = &v["personnel"];
+    let mut names: Vec<&str> = PERSONNEL_ROLES
+        .iter()
+        .filter_map(|role| personnel[role].as_array())
+        .flatten()
+        .flat_map(|e| [e["name"].as_str(), e["name_alt"].as_str()])
+        .flatten()
+        .collect();
+    for g in personnel["group"].as_array().into_iter().flatten() {
+        names.extend([g["name"].as_str(), g["abbr"].as_str()].into_iter().flatten());
+        for m in g["members"].as_array().into_iter().flatten() {
+            names.extend([m["name"].as_str(), m["name_alt"].as_str()].into_iter().flatten());
+        }
+    }
+    names
+}
+
+/// トラックの作曲者（文字列または共作の配列）
+fn composers(v: &Value) -> Vec<&str> {
+    v["tracks"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|t| match &t["composer"] {
+            Value::String(s) => vec![s.as_str()],
+            Value::Array(a) => a.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        })
+        .collect()
+}
+
+fn search_record(v: &Value, display_label: &str, params: &SearchParams) -> Vec<SearchMatch> {
+    let q = params.q.trim();
+    let track_titles: Vec<&str> = v["tracks"].as_array().into_iter().flatten().filter_map(|t| t["title"].as_str()).collect();
+    let mut fields: Vec<(&str, Vec<&str>)> = vec![
+        ("title", vec![v["title"].as_str().unwrap_or(""), v["title_alt"].as_str().unwrap_or("")]),
+        ("display_label", vec![display_label]),
+        ("personnel", personnel_names(v)),
+        ("tracks.title", track_titles),
+        ("composers", v["composers"].as_array().into_iter().flatten().filter_map(Value::as_str).collect()),
+        ("tracks.composer", composers(v)),
+    ];
+    if params.comments {
+        fields.push(("comment", vec![v["comment"].as_str().unwrap_or("")]));
+    }
+    // 同じフィールドで複数ヒットしても最初の 1 件だけ返す
+    fields
+        .into_iter()
+        .filter_map(|(field, texts)| {
+            texts.into_iter().find_map(|text| find_snippet(text, q)).map(|snippet| SearchMatch {
+                field: field.to_string(),
+                snippet,
+            })
+        })
+        .collect()
+}
+
+#[utoipa::path(get, path = "/api/search", tag = "records", summary = "全文検索", params(LibraryQuery))]
+pub async fn search(
+    Lib(library): Lib,
+    Query(params): Query<SearchParams>,
+) -> impl IntoResponse {
+    if params.q.trim().is_empty() {
+        return field_error("q", "is required");
+    }
+    let Some(records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    let results: Vec<SearchResult> = records
+        .into_iter()
+        .filter_map(|(filename, v)| {
+            let display_label = display_label_from_value(&v);
+            let matches = search_record(&v, &display_label, &params);
+            if matches.is_empty() {
+                None
+            } else {
+                Some(SearchResult {
+                    filename,
+                    display_label,
+                    matches,
+                })
+            }
+        })
+        .collect();
+    (StatusCode::OK, Json(results)).into_response()
+}
+
+#[cfg(test)]
+mod snippet_tests {
+    use super::{find_snippet, Snippet};
+
+    #[test]
+    fn case_insensitive_hit_keeps_original_text() {
+        assert_eq!(
+            find_snippet("Midnight Mood", "mood"),
+            Some(Snippet {
+                before: "Midnight ".into(),
+                hit: "Mood".into(),
+                after: String::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn long_text_is_trimmed_with_ellipsis() {
+        let text = format!("{}儚い美しさ{}", "あ".repeat(40), "い".repeat(40));
+        let s = find_snippet(&text, "儚い").unwrap();
+        assert_eq!(s.before, format!("…{}", "あ".repeat(30)));
+        assert_eq!(s.after, format!("美しさ{}…", "い".repeat(27)));
+    }
+
+    #[test]
+    fn no_hit() {
+        assert_eq!(find_snippet("Alone", "together"), None);
+        assert_eq!(find_snippet("Alone", ""), None);
+    }
+}
+
+#[cfg(test)]
+mod search_tests {
+    use super::{search_record, SearchParams};
+    use serde_json::json;
+
+    #[test]
+    fn personnel_tracks_and_composers_are_searched() {
+        let v = json!({
+            "title": "Moanin'",
+            "personnel": {"leader": [{"name": "Art Blakey"}],
+                          "group": [{"name": "The Jazz Messengers", "abbr": "JM", "members": [{"name": "Lee Morgan"}]}]},
+            "composers": ["Bobby Timmons"],
+            "tracks": [{"title": "Along Came Betty", "composer": ["Benny Golson"]}],
+            "comment": "Lee Morgan のソロ"
+        });
+        let params = |q: &str, comments| SearchParams { q: q.into(), comments };
+        let fields = |q: &str, comments| -> Vec<String> {
+            search_record(&v, "Art Blakey: Moanin'", &params(q, comments)).into_iter().map(|m| m.field).collect()
+        };
+        assert_eq!(fields("lee morgan", false), vec!["personnel"]);
+        assert_eq!(fields("lee morgan", true), vec!["personnel", "comment"]);
+        assert_eq!(fields("betty", false), vec!["tracks.title"]);
+        assert_eq!(fields("golson", false), vec!["tracks.composer"]);
+        assert_eq!(fields("timmons", false), vec!["composers"]);
+    }
+}
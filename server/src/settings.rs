@@ -0,0 +1,200 @@
+//! アプリの設定。`{DB_PATH}/.config/settings.json` に保存する（レコード一覧には出ない）。
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard};
+
+use crate::date::UtcOffset;
+use crate::filename::validate_classical_pattern;
+use crate::label::{validate_template, LabelTemplates};
+use crate::listen::write_json;
+use crate::import_conflict::ConflictPolicy;
+use crate::metadata::validate_provider;
+use crate::problem::{error, field_error, Problem};
+use crate::AppState;
+
+/// 設定ファイルを置くディレクトリ（DB_PATH からの相対）
+pub const CONFIG_DIR: &str = ".config";
+const SETTINGS_FILE: &str = "settings.json";
+
+/// バッジの凡例（例: "🔴" = "リッピングし直し"）
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BadgeLegend {
+    pub badge: String,
+    pub label: String,
+}
+
+/// サイドバーの表示形式。表示はフロントが組み立てるので、サーバーは値をそのまま保存するだけ。
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SidebarDisplay {
+    /// "comfortable"（既定）/ "compact"
+    #[serde(default)]
+    pub density: String,
+    /// "artist_title"（既定）/ "title_artist"
+    #[serde(default)]
+    pub label_format: String,
+    #[serde(default)]
+    pub show_score: bool,
+    #[serde(default)]
+    pub show_year: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Settings {
+    /// サイドバーのバッジの凡例（表示順）
+    #[serde(default)]
+    pub badges: Vec<BadgeLegend>,
+    /// フロントでエラーが起きたとき、診断情報を `/api/client-errors` に送る
+    #[serde(default)]
+    pub report_client_errors: bool,
+    /// 入力チェックで保存できなかった項目とルールを `/api/validation-failures` で記録する（値は記録しない）
+    #[serde(default)]
+    pub log_validation_failures: bool,
+    /// フロントの保存の待ち時間（秒）。0 なら既定値（10 秒）。
+    #[serde(default)]
+    pub save_timeout_secs: u32,
+    #[serde(default)]
+    pub sidebar: SidebarDisplay,
+    /// 保存時の記録日（date）の扱い: "first_logged"（既定）/ "last_updated" / "ask"。フロントが使う
+    #[serde(default)]
+    pub date_mode: String,
+    /// 保存のたびに updated_date を今日にする
+    #[serde(default)]
+    pub track_updated_date: bool,
+    /// 日付（今日・ファイルの更新日）を数える時差（例 "+09:00"）。空ならサーバーは UTC、フロントはブラウザの時差
+    #[serde(default)]
+    pub utc_offset: String,
+    /// メインジャンルごとの表示ラベルのテンプレート（例: "Classical" → "{composer}: {title}"）
+    #[serde(default)]
+    pub label_templates: LabelTemplates,
+    /// Classical のファイル名の候補のパターン（例 "{soloist_last}_{instrument}__{composer_last}_{title}"）。フロントが使う
+    #[serde(default)]
+    pub classical_filename: String,
+    /// リリース情報を探す外部のデータベース（"musicbrainz" / "discogs" / "vgmdb"）。空なら MusicBrainz
+    #[serde(default)]
+    pub metadata_provider: String,
+    /// 取り込みで既存のレコードとぶつかったときの扱い（取り込みごとに変えられる）
+    #[serde(default)]
+    pub import_conflict: ConflictPolicy,
+}
+
+/// 保存の待ち時間の上限（秒）
+const SAVE_TIMEOUT_MAX_SECS: u32 = 600;
+
+/// ライブラリの時差（分）。起動時と設定の保存時に `utc_offset` から入れる
+static LIBRARY_OFFSET_MINUTES: AtomicI32 = AtomicI32::new(0);
+
+/// サーバーで日付を数えるときの時差
+pub fn library_offset() -> UtcOffset {
+    UtcOffset::from_minutes(LIBRARY_OFFSET_MINUTES.load(Ordering::Relaxed)).unwrap_or_default()
+}
+
+/// 表示ラベルのテンプレート。起動時と設定の保存時に入れる
+static LABEL_TEMPLATES: RwLock<LabelTemplates> = RwLock::new(LabelTemplates::new());
+
+/// サーバーで表示ラベルを作るときのテンプレート
+pub fn label_templates() -> RwLockReadGuard<'static, LabelTemplates> {
+    LABEL_TEMPLATES.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// 設定のうちサーバーの動きに関わるもの（時差・ラベルのテンプレート）を反映する。読めない時差は UTC
+pub fn apply(settings: &Settings) {
+    let offset = UtcOffset::parse(&settings.utc_offset).unwrap_or_default();
+    LIBRARY_OFFSET_MINUTES.store(offset.minutes(), Ordering::Relaxed);
+    *LABEL_TEMPLATES.write().unwrap_or_else(PoisonError::into_inner) = settings.label_templates.clone();
+}
+
+/// `{DB_PATH}/.config/{name}` のパス
+pub fn config_path(db_path: &Path, name: &str) -> PathBuf {
+    db_path.join(CONFIG_DIR).join(name)
+}
+
+/// `.config/{name}` の JSON を読む。ファイルがなければ既定値
+pub fn load_config_json<T: serde::de::DeserializeOwned + Default>(db_path: &Path, name: &str) -> Result<T, String> {
+    match fs::read_to_string(config_path(db_path, name)) {
+        Ok(s) => serde_json::from_str(&s).map_err(|e| format!("{}: {}", name, e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// `.config/{name}` に JSON で書く（ディレクトリがなければ作る）
+pub fn save_config_json<T: serde::Serialize>(db_path: &Path, name: &str, v: &T) -> Result<(), String> {
+    fs::create_dir_all(db_path.join(CONFIG_DIR)).map_err(|e| e.to_string())?;
+    write_json(&config_path(db_path, name), &serde_json::to_value(v).map_err(|e| e.to_string())?)
+}
+
+/// 設定を読む。ファイルがなければ既定値。
+pub fn load_settings(db_path: &Path) -> Result<Settings, String> {
+    match fs::read_to_string(config_path(db_path, SETTINGS_FILE)) {
+        Ok(data) => serde_json::from_str(&data).map_err(|e| format!("invalid settings: {}", e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Settings::default()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// `GET /api/settings`
+#[utoipa::path(get, path = "/api/settings", tag = "settings", summary = "設定")]
+pub async fn get_settings(State(state): State<AppState>) -> impl IntoResponse {
+    match load_settings(&state.db_path) {
+        Ok(s) => (StatusCode::OK, Json(s)).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// `POST /api/settings` 設定全体を置き換える。バッジが空の凡例は捨てる。
+#[utoipa::path(post, path = "/api/settings", tag = "settings", summary = "設定を保存する", request_body = serde_json::Value)]
+pub async fn save_settings(State(state): State<AppState>, Json(mut body): Json<Settings>) -> impl IntoResponse {
+    body.badges.retain(|b| !b.badge.trim().is_empty());
+    for b in &mut body.badges {
+        b.badge = b.badge.trim().to_string();
+        b.label = b.label.trim().to_string();
+    }
+    body.save_timeout_secs = body.save_timeout_secs.min(SAVE_TIMEOUT_MAX_SECS);
+    if !body.utc_offset.trim().is_empty() {
+        let Some(offset) = UtcOffset::parse(&body.utc_offset) else {
+            return field_error("utc_offset", "must be like +09:00");
+        };
+        body.utc_offset = offset.to_string();
+    } else {
+        body.utc_offset.clear();
+    }
+    let templates = std::mem::take(&mut body.label_templates.0);
+    for (genre, template) in templates {
+        let (genre, template) = (genre.trim().to_string(), template.trim().to_string());
+        if genre.is_empty() || template.is_empty() {
+            continue;
+        }
+        if let Err(e) = validate_template(&template) {
+            let problem = Problem::new(StatusCode::BAD_REQUEST, format!("{}: {}", genre, e)).field(format!("label_templates.{}", genre), e);
+            return problem.into_response();
+        }
+        body.label_templates.0.insert(genre, template);
+    }
+    body.classical_filename = body.classical_filename.trim().to_string();
+    if !body.classical_filename.is_empty() {
+        if let Err(e) = validate_classical_pattern(&body.classical_filename) {
+            return field_error("classical_filename", e);
+        }
+    }
+    body.metadata_provider = body.metadata_provider.trim().to_string();
+    if !body.metadata_provider.is_empty() {
+        if let Err(e) = validate_provider(&body.metadata_provider) {
+            return field_error("metadata_provider", e);
+        }
+    }
+    let path = config_path(&state.db_path, SETTINGS_FILE);
+    let res = fs::create_dir_all(state.db_path.join(CONFIG_DIR))
+        .map_err(|e| e.to_string())
+        .and_then(|_| serde_json::to_value(&body).map_err(|e| e.to_string()))
+        .and_then(|v| write_json(&path, &v));
+    match res {
+        Ok(()) => {
+            apply(&body);
+            (StatusCode::OK, Json(body)).into_response()
+        }
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
@@ -0,0 +1,443 @@
+//! `/api/stats` の集計（完成度・スコア分布・ジャンル・年代・曲数・人数）。
+//! 完成度は nekokan_music_core の `completeness` で数える（フォームのメーターと同じ）。
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::completeness::completeness_from_value;
+use crate::date::{self, Date};
+use crate::libraries::{Lib, LibraryQuery};
+use crate::names::NameIndex;
+use crate::problem::error;
+use crate::search::PERSONNEL_ROLES;
+use crate::validation::ScoreScale;
+use crate::{display_label_from_value, load_all_records, AppState};
+
+#[derive(serde::Serialize)]
+struct CompletenessStats {
+    /// 平均完成度（%）
+    average: f64,
+    /// 完成度 100% のレコード数
+    complete: usize,
+    /// 10% 刻みの件数（0〜9%, 10〜19%, …, 90〜100%）
+    buckets: [usize; 10],
+}
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+struct ScoreBucket {
+    score: f64,
+    count: usize,
+}
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+struct ScoreStats {
+    /// 設定中の尺度（validation-policy.json の score）
+    scale: ScoreScale,
+    /// 尺度の値ごとの件数（小さい順、0 件の値も含む）
+    buckets: Vec<ScoreBucket>,
+    /// 尺度に合わないスコアの件数（尺度を変えたあと変換していないレコードなど）
+    out_of_scale: usize,
+    /// スコアなし（0 または欠落）の件数
+    unscored: usize,
+}
+
+/// スコアを尺度の値ごとに数える。
+fn score_stats(scores: impl IntoIterator<Item = Option<f64>>, scale: &ScoreScale) -> ScoreStats {
+    let mut buckets: Vec<ScoreBucket> = scale.values().into_iter().map(|score| ScoreBucket { score, count: 0 }).collect();
+    let (mut out_of_scale, mut unscored) = (0, 0);
+    for s in scores {
+        match s {
+            None => unscored += 1,
+            Some(s) if s <= 0.0 => unscored += 1,
+            Some(s) => match buckets.iter_mut().find(|b| (b.score - s).abs() < 1e-9) {
+                Some(b) => b.count += 1,
+                None => out_of_scale += 1,
+            },
+        }
+    }
+    ScoreStats { scale: *scale, buckets, out_of_scale, unscored }
+}
+
+#[derive(serde::Serialize, Debug, Default, PartialEq)]
+pub(crate) struct GenreCount {
+    pub(crate) genre: String,
+    pub(crate) count: usize,
+    /// サブジャンルごとの件数（多い順）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) sub: Vec<GenreCount>,
+}
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+struct ComposerCount {
+    /// 正規名（名前の台帳の別名はまとめる）
+    name: String,
+    count: usize,
+}
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+struct DecadeCount {
+    /// 1950 なら 1950〜1959 年
+    decade: i64,
+    count: usize,
+}
+
+/// ジャンル・年代・曲数・人数の集計（ダッシュボードと curl 向け）
+#[derive(serde::Serialize, Debug, Default, PartialEq)]
+pub(crate) struct LibraryStats {
+    /// メインジャンルごとの件数（多い順、同数は名前順）。未入力は ""
+    pub(crate) genres: Vec<GenreCount>,
+    /// 発売年の年代ごとの件数（古い順）
+    decades: Vec<DecadeCount>,
+    /// 発売年が未入力のレコード数
+    unknown_year: usize,
+    /// 全レコードのトラック数の合計
+    pub(crate) tracks: usize,
+    /// personnel に出てくる人の数（同じ人は 1 人。名前の台帳の別名もまとめる）
+    personnel: usize,
+    /// レコードの composers（作品の作曲者）ごとのレコード数（多い順、同数は名前順）
+    composers: Vec<ComposerCount>,
+}
+
+fn by_count_desc(counts: BTreeMap<String, usize>) -> Vec<(String, usize)> {
+    let mut v: Vec<(String, usize)> = counts.into_iter().collect();
+    v.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    v
+}
+
+/// personnel の人の名前（役割ごとの name とグループのメンバー。グループ名そのものは数えない）
+fn personnel_people(v: &Value) -> impl Iterator<Item = &str> {
+    let personnel = &v["personnel"];
+    let roles = PERSONNEL_ROLES.iter().filter_map(|role| personnel[role].as_array()).flatten();
+    let members = personnel["group"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|g| g["members"].as_array())
+        .flatten();
+    roles.chain(members).filter_map(|e| e["name"].as_str()).filter(|n| !n.trim().is_empty())
+}
+
+pub(crate) fn library_stats<'a>(records: impl IntoIterator<Item = &'a Value>, names: &NameIndex) -> LibraryStats {
+    let mut genres: BTreeMap<String, (usize, BTreeMap<String, usize>)> = BTreeMap::new();
+    let mut decades: BTreeMap<i64, usize> = BTreeMap::new();
+    let mut people = HashSet::new();
+    let mut composers: BTreeMap<String, usize> = BTreeMap::new();
+    let mut stats = LibraryStats::default();
+    for v in records {
+        let main = v["janre"]["main"].as_str().unwrap_or("").trim().to_string();
+        let entry = genres.entry(main).or_default();
+        entry.0 += 1;
+        for sub in v["janre"]["sub"].as_array().into_iter().flatten().filter_map(Value::as_str) {
+            if !sub.trim().is_empty() {
+                *entry.1.entry(sub.trim().to_string()).or_default() += 1;
+            }
+        }
+        match crate::int_from_value(&v["release_year"]).filter(|&y| y > 0) {
+            Some(y) => *decades.entry(y.div_euclid(10) * 10).or_default() += 1,
+            None => stats.unknown_year += 1,
+        }
+        stats.tracks += v["tracks"].as_array().map_or(0, Vec::len);
+        people.extend(personnel_people(v).map(|n| names.canonical(n).to_lowercase()));
+        let on_record: HashSet<String> = v["composers"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .filter(|c| !c.trim().is_empty())
+            .map(|c| names.canonical(c))
+            .collect();
+        for c in on_record {
+            *composers.entry(c).or_default() += 1;
+        }
+    }
+    let mut main: Vec<GenreCount> = genres
+        .into_iter()
+        .map(|(genre, (count, sub))| GenreCount {
+            genre,
+            count,
+            sub: by_count_desc(sub).into_iter().map(|(genre, count)| GenreCount { genre, count, sub: vec![] }).collect(),
+        })
+        .collect();
+    main.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.genre.cmp(&b.genre)));
+    stats.genres = main;
+    stats.decades = decades.into_iter().map(|(decade, count)| DecadeCount { decade, count }).collect();
+    stats.personnel = people.len();
+    stats.composers = by_count_desc(composers).into_iter().map(|(name, count)| ComposerCount { name, count }).collect();
+    stats
+}
+
+#[derive(serde::Serialize)]
+struct Stats {
+    total: usize,
+    completeness: CompletenessStats,
+    scores: ScoreStats,
+    #[serde(flatten)]
+    library: LibraryStats,
+}
+
+#[utoipa::path(get, path = "/api/stats", tag = "reports", summary = "統計", params(LibraryQuery))]
+pub async fn get_stats(State(state): State<AppState>, Lib(library): Lib) -> impl IntoResponse {
+    let Some(records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    let policy = match crate::policy::load_policy(&state.db_path) {
+        Ok(p) => p,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    // 台帳が読めなくても集計はする（別名をまとめないだけ）
+    let names = crate::names::load_names(&state.db_path).unwrap_or_default().index();
+    let covers = crate::covers::cover_files(&library.dir);
+    let mut buckets = [0usize; 10];
+    let mut sum = 0u64;
+    let mut complete = 0;
+    for (_, v) in &records {
+        let p = completeness_from_value(v, covers.contains_cover_of(v));
+        sum += u64::from(p);
+        if p == 100 {
+            complete += 1;
+        }
+        buckets[usize::from(p / 10).min(9)] += 1;
+    }
+    let total = records.len();
+    let average = if total == 0 { 0.0 } else { sum as f64 / total as f64 };
+    let stats = Stats {
+        total,
+        completeness: CompletenessStats {
+            average,
+            complete,
+            buckets,
+        },
+        scores: score_stats(records.iter().map(|(_, v)| crate::score_from_value(v)), &policy.score),
+        library: library_stats(records.iter().map(|(_, v)| v), &names),
+    };
+    (StatusCode::OK, Json(stats)).into_response()
+}
+
+/// ファイルの更新日時などを "YYYY/MM/DD" にする。日の区切りはライブラリの時差（設定の `utc_offset`）。
+pub(crate) fn date_from_system_time(t: std::time::SystemTime) -> Option<String> {
+    let secs = t.duration_since(std::time::UNIX_EPOCH).ok()?.as_secs() as i64;
+    Some(Date::from_unix_secs(secs, crate::settings::library_offset()).to_string())
+}
+
+/// 現在時刻を RFC 3339（UTC, 秒まで）で返す。例: "2026-10-16T09:53:20Z"
+pub(crate) fn now_timestamp() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    date::timestamp_from_unix_secs(secs)
+}
+
+/// ファイルの更新日時などを RFC 3339（UTC）にする。
+pub(crate) fn timestamp_from_system_time(t: std::time::SystemTime) -> Option<String> {
+    let secs = t.duration_since(std::time::UNIX_EPOCH).ok()?.as_secs() as i64;
+    Some(date::timestamp_from_unix_secs(secs))
+}
+
+/// その日を含む週の月曜日（通算日数）。1970/01/01 は木曜日。
+fn week_start(days: i64) -> i64 {
+    days - (days + 3).rem_euclid(7)
+}
+
+#[derive(serde::Deserialize)]
+pub struct ListenStatsParams {
+    /// 集計開始日（YYYY/MM/DD、この日を含む）。省略時は制限なし。
+    from: Option<String>,
+    /// 集計終了日（YYYY/MM/DD、この日を含む）。省略時は制限なし。
+    to: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct RecordListens {
+    filename: String,
+    display_label: String,
+    count: usize,
+}
+
+#[derive(serde::Serialize)]
+struct WeekListens {
+    /// 週の初め（月曜日, YYYY/MM/DD）
+    week_start: String,
+    count: usize,
+}
+
+#[derive(serde::Serialize)]
+struct GenreShare {
+    genre: String,
+    count: usize,
+    /// 期間内の全再生に占める割合（0.0〜1.0）
+    ratio: f64,
+}
+
+#[derive(serde::Serialize)]
+struct ListenStats {
+    from: Option<String>,
+    to: Option<String>,
+    total: usize,
+    /// 再生回数の多い順（同数はラベル順）
+    records: Vec<RecordListens>,
+    /// 期間内の週ごとの再生回数（再生のない週も 0 で埋める）
+    weeks: Vec<WeekListens>,
+    /// Main ジャンル別の再生回数（多い順）
+    genres: Vec<GenreShare>,
+}
+
+/// `GET /api/stats/listens?from=&to=` 期間内の再生記録を集計する。
+#[utoipa::path(get, path = "/api/stats/listens", tag = "reports", summary = "再生の統計", params(LibraryQuery))]
+pub async fn get_listen_stats(
+    Lib(library): Lib,
+    Query(params): Query<ListenStatsParams>,
+) -> impl IntoResponse {
+    let from = params.from.filter(|s| !s.trim().is_empty());
+    let to = params.to.filter(|s| !s.trim().is_empty());
+    for d in from.iter().chain(to.iter()) {
+        if !date::is_valid(d) {
+            return error(StatusCode::BAD_REQUEST, "from/to must be YYYY/MM/DD");
+        }
+    }
+    let Some(records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    let in_range = |d: &str| {
+        from.as_deref().is_none_or(|f| d >= f) && to.as_deref().is_none_or(|t| d <= t)
+    };
+    let mut per_record = Vec::new();
+    let mut per_week: std::collections::BTreeMap<i64, usize> = Default::default();
+    let mut per_genre: std::collections::HashMap<String, usize> = Default::default();
+    let mut total = 0;
+    for (filename, v) in &records {
+        let Some(listens) = v["listens"].as_array() else {
+            continue;
+        };
+        let days: Vec<i64> = listens
+            .iter()
+            .filter_map(|d| d.as_str())
+            .filter(|d| in_range(d))
+            .filter_map(Date::parse)
+            .map(Date::days)
+            .collect();
+        if days.is_empty() {
+            continue;
+        }
+        for &d in &days {
+            *per_week.entry(week_start(d)).or_default() += 1;
+        }
+        let genre = v["janre"]["main"].as_str().unwrap_or("").to_string();
+        *per_genre.entry(genre).or_default() += days.len();
+        total += days.len();
+        per_record.push(RecordListens {
+            filename: filename.clone(),
+            display_label: display_label_from_value(v),
+            count: days.len(),
+        });
+    }
+    per_record.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.display_label.cmp(&b.display_label)));
+
+    let mut weeks = Vec::new();
+    if let (Some(&first), Some(&last)) = (per_week.keys().next(), per_week.keys().next_back()) {
+        let mut w = first;
+        while w <= last {
+            weeks.push(WeekListens {
+                week_start: Date::from_days(w).to_string(),
+                count: per_week.get(&w).copied().unwrap_or(0),
+            });
+            w += 7;
+        }
+    }
+
+    let mut genres: Vec<GenreShare> = per_genre
+        .into_iter()
+        .map(|(genre, count)| GenreShare {
+            genre,
+            count,
+            ratio: count as f64 / total as f64,
+        })
+        .collect();
+    genres.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.genre.cmp(&b.genre)));
+
+    let stats = ListenStats {
+        from,
+        to,
+        total,
+        records: per_record,
+        weeks,
+        genres,
+    };
+    (StatusCode::OK, Json(stats)).into_response()
+}
+
+#[cfg(test)]
+mod date_tests {
+    use super::{date_from_system_time, week_start};
+    use crate::date::Date;
+
+    fn days(s: &str) -> i64 {
+        Date::parse(s).unwrap().days()
+    }
+
+    #[test]
+    fn system_time_becomes_a_date() {
+        let d = days("2024/02/29");
+        let t = std::time::UNIX_EPOCH + std::time::Duration::from_secs(d as u64 * 86_400 + 3_600);
+        assert_eq!(date_from_system_time(t).as_deref(), Some("2024/02/29"));
+    }
+
+    #[test]
+    fn week_starts_on_monday() {
+        // 2026/10/16 は金曜日、その週の月曜は 10/12
+        assert_eq!(Date::from_days(week_start(days("2026/10/16"))).to_string(), "2026/10/12");
+        let monday = days("2026/10/12");
+        assert_eq!(week_start(monday), monday);
+    }
+}
+
+#[cfg(test)]
+mod score_stats_tests {
+    use super::score_stats;
+    use crate::validation::ScoreScale;
+
+    #[test]
+    fn scores_bucket_by_configured_scale() {
+        let half = ScoreScale { min: 0.5, max: 2.0, step: 0.5 };
+        let stats = score_stats([Some(1.5), Some(1.5), Some(2.0), Some(6.0), Some(0.0), None], &half);
+        let counts: Vec<(f64, usize)> = stats.buckets.iter().map(|b| (b.score, b.count)).collect();
+        assert_eq!(counts, vec![(0.5, 0), (1.0, 0), (1.5, 2), (2.0, 1)]);
+        assert_eq!((stats.out_of_scale, stats.unscored), (1, 2));
+    }
+}
+
+#[cfg(test)]
+mod library_stats_tests {
+    use super::library_stats;
+    use crate::names::{NameRegistry, Person};
+    use serde_json::json;
+
+    #[test]
+    fn genres_decades_tracks_and_people() {
+        let records = [
+            json!({"janre": {"main": "Jazz", "sub": ["Hard Bop"]}, "release_year": 1959, "tracks": [{}, {}],
+                   "personnel": {"leader": [{"name": "Thelonious Monk"}], "sidemen": [{"name": "Art Blakey"}]}}),
+            json!({"janre": {"main": "Jazz", "sub": ["Hard Bop", "Bebop"]}, "release_year": 1952, "tracks": [{}],
+                   "personnel": {"leader": [{"name": "T. Monk"}]}}),
+            json!({"janre": {"main": "Classical", "sub": []}, "release_year": 0, "tracks": [], "composers": ["T. Monk", "Bach", "Bach"],
+                   "personnel": {"group": [{"name": "Quartet", "members": [{"name": "art blakey"}, {"name": "Ron Carter"}]}]}}),
+        ];
+        let names = NameRegistry { people: vec![Person { name: "Thelonious Monk".into(), aliases: vec!["T. Monk".into()] }] }.index();
+        let stats = library_stats(records.iter(), &names);
+        let genres: Vec<(&str, usize)> = stats.genres.iter().map(|g| (g.genre.as_str(), g.count)).collect();
+        assert_eq!(genres, [("Jazz", 2), ("Classical", 1)]);
+        let sub: Vec<(&str, usize)> = stats.genres[0].sub.iter().map(|g| (g.genre.as_str(), g.count)).collect();
+        assert_eq!(sub, [("Hard Bop", 2), ("Bebop", 1)]);
+        let decades: Vec<(i64, usize)> = stats.decades.iter().map(|d| (d.decade, d.count)).collect();
+        assert_eq!(decades, [(1950, 2)]);
+        assert_eq!((stats.unknown_year, stats.tracks, stats.personnel), (1, 3, 3));
+        let composers: Vec<(&str, usize)> = stats.composers.iter().map(|c| (c.name.as_str(), c.count)).collect();
+        assert_eq!(composers, [("Bach", 1), ("Thelonious Monk", 1)]);
+    }
+}
@@ -0,0 +1,392 @@
+//! レコード JSON の置き場所。
+//! 既定は DB_PATH 直下にフラットに置くが、数万件規模ではディレクトリ一覧が遅くなるため、
+//! `DB_LAYOUT` で先頭文字（prefix）・ハッシュ（hash）のサブディレクトリに分け、`DB_COMPRESS=zstd` で圧縮して置ける。
+//! どの置き方でも API から見えるファイル名は "xxx.json" のまま変わらない。
+
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::attachments::ATTACHMENTS_DIR;
+use crate::covers::COVERS_DIR;
+use crate::migrations;
+use crate::schema::to_canonical_json;
+
+/// 圧縮したレコードの拡張子（"xxx.json.zst"）
+pub const ZSTD_EXT: &str = ".zst";
+const ZSTD_LEVEL: i32 = 3;
+
+/// レコードの読み書き。ファイル名はいずれも "xxx.json"（検証済み）。
+pub trait Storage: Send + Sync {
+    /// 全レコードのファイル名（名前順）
+    fn list(&self) -> io::Result<Vec<String>>;
+    /// レコードの中身（圧縮していれば展開したもの）
+    fn read(&self, filename: &str) -> io::Result<Vec<u8>>;
+    fn write(&self, filename: &str, data: &[u8]) -> io::Result<()>;
+    fn modified(&self, filename: &str) -> io::Result<SystemTime>;
+    /// レコードを消す。どこにもなければ NotFound。
+    fn remove(&self, filename: &str) -> io::Result<()>;
+    /// レコードの名前を変える（中身はそのまま）。`to` が既にあれば AlreadyExists。
+    fn rename(&self, from: &str, to: &str) -> io::Result<()>;
+
+    fn exists(&self, filename: &str) -> bool {
+        self.modified(filename).is_ok()
+    }
+}
+
+/// サブディレクトリへの振り分け方
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layout {
+    /// DB_PATH 直下
+    Flat,
+    /// ファイル名の先頭 1 文字（英数字以外は "_"）
+    Prefix,
+    /// ファイル名のハッシュ下位 8 ビット（"00"〜"ff" の 256 個）。かな・漢字のファイル名が多いならこちら。
+    Hash,
+}
+
+impl Layout {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "" | "flat" => Some(Self::Flat),
+            "prefix" => Some(Self::Prefix),
+            "hash" => Some(Self::Hash),
+            _ => None,
+        }
+    }
+}
+
+/// 振り分け先のサブディレクトリ名（Flat なら None）
+pub fn shard_of(layout: Layout, filename: &str) -> Option<String> {
+    match layout {
+        Layout::Flat => None,
+        Layout::Prefix => Some(
+            filename
+                .chars()
+                .next()
+                .filter(char::is_ascii_alphanumeric)
+                .map_or("_".to_string(), |c| c.to_ascii_lowercase().to_string()),
+        ),
+        Layout::Hash => {
+            // FNV-1a（32 bit）。プロセスやバージョンをまたいで同じ値になるものを使う。
+            let h = filename
+                .bytes()
+                .fold(0x811c_9dc5u32, |h, b| (h ^ u32::from(b)).wrapping_mul(0x0100_0193));
+            Some(format!("{:02x}", h & 0xff))
+        }
+    }
+}
+
+/// 書きかけのファイルを残さないよう、同じディレクトリの一時ファイルに書いて fsync してから置き換える。
+/// 途中で落ちても、元のファイルか新しいファイルのどちらかが残る。
+pub fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    static SEQ: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let seq = SEQ.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    // ".json" で終わらない名前にして、一覧に出ないようにする
+    let tmp = dir.join(format!(".{}.{}-{}.tmp", name, std::process::id(), seq));
+    let result = (|| {
+        let mut f = fs::File::create(&tmp)?;
+        f.write_all(data)?;
+        f.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result?;
+    // 置き換えたこと自体も残るようにディレクトリも同期する（Windows ではディレクトリを開けないので飛ばす）
+    if let Ok(d) = fs::File::open(dir) {
+        let _ = d.sync_all();
+    }
+    Ok(())
+}
+
+/// ファイルシステム上のストレージ
+#[derive(Clone)]
+pub struct FsStorage {
+    root: PathBuf,
+    layout: Layout,
+    compress: bool,
+}
+
+impl FsStorage {
+    pub fn new(root: impl Into<PathBuf>, layout: Layout, compress: bool) -> Self {
+        Self { root: root.into(), layout, compress }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// レコードが置かれうる場所（いまの設定の場所と、設定を変える前の場所）
+    pub fn record_paths(&self, filename: &str) -> Vec<PathBuf> {
+        self.candidates(filename).into_iter().map(|(p, _)| p).collect()
+    }
+
+    fn path_for(&self, dir: Option<&str>, filename: &str, compressed: bool) -> PathBuf {
+        let dir = dir.map_or(self.root.clone(), |d| self.root.join(d));
+        if compressed {
+            dir.join(format!("{}{}", filename, ZSTD_EXT))
+        } else {
+            dir.join(filename)
+        }
+    }
+
+    /// 読み込み時に探す場所。いまの設定の場所を先に、設定を変える前の置き方（フラット・非圧縮）も探す。
+    fn candidates(&self, filename: &str) -> Vec<(PathBuf, bool)> {
+        let shard = shard_of(self.layout, filename);
+        let mut out: Vec<(PathBuf, bool)> = Vec::new();
+        for dir in [shard.as_deref(), None] {
+            for compressed in [self.compress, !self.compress] {
+                let p = (self.path_for(dir, filename, compressed), compressed);
+                if !out.contains(&p) {
+                    out.push(p);
+                }
+            }
+        }
+        out
+    }
+
+    fn locate(&self, filename: &str) -> io::Result<(PathBuf, bool)> {
+        self.candidates(filename)
+            .into_iter()
+            .find(|(p, _)| p.is_file())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} not found", filename)))
+    }
+
+    fn collect_dir(dir: &Path, names: &mut BTreeSet<String>) -> io::Result<()> {
+        for entry in fs::read_dir(dir)?.filter_map(|e| e.ok()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let name = name.strip_suffix(ZSTD_EXT).unwrap_or(&name);
+            if name.ends_with(".json") && entry.path().is_file() {
+                names.insert(name.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// 設定と違う場所・形式にあるレコードを設定どおりに置き直す。置き直した件数を返す。
+    pub fn migrate(&self) -> io::Result<usize> {
+        let mut moved = 0;
+        for filename in self.list()? {
+            let target = self.path_for(shard_of(self.layout, &filename).as_deref(), &filename, self.compress);
+            if !target.is_file() {
+                let data = self.read(&filename)?;
+                self.write(&filename, &data)?;
+                moved += 1;
+            }
+        }
+        Ok(moved)
+    }
+}
+
+impl Storage for FsStorage {
+    fn list(&self) -> io::Result<Vec<String>> {
+        let mut names = BTreeSet::new();
+        Self::collect_dir(&self.root, &mut names)?;
+        if self.layout != Layout::Flat {
+            for entry in fs::read_dir(&self.root)?.filter_map(|e| e.ok()) {
+                let name = entry.file_name().to_string_lossy().to_string();
+                // .config / .notes / covers / attachments はレコードのディレクトリではない
+                if entry.path().is_dir() && !name.starts_with('.') && name != COVERS_DIR && name != ATTACHMENTS_DIR {
+                    Self::collect_dir(&entry.path(), &mut names)?;
+                }
+            }
+        }
+        Ok(names.into_iter().collect())
+    }
+
+    fn read(&self, filename: &str) -> io::Result<Vec<u8>> {
+        let (path, compressed) = self.locate(filename)?;
+        let data = fs::read(path)?;
+        if compressed {
+            zstd::decode_all(data.as_slice())
+        } else {
+            Ok(data)
+        }
+    }
+
+    fn write(&self, filename: &str, data: &[u8]) -> io::Result<()> {
+        let shard = shard_of(self.layout, filename);
+        if let Some(ref d) = shard {
+            fs::create_dir_all(self.root.join(d))?;
+        }
+        let target = self.path_for(shard.as_deref(), filename, self.compress);
+        if self.compress {
+            write_atomic(&target, &zstd::encode_all(data, ZSTD_LEVEL)?)?;
+        } else {
+            write_atomic(&target, data)?;
+        }
+        // 設定を変える前の場所に残った古い版は消す（一覧に二重に出ないように）
+        for (p, _) in self.candidates(filename) {
+            if p != target && p.is_file() {
+                fs::remove_file(p)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn modified(&self, filename: &str) -> io::Result<SystemTime> {
+        fs::metadata(self.locate(filename)?.0)?.modified()
+    }
+
+    fn remove(&self, filename: &str) -> io::Result<()> {
+        // 置き方を変える前の場所に残っている版もまとめて消す
+        let mut removed = false;
+        for (p, _) in self.candidates(filename) {
+            if p.is_file() {
+                fs::remove_file(p)?;
+                removed = true;
+            }
+        }
+        if removed {
+            Ok(())
+        } else {
+            Err(io::Error::new(io::ErrorKind::NotFound, format!("{} not found", filename)))
+        }
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        if self.exists(to) {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", to)));
+        }
+        let (path, compressed) = self.locate(from)?;
+        let shard = shard_of(self.layout, to);
+        if let Some(ref d) = shard {
+            fs::create_dir_all(self.root.join(d))?;
+        }
+        // 同じ DB_PATH の中なので fs::rename で一度に置き換わる（途中で止まっても両方あるか片方だけ）
+        fs::rename(path, self.path_for(shard.as_deref(), to, compressed))?;
+        // 置き方を変える前の場所に残っている古い版は消す
+        for (p, _) in self.candidates(from) {
+            if p.is_file() {
+                fs::remove_file(p)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// レコードを読めなかった理由
+pub enum ReadError {
+    NotFound(String),
+    Invalid(String),
+    /// このサーバーより新しい形式（`schema_version`）
+    Unsupported(String),
+}
+
+impl ReadError {
+    pub fn status(&self) -> axum::http::StatusCode {
+        match self {
+            ReadError::NotFound(_) => axum::http::StatusCode::NOT_FOUND,
+            ReadError::Invalid(_) | ReadError::Unsupported(_) => axum::http::StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            ReadError::NotFound(e) => format!("file not found: {}", e),
+            ReadError::Invalid(e) => format!("invalid json: {}", e),
+            ReadError::Unsupported(e) => e.clone(),
+        }
+    }
+}
+
+/// レコードを JSON として読む。古い形式（`schema_version`）のものは今の形式に直して返す（migrations.rs）。
+/// Issue #14: UTF-8 でないファイル（BOM つき・古いエンコーディング）も読めるよう lossy で文字列にする。
+pub fn read_record(storage: &dyn Storage, filename: &str) -> Result<Value, ReadError> {
+    let bytes = storage.read(filename).map_err(|e| ReadError::NotFound(e.to_string()))?;
+    let mut v: Value = serde_json::from_str(&String::from_utf8_lossy(&bytes)).map_err(|e| ReadError::Invalid(e.to_string()))?;
+    migrations::upgrade(&mut v).map_err(ReadError::Unsupported)?;
+    Ok(v)
+}
+
+/// レコードを整形した JSON で書く。項目は決まった並び（`schema::RECORD_FIELDS`）にする。
+/// `schema_version` のない値はフォームやサーバーが今の形式で作ったものなので今の版を付け、古い版のものは直してから書く。
+pub fn write_record(storage: &dyn Storage, filename: &str, v: &Value) -> Result<(), String> {
+    let mut v = v.clone();
+    match v.as_object_mut() {
+        Some(obj) if !obj.contains_key("schema_version") => {
+            obj.insert("schema_version".into(), Value::from(migrations::SCHEMA_VERSION));
+        }
+        _ => {
+            migrations::upgrade(&mut v)?;
+        }
+    }
+    storage.write(filename, to_canonical_json(&v).as_bytes()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod storage_tests {
+    use super::{shard_of, write_atomic, FsStorage, Layout, Storage};
+    use std::fs;
+
+    #[test]
+    fn atomic_write_leaves_no_temp_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let p = dir.path().join("a.json");
+        write_atomic(&p, br#"{"v":1}"#).unwrap();
+        write_atomic(&p, br#"{"v":2}"#).unwrap();
+        assert_eq!(fs::read_to_string(&p).unwrap(), r#"{"v":2}"#);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn shards_are_stable() {
+        assert_eq!(shard_of(Layout::Flat, "Bill_Evans__Alone.json"), None);
+        assert_eq!(shard_of(Layout::Prefix, "Bill_Evans__Alone.json").as_deref(), Some("b"));
+        assert_eq!(shard_of(Layout::Prefix, "秋吉敏子__孤軍.json").as_deref(), Some("_"));
+        let h = shard_of(Layout::Hash, "Bill_Evans__Alone.json").unwrap();
+        assert_eq!(h.len(), 2);
+        assert_eq!(shard_of(Layout::Hash, "Bill_Evans__Alone.json").unwrap(), h);
+    }
+
+    #[test]
+    fn flat_records_migrate_to_compressed_shards() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("Bill_Evans__Alone.json"), r#"{"title":"Alone"}"#).unwrap();
+        fs::create_dir(dir.path().join(".config")).unwrap();
+        fs::write(dir.path().join(".config").join("settings.json"), "{}").unwrap();
+
+        let storage = FsStorage::new(dir.path(), Layout::Prefix, true);
+        // 移行前でも読める
+        assert_eq!(storage.read("Bill_Evans__Alone.json").unwrap(), br#"{"title":"Alone"}"#);
+        assert_eq!(storage.migrate().unwrap(), 1);
+        assert!(!dir.path().join("Bill_Evans__Alone.json").exists());
+        assert!(dir.path().join("b").join("Bill_Evans__Alone.json.zst").is_file());
+        assert_eq!(storage.list().unwrap(), vec!["Bill_Evans__Alone.json".to_string()]);
+        assert_eq!(storage.read("Bill_Evans__Alone.json").unwrap(), br#"{"title":"Alone"}"#);
+        assert_eq!(storage.migrate().unwrap(), 0);
+    }
+
+    #[test]
+    fn remove_deletes_every_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("Bill_Evans__Alone.json"), "{}").unwrap();
+        let storage = FsStorage::new(dir.path(), Layout::Prefix, false);
+        fs::create_dir(dir.path().join("b")).unwrap();
+        fs::write(dir.path().join("b").join("Bill_Evans__Alone.json"), "{}").unwrap();
+        storage.remove("Bill_Evans__Alone.json").unwrap();
+        assert!(storage.list().unwrap().is_empty());
+        assert_eq!(storage.remove("Bill_Evans__Alone.json").unwrap_err().kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn rename_moves_into_the_new_shard() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = FsStorage::new(dir.path(), Layout::Prefix, true);
+        storage.write("Bil_Evans__Alone.json", b"{}").unwrap();
+        storage.write("Bill_Evans__Waltz.json", b"{}").unwrap();
+        storage.rename("Bil_Evans__Alone.json", "Bill_Evans__Alone.json").unwrap();
+        assert_eq!(storage.list().unwrap(), vec!["Bill_Evans__Alone.json", "Bill_Evans__Waltz.json"]);
+        assert_eq!(storage.read("Bill_Evans__Alone.json").unwrap(), b"{}");
+        let kind = |r: std::io::Result<()>| r.unwrap_err().kind();
+        assert_eq!(kind(storage.rename("Bill_Evans__Alone.json", "Bill_Evans__Waltz.json")), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(kind(storage.rename("Nobody.json", "Somebody.json")), std::io::ErrorKind::NotFound);
+    }
+}
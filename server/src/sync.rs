@@ -0,0 +1,357 @@
+//! 2 台のサーバーの同期（自宅と VPS など）。こちらを「取り込む側」にして、取り込み元（primary）の変更を引いてくる。
+//! 取り込み元の `GET /api/sync/manifest`（ファイル名・更新日時・中身のハッシュ）と前回の同期時のハッシュを比べ、
+//! 向こうだけが変わったものを取り込む。こちらでも変わっていたものは上書きせず、衝突として報告する。
+//! 扱うのはレコードだけ（カバー・メモ・添付は対象外）。こちらで消したものも消したまま（消えたことは報告する）。
+//! `POST /api/sync/pull` で手動、`interval_minutes` を設定すれば定期的に取り込む。
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::problem::error;
+use crate::settings::{load_config_json, save_config_json};
+use crate::stats::{now_timestamp, timestamp_from_system_time};
+use crate::versions::{self, Precondition};
+use crate::AppState;
+
+const CONFIG_FILE: &str = "sync.json";
+const STATE_FILE: &str = "sync-state.json";
+/// 取り込み元への 1 リクエストの待ち時間
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+/// 定期取り込みの時刻を確かめる間隔
+const SCHEDULER_TICK: Duration = Duration::from_secs(60);
+
+/// 取り込み中か（同時に 2 つ走らせない）
+static PULLING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SyncConfig {
+    /// 取り込み元の URL（"https://music.example.com"）。空なら同期しない
+    #[serde(default)]
+    pub primary: String,
+    /// 取り込み元が読み取りにもトークンを求めるとき（Authorization: Bearer）
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub token: String,
+    /// 自動で取り込む間隔（分）。0 なら手動だけ
+    #[serde(default)]
+    pub interval_minutes: u32,
+}
+
+/// 前回の同期の状態
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct SyncState {
+    /// ファイル名 → 前回そろえたときの中身のハッシュ
+    #[serde(default)]
+    synced: BTreeMap<String, String>,
+    #[serde(default)]
+    last_report: Option<SyncReport>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ManifestEntry {
+    pub filename: String,
+    /// 最終更新日時（RFC 3339）。表示用で、判定にはハッシュを使う
+    #[serde(default)]
+    pub modified: Option<String>,
+    pub hash: String,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SyncConflict {
+    pub filename: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SyncReport {
+    /// RFC 3339
+    pub at: String,
+    pub dry_run: bool,
+    /// 取り込んだ（dry_run なら取り込む）もの
+    pub pulled: Vec<String>,
+    pub up_to_date: usize,
+    /// こちらだけで変えた・消したもの（そのまま）
+    pub local_changes: Vec<String>,
+    /// 両方で変わっていて取り込まなかったもの
+    pub conflicts: Vec<SyncConflict>,
+    /// 取り込み元にないもの（向こうで消したか、こちらで作ったもの）
+    pub only_here: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// 中身のハッシュ。整形や圧縮の違いで変わらないよう、読んだ値を詰めた JSON にして計る。
+pub fn value_hash(v: &Value) -> String {
+    let bytes = serde_json::to_vec(v).unwrap_or_default();
+    Sha256::digest(&bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[derive(Debug, PartialEq)]
+enum Action {
+    UpToDate,
+    Pull,
+    /// こちらだけが変わった（消した）
+    LocalChange,
+    Conflict(&'static str),
+}
+
+/// こちらのハッシュ・取り込み元のハッシュ・前回そろえたときのハッシュから、どうするか決める
+fn decide(local: Option<&str>, remote: &str, synced: Option<&str>) -> Action {
+    match (local, synced) {
+        (Some(l), _) if l == remote => Action::UpToDate,
+        (None, None) => Action::Pull,
+        (None, Some(s)) if s == remote => Action::LocalChange,
+        (None, Some(_)) => Action::Conflict("こちらで消したが、取り込み元で変わっている"),
+        (Some(l), Some(s)) if l == s => Action::Pull,
+        (Some(_), Some(s)) if s == remote => Action::LocalChange,
+        (Some(_), Some(_)) => Action::Conflict("両方で変わっている"),
+        (Some(_), None) => Action::Conflict("同期する前から両方にあり、中身が違う"),
+    }
+}
+
+pub fn load_config(db_path: &Path) -> Result<SyncConfig, String> {
+    load_config_json(db_path, CONFIG_FILE)
+}
+
+/// URL のパスに入れるためのエスケープ（英数字と - _ . ~ 以外）
+fn encode_path_segment(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn fetch_json(cfg: &SyncConfig, path: &str) -> Result<Value, String> {
+    let url = format!("{}{}", cfg.primary.trim_end_matches('/'), path);
+    let mut req = ureq::get(&url).timeout(HTTP_TIMEOUT);
+    if !cfg.token.is_empty() {
+        req = req.set("Authorization", &format!("Bearer {}", cfg.token));
+    }
+    match req.call() {
+        Ok(resp) => resp.into_json().map_err(|e| format!("{}: {}", url, e)),
+        Err(ureq::Error::Status(code, resp)) => {
+            let msg: Value = resp.into_json().unwrap_or(Value::Null);
+            Err(format!("{}: {} {}", url, code, crate::problem::message_of(&msg)))
+        }
+        Err(e) => Err(format!("{}: {}", url, e)),
+    }
+}
+
+/// 取り込む（ブロッキング。HTTP を使うので spawn_blocking から呼ぶ）
+fn pull(state: &AppState, cfg: &SyncConfig, dry_run: bool) -> Result<SyncReport, String> {
+    let manifest: Vec<ManifestEntry> =
+        serde_json::from_value(fetch_json(cfg, "/api/sync/manifest")?).map_err(|e| format!("manifest: {}", e))?;
+    let mut sync_state: SyncState = load_config_json(&state.db_path, STATE_FILE)?;
+    // ファイル名 → (中身のハッシュ, 版)。取り込むときは、ここで読んだ版のままのときだけ書く
+    let local: BTreeMap<String, (String, String)> = state
+        .records
+        .records(&*state.storage)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|(name, r)| (name, (value_hash(&r.value), versions::version_of(&r.value))))
+        .collect();
+
+    let mut report = SyncReport { at: now_timestamp(), dry_run, ..Default::default() };
+    let remote_names: BTreeSet<&str> = manifest.iter().map(|e| e.filename.as_str()).collect();
+    for entry in &manifest {
+        if crate::normalize_filename(&entry.filename).as_deref() != Some(entry.filename.as_str()) {
+            report.errors.push(format!("{}: ファイル名が不正なので飛ばしました", entry.filename));
+            continue;
+        }
+        let local_hash = local.get(&entry.filename).map(|(hash, _)| hash.as_str());
+        match decide(local_hash, &entry.hash, sync_state.synced.get(&entry.filename).map(String::as_str)) {
+            Action::UpToDate => {
+                report.up_to_date += 1;
+                if !dry_run {
+                    sync_state.synced.insert(entry.filename.clone(), entry.hash.clone());
+                }
+            }
+            Action::LocalChange => report.local_changes.push(entry.filename.clone()),
+            Action::Conflict(reason) => {
+                report.conflicts.push(SyncConflict { filename: entry.filename.clone(), reason: reason.to_string() })
+            }
+            Action::Pull if dry_run => report.pulled.push(entry.filename.clone()),
+            Action::Pull => {
+                let read = match local.get(&entry.filename) {
+                    Some((_, version)) => Precondition::Version(version.clone()),
+                    None => Precondition::Absent,
+                };
+                let res = fetch_json(cfg, &format!("/api/files/{}", encode_path_segment(&entry.filename)))
+                    .and_then(|mut v| {
+                        // 古いサーバーからのものは、こちらで読んだときと同じ形にしてから書いて数える
+                        crate::migrations::upgrade(&mut v)?;
+                        versions::write(&*state.storage, &entry.filename, &read, &v).map(|_| value_hash(&v)).map_err(|e| e.to_string())
+                    });
+                match res {
+                    Ok(hash) => {
+                        sync_state.synced.insert(entry.filename.clone(), hash);
+                        report.pulled.push(entry.filename.clone());
+                    }
+                    Err(e) => report.errors.push(e),
+                }
+            }
+        }
+    }
+    report.only_here = local.keys().filter(|n| !remote_names.contains(n.as_str())).cloned().collect();
+    sync_state.last_report = Some(report.clone());
+    save_config_json(&state.db_path, STATE_FILE, &sync_state)?;
+    Ok(report)
+}
+
+/// 取り込みを 1 回走らせる。走っている最中なら Err。
+async fn run_pull(state: AppState, dry_run: bool) -> Result<SyncReport, (StatusCode, String)> {
+    let cfg = load_config(&state.db_path).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    if cfg.primary.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "取り込み元（primary）が設定されていません".into()));
+    }
+    if PULLING.swap(true, Ordering::SeqCst) {
+        return Err((StatusCode::CONFLICT, "同期の最中です".into()));
+    }
+    let res = tokio::task::spawn_blocking(move || pull(&state, &cfg, dry_run)).await;
+    PULLING.store(false, Ordering::SeqCst);
+    match res {
+        Ok(Ok(report)) => Ok(report),
+        Ok(Err(e)) => Err((StatusCode::BAD_GATEWAY, e)),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+/// `GET /api/sync/manifest`（取り込み元として）全レコードのファイル名・更新日時・ハッシュ
+#[utoipa::path(get, path = "/api/sync/manifest", tag = "sync", summary = "全レコードのハッシュ（取り込む側が読む）")]
+pub async fn manifest(State(state): State<AppState>) -> impl IntoResponse {
+    match state.records.records(&*state.storage) {
+        Ok(records) => {
+            let list: Vec<ManifestEntry> = records
+                .into_iter()
+                .map(|(filename, r)| ManifestEntry {
+                    hash: value_hash(&r.value),
+                    modified: r.modified.and_then(timestamp_from_system_time),
+                    filename,
+                })
+                .collect();
+            (StatusCode::OK, Json(list)).into_response()
+        }
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+#[derive(Default, serde::Deserialize, utoipa::ToSchema)]
+pub struct PullBody {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// `POST /api/sync/pull` `{"dry_run": true}`（本文は省略可）
+#[utoipa::path(post, path = "/api/sync/pull", tag = "sync", summary = "今すぐ取り込む", request_body = PullBody)]
+pub async fn pull_now(State(state): State<AppState>, body: Option<Json<PullBody>>) -> impl IntoResponse {
+    let dry_run = body.map(|b| b.0.dry_run).unwrap_or_default();
+    match run_pull(state, dry_run).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err((status, e)) => error(status, e),
+    }
+}
+
+/// 返すときはトークンを伏せる
+fn public_config(cfg: &SyncConfig) -> Value {
+    serde_json::json!({"primary": cfg.primary, "interval_minutes": cfg.interval_minutes, "has_token": !cfg.token.is_empty()})
+}
+
+/// `GET /api/sync` 設定と前回の結果
+#[utoipa::path(get, path = "/api/sync", tag = "sync", summary = "同期の設定と前回の結果")]
+pub async fn get_status(State(state): State<AppState>) -> impl IntoResponse {
+    let (cfg, sync_state) = match (load_config(&state.db_path), load_config_json::<SyncState>(&state.db_path, STATE_FILE)) {
+        (Ok(c), Ok(s)) => (c, s),
+        (Err(e), _) | (_, Err(e)) => return error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    (StatusCode::OK, Json(serde_json::json!({"config": public_config(&cfg), "last_report": sync_state.last_report})))
+        .into_response()
+}
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[schema(as = SyncConfigBody)]
+pub struct ConfigBody {
+    primary: String,
+    /// None なら今のトークンのまま、空文字なら消す
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default)]
+    interval_minutes: u32,
+}
+
+/// `POST /api/sync` 設定を保存する
+#[utoipa::path(post, path = "/api/sync", tag = "sync", summary = "同期の設定を保存する", request_body = ConfigBody)]
+pub async fn save_config(State(state): State<AppState>, Json(body): Json<ConfigBody>) -> impl IntoResponse {
+    let primary = body.primary.trim().trim_end_matches('/').to_string();
+    if !(primary.is_empty() || primary.starts_with("http://") || primary.starts_with("https://")) {
+        return error(StatusCode::BAD_REQUEST, "取り込み元は http:// か https:// で始まる URL");
+    }
+    let mut cfg = match load_config(&state.db_path) {
+        Ok(c) => c,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    cfg.primary = primary;
+    cfg.interval_minutes = body.interval_minutes;
+    if let Some(t) = body.token {
+        cfg.token = t.trim().to_string();
+    }
+    match save_config_json(&state.db_path, CONFIG_FILE, &cfg) {
+        Ok(()) => (StatusCode::OK, Json(public_config(&cfg))).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+/// 定期取り込み。設定は毎回読み直すので、画面で間隔を変えればそのまま効く。
+pub fn spawn_scheduler(state: AppState) {
+    tokio::spawn(async move {
+        let mut last_run = tokio::time::Instant::now();
+        loop {
+            tokio::time::sleep(SCHEDULER_TICK).await;
+            let Ok(cfg) = load_config(&state.db_path) else {
+                continue;
+            };
+            let interval = Duration::from_secs(u64::from(cfg.interval_minutes) * 60);
+            if cfg.primary.is_empty() || cfg.interval_minutes == 0 || last_run.elapsed() < interval {
+                continue;
+            }
+            last_run = tokio::time::Instant::now();
+            match run_pull(state.clone(), false).await {
+                Ok(r) if !r.conflicts.is_empty() || !r.errors.is_empty() => {
+                    eprintln!("同期: {} 件取り込み、衝突 {} 件、エラー {} 件", r.pulled.len(), r.conflicts.len(), r.errors.len())
+                }
+                Ok(_) => {}
+                Err((_, e)) => eprintln!("同期に失敗しました: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod sync_tests {
+    use super::{decide, encode_path_segment, value_hash, Action};
+    use serde_json::json;
+
+    #[test]
+    fn only_remote_changes_are_pulled() {
+        assert_eq!(decide(Some("a"), "a", None), Action::UpToDate);
+        assert_eq!(decide(None, "a", None), Action::Pull);
+        // こちらは前回のまま、向こうが変わった
+        assert_eq!(decide(Some("a"), "b", Some("a")), Action::Pull);
+        // 向こうは前回のまま、こちらが変わった・消した
+        assert_eq!(decide(Some("b"), "a", Some("a")), Action::LocalChange);
+        assert_eq!(decide(None, "a", Some("a")), Action::LocalChange);
+        assert!(matches!(decide(Some("b"), "c", Some("a")), Action::Conflict(_)));
+        assert!(matches!(decide(Some("b"), "c", None), Action::Conflict(_)));
+    }
+
+    #[test]
+    fn hash_ignores_formatting() {
+        let a: serde_json::Value = serde_json::from_str("{\n  \"title\": \"Alone\",\n  \"score\": 5\n}").unwrap();
+        assert_eq!(value_hash(&a), value_hash(&json!({"score": 5, "title": "Alone"})));
+        assert_eq!(encode_path_segment("秋吉敏子__孤軍.json"), "%E7%A7%8B%E5%90%89%E6%95%8F%E5%AD%90__%E5%AD%A4%E8%BB%8D.json");
+    }
+}
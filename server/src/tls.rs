@@ -0,0 +1,50 @@
+//! HTTPS で待ち受ける設定。`--tls-cert` と `--tls-key`（PEM ファイルのパス、環境変数 `TLS_CERT` / `TLS_KEY` でも可）を
+//! 両方指定すれば HTTPS にする（リバースプロキシなしでほかの端末から使う用）。
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// HTTPS で待ち受けているか（Cookie に Secure を付ける）
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TlsPaths {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+/// 証明書と鍵のどちらか片方だけなら設定の誤り
+fn tls_paths(cert: Option<PathBuf>, key: Option<PathBuf>) -> Result<Option<TlsPaths>, &'static str> {
+    match (cert, key) {
+        (Some(cert), Some(key)) => Ok(Some(TlsPaths { cert, key })),
+        (None, None) => Ok(None),
+        _ => Err("--tls-cert and --tls-key (TLS_CERT / TLS_KEY) must be set together"),
+    }
+}
+
+/// 証明書と鍵のパスを確かめ、HTTPS にするかを覚えておく
+pub fn configure(cert: Option<PathBuf>, key: Option<PathBuf>) -> Result<Option<TlsPaths>, &'static str> {
+    let paths = tls_paths(cert, key)?;
+    ENABLED.store(paths.is_some(), Ordering::Relaxed);
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tls_tests {
+    use super::{tls_paths, TlsPaths};
+
+    #[test]
+    fn cert_and_key_come_together() {
+        assert_eq!(tls_paths(None, None), Ok(None));
+        assert_eq!(
+            tls_paths(Some("c.pem".into()), Some("k.pem".into())),
+            Ok(Some(TlsPaths { cert: "c.pem".into(), key: "k.pem".into() }))
+        );
+        assert!(tls_paths(Some("c.pem".into()), None).is_err());
+        assert!(tls_paths(None, Some("k.pem".into())).is_err());
+    }
+}
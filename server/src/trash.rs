@@ -0,0 +1,182 @@
+//! 削除したレコードのごみ箱。`DELETE /api/files/{name}` で消したレコードの中身を、ライブラリの `.trash` に
+//! "{消した時刻}__{ファイル名}"（"2026-10-16T125034Z__a.json"）で残す。カバー・メモ・添付は残さない。
+//! 保存期間（`--trash-keep` / `--trash-max-days`）を過ぎたものは retention.rs の整理で消す。
+//!
+//! - `GET /api/trash` ごみ箱の中身（新しい順）
+//! - `POST /api/trash/{name}/restore` 元の名前のレコードに戻す（同じ名前のレコードがあれば 409）
+
+use axum::{
+    extract::Path as UrlPath,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::audit::RecordChange;
+use crate::backup::{created_of, snapshot_name};
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+use crate::stats::now_timestamp;
+use crate::storage::write_atomic;
+use crate::versions::{self, Precondition};
+
+/// ライブラリの中のごみ箱のディレクトリ（"." で始まるのでレコードの一覧には出ない。バックアップには入る）
+pub const TRASH_DIR: &str = ".trash";
+
+/// ごみ箱の 1 件
+#[derive(Clone, Debug, PartialEq, serde::Serialize, utoipa::ToSchema)]
+pub struct TrashEntry {
+    /// ごみ箱の中の名前
+    pub name: String,
+    /// 元のファイル名
+    pub filename: String,
+    /// 消した時刻（RFC 3339、UTC）
+    pub deleted: String,
+    pub bytes: u64,
+}
+
+/// "2026-10-16T125034Z__a.json" → ("2026-10-16T125034Z", "a.json")。ごみ箱の名前でなければ None
+fn split_name(name: &str) -> Option<(&str, &str)> {
+    let (stamp, filename) = name.split_once("__")?;
+    let valid = stamp.len() == 18
+        && stamp.as_bytes()[..4].iter().all(u8::is_ascii_digit)
+        && filename.ends_with(".json")
+        && !filename.contains(['/', '\\'])
+        && !filename.contains("..");
+    valid.then_some((stamp, filename))
+}
+
+/// 消すレコードの中身をごみ箱に入れる
+pub fn put(dir: &Path, filename: &str, data: &[u8]) -> io::Result<()> {
+    let trash = dir.join(TRASH_DIR);
+    fs::create_dir_all(&trash)?;
+    write_atomic(&trash.join(format!("{}__{}", snapshot_name(&now_timestamp()), filename)), data)
+}
+
+/// ごみ箱の中身（新しい順）
+pub fn list(dir: &Path) -> io::Result<Vec<TrashEntry>> {
+    let entries = match fs::read_dir(dir.join(TRASH_DIR)) {
+        Ok(e) => e,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut list = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some((stamp, filename)) = split_name(&name) else {
+            continue;
+        };
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        let (deleted, filename) = (created_of(stamp), filename.to_string());
+        list.push(TrashEntry { deleted, filename, bytes: entry.metadata()?.len(), name });
+    }
+    list.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(list)
+}
+
+/// `cutoff`（RFC 3339）より前に消したものと、新しい `keep` 個（0 なら数では消さない）より古いもの。`list` は新しい順
+pub fn expired<'a>(list: &'a [TrashEntry], keep: usize, cutoff: Option<&str>) -> Vec<&'a TrashEntry> {
+    list.iter()
+        .enumerate()
+        .filter(|(i, e)| (keep > 0 && *i >= keep) || cutoff.is_some_and(|c| e.deleted.as_str() < c))
+        .map(|(_, e)| e)
+        .collect()
+}
+
+/// `select` で選んだ名前のものを消す。消したもの
+pub fn remove(dir: &Path, select: impl FnOnce(&[TrashEntry]) -> Vec<String>) -> io::Result<Vec<TrashEntry>> {
+    let list = list(dir)?;
+    let names = select(&list);
+    let mut removed = Vec::new();
+    for e in list.into_iter().filter(|e| names.contains(&e.name)) {
+        fs::remove_file(dir.join(TRASH_DIR).join(&e.name))?;
+        removed.push(e);
+    }
+    Ok(removed)
+}
+
+/// `GET /api/trash`
+#[utoipa::path(
+    get,
+    path = "/api/trash",
+    tag = "records",
+    summary = "ごみ箱（削除したレコード）の中身",
+    params(LibraryQuery),
+    responses((status = 200, description = "新しい順", body = Vec<TrashEntry>)),
+)]
+pub async fn get_list(Lib(library): Lib) -> Response {
+    match tokio::task::spawn_blocking(move || list(&library.dir)).await {
+        Ok(Ok(entries)) => Json(entries).into_response(),
+        Ok(Err(e)) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// `POST /api/trash/{name}/restore`
+#[utoipa::path(post, path = "/api/trash/{name}/restore", tag = "records", summary = "ごみ箱のレコードを元の名前に戻す", params(LibraryQuery))]
+pub async fn restore(Lib(library): Lib, UrlPath(name): UrlPath<String>) -> Response {
+    let Some((_, filename)) = split_name(&name) else {
+        return error(StatusCode::BAD_REQUEST, "invalid path");
+    };
+    let path = library.dir.join(TRASH_DIR).join(&name);
+    let value: Value = match fs::read(&path) {
+        Ok(data) => match serde_json::from_slice(&data) {
+            Ok(v) => v,
+            Err(e) => return error(StatusCode::UNPROCESSABLE_ENTITY, format!("ごみ箱のレコードを読めません: {}", e)),
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return error(StatusCode::NOT_FOUND, "ごみ箱にありません"),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    let written = match versions::write(&*library.storage, filename, &Precondition::Absent, &value) {
+        Ok(w) => w,
+        Err(e) => return e.into_response(),
+    };
+    // 戻したあとに消せなくても、レコードは戻っているので成功として返す
+    let _ = fs::remove_file(&path);
+    let change = RecordChange::saved(filename, None, &written.value).in_library(&library);
+    let mut res = Json(serde_json::json!({"ok": true, "filename": filename, "version": written.version})).into_response();
+    res.extensions_mut().insert(change);
+    res
+}
+
+#[cfg(test)]
+mod trash_tests {
+    use super::{expired, list, put, remove, split_name, TRASH_DIR};
+
+    #[test]
+    fn deleted_records_are_kept_until_they_expire() {
+        let dir = tempfile::tempdir().unwrap();
+        put(dir.path(), "Andrew_Hill__Black_Fire.json", br#"{"title":"Black Fire"}"#).unwrap();
+        let trash = dir.path().join(TRASH_DIR);
+        std::fs::write(trash.join("2026-09-01T000000Z__a.json"), "{}").unwrap();
+        std::fs::write(trash.join("2026-09-20T000000Z__b.json"), "{}").unwrap();
+        std::fs::write(trash.join("notes.txt"), "").unwrap();
+        let entries = list(dir.path()).unwrap();
+        let files: Vec<&str> = entries.iter().map(|e| e.filename.as_str()).collect();
+        assert_eq!(files, ["Andrew_Hill__Black_Fire.json", "b.json", "a.json"]);
+        assert_eq!(entries[2].deleted, "2026-09-01T00:00:00Z");
+
+        let names = |v: Vec<&super::TrashEntry>| v.into_iter().map(|e| e.filename.clone()).collect::<Vec<_>>();
+        assert_eq!(names(expired(&entries, 0, Some("2026-09-15T00:00:00Z"))), ["a.json"]);
+        assert_eq!(names(expired(&entries, 1, None)), ["b.json", "a.json"]);
+        assert!(expired(&entries, 0, None).is_empty());
+
+        let removed = remove(dir.path(), |l| expired(l, 2, None).into_iter().map(|e| e.name.clone()).collect()).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(list(dir.path()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn names_outside_the_trash_are_refused() {
+        assert_eq!(split_name("2026-10-16T125034Z__a__b.json"), Some(("2026-10-16T125034Z", "a__b.json")));
+        assert!(split_name("2026-10-16T125034Z__../a.json").is_none());
+        assert!(split_name("a.json").is_none());
+    }
+}
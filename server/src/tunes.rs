@@ -0,0 +1,186 @@
+//! 曲の索引（`GET /api/tunes?q=`）。全レコードのトラック名を正規化して同じ曲をまとめ、
+//! 収録アルバム・作曲者・長さを並べる（スタンダードの演奏を聴き比べる用）。作曲者は names.rs の台帳で正規名にする。
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::names::NameIndex;
+use crate::problem::{error, field_error};
+use crate::{display_label_from_value, int_from_value, load_all_records, AppState};
+
+/// 返す曲の上限（収録数の多い順）
+const TUNES_MAX: usize = 100;
+
+#[derive(serde::Deserialize)]
+pub struct TunesParams {
+    q: String,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct TuneVersion {
+    filename: String,
+    display_label: String,
+    release_year: Option<i64>,
+    disc_no: Option<i64>,
+    no: Option<i64>,
+    /// トラックに書かれたままの曲名（"Moanin' (Alternate Take)" など）
+    title: String,
+    composer: String,
+    length: String,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct Tune {
+    /// いちばん多い表記（同数なら短いもの）
+    title: String,
+    /// 発売年順（不明は最後）
+    versions: Vec<TuneVersion>,
+}
+
+/// 曲名を比べる形にする。括弧の中（"(Alternate Take)" "[Live]" など）を除き、
+/// 小文字にして英数字・かな・漢字以外を空白にまとめる（"Moanin'" と "Moanin" は同じ曲）。
+pub fn tune_key(title: &str) -> String {
+    let mut depth = 0usize;
+    let mut out = String::new();
+    for c in title.chars() {
+        match c {
+            '(' | '[' | '（' => depth += 1,
+            ')' | ']' | '）' => depth = depth.saturating_sub(1),
+            _ if depth > 0 => {}
+            _ if c.is_alphanumeric() => out.extend(c.to_lowercase()),
+            '\'' | '’' => {}
+            _ => out.push(' '),
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// composer は文字列または文字列配列（共作）。
+fn composer_text(v: &Value, index: &NameIndex) -> String {
+    let names: Vec<&str> = match v {
+        Value::String(s) => vec![s.as_str()],
+        Value::Array(a) => a.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    };
+    names
+        .into_iter()
+        .filter(|n| !n.trim().is_empty())
+        .map(|n| index.canonical(n))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 曲名が `q` を含むトラックを曲ごとにまとめる（収録数の多い順、同数は曲名順）。
+fn collect_tunes(records: &[(String, Value)], q: &str, index: &NameIndex) -> Vec<Tune> {
+    let needle = tune_key(q);
+    let mut groups: HashMap<String, Vec<TuneVersion>> = HashMap::new();
+    for (filename, v) in records {
+        let Some(tracks) = v["tracks"].as_array() else {
+            continue;
+        };
+        let display_label = display_label_from_value(v);
+        for t in tracks {
+            let title = t["title"].as_str().unwrap_or("").trim();
+            let key = tune_key(title);
+            if key.is_empty() || !key.contains(&needle) {
+                continue;
+            }
+            groups.entry(key).or_default().push(TuneVersion {
+                filename: filename.clone(),
+                display_label: display_label.clone(),
+                release_year: int_from_value(&v["release_year"]),
+                disc_no: int_from_value(&t["disc_no"]),
+                no: int_from_value(&t["no"]),
+                title: title.to_string(),
+                composer: composer_text(&t["composer"], index),
+                length: t["length"].as_str().unwrap_or("").to_string(),
+            });
+        }
+    }
+    let mut tunes: Vec<Tune> = groups
+        .into_values()
+        .map(|mut versions| {
+            versions.sort_by(|a, b| {
+                (a.release_year.is_none(), a.release_year, &a.display_label)
+                    .cmp(&(b.release_year.is_none(), b.release_year, &b.display_label))
+            });
+            let mut counts: HashMap<&str, usize> = HashMap::new();
+            for v in &versions {
+                *counts.entry(v.title.as_str()).or_default() += 1;
+            }
+            let title = counts
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.len().cmp(&a.0.len())).then_with(|| b.0.cmp(a.0)))
+                .map(|(t, _)| t.to_string())
+                .unwrap_or_default();
+            Tune { title, versions }
+        })
+        .collect();
+    tunes.sort_by(|a, b| b.versions.len().cmp(&a.versions.len()).then_with(|| a.title.cmp(&b.title)));
+    tunes.truncate(TUNES_MAX);
+    tunes
+}
+
+/// `GET /api/tunes?q=`
+#[utoipa::path(get, path = "/api/tunes", tag = "reports", summary = "曲の索引", params(LibraryQuery))]
+pub async fn get_tunes(State(state): State<AppState>, Lib(library): Lib, Query(params): Query<TunesParams>) -> impl IntoResponse {
+    if tune_key(&params.q).is_empty() {
+        return field_error("q", "is required");
+    }
+    let index = match crate::names::load_names(&state.db_path) {
+        Ok(r) => r.index(),
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    let Some(records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    (StatusCode::OK, Json(collect_tunes(&records, &params.q, &index))).into_response()
+}
+
+#[cfg(test)]
+mod tunes_tests {
+    use super::{collect_tunes, tune_key};
+    use crate::names::{NameRegistry, Person};
+    use serde_json::json;
+
+    #[test]
+    fn keys_ignore_takes_and_apostrophes() {
+        assert_eq!(tune_key("Moanin' (Alternate Take)"), "moanin");
+        assert_eq!(tune_key("MOANIN"), "moanin");
+        assert_eq!(tune_key("'Round Midnight [Live]"), "round midnight");
+        assert_eq!(tune_key("(Take 2)"), "");
+    }
+
+    #[test]
+    fn versions_group_across_records() {
+        let records = vec![
+            (
+                "b.json".to_string(),
+                json!({"title": "Moanin'", "release_year": 1958, "personnel": {"leader": [{"name": "Art Blakey"}]},
+                       "tracks": [{"disc_no": 1, "no": 1, "title": "Moanin'", "composer": "Bobby Timmons", "length": "9:35"},
+                                  {"disc_no": 1, "no": 2, "title": "Are You Real", "composer": "Benny Golson", "length": "4:48"}]}),
+            ),
+            (
+                "a.json".to_string(),
+                json!({"title": "Live", "release_year": 1959, "personnel": {"leader": [{"name": "Art Blakey"}]},
+                       "tracks": [{"disc_no": 1, "no": 3, "title": "Moanin (Live)", "composer": ["B. Timmons"], "length": "11:02"}]}),
+            ),
+        ];
+        let names = NameRegistry {
+            people: vec![Person { name: "Bobby Timmons".into(), aliases: vec!["B. Timmons".into()] }],
+        };
+        let tunes = collect_tunes(&records, "moanin'", &names.index());
+        assert_eq!(tunes.len(), 1);
+        assert_eq!(tunes[0].title, "Moanin'");
+        let years: Vec<Option<i64>> = tunes[0].versions.iter().map(|v| v.release_year).collect();
+        assert_eq!(years, vec![Some(1958), Some(1959)]);
+        assert_eq!(tunes[0].versions[1].composer, "Bobby Timmons");
+    }
+}
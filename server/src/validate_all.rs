@@ -0,0 +1,113 @@
+//! 全レコードの一括チェック（`GET /api/validate-all`）。
+//! 共有クレート（nekokan_music_core）の validate_form を全ファイルにかけ、ファイルごとのエラーを返す。
+//! 入力チェックが厳しくなる前に作ったファイルは、開いて保存するまで今の規則に反したままになるため。
+//! 保存は止めない警告（発売年と録音年の食い違い、大きすぎるレコードなど）も warnings として返す。
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde_json::Value;
+
+use crate::libraries::{Lib, LibraryQuery};
+use crate::problem::error;
+use crate::types::MusicData;
+use crate::validation::{validate_form, warn_form, FieldErrors, ValidationPolicy};
+use crate::{display_label_from_value, AppState};
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct FieldError {
+    /// validate_form のキー（"title"、"tracks.0.length" など）。JSON として読めないときは空
+    field: String,
+    message: String,
+}
+
+#[derive(serde::Serialize)]
+struct Failure {
+    filename: String,
+    display_label: String,
+    errors: Vec<FieldError>,
+    warnings: Vec<FieldError>,
+}
+
+#[derive(serde::Serialize)]
+struct Report {
+    /// チェックしたファイル数
+    checked: usize,
+    failures: Vec<Failure>,
+}
+
+/// キー順に並べる
+fn sorted(map: FieldErrors) -> Vec<FieldError> {
+    let mut list: Vec<FieldError> = map.into_iter().map(|(field, message)| FieldError { field, message }).collect();
+    list.sort_by(|a, b| a.field.cmp(&b.field));
+    list
+}
+
+/// 1 ファイル分のチェック。(エラー, 警告) を返す（どちらもなければ空）。
+pub fn check_record(filename: &str, v: &Value, policy: &ValidationPolicy) -> (Vec<FieldError>, Vec<FieldError>) {
+    let data: MusicData = match serde_json::from_value(v.clone()) {
+        Ok(d) => d,
+        Err(e) => {
+            let error = FieldError { field: String::new(), message: format!("レコードの形式が正しくありません: {}", e) };
+            return (vec![error], vec![]);
+        }
+    };
+    (sorted(validate_form(&data, filename.trim_end_matches(".json"), policy)), sorted(warn_form(&data, policy)))
+}
+
+#[utoipa::path(get, path = "/api/validate-all", tag = "schema", summary = "全レコードの入力チェック", params(LibraryQuery))]
+pub async fn validate_all(State(state): State<AppState>, Lib(library): Lib) -> impl IntoResponse {
+    let Ok(names) = library.storage.list() else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    let policy = match crate::policy::load_policy(&state.db_path) {
+        Ok(p) => p,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    let mut report = Report { checked: names.len(), failures: Vec::new() };
+    for filename in names {
+        // 読めない・JSON として壊れているファイルもここで拾う（load_all_records は飛ばしてしまう）
+        let (display_label, (errors, warnings)) = match crate::storage::read_record(&*library.storage, &filename) {
+            Ok(v) => (display_label_from_value(&v), check_record(&filename, &v, &policy)),
+            Err(e) => (String::new(), (vec![FieldError { field: String::new(), message: e.message() }], vec![])),
+        };
+        if !errors.is_empty() || !warnings.is_empty() {
+            report.failures.push(Failure { filename, display_label, errors, warnings });
+        }
+    }
+    (StatusCode::OK, Json(report)).into_response()
+}
+
+#[cfg(test)]
+mod validate_all_tests {
+    use super::check_record;
+    use crate::validation::ValidationPolicy;
+    use serde_json::json;
+
+    #[test]
+    fn old_records_report_todays_rule_violations() {
+        let v = json!({
+            "title": "Alone",
+            "janre": {"main": "Jazz", "sub": ["Piano"]},
+            "label": "Verve",
+            "id": "V6-8792",
+            "release_year": 1968,
+            "record_year": [1968],
+            "personnel": {"leader": [{"name": "Bill Evans", "instruments": "p", "tracks": ""}]},
+            "tracks": [{"disc_no": 1, "no": 1, "title": "Here's That Rainy Day", "composer": "", "length": "6分"}],
+            "comment": "",
+            "date": "2020/1/5",
+            "score": 5
+        });
+        let (errors, warnings) = check_record("Bill_Evans__Alone.json", &v, &ValidationPolicy::default());
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert!(fields.contains(&"date"), "{:?}", fields);
+        assert!(fields.iter().any(|f| f.starts_with("tracks")), "{:?}", fields);
+        assert!(warnings.is_empty());
+        assert!(!check_record("x.json", &json!({"title": 1}), &ValidationPolicy::default()).0.is_empty());
+
+        let typo = json!({"release_year": 1995, "record_year": [1959]});
+        let mut typo_record = v.clone();
+        typo_record.as_object_mut().unwrap().extend(typo.as_object().unwrap().clone());
+        let (_, warnings) = check_record("Bill_Evans__Alone.json", &typo_record, &ValidationPolicy::default());
+        assert_eq!(warnings.iter().map(|w| w.field.as_str()).collect::<Vec<_>>(), vec!["release_year"]);
+    }
+}
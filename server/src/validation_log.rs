@@ -0,0 +1,209 @@
+//! 入力チェックで保存できなかったときの、どの項目がどのルールで止まったかの記録（`{DB_PATH}/.config/validation-failures.jsonl`）。
+//! 入力チェックはフロントでかけるので、止まったらフロントが `POST /api/validation-failures` で送る。設定の `log_validation_failures` が
+//! オフのあいだは受け取っても捨てる。項目のキーは配列の位置を落として（"tracks[3].title" → "tracks[].title"）、入力した値は送らない。
+//! 一括チェックの「よくあるエラー」は `GET /api/validation-failures/summary` で多い順にまとめたもの。
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::problem::error;
+use crate::settings::{config_path, load_settings, CONFIG_DIR};
+use crate::stats::now_timestamp;
+use crate::AppState;
+
+pub const LOG_FILE: &str = "validation-failures.jsonl";
+/// メッセージの最大文字数（ルールの文なので短いはず）
+const MESSAGE_MAX_CHARS: usize = 200;
+/// 1 回の報告で受け取る項目の数
+const FIELDS_MAX: usize = 100;
+/// まとめで返す組の数
+const SUMMARY_TOP: usize = 20;
+
+/// "personnel.group[0].members[12].tracks" → "personnel.group[].members[].tracks"
+pub fn field_kind(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut in_index = false;
+    for c in field.chars() {
+        match c {
+            '[' => {
+                in_index = true;
+                out.push('[');
+            }
+            ']' => {
+                in_index = false;
+                out.push(']');
+            }
+            _ if in_index => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::ToSchema)]
+pub struct FailureReport {
+    /// どこで止まったか（"form" / "quick_entry"）
+    #[serde(default)]
+    pub source: String,
+    /// 項目のキー → メッセージ
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Failure {
+    field: String,
+    message: String,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct LogLine {
+    received: String,
+    #[serde(default)]
+    source: String,
+    failures: Vec<Failure>,
+}
+
+/// 記録する形にする（キーは位置を落とし、同じ組は 1 つにまとめる）
+fn anonymize(report: FailureReport) -> (String, Vec<Failure>) {
+    let mut failures: Vec<Failure> = Vec::new();
+    for (key, message) in report.fields.into_iter().take(FIELDS_MAX) {
+        let message: String = message.chars().take(MESSAGE_MAX_CHARS).collect();
+        let f = Failure { field: field_kind(&key), message };
+        if !failures.contains(&f) {
+            failures.push(f);
+        }
+    }
+    let source = match report.source.as_str() {
+        "form" | "quick_entry" => report.source,
+        _ => String::new(),
+    };
+    (source, failures)
+}
+
+/// `POST /api/validation-failures`
+#[utoipa::path(
+    post,
+    path = "/api/validation-failures",
+    tag = "schema",
+    summary = "入力チェックで止まった項目を記録する",
+    request_body = FailureReport,
+)]
+pub async fn report(State(state): State<AppState>, Json(body): Json<FailureReport>) -> impl IntoResponse {
+    if !load_settings(&state.db_path).is_ok_and(|s| s.log_validation_failures) {
+        return (StatusCode::OK, Json(serde_json::json!({"ok": true, "logged": false}))).into_response();
+    }
+    let (source, failures) = anonymize(body);
+    if failures.is_empty() {
+        return (StatusCode::OK, Json(serde_json::json!({"ok": true, "logged": false}))).into_response();
+    }
+    let line = LogLine { received: now_timestamp(), source, failures };
+    let res = fs::create_dir_all(state.db_path.join(CONFIG_DIR))
+        .and_then(|_| {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(config_path(&state.db_path, LOG_FILE))
+        })
+        .and_then(|mut f| writeln!(f, "{}", serde_json::to_string(&line).unwrap_or_default()));
+    match res {
+        Ok(()) => (StatusCode::OK, Json(serde_json::json!({"ok": true, "logged": true}))).into_response(),
+        Err(err) => error(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct FailureCount {
+    pub field: String,
+    pub message: String,
+    pub count: usize,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct FailureSummary {
+    /// 記録がオンか
+    pub enabled: bool,
+    /// 保存が止まった回数
+    pub blocked_saves: usize,
+    /// 多い順（同じ数ならキーの順）
+    pub top: Vec<FailureCount>,
+}
+
+/// JSONL を数える。読めない行は飛ばす
+fn summarize(text: &str) -> (usize, Vec<FailureCount>) {
+    let mut counts: HashMap<(String, String), usize> = HashMap::new();
+    let mut blocked = 0;
+    for line in text.lines().filter_map(|l| serde_json::from_str::<LogLine>(l).ok()) {
+        blocked += 1;
+        for f in line.failures {
+            *counts.entry((f.field, f.message)).or_default() += 1;
+        }
+    }
+    let mut top: Vec<FailureCount> =
+        counts.into_iter().map(|((field, message), count)| FailureCount { field, message, count }).collect();
+    top.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.field.cmp(&b.field)).then_with(|| a.message.cmp(&b.message)));
+    top.truncate(SUMMARY_TOP);
+    (blocked, top)
+}
+
+fn load_summary(db_path: &Path) -> Result<FailureSummary, String> {
+    let enabled = load_settings(db_path)?.log_validation_failures;
+    let text = match fs::read_to_string(config_path(db_path, LOG_FILE)) {
+        Ok(t) => t,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.to_string()),
+    };
+    let (blocked_saves, top) = summarize(&text);
+    Ok(FailureSummary { enabled, blocked_saves, top })
+}
+
+/// `GET /api/validation-failures/summary`
+#[utoipa::path(get, path = "/api/validation-failures/summary", tag = "schema", summary = "よくある入力エラー")]
+pub async fn summary(State(state): State<AppState>) -> impl IntoResponse {
+    match tokio::task::spawn_blocking(move || load_summary(&state.db_path)).await {
+        Ok(Ok(s)) => (StatusCode::OK, Json(s)).into_response(),
+        Ok(Err(e)) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod validation_log_tests {
+    use super::{anonymize, field_kind, summarize, FailureReport};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn keys_lose_their_positions() {
+        assert_eq!(field_kind("personnel.group[0].members[12].tracks"), "personnel.group[].members[].tracks");
+        let fields: BTreeMap<String, String> = [
+            ("tracks[0].length", "m:ss 形式"),
+            ("tracks[7].length", "m:ss 形式"),
+            ("title", "必須です"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let (source, failures) = anonymize(FailureReport { source: "somewhere".into(), fields });
+        assert_eq!(source, "");
+        assert_eq!(
+            failures.iter().map(|f| f.field.as_str()).collect::<Vec<_>>(),
+            vec!["title", "tracks[].length"]
+        );
+    }
+
+    #[test]
+    fn summary_counts_most_common_first() {
+        let text = [
+            r#"{"received":"a","source":"form","failures":[{"field":"title","message":"必須です"},{"field":"date","message":"YYYY/MM/DD"}]}"#,
+            "not json",
+            r#"{"received":"b","source":"quick_entry","failures":[{"field":"date","message":"YYYY/MM/DD"}]}"#,
+        ]
+        .join("\n");
+        let (blocked, top) = summarize(&text);
+        assert_eq!(blocked, 2);
+        assert_eq!(top.iter().map(|c| (c.field.as_str(), c.count)).collect::<Vec<_>>(), vec![("date", 2), ("title", 1)]);
+    }
+}
@@ -0,0 +1,75 @@
+//! 入力チェックの制約を JSON で公開する（取り込みスクリプトなどが API に送る前に確かめられるように）。
+//! 制約そのものはフロントと共有の `rules.rs` にあり、必須項目はポリシー（policy.rs）で切り替わる。
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde_json::{json, Value};
+
+use crate::problem::error;
+use crate::validation::{ValidationPolicy, LARGE_RECORD_KB, POLICY_FIELDS, RELEASE_GAP_YEARS};
+use crate::AppState;
+use crate::rules::{Format, FIELD_RULES, FILENAME_FORBIDDEN, FILENAME_MAX_BYTES, UNKNOWN, YEAR_MAX, YEAR_MIN};
+
+pub fn rules_json(policy: &ValidationPolicy) -> Value {
+    let fields: Vec<Value> = FIELD_RULES
+        .iter()
+        .map(|r| {
+            let mut v = json!({"path": r.path, "required": r.required});
+            if let Some(max) = r.max_len {
+                v["max_length"] = json!(max);
+            }
+            if let Some(f) = r.format {
+                v["format"] = json!(f.name());
+            }
+            v
+        })
+        .collect();
+    let formats: serde_json::Map<String, Value> = [Format::Date, Format::Length, Format::Year, Format::Url]
+        .into_iter()
+        .map(|f| (f.name().to_string(), json!(f.description())))
+        .collect();
+    json!({
+        "fields": fields,
+        "formats": formats,
+        "year": {"min": YEAR_MIN, "max": YEAR_MAX},
+        "score": policy.score,
+        // 超えても保存はできる（警告だけ）
+        "release_gap_years": policy.release_gap_years.unwrap_or(RELEASE_GAP_YEARS),
+        // 保存したときにこれ（KB）を超えるレコードを警告する
+        "large_record_kb": policy.large_record_kb.unwrap_or(LARGE_RECORD_KB),
+        "filename": {
+            "max_bytes": FILENAME_MAX_BYTES,
+            "forbidden_chars": FILENAME_FORBIDDEN.iter().map(|c| c.to_string()).collect::<Vec<_>>(),
+        },
+        "max_length_unit": "chars",
+        // 長さ・参加トラックの「不明」。録音年は record_year を空にして record_year_unknown: true
+        "unknown": {"marker": UNKNOWN, "fields": ["tracks[].length", "personnel.*.tracks"], "record_year_flag": "record_year_unknown"},
+        // fields の required は既定。ここに当てはまるレコードでは上書きされる
+        "policy": policy,
+        "policy_fields": POLICY_FIELDS,
+    })
+}
+
+/// `GET /api/validation-rules`
+#[utoipa::path(get, path = "/api/validation-rules", tag = "schema", summary = "入力チェックのルール")]
+pub async fn get_rules(State(state): State<AppState>) -> impl IntoResponse {
+    match crate::policy::load_policy(&state.db_path) {
+        Ok(p) => (StatusCode::OK, Json(rules_json(&p))).into_response(),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, e),
+    }
+}
+
+#[cfg(test)]
+mod validation_rules_tests {
+    use super::rules_json;
+    use crate::validation::ValidationPolicy;
+
+    #[test]
+    fn rules_list_required_fields_and_limits() {
+        let v = rules_json(&ValidationPolicy::default());
+        let title = v["fields"].as_array().unwrap().iter().find(|f| f["path"] == "title").unwrap();
+        assert_eq!(title["required"], true);
+        assert_eq!(title["max_length"], 128);
+        assert_eq!(v["year"]["min"], 1900);
+        assert_eq!(v["formats"]["date"], "YYYY/MM/DD");
+    }
+}
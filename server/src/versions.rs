@@ -0,0 +1,246 @@
+//! 保存の楽観的排他。同じレコードを 2 つのタブで開いていると、あとの保存が先の保存を黙って消してしまうので、
+//! `GET /api/files/{name}` は中身のハッシュを `ETag` で返し、`POST /api/save` はそれを `If-Match` で受け取る。
+//! 今のレコードと合わなければ書かずに 409 と今の版（`current_version`）を返す。
+//! 新しく作るときは `If-None-Match: *`（同じ名前のレコードがあれば 409）。どちらもない保存は 428 で断る。
+//! 確かめずに書きたいとき（スクリプトなど）は `If-Match: *` をはっきり付ける。
+//! サーバーの中でレコードを書くところ（再生記録の追記・カバー・一括の書き換え・取り込み・同期など）もみな `write` / `update` を通すので、
+//! フォームの保存と混ざって片方が消えることはなく、書き換えたあとの古い版での保存は 409 になる。
+
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde_json::Value;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::problem::Problem;
+use crate::storage::{read_record, write_record, ReadError, Storage};
+
+/// 確かめてから書くまでの間にほかの書き込みが入らないように。レコードを書くところ（`write` / `update`）と、
+/// レコードの名前を変える・消すところが持つ
+static SAVE_LOCK: Mutex<()> = Mutex::new(());
+
+pub fn save_lock() -> MutexGuard<'static, ()> {
+    SAVE_LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// レコードの版（整形や圧縮の違いで変わらない中身のハッシュ）。
+/// 形式の版（`schema_version`）は中身ではないので入れない。書くときに付いても、読んだときの版と変わらない
+pub fn version_of(v: &Value) -> String {
+    match v.as_object().filter(|obj| obj.contains_key("schema_version")) {
+        Some(obj) => {
+            let mut obj = obj.clone();
+            obj.remove("schema_version");
+            crate::sync::value_hash(&Value::Object(obj))
+        }
+        None => crate::sync::value_hash(v),
+    }
+}
+
+/// `ETag` に入れる形（"\"abc\""）
+pub fn etag(version: &str) -> String {
+    format!("\"{}\"", version)
+}
+
+/// 保存の前提
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Precondition {
+    /// 確かめない
+    Any,
+    /// 今のレコードがこの版のときだけ
+    Version(String),
+    /// まだないときだけ
+    Absent,
+}
+
+impl Precondition {
+    /// `If-Match` も `If-None-Match: *` もなければ None（428 で断る）
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let get = |name| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim).filter(|v| !v.is_empty());
+        match get(header::IF_MATCH) {
+            Some("*") => return Some(Self::Any),
+            Some(tag) => return Some(Self::Version(tag.trim_start_matches("W/").trim_matches('"').to_string())),
+            None => {}
+        }
+        if get(header::IF_NONE_MATCH) == Some("*") {
+            return Some(Self::Absent);
+        }
+        None
+    }
+}
+
+/// 前提のない保存への 428
+pub fn required() -> Problem {
+    Problem::new(StatusCode::PRECONDITION_REQUIRED, "If-Match（読んだときの ETag、確かめずに書くなら *）か If-None-Match: * を付けてください")
+}
+
+/// 書けなかったとき
+#[derive(Debug, PartialEq)]
+pub enum WriteError {
+    /// 前提に合わない（409）。`current` は今の版（レコードがなければ None）
+    Conflict { message: String, current: Option<String> },
+    /// 読めない・書けない
+    Failed(StatusCode, String),
+}
+
+impl WriteError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            WriteError::Conflict { .. } => StatusCode::CONFLICT,
+            WriteError::Failed(status, _) => *status,
+        }
+    }
+
+    pub fn problem(&self) -> Problem {
+        match self {
+            WriteError::Conflict { message, current } => Problem::new(StatusCode::CONFLICT, message.clone())
+                .code("version_conflict")
+                .with("current_version", current.clone()),
+            WriteError::Failed(status, message) => Problem::new(*status, message.clone()),
+        }
+    }
+}
+
+impl std::fmt::Display for WriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WriteError::Conflict { message, .. } | WriteError::Failed(_, message) => f.write_str(message),
+        }
+    }
+}
+
+impl IntoResponse for WriteError {
+    fn into_response(self) -> Response {
+        self.problem().into_response()
+    }
+}
+
+/// 書いた結果
+#[derive(Debug)]
+pub struct Written {
+    /// 書く前のレコード（新しく作ったときは None）
+    pub old: Option<Value>,
+    /// 書いた形（数の書き方など）で読み直したもの
+    pub value: Value,
+    /// 書いたあとの版（GET の ETag と同じ）
+    pub version: String,
+}
+
+/// 前提を確かめてから書く。レコードを書くところはみなここか `update` を通るので、確かめてから書くまでの間に
+/// ほかの保存が入って黙って消えることはない
+pub fn write(storage: &dyn Storage, filename: &str, pre: &Precondition, data: &Value) -> Result<Written, WriteError> {
+    let _lock = save_lock();
+    write_locked(storage, filename, pre, data)
+}
+
+/// 今のレコードを読み、`f` で変えて書く（再生記録の追記・一括の書き換えなど）。読むところからロックを持ち、
+/// 読んだときの版で確かめて書く。`f` が false を返したら書かずに None
+pub fn update(
+    storage: &dyn Storage,
+    filename: &str,
+    f: impl FnOnce(&mut Value) -> Result<bool, WriteError>,
+) -> Result<Option<Written>, WriteError> {
+    let _lock = save_lock();
+    let mut v = read_record(storage, filename).map_err(|e| WriteError::Failed(e.status(), e.message()))?;
+    let pre = Precondition::Version(version_of(&v));
+    if !f(&mut v)? {
+        return Ok(None);
+    }
+    write_locked(storage, filename, &pre, &v).map(Some)
+}
+
+/// レコードの名前を変える。`pre` は変える前のレコードで確かめる
+pub fn rename(storage: &dyn Storage, from: &str, to: &str, pre: &Precondition) -> Result<(), WriteError> {
+    let _lock = save_lock();
+    check_locked(storage, from, pre)?;
+    storage.rename(from, to).map_err(|e| {
+        let status = match e.kind() {
+            std::io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+            std::io::ErrorKind::AlreadyExists => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        WriteError::Failed(status, e.to_string())
+    })
+}
+
+fn write_locked(storage: &dyn Storage, filename: &str, pre: &Precondition, data: &Value) -> Result<Written, WriteError> {
+    let old = check_locked(storage, filename, pre)?;
+    write_record(storage, filename, data).map_err(|e| WriteError::Failed(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let value = read_record(storage, filename).unwrap_or_else(|_| data.clone());
+    Ok(Written { old, version: version_of(&value), value })
+}
+
+/// 前提に合うか確かめ、今のレコード（なければ None）を返す。SAVE_LOCK を持って呼ぶ
+fn check_locked(storage: &dyn Storage, filename: &str, pre: &Precondition) -> Result<Option<Value>, WriteError> {
+    let old = read_record(storage, filename);
+    let current = match &old {
+        Ok(v) => Some(version_of(v)),
+        Err(ReadError::NotFound(_)) => None,
+        // 壊れた JSON は版が取れないので、誰かが書き換えたものとして扱う
+        Err(ReadError::Invalid(_) | ReadError::Unsupported(_)) => Some(String::new()),
+    };
+    let ok = match pre {
+        Precondition::Any => true,
+        Precondition::Version(v) => current.as_deref() == Some(v.as_str()),
+        Precondition::Absent => current.is_none(),
+    };
+    if !ok {
+        let message = match (pre, &current) {
+            (Precondition::Absent, _) => format!("{} は既にあります", filename),
+            (_, None) => format!("{} はほかで削除されています", filename),
+            _ => format!("{} はほかのタブ・端末で変更されています", filename),
+        };
+        return Err(WriteError::Conflict { message, current });
+    }
+    Ok(old.ok())
+}
+
+#[cfg(test)]
+mod versions_tests {
+    use super::{update, version_of, write, Precondition, WriteError};
+    use crate::storage::{FsStorage, Layout};
+    use axum::http::{HeaderMap, StatusCode};
+    use serde_json::json;
+
+    #[test]
+    fn headers_to_precondition() {
+        let mut h = HeaderMap::new();
+        assert_eq!(Precondition::from_headers(&h), None);
+        h.insert("if-none-match", "W/\"abc\"".parse().unwrap());
+        assert_eq!(Precondition::from_headers(&h), None);
+        h.insert("if-none-match", "*".parse().unwrap());
+        assert_eq!(Precondition::from_headers(&h), Some(Precondition::Absent));
+        h.insert("if-match", "W/\"abc\"".parse().unwrap());
+        assert_eq!(Precondition::from_headers(&h), Some(Precondition::Version("abc".into())));
+        h.insert("if-match", "*".parse().unwrap());
+        assert_eq!(Precondition::from_headers(&h), Some(Precondition::Any));
+    }
+
+    #[test]
+    fn stale_version_is_a_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = FsStorage::new(dir.path(), Layout::Flat, false);
+        let first = json!({"title": "A", "score": 5.0});
+        let created = write(&storage, "a.json", &Precondition::Absent, &first).unwrap();
+        assert!(created.old.is_none());
+        let v1 = version_of(&first);
+        assert_eq!(created.version, v1);
+        assert!(write(&storage, "a.json", &Precondition::Absent, &first).is_err());
+
+        // 追記などのサーバー側の書き換えも版を変えるので、古い版での保存は 409
+        let listened = update(&storage, "a.json", |v| {
+            v["listens"] = json!(["2026/10/16"]);
+            Ok(true)
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(listened.old.as_ref().map(version_of), Some(v1.clone()));
+        let err = write(&storage, "a.json", &Precondition::Version(v1), &json!({"title": "B"})).unwrap_err();
+        assert_eq!(err, WriteError::Conflict { message: "a.json はほかのタブ・端末で変更されています".into(), current: Some(listened.version.clone()) });
+        assert_eq!(err.problem().to_value()["current_version"], listened.version.as_str());
+        assert!(write(&storage, "a.json", &Precondition::Version(listened.version), &json!({"title": "B"})).is_ok());
+        assert!(write(&storage, "a.json", &Precondition::Any, &json!({"title": "C"})).is_ok());
+        assert!(update(&storage, "a.json", |_| Ok(false)).unwrap().is_none());
+        assert!(matches!(update(&storage, "none.json", |_| Ok(true)), Err(WriteError::Failed(StatusCode::NOT_FOUND, _))));
+    }
+}
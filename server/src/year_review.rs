@@ -0,0 +1,252 @@
+//! 年間のふりかえり（`GET /api/export/year-review?year=2024`）。毎年 12 月に書く記事の下書きを Markdown で出す。
+//! その年に登録したレコード（`date`）・聴いた記録（`listens`）・監査ログの保存回数から組み立てる。
+
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use serde_json::Value;
+use std::fmt::Write;
+
+use crate::audit::AUDIT_FILE;
+use crate::length::{format_duration, parse_length};
+use crate::libraries::{Lib, LibraryQuery};
+use crate::names::NameIndex;
+use crate::problem::{error, field_error};
+use crate::settings::config_path;
+use crate::stats::{date_from_system_time, library_stats, LibraryStats};
+use crate::{display_label_from_value, load_all_records, score_from_value, AppState};
+
+/// 「評価の高いもの」「よく聴いたもの」に出す件数
+const TOP_N: usize = 10;
+
+#[derive(serde::Deserialize)]
+pub struct YearReviewParams {
+    /// 省略時は今年
+    year: Option<i64>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Ranked {
+    display_label: String,
+    value: f64,
+}
+
+#[derive(Debug, PartialEq)]
+struct Longest {
+    display_label: String,
+    secs: u64,
+    tracks: usize,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct YearReview {
+    year: i64,
+    /// その年に登録したレコード数（下書きは除く）
+    added: usize,
+    /// その年に聴いた回数と、聴いたレコード数
+    listens: usize,
+    listened_records: usize,
+    /// 登録したレコードのうちスコアの高いもの
+    top_rated: Vec<Ranked>,
+    /// よく聴いたもの（回数）
+    most_listened: Vec<Ranked>,
+    /// 登録したレコードのジャンル・曲数
+    library: LibraryStats,
+    /// 登録したレコードのうち、曲の長さの合計がいちばん長いもの
+    longest: Option<Longest>,
+    /// 監査ログにあるレコードの保存回数
+    saves: usize,
+}
+
+fn in_year(date: &str, year: i64) -> bool {
+    crate::date::Date::parse(date).is_some_and(|d| d.year == year)
+}
+
+/// 監査ログの 1 行がその年のレコードの保存（成功したもの）か
+fn is_save_in_year(line: &str, year: i64) -> bool {
+    let Ok(v) = serde_json::from_str::<Value>(line) else {
+        return false;
+    };
+    v["at"].as_str().is_some_and(|at| at.starts_with(&format!("{:04}-", year)))
+        && v["method"] == "POST"
+        && v["path"] == "/api/save"
+        && v["status"].as_u64().is_some_and(|s| (200..300).contains(&s))
+}
+
+fn collect_review(records: &[(String, Value)], year: i64, names: &NameIndex, audit: &str) -> YearReview {
+    let mut review = YearReview { year, ..Default::default() };
+    let added: Vec<&Value> = records
+        .iter()
+        .map(|(_, v)| v)
+        .filter(|v| v["draft"].as_bool() != Some(true))
+        .filter(|v| v["date"].as_str().is_some_and(|d| in_year(d, year)))
+        .collect();
+    review.added = added.len();
+
+    let mut top_rated: Vec<Ranked> = added
+        .iter()
+        .filter_map(|v| {
+            let score = score_from_value(v).filter(|s| *s > 0.0)?;
+            Some(Ranked { display_label: display_label_from_value(v), value: score })
+        })
+        .collect();
+    top_rated.sort_by(|a, b| b.value.total_cmp(&a.value).then_with(|| a.display_label.cmp(&b.display_label)));
+    top_rated.truncate(TOP_N);
+    review.top_rated = top_rated;
+
+    review.longest = added
+        .iter()
+        .filter_map(|v| {
+            let tracks = v["tracks"].as_array()?;
+            let secs: u64 = tracks.iter().filter_map(|t| t["length"].as_str().and_then(parse_length)).sum();
+            (secs > 0).then(|| Longest { display_label: display_label_from_value(v), secs, tracks: tracks.len() })
+        })
+        .max_by(|a, b| a.secs.cmp(&b.secs).then_with(|| b.display_label.cmp(&a.display_label)));
+    review.library = library_stats(added.iter().copied(), names);
+
+    let mut most_listened = Vec::new();
+    for (_, v) in records {
+        let count = v["listens"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .filter(|d| in_year(d, year))
+            .count();
+        if count > 0 {
+            review.listens += count;
+            review.listened_records += 1;
+            most_listened.push(Ranked { display_label: display_label_from_value(v), value: count as f64 });
+        }
+    }
+    most_listened.sort_by(|a, b| b.value.total_cmp(&a.value).then_with(|| a.display_label.cmp(&b.display_label)));
+    most_listened.truncate(TOP_N);
+    review.most_listened = most_listened;
+
+    review.saves = audit.lines().filter(|l| is_save_in_year(l, year)).count();
+    review
+}
+
+/// 表のセルに入れられるようにする
+pub(crate) fn cell(s: &str) -> String {
+    s.replace('|', "\\|")
+}
+
+fn render_markdown(r: &YearReview) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {} 年のふりかえり\n", r.year);
+    let _ = writeln!(out, "- 登録したレコード: {} 枚（{} 曲）", r.added, r.library.tracks);
+    let _ = writeln!(out, "- 聴いた回数: {} 回（{} 枚）", r.listens, r.listened_records);
+    let _ = writeln!(out, "- レコードの保存: {} 回", r.saves);
+
+    let _ = writeln!(out, "\n## 新しく加えた中で評価の高いもの\n");
+    if r.top_rated.is_empty() {
+        let _ = writeln!(out, "（スコアを付けたレコードはありません）");
+    }
+    for (i, e) in r.top_rated.iter().enumerate() {
+        let _ = writeln!(out, "{}. {} — {}", i + 1, e.display_label, e.value);
+    }
+
+    let _ = writeln!(out, "\n## ジャンル\n");
+    if r.library.genres.is_empty() {
+        let _ = writeln!(out, "（登録したレコードはありません）");
+    } else {
+        let _ = writeln!(out, "| ジャンル | 枚数 | サブジャンル |\n| --- | ---: | --- |");
+        for g in &r.library.genres {
+            let sub: Vec<String> = g.sub.iter().map(|s| format!("{} {}", cell(&s.genre), s.count)).collect();
+            let name = if g.genre.is_empty() { "（未入力）" } else { &g.genre };
+            let _ = writeln!(out, "| {} | {} | {} |", cell(name), g.count, sub.join("、"));
+        }
+    }
+
+    let _ = writeln!(out, "\n## よく聴いたもの\n");
+    if r.most_listened.is_empty() {
+        let _ = writeln!(out, "（聴いた記録はありません）");
+    }
+    for (i, e) in r.most_listened.iter().enumerate() {
+        let _ = writeln!(out, "{}. {} — {} 回", i + 1, e.display_label, e.value);
+    }
+
+    if let Some(l) = &r.longest {
+        let _ = writeln!(out, "\n## いちばん長いアルバム\n");
+        let _ = writeln!(out, "{}（{}、{} 曲）", l.display_label, format_duration(l.secs), l.tracks);
+    }
+    out
+}
+
+/// `GET /api/export/year-review?year=2024`
+#[utoipa::path(get, path = "/api/export/year-review", tag = "import-export", summary = "年のふりかえり", params(LibraryQuery))]
+pub async fn export_year_review(
+    State(state): State<AppState>,
+    Lib(library): Lib,
+    Query(params): Query<YearReviewParams>,
+) -> impl IntoResponse {
+    let year = match params.year {
+        Some(y) if !(1..=9999).contains(&y) => {
+            return field_error("year", "must be 1..=9999")
+        }
+        Some(y) => y,
+        None => date_from_system_time(std::time::SystemTime::now())
+            .and_then(|d| crate::date::Date::parse(&d))
+            .map_or(1970, |d| d.year),
+    };
+    let Some(records) = load_all_records(&*library.storage) else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "cannot read db directory");
+    };
+    let names = crate::names::load_names(&state.db_path).unwrap_or_default().index();
+    // 監査ログがなければ保存回数は 0
+    let audit = std::fs::read_to_string(config_path(&state.db_path, AUDIT_FILE)).unwrap_or_default();
+    let body = render_markdown(&collect_review(&records, year, &names, &audit));
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "text/markdown; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"year-review-{}.md\"", year)),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod year_review_tests {
+    use super::{collect_review, render_markdown};
+    use crate::names::NameRegistry;
+    use serde_json::json;
+
+    #[test]
+    fn review_counts_only_the_given_year() {
+        let records = vec![
+            ("a.json".to_string(), json!({
+                "title": "Moanin'", "personnel": {"leader": [{"name": "Art Blakey"}]}, "janre": {"main": "Jazz", "sub": ["Hard Bop"]},
+                "date": "2024/03/05", "score": 5, "listens": ["2024/03/05", "2024/12/01", "2023/01/01"],
+                "tracks": [{"length": "9:32"}, {"length": "?"}]})),
+            ("b.json".to_string(), json!({
+                "title": "Kind of Blue", "janre": {"main": "Jazz"}, "date": "2024/07/01", "score": 4,
+                "tracks": [{"length": "1:00:00"}, {"length": "9:22"}]})),
+            ("c.json".to_string(), json!({"title": "Old", "date": "2023/01/01", "score": 5, "listens": ["2024/02/02"]})),
+            ("d.json".to_string(), json!({"title": "Draft", "date": "2024/01/01", "draft": true})),
+        ];
+        let audit = concat!(
+            r#"{"at":"2024-03-05T10:00:00Z","token":null,"method":"POST","path":"/api/save","status":200}"#, "\n",
+            r#"{"at":"2024-03-05T10:01:00Z","token":null,"method":"POST","path":"/api/save","status":400}"#, "\n",
+            r#"{"at":"2023-03-05T10:00:00Z","token":null,"method":"POST","path":"/api/save","status":200}"#, "\n",
+            r#"{"at":"2024-03-05T10:00:00Z","token":null,"method":"DELETE","path":"/api/files/a.json","status":200}"#, "\n",
+        );
+        let r = collect_review(&records, 2024, &NameRegistry::default().index(), audit);
+        assert_eq!((r.added, r.listens, r.listened_records, r.saves), (2, 3, 2, 1));
+        assert_eq!(r.top_rated.len(), 2);
+        assert_eq!(r.top_rated[0].value, 5.0);
+        assert_eq!(r.library.genres[0].genre, "Jazz");
+        assert_eq!(r.library.genres[0].count, 2);
+        // "1:00:00" は分:秒として読めないので数えない
+        assert_eq!(r.longest.as_ref().map(|l| l.secs), Some(572));
+
+        let md = render_markdown(&r);
+        assert!(md.starts_with("# 2024 年のふりかえり\n"));
+        assert!(md.contains("| Jazz | 2 | Hard Bop 1 |"));
+        assert!(md.contains("（9:32、2 曲）"));
+    }
+}
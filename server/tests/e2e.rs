@@ -0,0 +1,232 @@
+//! サーバーとフロントの境目を通しで確かめるテスト。`tests/fixtures/db` を一時ディレクトリに写し、ビルド済みのサーバーを空いているポートで起動する。
+//!
+//! - `api_round_trip`: 一覧・読み込み・保存（`If-Match`）・読み直しを HTTP で確かめる（いつも走る）
+//! - `browser_edit_and_save`: ヘッドレスの Chrome でフロントを開き、一覧 → レコードを開く → 編集 → 保存 → 再読み込みをたどる。
+//!   フロントはテストの中で `trunk build` する（古い `dist` を開かないように毎回。`E2E_DIST_DIR` を渡せばそこにあるものを使う）。
+//!   trunk と wasm32 のターゲットと Chrome（`CHROME` でパスを渡せる）が要るので `#[ignore]`。CI では `rust.yml` の `e2e` で走る。
+//!
+//! ```sh
+//! cargo test -p nekokan_music_server --test e2e -- --include-ignored
+//! ```
+
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+const RECORD: &str = "Andrew_Hill__Black_Fire.json";
+const LABEL: &str = "Andrew Hill: Black Fire";
+/// サーバーが待ち受けるまでの待ち時間
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// 起動したサーバー。落とすと止める
+struct Server {
+    child: Child,
+    base: String,
+    db: tempfile::TempDir,
+}
+
+impl Server {
+    fn start(dist: &Path) -> Server {
+        let db = tempfile::tempdir().unwrap();
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/db");
+        for entry in std::fs::read_dir(fixtures).unwrap() {
+            let entry = entry.unwrap();
+            std::fs::copy(entry.path(), db.path().join(entry.file_name())).unwrap();
+        }
+        // 空いているポートを借りてすぐ返す（間に取られることはまずない）
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let child = Command::new(env!("CARGO_BIN_EXE_nekokan_music_server"))
+            .args(["--host", "127.0.0.1", "--port", &port.to_string()])
+            .arg("--db-path")
+            .arg(db.path())
+            .arg("--dist-dir")
+            .arg(dist)
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+        let server = Server { child, base: format!("http://127.0.0.1:{}", port), db };
+        let started = Instant::now();
+        while ureq::get(&server.url("/api/list")).call().is_err() {
+            assert!(started.elapsed() < STARTUP_TIMEOUT, "server did not start on {}", server.base);
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        server
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base, path)
+    }
+
+    /// ディスクの上のレコード
+    fn record_on_disk(&self, filename: &str) -> Value {
+        serde_json::from_str(&std::fs::read_to_string(self.db.path().join(filename)).unwrap()).unwrap()
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn wa_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../nekokan_music_wa")
+}
+
+fn dist_dir() -> PathBuf {
+    std::env::var_os("E2E_DIST_DIR").map(PathBuf::from).unwrap_or_else(|| wa_dir().join("dist"))
+}
+
+/// フロントを `trunk build` した dist（`E2E_DIST_DIR` があればそれをそのまま使う）
+fn built_dist() -> PathBuf {
+    if let Some(dir) = std::env::var_os("E2E_DIST_DIR") {
+        return PathBuf::from(dir);
+    }
+    let status = Command::new(std::env::var_os("TRUNK").unwrap_or_else(|| "trunk".into()))
+        .arg("build")
+        .current_dir(wa_dir())
+        .status()
+        .unwrap_or_else(|e| panic!("trunk を起動できません（cargo install trunk --locked と rustup target add wasm32-unknown-unknown）: {}", e));
+    assert!(status.success(), "trunk build に失敗しました: {}", status);
+    dist_dir()
+}
+
+#[test]
+fn api_round_trip() {
+    let server = Server::start(&dist_dir());
+
+    let list: Vec<Value> = ureq::get(&server.url("/api/list-with-labels")).call().unwrap().into_json().unwrap();
+    let names: Vec<&str> = list.iter().filter_map(|e| e["filename"].as_str()).collect();
+    assert_eq!(names, ["Ahmad_Jamal__In_Concert.json", RECORD]);
+    assert_eq!(list[1]["display_label"], LABEL);
+    // サイドバーが 1 件ずつ読まなくても絞り込み・並べ替えられるよう、スコア・ジャンル・年も入っている
+    assert_eq!((&list[1]["score"], &list[1]["genre"], &list[1]["release_year"]), (&json!(6.0), &json!("Jazz"), &json!(1964)));
+    assert_eq!(list[1]["record_year"], json!([1963]));
+
+    let resp = ureq::get(&server.url(&format!("/api/files/{}", RECORD))).call().unwrap();
+    let etag = resp.header("ETag").unwrap().to_string();
+    let mut data: Value = resp.into_json().unwrap();
+    data["comment"] = Value::from("通しのテストで書き換えた");
+    let saved: Value = ureq::post(&server.url("/api/save"))
+        .set("If-Match", &etag)
+        .send_json(serde_json::json!({"filename": RECORD.trim_end_matches(".json"), "data": data}))
+        .unwrap()
+        .into_json()
+        .unwrap();
+    assert_eq!(saved["ok"], true);
+
+    let resp = ureq::get(&server.url(&format!("/api/files/{}", RECORD))).call().unwrap();
+    assert_eq!(resp.header("ETag").map(|e| e.trim_matches('"')), saved["version"].as_str());
+    let reloaded: Value = resp.into_json().unwrap();
+    assert_eq!(reloaded["comment"], "通しのテストで書き換えた");
+    assert_eq!(server.record_on_disk(RECORD)["comment"], "通しのテストで書き換えた");
+
+    // 読んだあとにほかで保存されていれば書かない
+    let stale = ureq::post(&server.url("/api/save"))
+        .set("If-Match", &etag)
+        .send_json(serde_json::json!({"filename": RECORD.trim_end_matches(".json"), "data": data}));
+    let Err(ureq::Error::Status(409, resp)) = stale else { panic!("{:?}", stale.map(|r| r.status())) };
+    assert_eq!(resp.content_type(), "application/problem+json");
+    let problem: Value = resp.into_json().unwrap();
+    assert_eq!((problem["code"].as_str(), problem["current_version"].as_str()), (Some("version_conflict"), saved["version"].as_str()));
+
+    // 再生記録の追記も版を変えるので、追記の前に読んだ版での保存は書かない
+    let listened: Value = ureq::post(&server.url("/api/listen"))
+        .send_json(json!({"filename": RECORD, "date": "2026/10/16"}))
+        .unwrap()
+        .into_json()
+        .unwrap();
+    assert_eq!(listened["previous_version"], saved["version"]);
+    let after_listen = ureq::post(&server.url("/api/save"))
+        .set("If-Match", &format!("\"{}\"", saved["version"].as_str().unwrap()))
+        .send_json(json!({"filename": RECORD.trim_end_matches(".json"), "data": data}));
+    let Err(ureq::Error::Status(409, resp)) = after_listen else { panic!("{:?}", after_listen.map(|r| r.status())) };
+    assert_eq!(resp.into_json::<Value>().unwrap()["current_version"], listened["version"]);
+
+    // 前提のない保存は断る
+    let bare = ureq::post(&server.url("/api/save")).send_json(json!({"filename": RECORD.trim_end_matches(".json"), "data": data}));
+    let Err(ureq::Error::Status(428, resp)) = bare else { panic!("{:?}", bare.map(|r| r.status())) };
+    assert_eq!(resp.into_json::<Value>().unwrap()["code"], "precondition_required");
+
+    // 名前の変更も読んだ版で確かめ、変えたあとの版を返す（続く保存はその版で書ける）
+    let other = "Ahmad_Jamal__In_Concert.json";
+    let renamed_title = server.record_on_disk(other)["title"].clone();
+    let other_etag = ureq::get(&server.url(&format!("/api/files/{}", other))).call().unwrap().header("ETag").unwrap().to_string();
+    let stale = ureq::post(&server.url("/api/rename")).send_json(json!({"from": other, "to": "Ahmad_Jamal__Live.json", "version": saved["version"]}));
+    let Err(ureq::Error::Status(409, _)) = stale else { panic!("{:?}", stale.map(|r| r.status())) };
+    let renamed: Value = ureq::post(&server.url("/api/rename"))
+        .send_json(json!({"from": other, "to": "Ahmad_Jamal__Live.json", "version": other_etag.trim_matches('"')}))
+        .unwrap()
+        .into_json()
+        .unwrap();
+    let resp = ureq::get(&server.url("/api/files/Ahmad_Jamal__Live.json")).call().unwrap();
+    assert_eq!(resp.header("ETag").map(|e| e.trim_matches('"')), renamed["version"].as_str());
+
+    // 削除したレコードはごみ箱に残り、元の名前に戻せる
+    ureq::delete(&server.url("/api/files/Ahmad_Jamal__Live.json")).call().unwrap();
+    let trash: Vec<Value> = ureq::get(&server.url("/api/trash")).call().unwrap().into_json().unwrap();
+    assert_eq!(trash[0]["filename"], "Ahmad_Jamal__Live.json");
+    ureq::post(&server.url(&format!("/api/trash/{}/restore", trash[0]["name"].as_str().unwrap()))).call().unwrap();
+    assert_eq!(server.record_on_disk("Ahmad_Jamal__Live.json")["title"], renamed_title);
+    assert!(ureq::get(&server.url("/api/trash")).call().unwrap().into_json::<Vec<Value>>().unwrap().is_empty());
+
+    // API の一覧と Swagger UI（JS・CSS もサーバーが出す）
+    let spec: Value = ureq::get(&server.url("/api/openapi.json")).call().unwrap().into_json().unwrap();
+    assert!(spec["paths"]["/api/trash/{name}/restore"]["post"].is_object());
+    let docs = ureq::get(&server.url("/api/docs/")).call().unwrap().into_string().unwrap();
+    assert!(docs.contains("swagger-ui"));
+    assert!(!docs.contains("unpkg.com"));
+    assert_eq!(ureq::get(&server.url("/api/docs/swagger-ui-bundle.js")).call().unwrap().status(), 200);
+
+    // ハンドラに届かないエラー（本文の項目が足りない）も同じ形
+    let Err(ureq::Error::Status(422, resp)) = ureq::post(&server.url("/api/save")).set("If-Match", "*").send_json(json!({"data": {}})) else { panic!() };
+    let problem: Value = resp.into_json().unwrap();
+    assert_eq!((problem["code"].as_str(), problem["errors"][0]["field"].as_str()), (Some("invalid_body"), Some("filename")));
+}
+
+#[test]
+#[ignore = "trunk と Chrome が要る"]
+fn browser_edit_and_save() {
+    use headless_chrome::{Browser, LaunchOptions};
+
+    let dist = built_dist();
+    assert!(dist.join("index.html").exists(), "{} に index.html がありません", dist.display());
+    let server = Server::start(&dist);
+    let options = LaunchOptions::default_builder()
+        .headless(true)
+        .sandbox(false)
+        .path(std::env::var_os("CHROME").map(PathBuf::from))
+        .build()
+        .unwrap();
+    let browser = Browser::new(options).unwrap();
+    let tab = browser.new_tab().unwrap();
+    tab.set_default_timeout(Duration::from_secs(30));
+    let record_button = format!("//button[contains(@class, 'file-item') and contains(., '{}')]", LABEL);
+    let comment = |tab: &headless_chrome::Tab| {
+        tab.evaluate("document.getElementById('field-comment').value", false).unwrap().value.unwrap_or_default()
+    };
+
+    // 一覧が出て、レコードを開ける
+    tab.navigate_to(&server.url("/")).unwrap();
+    tab.wait_for_xpath(&record_button).unwrap().click().unwrap();
+    tab.wait_for_element("#field-title").unwrap();
+    assert_eq!(comment(&tab), "ヒルの王道とアヴァンギャルドを行き来する一枚。");
+
+    // 編集して保存する
+    tab.find_element("#field-comment").unwrap().click().unwrap();
+    tab.press_key("End").unwrap();
+    tab.type_str(" 再読み込みしても残る").unwrap();
+    tab.find_element("button.btn-save").unwrap().click().unwrap();
+    tab.wait_for_element(".save-ok").unwrap();
+    assert_eq!(server.record_on_disk(RECORD)["comment"], "ヒルの王道とアヴァンギャルドを行き来する一枚。 再読み込みしても残る");
+
+    // 読み込み直しても残っている
+    tab.reload(true, None).unwrap();
+    tab.wait_for_xpath(&record_button).unwrap().click().unwrap();
+    tab.wait_for_element("#field-title").unwrap();
+    assert_eq!(comment(&tab), "ヒルの王道とアヴァンギャルドを行き来する一枚。 再読み込みしても残る");
+}